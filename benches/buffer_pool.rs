@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Compares reading response bodies into fresh buffers with reading them
+//! into buffers from a `BufferPool`, reporting the time taken and how much
+//! work the allocator had to do.
+//!
+//! Run with `cargo bench --bench buffer_pool`.
+
+use bytes::Bytes;
+use futures_util::{StreamExt, stream};
+use hypertyper::buffer::BufferPool;
+use hypertyper::service::BodyStream;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counts allocations and the bytes they request.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const REQUESTS: usize = 2_000;
+const CHUNK_LEN: usize = 16 * 1024;
+static CHUNK: [u8; CHUNK_LEN] = [b'x'; CHUNK_LEN];
+
+/// A body of `chunks` chunks that arrives without allocating, apart from
+/// the stream itself.
+fn body(chunks: usize) -> BodyStream {
+    let chunks = std::iter::repeat_n(Bytes::from_static(&CHUNK), chunks).map(Ok);
+    BodyStream::new(stream::iter(chunks))
+}
+
+struct Measurement {
+    elapsed: Duration,
+    allocations: u64,
+    allocated_bytes: u64,
+}
+
+async fn measure<F, Fut>(mut read: F) -> Measurement
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = usize>,
+{
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..REQUESTS {
+        black_box(read().await);
+    }
+    Measurement {
+        elapsed: start.elapsed(),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes,
+    }
+}
+
+fn report(name: &str, m: &Measurement) {
+    println!(
+        "{name:<28} {:>10.2?}/req {:>8.1} allocs/req {:>10.1} KiB/req",
+        m.elapsed / REQUESTS as u32,
+        m.allocations as f64 / REQUESTS as f64,
+        m.allocated_bytes as f64 / REQUESTS as f64 / 1024.0,
+    );
+}
+
+async fn run(chunks: usize) {
+    println!(
+        "{} KiB bodies, {REQUESTS} requests",
+        chunks * CHUNK_LEN / 1024
+    );
+
+    let fresh = measure(|| async {
+        let mut body = body(chunks);
+        let mut buffer = Vec::new();
+        while let Some(chunk) = body.next().await {
+            buffer.extend_from_slice(&chunk.unwrap());
+        }
+        buffer.len()
+    })
+    .await;
+    report("fresh buffers", &fresh);
+
+    let pool = BufferPool::new(8).with_max_capacity(64 * 1024 * 1024);
+    let pooled = measure(|| async {
+        let buffer = body(chunks).read_pooled(&pool).await.unwrap();
+        buffer.len()
+    })
+    .await;
+    report("pooled buffers", &pooled);
+    println!();
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        for chunks in [1, 16, 256] {
+            run(chunks).await;
+        }
+    });
+}
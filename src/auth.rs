@@ -2,27 +2,159 @@
 // Copyright (C) 2025 Michael Dippery <michael@monkey-robot.com>
 
 //! HTTP authentication.
+//!
+//! Services authenticate requests with an [`Authenticator`], which adds
+//! credentials to each outgoing request. [`Auth`], which sends an API key
+//! as a bearer token, is the most common kind. [`BasicAuth`] sends a user
+//! name and password, and [`ApiKeyHeader`] and [`ApiKeyQuery`] send an API
+//! key in a custom header or a query parameter. [`JwtAuth`] sends a JWT
+//! bearer token and refreshes it before it expires. Services such as
+//! [`HttpPost`] accept any authenticator, so other credential shapes can be
+//! used by implementing the trait, and an [`AuthService`] applies one to
+//! every request made through a service. Keys can be looked up in the
+//! environment, configuration files, and other places with a
+//! [credential provider](credentials). [`Refreshable`]
+//! authenticators can also replace rejected credentials, so that a
+//! [`ReauthService`] can replay requests that fail with an HTTP 401.
+//!
+//! [`AuthService`]: crate::service::auth::AuthService
+//! [`ReauthService`]: crate::service::reauth::ReauthService
+//! [`HttpPost`]: crate::service::HttpPost
 
-use std::env;
+pub mod api_key;
+pub mod basic;
+pub mod credentials;
+#[cfg(feature = "digest-auth")]
+pub mod digest;
+#[cfg(feature = "hmac")]
+pub mod hmac;
+pub mod jwt;
+#[cfg(feature = "oauth1")]
+pub mod oauth1;
+pub mod oauth2;
+#[cfg(feature = "oidc")]
+pub mod oidc;
+pub mod secret;
+#[cfg(feature = "sigv4")]
+pub mod sigv4;
+#[cfg(feature = "hmac")]
+pub mod webhook;
+
+pub use api_key::{ApiKeyHeader, ApiKeyQuery};
+pub use basic::BasicAuth;
+pub use jwt::JwtAuth;
+pub use secret::Secret;
+
+use crate::HttpResult;
+use credentials::CredentialProvider;
+use reqwest::header::{self, HeaderValue};
+use reqwest::{Request, RequestBuilder};
+use std::{env, fmt};
+use zeroize::Zeroize;
+
+/// Adds credentials to outgoing HTTP requests.
+///
+/// Implementors only need to provide
+/// [`authenticate()`](Authenticator::authenticate), which can modify any
+/// part of the request: most add an `Authorization` header, but others
+/// add query parameters or sign the request's method, URL, and body.
+///
+/// # Examples
+///
+/// ```
+/// use hypertyper::HttpResult;
+/// use hypertyper::auth::Authenticator;
+/// use reqwest::Request;
+/// use reqwest::header::HeaderValue;
+///
+/// #[derive(Debug)]
+/// struct SessionCookie(String);
+///
+/// impl Authenticator for SessionCookie {
+///     fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+///         let cookie = HeaderValue::try_from(format!("session={}", self.0))?;
+///         request.headers_mut().insert("cookie", cookie);
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Authenticator: fmt::Debug + Send + Sync {
+    /// Adds credentials to `request`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the credentials cannot be added to the request,
+    /// in which case the request should not be sent.
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()>;
+
+    /// A short name for the kind of credentials, such as `bearer` or
+    /// `basic`, which is safe to show in logs and diagnostics.
+    fn scheme(&self) -> &str {
+        "custom"
+    }
+
+    /// Adds credentials to the request being built by `builder`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request cannot be built, or if
+    /// [`authenticate()`](Authenticator::authenticate) fails.
+    fn apply(&self, builder: RequestBuilder) -> HttpResult<RequestBuilder> {
+        let (client, request) = builder.build_split();
+        let mut request = request?;
+        self.authenticate(&mut request)?;
+        Ok(RequestBuilder::from_parts(client, request))
+    }
+}
+
+/// An [`Authenticator`] whose credentials can be replaced when an API
+/// rejects them.
+///
+/// A [`ReauthService`] refreshes the credentials of a refreshable
+/// authenticator when a request fails with an HTTP 401, and replays the
+/// request with the new ones.
+///
+/// [`ReauthService`]: crate::service::reauth::ReauthService
+pub trait Refreshable: Authenticator {
+    /// Obtains new credentials to replace ones that were rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if new credentials could not be obtained.
+    fn refresh(&self) -> impl Future<Output = HttpResult<()>> + Send;
+
+    /// True if [`refresh()`](Refreshable::refresh) can obtain new
+    /// credentials. Rejected requests are only replayed if it can.
+    fn can_refresh(&self) -> bool {
+        true
+    }
+}
 
 /// Manages authentication keys for HTTP client authorization.
 ///
+/// As an [`Authenticator`], the API key is sent as a bearer token in the
+/// `Authorization` header.
+///
+/// The key is kept in a [`Secret`], so it is zeroized when the `Auth` is
+/// dropped, and it is never shown when the `Auth` is formatted. It can
+/// only be read with [`expose()`](Auth::expose).
+///
 /// # Examples
 ///
 /// ```
 /// # use hypertyper::auth::Auth;
 /// let auth = Auth::new("ThisIsMyApiKey");
-/// assert_eq!(auth.api_key(), "ThisIsMyApiKey");
+/// assert_eq!(auth.expose(), "ThisIsMyApiKey");
+/// assert_eq!(format!("{auth:?}"), "Auth(****)");
 /// ```
-#[derive(Debug)]
 pub struct Auth {
-    api_key: String,
+    api_key: Secret,
 }
 
 impl Auth {
     /// Creates a new `Auth` structure using the given API key.
     pub fn new(api_key: impl Into<String>) -> Self {
-        let api_key = api_key.into();
+        let api_key = Secret::new(api_key);
         Self { api_key }
     }
 
@@ -31,21 +163,122 @@ impl Auth {
     /// Returns an error if the API key cannot be retrieved from the
     /// environment.
     pub fn from_env(envvar: impl Into<String>) -> Result<Auth, env::VarError> {
-        env::var(envvar.into()).map(|api_key| Self { api_key })
+        env::var(envvar.into()).map(Self::new)
+    }
+
+    /// Creates an `Auth` using the API key from `provider`.
+    ///
+    /// Returns [`HttpError::InvalidCredentials`](crate::HttpError::InvalidCredentials),
+    /// naming everywhere the provider looked, if it has no key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hypertyper::auth::Auth;
+    /// use hypertyper::auth::credentials::{CredentialChain, EnvVar};
+    ///
+    /// let provider = CredentialChain::new()
+    ///     .with(EnvVar::new("MYAPP_API_KEY"))
+    ///     .with_explicit(Some("ThisIsMyApiKey"));
+    /// let auth = Auth::from_provider(&provider).unwrap();
+    /// assert_eq!(auth.expose(), "ThisIsMyApiKey");
+    /// ```
+    pub fn from_provider(provider: &dyn CredentialProvider) -> HttpResult<Auth> {
+        credentials::require(provider).map(Self::new)
+    }
+
+    /// The actual API key.
+    ///
+    /// Prefer passing the `Auth` itself to services, which send it without
+    /// exposing it; this is only needed to hand the key to code that cannot
+    /// take an [`Authenticator`].
+    pub fn expose(&self) -> &str {
+        self.api_key.expose()
     }
 
     /// The actual API key.
+    #[deprecated(
+        since = "0.4.0",
+        note = "use `expose()`, which makes reading the key deliberate"
+    )]
     pub fn api_key(&self) -> &str {
-        &self.api_key
+        self.expose()
+    }
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Auth({})", self.api_key)
+    }
+}
+
+impl fmt::Display for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl Authenticator for Auth {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        let mut bearer = format!("Bearer {}", self.expose());
+        let value = HeaderValue::try_from(bearer.as_str());
+        bearer.zeroize();
+        let mut value = value?;
+        value.set_sensitive(true);
+        request.headers_mut().insert(header::AUTHORIZATION, value);
+        Ok(())
+    }
+
+    fn scheme(&self) -> &str {
+        "bearer"
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reqwest::Method;
     use std::ffi::OsString;
     use temp_env::{with_var, with_var_unset};
 
+    fn request() -> Request {
+        Request::new(Method::GET, "https://api.example.com/".parse().unwrap())
+    }
+
+    #[test]
+    fn it_authenticates_requests_with_a_bearer_token() -> HttpResult<()> {
+        let mut request = request();
+        Auth::new("ThisIsMyApiKey").authenticate(&mut request)?;
+        let value = &request.headers()[header::AUTHORIZATION];
+        assert_eq!(value, "Bearer ThisIsMyApiKey");
+        assert!(value.is_sensitive());
+        Ok(())
+    }
+
+    #[test]
+    fn it_applies_credentials_to_request_builders() -> HttpResult<()> {
+        let auth: &dyn Authenticator = &Auth::new("key");
+        let builder = reqwest::Client::new().post("https://api.example.com/users");
+        let request = auth.apply(builder)?.build()?;
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.headers()[header::AUTHORIZATION], "Bearer key");
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_show_keys() {
+        let auth = Auth::new("ThisIsMyApiKey");
+        assert_eq!(format!("{auth:?}"), "Auth(****)");
+        assert_eq!(auth.to_string(), "Auth(****)");
+        assert_eq!(auth.expose(), "ThisIsMyApiKey");
+    }
+
+    #[test]
+    fn it_rejects_keys_that_are_not_valid_header_values() {
+        let result = Auth::new("bad\nkey").authenticate(&mut request());
+        assert!(result.is_err());
+    }
+
     #[test]
     fn it_creates_an_auth_key_from_the_environment() {
         let key_name = "AUTH_API_KEY";
@@ -54,7 +287,7 @@ mod tests {
             let auth = Auth::from_env(key_name);
             assert!(auth.is_ok());
             let auth = auth.unwrap();
-            assert_eq!(auth.api_key(), key_value);
+            assert_eq!(auth.expose(), key_value);
         })
     }
 
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! API keys sent in a custom header or a query parameter.
+//!
+//! Besides bearer tokens, which are sent with [`Auth`](crate::auth::Auth),
+//! APIs commonly expect their keys in one of two places: a custom header,
+//! such as `X-Api-Key`, which is sent with [`ApiKeyHeader`], or a query
+//! parameter, such as `?api_key=...`, which is sent with [`ApiKeyQuery`].
+//! Both are [authenticators](Authenticator), so they can be passed to
+//! services wherever bearer keys can, and applied to every request with an
+//! [`AuthService`](crate::service::auth::AuthService).
+//!
+//! Keys in query parameters end up in server and proxy logs, so prefer a
+//! header when the API supports both.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::auth::{ApiKeyHeader, ApiKeyQuery, Authenticator};
+//! use reqwest::header::HeaderName;
+//! use reqwest::{Method, Request};
+//!
+//! let url = "https://api.example.com/search?q=rust".parse().unwrap();
+//! let mut request = Request::new(Method::GET, url);
+//!
+//! let auth = ApiKeyHeader::new("secret").with_header(HeaderName::from_static("x-goog-api-key"));
+//! auth.authenticate(&mut request).unwrap();
+//! assert_eq!(request.headers()["x-goog-api-key"], "secret");
+//!
+//! ApiKeyQuery::new("secret").authenticate(&mut request).unwrap();
+//! assert_eq!(request.url().query(), Some("q=rust&api_key=secret"));
+//! ```
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use reqwest::Request;
+use reqwest::header::{HeaderName, HeaderValue};
+use std::fmt;
+
+/// The header that [`ApiKeyHeader`] sends keys in by default.
+pub const DEFAULT_HEADER: &str = "x-api-key";
+
+/// The query parameter that [`ApiKeyQuery`] sends keys in by default.
+pub const DEFAULT_PARAM: &str = "api_key";
+
+/// Authenticates requests with an API key in a custom header.
+///
+/// See the [module documentation](crate::auth::api_key) for details.
+#[derive(Clone)]
+pub struct ApiKeyHeader {
+    header: HeaderName,
+    api_key: String,
+}
+
+impl ApiKeyHeader {
+    /// Sends `api_key` in the [`X-Api-Key`](DEFAULT_HEADER) header.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            header: HeaderName::from_static(DEFAULT_HEADER),
+            api_key: api_key.into(),
+        }
+    }
+
+    /// Sends the key in `header` instead.
+    pub fn with_header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// The header that the key is sent in.
+    pub fn header(&self) -> &HeaderName {
+        &self.header
+    }
+
+    /// The API key.
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+}
+
+impl Authenticator for ApiKeyHeader {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        let mut value = HeaderValue::try_from(self.api_key.as_str())?;
+        value.set_sensitive(true);
+        request.headers_mut().insert(self.header.clone(), value);
+        Ok(())
+    }
+
+    fn scheme(&self) -> &str {
+        "api-key-header"
+    }
+}
+
+impl fmt::Debug for ApiKeyHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyHeader")
+            .field("header", &self.header)
+            .field("api_key", &"****")
+            .finish()
+    }
+}
+
+/// Authenticates requests with an API key in a query parameter.
+///
+/// Any parameters with the same name that are already in the URL are
+/// replaced.
+///
+/// See the [module documentation](crate::auth::api_key) for details.
+#[derive(Clone)]
+pub struct ApiKeyQuery {
+    param: String,
+    api_key: String,
+}
+
+impl ApiKeyQuery {
+    /// Sends `api_key` in the [`api_key`](DEFAULT_PARAM) query parameter.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            param: String::from(DEFAULT_PARAM),
+            api_key: api_key.into(),
+        }
+    }
+
+    /// Sends the key in the query parameter `param` instead.
+    pub fn with_param(mut self, param: impl Into<String>) -> Self {
+        self.param = param.into();
+        self
+    }
+
+    /// The query parameter that the key is sent in.
+    pub fn param(&self) -> &str {
+        &self.param
+    }
+
+    /// The API key.
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+}
+
+impl Authenticator for ApiKeyQuery {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        let url = request.url_mut();
+        let others: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| *name != self.param)
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(others)
+            .append_pair(&self.param, &self.api_key);
+        Ok(())
+    }
+
+    fn scheme(&self) -> &str {
+        "api-key-query"
+    }
+}
+
+impl fmt::Debug for ApiKeyQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyQuery")
+            .field("param", &self.param)
+            .field("api_key", &"****")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Method;
+
+    fn request(url: &str) -> Request {
+        Request::new(Method::GET, url.parse().unwrap())
+    }
+
+    #[test]
+    fn it_sends_keys_in_the_default_header() -> HttpResult<()> {
+        let mut request = request("https://api.example.com/");
+        ApiKeyHeader::new("secret").authenticate(&mut request)?;
+        let value = &request.headers()["X-Api-Key"];
+        assert_eq!(value, "secret");
+        assert!(value.is_sensitive());
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_keys_that_are_not_valid_header_values() {
+        let result = ApiKeyHeader::new("sec\nret").authenticate(&mut request("https://a.example/"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_replaces_existing_key_parameters() -> HttpResult<()> {
+        let mut request = request("https://api.example.com/?key=old&q=a%20b&key=older");
+        ApiKeyQuery::new("new key")
+            .with_param("key")
+            .authenticate(&mut request)?;
+        assert_eq!(request.url().query(), Some("q=a+b&key=new+key"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_adds_a_query_to_urls_without_one() -> HttpResult<()> {
+        let mut request = request("https://api.example.com/users");
+        ApiKeyQuery::new("secret").authenticate(&mut request)?;
+        assert_eq!(
+            request.url().as_str(),
+            "https://api.example.com/users?api_key=secret"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_reveal_keys_when_debugged() {
+        assert!(!format!("{:?}", ApiKeyHeader::new("secret")).contains("secret"));
+        assert!(!format!("{:?}", ApiKeyQuery::new("secret")).contains("secret"));
+    }
+}
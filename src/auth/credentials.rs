@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Where API keys come from.
+//!
+//! Command-line tools usually accept an API key in several places: a
+//! command-line option, an environment variable such as `MYAPP_API_KEY`,
+//! a file in the user's configuration directory, and sometimes the
+//! operating system's keyring. A [`CredentialProvider`] looks for a key in
+//! one of those places, and a [`CredentialChain`] asks each of its
+//! providers in turn and uses the first key that is found.
+//! [`CredentialChain::for_app()`] builds the usual chain for an app, and
+//! [`Auth::from_provider()`](crate::auth::Auth::from_provider) creates an
+//! [`Auth`](crate::auth::Auth) from any provider.
+//!
+//! The `Keyring` provider is only available when the **keyring** feature
+//! is enabled.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::HttpResult;
+//! use hypertyper::auth::Auth;
+//! use hypertyper::auth::credentials::CredentialChain;
+//!
+//! # fn run(api_key_option: Option<String>) -> HttpResult<()> {
+//! // Uses the --api-key option if it was given, then MYAPP_API_KEY, then
+//! // ~/.config/myapp/credentials.
+//! let chain = CredentialChain::for_app("myapp").with_explicit(api_key_option);
+//! let auth = Auth::from_provider(&chain)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{HttpError, HttpResult};
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs, io};
+
+/// The name of the file that [`CredentialsFile::in_config_dir()`] reads.
+pub const CREDENTIALS_FILE: &str = "credentials";
+
+/// Looks for a credential in one place.
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    /// The credential, or `None` if this provider does not have one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the place the provider looks in exists but
+    /// cannot be read.
+    fn credential(&self) -> HttpResult<Option<String>>;
+
+    /// Where the provider looks, such as `environment variable
+    /// MYAPP_API_KEY`, for telling users where a missing credential can be
+    /// set.
+    fn location(&self) -> String;
+}
+
+/// A credential that was given explicitly, such as with a command-line
+/// option, if there is one.
+#[derive(Clone)]
+pub struct Explicit {
+    value: Option<String>,
+}
+
+impl Explicit {
+    /// Provides `value`, if it is set.
+    pub fn new(value: Option<impl Into<String>>) -> Self {
+        let value = value.map(Into::into);
+        Self { value }
+    }
+}
+
+impl CredentialProvider for Explicit {
+    fn credential(&self) -> HttpResult<Option<String>> {
+        Ok(self.value.clone())
+    }
+
+    fn location(&self) -> String {
+        String::from("explicit value")
+    }
+}
+
+impl fmt::Debug for Explicit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Explicit")
+            .field("value", &self.value.as_ref().map(|_| "****"))
+            .finish()
+    }
+}
+
+/// Reads a credential from an environment variable.
+///
+/// Variables that are set to an empty string are treated as unset.
+#[derive(Clone, Debug)]
+pub struct EnvVar {
+    name: String,
+}
+
+impl EnvVar {
+    /// Reads the variable called `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self { name }
+    }
+
+    /// The name of the variable.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl CredentialProvider for EnvVar {
+    fn credential(&self) -> HttpResult<Option<String>> {
+        Ok(env::var(&self.name).ok().filter(|value| !value.is_empty()))
+    }
+
+    fn location(&self) -> String {
+        format!("environment variable {}", self.name)
+    }
+}
+
+/// Reads a credential from a file.
+///
+/// By default, the whole file, less surrounding whitespace, is the
+/// credential. A file can instead hold several credentials as
+/// `name = value` lines, one of which is picked out with
+/// [`with_key()`](CredentialsFile::with_key); blank lines, lines starting
+/// with `#`, and `[section]` headers are skipped, and values may be
+/// quoted. A file that does not exist has no credential.
+#[derive(Clone, Debug)]
+pub struct CredentialsFile {
+    path: PathBuf,
+    key: Option<String>,
+}
+
+impl CredentialsFile {
+    /// Reads the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self { path, key: None }
+    }
+
+    /// Reads the [`credentials`](CREDENTIALS_FILE) file in `app`'s
+    /// directory in the user's configuration directory.
+    ///
+    /// The configuration directory is `$XDG_CONFIG_HOME` if it is set, and
+    /// `~/.config` otherwise.
+    pub fn in_config_dir(app: &str) -> Self {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .unwrap_or_default();
+        Self::new(config_dir.join(app).join(CREDENTIALS_FILE))
+    }
+
+    /// Reads the value of `key` from a file of `name = value` lines,
+    /// rather than the whole file.
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// The path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl CredentialProvider for CredentialsFile {
+    fn credential(&self) -> HttpResult<Option<String>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let value = match &self.key {
+            None => Some(contents.trim()),
+            Some(key) => contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.starts_with('#') && !line.starts_with('['))
+                .filter_map(|line| line.split_once('='))
+                .find(|(name, _)| name.trim() == key)
+                .map(|(_, value)| unquote(value.trim())),
+        };
+        Ok(value.filter(|value| !value.is_empty()).map(str::to_string))
+    }
+
+    fn location(&self) -> String {
+        match &self.key {
+            Some(key) => format!("{key} in {}", self.path.display()),
+            None => format!("file {}", self.path.display()),
+        }
+    }
+}
+
+/// `value` without the single or double quotes around it, if it has them.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| {
+            value
+                .strip_prefix(quote)
+                .and_then(|value| value.strip_suffix(quote))
+        })
+        .unwrap_or(value)
+}
+
+/// Reads a credential from the operating system's keyring.
+///
+/// This uses the Keychain on macOS, the Credential Manager on Windows, and
+/// the kernel keyring on Linux. Only available when the **keyring**
+/// feature is enabled.
+#[cfg(feature = "keyring")]
+#[derive(Clone, Debug)]
+pub struct Keyring {
+    service: String,
+    user: String,
+}
+
+#[cfg(feature = "keyring")]
+impl Keyring {
+    /// Reads the password saved for `user` of `service`.
+    pub fn new(service: impl Into<String>, user: impl Into<String>) -> Self {
+        let service = service.into();
+        let user = user.into();
+        Self { service, user }
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl CredentialProvider for Keyring {
+    fn credential(&self) -> HttpResult<Option<String>> {
+        let keyring_error = |err: keyring::Error| {
+            HttpError::InvalidCredentials(format!("could not read {}: {err}", self.location()))
+        };
+        let entry = keyring::Entry::new(&self.service, &self.user).map_err(keyring_error)?;
+        match entry.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(keyring_error(err)),
+        }
+    }
+
+    fn location(&self) -> String {
+        format!("keyring entry {} for {}", self.service, self.user)
+    }
+}
+
+/// Asks each of a list of providers for a credential in turn, and uses the
+/// first one that is found.
+///
+/// See the [module documentation](crate::auth::credentials) for details.
+#[derive(Debug, Default)]
+pub struct CredentialChain {
+    providers: Vec<Box<dyn CredentialProvider>>,
+}
+
+impl CredentialChain {
+    /// Creates a chain with no providers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The usual chain for `app`: the `APP_API_KEY` environment variable,
+    /// where `APP` is `app` in uppercase with dashes replaced by
+    /// underscores, then the [credentials file](CredentialsFile::in_config_dir)
+    /// in `app`'s configuration directory, and, if the **keyring** feature
+    /// is enabled, the keyring entry for the `api_key` user of `app`.
+    pub fn for_app(app: &str) -> Self {
+        let var = format!("{}_API_KEY", app.to_ascii_uppercase().replace('-', "_"));
+        let chain = Self::new()
+            .with(EnvVar::new(var))
+            .with(CredentialsFile::in_config_dir(app));
+        #[cfg(feature = "keyring")]
+        let chain = chain.with(Keyring::new(app, "api_key"));
+        chain
+    }
+
+    /// Adds `provider` to the end of the chain.
+    pub fn with(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    /// Adds `value` to the front of the chain, so that it is used if it is
+    /// set.
+    pub fn with_explicit(mut self, value: Option<impl Into<String>>) -> Self {
+        self.providers.insert(0, Box::new(Explicit::new(value)));
+        self
+    }
+}
+
+impl CredentialProvider for CredentialChain {
+    fn credential(&self) -> HttpResult<Option<String>> {
+        for provider in &self.providers {
+            if let Some(credential) = provider.credential()? {
+                return Ok(Some(credential));
+            }
+        }
+        Ok(None)
+    }
+
+    fn location(&self) -> String {
+        let locations: Vec<_> = self.providers.iter().map(|p| p.location()).collect();
+        locations.join(", ")
+    }
+}
+
+/// The credential from `provider`.
+///
+/// # Errors
+///
+/// Returns [`HttpError::InvalidCredentials`], naming where the provider
+/// looked, if it has no credential.
+pub(crate) fn require(provider: &dyn CredentialProvider) -> HttpResult<String> {
+    provider.credential()?.ok_or_else(|| {
+        HttpError::InvalidCredentials(format!(
+            "no credential was found in {}",
+            provider.location()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_env::{with_var, with_vars};
+
+    #[test]
+    fn it_provides_explicit_values() -> HttpResult<()> {
+        assert_eq!(
+            Explicit::new(Some("key")).credential()?.as_deref(),
+            Some("key")
+        );
+        assert_eq!(Explicit::new(None::<String>).credential()?, None);
+        assert!(!format!("{:?}", Explicit::new(Some("key"))).contains("key"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_environment_variables() {
+        let provider = EnvVar::new("HYPERTYPER_TEST_API_KEY");
+        with_var("HYPERTYPER_TEST_API_KEY", Some("key"), || {
+            assert_eq!(provider.credential().unwrap().as_deref(), Some("key"));
+        });
+        with_var("HYPERTYPER_TEST_API_KEY", Some(""), || {
+            assert_eq!(provider.credential().unwrap(), None);
+        });
+        assert_eq!(
+            provider.location(),
+            "environment variable HYPERTYPER_TEST_API_KEY"
+        );
+    }
+
+    #[test]
+    fn it_reads_credentials_files() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("credentials");
+        let provider = CredentialsFile::new(&path);
+        assert_eq!(provider.credential()?, None);
+
+        fs::write(&path, "  key\n")?;
+        assert_eq!(provider.credential()?.as_deref(), Some("key"));
+
+        fs::write(
+            &path,
+            "# Keys\n[default]\napi_key = \"key\"\nsecret='secret'\nempty =\n",
+        )?;
+        let read = |key: &str| CredentialsFile::new(&path).with_key(key).credential();
+        assert_eq!(read("api_key")?.as_deref(), Some("key"));
+        assert_eq!(read("secret")?.as_deref(), Some("secret"));
+        assert_eq!(read("empty")?, None);
+        assert_eq!(read("missing")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_finds_credentials_files_in_the_config_dir() {
+        with_var("XDG_CONFIG_HOME", Some("/etc/xdg"), || {
+            let provider = CredentialsFile::in_config_dir("myapp");
+            assert_eq!(provider.path(), Path::new("/etc/xdg/myapp/credentials"));
+        });
+        with_vars(
+            [("XDG_CONFIG_HOME", None), ("HOME", Some("/home/user"))],
+            || {
+                let provider = CredentialsFile::in_config_dir("myapp");
+                assert_eq!(
+                    provider.path(),
+                    Path::new("/home/user/.config/myapp/credentials")
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn it_uses_the_first_credential_in_a_chain() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir(dir.path().join("my-app"))?;
+        fs::write(dir.path().join("my-app").join("credentials"), "from-file")?;
+        let config_home = dir.path().to_str().unwrap();
+
+        with_vars(
+            [
+                ("XDG_CONFIG_HOME", Some(config_home)),
+                ("MY_APP_API_KEY", Some("from-env")),
+            ],
+            || {
+                let chain = CredentialChain::for_app("my-app");
+                assert_eq!(chain.credential().unwrap().as_deref(), Some("from-env"));
+                let chain = chain.with_explicit(Some("explicit"));
+                assert_eq!(chain.credential().unwrap().as_deref(), Some("explicit"));
+            },
+        );
+        with_vars(
+            [
+                ("XDG_CONFIG_HOME", Some(config_home)),
+                ("MY_APP_API_KEY", None),
+            ],
+            || {
+                let chain = CredentialChain::for_app("my-app").with_explicit(None::<String>);
+                assert_eq!(chain.credential().unwrap().as_deref(), Some("from-file"));
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_names_every_location_when_no_credential_is_found() {
+        let chain = CredentialChain::new()
+            .with(EnvVar::new("HYPERTYPER_UNSET_API_KEY"))
+            .with(CredentialsFile::new("/nonexistent/credentials").with_key("api_key"));
+        let err = require(&chain).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            HttpError::InvalidCredentials(String::from(
+                "no credential was found in environment variable HYPERTYPER_UNSET_API_KEY, \
+                 api_key in /nonexistent/credentials"
+            ))
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,740 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! HTTP Digest authentication.
+//!
+//! Servers that use [RFC 7616] Digest authentication answer the first
+//! request with HTTP 401 and a `WWW-Authenticate` challenge carrying a
+//! nonce, and expect later requests to prove that the client knows the
+//! password by hashing it with the nonce, the method, and the URI. Many
+//! embedded devices, such as cameras and routers, support no other
+//! scheme.
+//!
+//! A [`DigestAuth`] remembers the most recent challenge for each realm it
+//! has been sent, along with the client nonce and nonce count it uses to
+//! answer it, and authenticates requests to the same origin with it. A
+//! [`DigestService`] sends each request, and if the server answers with a
+//! Digest challenge, records it and sends the request again; requests
+//! after the first are authenticated up front and are only replayed when
+//! the server sends a new nonce.
+//!
+//! MD5, SHA-256, and their `-sess` variants are supported, as are the
+//! `auth` and `auth-int` qualities of protection and hashed user names.
+//! Challenges for SHA-512-256 are skipped in favor of any others the
+//! server offers.
+//!
+//! This is only available when the **digest-auth** feature is enabled.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::auth::digest::{DigestAuth, DigestService};
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetResponse;
+//! use reqwest::header::HeaderMap;
+//!
+//! # async fn run<S: HttpGetResponse + Sync>(camera: S) -> HttpResult<()> {
+//! let auth = DigestAuth::new("admin", "hunter2");
+//! let service = DigestService::new(camera, auth);
+//! let response = service
+//!     .get_response("http://192.168.1.64/ISAPI/System/status", &HeaderMap::new())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [RFC 7616]: https://www.rfc-editor.org/rfc/rfc7616
+
+use crate::auth::Authenticator;
+use crate::headers::{self, Challenge};
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{BinaryResponse, HttpBinary, HttpGetResponse, HttpPost, HttpResponse};
+use crate::{HttpError, HttpResult};
+use bytes::Bytes;
+use reqwest::header::{self as header_names, HeaderMap, HeaderValue};
+use reqwest::{IntoUrl, Method, Request, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Random bytes in each client nonce.
+const CNONCE_BYTES: usize = 16;
+
+/// The hash algorithm named in a Digest challenge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    /// `MD5`, which is assumed when a challenge does not name one.
+    Md5,
+
+    /// `MD5-sess`.
+    Md5Sess,
+
+    /// `SHA-256`.
+    Sha256,
+
+    /// `SHA-256-sess`.
+    Sha256Sess,
+}
+
+impl DigestAlgorithm {
+    /// The algorithm called `name`, ignoring case, if it is supported.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Md5, Self::Md5Sess, Self::Sha256, Self::Sha256Sess]
+            .into_iter()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The algorithm's name, as it is sent in the `Authorization` header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    /// Whether the password hash is combined with the nonces, as the
+    /// `-sess` variants are.
+    pub fn is_session(&self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    /// The lowercase hex digest of `data`.
+    fn hash(&self, data: &str) -> String {
+        match self {
+            Self::Md5 | Self::Md5Sess => {
+                hex::encode(<md5::Md5 as md5::Digest>::digest(data.as_bytes()))
+            }
+            Self::Sha256 | Self::Sha256Sess => {
+                hex::encode(<Sha256 as sha2::Digest>::digest(data.as_bytes()))
+            }
+        }
+    }
+}
+
+/// The quality of protection used to answer a challenge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Qop {
+    Auth,
+    AuthInt,
+}
+
+impl Qop {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Auth => "auth",
+            Self::AuthInt => "auth-int",
+        }
+    }
+}
+
+/// A challenge that was answered, and the state used to answer it.
+#[derive(Clone, Debug)]
+struct Session {
+    origin: String,
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: DigestAlgorithm,
+    qop: Option<Qop>,
+    userhash: bool,
+    cnonce: String,
+    nc: u32,
+}
+
+/// Authenticates requests with HTTP Digest credentials.
+///
+/// Clones share the challenges they have been sent.
+///
+/// See the [module documentation](crate::auth::digest) for details.
+#[derive(Clone)]
+pub struct DigestAuth {
+    username: String,
+    password: String,
+    sessions: Arc<Mutex<Vec<Session>>>,
+}
+
+impl DigestAuth {
+    /// Creates credentials for `username` and `password`.
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            sessions: Arc::default(),
+        }
+    }
+
+    /// The user name.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Records the first supported Digest challenge in `challenges`, which
+    /// were sent in response to a request to `uri`.
+    ///
+    /// The challenge replaces any that was recorded for the same realm, so
+    /// a new nonce, such as one sent with `stale=true`, starts a new nonce
+    /// count. Returns `false` if none of the challenges could be answered.
+    pub fn challenge(&self, uri: &str, challenges: &[Challenge]) -> HttpResult<bool> {
+        let Some(mut session) = challenges
+            .iter()
+            .find_map(|challenge| self.session(uri, challenge))
+        else {
+            return Ok(false);
+        };
+        session.cnonce = cnonce()?;
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|s| s.origin != session.origin || s.realm != session.realm);
+        sessions.push(session);
+        Ok(true)
+    }
+
+    /// The `Authorization` header for a request to `uri`, if a challenge
+    /// has been recorded for its origin.
+    ///
+    /// `body` is only hashed if the server asked for `auth-int`
+    /// protection, in which case a missing body is treated as empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::InvalidCredentials`] if the user name cannot be
+    /// sent in a header.
+    pub fn authorization(
+        &self,
+        method: &Method,
+        uri: &str,
+        body: Option<&[u8]>,
+    ) -> HttpResult<Option<HeaderValue>> {
+        let (origin, target) = split_uri(uri);
+        let session = {
+            let mut sessions = self.sessions.lock().unwrap();
+            match sessions.iter_mut().rev().find(|s| s.origin == origin) {
+                Some(session) => {
+                    session.nc += 1;
+                    session.clone()
+                }
+                None => return Ok(None),
+            }
+        };
+        self.respond(&session, method, &target, body).map(Some)
+    }
+
+    /// Builds a session from `challenge`, if it is a Digest challenge that
+    /// can be answered.
+    fn session(&self, uri: &str, challenge: &Challenge) -> Option<Session> {
+        if !challenge.is_scheme("Digest") {
+            return None;
+        }
+        let algorithm = match challenge.param("algorithm") {
+            Some(name) => DigestAlgorithm::from_name(name)?,
+            None => DigestAlgorithm::Md5,
+        };
+        let qop = match challenge.param("qop") {
+            None => None,
+            Some(qop) => {
+                let offered: Vec<_> = qop.split(',').map(str::trim).collect();
+                if offered.iter().any(|qop| qop.eq_ignore_ascii_case("auth")) {
+                    Some(Qop::Auth)
+                } else if offered
+                    .iter()
+                    .any(|qop| qop.eq_ignore_ascii_case("auth-int"))
+                {
+                    Some(Qop::AuthInt)
+                } else {
+                    return None;
+                }
+            }
+        };
+        let (origin, _) = split_uri(uri);
+        Some(Session {
+            origin,
+            realm: challenge.realm().unwrap_or_default().to_string(),
+            nonce: challenge.param("nonce")?.to_string(),
+            opaque: challenge.param("opaque").map(str::to_string),
+            algorithm,
+            qop,
+            userhash: challenge
+                .param("userhash")
+                .is_some_and(|userhash| userhash.eq_ignore_ascii_case("true")),
+            cnonce: String::new(),
+            nc: 0,
+        })
+    }
+
+    /// The `Authorization` header answering `session` for a request.
+    fn respond(
+        &self,
+        session: &Session,
+        method: &Method,
+        target: &str,
+        body: Option<&[u8]>,
+    ) -> HttpResult<HeaderValue> {
+        let algorithm = session.algorithm;
+        let nc = format!("{:08x}", session.nc);
+
+        let mut ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            self.username, session.realm, self.password
+        ));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(&format!("{ha1}:{}:{}", session.nonce, session.cnonce));
+        }
+        let ha2 = match session.qop {
+            Some(Qop::AuthInt) => {
+                let body = String::from_utf8_lossy(body.unwrap_or_default());
+                algorithm.hash(&format!("{method}:{target}:{}", algorithm.hash(&body)))
+            }
+            _ => algorithm.hash(&format!("{method}:{target}")),
+        };
+        let response = match session.qop {
+            Some(qop) => algorithm.hash(&format!(
+                "{ha1}:{}:{nc}:{}:{}:{ha2}",
+                session.nonce,
+                session.cnonce,
+                qop.name()
+            )),
+            None => algorithm.hash(&format!("{ha1}:{}:{ha2}", session.nonce)),
+        };
+
+        let username = if session.userhash {
+            algorithm.hash(&format!("{}:{}", self.username, session.realm))
+        } else {
+            self.username.clone()
+        };
+        if username.chars().any(char::is_control) {
+            return Err(HttpError::InvalidCredentials(String::from(
+                "Digest authentication user names cannot contain control characters",
+            )));
+        }
+
+        let mut value = format!(
+            "Digest username={}, realm={}, uri={}, algorithm={}, nonce={}",
+            quote(&username),
+            quote(&session.realm),
+            quote(target),
+            algorithm.name(),
+            quote(&session.nonce),
+        );
+        if let Some(qop) = session.qop {
+            value.push_str(&format!(
+                ", nc={nc}, cnonce={}, qop={}",
+                quote(&session.cnonce),
+                qop.name()
+            ));
+        }
+        value.push_str(&format!(", response={}", quote(&response)));
+        if let Some(opaque) = &session.opaque {
+            value.push_str(&format!(", opaque={}", quote(opaque)));
+        }
+        if session.userhash {
+            value.push_str(", userhash=true");
+        }
+        let mut value = HeaderValue::try_from(value)?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+impl Authenticator for DigestAuth {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        let body = request.body().and_then(|body| body.as_bytes());
+        let value = self.authorization(request.method(), request.url().as_str(), body)?;
+        if let Some(value) = value {
+            request
+                .headers_mut()
+                .insert(header_names::AUTHORIZATION, value);
+        }
+        Ok(())
+    }
+
+    fn scheme(&self) -> &str {
+        "digest"
+    }
+}
+
+impl fmt::Debug for DigestAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let realms: Vec<_> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|session| session.realm.clone())
+            .collect();
+        f.debug_struct("DigestAuth")
+            .field("username", &self.username)
+            .field("password", &"****")
+            .field("realms", &realms)
+            .finish()
+    }
+}
+
+/// A new client nonce.
+fn cnonce() -> HttpResult<String> {
+    let mut bytes = [0; CNONCE_BYTES];
+    getrandom::fill(&mut bytes).map_err(std::io::Error::from)?;
+    Ok(hex::encode(bytes))
+}
+
+/// The origin of `uri`, and its path and query, which are what Digest
+/// responses are computed over.
+///
+/// Relative URIs, as are used with test services, have an empty origin.
+fn split_uri(uri: &str) -> (String, String) {
+    match Url::parse(uri) {
+        Ok(url) => {
+            let target = match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            };
+            (url.origin().ascii_serialization(), target)
+        }
+        Err(_) => (String::new(), uri.to_string()),
+    }
+}
+
+/// `value` as a quoted string.
+fn quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+/// Wraps an HTTP service and answers Digest challenges from it.
+///
+/// See the [module documentation](crate::auth::digest) for details.
+#[derive(Debug)]
+pub struct DigestService<S> {
+    inner: S,
+    auth: DigestAuth,
+}
+
+impl<S> DigestService<S> {
+    /// Wraps `inner` in a service that authenticates requests with `auth`.
+    pub fn new(inner: S, auth: DigestAuth) -> Self {
+        Self { inner, auth }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The credentials that requests are authenticated with.
+    pub fn auth(&self) -> &DigestAuth {
+        &self.auth
+    }
+
+    /// Sends a request to `uri` with `send`, and sends it again if the
+    /// server answers with a new Digest challenge.
+    async fn send<R, F, Fut>(
+        &self,
+        uri: &str,
+        challenges: fn(&HttpResult<R>) -> Vec<Challenge>,
+        send: F,
+    ) -> HttpResult<R>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = HttpResult<R>>,
+    {
+        match send().await {
+            result if !self.auth.challenge(uri, &challenges(&result))? => return result,
+            _ => {}
+        }
+        send().await
+    }
+
+    /// `headers` with an `Authorization` header for a request to `uri`.
+    fn authorize(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+    ) -> HttpResult<HeaderMap> {
+        let mut headers = headers.clone();
+        if let Some(value) = self.auth.authorization(&method, uri, body)? {
+            headers.insert(header_names::AUTHORIZATION, value);
+        }
+        Ok(headers)
+    }
+}
+
+/// The challenges sent with an HTTP 401 error.
+fn error_challenges<R>(result: &HttpResult<R>) -> Vec<Challenge> {
+    match result {
+        Err(err) => err.challenges().to_vec(),
+        Ok(_) => Vec::new(),
+    }
+}
+
+/// The challenges sent with an HTTP 401 response or error.
+fn response_challenges(result: &HttpResult<HttpResponse>) -> Vec<Challenge> {
+    match result {
+        Ok(response) if response.status() == StatusCode::UNAUTHORIZED => {
+            headers::www_authenticate(response.headers())
+        }
+        _ => error_challenges(result),
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for DigestService<S> {
+    async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str();
+        self.send(uri, error_challenges, || {
+            self.inner.post(uri, &self.auth, data)
+        })
+        .await
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGetResponse for DigestService<S> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str();
+        self.send(uri, response_challenges, || async move {
+            let headers = self.authorize(Method::GET, uri, headers, None)?;
+            self.inner.get_response(uri, &headers).await
+        })
+        .await
+    }
+}
+
+impl<S: HttpBinary + Sync> HttpBinary for DigestService<S> {
+    async fn get_binary<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str();
+        self.send(uri, error_challenges, || async move {
+            let headers = self.authorize(Method::GET, uri, headers, None)?;
+            self.inner.get_binary(uri, &headers).await
+        })
+        .await
+    }
+
+    async fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str();
+        self.send(uri, error_challenges, || {
+            let body = body.clone();
+            async move {
+                let headers = self.authorize(Method::POST, uri, headers, Some(&body))?;
+                self.inner.post_binary(uri, &headers, body).await
+            }
+        })
+        .await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for DigestService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Post, Verb::GetResponse, Verb::Binary])
+            .wrap(Middleware::new("digest"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as SyncMutex;
+
+    // The example from RFC 7616 § 3.9.1.
+    const URI: &str = "http://www.example.org/dir/index.html";
+    const REALM: &str = "http-auth@example.org";
+    const NONCE: &str = "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v";
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+    const OPAQUE: &str = "FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS";
+
+    fn challenge(algorithm: &str) -> Challenge {
+        challenge_with(algorithm, NONCE, "auth, auth-int")
+    }
+
+    fn challenge_with(algorithm: &str, nonce: &str, qop: &str) -> Challenge {
+        Challenge::new("Digest")
+            .with_param("realm", REALM)
+            .with_param("qop", qop)
+            .with_param("algorithm", algorithm)
+            .with_param("nonce", nonce)
+            .with_param("opaque", OPAQUE)
+    }
+
+    fn example_auth(challenges: &[Challenge]) -> HttpResult<DigestAuth> {
+        let auth = DigestAuth::new("Mufasa", "Circle of Life");
+        assert!(auth.challenge(URI, challenges)?);
+        auth.sessions.lock().unwrap()[0].cnonce = String::from(CNONCE);
+        Ok(auth)
+    }
+
+    fn authorization(auth: &DigestAuth, uri: &str) -> HttpResult<Option<String>> {
+        let value = auth.authorization(&Method::GET, uri, None)?;
+        Ok(value.map(|value| value.to_str().unwrap().to_string()))
+    }
+
+    #[test]
+    fn it_answers_md5_challenges() -> HttpResult<()> {
+        let auth = example_auth(&[challenge("MD5")])?;
+        assert_eq!(
+            authorization(&auth, URI)?.unwrap(),
+            format!(
+                r#"Digest username="Mufasa", realm="{REALM}", uri="/dir/index.html", algorithm=MD5, nonce="{NONCE}", nc=00000001, cnonce="{CNONCE}", qop=auth, response="8ca523f5e9506fed4657c9700eebdbec", opaque="{OPAQUE}""#
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_answers_the_first_supported_challenge() -> HttpResult<()> {
+        let challenges = [
+            Challenge::new("Basic").with_param("realm", REALM),
+            challenge("SHA-512-256"),
+            challenge("SHA-256"),
+            challenge("MD5"),
+        ];
+        let auth = example_auth(&challenges)?;
+        let value = authorization(&auth, URI)?.unwrap();
+        assert!(value.contains("algorithm=SHA-256,"));
+        assert!(value.contains(
+            r#"response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_requests_with_the_same_nonce() -> HttpResult<()> {
+        let auth = example_auth(&[challenge("MD5")])?;
+        assert!(authorization(&auth, URI)?.unwrap().contains("nc=00000001"));
+        let value = authorization(&auth, "http://www.example.org/dir/other.html")?.unwrap();
+        assert!(value.contains("nc=00000002"));
+        assert!(value.contains(r#"uri="/dir/other.html""#));
+
+        let stale = challenge_with("MD5", "new-nonce", "auth").with_param("stale", "true");
+        assert!(auth.challenge(URI, &[stale])?);
+        let value = authorization(&auth, URI)?.unwrap();
+        assert!(value.contains(r#"nonce="new-nonce""#));
+        assert!(value.contains("nc=00000001"));
+        assert!(!value.contains(CNONCE));
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_authenticates_requests_to_challenged_origins() -> HttpResult<()> {
+        let auth = example_auth(&[challenge("MD5")])?;
+        assert_eq!(authorization(&auth, "http://other.example.org/")?, None);
+        let mut request = Request::new(Method::GET, "https://www.example.org/".parse().unwrap());
+        auth.authenticate(&mut request)?;
+        assert!(!request.headers().contains_key(header_names::AUTHORIZATION));
+        Ok(())
+    }
+
+    #[test]
+    fn it_supports_legacy_and_session_challenges() -> HttpResult<()> {
+        let legacy = Challenge::new("Digest")
+            .with_param("realm", "testrealm@host.com")
+            .with_param("nonce", "dcd98b7102dd2f0e8b11d0f600bfb0c093");
+        let auth = DigestAuth::new("Mufasa", "Circle Of Life");
+        assert!(auth.challenge(URI, &[legacy])?);
+        let value = authorization(&auth, URI)?.unwrap();
+        assert!(!value.contains("qop="));
+        assert!(!value.contains("cnonce="));
+
+        let auth = example_auth(&[challenge("SHA-256-sess").with_param("userhash", "true")])?;
+        let value = authorization(&auth, URI)?.unwrap();
+        assert!(value.contains("algorithm=SHA-256-sess,"));
+        assert!(value.ends_with("userhash=true"));
+        assert!(!value.contains("Mufasa"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_ignores_challenges_it_cannot_answer() -> HttpResult<()> {
+        let auth = DigestAuth::new("Mufasa", "Circle of Life");
+        let no_nonce = Challenge::new("Digest").with_param("realm", REALM);
+        let bad_qop = challenge_with("MD5", NONCE, "auth-conf");
+        assert!(!auth.challenge(URI, &[Challenge::new("Bearer"), no_nonce])?);
+        assert!(!auth.challenge(URI, &[bad_qop])?);
+        assert_eq!(authorization(&auth, URI)?, None);
+        Ok(())
+    }
+
+    /// A device that challenges requests without valid Digest credentials.
+    #[derive(Default)]
+    struct Camera {
+        authorizations: SyncMutex<Vec<Option<String>>>,
+    }
+
+    impl HttpGetResponse for Camera {
+        async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            let sent = headers
+                .get(header_names::AUTHORIZATION)
+                .map(|value| value.to_str().unwrap().to_string());
+            self.authorizations.lock().unwrap().push(sent.clone());
+
+            // Answers the challenge with the right password and the
+            // client's nonce to find the response that is expected.
+            let expected = sent.as_deref().and_then(|sent| {
+                let cnonce = sent.split("cnonce=\"").nth(1)?.split('"').next()?;
+                let auth = DigestAuth::new("admin", "hunter2");
+                auth.challenge(uri.as_str(), &[challenge("MD5")]).ok()?;
+                auth.sessions.lock().unwrap()[0].cnonce = cnonce.to_string();
+                authorization(&auth, uri.as_str()).ok()?
+            });
+            if expected.is_some() && expected == sent {
+                Ok(HttpResponse::new(StatusCode::OK, "ok"))
+            } else {
+                let challenge = HeaderValue::try_from(challenge("MD5").to_string())?;
+                Ok(HttpResponse::new(StatusCode::UNAUTHORIZED, "")
+                    .with_header(header_names::WWW_AUTHENTICATE, challenge))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn it_replays_challenged_requests() -> HttpResult<()> {
+        let service = DigestService::new(Camera::default(), DigestAuth::new("admin", "hunter2"));
+        let response = service.get_response(URI, &HeaderMap::new()).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let authorizations = service.inner().authorizations.lock().unwrap().clone();
+        assert_eq!(authorizations.len(), 2);
+        assert_eq!(authorizations[0], None);
+        assert!(authorizations[1].as_ref().unwrap().starts_with("Digest "));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_when_credentials_are_rejected() -> HttpResult<()> {
+        let service = DigestService::new(Camera::default(), DigestAuth::new("admin", "wrong"));
+        let response = service.get_response(URI, &HeaderMap::new()).await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(service.inner().authorizations.lock().unwrap().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_show_passwords() {
+        let auth = DigestAuth::new("Mufasa", "Circle of Life");
+        assert!(!format!("{auth:?}").contains("Circle"));
+    }
+}
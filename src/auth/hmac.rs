@@ -0,0 +1,558 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! HMAC request signing.
+//!
+//! Many payment and webhook APIs expect each request to carry an HMAC of
+//! some combination of its method, path, a timestamp, and its body, in a
+//! header of their choosing. An [`HmacSigner`] builds the message to sign
+//! from a template, such as `{timestamp}{method}{path_and_query}{body}`,
+//! and sends the signature in the configured header. The hash algorithm,
+//! the signature's encoding, and the header that carries the timestamp can
+//! all be changed to match the API.
+//!
+//! The following placeholders can be used in templates; any other text is
+//! signed as it is:
+//!
+//! - `{method}` - the request method, such as `POST`
+//! - `{host}` - the host the request is sent to, with its port if it has one
+//! - `{path}` - the path of the request's URL
+//! - `{query}` - the query string of the request's URL, without the `?`
+//! - `{path_and_query}` - the path followed by the query string, if there
+//!   is one
+//! - `{timestamp}` - the time the request was signed
+//! - `{body}` - the request body
+//!
+//! Signing is usually one part of an API's authentication, alongside a key
+//! that identifies the caller. A signer can
+//! [wrap another authenticator](HmacSigner::with_auth), which is applied
+//! before the request is signed, so both can be applied to every request
+//! with a single [`AuthService`]. A signer is also an [`Interceptor`], so
+//! it can sign requests in a [`LayeredService`] instead, alongside other
+//! interceptors; relative URIs are signed as they are, so templates that
+//! include the `{host}` need absolute URIs. Streamed bodies cannot be
+//! signed before they are sent, so requests with them fail with
+//! [`HttpError::InvalidCredentials`].
+//!
+//! This is only available when the **hmac** feature is enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::auth::ApiKeyHeader;
+//! use hypertyper::auth::hmac::{HmacAlgorithm, HmacSigner, SignatureEncoding};
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::auth::AuthService;
+//! use reqwest::header::HeaderName;
+//!
+//! fn signed<S: HttpService>(service: S, key: &str, secret: &str) -> AuthService<S> {
+//!     let signer = HmacSigner::new(secret, HeaderName::from_static("x-signature"))
+//!         .with_algorithm(HmacAlgorithm::Sha512)
+//!         .with_template("{timestamp}\n{method}\n{path_and_query}\n{body}")
+//!         .with_encoding(SignatureEncoding::Base64)
+//!         .with_timestamp_header(HeaderName::from_static("x-timestamp"))
+//!         .with_auth(ApiKeyHeader::new(key));
+//!     AuthService::new(service, signer)
+//! }
+//! ```
+//!
+//! [`AuthService`]: crate::service::auth::AuthService
+//! [`LayeredService`]: crate::service::layer::LayeredService
+
+use crate::auth::Authenticator;
+use crate::service::auth::{resolve, unresolve};
+use crate::service::layer::{self, Interceptor};
+use crate::{HttpError, HttpResult};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use reqwest::Request;
+use reqwest::header::{HeaderName, HeaderValue};
+use sha2::{Sha256, Sha384, Sha512};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The template that signers use unless they are given another.
+pub const DEFAULT_TEMPLATE: &str = "{timestamp}{method}{path_and_query}{body}";
+
+/// The hash function used to compute signatures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HmacAlgorithm {
+    /// HMAC-SHA256.
+    #[default]
+    Sha256,
+
+    /// HMAC-SHA384.
+    Sha384,
+
+    /// HMAC-SHA512.
+    Sha512,
+}
+
+impl HmacAlgorithm {
+    /// Computes the HMAC of `message` with `key`.
+    pub fn sign(&self, key: &[u8], message: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => mac::<Hmac<Sha256>>(key, message),
+            Self::Sha384 => mac::<Hmac<Sha384>>(key, message),
+            Self::Sha512 => mac::<Hmac<Sha512>>(key, message),
+        }
+    }
+
+    /// True if `signature` is the HMAC of `message` with `key`.
+    ///
+    /// The signatures are compared in constant time, so the comparison
+    /// does not reveal how much of `signature` is correct.
+    pub fn verify(&self, key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            Self::Sha256 => verify::<Hmac<Sha256>>(key, message, signature),
+            Self::Sha384 => verify::<Hmac<Sha384>>(key, message, signature),
+            Self::Sha512 => verify::<Hmac<Sha512>>(key, message, signature),
+        }
+    }
+}
+
+/// How signatures are encoded in their header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    /// Lowercase hexadecimal.
+    #[default]
+    Hex,
+
+    /// Standard base64, with padding.
+    Base64,
+}
+
+impl SignatureEncoding {
+    /// Encodes `signature`.
+    pub fn encode(&self, signature: &[u8]) -> String {
+        match self {
+            Self::Hex => hex::encode(signature),
+            Self::Base64 => STANDARD.encode(signature),
+        }
+    }
+}
+
+/// How the signing time is formatted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// Seconds since the Unix epoch.
+    #[default]
+    Seconds,
+
+    /// Milliseconds since the Unix epoch.
+    Millis,
+}
+
+impl TimestampFormat {
+    /// Formats `time`.
+    pub fn format(&self, time: SystemTime) -> String {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        match self {
+            Self::Seconds => since_epoch.as_secs().to_string(),
+            Self::Millis => since_epoch.as_millis().to_string(),
+        }
+    }
+}
+
+/// Signs requests with an HMAC over parts of the request.
+///
+/// See the [module documentation](crate::auth::hmac) for details.
+#[derive(Clone)]
+pub struct HmacSigner {
+    secret: Vec<u8>,
+    header: HeaderName,
+    algorithm: HmacAlgorithm,
+    template: String,
+    encoding: SignatureEncoding,
+    prefix: String,
+    timestamp_header: Option<HeaderName>,
+    timestamp_format: TimestampFormat,
+    auth: Option<Arc<dyn Authenticator>>,
+}
+
+impl HmacSigner {
+    /// Signs requests with `secret`, sending the signature in `header`.
+    ///
+    /// Requests are signed with HMAC-SHA256 over the
+    /// [default template](DEFAULT_TEMPLATE), and the signature is sent in
+    /// hex.
+    pub fn new(secret: impl Into<Vec<u8>>, header: HeaderName) -> Self {
+        Self {
+            secret: secret.into(),
+            header,
+            algorithm: HmacAlgorithm::default(),
+            template: String::from(DEFAULT_TEMPLATE),
+            encoding: SignatureEncoding::default(),
+            prefix: String::new(),
+            timestamp_header: None,
+            timestamp_format: TimestampFormat::default(),
+            auth: None,
+        }
+    }
+
+    /// Signs requests with `algorithm` instead.
+    pub fn with_algorithm(mut self, algorithm: HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Builds the message to sign from `template` instead.
+    ///
+    /// See the [module documentation](crate::auth::hmac) for the
+    /// placeholders that can be used.
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Encodes signatures with `encoding` instead.
+    pub fn with_encoding(mut self, encoding: SignatureEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sends `prefix` before the signature, as in `sha256=...`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sends the signing time in `header`, so the server can rebuild the
+    /// signed message.
+    pub fn with_timestamp_header(mut self, header: HeaderName) -> Self {
+        self.timestamp_header = Some(header);
+        self
+    }
+
+    /// Formats the signing time with `format` instead of as seconds.
+    pub fn with_timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamp_format = format;
+        self
+    }
+
+    /// Applies `auth` to each request before it is signed.
+    pub fn with_auth(mut self, auth: impl Authenticator + 'static) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// The header that signatures are sent in.
+    pub fn header(&self) -> &HeaderName {
+        &self.header
+    }
+
+    /// The hash function that signatures are computed with.
+    pub fn algorithm(&self) -> HmacAlgorithm {
+        self.algorithm
+    }
+
+    /// The template that the message to sign is built from.
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// The message that would be signed for `request` at `time`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::InvalidCredentials`] if the template has an
+    /// unknown placeholder, or if it includes the body and the request
+    /// has a streamed body.
+    pub fn message(&self, request: &Request, time: SystemTime) -> HttpResult<Vec<u8>> {
+        let url = request.url();
+        let mut message = Vec::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find('{') {
+            message.extend_from_slice(&rest.as_bytes()[..start]);
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| {
+                    HttpError::InvalidCredentials(format!(
+                        "unclosed placeholder in HMAC template: {}",
+                        self.template
+                    ))
+                })?;
+            match &rest[start + 1..end] {
+                "method" => message.extend_from_slice(request.method().as_str().as_bytes()),
+                "host" => {
+                    let host = url.host_str().unwrap_or_default();
+                    message.extend_from_slice(host.as_bytes());
+                    if let Some(port) = url.port() {
+                        message.extend_from_slice(format!(":{port}").as_bytes());
+                    }
+                }
+                "path" => message.extend_from_slice(url.path().as_bytes()),
+                "query" => message.extend_from_slice(url.query().unwrap_or_default().as_bytes()),
+                "path_and_query" => {
+                    message.extend_from_slice(url.path().as_bytes());
+                    if let Some(query) = url.query() {
+                        message.push(b'?');
+                        message.extend_from_slice(query.as_bytes());
+                    }
+                }
+                "timestamp" => {
+                    message.extend_from_slice(self.timestamp_format.format(time).as_bytes())
+                }
+                "body" => match request.body() {
+                    None => {}
+                    Some(body) => match body.as_bytes() {
+                        Some(bytes) => message.extend_from_slice(bytes),
+                        None => {
+                            return Err(HttpError::InvalidCredentials(String::from(
+                                "requests with streamed bodies cannot be signed",
+                            )));
+                        }
+                    },
+                },
+                placeholder => {
+                    return Err(HttpError::InvalidCredentials(format!(
+                        "unknown placeholder in HMAC template: {{{placeholder}}}"
+                    )));
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        message.extend_from_slice(rest.as_bytes());
+        Ok(message)
+    }
+
+    /// Signs `request` as if it were sent at `time`.
+    ///
+    /// [`authenticate()`](Authenticator::authenticate) signs requests with
+    /// the current time; this is useful for signing requests that will be
+    /// sent later, and for testing.
+    pub fn sign_at(&self, request: &mut Request, time: SystemTime) -> HttpResult<()> {
+        if let Some(auth) = &self.auth {
+            auth.authenticate(request)?;
+        }
+        let message = self.message(request, time)?;
+        let signature = self.algorithm.sign(&self.secret, &message);
+        let signature = format!("{}{}", self.prefix, self.encoding.encode(&signature));
+
+        let mut value = HeaderValue::try_from(signature)?;
+        value.set_sensitive(true);
+        let headers = request.headers_mut();
+        headers.insert(self.header.clone(), value);
+        if let Some(header) = &self.timestamp_header {
+            let timestamp = self.timestamp_format.format(time);
+            headers.insert(header.clone(), HeaderValue::try_from(timestamp)?);
+        }
+        Ok(())
+    }
+}
+
+impl Authenticator for HmacSigner {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        self.sign_at(request, SystemTime::now())
+    }
+
+    fn scheme(&self) -> &str {
+        "hmac"
+    }
+}
+
+impl Interceptor for HmacSigner {
+    /// Signs the request, adding the signature and the headers of any
+    /// wrapped authenticator to it.
+    fn before_request(&self, request: &mut layer::Request) -> HttpResult<()> {
+        let (url, relative) = resolve(request.uri())?;
+        let mut signed = Request::new(request.method().clone(), url);
+        *signed.headers_mut() = request.headers().clone();
+        *signed.body_mut() = request.body().cloned().map(Into::into);
+        self.authenticate(&mut signed)?;
+        request.set_uri(unresolve(signed.url(), relative));
+        *request.headers_mut() = signed.headers().clone();
+        Ok(())
+    }
+}
+
+impl fmt::Debug for HmacSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacSigner")
+            .field("secret", &"****")
+            .field("header", &self.header)
+            .field("algorithm", &self.algorithm)
+            .field("template", &self.template)
+            .field("encoding", &self.encoding)
+            .field("prefix", &self.prefix)
+            .field("timestamp_header", &self.timestamp_header)
+            .field("timestamp_format", &self.timestamp_format)
+            .field("auth", &self.auth)
+            .finish()
+    }
+}
+
+fn mac<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn verify<M: Mac + KeyInit>(key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.verify_slice(signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::ApiKeyHeader;
+    use reqwest::Method;
+    use std::time::Duration;
+
+    const SIGNATURE: HeaderName = HeaderName::from_static("x-signature");
+
+    fn time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)
+    }
+
+    fn request(method: Method, url: &str, body: Option<&str>) -> Request {
+        let mut request = Request::new(method, url.parse().unwrap());
+        if let Some(body) = body {
+            *request.body_mut() = Some(body.to_string().into());
+        }
+        request
+    }
+
+    #[test]
+    fn it_computes_known_signatures() {
+        // RFC 4231, test case 2.
+        let key = b"Jefe";
+        let message = b"what do ya want for nothing?";
+        assert_eq!(
+            hex::encode(HmacAlgorithm::Sha256.sign(key, message)),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex::encode(HmacAlgorithm::Sha384.sign(key, message)),
+            "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e\
+             8e2240ca5e69e2c78b3239ecfab21649"
+        );
+        assert_eq!(
+            hex::encode(HmacAlgorithm::Sha512.sign(key, message)),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn it_verifies_signatures() {
+        let signature = HmacAlgorithm::Sha384.sign(b"Jefe", b"message");
+        assert!(HmacAlgorithm::Sha384.verify(b"Jefe", b"message", &signature));
+        assert!(!HmacAlgorithm::Sha384.verify(b"Jefe", b"massage", &signature));
+        assert!(!HmacAlgorithm::Sha256.verify(b"Jefe", b"message", &signature));
+        assert!(!HmacAlgorithm::Sha384.verify(b"Jefe", b"message", &signature[..47]));
+    }
+
+    #[test]
+    fn it_builds_messages_from_templates() -> HttpResult<()> {
+        let request = request(
+            Method::POST,
+            "https://api.example.com:8443/v1/orders?limit=5",
+            Some(r#"{"id":1}"#),
+        );
+        let signer = HmacSigner::new("secret", SIGNATURE)
+            .with_template("{method}|{host}|{path}|{query}|{path_and_query}|{timestamp}|{body}");
+        let message = signer.message(&request, time())?;
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            r#"POST|api.example.com:8443|/v1/orders|limit=5|/v1/orders?limit=5|1700000000|{"id":1}"#
+        );
+
+        let request = self::request(Method::GET, "https://api.example.com/v1/orders", None);
+        let signer = signer
+            .with_template("{timestamp}{method}{path_and_query}{body}")
+            .with_timestamp_format(TimestampFormat::Millis);
+        let message = signer.message(&request, time())?;
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            "1700000000123GET/v1/orders"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_signs_requests() -> HttpResult<()> {
+        let mut request = request(
+            Method::POST,
+            "https://api.example.com/v1/orders",
+            Some(r#"{"id":1}"#),
+        );
+        let signer = HmacSigner::new("secret", SIGNATURE)
+            .with_prefix("sha256=")
+            .with_timestamp_header(HeaderName::from_static("x-timestamp"));
+        signer.sign_at(&mut request, time())?;
+
+        let message = br#"1700000000POST/v1/orders{"id":1}"#;
+        let expected = format!(
+            "sha256={}",
+            hex::encode(HmacAlgorithm::Sha256.sign(b"secret", message))
+        );
+        let signature = &request.headers()[SIGNATURE];
+        assert_eq!(signature, expected.as_str());
+        assert!(signature.is_sensitive());
+        assert_eq!(request.headers()["x-timestamp"], "1700000000");
+        Ok(())
+    }
+
+    #[test]
+    fn it_encodes_signatures_in_base64() -> HttpResult<()> {
+        let mut request = request(Method::GET, "https://api.example.com/", None);
+        let signer = HmacSigner::new("secret", SIGNATURE)
+            .with_algorithm(HmacAlgorithm::Sha512)
+            .with_encoding(SignatureEncoding::Base64)
+            .with_template("{method}{path}");
+        signer.sign_at(&mut request, time())?;
+        let expected = STANDARD.encode(HmacAlgorithm::Sha512.sign(b"secret", b"GET/"));
+        assert_eq!(request.headers()[SIGNATURE], expected.as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn it_applies_other_authenticators_before_signing() -> HttpResult<()> {
+        let mut request = request(Method::GET, "https://api.example.com/", None);
+        let signer = HmacSigner::new("secret", SIGNATURE).with_auth(ApiKeyHeader::new("key"));
+        signer.sign_at(&mut request, time())?;
+        assert_eq!(request.headers()["x-api-key"], "key");
+        assert!(request.headers().contains_key(SIGNATURE));
+        Ok(())
+    }
+
+    #[test]
+    fn it_signs_requests_as_an_interceptor() -> HttpResult<()> {
+        let mut request =
+            layer::Request::new(Method::POST, "/v1/orders?page=2").with_body(r#"{"id":1}"#);
+        let signer = HmacSigner::new("secret", SIGNATURE)
+            .with_template("{method}{path_and_query}{body}")
+            .with_auth(ApiKeyHeader::new("key"));
+        signer.before_request(&mut request)?;
+
+        let message = br#"POST/v1/orders?page=2{"id":1}"#;
+        let expected = hex::encode(HmacAlgorithm::Sha256.sign(b"secret", message));
+        assert_eq!(request.uri(), "/v1/orders?page=2");
+        assert_eq!(request.headers()[SIGNATURE], expected.as_str());
+        assert_eq!(request.headers()["x-api-key"], "key");
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_bad_templates() {
+        let request = request(Method::GET, "https://api.example.com/", None);
+        for template in ["{method}{nonce}", "{method"] {
+            let signer = HmacSigner::new("secret", SIGNATURE).with_template(template);
+            let result = signer.message(&request, time());
+            assert!(matches!(result, Err(HttpError::InvalidCredentials(_))));
+        }
+    }
+
+    #[test]
+    fn it_does_not_show_secrets() {
+        let signer = HmacSigner::new("my-secret", SIGNATURE);
+        assert!(!format!("{signer:?}").contains("my-secret"));
+    }
+}
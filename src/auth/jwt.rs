@@ -0,0 +1,420 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! JWT bearer tokens that know when they expire.
+//!
+//! Many APIs issue short-lived [JSON Web Tokens] to send as bearer tokens.
+//! A [`JwtAuth`] reads the token's `exp` claim, so it can tell when the
+//! token has expired instead of finding out from an HTTP 401. The token's
+//! signature is not verified; that is the server's job.
+//!
+//! Given a refresh callback, a `JwtAuth` replaces its token before it
+//! expires. Authenticators cannot wait, so call
+//! [`refresh_if_needed()`](JwtAuth::refresh_if_needed) before a request to
+//! be sure it is sent with a fresh token. Requests authenticated while the
+//! token is about to expire also start a refresh in the background, so a
+//! client that makes steady requests keeps its token fresh without waiting
+//! at all. A request with an expired token fails with
+//! [`HttpError::InvalidToken`] rather than being sent to be rejected.
+//!
+//! A `JwtAuth` with a refresh callback is [`Refreshable`], so a
+//! [`ReauthService`] can also replace a token that the API rejects before
+//! it expires, such as one that has been revoked.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::auth::JwtAuth;
+//! use hypertyper::prelude::*;
+//!
+//! # async fn log_in() -> HttpResult<String> { todo!() }
+//! # async fn run(service: impl HttpPost) -> HttpResult<()> {
+//! let auth = JwtAuth::new(log_in().await?)?.with_refresh(log_in);
+//!
+//! // Later, perhaps after lunch:
+//! auth.refresh_if_needed().await?;
+//! let user: serde_json::Value = service
+//!     .post("https://api.example.com/users", &auth, &serde_json::json!({"name": "foo"}))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [JSON Web Tokens]: https://www.rfc-editor.org/rfc/rfc7519
+//! [`ReauthService`]: crate::service::reauth::ReauthService
+
+use crate::auth::{Authenticator, Refreshable};
+use crate::{HttpError, HttpResult};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use reqwest::Request;
+use reqwest::header::{self, HeaderValue};
+use serde::Deserialize;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long before a token expires to refresh it, by default.
+pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// A callback that obtains a new token.
+type Refresh = dyn Fn() -> Pin<Box<dyn Future<Output = HttpResult<String>> + Send>> + Send + Sync;
+
+/// The claims of a JWT that are read.
+#[derive(Deserialize)]
+struct Claims {
+    exp: Option<f64>,
+}
+
+/// A JSON Web Token and its expiry time.
+#[derive(Clone)]
+pub struct Jwt {
+    token: String,
+    expires_at: Option<SystemTime>,
+}
+
+impl Jwt {
+    /// Decodes the claims of `token` without verifying its signature.
+    ///
+    /// Returns [`HttpError::InvalidToken`] if `token` is not a JWT with a
+    /// JSON payload, or if its `exp` claim is not a valid time.
+    pub fn decode(token: impl Into<String>) -> HttpResult<Self> {
+        let token = token.into();
+        let invalid = |reason: &str| HttpError::InvalidToken(format!("not a JWT: {reason}"));
+        let mut parts = token.split('.');
+        let (Some(_), Some(payload), Some(_), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid("expected three parts"));
+        };
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|_| invalid("payload is not base64url"))?;
+        let claims: Claims =
+            serde_json::from_slice(&payload).map_err(|_| invalid("payload is not JSON"))?;
+        let expires_at = match claims.exp {
+            Some(exp) => {
+                let expires_at = Duration::try_from_secs_f64(exp)
+                    .ok()
+                    .and_then(|exp| UNIX_EPOCH.checked_add(exp))
+                    .ok_or_else(|| invalid("exp is not a valid time"))?;
+                Some(expires_at)
+            }
+            None => None,
+        };
+        Ok(Self { token, expires_at })
+    }
+
+    /// The encoded token.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// When the token expires, if it has an `exp` claim.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    /// True if the token has expired.
+    ///
+    /// Tokens without an `exp` claim never expire.
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::ZERO)
+    }
+
+    /// True if the token has expired or will expire within `duration`.
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        self.expires_at.is_some_and(|expires_at| {
+            SystemTime::now()
+                .checked_add(duration)
+                .is_none_or(|deadline| expires_at <= deadline)
+        })
+    }
+}
+
+impl fmt::Debug for Jwt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Jwt")
+            .field("token", &"****")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Authenticates requests with a JWT bearer token that is refreshed before
+/// it expires.
+///
+/// Clones share the same token, so a refresh through one clone is seen by
+/// all of them.
+///
+/// See the [module documentation](crate::auth::jwt) for details.
+#[derive(Clone)]
+pub struct JwtAuth {
+    current: Arc<RwLock<Arc<Jwt>>>,
+    refresh: Option<Arc<Refresh>>,
+    refresh_margin: Duration,
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl JwtAuth {
+    /// Creates an authenticator that sends `token`.
+    ///
+    /// Returns [`HttpError::InvalidToken`] if `token` is not a JWT.
+    pub fn new(token: impl Into<String>) -> HttpResult<Self> {
+        let jwt = Jwt::decode(token)?;
+        Ok(Self {
+            current: Arc::new(RwLock::new(Arc::new(jwt))),
+            refresh: None,
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            refreshing: Arc::default(),
+        })
+    }
+
+    /// Obtains a new token from `refresh` whenever the current one is about
+    /// to expire.
+    pub fn with_refresh<F, Fut>(mut self, refresh: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HttpResult<String>> + Send + 'static,
+    {
+        self.refresh = Some(Arc::new(move || Box::pin(refresh())));
+        self
+    }
+
+    /// Refreshes tokens `refresh_margin` before they expire.
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// The current token.
+    pub fn jwt(&self) -> Arc<Jwt> {
+        Arc::clone(&self.current.read().unwrap())
+    }
+
+    /// True if the current token has expired.
+    pub fn is_expired(&self) -> bool {
+        self.jwt().is_expired()
+    }
+
+    /// True if the current token should be refreshed.
+    pub fn needs_refresh(&self) -> bool {
+        self.jwt().expires_within(self.refresh_margin)
+    }
+
+    /// Replaces the current token with `token`.
+    ///
+    /// Returns [`HttpError::InvalidToken`], and keeps the current token, if
+    /// `token` is not a JWT.
+    pub fn set_token(&self, token: impl Into<String>) -> HttpResult<()> {
+        let jwt = Jwt::decode(token)?;
+        *self.current.write().unwrap() = Arc::new(jwt);
+        Ok(())
+    }
+
+    /// Refreshes the token if it has expired or is about to.
+    ///
+    /// Concurrent callers wait for a single refresh rather than each
+    /// starting their own. Does nothing if no refresh callback was given.
+    pub async fn refresh_if_needed(&self) -> HttpResult<()> {
+        let Some(refresh) = &self.refresh else {
+            return Ok(());
+        };
+        if !self.needs_refresh() {
+            return Ok(());
+        }
+        let _refreshing = self.refreshing.lock().await;
+        if !self.needs_refresh() {
+            return Ok(());
+        }
+        self.set_token(refresh().await?)
+    }
+
+    /// Starts a refresh in the background, if there is a Tokio runtime to
+    /// run it on.
+    fn spawn_refresh(&self) {
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let auth = self.clone();
+            runtime.spawn(async move {
+                let _ = auth.refresh_if_needed().await;
+            });
+        }
+    }
+}
+
+impl fmt::Debug for JwtAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtAuth")
+            .field("jwt", &self.jwt())
+            .field("refresh_margin", &self.refresh_margin)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Authenticator for JwtAuth {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        let jwt = self.jwt();
+        if self.refresh.is_some() && jwt.expires_within(self.refresh_margin) {
+            self.spawn_refresh();
+        }
+        if jwt.is_expired() {
+            return Err(HttpError::InvalidToken(String::from(
+                "the JWT has expired; call refresh_if_needed() before sending requests",
+            )));
+        }
+        let mut value = HeaderValue::try_from(format!("Bearer {}", jwt.token()))?;
+        value.set_sensitive(true);
+        request.headers_mut().insert(header::AUTHORIZATION, value);
+        Ok(())
+    }
+
+    fn scheme(&self) -> &str {
+        "bearer"
+    }
+}
+
+impl Refreshable for JwtAuth {
+    /// Replaces the current token with one from the refresh callback,
+    /// whether or not it is about to expire.
+    ///
+    /// If the token is replaced by another caller while waiting to
+    /// refresh it, that token is used instead. Does nothing if no refresh
+    /// callback was given.
+    async fn refresh(&self) -> HttpResult<()> {
+        let Some(refresh) = &self.refresh else {
+            return Ok(());
+        };
+        let rejected = self.jwt();
+        let _refreshing = self.refreshing.lock().await;
+        if self.jwt().token() == rejected.token() {
+            self.set_token(refresh().await?)?;
+        }
+        Ok(())
+    }
+
+    fn can_refresh(&self) -> bool {
+        self.refresh.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Method;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// An unsigned JWT that expires `offset` seconds from now.
+    fn token_expiring_in(offset: i64) -> String {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let exp = now.as_secs() as i64 + offset;
+        let payload = URL_SAFE_NO_PAD.encode(format!(r#"{{"sub":"foo","exp":{exp}}}"#));
+        format!("eyJhbGciOiJub25lIn0.{payload}.")
+    }
+
+    fn request() -> Request {
+        Request::new(Method::GET, "https://api.example.com/".parse().unwrap())
+    }
+
+    #[test]
+    fn it_decodes_expiry_times() -> HttpResult<()> {
+        let jwt = Jwt::decode(token_expiring_in(3600))?;
+        assert!(!jwt.is_expired());
+        assert!(jwt.expires_within(Duration::from_secs(3601)));
+        assert!(!jwt.expires_within(Duration::from_secs(3590)));
+        assert!(Jwt::decode(token_expiring_in(-1))?.is_expired());
+
+        let forever = format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(r#"{"sub":"foo"}"#));
+        assert_eq!(Jwt::decode(forever)?.expires_at(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_tokens_that_are_not_jwts() {
+        assert!(matches!(
+            Jwt::decode("opaque-token"),
+            Err(HttpError::InvalidToken(_))
+        ));
+        assert!(Jwt::decode("a.!!!.c").is_err());
+        for exp in ["-5", "1e300", "1.8e19"] {
+            let claims = format!(r#"{{"exp":{exp}}}"#);
+            let bad_exp = format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(claims));
+            assert!(
+                matches!(Jwt::decode(bad_exp), Err(HttpError::InvalidToken(_))),
+                "{exp}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_sends_the_token_as_a_bearer_token() -> HttpResult<()> {
+        let token = token_expiring_in(3600);
+        let auth = JwtAuth::new(&token)?;
+        let mut request = request();
+        auth.authenticate(&mut request)?;
+        assert_eq!(
+            request.headers()[header::AUTHORIZATION],
+            format!("Bearer {token}").as_str()
+        );
+        assert!(!format!("{auth:?}").contains(&token));
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_send_expired_tokens() -> HttpResult<()> {
+        let auth = JwtAuth::new(token_expiring_in(-60))?;
+        assert!(auth.is_expired());
+        let result = auth.authenticate(&mut request());
+        assert!(matches!(result, Err(HttpError::InvalidToken(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_tokens_that_are_about_to_expire() -> HttpResult<()> {
+        let refreshes = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&refreshes);
+        let auth = JwtAuth::new(token_expiring_in(30))?.with_refresh(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Ok(token_expiring_in(3600)) }
+        });
+        assert!(auth.needs_refresh());
+
+        let clone = auth.clone();
+        let (a, b) = tokio::join!(auth.refresh_if_needed(), clone.refresh_if_needed());
+        a?;
+        b?;
+        assert!(!auth.needs_refresh());
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+        auth.refresh_if_needed().await?;
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_rejected_tokens() -> HttpResult<()> {
+        let rejected = token_expiring_in(3600);
+        let auth = JwtAuth::new(&rejected)?;
+        assert!(!auth.can_refresh());
+
+        let auth = auth.with_refresh(|| async { Ok(token_expiring_in(7200)) });
+        assert!(auth.can_refresh());
+        Refreshable::refresh(&auth).await?;
+        assert_ne!(auth.jwt().token(), rejected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_in_the_background_while_authenticating() -> HttpResult<()> {
+        let auth = JwtAuth::new(token_expiring_in(30))?
+            .with_refresh(|| async { Ok(token_expiring_in(3600)) });
+        auth.authenticate(&mut request())?;
+        for _ in 0..10 {
+            if !auth.needs_refresh() {
+                return Ok(());
+            }
+            tokio::task::yield_now().await;
+        }
+        panic!("token was not refreshed in the background");
+    }
+}
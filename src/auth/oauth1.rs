@@ -0,0 +1,479 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! OAuth 1.0a request signing.
+//!
+//! Older APIs, such as those of Twitter and Flickr, authenticate each
+//! request with an [RFC 5849] signature rather than a bearer token. An
+//! [`OAuth1`] authenticator builds the signature base string from the
+//! request's method, URL, query parameters, form body, and its own
+//! `oauth_*` parameters, signs it with the consumer secret and token
+//! secret, and sends the result in the `Authorization` header. Each
+//! request is signed with a new nonce and the current time.
+//!
+//! Requests are signed with `HMAC-SHA1` by default, which is what nearly
+//! every OAuth 1.0a API expects; `HMAC-SHA256` and `PLAINTEXT` can be used
+//! instead with [`with_signature_method()`](OAuth1::with_signature_method).
+//! Form bodies must be sent whole, rather than streamed, so that their
+//! parameters can be signed.
+//!
+//! The same authenticator is used for each leg of the three-legged flow:
+//! with a [callback](OAuth1::with_callback) to get a request token, with
+//! the request token and a [verifier](OAuth1::with_verifier) to exchange
+//! it for an access token, and with the access token to call the API.
+//!
+//! This is only available when the **oauth1** feature is enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::auth::Authenticator;
+//! use hypertyper::auth::oauth1::OAuth1;
+//! use reqwest::header::AUTHORIZATION;
+//! use reqwest::{Method, Request};
+//!
+//! let auth = OAuth1::new("consumer-key", "consumer-secret")
+//!     .with_token("access-token", "token-secret");
+//! let url = "https://api.example.com/1.1/statuses/home_timeline.json".parse().unwrap();
+//! let mut request = Request::new(Method::GET, url);
+//! auth.authenticate(&mut request).unwrap();
+//! let authorization = request.headers()[AUTHORIZATION].to_str().unwrap();
+//! assert!(authorization.starts_with("OAuth oauth_consumer_key=\"consumer-key\""));
+//! ```
+//!
+//! [RFC 5849]: https://www.rfc-editor.org/rfc/rfc5849
+
+use crate::auth::Authenticator;
+use crate::{HttpError, HttpResult};
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use reqwest::Request;
+use reqwest::header::{self, HeaderValue};
+use sha1::Sha1;
+use sha2::Sha256;
+use std::fmt;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Random bytes in each nonce.
+const NONCE_BYTES: usize = 24;
+
+/// How requests are signed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignatureMethod {
+    /// `HMAC-SHA1`.
+    #[default]
+    HmacSha1,
+
+    /// `HMAC-SHA256`.
+    HmacSha256,
+
+    /// `PLAINTEXT`, which sends the secrets themselves and should only be
+    /// used over HTTPS.
+    Plaintext,
+}
+
+impl SignatureMethod {
+    /// The method's name, as it is sent in `oauth_signature_method`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HmacSha1 => "HMAC-SHA1",
+            Self::HmacSha256 => "HMAC-SHA256",
+            Self::Plaintext => "PLAINTEXT",
+        }
+    }
+
+    /// The signature of `base_string` with `key`.
+    fn sign(&self, key: &str, base_string: &str) -> String {
+        match self {
+            Self::HmacSha1 => {
+                let mut mac =
+                    Hmac::<Sha1>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key");
+                mac.update(base_string.as_bytes());
+                STANDARD.encode(mac.finalize().into_bytes())
+            }
+            Self::HmacSha256 => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key");
+                mac.update(base_string.as_bytes());
+                STANDARD.encode(mac.finalize().into_bytes())
+            }
+            Self::Plaintext => key.to_string(),
+        }
+    }
+}
+
+/// Signs requests with OAuth 1.0a credentials.
+///
+/// See the [module documentation](crate::auth::oauth1) for details.
+#[derive(Clone)]
+pub struct OAuth1 {
+    consumer_key: String,
+    consumer_secret: String,
+    token: Option<String>,
+    token_secret: String,
+    signature_method: SignatureMethod,
+    realm: Option<String>,
+    callback: Option<String>,
+    verifier: Option<String>,
+}
+
+impl OAuth1 {
+    /// Signs requests as the client identified by `consumer_key`.
+    pub fn new(consumer_key: impl Into<String>, consumer_secret: impl Into<String>) -> Self {
+        Self {
+            consumer_key: consumer_key.into(),
+            consumer_secret: consumer_secret.into(),
+            token: None,
+            token_secret: String::new(),
+            signature_method: SignatureMethod::default(),
+            realm: None,
+            callback: None,
+            verifier: None,
+        }
+    }
+
+    /// Signs requests with a request or access token and its secret.
+    pub fn with_token(mut self, token: impl Into<String>, token_secret: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self.token_secret = token_secret.into();
+        self
+    }
+
+    /// Signs requests with `method` instead of `HMAC-SHA1`.
+    pub fn with_signature_method(mut self, method: SignatureMethod) -> Self {
+        self.signature_method = method;
+        self
+    }
+
+    /// Sends `realm` in the `Authorization` header.
+    pub fn with_realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+
+    /// Sends `callback` as `oauth_callback`, as is done when asking for a
+    /// request token. Use `oob` for clients that cannot receive callbacks.
+    pub fn with_callback(mut self, callback: impl Into<String>) -> Self {
+        self.callback = Some(callback.into());
+        self
+    }
+
+    /// Sends `verifier` as `oauth_verifier`, as is done when exchanging a
+    /// request token for an access token.
+    pub fn with_verifier(mut self, verifier: impl Into<String>) -> Self {
+        self.verifier = Some(verifier.into());
+        self
+    }
+
+    /// The consumer key.
+    pub fn consumer_key(&self) -> &str {
+        &self.consumer_key
+    }
+
+    /// The token, if there is one.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// The method requests are signed with.
+    pub fn signature_method(&self) -> SignatureMethod {
+        self.signature_method
+    }
+
+    /// Signs `request` as if it were sent at `time` with `nonce`.
+    ///
+    /// [`authenticate()`](Authenticator::authenticate) signs requests with
+    /// the current time and a random nonce; this is useful for testing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::InvalidCredentials`] if the request has a form
+    /// body that is streamed, since its parameters cannot be signed.
+    pub fn sign_at(&self, request: &mut Request, time: SystemTime, nonce: &str) -> HttpResult<()> {
+        let timestamp = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string();
+        let mut oauth_params = vec![
+            ("oauth_consumer_key", self.consumer_key.as_str()),
+            ("oauth_nonce", nonce),
+            ("oauth_signature_method", self.signature_method.name()),
+            ("oauth_timestamp", timestamp.as_str()),
+            ("oauth_version", "1.0"),
+        ];
+        if let Some(token) = &self.token {
+            oauth_params.push(("oauth_token", token));
+        }
+        if let Some(callback) = &self.callback {
+            oauth_params.push(("oauth_callback", callback));
+        }
+        if let Some(verifier) = &self.verifier {
+            oauth_params.push(("oauth_verifier", verifier));
+        }
+        oauth_params.sort();
+
+        let key = format!(
+            "{}&{}",
+            encode(&self.consumer_secret),
+            encode(&self.token_secret)
+        );
+        let signature = match self.signature_method {
+            SignatureMethod::Plaintext => key,
+            method => method.sign(&key, &base_string(request, &oauth_params)?),
+        };
+
+        let mut authorization = String::from("OAuth ");
+        if let Some(realm) = &self.realm {
+            authorization.push_str(&format!("realm=\"{}\", ", encode(realm)));
+        }
+        let oauth_params = oauth_params
+            .iter()
+            .copied()
+            .chain([("oauth_signature", signature.as_str())])
+            .map(|(name, value)| format!("{name}=\"{}\"", encode(value)))
+            .collect::<Vec<_>>();
+        authorization.push_str(&oauth_params.join(", "));
+
+        let mut value = HeaderValue::try_from(authorization)?;
+        value.set_sensitive(true);
+        request.headers_mut().insert(header::AUTHORIZATION, value);
+        Ok(())
+    }
+}
+
+impl Authenticator for OAuth1 {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        let mut bytes = [0; NONCE_BYTES];
+        getrandom::fill(&mut bytes).map_err(io::Error::from)?;
+        self.sign_at(request, SystemTime::now(), &URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    fn scheme(&self) -> &str {
+        "oauth1"
+    }
+}
+
+impl fmt::Debug for OAuth1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth1")
+            .field("consumer_key", &self.consumer_key)
+            .field("consumer_secret", &"****")
+            .field("token", &self.token)
+            .field("token_secret", &"****")
+            .field("signature_method", &self.signature_method)
+            .field("realm", &self.realm)
+            .field("callback", &self.callback)
+            .field("verifier", &self.verifier.as_ref().map(|_| "****"))
+            .finish()
+    }
+}
+
+/// The signature base string for `request`, as described in
+/// [RFC 5849 § 3.4.1](https://www.rfc-editor.org/rfc/rfc5849#section-3.4.1).
+fn base_string(request: &Request, oauth_params: &[(&str, &str)]) -> HttpResult<String> {
+    let url = request.url();
+    let mut params: Vec<_> = url
+        .query_pairs()
+        .map(|(name, value)| (encode(&name), encode(&value)))
+        .collect();
+    params.extend(
+        oauth_params
+            .iter()
+            .map(|(name, value)| (encode(name), encode(value))),
+    );
+    let is_form = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    if let (true, Some(body)) = (is_form, request.body()) {
+        let body = body.as_bytes().ok_or_else(|| {
+            HttpError::InvalidCredentials(String::from(
+                "OAuth 1.0a cannot sign streamed form bodies",
+            ))
+        })?;
+        params.extend(
+            url::form_urlencoded::parse(body).map(|(name, value)| (encode(&name), encode(&value))),
+        );
+    }
+    params.sort();
+    let params: Vec<_> = params
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+
+    let mut base_url = format!(
+        "{}://{}",
+        url.scheme(),
+        url.host_str().unwrap_or_default().to_ascii_lowercase()
+    );
+    if let Some(port) = url.port() {
+        base_url.push_str(&format!(":{port}"));
+    }
+    base_url.push_str(url.path());
+
+    Ok(format!(
+        "{}&{}&{}",
+        request.method().as_str().to_ascii_uppercase(),
+        encode(&base_url),
+        encode(&params.join("&"))
+    ))
+}
+
+/// Percent-encodes everything in `s` but unreserved characters, as
+/// described in [RFC 5849 § 3.6](https://www.rfc-editor.org/rfc/rfc5849#section-3.6).
+fn encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Method;
+    use std::time::Duration;
+
+    // The example from Twitter's documentation on creating signatures.
+    const NONCE: &str = "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg";
+    const BODY: &str =
+        "status=Hello%20Ladies%20%2B%20Gentlemen%2C%20a%20signed%20OAuth%20request%21";
+
+    fn example_auth() -> OAuth1 {
+        OAuth1::new(
+            "xvz1evFS4wEEPTGEFPHBog",
+            "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+        )
+        .with_token(
+            "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+        )
+    }
+
+    fn example_request() -> Request {
+        let url = "https://api.twitter.com/1.1/statuses/update.json?include_entities=true";
+        let mut request = Request::new(Method::POST, url.parse().unwrap());
+        request.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        *request.body_mut() = Some(BODY.into());
+        request
+    }
+
+    fn time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_318_622_958)
+    }
+
+    fn authorization(request: &Request) -> &str {
+        request.headers()[header::AUTHORIZATION].to_str().unwrap()
+    }
+
+    #[test]
+    fn it_builds_signature_base_strings() -> HttpResult<()> {
+        let params = [
+            ("oauth_consumer_key", "xvz1evFS4wEEPTGEFPHBog"),
+            ("oauth_nonce", NONCE),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "1318622958"),
+            (
+                "oauth_token",
+                "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            ),
+            ("oauth_version", "1.0"),
+        ];
+        assert_eq!(
+            base_string(&example_request(), &params)?,
+            "POST&https%3A%2F%2Fapi.twitter.com%2F1.1%2Fstatuses%2Fupdate.json&\
+             include_entities%3Dtrue%26oauth_consumer_key%3Dxvz1evFS4wEEPTGEFPHBog%26\
+             oauth_nonce%3DkYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg%26\
+             oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D1318622958%26\
+             oauth_token%3D370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb%26\
+             oauth_version%3D1.0%26\
+             status%3DHello%2520Ladies%2520%252B%2520Gentlemen%252C%2520a%2520signed%2520OAuth%2520request%2521"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_signs_requests_with_hmac_sha1() -> HttpResult<()> {
+        let mut request = example_request();
+        example_auth().sign_at(&mut request, time(), NONCE)?;
+        assert_eq!(
+            authorization(&request),
+            "OAuth oauth_consumer_key=\"xvz1evFS4wEEPTGEFPHBog\", \
+             oauth_nonce=\"kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg\", \
+             oauth_signature_method=\"HMAC-SHA1\", oauth_timestamp=\"1318622958\", \
+             oauth_token=\"370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb\", \
+             oauth_version=\"1.0\", oauth_signature=\"hCtSmYh%2BiHYCEqBWrE7C7hYmtUk%3D\""
+        );
+        assert!(request.headers()[header::AUTHORIZATION].is_sensitive());
+        Ok(())
+    }
+
+    #[test]
+    fn it_sends_callbacks_verifiers_and_realms() -> HttpResult<()> {
+        let mut request = Request::new(
+            Method::POST,
+            "https://api.example.com/oauth/request_token"
+                .parse()
+                .unwrap(),
+        );
+        OAuth1::new("key", "secret")
+            .with_realm("Example")
+            .with_callback("https://app.example.com/callback")
+            .with_verifier("verifier")
+            .sign_at(&mut request, time(), "nonce")?;
+        let authorization = authorization(&request);
+        assert!(authorization.starts_with("OAuth realm=\"Example\", oauth_callback="));
+        assert!(
+            authorization.contains("oauth_callback=\"https%3A%2F%2Fapp.example.com%2Fcallback\"")
+        );
+        assert!(authorization.contains("oauth_verifier=\"verifier\""));
+        assert!(!authorization.contains("oauth_token="));
+        Ok(())
+    }
+
+    #[test]
+    fn it_signs_requests_with_plaintext() -> HttpResult<()> {
+        let mut request = example_request();
+        OAuth1::new("key", "secret&more")
+            .with_token("token", "token secret")
+            .with_signature_method(SignatureMethod::Plaintext)
+            .sign_at(&mut request, time(), "nonce")?;
+        assert!(
+            authorization(&request)
+                .ends_with("oauth_signature=\"secret%2526more%26token%2520secret\"")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_signs_with_a_new_nonce_each_time() -> HttpResult<()> {
+        let auth = example_auth().with_signature_method(SignatureMethod::HmacSha256);
+        let mut first = example_request();
+        let mut second = example_request();
+        auth.authenticate(&mut first)?;
+        auth.authenticate(&mut second)?;
+        assert!(authorization(&first).contains("oauth_signature_method=\"HMAC-SHA256\""));
+        assert_ne!(authorization(&first), authorization(&second));
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_show_secrets() {
+        let debug = format!("{:?}", example_auth());
+        assert!(!debug.contains("kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw"));
+        assert!(!debug.contains("LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE"));
+    }
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! OAuth 2.0 authorization.
+//!
+//! This module contains the types shared by the OAuth 2.0 grants, such as
+//! the [`TokenResponse`] returned by an authorization server's token
+//! endpoint. The grants themselves live in submodules:
+//!
+//! - [`authorization_code`]: the [authorization code grant] with PKCE, for
+//!   desktop apps and CLIs that log users in through a browser.
+//! - [`client_credentials`]: the [client credentials grant] for services
+//!   that authenticate as themselves, with cached and automatically
+//!   refreshed tokens.
+//! - [`device`]: the [device authorization grant] for headless CLIs and
+//!   other devices without a browser.
+//!
+//! Refresh tokens are persisted with a [`TokenStore`](store::TokenStore).
+//!
+//! [authorization code grant]: https://www.rfc-editor.org/rfc/rfc6749#section-4.1
+//! [client credentials grant]: https://www.rfc-editor.org/rfc/rfc6749#section-4.4
+//! [device authorization grant]: https://www.rfc-editor.org/rfc/rfc8628
+
+pub mod authorization_code;
+pub mod client_credentials;
+pub mod device;
+pub mod store;
+
+use crate::service::HttpResponse;
+use crate::{HttpError, HttpResult};
+use serde::Deserialize;
+
+/// A successful response from an authorization server's token endpoint, as
+/// described in [RFC 6749 § 5.1].
+///
+/// [RFC 6749 § 5.1]: https://www.rfc-editor.org/rfc/rfc6749#section-5.1
+#[derive(Clone, Debug, Deserialize)]
+pub struct TokenResponse {
+    /// The access token issued by the authorization server.
+    pub access_token: String,
+
+    /// The type of the token, usually `Bearer`.
+    pub token_type: String,
+
+    /// How long the access token is valid for, in seconds.
+    pub expires_in: Option<u64>,
+
+    /// A token that can be used to obtain new access tokens.
+    pub refresh_token: Option<String>,
+
+    /// The scopes granted, if they differ from the scopes requested.
+    pub scope: Option<String>,
+
+    /// An OpenID Connect ID token, if the `openid` scope was requested.
+    pub id_token: Option<String>,
+}
+
+/// An error response from an authorization server, as described in
+/// [RFC 6749 § 5.2].
+///
+/// [RFC 6749 § 5.2]: https://www.rfc-editor.org/rfc/rfc6749#section-5.2
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+impl From<ErrorResponse> for HttpError {
+    fn from(response: ErrorResponse) -> Self {
+        HttpError::OAuth {
+            error: response.error,
+            description: response.error_description,
+        }
+    }
+}
+
+/// Parses a response from an authorization server endpoint.
+///
+/// Error responses that follow RFC 6749 are returned as
+/// [`HttpError::OAuth`], including those that some servers, such as
+/// GitHub's, send with a successful status; any other unsuccessful
+/// response is returned as an ordinary HTTP error.
+pub(crate) fn parse_response<T>(response: HttpResponse) -> HttpResult<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if response.status().is_success() {
+        if let Ok(err) = response.json::<ErrorResponse>() {
+            return Err(err.into());
+        }
+        return response.json();
+    }
+    match response.json::<ErrorResponse>() {
+        Ok(err) => Err(err.into()),
+        Err(_) => response
+            .error_for_status()
+            .and_then(|response| response.json()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn it_parses_token_responses() -> HttpResult<()> {
+        let body = r#"{"access_token":"abc","token_type":"Bearer","expires_in":3600}"#;
+        let response = HttpResponse::new(StatusCode::OK, body);
+        let token: TokenResponse = parse_response(response)?;
+        assert_eq!(token.access_token, "abc");
+        assert_eq!(token.expires_in, Some(3600));
+        assert!(token.refresh_token.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_error_responses() {
+        let body = r#"{"error":"invalid_grant","error_description":"Code expired"}"#;
+        let response = HttpResponse::new(StatusCode::BAD_REQUEST, body);
+        let result: HttpResult<TokenResponse> = parse_response(response);
+        assert!(matches!(
+            result,
+            Err(HttpError::OAuth { error, description: Some(description) })
+                if error == "invalid_grant" && description == "Code expired"
+        ));
+    }
+
+    #[test]
+    fn it_parses_error_responses_with_successful_statuses() {
+        let body = r#"{"error":"authorization_pending","error_uri":"https://docs.github.com"}"#;
+        let response = HttpResponse::new(StatusCode::OK, body);
+        let result: HttpResult<TokenResponse> = parse_response(response);
+        assert!(matches!(
+            result,
+            Err(HttpError::OAuth { error, description: None }) if error == "authorization_pending"
+        ));
+    }
+
+    #[test]
+    fn it_returns_http_errors_for_other_failures() {
+        let response = HttpResponse::new(StatusCode::BAD_GATEWAY, "<html>Bad gateway</html>");
+        let result: HttpResult<TokenResponse> = parse_response(response);
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::BAD_GATEWAY))
+        ));
+    }
+}
@@ -0,0 +1,571 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! The OAuth 2.0 authorization code grant with PKCE.
+//!
+//! The [authorization code grant] is how desktop apps and CLIs log a user
+//! in through their browser. An [`AuthorizationCodeFlow`] builds the URL
+//! to send the user to, and the authorization server redirects the browser
+//! back to the app's redirect URI with a code that the flow exchanges for
+//! tokens. Every request is protected by [PKCE], so a code intercepted on
+//! its way back to the app is useless without the [`Pkce`] verifier that
+//! only the app knows.
+//!
+//! Receiving the redirect is left to the app, which usually listens on a
+//! loopback port or registers a custom URL scheme. The app passes a hook
+//! to [`login()`](AuthorizationCodeFlow::login) that opens the
+//! authorization URL and returns the URL the browser was redirected to.
+//!
+//! Refresh tokens are saved to a [`TokenStore`], so later runs can call
+//! [`refresh()`](AuthorizationCodeFlow::refresh) instead of asking the user
+//! to log in again.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::auth::oauth2::authorization_code::AuthorizationCodeFlow;
+//! use hypertyper::auth::oauth2::store::FileTokenStore;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpPostForm;
+//!
+//! # async fn wait_for_redirect(url: url::Url) -> HttpResult<String> { todo!() }
+//! # async fn run(service: impl HttpPostForm + Sync) -> HttpResult<()> {
+//! let flow = AuthorizationCodeFlow::new(
+//!     service,
+//!     "my-client-id",
+//!     "https://auth.example.com/authorize",
+//!     "https://auth.example.com/token",
+//!     "http://127.0.0.1:8400/callback",
+//!     FileTokenStore::new("/home/me/.config/my-app/token"),
+//! )
+//! .with_scopes(["openid", "profile"]);
+//!
+//! let token = match flow.refresh().await? {
+//!     Some(token) => token,
+//!     None => flow.login(wait_for_redirect).await?,
+//! };
+//! println!("Logged in with {}", token.access_token);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [authorization code grant]: https://www.rfc-editor.org/rfc/rfc6749#section-4.1
+//! [PKCE]: https://www.rfc-editor.org/rfc/rfc7636
+
+use crate::auth::oauth2::store::TokenStore;
+use crate::auth::oauth2::{TokenResponse, parse_response};
+use crate::service::HttpPostForm;
+use crate::{HttpError, HttpResult};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+use url::Url;
+
+/// The PKCE challenge method used for every request.
+pub const CHALLENGE_METHOD: &str = "S256";
+
+/// Random bytes encoded into each verifier, giving the 43-character
+/// verifier recommended by RFC 7636.
+const VERIFIER_BYTES: usize = 32;
+
+/// Random bytes encoded into each `state` parameter.
+const STATE_BYTES: usize = 16;
+
+/// A URL-safe string made from `len` random bytes.
+fn random_string(len: usize) -> io::Result<String> {
+    let mut bytes = vec![0; len];
+    getrandom::fill(&mut bytes)?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// A PKCE code verifier and its challenge, as described in [RFC 7636].
+///
+/// [RFC 7636]: https://www.rfc-editor.org/rfc/rfc7636#section-4.1
+#[derive(Clone)]
+pub struct Pkce {
+    verifier: String,
+    challenge: String,
+}
+
+impl Pkce {
+    /// Generates a new verifier from a cryptographically secure source of
+    /// randomness.
+    pub fn new() -> HttpResult<Self> {
+        Self::from_verifier(random_string(VERIFIER_BYTES)?)
+    }
+
+    /// Uses an existing `verifier`.
+    ///
+    /// Returns [`HttpError::InvalidCredentials`] if the verifier is not
+    /// 43 to 128 unreserved URL characters.
+    pub fn from_verifier(verifier: impl Into<String>) -> HttpResult<Self> {
+        let verifier = verifier.into();
+        let unreserved = |c: char| c.is_ascii_alphanumeric() || "-._~".contains(c);
+        if !(43..=128).contains(&verifier.len()) || !verifier.chars().all(unreserved) {
+            return Err(HttpError::InvalidCredentials(String::from(
+                "PKCE verifiers must be 43 to 128 unreserved URL characters",
+            )));
+        }
+        let challenge = challenge(&verifier);
+        Ok(Self {
+            verifier,
+            challenge,
+        })
+    }
+
+    /// The secret verifier, which is sent when the code is exchanged.
+    pub fn verifier(&self) -> &str {
+        &self.verifier
+    }
+
+    /// The challenge derived from the verifier, which is sent in the
+    /// authorization URL.
+    pub fn challenge(&self) -> &str {
+        &self.challenge
+    }
+
+    /// True if `verifier` is the verifier that `challenge` was derived
+    /// from.
+    pub fn verify(verifier: &str, challenge: &str) -> bool {
+        self::challenge(verifier) == challenge
+    }
+}
+
+impl fmt::Debug for Pkce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkce")
+            .field("verifier", &"****")
+            .field("challenge", &self.challenge)
+            .finish()
+    }
+}
+
+/// The S256 challenge for `verifier`.
+fn challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// A pending authorization: the URL to send the user to and the secrets
+/// needed to finish logging in when they return.
+#[derive(Clone, Debug)]
+pub struct AuthorizationRequest {
+    url: Url,
+    state: String,
+    pkce: Pkce,
+}
+
+impl AuthorizationRequest {
+    /// The URL to open in the user's browser.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The `state` parameter, which the authorization server sends back
+    /// unchanged with the redirect.
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    /// The PKCE verifier and challenge for this request.
+    pub fn pkce(&self) -> &Pkce {
+        &self.pkce
+    }
+
+    /// Extracts the authorization code from the URL the browser was
+    /// redirected to.
+    ///
+    /// Returns [`HttpError::OAuth`] if the authorization server reported
+    /// an error, such as `access_denied` when the user declines, and
+    /// [`HttpError::InvalidCredentials`] if the redirect's `state` does
+    /// not match this request, which means it was not a response to it.
+    pub fn code(&self, redirect: &str) -> HttpResult<String> {
+        let redirect = Url::parse(redirect)?;
+        let param = |name: &str| {
+            redirect
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        if param("state").as_deref() != Some(self.state.as_str()) {
+            return Err(HttpError::InvalidCredentials(String::from(
+                "the redirect's state does not match the authorization request",
+            )));
+        }
+        if let Some(error) = param("error") {
+            return Err(HttpError::OAuth {
+                error,
+                description: param("error_description"),
+            });
+        }
+        param("code").ok_or_else(|| {
+            HttpError::InvalidCredentials(String::from("the redirect has no authorization code"))
+        })
+    }
+}
+
+/// Obtains access tokens using the authorization code grant with PKCE.
+///
+/// See the [module documentation](crate::auth::oauth2::authorization_code)
+/// for details.
+pub struct AuthorizationCodeFlow<S, T> {
+    service: S,
+    client_id: String,
+    client_secret: Option<String>,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    redirect_uri: String,
+    scopes: Vec<String>,
+    store: T,
+}
+
+impl<S, T> fmt::Debug for AuthorizationCodeFlow<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthorizationCodeFlow")
+            .field("client_id", &self.client_id)
+            .field("authorization_endpoint", &self.authorization_endpoint)
+            .field("token_endpoint", &self.token_endpoint)
+            .field("redirect_uri", &self.redirect_uri)
+            .field("scopes", &self.scopes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: HttpPostForm + Sync, T: TokenStore> AuthorizationCodeFlow<S, T> {
+    /// Creates a flow for the public client identified by `client_id`,
+    /// which saves refresh tokens to `store`.
+    pub fn new(
+        service: S,
+        client_id: impl Into<String>,
+        authorization_endpoint: impl Into<String>,
+        token_endpoint: impl Into<String>,
+        redirect_uri: impl Into<String>,
+        store: T,
+    ) -> Self {
+        Self {
+            service,
+            client_id: client_id.into(),
+            client_secret: None,
+            authorization_endpoint: authorization_endpoint.into(),
+            token_endpoint: token_endpoint.into(),
+            redirect_uri: redirect_uri.into(),
+            scopes: Vec::new(),
+            store,
+        }
+    }
+
+    /// Sets the scopes to request.
+    pub fn with_scopes<I, V>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sends `client_secret` with token requests, for authorization servers
+    /// that issue secrets to desktop apps.
+    pub fn with_client_secret(mut self, client_secret: impl Into<String>) -> Self {
+        self.client_secret = Some(client_secret.into());
+        self
+    }
+
+    /// The store refresh tokens are saved to.
+    pub fn store(&self) -> &T {
+        &self.store
+    }
+
+    /// Starts an authorization, generating a new `state` and PKCE
+    /// verifier.
+    pub fn authorize(&self) -> HttpResult<AuthorizationRequest> {
+        let state = random_string(STATE_BYTES)?;
+        let pkce = Pkce::new()?;
+        let mut url = Url::parse(&self.authorization_endpoint)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &self.client_id)
+                .append_pair("redirect_uri", &self.redirect_uri);
+            if !self.scopes.is_empty() {
+                query.append_pair("scope", &self.scopes.join(" "));
+            }
+            query
+                .append_pair("state", &state)
+                .append_pair("code_challenge", pkce.challenge())
+                .append_pair("code_challenge_method", CHALLENGE_METHOD);
+        }
+        Ok(AuthorizationRequest { url, state, pkce })
+    }
+
+    /// Finishes an authorization by exchanging the code in `redirect`, the
+    /// URL the browser was redirected to, for tokens.
+    ///
+    /// A refresh token in the response is saved to the store.
+    pub async fn exchange(
+        &self,
+        request: &AuthorizationRequest,
+        redirect: &str,
+    ) -> HttpResult<TokenResponse> {
+        let code = request.code(redirect)?;
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &self.redirect_uri),
+            ("code_verifier", request.pkce.verifier()),
+        ];
+        self.request_token(&form).await
+    }
+
+    /// Logs the user in.
+    ///
+    /// `redirect` is called with the authorization URL; it should send the
+    /// user there, wait for the authorization server to redirect them back,
+    /// and return the URL they were redirected to.
+    pub async fn login<F, Fut>(&self, redirect: F) -> HttpResult<TokenResponse>
+    where
+        F: FnOnce(Url) -> Fut,
+        Fut: Future<Output = HttpResult<String>>,
+    {
+        let request = self.authorize()?;
+        let redirected_to = redirect(request.url.clone()).await?;
+        self.exchange(&request, &redirected_to).await
+    }
+
+    /// Obtains a new access token with the saved refresh token.
+    ///
+    /// Returns `None` if no refresh token is saved, in which case the user
+    /// must [log in](Self::login). If the authorization server issues a new
+    /// refresh token, it replaces the saved one. If the saved token is
+    /// rejected as `invalid_grant`, because it expired or was revoked, it
+    /// is removed from the store before the error is returned.
+    pub async fn refresh(&self) -> HttpResult<Option<TokenResponse>> {
+        let Some(refresh_token) = self.store.load()? else {
+            return Ok(None);
+        };
+        let form = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+        ];
+        match self.request_token(&form).await {
+            Err(HttpError::OAuth { error, description }) if error == "invalid_grant" => {
+                self.store.clear()?;
+                Err(HttpError::OAuth { error, description })
+            }
+            result => result.map(Some),
+        }
+    }
+
+    /// Sends `form` to the token endpoint along with the client's
+    /// credentials, saving any refresh token in the response.
+    async fn request_token(&self, form: &[(&str, &str)]) -> HttpResult<TokenResponse> {
+        let mut form = form.to_vec();
+        form.push(("client_id", &self.client_id));
+        if let Some(client_secret) = &self.client_secret {
+            form.push(("client_secret", client_secret));
+        }
+        let response = self
+            .service
+            .post_form(self.token_endpoint.as_str(), &form)
+            .await?;
+        let token: TokenResponse = parse_response(response)?;
+        if let Some(refresh_token) = &token.refresh_token {
+            self.store.save(refresh_token)?;
+        }
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::oauth2::store::MemoryTokenStore;
+    use crate::service::HttpResponse;
+    use reqwest::{IntoUrl, StatusCode};
+    use serde::Serialize;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::Mutex;
+
+    const REDIRECT_URI: &str = "http://127.0.0.1:8400/callback";
+
+    /// A token endpoint that answers with scripted responses and records
+    /// the forms it was sent.
+    #[derive(Default)]
+    struct TokenServer {
+        responses: Mutex<VecDeque<(StatusCode, &'static str)>>,
+        forms: Mutex<Vec<HashMap<String, String>>>,
+    }
+
+    impl TokenServer {
+        fn new(responses: impl IntoIterator<Item = (StatusCode, &'static str)>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into_iter().collect()),
+                forms: Mutex::default(),
+            }
+        }
+
+        fn last_form(&self) -> HashMap<String, String> {
+            self.forms.lock().unwrap().last().unwrap().clone()
+        }
+    }
+
+    impl HttpPostForm for TokenServer {
+        async fn post_form<U, D>(&self, _uri: U, form: &D) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+        {
+            let pairs: Vec<(String, String)> = serde_json::from_value(serde_json::to_value(form)?)?;
+            self.forms.lock().unwrap().push(pairs.into_iter().collect());
+            let (status, body) = self.responses.lock().unwrap().pop_front().unwrap();
+            Ok(HttpResponse::new(status, body))
+        }
+    }
+
+    const TOKENS: (StatusCode, &str) = (
+        StatusCode::OK,
+        r#"{"access_token":"access-1","token_type":"Bearer","refresh_token":"refresh-1"}"#,
+    );
+
+    fn flow(server: TokenServer) -> AuthorizationCodeFlow<TokenServer, MemoryTokenStore> {
+        AuthorizationCodeFlow::new(
+            server,
+            "client-1",
+            "https://auth.example.com/authorize?prompt=consent",
+            "https://auth.example.com/token",
+            REDIRECT_URI,
+            MemoryTokenStore::new(),
+        )
+    }
+
+    fn redirect(request: &AuthorizationRequest, params: &str) -> String {
+        format!("{REDIRECT_URI}?{params}&state={}", request.state())
+    }
+
+    #[test]
+    fn it_derives_challenges_from_verifiers() -> HttpResult<()> {
+        // From RFC 7636, Appendix B.
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk")?;
+        assert_eq!(
+            pkce.challenge(),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+        assert!(Pkce::verify(pkce.verifier(), pkce.challenge()));
+        assert!(!Pkce::verify("another-verifier", pkce.challenge()));
+        assert!(!format!("{pkce:?}").contains(pkce.verifier()));
+        Ok(())
+    }
+
+    #[test]
+    fn it_generates_unique_valid_verifiers() -> HttpResult<()> {
+        let a = Pkce::new()?;
+        let b = Pkce::new()?;
+        assert_eq!(a.verifier().len(), 43);
+        assert_ne!(a.verifier(), b.verifier());
+        assert!(Pkce::from_verifier(a.verifier()).is_ok());
+        assert!(Pkce::from_verifier("too-short").is_err());
+        assert!(Pkce::from_verifier("a".repeat(42) + "!").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_builds_authorization_urls() -> HttpResult<()> {
+        let flow = flow(TokenServer::default()).with_scopes(["openid", "profile"]);
+        let request = flow.authorize()?;
+        let params: HashMap<_, _> = request.url().query_pairs().into_owned().collect();
+        assert_eq!(params["prompt"], "consent");
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], "client-1");
+        assert_eq!(params["redirect_uri"], REDIRECT_URI);
+        assert_eq!(params["scope"], "openid profile");
+        assert_eq!(params["state"], request.state());
+        assert_eq!(params["code_challenge"], request.pkce().challenge());
+        assert_eq!(params["code_challenge_method"], "S256");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_logs_in_and_saves_the_refresh_token() -> HttpResult<()> {
+        let flow = flow(TokenServer::new([TOKENS]));
+        let mut challenge = None;
+        let token = flow
+            .login(|url| {
+                let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+                challenge = Some(params["code_challenge"].clone());
+                async move {
+                    Ok(format!(
+                        "{REDIRECT_URI}?code=code-1&state={}",
+                        params["state"]
+                    ))
+                }
+            })
+            .await?;
+        assert_eq!(token.access_token, "access-1");
+        assert_eq!(flow.store().load()?.as_deref(), Some("refresh-1"));
+
+        let form = flow.service.last_form();
+        assert_eq!(form["grant_type"], "authorization_code");
+        assert_eq!(form["code"], "code-1");
+        assert_eq!(form["redirect_uri"], REDIRECT_URI);
+        assert!(Pkce::verify(&form["code_verifier"], &challenge.unwrap()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_rejects_redirects_with_the_wrong_state() -> HttpResult<()> {
+        let flow = flow(TokenServer::default());
+        let request = flow.authorize()?;
+        let forged = format!("{REDIRECT_URI}?code=code-1&state=forged");
+        let result = flow.exchange(&request, &forged).await;
+        assert!(matches!(result, Err(HttpError::InvalidCredentials(_))));
+        assert!(flow.service.forms.lock().unwrap().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_authorization_errors() -> HttpResult<()> {
+        let flow = flow(TokenServer::default());
+        let request = flow.authorize()?;
+        let denied = redirect(&request, "error=access_denied&error_description=No+thanks");
+        assert!(matches!(
+            request.code(&denied),
+            Err(HttpError::OAuth { error, description: Some(description) })
+                if error == "access_denied" && description == "No thanks"
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_with_the_saved_token() -> HttpResult<()> {
+        let rotated = (
+            StatusCode::OK,
+            r#"{"access_token":"access-2","token_type":"Bearer","refresh_token":"refresh-2"}"#,
+        );
+        let flow = flow(TokenServer::new([rotated])).with_client_secret("s3cret");
+        assert!(flow.refresh().await?.is_none());
+
+        flow.store().save("refresh-1")?;
+        let token = flow.refresh().await?.unwrap();
+        assert_eq!(token.access_token, "access-2");
+        assert_eq!(flow.store().load()?.as_deref(), Some("refresh-2"));
+
+        let form = flow.service.last_form();
+        assert_eq!(form["grant_type"], "refresh_token");
+        assert_eq!(form["refresh_token"], "refresh-1");
+        assert_eq!(form["client_secret"], "s3cret");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_forgets_rejected_refresh_tokens() -> HttpResult<()> {
+        let rejected = (StatusCode::BAD_REQUEST, r#"{"error":"invalid_grant"}"#);
+        let flow = flow(TokenServer::new([rejected]));
+        flow.store().save("refresh-1")?;
+        let result = flow.refresh().await;
+        assert!(matches!(result, Err(HttpError::OAuth { error, .. }) if error == "invalid_grant"));
+        assert_eq!(flow.store().load()?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,592 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! The OAuth 2.0 client credentials grant.
+//!
+//! The [client credentials grant] lets a service authenticate as itself,
+//! rather than on behalf of a user, by exchanging a client ID and secret
+//! for an access token. [`ClientCredentials`] fetches a token the first
+//! time one is needed, caches it, and fetches a new one shortly before it
+//! expires. The refresh time is jittered, so many clients started together
+//! do not all refresh at once.
+//!
+//! A [`ClientCredentialsService`] wraps the service used to call the API
+//! and sends the cached token with every request. If the API rejects a
+//! token with HTTP 401 anyway, perhaps because it was revoked, a new token
+//! is fetched and the request is retried once.
+//!
+//! The client ID and secret are sent in the body of the token request, as
+//! described in [RFC 6749 § 2.3.1].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::auth::oauth2::client_credentials::{
+//!     ClientCredentials, ClientCredentialsService,
+//! };
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::{HttpGetResponse, HttpPostForm};
+//! use reqwest::header::HeaderMap;
+//!
+//! # async fn run<T, S>(token_service: T, api: S) -> HttpResult<()>
+//! # where
+//! #     T: HttpPostForm + Sync,
+//! #     S: HttpGetResponse + Sync,
+//! # {
+//! let credentials = ClientCredentials::new(
+//!     token_service,
+//!     "https://auth.example.com/oauth/token",
+//!     "my-client-id",
+//!     "my-client-secret",
+//! )
+//! .with_scopes(["reports:read"]);
+//!
+//! let service = ClientCredentialsService::new(api, credentials);
+//! let response = service
+//!     .get_response("https://api.example.com/reports", &HeaderMap::new())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [client credentials grant]: https://www.rfc-editor.org/rfc/rfc6749#section-4.4
+//! [RFC 6749 § 2.3.1]: https://www.rfc-editor.org/rfc/rfc6749#section-2.3.1
+
+use crate::auth::oauth2::{TokenResponse, parse_response};
+use crate::auth::{Auth, Authenticator};
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{
+    BinaryResponse, HttpBinary, HttpGetResponse, HttpPost, HttpPostForm, HttpResponse,
+};
+use crate::{HttpError, HttpResult};
+use bytes::Bytes;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{IntoUrl, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// The grant type sent to the token endpoint.
+pub const GRANT_TYPE: &str = "client_credentials";
+
+/// How long before a token expires to refresh it, by default.
+pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// The most that is randomly added to the refresh margin, by default.
+pub const DEFAULT_JITTER: Duration = Duration::from_secs(30);
+
+/// An access token and when it should be refreshed.
+struct CachedToken {
+    access_token: String,
+    refresh_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn is_fresh(&self) -> bool {
+        self.refresh_at.is_none_or(|at| Instant::now() < at)
+    }
+}
+
+/// Obtains and caches access tokens using the client credentials grant.
+///
+/// See the [module documentation](crate::auth::oauth2::client_credentials)
+/// for details.
+pub struct ClientCredentials<S> {
+    service: S,
+    token_endpoint: String,
+    client_id: String,
+    client_secret: String,
+    scopes: Vec<String>,
+    refresh_margin: Duration,
+    jitter: Duration,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl<S> fmt::Debug for ClientCredentials<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCredentials")
+            .field("token_endpoint", &self.token_endpoint)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"****")
+            .field("scopes", &self.scopes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: HttpPostForm + Sync> ClientCredentials<S> {
+    /// Creates a client that requests tokens from `token_endpoint` through
+    /// `service`, authenticating as `client_id` with `client_secret`.
+    pub fn new(
+        service: S,
+        token_endpoint: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            service,
+            token_endpoint: token_endpoint.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scopes: Vec::new(),
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            jitter: DEFAULT_JITTER,
+            cached: Mutex::default(),
+        }
+    }
+
+    /// Sets the scopes to request.
+    pub fn with_scopes<I, T>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Refreshes tokens `refresh_margin` before they expire.
+    ///
+    /// Tokens that expire sooner than twice the margin are refreshed
+    /// halfway through their lifetime instead.
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// Adds a random delay of up to `jitter` to the refresh margin.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The current access token, fetching a new one if there is no cached
+    /// token or the cached token is about to expire.
+    ///
+    /// Concurrent callers wait for a single token request rather than
+    /// each making their own.
+    pub async fn token(&self) -> HttpResult<Auth> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref() {
+            if token.is_fresh() {
+                return Ok(Auth::new(&token.access_token));
+            }
+        }
+        self.fetch(&mut cached).await
+    }
+
+    /// Fetches a new access token to replace `rejected`, which the API
+    /// refused.
+    ///
+    /// If the cached token has already been replaced, perhaps by another
+    /// request that was also refused, the replacement is returned instead
+    /// of fetching another one.
+    pub async fn replace(&self, rejected: &Auth) -> HttpResult<Auth> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref() {
+            if token.access_token != rejected.expose() && token.is_fresh() {
+                return Ok(Auth::new(&token.access_token));
+            }
+        }
+        self.fetch(&mut cached).await
+    }
+
+    async fn fetch(&self, cached: &mut Option<CachedToken>) -> HttpResult<Auth> {
+        *cached = None;
+        let scope = self.scopes.join(" ");
+        let mut form = vec![
+            ("grant_type", GRANT_TYPE),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+        if !scope.is_empty() {
+            form.push(("scope", &scope));
+        }
+        let issued_at = Instant::now();
+        let response = self
+            .service
+            .post_form(self.token_endpoint.as_str(), &form)
+            .await?;
+        let token: TokenResponse = parse_response(response)?;
+        if !token.token_type.eq_ignore_ascii_case("bearer") {
+            return Err(HttpError::InvalidToken(format!(
+                "unsupported token type {}",
+                token.token_type
+            )));
+        }
+
+        let refresh_at = token
+            .expires_in
+            .map(|expires_in| issued_at + self.lifetime(Duration::from_secs(expires_in)));
+        let auth = Auth::new(&token.access_token);
+        *cached = Some(CachedToken {
+            access_token: token.access_token,
+            refresh_at,
+        });
+        Ok(auth)
+    }
+
+    /// How long a token that expires after `expires_in` should be used.
+    fn lifetime(&self, expires_in: Duration) -> Duration {
+        let jitter = self.jitter.mul_f64(fastrand::f64());
+        let early = (self.refresh_margin + jitter).min(expires_in / 2);
+        expires_in - early
+    }
+}
+
+/// Wraps an HTTP service and authenticates every request made through it
+/// with a token obtained by a [`ClientCredentials`] grant.
+///
+/// See the [module documentation](crate::auth::oauth2::client_credentials)
+/// for details.
+#[derive(Debug)]
+pub struct ClientCredentialsService<S, T> {
+    inner: S,
+    credentials: ClientCredentials<T>,
+}
+
+impl<S, T: HttpPostForm + Sync> ClientCredentialsService<S, T> {
+    /// Wraps `inner` in a service that authenticates requests with tokens
+    /// from `credentials`.
+    pub fn new(inner: S, credentials: ClientCredentials<T>) -> Self {
+        Self { inner, credentials }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The grant that supplies tokens.
+    pub fn credentials(&self) -> &ClientCredentials<T> {
+        &self.credentials
+    }
+
+    /// Sends a request with `send`, retrying once with a new token if
+    /// `rejected` says the token was refused.
+    async fn send<R, F, Fut>(&self, rejected: fn(&HttpResult<R>) -> bool, send: F) -> HttpResult<R>
+    where
+        F: Fn(Auth) -> Fut,
+        Fut: Future<Output = HttpResult<R>>,
+    {
+        let auth = self.credentials.token().await?;
+        let refused = Auth::new(auth.expose());
+        match send(auth).await {
+            result if !rejected(&result) => return result,
+            _ => {}
+        }
+        let auth = self.credentials.replace(&refused).await?;
+        send(auth).await
+    }
+}
+
+/// True if `result` is an HTTP 401 error.
+fn is_unauthorized<R>(result: &HttpResult<R>) -> bool {
+    matches!(result, Err(err) if err.status() == Some(StatusCode::UNAUTHORIZED))
+}
+
+/// True if `result` is a response with an HTTP 401 status.
+fn is_unauthorized_response(result: &HttpResult<HttpResponse>) -> bool {
+    match result {
+        Ok(response) => response.status() == StatusCode::UNAUTHORIZED,
+        Err(_) => is_unauthorized(result),
+    }
+}
+
+/// `headers` with an `Authorization` header carrying `auth`.
+fn with_bearer(headers: &HeaderMap, auth: &Auth) -> HttpResult<HeaderMap> {
+    let mut value = HeaderValue::try_from(format!("Bearer {}", auth.expose()))?;
+    value.set_sensitive(true);
+    let mut headers = headers.clone();
+    headers.insert(header::AUTHORIZATION, value);
+    Ok(headers)
+}
+
+impl<S, T> HttpPost for ClientCredentialsService<S, T>
+where
+    S: HttpPost + Sync,
+    T: HttpPostForm + Sync,
+{
+    async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = &uri.as_str().to_string();
+        self.send(is_unauthorized, |auth| async move {
+            self.inner.post(uri.as_str(), &auth, data).await
+        })
+        .await
+    }
+}
+
+impl<S, T> HttpGetResponse for ClientCredentialsService<S, T>
+where
+    S: HttpGetResponse + Sync,
+    T: HttpPostForm + Sync,
+{
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = &uri.as_str().to_string();
+        self.send(is_unauthorized_response, |auth| async move {
+            let headers = with_bearer(headers, &auth)?;
+            self.inner.get_response(uri.as_str(), &headers).await
+        })
+        .await
+    }
+}
+
+impl<S, T> HttpBinary for ClientCredentialsService<S, T>
+where
+    S: HttpBinary + Sync,
+    T: HttpPostForm + Sync,
+{
+    async fn get_binary<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = &uri.as_str().to_string();
+        self.send(is_unauthorized, |auth| async move {
+            let headers = with_bearer(headers, &auth)?;
+            self.inner.get_binary(uri.as_str(), &headers).await
+        })
+        .await
+    }
+
+    async fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = &uri.as_str().to_string();
+        self.send(is_unauthorized, |auth| {
+            let body = body.clone();
+            async move {
+                let headers = with_bearer(headers, &auth)?;
+                self.inner.post_binary(uri.as_str(), &headers, body).await
+            }
+        })
+        .await
+    }
+}
+
+impl<S: HttpCapabilities, T> HttpCapabilities for ClientCredentialsService<S, T> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Post, Verb::GetResponse, Verb::Binary])
+            .wrap(Middleware::new("oauth2").with_config(GRANT_TYPE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Request;
+    use std::collections::VecDeque;
+    use std::sync::Mutex as SyncMutex;
+
+    const TOKEN_ENDPOINT: &str = "https://auth.example.com/token";
+
+    /// A token endpoint that issues `token-1`, `token-2`, and so on.
+    #[derive(Default)]
+    struct TokenServer {
+        expires_in: Option<u64>,
+        requests: SyncMutex<Vec<String>>,
+    }
+
+    impl TokenServer {
+        fn expiring_in(expires_in: u64) -> Self {
+            Self {
+                expires_in: Some(expires_in),
+                ..Self::default()
+            }
+        }
+
+        fn request_count(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+    }
+
+    impl HttpPostForm for TokenServer {
+        async fn post_form<U, D>(&self, _uri: U, form: &D) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+        {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(serde_json::to_string(form)?);
+            let expires_in = match self.expires_in {
+                Some(expires_in) => format!(r#","expires_in":{expires_in}"#),
+                None => String::new(),
+            };
+            let body = format!(
+                r#"{{"access_token":"token-{}","token_type":"bearer"{expires_in}}}"#,
+                requests.len()
+            );
+            Ok(HttpResponse::new(StatusCode::OK, body))
+        }
+    }
+
+    /// An API that answers with scripted statuses and records the tokens
+    /// it was sent.
+    #[derive(Default)]
+    struct Api {
+        statuses: SyncMutex<VecDeque<StatusCode>>,
+        tokens: SyncMutex<Vec<String>>,
+    }
+
+    impl Api {
+        fn new(statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+            Self {
+                statuses: SyncMutex::new(statuses.into_iter().collect()),
+                tokens: SyncMutex::default(),
+            }
+        }
+
+        fn respond(&self, authorization: Option<&HeaderValue>) -> StatusCode {
+            let authorization = authorization.map(|value| value.to_str().unwrap().to_string());
+            self.tokens
+                .lock()
+                .unwrap()
+                .push(authorization.unwrap_or_default());
+            self.statuses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(StatusCode::OK)
+        }
+
+        fn tokens(&self) -> Vec<String> {
+            self.tokens.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpGetResponse for Api {
+        async fn get_response<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            let status = self.respond(headers.get(header::AUTHORIZATION));
+            Ok(HttpResponse::new(status, "{}"))
+        }
+    }
+
+    impl HttpPost for Api {
+        async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, _data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let mut request = Request::new(reqwest::Method::POST, uri.into_url()?);
+            auth.authenticate(&mut request)?;
+            let status = self.respond(request.headers().get(header::AUTHORIZATION));
+            HttpResponse::new(status, "null").error_for_status()?.json()
+        }
+    }
+
+    fn credentials(server: TokenServer) -> ClientCredentials<TokenServer> {
+        ClientCredentials::new(server, TOKEN_ENDPOINT, "client-1", "s3cret")
+            .with_jitter(Duration::ZERO)
+    }
+
+    #[tokio::test]
+    async fn it_fetches_tokens_lazily_and_caches_them() -> HttpResult<()> {
+        let credentials = credentials(TokenServer::default()).with_scopes(["read", "write"]);
+        assert_eq!(credentials.service.request_count(), 0);
+
+        assert_eq!(credentials.token().await?.expose(), "token-1");
+        assert_eq!(credentials.token().await?.expose(), "token-1");
+        let requests = credentials.service.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains(r#"["grant_type","client_credentials"]"#));
+        assert!(requests[0].contains(r#"["client_secret","s3cret"]"#));
+        assert!(requests[0].contains(r#"["scope","read write"]"#));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_refreshes_tokens_before_they_expire() -> HttpResult<()> {
+        let credentials = credentials(TokenServer::expiring_in(600));
+        credentials.token().await?;
+
+        tokio::time::advance(Duration::from_secs(539)).await;
+        assert_eq!(credentials.token().await?.expose(), "token-1");
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(credentials.token().await?.expose(), "token-2");
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_refreshes_short_lived_tokens_halfway() -> HttpResult<()> {
+        let credentials = credentials(TokenServer::expiring_in(60));
+        credentials.token().await?;
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert_eq!(credentials.token().await?.expose(), "token-2");
+        Ok(())
+    }
+
+    #[test]
+    fn it_jitters_refresh_times() {
+        let credentials = ClientCredentials::new(TokenServer::default(), TOKEN_ENDPOINT, "a", "b");
+        let lifetime = credentials.lifetime(Duration::from_secs(3600));
+        assert!(lifetime <= Duration::from_secs(3540));
+        assert!(lifetime >= Duration::from_secs(3510));
+    }
+
+    #[tokio::test]
+    async fn it_retries_once_with_a_new_token_on_401() -> HttpResult<()> {
+        let api = Api::new([StatusCode::UNAUTHORIZED]);
+        let service = ClientCredentialsService::new(api, credentials(TokenServer::default()));
+        let response = service.get_response("/reports", &HeaderMap::new()).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            service.inner().tokens(),
+            ["Bearer token-1", "Bearer token-2"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_after_one_retry() {
+        let api = Api::new([StatusCode::UNAUTHORIZED, StatusCode::UNAUTHORIZED]);
+        let service = ClientCredentialsService::new(api, credentials(TokenServer::default()));
+        let result: HttpResult<()> = service
+            .post(
+                "https://api.example.com/reports",
+                &Auth::new("ignored"),
+                &(),
+            )
+            .await;
+        assert!(matches!(result, Err(HttpError::Unauthorized(_))));
+        assert_eq!(service.inner().tokens().len(), 2);
+        assert_eq!(service.credentials().service.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn it_reuses_a_token_that_was_already_replaced() -> HttpResult<()> {
+        let credentials = credentials(TokenServer::default());
+        let stale = credentials.token().await?;
+        assert_eq!(credentials.replace(&stale).await?.expose(), "token-2");
+        assert_eq!(credentials.replace(&stale).await?.expose(), "token-2");
+        assert_eq!(credentials.service.request_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_show_the_secret() {
+        let credentials = credentials(TokenServer::default());
+        assert!(!format!("{credentials:?}").contains("s3cret"));
+    }
+}
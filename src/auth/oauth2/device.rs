@@ -0,0 +1,514 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! The OAuth 2.0 device authorization grant.
+//!
+//! The [device authorization grant] lets an application that cannot open a
+//! browser, such as a CLI running on a remote server, obtain an access
+//! token. The application requests a [`DeviceCode`], shows the user a
+//! short code and a URL to visit on another device, and then polls the
+//! authorization server until the user approves or denies the request.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::auth::oauth2::device::DeviceFlow;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpPostForm;
+//!
+//! # async fn run(service: impl HttpPostForm + Sync) -> HttpResult<()> {
+//! let flow = DeviceFlow::new(
+//!     service,
+//!     "my-client-id",
+//!     "https://github.com/login/device/code",
+//!     "https://github.com/login/oauth/access_token",
+//! )
+//! .with_scopes(["repo", "read:org"]);
+//!
+//! let token = flow
+//!     .login(|code| println!("Visit {} and enter {}", code.verification_uri, code.user_code))
+//!     .await?;
+//! println!("Logged in with {}", token.access_token);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`request_code()`](DeviceFlow::request_code) and
+//! [`poll()`](DeviceFlow::poll) can also be called separately, for
+//! example to show the code in a UI before polling starts:
+//!
+//! ```no_run
+//! # use hypertyper::auth::oauth2::device::DeviceFlow;
+//! # use hypertyper::prelude::*;
+//! # use hypertyper::service::HttpPostForm;
+//! # async fn run(flow: DeviceFlow<impl HttpPostForm + Sync>) -> HttpResult<()> {
+//! let code = flow.request_code().await?;
+//! println!("Visit {} and enter {}", code.verification_uri, code.user_code);
+//! let token = flow.poll(&code).await?;
+//! println!("Logged in with {}", token.access_token);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A flow [with a store](DeviceFlow::with_store) saves the refresh token
+//! it is issued, so that later runs can [refresh](DeviceFlow::refresh) the
+//! access token rather than asking the user to log in again.
+//!
+//! [device authorization grant]: https://www.rfc-editor.org/rfc/rfc8628
+
+use crate::auth::oauth2::store::TokenStore;
+use crate::auth::oauth2::{TokenResponse, parse_response};
+use crate::service::HttpPostForm;
+use crate::{HttpError, HttpResult};
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The grant type used to poll the token endpoint.
+pub const GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// How much longer to wait between polls when asked to slow down.
+const SLOW_DOWN_INCREMENT: u64 = 5;
+
+/// A device code issued by an authorization server, as described in
+/// [RFC 8628 § 3.2].
+///
+/// [RFC 8628 § 3.2]: https://www.rfc-editor.org/rfc/rfc8628#section-3.2
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeviceCode {
+    /// The code used to poll for an access token.
+    pub device_code: String,
+
+    /// The code the user should enter at the verification URI.
+    pub user_code: String,
+
+    /// The URI the user should visit to approve the request.
+    ///
+    /// Some providers, such as Google, call this `verification_url`.
+    #[serde(alias = "verification_url")]
+    pub verification_uri: String,
+
+    /// A verification URI that includes the user code, which can be shown
+    /// to the user as a link or a QR code.
+    pub verification_uri_complete: Option<String>,
+
+    /// How long the device code is valid for, in seconds.
+    pub expires_in: u64,
+
+    /// How long to wait between polls, in seconds.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// Obtains an access token using the device authorization grant.
+///
+/// See the [module documentation](crate::auth::oauth2::device) for details.
+pub struct DeviceFlow<S> {
+    service: S,
+    client_id: String,
+    device_authorization_endpoint: String,
+    token_endpoint: String,
+    scopes: Vec<String>,
+    store: Option<Box<dyn TokenStore>>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for DeviceFlow<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceFlow")
+            .field("service", &self.service)
+            .field("client_id", &self.client_id)
+            .field(
+                "device_authorization_endpoint",
+                &self.device_authorization_endpoint,
+            )
+            .field("token_endpoint", &self.token_endpoint)
+            .field("scopes", &self.scopes)
+            .field("has_store", &self.store.is_some())
+            .finish()
+    }
+}
+
+impl<S: HttpPostForm + Sync> DeviceFlow<S> {
+    /// Creates a device flow for the client identified by `client_id`.
+    pub fn new(
+        service: S,
+        client_id: impl Into<String>,
+        device_authorization_endpoint: impl Into<String>,
+        token_endpoint: impl Into<String>,
+    ) -> Self {
+        Self {
+            service,
+            client_id: client_id.into(),
+            device_authorization_endpoint: device_authorization_endpoint.into(),
+            token_endpoint: token_endpoint.into(),
+            scopes: Vec::new(),
+            store: None,
+        }
+    }
+
+    /// Sets the scopes to request.
+    pub fn with_scopes<I, T>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Saves refresh tokens to `store`, so that they can be used to
+    /// [refresh](Self::refresh) the access token later.
+    pub fn with_store(mut self, store: impl TokenStore + 'static) -> Self {
+        self.store = Some(Box::new(store));
+        self
+    }
+
+    /// Requests a device code and user code from the authorization server.
+    ///
+    /// Show [`DeviceCode::user_code`] and [`DeviceCode::verification_uri`]
+    /// to the user, then call [`poll()`](Self::poll) to wait for them to
+    /// approve the request.
+    pub async fn request_code(&self) -> HttpResult<DeviceCode> {
+        let scope = self.scopes.join(" ");
+        let mut form = vec![("client_id", self.client_id.as_str())];
+        if !scope.is_empty() {
+            form.push(("scope", &scope));
+        }
+        let response = self
+            .service
+            .post_form(self.device_authorization_endpoint.as_str(), &form)
+            .await?;
+        parse_response(response)
+    }
+
+    /// Logs the user in: requests a device code, passes it to `display` to
+    /// show to the user, and [polls](Self::poll) until they approve or deny
+    /// the request.
+    pub async fn login<F>(&self, display: F) -> HttpResult<TokenResponse>
+    where
+        F: FnOnce(&DeviceCode),
+    {
+        let code = self.request_code().await?;
+        display(&code);
+        self.poll(&code).await
+    }
+
+    /// Polls the token endpoint until the user approves or denies the
+    /// request, or the device code expires.
+    ///
+    /// The token endpoint is polled no more often than the interval
+    /// requested by the authorization server, and the interval is
+    /// lengthened whenever the server asks the client to slow down.
+    ///
+    /// Returns [`HttpError::OAuth`] with an error of `access_denied` if
+    /// the user denies the request, or `expired_token` if the device code
+    /// expires first. If the flow has a store, a refresh token in the
+    /// response is saved to it. A device code whose lifetime is too long to
+    /// represent never expires, and an interval that long means the code
+    /// expires before it can be polled.
+    pub async fn poll(&self, code: &DeviceCode) -> HttpResult<TokenResponse> {
+        let deadline = Instant::now().checked_add(Duration::from_secs(code.expires_in));
+        let mut interval = code.interval;
+        let form = [
+            ("grant_type", GRANT_TYPE),
+            ("device_code", &code.device_code),
+            ("client_id", &self.client_id),
+        ];
+
+        loop {
+            let next_poll = match Instant::now().checked_add(Duration::from_secs(interval)) {
+                Some(next_poll) if deadline.is_none_or(|deadline| next_poll < deadline) => {
+                    next_poll
+                }
+                _ => {
+                    return Err(HttpError::OAuth {
+                        error: String::from("expired_token"),
+                        description: Some(String::from("The device code expired")),
+                    });
+                }
+            };
+            tokio::time::sleep_until(next_poll).await;
+
+            let response = self
+                .service
+                .post_form(self.token_endpoint.as_str(), &form)
+                .await?;
+            match parse_response(response) {
+                Err(HttpError::OAuth { error, .. }) if error == "authorization_pending" => {}
+                Err(HttpError::OAuth { error, .. }) if error == "slow_down" => {
+                    interval = interval.saturating_add(SLOW_DOWN_INCREMENT);
+                }
+                result => return self.save(result),
+            }
+        }
+    }
+
+    /// Obtains a new access token with the saved refresh token.
+    ///
+    /// Returns `None` if the flow has no store or no refresh token is
+    /// saved, in which case the user must [log in](Self::login). If the
+    /// authorization server issues a new refresh token, it replaces the
+    /// saved one. If the saved token is rejected as `invalid_grant`, it is
+    /// removed from the store before the error is returned.
+    pub async fn refresh(&self) -> HttpResult<Option<TokenResponse>> {
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+        let Some(refresh_token) = store.load()? else {
+            return Ok(None);
+        };
+        let form = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+            ("client_id", &self.client_id),
+        ];
+        let response = self
+            .service
+            .post_form(self.token_endpoint.as_str(), &form)
+            .await?;
+        match parse_response(response) {
+            Err(HttpError::OAuth { error, description }) if error == "invalid_grant" => {
+                store.clear()?;
+                Err(HttpError::OAuth { error, description })
+            }
+            result => self.save(result).map(Some),
+        }
+    }
+
+    /// Saves the refresh token in `result`, if there is one and the flow
+    /// has a store.
+    fn save(&self, result: HttpResult<TokenResponse>) -> HttpResult<TokenResponse> {
+        let token = result?;
+        if let (Some(store), Some(refresh_token)) = (&self.store, &token.refresh_token) {
+            store.save(refresh_token)?;
+        }
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::oauth2::store::MemoryTokenStore;
+    use crate::service::HttpResponse;
+    use reqwest::{IntoUrl, StatusCode};
+    use serde::Serialize;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    const DEVICE_ENDPOINT: &str = "https://auth.example.com/device";
+    const TOKEN_ENDPOINT: &str = "https://auth.example.com/token";
+
+    #[derive(Default)]
+    struct ScriptedServer {
+        responses: Mutex<VecDeque<HttpResponse>>,
+        requests: Mutex<Vec<(String, Instant, String)>>,
+    }
+
+    impl ScriptedServer {
+        fn new(responses: impl IntoIterator<Item = (StatusCode, &'static str)>) -> Self {
+            let responses = responses
+                .into_iter()
+                .map(|(status, body)| HttpResponse::new(status, body))
+                .collect();
+            Self {
+                responses: Mutex::new(responses),
+                requests: Mutex::default(),
+            }
+        }
+
+        fn poll_times(&self) -> Vec<Instant> {
+            let requests = self.requests.lock().unwrap();
+            requests
+                .iter()
+                .filter(|(uri, ..)| uri == TOKEN_ENDPOINT)
+                .map(|(_, at, _)| *at)
+                .collect()
+        }
+    }
+
+    impl HttpPostForm for ScriptedServer {
+        async fn post_form<U, D>(&self, uri: U, form: &D) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+        {
+            let body = serde_json::to_string(form)?;
+            let request = (uri.as_str().to_string(), Instant::now(), body);
+            self.requests.lock().unwrap().push(request);
+            let response = self.responses.lock().unwrap().pop_front();
+            Ok(response.expect("no more scripted responses"))
+        }
+    }
+
+    fn code(expires_in: u64) -> DeviceCode {
+        DeviceCode {
+            device_code: String::from("device-123"),
+            user_code: String::from("WDJB-MJHT"),
+            verification_uri: String::from("https://example.com/device"),
+            verification_uri_complete: None,
+            expires_in,
+            interval: 5,
+        }
+    }
+
+    const PENDING: (StatusCode, &str) = (
+        StatusCode::BAD_REQUEST,
+        r#"{"error":"authorization_pending"}"#,
+    );
+    const SLOW_DOWN: (StatusCode, &str) = (StatusCode::BAD_REQUEST, r#"{"error":"slow_down"}"#);
+    const TOKEN: (StatusCode, &str) = (
+        StatusCode::OK,
+        r#"{"access_token":"token-abc","token_type":"Bearer"}"#,
+    );
+
+    fn flow(server: ScriptedServer) -> DeviceFlow<ScriptedServer> {
+        DeviceFlow::new(server, "client-1", DEVICE_ENDPOINT, TOKEN_ENDPOINT)
+    }
+
+    #[tokio::test]
+    async fn it_requests_a_device_code() -> HttpResult<()> {
+        let server = ScriptedServer::new([(
+            StatusCode::OK,
+            r#"{
+                "device_code": "device-123",
+                "user_code": "WDJB-MJHT",
+                "verification_uri": "https://example.com/device",
+                "expires_in": 1800
+            }"#,
+        )]);
+        let flow = flow(server).with_scopes(["read", "write"]);
+        let code = flow.request_code().await?;
+        assert_eq!(code.user_code, "WDJB-MJHT");
+        assert_eq!(code.interval, 5);
+
+        let requests = flow.service.requests.lock().unwrap();
+        assert_eq!(requests[0].0, DEVICE_ENDPOINT);
+        assert!(requests[0].2.contains(r#"["scope","read write"]"#));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_polls_until_the_user_approves() -> HttpResult<()> {
+        let start = Instant::now();
+        let flow = flow(ScriptedServer::new([PENDING, PENDING, TOKEN]));
+        let token = flow.poll(&code(600)).await?;
+        assert_eq!(token.access_token, "token-abc");
+
+        let elapsed: Vec<_> = flow
+            .service
+            .poll_times()
+            .iter()
+            .map(|t| *t - start)
+            .collect();
+        let expected: Vec<_> = [5, 10, 15].map(Duration::from_secs).into();
+        assert_eq!(elapsed, expected);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_slows_down_when_asked() -> HttpResult<()> {
+        let start = Instant::now();
+        let flow = flow(ScriptedServer::new([SLOW_DOWN, PENDING, TOKEN]));
+        flow.poll(&code(600)).await?;
+
+        let elapsed: Vec<_> = flow
+            .service
+            .poll_times()
+            .iter()
+            .map(|t| *t - start)
+            .collect();
+        let expected: Vec<_> = [5, 15, 25].map(Duration::from_secs).into();
+        assert_eq!(elapsed, expected);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_logs_in_with_google_and_github_style_responses() -> HttpResult<()> {
+        let code = (
+            StatusCode::OK,
+            r#"{
+                "device_code": "device-123",
+                "user_code": "WDJB-MJHT",
+                "verification_url": "https://www.google.com/device",
+                "expires_in": 1800,
+                "interval": 5
+            }"#,
+        );
+        let github_pending = (StatusCode::OK, r#"{"error":"authorization_pending"}"#);
+        let flow = flow(ScriptedServer::new([code, github_pending, TOKEN]));
+        let mut shown = None;
+        let token = flow
+            .login(|code| shown = Some(code.verification_uri.clone()))
+            .await?;
+        assert_eq!(token.access_token, "token-abc");
+        assert_eq!(shown.as_deref(), Some("https://www.google.com/device"));
+        assert_eq!(flow.service.poll_times().len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_saves_and_uses_refresh_tokens() -> HttpResult<()> {
+        let store = Arc::new(MemoryTokenStore::new());
+        let with_refresh = (
+            StatusCode::OK,
+            r#"{"access_token":"token-abc","token_type":"Bearer","refresh_token":"refresh-1"}"#,
+        );
+        let revoked = (StatusCode::BAD_REQUEST, r#"{"error":"invalid_grant"}"#);
+        let flow = flow(ScriptedServer::new([with_refresh, TOKEN, revoked]))
+            .with_store(Arc::clone(&store));
+
+        flow.poll(&code(600)).await?;
+        assert_eq!(store.load()?.as_deref(), Some("refresh-1"));
+
+        let token = flow.refresh().await?.unwrap();
+        assert_eq!(token.access_token, "token-abc");
+        let requests = flow.service.requests.lock().unwrap().clone();
+        assert!(requests[1].2.contains(r#"["refresh_token","refresh-1"]"#));
+        assert_eq!(store.load()?.as_deref(), Some("refresh-1"));
+
+        let result = flow.refresh().await;
+        assert!(matches!(result, Err(HttpError::OAuth { error, .. }) if error == "invalid_grant"));
+        assert_eq!(store.load()?, None);
+        assert!(flow.refresh().await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_stops_when_the_user_denies_the_request() {
+        let denied = (StatusCode::BAD_REQUEST, r#"{"error":"access_denied"}"#);
+        let flow = flow(ScriptedServer::new([PENDING, denied]));
+        let result = flow.poll(&code(600)).await;
+        assert!(matches!(result, Err(HttpError::OAuth { error, .. }) if error == "access_denied"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_stops_when_the_code_expires() {
+        let flow = flow(ScriptedServer::new([PENDING, PENDING]));
+        let result = flow.poll(&code(12)).await;
+        assert!(matches!(result, Err(HttpError::OAuth { error, .. }) if error == "expired_token"));
+        assert_eq!(flow.service.poll_times().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_copes_with_lifetimes_and_intervals_too_long_to_represent() -> HttpResult<()> {
+        let patient = flow(ScriptedServer::new([PENDING, TOKEN]));
+        let token = patient.poll(&code(u64::MAX)).await?;
+        assert_eq!(token.access_token, "token-abc");
+
+        let stalled = flow(ScriptedServer::new([TOKEN]));
+        let code = DeviceCode {
+            interval: u64::MAX,
+            ..code(600)
+        };
+        let result = stalled.poll(&code).await;
+        assert!(matches!(result, Err(HttpError::OAuth { error, .. }) if error == "expired_token"));
+        assert!(stalled.service.poll_times().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Persistent storage for refresh tokens.
+//!
+//! Grants that issue refresh tokens, such as the
+//! [authorization code grant](super::authorization_code), save them to a
+//! [`TokenStore`] so that a user only has to log in once, rather than every
+//! time a CLI or desktop app starts. [`MemoryTokenStore`] keeps the token
+//! for the life of the process, and [`FileTokenStore`] keeps it in a file
+//! that only the current user can read. Other backends, such as the
+//! operating system's keychain, can be used by implementing the trait.
+//!
+//! With the **encrypted-store** feature, an
+//! [`EncryptedTokenStore`](encrypted::EncryptedTokenStore) encrypts tokens
+//! with a passphrase or a key kept in the keyring before saving them to
+//! another store, so that tokens on disk are useless without the key.
+
+#[cfg(feature = "encrypted-store")]
+pub mod encrypted;
+
+use crate::HttpResult;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Saves and loads a refresh token.
+pub trait TokenStore: Send + Sync {
+    /// The saved refresh token, if there is one.
+    fn load(&self) -> HttpResult<Option<String>>;
+
+    /// Saves `refresh_token`, replacing any token that was already saved.
+    fn save(&self, refresh_token: &str) -> HttpResult<()>;
+
+    /// Removes the saved refresh token, if there is one.
+    fn clear(&self) -> HttpResult<()>;
+}
+
+impl<T: TokenStore + ?Sized> TokenStore for Arc<T> {
+    fn load(&self) -> HttpResult<Option<String>> {
+        (**self).load()
+    }
+
+    fn save(&self, refresh_token: &str) -> HttpResult<()> {
+        (**self).save(refresh_token)
+    }
+
+    fn clear(&self) -> HttpResult<()> {
+        (**self).clear()
+    }
+}
+
+/// Keeps a refresh token in memory.
+#[derive(Default)]
+pub struct MemoryTokenStore {
+    token: Mutex<Option<String>>,
+}
+
+impl MemoryTokenStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for MemoryTokenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let saved = self.token.lock().unwrap().is_some();
+        f.debug_struct("MemoryTokenStore")
+            .field("saved", &saved)
+            .finish()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> HttpResult<Option<String>> {
+        Ok(self.token.lock().unwrap().clone())
+    }
+
+    fn save(&self, refresh_token: &str) -> HttpResult<()> {
+        *self.token.lock().unwrap() = Some(refresh_token.to_string());
+        Ok(())
+    }
+
+    fn clear(&self) -> HttpResult<()> {
+        *self.token.lock().unwrap() = None;
+        Ok(())
+    }
+}
+
+/// Keeps a refresh token in a file.
+///
+/// On Unix, the file is created readable and writable only by its owner.
+/// The token is written to a temporary file that replaces the old one, so
+/// a crash while saving never leaves a partial token behind.
+#[derive(Clone, Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    /// Creates a store that keeps the token in the file at `path`.
+    ///
+    /// The file and its parent directories are created when a token is
+    /// first saved.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self { path }
+    }
+
+    /// The path of the file the token is kept in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> HttpResult<Option<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(token) => {
+                let token = token.trim();
+                Ok((!token.is_empty()).then(|| token.to_string()))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, refresh_token: &str) -> HttpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(refresh_token.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    fn clear(&self) -> HttpResult<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_tokens_in_memory() -> HttpResult<()> {
+        let store = MemoryTokenStore::new();
+        assert_eq!(store.load()?, None);
+        store.save("refresh-1")?;
+        assert_eq!(store.load()?.as_deref(), Some("refresh-1"));
+        assert!(!format!("{store:?}").contains("refresh-1"));
+        store.clear()?;
+        assert_eq!(store.load()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_tokens_in_files() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let store = FileTokenStore::new(dir.path().join("app").join("token"));
+        assert_eq!(store.load()?, None);
+        store.clear()?;
+
+        store.save("refresh-1")?;
+        store.save("refresh-2")?;
+        assert_eq!(store.load()?.as_deref(), Some("refresh-2"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(store.path())?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        store.clear()?;
+        assert_eq!(store.load()?, None);
+        Ok(())
+    }
+}
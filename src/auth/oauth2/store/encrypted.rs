@@ -0,0 +1,372 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Encrypted token storage.
+//!
+//! An [`EncryptedTokenStore`] encrypts tokens with XChaCha20-Poly1305
+//! before handing them to another store, such as a [`FileTokenStore`], so
+//! a token that is copied off the disk is useless without its key. The
+//! key is a [`TokenKey`], which can be derived from a passphrase with
+//! PBKDF2, given directly, or, with the **keyring** feature, generated once
+//! and kept in the operating system's keyring.
+//!
+//! This is only available when the **encrypted-store** feature is enabled.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::HttpResult;
+//! use hypertyper::auth::oauth2::store::encrypted::{EncryptedTokenStore, TokenKey};
+//! use hypertyper::auth::oauth2::store::{FileTokenStore, TokenStore};
+//!
+//! # fn run(passphrase: String) -> HttpResult<()> {
+//! let store = EncryptedTokenStore::new(
+//!     FileTokenStore::new("/home/user/.config/myapp/token"),
+//!     TokenKey::passphrase(passphrase),
+//! );
+//! store.save("refresh-token")?;
+//! assert_eq!(store.load()?.as_deref(), Some("refresh-token"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`FileTokenStore`]: super::FileTokenStore
+
+use crate::auth::Secret;
+use crate::auth::oauth2::store::TokenStore;
+use crate::{HttpError, HttpResult};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use sha2::Sha256;
+use std::{fmt, io};
+use zeroize::Zeroize;
+
+/// The PBKDF2-HMAC-SHA256 iterations used to derive keys from
+/// passphrases unless [`TokenKey::with_rounds()`] says otherwise.
+pub const DEFAULT_ROUNDS: u32 = 600_000;
+
+/// The version of the encrypted format, which is its first byte.
+const VERSION: u8 = 1;
+
+const SALT_BYTES: usize = 16;
+const NONCE_BYTES: usize = 24;
+
+/// The version, rounds, salt, and nonce that begin each encrypted token.
+const HEADER_BYTES: usize = 1 + 4 + SALT_BYTES + NONCE_BYTES;
+
+/// The key that tokens are encrypted with.
+pub struct TokenKey(KeySource);
+
+enum KeySource {
+    Passphrase { passphrase: Secret, rounds: u32 },
+    Key([u8; 32]),
+}
+
+impl TokenKey {
+    /// Derives keys from `passphrase` with PBKDF2-HMAC-SHA256.
+    ///
+    /// Each token is encrypted with a key derived with a new salt.
+    pub fn passphrase(passphrase: impl Into<String>) -> Self {
+        let passphrase = Secret::new(passphrase);
+        Self(KeySource::Passphrase {
+            passphrase,
+            rounds: DEFAULT_ROUNDS,
+        })
+    }
+
+    /// Derives keys from the passphrase with `rounds` iterations of
+    /// PBKDF2, rather than [`DEFAULT_ROUNDS`].
+    ///
+    /// Tokens remember how many rounds they were encrypted with, so this
+    /// can be changed without losing saved tokens. Keys that are not
+    /// passphrases are not affected.
+    pub fn with_rounds(mut self, rounds: u32) -> Self {
+        if let KeySource::Passphrase { rounds: r, .. } = &mut self.0 {
+            *r = rounds.max(1);
+        }
+        self
+    }
+
+    /// Uses `key` directly.
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self(KeySource::Key(key))
+    }
+
+    /// Uses the key kept in the keyring entry for `user` of `service`,
+    /// generating and saving a new one if there is none.
+    ///
+    /// Only available when the **keyring** feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::InvalidCredentials`] if the keyring cannot be
+    /// used, or if the entry does not hold a key.
+    #[cfg(feature = "keyring")]
+    pub fn from_keyring(service: &str, user: &str) -> HttpResult<Self> {
+        let keyring_error = |err: keyring::Error| {
+            HttpError::InvalidCredentials(format!(
+                "could not use keyring entry {service} for {user}: {err}"
+            ))
+        };
+        let entry = keyring::Entry::new(service, user).map_err(keyring_error)?;
+        match entry.get_password() {
+            Ok(encoded) => {
+                let encoded = Secret::new(encoded);
+                let mut bytes = URL_SAFE_NO_PAD.decode(encoded.expose()).unwrap_or_default();
+                let key = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| {
+                    HttpError::InvalidCredentials(format!(
+                        "keyring entry {service} for {user} is not a token key"
+                    ))
+                });
+                bytes.zeroize();
+                Ok(Self::from_bytes(key?))
+            }
+            Err(keyring::Error::NoEntry) => {
+                let key = random::<32>()?;
+                let encoded = Secret::new(URL_SAFE_NO_PAD.encode(key));
+                entry
+                    .set_password(encoded.expose())
+                    .map_err(keyring_error)?;
+                Ok(Self::from_bytes(key))
+            }
+            Err(err) => Err(keyring_error(err)),
+        }
+    }
+
+    /// The key for a token encrypted with `rounds` and `salt`.
+    fn derive(&self, rounds: u32, salt: &[u8]) -> HttpResult<[u8; 32]> {
+        match &self.0 {
+            KeySource::Key(key) if rounds == 0 => Ok(*key),
+            KeySource::Passphrase { passphrase, .. } if rounds > 0 => {
+                let mut key = [0; 32];
+                pbkdf2::pbkdf2_hmac::<Sha256>(
+                    passphrase.expose().as_bytes(),
+                    salt,
+                    rounds,
+                    &mut key,
+                );
+                Ok(key)
+            }
+            _ => Err(HttpError::InvalidCredentials(String::from(
+                "the saved token was encrypted with a different kind of key",
+            ))),
+        }
+    }
+
+    /// The rounds to encrypt a new token with, which are 0 for keys that
+    /// are not derived.
+    fn rounds(&self) -> u32 {
+        match &self.0 {
+            KeySource::Passphrase { rounds, .. } => *rounds,
+            KeySource::Key(_) => 0,
+        }
+    }
+}
+
+impl Drop for TokenKey {
+    fn drop(&mut self) {
+        if let KeySource::Key(key) = &mut self.0 {
+            key.zeroize();
+        }
+    }
+}
+
+impl fmt::Debug for TokenKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            KeySource::Passphrase { rounds, .. } => f
+                .debug_struct("TokenKey")
+                .field("passphrase", &"****")
+                .field("rounds", rounds)
+                .finish(),
+            KeySource::Key(_) => f.debug_struct("TokenKey").field("key", &"****").finish(),
+        }
+    }
+}
+
+/// Encrypts tokens before saving them to another store.
+///
+/// See the [module documentation](crate::auth::oauth2::store::encrypted)
+/// for details.
+#[derive(Debug)]
+pub struct EncryptedTokenStore<S> {
+    inner: S,
+    key: TokenKey,
+}
+
+impl<S: TokenStore> EncryptedTokenStore<S> {
+    /// Saves tokens to `inner`, encrypted with `key`.
+    pub fn new(inner: S, key: TokenKey) -> Self {
+        Self { inner, key }
+    }
+
+    /// The store that encrypted tokens are saved to.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn encrypt(&self, token: &str) -> HttpResult<String> {
+        let rounds = self.key.rounds();
+        let salt = if rounds > 0 {
+            random::<SALT_BYTES>()?
+        } else {
+            [0; SALT_BYTES]
+        };
+        let nonce = random::<NONCE_BYTES>()?;
+        let mut header = Vec::with_capacity(HEADER_BYTES);
+        header.push(VERSION);
+        header.extend_from_slice(&rounds.to_be_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce);
+
+        let mut key = self.key.derive(rounds, &salt)?;
+        let cipher = XChaCha20Poly1305::new(&Key::from(key));
+        key.zeroize();
+        let payload = Payload {
+            msg: token.as_bytes(),
+            aad: &header,
+        };
+        let ciphertext = cipher
+            .encrypt(&XNonce::from(nonce), payload)
+            .map_err(|_| HttpError::InvalidCredentials(String::from("could not encrypt token")))?;
+        header.extend_from_slice(&ciphertext);
+        Ok(URL_SAFE_NO_PAD.encode(header))
+    }
+
+    fn decrypt(&self, encrypted: &str) -> HttpResult<String> {
+        let malformed =
+            || HttpError::InvalidCredentials(String::from("the saved token is corrupt"));
+        let bytes = URL_SAFE_NO_PAD.decode(encrypted).map_err(|_| malformed())?;
+        if bytes.len() < HEADER_BYTES || bytes[0] != VERSION {
+            return Err(malformed());
+        }
+        let (header, ciphertext) = bytes.split_at(HEADER_BYTES);
+        let rounds = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+        let salt = &header[5..5 + SALT_BYTES];
+        let nonce =
+            <[u8; NONCE_BYTES]>::try_from(&header[5 + SALT_BYTES..]).map_err(|_| malformed())?;
+
+        let mut key = self.key.derive(rounds, salt)?;
+        let cipher = XChaCha20Poly1305::new(&Key::from(key));
+        key.zeroize();
+        let payload = Payload {
+            msg: ciphertext,
+            aad: header,
+        };
+        let plaintext = cipher.decrypt(&XNonce::from(nonce), payload).map_err(|_| {
+            HttpError::InvalidCredentials(String::from(
+                "could not decrypt the saved token; the key may be wrong",
+            ))
+        })?;
+        String::from_utf8(plaintext).map_err(|_| malformed())
+    }
+}
+
+impl<S: TokenStore> TokenStore for EncryptedTokenStore<S> {
+    fn load(&self) -> HttpResult<Option<String>> {
+        match self.inner.load()? {
+            Some(encrypted) => self.decrypt(&encrypted).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, refresh_token: &str) -> HttpResult<()> {
+        self.inner.save(&self.encrypt(refresh_token)?)
+    }
+
+    fn clear(&self) -> HttpResult<()> {
+        self.inner.clear()
+    }
+}
+
+/// `N` random bytes.
+fn random<const N: usize>() -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::oauth2::store::{FileTokenStore, MemoryTokenStore};
+
+    fn passphrase(passphrase: &str) -> TokenKey {
+        TokenKey::passphrase(passphrase).with_rounds(1_000)
+    }
+
+    #[test]
+    fn it_encrypts_tokens_with_passphrases() -> HttpResult<()> {
+        let store = EncryptedTokenStore::new(MemoryTokenStore::new(), passphrase("hunter2"));
+        assert_eq!(store.load()?, None);
+        store.save("refresh-1")?;
+        let saved = store.inner().load()?.unwrap();
+        assert!(!saved.contains("refresh-1"));
+        assert_eq!(store.load()?.as_deref(), Some("refresh-1"));
+
+        store.save("refresh-1")?;
+        assert_ne!(store.inner().load()?.unwrap(), saved);
+
+        store.clear()?;
+        assert_eq!(store.inner().load()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_encrypts_tokens_with_keys() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("token");
+        let store =
+            EncryptedTokenStore::new(FileTokenStore::new(&path), TokenKey::from_bytes([7; 32]));
+        store.save("refresh-1")?;
+        let reopened =
+            EncryptedTokenStore::new(FileTokenStore::new(&path), TokenKey::from_bytes([7; 32]));
+        assert_eq!(reopened.load()?.as_deref(), Some("refresh-1"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_remembers_the_rounds_tokens_were_encrypted_with() -> HttpResult<()> {
+        let inner = MemoryTokenStore::new();
+        let store = EncryptedTokenStore::new(inner, passphrase("hunter2"));
+        store.save("refresh-1")?;
+        let store = EncryptedTokenStore::new(
+            store.inner,
+            TokenKey::passphrase("hunter2").with_rounds(2_000),
+        );
+        assert_eq!(store.load()?.as_deref(), Some("refresh-1"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_the_wrong_key() -> HttpResult<()> {
+        let store = EncryptedTokenStore::new(MemoryTokenStore::new(), passphrase("hunter2"));
+        store.save("refresh-1")?;
+
+        let store = EncryptedTokenStore::new(store.inner, passphrase("hunter3"));
+        assert!(matches!(
+            store.load(),
+            Err(HttpError::InvalidCredentials(_))
+        ));
+        let store = EncryptedTokenStore::new(store.inner, TokenKey::from_bytes([0; 32]));
+        assert!(matches!(
+            store.load(),
+            Err(HttpError::InvalidCredentials(_))
+        ));
+
+        store.inner().save("not encrypted")?;
+        assert!(matches!(
+            store.load(),
+            Err(HttpError::InvalidCredentials(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_show_keys() {
+        assert!(!format!("{:?}", passphrase("hunter2")).contains("hunter2"));
+        assert!(!format!("{:?}", TokenKey::from_bytes([7; 32])).contains('7'));
+    }
+}
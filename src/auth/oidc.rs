@@ -0,0 +1,376 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! OpenID Connect ID token validation.
+//!
+//! An [`OidcProvider`] [discovers](crate::discovery::well_known) an OpenID
+//! Connect provider's metadata, fetches the provider's signing keys, and
+//! uses them to validate the ID tokens it issues. Signing keys are cached
+//! and periodically refreshed, and a token signed with a key the provider
+//! has only recently rotated in causes the keys to be refetched
+//! immediately.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::auth::oidc::{IdTokenClaims, OidcProvider};
+//! use hypertyper::prelude::*;
+//!
+//! # async fn run(service: impl HttpGet + Sync, id_token: &str) -> HttpResult<()> {
+//! let provider = OidcProvider::discover(service, "https://accounts.google.com").await?;
+//! let claims: IdTokenClaims = provider.validate(id_token, "my-client-id").await?;
+//! println!("Hello, {}", claims.sub);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::decode;
+use crate::discovery::well_known::{self, OpenIdConfiguration};
+use crate::service::HttpGet;
+use crate::{HttpError, HttpResult};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The standard claims found in an OpenID Connect ID token.
+///
+/// Use your own type with [`OidcProvider::validate()`] if you need claims
+/// that are not included here.
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdTokenClaims {
+    /// The issuer of the token.
+    pub iss: String,
+
+    /// The unique identifier of the user the token was issued for.
+    pub sub: String,
+
+    /// The client or clients the token was issued to.
+    pub aud: Audience,
+
+    /// When the token expires, in seconds since the Unix epoch.
+    pub exp: u64,
+
+    /// When the token was issued, in seconds since the Unix epoch.
+    pub iat: u64,
+
+    /// The nonce from the authentication request, if one was sent.
+    pub nonce: Option<String>,
+
+    /// The user's email address, if the `email` scope was granted.
+    pub email: Option<String>,
+
+    /// The user's full name, if the `profile` scope was granted.
+    pub name: Option<String>,
+}
+
+/// The `aud` claim of a token, which may be a single client or a list of
+/// clients.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum Audience {
+    /// A token issued to a single client.
+    Single(String),
+
+    /// A token issued to several clients.
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    /// Whether the token was issued to `client_id`.
+    pub fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::Single(aud) => aud == client_id,
+            Audience::Multiple(auds) => auds.iter().any(|aud| aud == client_id),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedKeys {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// An OpenID Connect provider whose ID tokens can be validated.
+#[derive(Debug)]
+pub struct OidcProvider<S> {
+    service: S,
+    config: OpenIdConfiguration,
+    keys: RwLock<Option<CachedKeys>>,
+    key_ttl: Duration,
+    min_refresh_interval: Duration,
+    leeway: Duration,
+}
+
+impl<S: HttpGet + Sync> OidcProvider<S> {
+    /// Fetches the metadata for the provider identified by `issuer`, using
+    /// `service` to make requests.
+    ///
+    /// Returns an error if the metadata cannot be fetched, or if the issuer
+    /// in the metadata does not match `issuer`, as required by the OpenID
+    /// Connect Discovery specification.
+    pub async fn discover(service: S, issuer: &str) -> HttpResult<Self> {
+        let config = well_known::openid_configuration(&service, issuer).await?;
+        if config.issuer.trim_end_matches('/') != issuer.trim_end_matches('/') {
+            return Err(HttpError::InvalidToken(format!(
+                "provider metadata is for issuer {}, not {issuer}",
+                config.issuer
+            )));
+        }
+        Ok(Self::with_configuration(service, config))
+    }
+
+    /// Creates a provider from metadata that has already been fetched.
+    pub fn with_configuration(service: S, config: OpenIdConfiguration) -> Self {
+        Self {
+            service,
+            config,
+            keys: RwLock::new(None),
+            key_ttl: Duration::from_secs(60 * 60),
+            min_refresh_interval: Duration::from_secs(60),
+            leeway: Duration::from_secs(60),
+        }
+    }
+
+    /// Sets how long signing keys are cached before they are refetched.
+    ///
+    /// The default is one hour.
+    pub fn with_key_ttl(mut self, ttl: Duration) -> Self {
+        self.key_ttl = ttl;
+        self
+    }
+
+    /// Sets how much clock skew is tolerated when checking the token's
+    /// expiration time.
+    ///
+    /// The default is one minute.
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// The provider's metadata.
+    pub fn configuration(&self) -> &OpenIdConfiguration {
+        &self.config
+    }
+
+    /// Validates an ID token that was issued to `client_id` and returns its
+    /// claims.
+    ///
+    /// The token's signature, issuer, audience, and expiration time are all
+    /// checked. Only asymmetric signing algorithms that the provider
+    /// advertises are accepted.
+    pub async fn validate<C>(&self, token: &str, client_id: &str) -> HttpResult<C>
+    where
+        C: DeserializeOwned,
+    {
+        let header = jsonwebtoken::decode_header(token).map_err(invalid_token)?;
+        self.check_algorithm(header.alg)?;
+
+        let kid = header.kid.unwrap_or_default();
+        let key = match self.find_key(&kid, false).await? {
+            Some(key) => key,
+            // The provider may have rotated in a new key since we last
+            // fetched them.
+            None => self
+                .find_key(&kid, true)
+                .await?
+                .ok_or_else(|| HttpError::InvalidToken(format!("unknown signing key: {kid}")))?,
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[client_id]);
+        validation.leeway = self.leeway.as_secs();
+        let data = jsonwebtoken::decode::<C>(token, &key, &validation).map_err(invalid_token)?;
+        Ok(data.claims)
+    }
+
+    /// Fetches the provider's signing keys, replacing any cached keys.
+    pub async fn refresh_keys(&self) -> HttpResult<()> {
+        let body = self.service.get(self.config.jwks_uri.as_str()).await?;
+        let keys = decode::json(&body)?;
+        let fetched_at = Instant::now();
+        *self.keys.write().unwrap() = Some(CachedKeys { keys, fetched_at });
+        Ok(())
+    }
+
+    fn check_algorithm(&self, alg: Algorithm) -> HttpResult<()> {
+        let symmetric = matches!(alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512);
+        let supported = &self.config.id_token_signing_alg_values_supported;
+        let advertised = supported.is_empty() || supported.iter().any(|a| a == &format!("{alg:?}"));
+        if symmetric || !advertised {
+            Err(HttpError::InvalidToken(format!(
+                "unsupported signing algorithm: {alg:?}"
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn find_key(&self, kid: &str, force_refresh: bool) -> HttpResult<Option<DecodingKey>> {
+        let stale = {
+            let keys = self.keys.read().unwrap();
+            match keys.as_ref() {
+                None => true,
+                Some(cached) => {
+                    let age = cached.fetched_at.elapsed();
+                    age >= self.key_ttl || (force_refresh && age >= self.min_refresh_interval)
+                }
+            }
+        };
+        if stale {
+            self.refresh_keys().await?;
+        }
+
+        let keys = self.keys.read().unwrap();
+        let Some(cached) = keys.as_ref() else {
+            return Ok(None);
+        };
+        let jwk = if kid.is_empty() && cached.keys.keys.len() == 1 {
+            cached.keys.keys.first()
+        } else {
+            cached.keys.find(kid)
+        };
+        jwk.map(DecodingKey::from_jwk)
+            .transpose()
+            .map_err(invalid_token)
+    }
+}
+
+fn invalid_token(err: jsonwebtoken::errors::Error) -> HttpError {
+    HttpError::InvalidToken(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header};
+    use reqwest::{IntoUrl, StatusCode};
+    use serde::Serialize;
+    use std::fs;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[derive(Default)]
+    struct ProviderService {
+        key_fetches: AtomicU32,
+    }
+
+    impl HttpGet for ProviderService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            let path = match uri.as_str() {
+                "https://login.example.com/.well-known/openid-configuration" => {
+                    "tests/data/oidc/openid-configuration.json"
+                }
+                "https://login.example.com/keys" => {
+                    self.key_fetches.fetch_add(1, Ordering::SeqCst);
+                    "tests/data/oidc/jwks.json"
+                }
+                _ => return Err(HttpError::Http(StatusCode::NOT_FOUND)),
+            };
+            Ok(fs::read_to_string(path).expect("could not read test data"))
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Claims<'a> {
+        iss: &'a str,
+        sub: &'a str,
+        aud: &'a str,
+        exp: u64,
+        iat: u64,
+    }
+
+    fn sign(kid: &str, aud: &str, expires_in: i64) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let claims = Claims {
+            iss: "https://login.example.com",
+            sub: "user-1234",
+            aud,
+            exp: now.saturating_add_signed(expires_in),
+            iat: now,
+        };
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(kid.to_string());
+        let pem = fs::read("tests/data/oidc/signing-key.pem").unwrap();
+        let key = EncodingKey::from_ec_pem(&pem).unwrap();
+        jsonwebtoken::encode(&header, &claims, &key).unwrap()
+    }
+
+    async fn provider() -> OidcProvider<ProviderService> {
+        OidcProvider::discover(ProviderService::default(), "https://login.example.com")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_validates_id_tokens() -> HttpResult<()> {
+        let provider = provider().await;
+        let token = sign("test-key", "my-client", 300);
+        let claims: IdTokenClaims = provider.validate(&token, "my-client").await?;
+        assert_eq!(claims.sub, "user-1234");
+        assert!(claims.aud.contains("my-client"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_caches_signing_keys() -> HttpResult<()> {
+        let provider = provider().await;
+        let token = sign("test-key", "my-client", 300);
+        let _: IdTokenClaims = provider.validate(&token, "my-client").await?;
+        let _: IdTokenClaims = provider.validate(&token, "my-client").await?;
+        assert_eq!(provider.service.key_fetches.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_rejects_tokens_for_other_clients() {
+        let provider = provider().await;
+        let token = sign("test-key", "someone-else", 300);
+        let result: HttpResult<IdTokenClaims> = provider.validate(&token, "my-client").await;
+        assert!(matches!(result, Err(HttpError::InvalidToken(_))));
+    }
+
+    #[tokio::test]
+    async fn it_rejects_expired_tokens() {
+        let provider = provider().await;
+        let token = sign("test-key", "my-client", -3600);
+        let result: HttpResult<IdTokenClaims> = provider.validate(&token, "my-client").await;
+        assert!(matches!(result, Err(HttpError::InvalidToken(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_refetches_keys_when_it_sees_an_unknown_key_id() {
+        let provider = provider().await;
+        let _: HttpResult<IdTokenClaims> = provider
+            .validate(&sign("test-key", "my-client", 300), "my-client")
+            .await;
+        tokio::time::advance(Duration::from_secs(120)).await;
+        let result: HttpResult<IdTokenClaims> = provider
+            .validate(&sign("rotated-key", "my-client", 300), "my-client")
+            .await;
+        assert!(matches!(result, Err(HttpError::InvalidToken(msg)) if msg.contains("rotated-key")));
+        assert_eq!(provider.service.key_fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn it_rejects_mismatched_issuers() {
+        let result = OidcProvider::discover(
+            ProviderService::default(),
+            "https://login.example.com/other",
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Secrets that are kept out of logs and memory.
+//!
+//! A [`Secret`] holds a credential, such as an API key, that should not be
+//! seen by anything but the request it authenticates. Its [`Debug`] and
+//! [`Display`] output is always `****`, so it cannot leak into logs by way
+//! of a struct that contains it, and its memory is overwritten with zeros
+//! when it is dropped. The value can only be read with
+//! [`expose()`](Secret::expose), which makes every use of it easy to find.
+//!
+//! [`Debug`]: fmt::Debug
+//! [`Display`]: fmt::Display
+
+use std::fmt;
+use zeroize::Zeroize;
+
+/// A credential that is redacted when formatted and zeroized when
+/// dropped.
+///
+/// # Examples
+///
+/// ```
+/// use hypertyper::auth::secret::Secret;
+///
+/// let secret = Secret::new("ThisIsMyApiKey");
+/// assert_eq!(format!("{secret:?}"), "****");
+/// assert_eq!(secret.expose(), "ThisIsMyApiKey");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wraps `value`.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The secret value.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("****")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("****")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_redacts_secrets() {
+        let secret = Secret::from("hunter2");
+        assert_eq!(format!("{secret:?}"), "****");
+        assert_eq!(secret.to_string(), "****");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some(****)");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+}
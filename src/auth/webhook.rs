@@ -0,0 +1,443 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Verification of inbound webhook signatures.
+//!
+//! APIs that call back into an application with webhooks sign each
+//! delivery with an HMAC of its body, keyed with a secret shared with the
+//! application, so that the application can tell real deliveries from
+//! forged ones. A [`WebhookVerifier`] checks a delivery's signature before
+//! its body is trusted. Two common schemes are supported:
+//!
+//! - [`StripeVerifier`] checks signatures in the style of Stripe's
+//!   `Stripe-Signature` header, `t=1492774577,v1=5257a869...`, which signs
+//!   the timestamp along with the body. Deliveries whose timestamp is more
+//!   than a [tolerance](StripeVerifier::with_tolerance) away from the
+//!   current time are rejected, so a captured delivery cannot be replayed
+//!   later.
+//! - [`GitHubVerifier`] checks signatures in the style of GitHub's
+//!   `X-Hub-Signature-256` header, `sha256=757107ea...`, which signs only
+//!   the body.
+//!
+//! Signatures are compared in constant time, so a failed comparison does
+//! not reveal how much of a forged signature was correct. Deliveries that
+//! fail verification are rejected with [`HttpError::InvalidSignature`].
+//!
+//! The body must be verified exactly as it was received: parsing and
+//! reserializing JSON changes its bytes, and with them the signature.
+//!
+//! This is only available when the **hmac** feature is enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::HttpResult;
+//! use hypertyper::auth::webhook::{GitHubVerifier, WebhookVerifier};
+//! use reqwest::header::HeaderMap;
+//!
+//! fn handle(headers: &HeaderMap, body: &[u8]) -> HttpResult<()> {
+//!     let verifier = GitHubVerifier::new("It's a Secret to Everybody");
+//!     verifier.verify(headers, body)?;
+//!     // The body can be trusted.
+//!     Ok(())
+//! }
+//! ```
+
+use crate::auth::hmac::HmacAlgorithm;
+use crate::{HttpError, HttpResult};
+use reqwest::header::{HeaderMap, HeaderName};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How far a Stripe-style signature's timestamp may be from the current
+/// time, by default.
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+/// Verifies the signatures of webhook deliveries.
+pub trait WebhookVerifier {
+    /// The header that carries the signature.
+    fn header(&self) -> HeaderName;
+
+    /// Verifies `signature`, the value of the signature header, against
+    /// `body`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::InvalidSignature`] if the signature is
+    /// malformed or does not match `body`.
+    fn verify_signature(&self, signature: &str, body: &[u8]) -> HttpResult<()>;
+
+    /// Verifies the signature in a delivery's `headers` against its
+    /// `body`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::InvalidSignature`] if the signature header is
+    /// missing, or if [`verify_signature()`] fails.
+    ///
+    /// [`verify_signature()`]: WebhookVerifier::verify_signature
+    fn verify(&self, headers: &HeaderMap, body: &[u8]) -> HttpResult<()> {
+        let header = self.header();
+        let signature = headers
+            .get(&header)
+            .ok_or_else(|| invalid(format!("missing {header} header")))?
+            .to_str()
+            .map_err(|_| invalid(format!("{header} header is not text")))?;
+        self.verify_signature(signature, body)
+    }
+}
+
+/// Verifies Stripe-style signatures, which sign a timestamp along with the
+/// body.
+///
+/// The signature header lists the signing time and one or more
+/// signatures, as in `t=1492774577,v1=5257a869...,v1=6ffbb59b...`, so that
+/// signatures from an old and a new secret can be sent while the secret is
+/// rotated. The signed message is the timestamp, a period, and the body.
+///
+/// # Examples
+///
+/// ```
+/// use hypertyper::auth::webhook::{StripeVerifier, WebhookVerifier};
+/// use std::time::{Duration, SystemTime};
+///
+/// let verifier = StripeVerifier::new("whsec_test").with_tolerance(Duration::from_secs(60));
+/// let body = br#"{"id":"evt_test"}"#;
+/// let signature = verifier.sign_at(body, SystemTime::now());
+/// assert!(verifier.verify_signature(&signature, body).is_ok());
+/// assert!(verifier.verify_signature(&signature, b"{}").is_err());
+/// ```
+#[derive(Clone)]
+pub struct StripeVerifier {
+    secret: Vec<u8>,
+    header: HeaderName,
+    scheme: String,
+    algorithm: HmacAlgorithm,
+    tolerance: Duration,
+}
+
+impl StripeVerifier {
+    /// Verifies signatures made with `secret`.
+    ///
+    /// Signatures are read from the `Stripe-Signature` header, and must be
+    /// HMAC-SHA256 signatures under the `v1` scheme whose timestamp is
+    /// within the [default tolerance](DEFAULT_TOLERANCE).
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            header: HeaderName::from_static("stripe-signature"),
+            scheme: String::from("v1"),
+            algorithm: HmacAlgorithm::Sha256,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Reads signatures from `header` instead.
+    pub fn with_header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Only accepts signatures under `scheme` instead of `v1`.
+    pub fn with_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.scheme = scheme.into();
+        self
+    }
+
+    /// Verifies signatures made with `algorithm` instead.
+    pub fn with_algorithm(mut self, algorithm: HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Rejects signatures whose timestamp is more than `tolerance` from
+    /// the current time.
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// How far a signature's timestamp may be from the current time.
+    pub fn tolerance(&self) -> Duration {
+        self.tolerance
+    }
+
+    /// The signature header that would be sent with `body` at `time`.
+    ///
+    /// This is mostly useful for testing webhook handlers.
+    pub fn sign_at(&self, body: &[u8], time: SystemTime) -> String {
+        let timestamp = seconds(time);
+        let signature = self.algorithm.sign(&self.secret, &message(timestamp, body));
+        format!("t={timestamp},{}={}", self.scheme, hex::encode(signature))
+    }
+
+    /// Verifies `signature` against `body` as though it were received at
+    /// `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::InvalidSignature`] if the signature is
+    /// malformed, its timestamp is not within the tolerance of `now`, or
+    /// none of its signatures under the expected scheme match `body`.
+    pub fn verify_at(&self, signature: &str, body: &[u8], now: SystemTime) -> HttpResult<()> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for (key, value) in signature.split(',').filter_map(|item| item.split_once('=')) {
+            match key.trim() {
+                "t" => timestamp = Some(value.trim()),
+                scheme if scheme == self.scheme => signatures.push(value.trim()),
+                _ => {}
+            }
+        }
+
+        let timestamp: u64 = timestamp
+            .ok_or_else(|| invalid("signature has no timestamp"))?
+            .parse()
+            .map_err(|_| invalid("signature timestamp is not a number"))?;
+        if seconds(now).abs_diff(timestamp) > self.tolerance.as_secs() {
+            return Err(invalid(format!(
+                "signature timestamp {timestamp} is outside the tolerance of {:?}",
+                self.tolerance
+            )));
+        }
+        if signatures.is_empty() {
+            return Err(invalid(format!(
+                "signature has no {} signatures",
+                self.scheme
+            )));
+        }
+
+        let message = message(timestamp, body);
+        let verified = signatures.iter().any(|signature| {
+            hex::decode(signature)
+                .is_ok_and(|signature| self.algorithm.verify(&self.secret, &message, &signature))
+        });
+        if verified {
+            Ok(())
+        } else {
+            Err(invalid("signature does not match the body"))
+        }
+    }
+}
+
+impl WebhookVerifier for StripeVerifier {
+    fn header(&self) -> HeaderName {
+        self.header.clone()
+    }
+
+    fn verify_signature(&self, signature: &str, body: &[u8]) -> HttpResult<()> {
+        self.verify_at(signature, body, SystemTime::now())
+    }
+}
+
+impl fmt::Debug for StripeVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StripeVerifier")
+            .field("header", &self.header)
+            .field("scheme", &self.scheme)
+            .field("algorithm", &self.algorithm)
+            .field("tolerance", &self.tolerance)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Verifies GitHub-style signatures, which sign only the body.
+///
+/// The signature header is the name of the hash function followed by the
+/// signature in hex, as in `sha256=757107ea...`.
+///
+/// # Examples
+///
+/// ```
+/// use hypertyper::auth::webhook::{GitHubVerifier, WebhookVerifier};
+///
+/// let verifier = GitHubVerifier::new("It's a Secret to Everybody");
+/// let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+/// assert!(verifier.verify_signature(signature, b"Hello, World!").is_ok());
+/// ```
+#[derive(Clone)]
+pub struct GitHubVerifier {
+    secret: Vec<u8>,
+    header: HeaderName,
+}
+
+impl GitHubVerifier {
+    /// Verifies HMAC-SHA256 signatures made with `secret`, which are read
+    /// from the `X-Hub-Signature-256` header.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            header: HeaderName::from_static("x-hub-signature-256"),
+        }
+    }
+
+    /// Reads signatures from `header` instead.
+    pub fn with_header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// The signature header that would be sent with `body`.
+    ///
+    /// This is mostly useful for testing webhook handlers.
+    pub fn sign(&self, body: &[u8]) -> String {
+        let signature = HmacAlgorithm::Sha256.sign(&self.secret, body);
+        format!("sha256={}", hex::encode(signature))
+    }
+}
+
+impl WebhookVerifier for GitHubVerifier {
+    fn header(&self) -> HeaderName {
+        self.header.clone()
+    }
+
+    fn verify_signature(&self, signature: &str, body: &[u8]) -> HttpResult<()> {
+        let signature = signature
+            .trim()
+            .strip_prefix("sha256=")
+            .ok_or_else(|| invalid("signature is not a sha256 signature"))?;
+        let signature =
+            hex::decode(signature).map_err(|_| invalid("signature is not hexadecimal"))?;
+        if HmacAlgorithm::Sha256.verify(&self.secret, body, &signature) {
+            Ok(())
+        } else {
+            Err(invalid("signature does not match the body"))
+        }
+    }
+}
+
+impl fmt::Debug for GitHubVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GitHubVerifier")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The message that Stripe-style signatures sign.
+fn message(timestamp: u64, body: &[u8]) -> Vec<u8> {
+    let mut message = format!("{timestamp}.").into_bytes();
+    message.extend_from_slice(body);
+    message
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn invalid(message: impl Into<String>) -> HttpError {
+    HttpError::InvalidSignature(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    const BODY: &[u8] = br#"{"id":"evt_test"}"#;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn it_verifies_stripe_signatures() -> HttpResult<()> {
+        let verifier = StripeVerifier::new("whsec_test");
+        let signature = "t=1492774577,\
+                         v1=91756ee38f5c256c6db5893fecc447d8729adb8023535a860e4c2b521391cec9";
+        assert_eq!(verifier.sign_at(BODY, at(1492774577)), signature);
+        verifier.verify_at(signature, BODY, at(1492774600))?;
+        assert!(matches!(
+            verifier.verify_at(signature, b"{}", at(1492774600)),
+            Err(HttpError::InvalidSignature(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn it_accepts_any_matching_stripe_signature() -> HttpResult<()> {
+        let verifier = StripeVerifier::new("whsec_test");
+        let signature = format!(
+            "t=1492774577,v1={},v0=ignored,{}",
+            "00".repeat(32),
+            verifier
+                .sign_at(BODY, at(1492774577))
+                .split_once(',')
+                .unwrap()
+                .1
+        );
+        verifier.verify_at(&signature, BODY, at(1492774577))
+    }
+
+    #[test]
+    fn it_rejects_stale_and_future_timestamps() {
+        let verifier = StripeVerifier::new("whsec_test").with_tolerance(Duration::from_secs(60));
+        let signature = verifier.sign_at(BODY, at(1_000_000));
+        assert!(verifier.verify_at(&signature, BODY, at(1_000_060)).is_ok());
+        assert!(verifier.verify_at(&signature, BODY, at(1_000_061)).is_err());
+        assert!(verifier.verify_at(&signature, BODY, at(999_939)).is_err());
+    }
+
+    #[test]
+    fn it_rejects_malformed_stripe_signatures() {
+        let verifier = StripeVerifier::new("whsec_test");
+        let now = at(1492774577);
+        for signature in [
+            "",
+            "v1=abcd",
+            "t=soon,v1=abcd",
+            "t=1492774577",
+            "t=1492774577,v1=zz",
+        ] {
+            assert!(
+                matches!(
+                    verifier.verify_at(signature, BODY, now),
+                    Err(HttpError::InvalidSignature(_))
+                ),
+                "{signature:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_verifies_github_signatures() -> HttpResult<()> {
+        let verifier = GitHubVerifier::new("It's a Secret to Everybody");
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert_eq!(verifier.sign(b"Hello, World!"), signature);
+        verifier.verify_signature(signature, b"Hello, World!")?;
+        assert!(
+            verifier
+                .verify_signature(signature, b"Hello, World?")
+                .is_err()
+        );
+        assert!(
+            verifier
+                .verify_signature("sha1=abcd", b"Hello, World!")
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_signatures_from_headers() -> HttpResult<()> {
+        let verifier = GitHubVerifier::new("It's a Secret to Everybody");
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            verifier.verify(&headers, b"Hello, World!"),
+            Err(HttpError::InvalidSignature(message)) if message.contains("x-hub-signature-256")
+        ));
+        let signature = HeaderValue::try_from(verifier.sign(b"Hello, World!"))?;
+        headers.insert("x-hub-signature-256", signature);
+        verifier.verify(&headers, b"Hello, World!")
+    }
+
+    #[test]
+    fn it_does_not_show_secrets() {
+        let stripe = format!("{:?}", StripeVerifier::new("whsec_test"));
+        let github = format!("{:?}", GitHubVerifier::new("hunter2"));
+        assert!(!stripe.contains("whsec_test"));
+        assert!(!github.contains("hunter2"));
+    }
+}
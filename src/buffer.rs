@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Reusable buffers for response bodies.
+//!
+//! Reading a response body into memory allocates a buffer that grows as
+//! the body arrives and is freed as soon as the body has been processed.
+//! For a client making thousands of requests a second with large bodies,
+//! that churn keeps the allocator busy. A [`BufferPool`] keeps buffers
+//! around after they are dropped, so that the next body can be read into
+//! memory that has already been allocated, and has likely already grown to
+//! a suitable size.
+//!
+//! Bodies are read into pooled buffers with
+//! [`BodyStream::read_pooled()`]. The [`PooledBuffer`] that is returned
+//! goes back to the pool when it is dropped.
+//!
+//! The pool is bounded in two ways: it holds at most
+//! [`max_buffers()`](BufferPool::max_buffers) idle buffers, and buffers
+//! that have grown beyond [`max_capacity()`](BufferPool::max_capacity)
+//! are freed rather than kept, so that one unusually large body does not
+//! pin its memory forever. [`stats()`](BufferPool::stats) reports how often
+//! buffers were reused, which helps with choosing those limits.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::buffer::BufferPool;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetStream;
+//!
+//! # async fn run<S: HttpGetStream>(service: S) -> HttpResult<()> {
+//! // Keep up to 16 idle buffers of up to 4 MiB each.
+//! let pool = BufferPool::new(16).with_max_capacity(4 * 1024 * 1024);
+//! for page in 1..=100 {
+//!     let body = service
+//!         .get_stream(format!("https://api.example.com/items?page={page}"))
+//!         .await?
+//!         .read_pooled(&pool)
+//!         .await?;
+//!     println!("Page {page} has {} bytes", body.len());
+//! }
+//! println!("Reused {} buffers", pool.stats().reuses);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[cfg(doc)]
+use crate::service::BodyStream;
+
+/// The capacity of buffers allocated by a [`BufferPool`] by default.
+pub const DEFAULT_INITIAL_CAPACITY: usize = 8 * 1024;
+
+/// The capacity beyond which buffers are not returned to a [`BufferPool`]
+/// by default.
+pub const DEFAULT_MAX_CAPACITY: usize = 1024 * 1024;
+
+/// A pool of buffers that can be reused to read response bodies.
+///
+/// Clones of a pool share the same buffers.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone)]
+pub struct BufferPool {
+    shared: Arc<Shared>,
+    max_buffers: usize,
+    initial_capacity: usize,
+    max_capacity: usize,
+}
+
+#[derive(Default)]
+struct Shared {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    allocations: AtomicU64,
+    reuses: AtomicU64,
+    discards: AtomicU64,
+}
+
+impl BufferPool {
+    /// Creates a pool that keeps up to `max_buffers` idle buffers.
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            shared: Arc::default(),
+            max_buffers,
+            initial_capacity: DEFAULT_INITIAL_CAPACITY,
+            max_capacity: DEFAULT_MAX_CAPACITY,
+        }
+    }
+
+    /// Sets the capacity of newly allocated buffers.
+    pub fn with_initial_capacity(mut self, initial_capacity: usize) -> Self {
+        self.initial_capacity = initial_capacity;
+        self
+    }
+
+    /// Sets the capacity beyond which buffers are freed instead of being
+    /// returned to the pool.
+    pub fn with_max_capacity(mut self, max_capacity: usize) -> Self {
+        self.max_capacity = max_capacity;
+        self
+    }
+
+    /// The maximum number of idle buffers in the pool.
+    pub fn max_buffers(&self) -> usize {
+        self.max_buffers
+    }
+
+    /// The capacity of newly allocated buffers.
+    pub fn initial_capacity(&self) -> usize {
+        self.initial_capacity
+    }
+
+    /// The capacity beyond which buffers are freed instead of being
+    /// returned to the pool.
+    pub fn max_capacity(&self) -> usize {
+        self.max_capacity
+    }
+
+    /// Takes an empty buffer from the pool, allocating a new one if the
+    /// pool has none.
+    pub fn get(&self) -> PooledBuffer {
+        let buffer = self.shared.buffers.lock().unwrap().pop();
+        let buffer = match buffer {
+            Some(buffer) => {
+                self.shared.reuses.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.shared.allocations.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(self.initial_capacity)
+            }
+        };
+        PooledBuffer {
+            buffer,
+            pool: Some(self.clone()),
+        }
+    }
+
+    /// How the pool has been used since it was created.
+    pub fn stats(&self) -> BufferPoolStats {
+        BufferPoolStats {
+            allocations: self.shared.allocations.load(Ordering::Relaxed),
+            reuses: self.shared.reuses.load(Ordering::Relaxed),
+            discards: self.shared.discards.load(Ordering::Relaxed),
+            idle: self.shared.buffers.lock().unwrap().len(),
+        }
+    }
+
+    fn put(&self, mut buffer: Vec<u8>) {
+        if buffer.capacity() <= self.max_capacity {
+            let mut buffers = self.shared.buffers.lock().unwrap();
+            if buffers.len() < self.max_buffers {
+                buffer.clear();
+                buffers.push(buffer);
+                return;
+            }
+        }
+        self.shared.discards.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Default for BufferPool {
+    /// Creates a pool that keeps up to 32 idle buffers.
+    fn default() -> Self {
+        Self::new(32)
+    }
+}
+
+impl fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("max_buffers", &self.max_buffers)
+            .field("initial_capacity", &self.initial_capacity)
+            .field("max_capacity", &self.max_capacity)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// How a [`BufferPool`] has been used since it was created.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BufferPoolStats {
+    /// Buffers that had to be allocated because the pool was empty.
+    pub allocations: u64,
+
+    /// Buffers that were taken from the pool instead of being allocated.
+    pub reuses: u64,
+
+    /// Buffers that were freed instead of being returned to the pool,
+    /// because the pool was full or they had grown too large.
+    pub discards: u64,
+
+    /// The number of idle buffers in the pool.
+    pub idle: usize,
+}
+
+/// A buffer taken from a [`BufferPool`], which returns to the pool when it
+/// is dropped.
+///
+/// A pooled buffer dereferences to a `Vec<u8>`.
+pub struct PooledBuffer {
+    buffer: Vec<u8>,
+    pool: Option<BufferPool>,
+}
+
+impl PooledBuffer {
+    /// Takes the buffer out of the pool for good.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        self.pool = None;
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl AsRef<[u8]> for PooledBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.put(std::mem::take(&mut self.buffer));
+        }
+    }
+}
+
+impl fmt::Debug for PooledBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledBuffer")
+            .field("len", &self.buffer.len())
+            .field("capacity", &self.buffer.capacity())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpResult;
+    use crate::service::BodyStream;
+    use bytes::Bytes;
+    use futures_util::stream;
+
+    #[test]
+    fn it_reuses_buffers() {
+        let pool = BufferPool::new(2).with_initial_capacity(16);
+        let mut buffer = pool.get();
+        assert_eq!(buffer.capacity(), 16);
+        buffer.extend_from_slice(&[1; 100]);
+        let ptr = buffer.as_ptr();
+        drop(buffer);
+
+        let buffer = pool.get();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 100);
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(
+            pool.stats(),
+            BufferPoolStats {
+                allocations: 1,
+                reuses: 1,
+                discards: 0,
+                idle: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn it_bounds_the_pool() {
+        let pool = BufferPool::new(1)
+            .with_initial_capacity(16)
+            .with_max_capacity(64);
+        let mut large = pool.get();
+        large.reserve(1024);
+        let small = [pool.get(), pool.get()];
+        drop(large);
+        drop(small);
+        assert_eq!(pool.stats().discards, 2);
+        assert_eq!(pool.stats().idle, 1);
+
+        let detached = pool.get().into_vec();
+        drop(detached);
+        assert_eq!(pool.stats().idle, 0);
+    }
+
+    #[tokio::test]
+    async fn it_reads_bodies_into_pooled_buffers() -> HttpResult<()> {
+        let pool = BufferPool::new(4);
+        for _ in 0..3 {
+            let chunks = ["hello, ", "world"].map(|chunk| Ok(Bytes::from(chunk)));
+            let body = BodyStream::new(stream::iter(chunks))
+                .read_pooled(&pool)
+                .await?;
+            assert_eq!(&body[..], b"hello, world");
+        }
+        assert_eq!(pool.stats().allocations, 1);
+        assert_eq!(pool.stats().reuses, 2);
+        Ok(())
+    }
+    #[tokio::test]
+    async fn it_does_not_trust_huge_content_lengths() -> HttpResult<()> {
+        let pool = BufferPool::new(4).with_max_capacity(64);
+        let chunks = ["hello"].map(|chunk| Ok(Bytes::from(chunk)));
+        let body = BodyStream::new(stream::iter(chunks))
+            .with_content_length(Some(u64::MAX))
+            .read_pooled(&pool)
+            .await?;
+        assert_eq!(&body[..], b"hello");
+        assert!(body.capacity() <= DEFAULT_INITIAL_CAPACITY.max(64));
+        Ok(())
+    }
+}
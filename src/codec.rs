@@ -0,0 +1,538 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Content negotiation between body formats.
+//!
+//! Some APIs can answer in more than one format, and choose one based on
+//! the `Accept` header of the request. A [`Codec`] encodes and decodes
+//! bodies in one format, and a [`Negotiator`] holds a set of codecs in
+//! order of preference. It advertises all of them in the `Accept` header,
+//! then decodes the response with whichever codec matches its
+//! `Content-Type`, failing with [`HttpError::UnexpectedContentType`], which
+//! lists the acceptable types, if none does.
+//!
+//! Codecs are grouped in tuples, so negotiation is resolved statically
+//! rather than through trait objects:
+//!
+//! ```no_run
+//! use hypertyper::codec::{JsonCodec, Negotiator};
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpBinary;
+//! # #[cfg(feature = "xml")]
+//! use hypertyper::codec::XmlCodec;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Report {
+//!     total: u64,
+//! }
+//!
+//! # #[cfg(feature = "xml")]
+//! # async fn run<S: HttpBinary + Sync>(service: S) -> HttpResult<()> {
+//! // Prefer JSON, but accept XML.
+//! let negotiator = Negotiator::new((JsonCodec, XmlCodec));
+//! let report: Report = negotiator.get(&service, "https://api.example.com/report").await?;
+//! println!("{} total", report.total);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Hypertyper provides codecs for JSON, and for XML, MessagePack, and CBOR
+//! when the **xml**, **msgpack**, and **cbor** features are enabled. Other
+//! formats can be negotiated by implementing [`Codec`].
+
+use crate::decode::{self, DEFAULT_SNIPPET_LEN};
+use crate::service::{BinaryResponse, HttpBinary};
+use crate::{HttpError, HttpResult};
+use reqwest::IntoUrl;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Encodes and decodes bodies in a single format.
+pub trait Codec {
+    /// The content type of the bodies this codec encodes, such as
+    /// `application/json`.
+    fn content_type(&self) -> &'static str;
+
+    /// Whether this codec can decode bodies of `content_type`, which is the
+    /// value of a `Content-Type` header.
+    ///
+    /// By default, this compares the media type of `content_type`, without
+    /// its parameters, to [`content_type()`](Self::content_type).
+    fn matches(&self, content_type: &str) -> bool {
+        media_type(content_type).eq_ignore_ascii_case(self.content_type())
+    }
+
+    /// Encodes `value` as a body.
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<Vec<u8>>;
+
+    /// Decodes `body` as a `T`.
+    fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> HttpResult<T>;
+}
+
+/// The media type of a `Content-Type` header value, without parameters
+/// such as `charset`.
+fn media_type(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or_default().trim()
+}
+
+/// Whether `content_type` is `subtype` or uses it as a structured syntax
+/// suffix, as `application/problem+json` does for `json`.
+fn has_subtype(content_type: &str, subtype: &str) -> bool {
+    let media_type = media_type(content_type).to_ascii_lowercase();
+    let Some((_, rest)) = media_type.split_once('/') else {
+        return false;
+    };
+    rest == subtype || rest.ends_with(&format!("+{subtype}"))
+}
+
+/// A codec for JSON bodies.
+///
+/// This matches `application/json` and any media type with a `+json`
+/// suffix, such as `application/problem+json`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
+
+    fn matches(&self, content_type: &str) -> bool {
+        has_subtype(content_type, "json")
+    }
+
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<Vec<u8>> {
+        Ok(crate::timing::to_vec(value)?)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> HttpResult<T> {
+        match std::str::from_utf8(body) {
+            Ok(body) => decode::json(body),
+            // JSON must be UTF-8; let serde_json report where it is not.
+            Err(_) => serde_json::from_slice(body).map_err(|source| HttpError::Decode {
+                source,
+                path: None,
+                snippet: String::from_utf8_lossy(&body[..body.len().min(DEFAULT_SNIPPET_LEN)])
+                    .into_owned(),
+            }),
+        }
+    }
+}
+
+/// A codec for XML bodies.
+///
+/// This matches `application/xml`, `text/xml`, and any media type with an
+/// `+xml` suffix. See [`decode::xml`] for details.
+#[cfg(feature = "xml")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XmlCodec;
+
+#[cfg(feature = "xml")]
+impl Codec for XmlCodec {
+    fn content_type(&self) -> &'static str {
+        decode::xml::CONTENT_TYPE
+    }
+
+    fn matches(&self, content_type: &str) -> bool {
+        has_subtype(content_type, "xml")
+    }
+
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<Vec<u8>> {
+        Ok(decode::xml::to_string(value)?.into_bytes())
+    }
+
+    fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> HttpResult<T> {
+        decode::xml::from_str(&String::from_utf8_lossy(body))
+    }
+}
+
+/// A codec for MessagePack bodies.
+///
+/// This matches `application/msgpack`, as well as the older
+/// `application/x-msgpack` and `application/vnd.msgpack`. See
+/// [`decode::msgpack`] for details.
+#[cfg(feature = "msgpack")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MsgPackCodec;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MsgPackCodec {
+    fn content_type(&self) -> &'static str {
+        decode::msgpack::CONTENT_TYPE
+    }
+
+    fn matches(&self, content_type: &str) -> bool {
+        ["msgpack", "x-msgpack", "vnd.msgpack"]
+            .iter()
+            .any(|subtype| has_subtype(content_type, subtype))
+    }
+
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<Vec<u8>> {
+        decode::msgpack::to_vec(value)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> HttpResult<T> {
+        decode::msgpack::from_slice(body)
+    }
+}
+
+/// A codec for CBOR bodies.
+///
+/// This matches `application/cbor` and any media type with a `+cbor`
+/// suffix. See [`decode::cbor`] for details.
+#[cfg(feature = "cbor")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl Codec for CborCodec {
+    fn content_type(&self) -> &'static str {
+        decode::cbor::CONTENT_TYPE
+    }
+
+    fn matches(&self, content_type: &str) -> bool {
+        has_subtype(content_type, "cbor")
+    }
+
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<Vec<u8>> {
+        decode::cbor::to_vec(value)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> HttpResult<T> {
+        decode::cbor::from_slice(body)
+    }
+}
+
+/// A set of codecs in order of preference.
+///
+/// This is implemented for single codecs and for tuples of up to eight
+/// codecs.
+pub trait CodecSet {
+    /// The content types of the codecs, in order of preference.
+    fn content_types(&self) -> Vec<&'static str>;
+
+    /// Encodes `value` with the preferred codec, returning its content type
+    /// along with the body.
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<(&'static str, Vec<u8>)>;
+
+    /// Decodes `body` with the first codec that matches `content_type`, or
+    /// returns `None` if no codec does.
+    fn decode<T: DeserializeOwned>(&self, content_type: &str, body: &[u8])
+    -> Option<HttpResult<T>>;
+}
+
+impl<C: Codec> CodecSet for C {
+    fn content_types(&self) -> Vec<&'static str> {
+        vec![self.content_type()]
+    }
+
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<(&'static str, Vec<u8>)> {
+        Ok((self.content_type(), Codec::encode(self, value)?))
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &self,
+        content_type: &str,
+        body: &[u8],
+    ) -> Option<HttpResult<T>> {
+        self.matches(content_type)
+            .then(|| Codec::decode(self, body))
+    }
+}
+
+macro_rules! codec_set {
+    ($($codec:ident),+) => {
+        impl<$($codec: Codec),+> CodecSet for ($($codec,)+) {
+            fn content_types(&self) -> Vec<&'static str> {
+                #[allow(non_snake_case)]
+                let ($($codec,)+) = self;
+                vec![$($codec.content_type()),+]
+            }
+
+            fn encode<T: Serialize + ?Sized>(
+                &self,
+                value: &T,
+            ) -> HttpResult<(&'static str, Vec<u8>)> {
+                CodecSet::encode(&self.0, value)
+            }
+
+            fn decode<T: DeserializeOwned>(
+                &self,
+                content_type: &str,
+                body: &[u8],
+            ) -> Option<HttpResult<T>> {
+                #[allow(non_snake_case)]
+                let ($($codec,)+) = self;
+                $(
+                    if $codec.matches(content_type) {
+                        return Some(Codec::decode($codec, body));
+                    }
+                )+
+                None
+            }
+        }
+    };
+}
+
+codec_set!(A);
+codec_set!(A, B);
+codec_set!(A, B, C);
+codec_set!(A, B, C, D);
+codec_set!(A, B, C, D, E);
+codec_set!(A, B, C, D, E, F);
+codec_set!(A, B, C, D, E, F, G);
+codec_set!(A, B, C, D, E, F, G, H);
+
+/// Negotiates the format of request and response bodies between a set of
+/// codecs.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone, Debug, Default)]
+pub struct Negotiator<C> {
+    codecs: C,
+}
+
+impl<C: CodecSet> Negotiator<C> {
+    /// Creates a negotiator that prefers `codecs` in the given order.
+    pub fn new(codecs: C) -> Self {
+        Self { codecs }
+    }
+
+    /// The codecs, in order of preference.
+    pub fn codecs(&self) -> &C {
+        &self.codecs
+    }
+
+    /// The value of an `Accept` header that accepts every codec, with
+    /// quality values that decrease in order of preference.
+    pub fn accept(&self) -> HeaderValue {
+        let ranges: Vec<_> = self
+            .codecs
+            .content_types()
+            .into_iter()
+            .enumerate()
+            .map(|(i, content_type)| match i {
+                0 => content_type.to_string(),
+                i => format!("{content_type};q=0.{}", 10usize.saturating_sub(i).max(1)),
+            })
+            .collect();
+        // Content types are static strings chosen by codecs, so this only
+        // fails for a codec with an invalid content type.
+        HeaderValue::from_str(&ranges.join(", ")).expect("invalid codec content type")
+    }
+
+    /// Decodes a body with the codec that matches its `content_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::UnexpectedContentType`] if no codec matches.
+    pub fn decode<T: DeserializeOwned>(&self, content_type: &str, body: &[u8]) -> HttpResult<T> {
+        self.codecs.decode(content_type, body).unwrap_or_else(|| {
+            Err(HttpError::UnexpectedContentType {
+                content_type: content_type.to_string(),
+                acceptable: self
+                    .codecs
+                    .content_types()
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            })
+        })
+    }
+
+    /// Decodes the body of `response` with the codec that matches its
+    /// `Content-Type` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::MissingContentType`] if the response has no
+    /// `Content-Type` header, and [`HttpError::UnexpectedContentType`] if no
+    /// codec matches it.
+    pub fn decode_response<T: DeserializeOwned>(&self, response: &BinaryResponse) -> HttpResult<T> {
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .ok_or(HttpError::MissingContentType)?
+            .to_str()?;
+        self.decode(content_type, response.body())
+    }
+
+    /// Sends a GET request to `uri` that accepts every codec, and decodes
+    /// the response with the codec that matches it.
+    pub async fn get<S, U, T>(&self, service: &S, uri: U) -> HttpResult<T>
+    where
+        S: HttpBinary + Sync,
+        U: IntoUrl + Send,
+        T: DeserializeOwned,
+    {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, self.accept());
+        let response = service.get_binary(uri, &headers).await?;
+        self.decode_response(&response)
+    }
+
+    /// Sends a POST request to `uri` with `data` encoded by the preferred
+    /// codec, accepting every codec, and decodes the response with the
+    /// codec that matches it.
+    pub async fn post<S, U, D, R>(&self, service: &S, uri: U, data: &D) -> HttpResult<R>
+    where
+        S: HttpBinary + Sync,
+        U: IntoUrl + Send,
+        D: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let (content_type, body) = self.codecs.encode(data)?;
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        headers.insert(header::ACCEPT, self.accept());
+        let response = service.post_binary(uri, &headers, body.into()).await?;
+        self.decode_response(&response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use reqwest::StatusCode;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct User {
+        name: String,
+    }
+
+    /// A codec for a made-up format that stores a single name as plain
+    /// text.
+    struct NameCodec;
+
+    impl Codec for NameCodec {
+        fn content_type(&self) -> &'static str {
+            "text/x-name"
+        }
+
+        fn encode<T: Serialize + ?Sized>(&self, value: &T) -> HttpResult<Vec<u8>> {
+            let value = serde_json::to_value(value)?;
+            Ok(value["name"]
+                .as_str()
+                .unwrap_or_default()
+                .as_bytes()
+                .to_vec())
+        }
+
+        fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> HttpResult<T> {
+            let name = String::from_utf8_lossy(body);
+            Ok(serde_json::from_value(serde_json::json!({ "name": name }))?)
+        }
+    }
+
+    /// Answers every request with a body of the given content type, and
+    /// records the headers of the last request.
+    struct Server {
+        content_type: &'static str,
+        body: &'static [u8],
+        headers: Mutex<HeaderMap>,
+    }
+
+    impl Server {
+        fn new(content_type: &'static str, body: &'static [u8]) -> Self {
+            Self {
+                content_type,
+                body,
+                headers: Mutex::default(),
+            }
+        }
+
+        fn response(&self) -> BinaryResponse {
+            BinaryResponse::new(StatusCode::OK, self.body).with_header(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(self.content_type),
+            )
+        }
+    }
+
+    impl HttpBinary for Server {
+        async fn get_binary<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            *self.headers.lock().unwrap() = headers.clone();
+            Ok(self.response())
+        }
+
+        async fn post_binary<U>(
+            &self,
+            _uri: U,
+            headers: &HeaderMap,
+            _body: Bytes,
+        ) -> HttpResult<BinaryResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            *self.headers.lock().unwrap() = headers.clone();
+            Ok(self.response())
+        }
+    }
+
+    #[test]
+    fn it_accepts_codecs_in_order_of_preference() {
+        let negotiator = Negotiator::new((JsonCodec, NameCodec));
+        assert_eq!(negotiator.accept(), "application/json, text/x-name;q=0.9");
+        assert_eq!(Negotiator::new(JsonCodec).accept(), "application/json");
+    }
+
+    #[test]
+    fn it_matches_media_types_without_parameters_and_with_suffixes() {
+        assert!(JsonCodec.matches("application/json; charset=utf-8"));
+        assert!(JsonCodec.matches("application/problem+json"));
+        assert!(!JsonCodec.matches("application/jsonx"));
+        assert!(NameCodec.matches("TEXT/X-NAME; q=1"));
+    }
+
+    #[tokio::test]
+    async fn it_decodes_with_the_codec_that_matches_the_response() -> HttpResult<()> {
+        let negotiator = Negotiator::new((JsonCodec, NameCodec));
+        let server = Server::new("text/x-name", b"foo");
+        let user: User = negotiator
+            .get(&server, "https://api.example.com/me")
+            .await?;
+        assert_eq!(user.name, "foo");
+        assert_eq!(
+            server.headers.lock().unwrap()[header::ACCEPT],
+            negotiator.accept()
+        );
+
+        let server = Server::new("application/json", br#"{"name": "bar"}"#);
+        let user = User {
+            name: String::from("bar"),
+        };
+        let created: User = negotiator
+            .post(&server, "https://api.example.com/users", &user)
+            .await?;
+        assert_eq!(created, user);
+        assert_eq!(
+            server.headers.lock().unwrap()[header::CONTENT_TYPE],
+            "application/json"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_lists_acceptable_types_for_unexpected_content_types() {
+        let negotiator = Negotiator::new((JsonCodec, NameCodec));
+        let server = Server::new("text/html", b"<html></html>");
+        let result: HttpResult<User> = negotiator.get(&server, "https://api.example.com/me").await;
+        match result {
+            Err(HttpError::UnexpectedContentType {
+                content_type,
+                acceptable,
+            }) => {
+                assert_eq!(content_type, "text/html");
+                assert_eq!(acceptable, ["application/json", "text/x-name"]);
+            }
+            other => panic!("expected an unexpected content type, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Decoding of JSON response bodies.
+//!
+//! When a response body cannot be deserialized into the expected type,
+//! `serde_json`'s error alone says little about what the server actually
+//! sent. A [`Decoder`] attaches the beginning of the body to the
+//! [`HttpError::Decode`] it returns, and can optionally try to decode the
+//! body as an API-specific error type before giving up, which is useful for
+//! APIs that return errors with an HTTP 200 status. A decoder can also be
+//! told to [reject fields](UnknownFields::Deny) that the expected type does
+//! not have, which is handy for strict contract checks in tests.
+//!
+//! With the **path-to-error** feature enabled, decoding errors also report
+//! the path to the value that did not match the expected type, such as
+//! `data.users[3].email`, which makes it much easier to track down changes
+//! to an API's schema.
+//!
+//! Newline-delimited JSON bodies can be decoded one line at a time with the
+//! [`ndjson`] module.
+//!
+//! Bodies that may not be JSON at all, such as error pages from proxies,
+//! can be recognized and decoded with the [`sniff`] module.
+//!
+//! With the **json-path** feature enabled, the [`path`] module can extract
+//! individual values from bodies with JSONPath expressions.
+//!
+//! With the **csv** feature enabled, the `csv` module decodes CSV bodies
+//! row by row.
+//!
+//! With the **html** feature enabled, the `html` module parses HTML bodies
+//! and extracts their contents with CSS selectors.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::decode::Decoder;
+//! use hypertyper::HttpError;
+//! use serde::Deserialize;
+//! use std::fmt;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct ApiError {
+//!     message: String,
+//! }
+//!
+//! impl fmt::Display for ApiError {
+//!     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!         f.write_str(&self.message)
+//!     }
+//! }
+//!
+//! impl std::error::Error for ApiError {}
+//!
+//! let decoder = Decoder::new().with_error_type::<ApiError>();
+//! let err = decoder.decode::<User>(r#"{"message": "rate limit exceeded"}"#).unwrap_err();
+//! let api_error = err.api_error::<ApiError>().unwrap();
+//! assert_eq!(api_error.message, "rate limit exceeded");
+//!
+//! let err = decoder.decode::<User>("<html>Bad Gateway</html>").unwrap_err();
+//! assert!(matches!(err, HttpError::Decode { snippet, .. } if snippet.starts_with("<html>")));
+//! ```
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "html")]
+pub mod html;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod ndjson;
+#[cfg(feature = "json-path")]
+pub mod path;
+pub mod schema;
+pub mod sniff;
+#[cfg(feature = "xml")]
+pub mod xml;
+
+use crate::{HttpError, HttpResult, timing};
+use serde::de::DeserializeOwned;
+use std::error::Error;
+
+/// The number of bytes of the body attached to decoding errors by default.
+pub const DEFAULT_SNIPPET_LEN: usize = 512;
+
+type ErrorDecoder = fn(&str) -> Option<Box<dyn Error + Send + Sync>>;
+
+/// Deserializes JSON response bodies, producing informative errors when the
+/// body does not match the expected type.
+///
+/// See the [module documentation](crate::decode) for details.
+#[derive(Clone, Debug)]
+pub struct Decoder {
+    snippet_len: usize,
+    error_decoder: Option<ErrorDecoder>,
+    unknown_fields: UnknownFields,
+    sniffing: bool,
+}
+
+impl Decoder {
+    /// Creates a decoder that attaches up to [`DEFAULT_SNIPPET_LEN`] bytes
+    /// of the body to decoding errors.
+    pub fn new() -> Self {
+        Self {
+            snippet_len: DEFAULT_SNIPPET_LEN,
+            error_decoder: None,
+            unknown_fields: UnknownFields::Ignore,
+            sniffing: false,
+        }
+    }
+
+    /// Sets the maximum number of bytes of the body attached to decoding
+    /// errors.
+    pub fn with_snippet_len(mut self, snippet_len: usize) -> Self {
+        self.snippet_len = snippet_len;
+        self
+    }
+
+    /// Tries to decode bodies that do not match the expected type as `E`.
+    ///
+    /// If the body can be decoded as an `E`, it is returned in an
+    /// [`HttpError::Api`], and can be retrieved with
+    /// [`HttpError::api_error()`].
+    pub fn with_error_type<E>(mut self) -> Self
+    where
+        E: DeserializeOwned + Error + Send + Sync + 'static,
+    {
+        self.error_decoder = Some(decode_error::<E>);
+        self
+    }
+
+    /// Sets what to do with fields in the body that `R` does not have.
+    ///
+    /// This works like `#[serde(deny_unknown_fields)]`, but applies to
+    /// every type the decoder decodes, including nested types, without
+    /// having to annotate each one.
+    pub fn with_unknown_fields(mut self, unknown_fields: UnknownFields) -> Self {
+        self.unknown_fields = unknown_fields;
+        self
+    }
+
+    /// Reports bodies that are HTML pages as [`HttpError::HtmlErrorPage`]
+    /// rather than [`HttpError::Decode`] when they cannot be decoded.
+    ///
+    /// See the [`sniff`] module for details.
+    pub fn with_sniffing(mut self) -> Self {
+        self.sniffing = true;
+        self
+    }
+
+    /// Deserializes `body` as an `R`.
+    pub fn decode<R: DeserializeOwned>(&self, body: &str) -> HttpResult<R> {
+        timing::deserialize(body, |body| self.decode_str(body))
+    }
+
+    fn decode_str<R: DeserializeOwned>(&self, body: &str) -> HttpResult<R> {
+        let value = from_str(body).map_err(|(source, path)| {
+            if let Some(err) = self.error_decoder.and_then(|decode| decode(body)) {
+                return HttpError::Api(err);
+            }
+            if self.sniffing && sniff::sniff(body.as_bytes()) == sniff::Format::Html {
+                return sniff::html_error_page(body);
+            }
+            let snippet = snippet(body, self.snippet_len).to_string();
+            HttpError::Decode {
+                source,
+                path,
+                snippet,
+            }
+        })?;
+        if self.unknown_fields == UnknownFields::Deny {
+            let (_, report) = schema::detect::<R>(body)?;
+            let unknown: Vec<_> = report.unknown_fields().map(String::from).collect();
+            if !unknown.is_empty() {
+                return Err(HttpError::UnknownFields(unknown));
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a [`Decoder`] does with fields in a body that the target type does
+/// not have.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum UnknownFields {
+    /// Unknown fields are ignored, which is serde's default behavior.
+    #[default]
+    Ignore,
+
+    /// Unknown fields cause an [`HttpError::UnknownFields`] error.
+    Deny,
+}
+
+/// Deserializes `body` as an `R` using the default [`Decoder`].
+pub fn json<R: DeserializeOwned>(body: &str) -> HttpResult<R> {
+    Decoder::new().decode(body)
+}
+
+#[cfg(feature = "path-to-error")]
+fn from_str<R: DeserializeOwned>(body: &str) -> Result<R, (serde_json::Error, Option<String>)> {
+    let mut de = serde_json::Deserializer::from_str(body);
+    let value = serde_path_to_error::deserialize(&mut de).map_err(|err| {
+        let path = err.path().to_string();
+        (err.into_inner(), Some(path))
+    })?;
+    de.end().map_err(|err| (err, None))?;
+    Ok(value)
+}
+
+#[cfg(not(feature = "path-to-error"))]
+fn from_str<R: DeserializeOwned>(body: &str) -> Result<R, (serde_json::Error, Option<String>)> {
+    serde_json::from_str(body).map_err(|err| (err, None))
+}
+
+fn decode_error<E>(body: &str) -> Option<Box<dyn Error + Send + Sync>>
+where
+    E: DeserializeOwned + Error + Send + Sync + 'static,
+{
+    serde_json::from_str::<E>(body)
+        .ok()
+        .map(|err| Box::new(err) as Box<dyn Error + Send + Sync>)
+}
+
+/// Returns at most `len` bytes from the start of `body`, without splitting
+/// a character.
+fn snippet(body: &str, len: usize) -> &str {
+    if body.len() <= len {
+        return body;
+    }
+    let mut end = len;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    &body[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::fmt;
+
+    #[derive(Debug, Deserialize)]
+    struct User {
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ApiError {
+        code: u32,
+    }
+
+    impl fmt::Display for ApiError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "API error {}", self.code)
+        }
+    }
+
+    impl Error for ApiError {}
+
+    #[test]
+    fn it_reports_html_pages_when_sniffing() {
+        let page = "<html><title>Bad Gateway</title></html>";
+        let err = Decoder::new().decode::<User>(page).unwrap_err();
+        assert!(matches!(err, HttpError::Decode { .. }));
+        let err = Decoder::new()
+            .with_sniffing()
+            .decode::<User>(page)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            HttpError::HtmlErrorPage { title: Some(title), .. } if title == "Bad Gateway"
+        ));
+    }
+
+    #[test]
+    fn it_decodes_valid_bodies() -> HttpResult<()> {
+        let decoder = Decoder::new().with_error_type::<ApiError>();
+        let user: User = decoder.decode(r#"{"name": "Alice"}"#)?;
+        assert_eq!(user.name, "Alice");
+        Ok(())
+    }
+
+    #[test]
+    fn it_attaches_the_start_of_the_body() {
+        let body = "<html><body>Service Unavailable</body></html>";
+        let err = Decoder::new()
+            .with_snippet_len(12)
+            .decode::<User>(body)
+            .unwrap_err();
+        assert!(matches!(&err, HttpError::Decode { snippet, .. } if snippet == "<html><body>"));
+        assert!(err.to_string().contains("<html><body>"));
+    }
+
+    #[cfg(feature = "path-to-error")]
+    #[test]
+    fn it_reports_the_path_to_the_mismatched_value() {
+        #[derive(Debug, Deserialize)]
+        struct Team {
+            #[allow(dead_code)]
+            members: Vec<User>,
+        }
+
+        let body = r#"{"members": [{"name": "Alice"}, {"name": 42}]}"#;
+        let err = json::<Team>(body).unwrap_err();
+        assert!(
+            matches!(&err, HttpError::Decode { path: Some(path), .. } if path == "members[1].name")
+        );
+        assert!(
+            err.to_string()
+                .starts_with("Error decoding response body at members[1].name:")
+        );
+    }
+
+    #[test]
+    fn it_denies_unknown_fields_when_asked() {
+        let body = r#"{"name": "Alice", "role": "admin"}"#;
+        assert!(json::<User>(body).is_ok());
+
+        let decoder = Decoder::new().with_unknown_fields(UnknownFields::Deny);
+        let err = decoder.decode::<User>(body).unwrap_err();
+        assert!(matches!(&err, HttpError::UnknownFields(fields) if fields == &["role"]));
+        assert!(decoder.decode::<User>(r#"{"name": "Alice"}"#).is_ok());
+    }
+
+    #[test]
+    fn it_does_not_split_characters() {
+        assert_eq!(snippet("héllo", 2), "h");
+        assert_eq!(snippet("héllo", 3), "hé");
+        assert_eq!(snippet("hi", 10), "hi");
+    }
+
+    #[test]
+    fn it_decodes_api_errors() {
+        let err = Decoder::new()
+            .with_error_type::<ApiError>()
+            .decode::<User>(r#"{"code": 42}"#)
+            .unwrap_err();
+        assert_eq!(err.api_error::<ApiError>().map(|e| e.code), Some(42));
+        assert_eq!(err.to_string(), "API returned an error: API error 42");
+    }
+
+    #[test]
+    fn it_falls_back_when_the_error_type_does_not_match() {
+        let err = Decoder::new()
+            .with_error_type::<ApiError>()
+            .decode::<User>(r#"{"unexpected": true}"#)
+            .unwrap_err();
+        assert!(matches!(err, HttpError::Decode { .. }));
+        assert!(err.api_error::<ApiError>().is_none());
+    }
+}
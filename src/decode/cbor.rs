@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! CBOR request and response bodies.
+//!
+//! [CBOR] is a compact binary format with a data model that extends JSON's,
+//! standardized in RFC 8949. This module serializes request bodies with
+//! [`to_vec()`] and deserializes response bodies with [`from_slice()`],
+//! using [ciborium] and the same serde derives used for JSON.
+//!
+//! [`HttpBinary::get_cbor()`] and [`HttpBinary::post_cbor()`] make requests
+//! with CBOR bodies in one step, with the `Content-Type` and `Accept`
+//! headers set to [`CONTENT_TYPE`].
+//!
+//! This module is only available with the **cbor** feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::decode::cbor;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Deserialize, PartialEq, Serialize)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let point = Point { x: 1, y: -1 };
+//! let body = cbor::to_vec(&point).unwrap();
+//! assert_eq!(body, b"\xa2\x61x\x01\x61y\x20");
+//! assert_eq!(cbor::from_slice::<Point>(&body).unwrap(), point);
+//! ```
+//!
+//! [CBOR]: https://cbor.io/
+//! [ciborium]: https://crates.io/crates/ciborium
+
+use crate::HttpResult;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+#[cfg(doc)]
+use crate::{HttpError, service::HttpBinary};
+
+/// The content type of CBOR request and response bodies.
+pub const CONTENT_TYPE: &str = "application/cbor";
+
+/// Deserializes the CBOR `body` as a `T`.
+///
+/// # Errors
+///
+/// Returns [`HttpError::CborDecode`] if `body` is not CBOR that matches
+/// `T`.
+pub fn from_slice<T: DeserializeOwned>(body: &[u8]) -> HttpResult<T> {
+    Ok(ciborium::from_reader(body)?)
+}
+
+/// Serializes `value` as CBOR.
+///
+/// # Errors
+///
+/// Returns [`HttpError::CborSerialization`] if `value` cannot be
+/// represented as CBOR.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> HttpResult<Vec<u8>> {
+    let mut body = Vec::new();
+    ciborium::into_writer(value, &mut body)?;
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::service::{BinaryResponse, HttpBinary};
+    use bytes::Bytes;
+    use reqwest::header::{self, HeaderMap};
+    use reqwest::{IntoUrl, StatusCode};
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct User {
+        name: String,
+        admin: bool,
+    }
+
+    /// Echoes request bodies back, and records the headers of the last
+    /// request.
+    #[derive(Default)]
+    struct EchoServer {
+        headers: Mutex<HeaderMap>,
+    }
+
+    impl HttpBinary for EchoServer {
+        async fn get_binary<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            *self.headers.lock().unwrap() = headers.clone();
+            Ok(BinaryResponse::new(StatusCode::OK, to_vec(&["foo"])?))
+        }
+
+        async fn post_binary<U>(
+            &self,
+            _uri: U,
+            headers: &HeaderMap,
+            body: Bytes,
+        ) -> HttpResult<BinaryResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            *self.headers.lock().unwrap() = headers.clone();
+            Ok(BinaryResponse::new(StatusCode::OK, body))
+        }
+    }
+
+    #[test]
+    fn it_round_trips_values() -> HttpResult<()> {
+        let user = User {
+            name: String::from("foo"),
+            admin: true,
+        };
+        let body = to_vec(&user)?;
+        assert!(body.len() < serde_json::to_vec(&user)?.len());
+        assert_eq!(from_slice::<User>(&body)?, user);
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_on_bodies_that_do_not_match() {
+        let body = to_vec(&[1, 2, 3]).unwrap();
+        assert!(matches!(
+            from_slice::<User>(&body),
+            Err(HttpError::CborDecode(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_sends_and_receives_cbor() -> HttpResult<()> {
+        let server = EchoServer::default();
+        let names: Vec<String> = server.get_cbor("https://api.example.com/names").await?;
+        assert_eq!(names, ["foo"]);
+        assert_eq!(server.headers.lock().unwrap()[header::ACCEPT], CONTENT_TYPE);
+
+        let user = User {
+            name: String::from("bar"),
+            admin: false,
+        };
+        let echoed: User = server
+            .post_cbor("https://api.example.com/users", &HeaderMap::new(), &user)
+            .await?;
+        assert_eq!(echoed, user);
+        let headers = server.headers.lock().unwrap();
+        assert_eq!(headers[header::CONTENT_TYPE], CONTENT_TYPE);
+        assert_eq!(headers[header::ACCEPT], CONTENT_TYPE);
+        Ok(())
+    }
+}
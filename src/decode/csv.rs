@@ -0,0 +1,338 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Decoding of CSV response bodies.
+//!
+//! Many reporting APIs only export CSV, and their exports can be large.
+//! [`rows()`] deserializes a streamed body one row at a time as it arrives,
+//! so the whole export never has to fit in memory. Quoted fields that span
+//! several lines, or several chunks of the body, are handled correctly.
+//!
+//! [`HttpGetStream::get_csv()`](crate::service::HttpGetStream::get_csv)
+//! requests and decodes a CSV body in one step.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use futures_util::StreamExt;
+//! use hypertyper::decode::csv::CsvOptions;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetStream;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Sale {
+//!     date: String,
+//!     amount: f64,
+//! }
+//!
+//! # async fn run<S: HttpGetStream + Sync>(service: S) -> HttpResult<()> {
+//! let options = CsvOptions::new().with_delimiter(b';');
+//! let mut sales = service
+//!     .get_csv::<Sale, _>("https://reports.example.com/sales.csv", &options)
+//!     .await?;
+//! let mut total = 0.0;
+//! while let Some(sale) = sales.next().await {
+//!     total += sale?.amount;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::service::BodyStream;
+use ::csv::ByteRecord;
+use bytes::Bytes;
+use csv_core::{ReadRecordResult, Reader, ReaderBuilder};
+use futures_util::{Stream, StreamExt, stream};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// How a CSV body is formatted.
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    delimiter: u8,
+    has_headers: bool,
+}
+
+impl CsvOptions {
+    /// Options for comma-separated values with a header row.
+    pub fn new() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: true,
+        }
+    }
+
+    /// Sets the byte that separates fields, such as `b'\t'` for
+    /// tab-separated values.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether the first row contains the names of the columns.
+    ///
+    /// With a header row, rows are deserialized by column name, so they can
+    /// be deserialized into structs with fields in any order. Without one,
+    /// they are deserialized by position.
+    pub fn with_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// The byte that separates fields.
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// Whether the first row contains the names of the columns.
+    pub fn has_headers(&self) -> bool {
+        self.has_headers
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A stream of rows deserialized from a CSV body.
+///
+/// This is returned by [`rows()`].
+pub struct CsvRows<T> {
+    rows: Pin<Box<dyn Stream<Item = HttpResult<T>> + Send>>,
+}
+
+impl<T> Stream for CsvRows<T> {
+    type Item = HttpResult<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rows.as_mut().poll_next(cx)
+    }
+}
+
+impl<T> fmt::Debug for CsvRows<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvRows").finish_non_exhaustive()
+    }
+}
+
+/// Deserializes each row of the CSV `body` as a `T`, as the body arrives.
+pub fn rows<T>(body: BodyStream, options: &CsvOptions) -> CsvRows<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let state = State {
+        body,
+        splitter: Splitter::new(options),
+        chunk: Bytes::new(),
+        eof: false,
+        headers: None,
+        has_headers: options.has_headers,
+    };
+    let rows = stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        match state.next_record().await {
+            Ok(Some(record)) => {
+                let row = record
+                    .deserialize(state.headers.as_ref())
+                    .map_err(Into::into);
+                Some((row, Some(state)))
+            }
+            Ok(None) => None,
+            // The body cannot be read any further after an error.
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+    CsvRows {
+        rows: Box::pin(rows),
+    }
+}
+
+struct State {
+    body: BodyStream,
+    splitter: Splitter,
+    chunk: Bytes,
+    eof: bool,
+    headers: Option<ByteRecord>,
+    has_headers: bool,
+}
+
+impl State {
+    /// Reads the next row that is not the header row, or `None` at the end
+    /// of the body.
+    async fn next_record(&mut self) -> HttpResult<Option<ByteRecord>> {
+        loop {
+            let (record, consumed) = self.splitter.split(&self.chunk, self.eof);
+            let _ = self.chunk.split_to(consumed);
+            match record {
+                Some(record) if self.has_headers && self.headers.is_none() => {
+                    self.headers = Some(record);
+                }
+                Some(record) => return Ok(Some(record)),
+                None if self.eof => return Ok(None),
+                None => match self.body.next().await {
+                    Some(chunk) => self.chunk = chunk?,
+                    None => self.eof = true,
+                },
+            }
+        }
+    }
+}
+
+/// Splits a CSV body into records incrementally, keeping partial records
+/// between chunks.
+struct Splitter {
+    reader: Reader,
+    output: Vec<u8>,
+    ends: Vec<usize>,
+    output_len: usize,
+    ends_len: usize,
+}
+
+impl Splitter {
+    fn new(options: &CsvOptions) -> Self {
+        let reader = ReaderBuilder::new().delimiter(options.delimiter).build();
+        Self {
+            reader,
+            output: vec![0; 1024],
+            ends: vec![0; 16],
+            output_len: 0,
+            ends_len: 0,
+        }
+    }
+
+    /// Reads from `input` until a complete record has been read, returning
+    /// the record, if any, and the number of bytes of `input` consumed.
+    ///
+    /// `eof` means that no more input follows `input`, so that the last
+    /// record is returned even if it does not end with a newline.
+    fn split(&mut self, mut input: &[u8], eof: bool) -> (Option<ByteRecord>, usize) {
+        let mut consumed = 0;
+        loop {
+            if input.is_empty() && !eof {
+                return (None, consumed);
+            }
+            let (result, nin, nout, nend) = self.reader.read_record(
+                input,
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            input = &input[nin..];
+            consumed += nin;
+            self.output_len += nout;
+            self.ends_len += nend;
+            match result {
+                ReadRecordResult::InputEmpty if eof => continue,
+                ReadRecordResult::InputEmpty | ReadRecordResult::End => {
+                    return (None, consumed);
+                }
+                ReadRecordResult::OutputFull => {
+                    let len = self.output.len() * 2;
+                    self.output.resize(len, 0);
+                }
+                ReadRecordResult::OutputEndsFull => {
+                    let len = self.ends.len() * 2;
+                    self.ends.resize(len, 0);
+                }
+                ReadRecordResult::Record => return (Some(self.take_record()), consumed),
+            }
+        }
+    }
+
+    fn take_record(&mut self) -> ByteRecord {
+        let mut start = 0;
+        let record = self.ends[..self.ends_len]
+            .iter()
+            .map(|&end| {
+                let field = &self.output[start..end];
+                start = end;
+                field
+            })
+            .collect();
+        self.output_len = 0;
+        self.ends_len = 0;
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Sale {
+        region: String,
+        amount: u32,
+    }
+
+    fn body(chunks: &[&'static str]) -> BodyStream {
+        let chunks: Vec<_> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+            .collect();
+        BodyStream::new(stream::iter(chunks))
+    }
+
+    async fn collect<T>(rows: CsvRows<T>) -> Vec<HttpResult<T>> {
+        rows.collect().await
+    }
+
+    #[tokio::test]
+    async fn it_deserializes_rows_split_across_chunks() -> HttpResult<()> {
+        let body = body(&["amount,reg", "ion\n10,\"North\nEast\"\n2", "0,South"]);
+        let rows = collect(rows::<Sale>(body, &CsvOptions::new())).await;
+        let rows: Vec<_> = rows.into_iter().collect::<HttpResult<_>>()?;
+        assert_eq!(
+            rows,
+            [
+                Sale {
+                    region: String::from("North\nEast"),
+                    amount: 10,
+                },
+                Sale {
+                    region: String::from("South"),
+                    amount: 20,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_deserializes_rows_without_headers_by_position() -> HttpResult<()> {
+        let options = CsvOptions::new().with_delimiter(b'\t').with_headers(false);
+        let rows = collect(rows::<(String, u32)>(body(&["a\t1\nb\t2\n"]), &options)).await;
+        let rows: Vec<_> = rows.into_iter().collect::<HttpResult<_>>()?;
+        assert_eq!(rows, [(String::from("a"), 1), (String::from("b"), 2)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reports_rows_that_do_not_match_the_type() {
+        let rows = collect(rows::<Sale>(
+            body(&["region,amount\nWest,lots\n"]),
+            &CsvOptions::new(),
+        ))
+        .await;
+        assert_eq!(rows.len(), 1);
+        assert!(matches!(rows[0], Err(HttpError::Csv(_))));
+    }
+
+    #[test]
+    fn it_grows_its_buffers_for_long_records() {
+        let mut splitter = Splitter::new(&CsvOptions::new());
+        let long = format!("{},{}\n", "x".repeat(5000), vec!["y"; 100].join(","));
+        let (record, consumed) = splitter.split(long.as_bytes(), false);
+        let record = record.unwrap();
+        assert_eq!(consumed, long.len());
+        assert_eq!(record.len(), 101);
+        assert_eq!(record[0].len(), 5000);
+    }
+}
@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Parsing of HTML response bodies.
+//!
+//! Some of the data an application needs is only published as web pages.
+//! A [`Document`] parses an HTML body and extracts text, attributes, and
+//! links from it with CSS selectors, so light scraping does not need a
+//! second HTTP stack. The underlying [`scraper`] document is available from
+//! [`Document::html()`] for anything the extractors do not cover.
+//!
+//! [`HttpGetStream::get_html()`](crate::service::HttpGetStream::get_html)
+//! requests and parses a page in one step, and resolves relative links
+//! against the page's URL.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::decode::html::Document;
+//! use url::Url;
+//!
+//! let body = r#"
+//!     <html>
+//!       <head><title>Releases</title></head>
+//!       <body>
+//!         <ul class="releases">
+//!           <li><a href="/v1.2.0">1.2.0</a></li>
+//!           <li><a href="/v1.1.0">1.1.0</a></li>
+//!         </ul>
+//!       </body>
+//!     </html>
+//! "#;
+//! let url = Url::parse("https://example.com/releases").unwrap();
+//! let document = Document::parse(body).with_url(url);
+//!
+//! assert_eq!(document.title().as_deref(), Some("Releases"));
+//! assert_eq!(document.texts(".releases a").unwrap(), ["1.2.0", "1.1.0"]);
+//! assert_eq!(
+//!     document.links(".releases a").unwrap()[0].as_str(),
+//!     "https://example.com/v1.2.0"
+//! );
+//! ```
+
+use crate::{HttpError, HttpResult};
+use scraper::{ElementRef, Html, Selector};
+use std::fmt;
+use url::Url;
+
+/// A parsed HTML document.
+///
+/// Documents can be sent to other threads, but not shared between them.
+#[derive(Clone)]
+pub struct Document {
+    html: Html,
+    url: Option<Url>,
+}
+
+impl Document {
+    /// Parses `body` as an HTML document.
+    ///
+    /// Like a browser, the parser recovers from malformed HTML instead of
+    /// failing.
+    pub fn parse(body: &str) -> Self {
+        Self {
+            html: Html::parse_document(body),
+            url: None,
+        }
+    }
+
+    /// Sets the URL the document was retrieved from, which relative links
+    /// are resolved against.
+    ///
+    /// A `<base>` element in the document takes precedence over `url`.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    /// The URL the document was retrieved from, if known.
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    /// The underlying parsed document.
+    pub fn html(&self) -> &Html {
+        &self.html
+    }
+
+    /// All elements that match `selector`, in document order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Selector`] if `selector` is not a valid CSS
+    /// selector.
+    pub fn select(&self, selector: &str) -> HttpResult<Vec<ElementRef<'_>>> {
+        let selector = parse_selector(selector)?;
+        Ok(self.html.select(&selector).collect())
+    }
+
+    /// The text of the document's `<title>` element, if it has one.
+    pub fn title(&self) -> Option<String> {
+        self.text("title").ok().flatten()
+    }
+
+    /// The text of the first element that matches `selector`, with
+    /// whitespace collapsed, or `None` if no element matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Selector`] if `selector` is not a valid CSS
+    /// selector.
+    pub fn text(&self, selector: &str) -> HttpResult<Option<String>> {
+        Ok(self.select(selector)?.first().map(text_of))
+    }
+
+    /// The text of each element that matches `selector`, with whitespace
+    /// collapsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Selector`] if `selector` is not a valid CSS
+    /// selector.
+    pub fn texts(&self, selector: &str) -> HttpResult<Vec<String>> {
+        Ok(self.select(selector)?.iter().map(text_of).collect())
+    }
+
+    /// The value of the `name` attribute of the first element that matches
+    /// `selector` and has that attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Selector`] if `selector` is not a valid CSS
+    /// selector.
+    pub fn attr(&self, selector: &str, name: &str) -> HttpResult<Option<String>> {
+        Ok(self.attrs(selector, name)?.into_iter().next())
+    }
+
+    /// The value of the `name` attribute of each element that matches
+    /// `selector`, skipping elements without that attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Selector`] if `selector` is not a valid CSS
+    /// selector.
+    pub fn attrs(&self, selector: &str, name: &str) -> HttpResult<Vec<String>> {
+        let values = self
+            .select(selector)?
+            .iter()
+            .filter_map(|element| element.attr(name))
+            .map(String::from)
+            .collect();
+        Ok(values)
+    }
+
+    /// The `href` of each element that matches `selector`, resolved against
+    /// the document's base URL.
+    ///
+    /// Links that cannot be resolved, such as relative links in a document
+    /// without a URL, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Selector`] if `selector` is not a valid CSS
+    /// selector.
+    pub fn links(&self, selector: &str) -> HttpResult<Vec<Url>> {
+        let base = self.base_url();
+        let links = self
+            .attrs(selector, "href")?
+            .iter()
+            .filter_map(|href| match &base {
+                Some(base) => base.join(href).ok(),
+                None => Url::parse(href).ok(),
+            })
+            .collect();
+        Ok(links)
+    }
+
+    /// The URL relative links are resolved against: the document's
+    /// `<base>` element, itself resolved against its URL, or its URL.
+    fn base_url(&self) -> Option<Url> {
+        let base = self.attr("base[href]", "href").ok().flatten();
+        match (base, &self.url) {
+            (Some(base), Some(url)) => url.join(&base).ok(),
+            (Some(base), None) => Url::parse(&base).ok(),
+            (None, url) => url.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Document")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+fn parse_selector(selector: &str) -> HttpResult<Selector> {
+    Selector::parse(selector).map_err(|err| HttpError::Selector {
+        selector: selector.to_string(),
+        message: err.to_string(),
+    })
+}
+
+fn text_of(element: &ElementRef<'_>) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"
+        <html>
+          <head>
+            <title>
+              Team   directory
+            </title>
+          </head>
+          <body>
+            <table id="people">
+              <tr><td class="name">Ada <b>Lovelace</b></td><td><a href="ada">Profile</a></td></tr>
+              <tr><td class="name">Grace Hopper</td><td><a href="https://example.org/grace">Profile</a></td></tr>
+              <tr><td class="name">Anonymous</td><td><a>No profile</a></td></tr>
+            </table>
+          </body>
+        </html>
+    "#;
+
+    fn document() -> Document {
+        Document::parse(PAGE).with_url(Url::parse("https://example.com/team/").unwrap())
+    }
+
+    #[test]
+    fn it_extracts_text_with_collapsed_whitespace() -> HttpResult<()> {
+        let document = document();
+        assert_eq!(document.title().as_deref(), Some("Team directory"));
+        assert_eq!(
+            document.texts("#people .name")?,
+            ["Ada Lovelace", "Grace Hopper", "Anonymous"]
+        );
+        assert_eq!(document.text(".missing")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_extracts_attributes_and_skips_elements_without_them() -> HttpResult<()> {
+        let document = document();
+        assert_eq!(
+            document.attrs("a", "href")?,
+            ["ada", "https://example.org/grace"]
+        );
+        assert_eq!(document.attr("a", "href")?.as_deref(), Some("ada"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_resolves_links_against_the_document_url() -> HttpResult<()> {
+        let links: Vec<_> = document()
+            .links("a")?
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            links,
+            ["https://example.com/team/ada", "https://example.org/grace"]
+        );
+
+        let links = Document::parse(PAGE).links("a")?;
+        assert_eq!(links.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_prefers_the_base_element_for_resolving_links() -> HttpResult<()> {
+        let document = Document::parse(r#"<base href="/docs/"><a href="intro">Intro</a>"#)
+            .with_url(Url::parse("https://example.com/index.html").unwrap());
+        assert_eq!(
+            document.links("a")?[0].as_str(),
+            "https://example.com/docs/intro"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_invalid_selectors() {
+        let err = document().texts("td[").unwrap_err();
+        assert!(matches!(err, HttpError::Selector { selector, .. } if selector == "td["));
+    }
+
+    #[test]
+    fn it_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Document>();
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Decoding of newline-delimited JSON response bodies.
+//!
+//! Many APIs stream results as [newline-delimited JSON](https://jsonlines.org/),
+//! also known as JSON Lines: one JSON value per line. [`values()`]
+//! deserializes each line of a streamed body as soon as it is complete, even
+//! if it arrived across several chunks. Blank lines are skipped, and
+//! `\r\n` line endings are accepted.
+//!
+//! A line that cannot be deserialized produces an error, like
+//! [`decode::json()`](crate::decode::json) would, but the lines after it
+//! are still decoded. An error reading the body ends the stream.
+//!
+//! [`HttpGetStream::get_ndjson()`](crate::service::HttpGetStream::get_ndjson)
+//! requests and decodes a body in one step.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use futures_util::StreamExt;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetStream;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     kind: String,
+//! }
+//!
+//! # async fn run<S: HttpGetStream + Sync>(service: S) -> HttpResult<()> {
+//! let mut events = service
+//!     .get_ndjson::<Event, _>("https://api.example.com/events/stream")
+//!     .await?;
+//! while let Some(event) = events.next().await {
+//!     println!("{}", event?.kind);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::decode::Decoder;
+use crate::service::BodyStream;
+use bytes::{Buf, BytesMut};
+use futures_util::{Stream, StreamExt, stream};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A stream of values deserialized from a newline-delimited JSON body.
+///
+/// This is returned by [`values()`].
+pub struct NdjsonValues<T> {
+    values: Pin<Box<dyn Stream<Item = HttpResult<T>> + Send>>,
+}
+
+impl<T> Stream for NdjsonValues<T> {
+    type Item = HttpResult<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.values.as_mut().poll_next(cx)
+    }
+}
+
+impl<T> fmt::Debug for NdjsonValues<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NdjsonValues").finish_non_exhaustive()
+    }
+}
+
+/// Deserializes each line of the newline-delimited JSON `body` as a `T`,
+/// as the body arrives.
+pub fn values<T>(body: BodyStream) -> NdjsonValues<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let state = State {
+        body,
+        buffer: BytesMut::new(),
+        eof: false,
+    };
+    let values = stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        match state.next_line().await {
+            Ok(Some(line)) => {
+                let value = String::from_utf8(line)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err).into())
+                    .and_then(|line| Decoder::new().decode(&line));
+                Some((value, Some(state)))
+            }
+            Ok(None) => None,
+            // The body cannot be read any further after an error.
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+    NdjsonValues {
+        values: Box::pin(values),
+    }
+}
+
+struct State {
+    body: BodyStream,
+    buffer: BytesMut,
+    eof: bool,
+}
+
+impl State {
+    /// Reads the next line that is not blank, without its line ending, or
+    /// `None` at the end of the body.
+    async fn next_line(&mut self) -> HttpResult<Option<Vec<u8>>> {
+        // Only the bytes after `searched` can contain the next newline.
+        let mut searched = 0;
+        loop {
+            let newline = self.buffer[searched..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map(|i| searched + i);
+            let line = match newline {
+                Some(i) => {
+                    let line = self.buffer.split_to(i).to_vec();
+                    self.buffer.advance(1);
+                    searched = 0;
+                    line
+                }
+                None if self.eof => std::mem::take(&mut self.buffer).to_vec(),
+                None => {
+                    searched = self.buffer.len();
+                    match self.body.next().await {
+                        Some(chunk) => self.buffer.extend_from_slice(&chunk?),
+                        None => self.eof = true,
+                    }
+                    continue;
+                }
+            };
+            let line = line.strip_suffix(b"\r").unwrap_or(&line);
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                return Ok(Some(line.to_vec()));
+            }
+            if self.eof && self.buffer.is_empty() {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use bytes::Bytes;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Event {
+        id: u32,
+    }
+
+    fn body(chunks: &[&'static str]) -> BodyStream {
+        let chunks: Vec<_> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+            .collect();
+        BodyStream::new(stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn it_decodes_lines_split_across_chunks() -> HttpResult<()> {
+        let body = body(&["{\"id\"", ": 1}\r\n\n{\"id\": 2}\n{\"i", "d\": 3}"]);
+        let events: Vec<_> = values::<Event>(body).collect().await;
+        let events: Vec<_> = events.into_iter().collect::<HttpResult<_>>()?;
+        assert_eq!(events, [Event { id: 1 }, Event { id: 2 }, Event { id: 3 }]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_keeps_decoding_after_a_malformed_line() {
+        let body = body(&["{\"id\": 1}\nnot json\n{\"id\": 3}\n\n"]);
+        let events: Vec<_> = values::<Event>(body).collect().await;
+        assert_eq!(events.len(), 3);
+        assert!(
+            matches!(&events[1], Err(HttpError::Decode { snippet, .. }) if snippet == "not json")
+        );
+        assert_eq!(events[2].as_ref().unwrap(), &Event { id: 3 });
+    }
+
+    #[tokio::test]
+    async fn it_stops_at_errors_reading_the_body() {
+        let chunks = vec![
+            Ok(Bytes::from_static(b"{\"id\": 1}\n{\"id\"")),
+            Err(HttpError::Timeout(std::time::Duration::from_secs(1))),
+            Ok(Bytes::from_static(b": 2}\n")),
+        ];
+        let body = BodyStream::new(stream::iter(chunks));
+        let events: Vec<_> = values::<Event>(body).collect().await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1], Err(HttpError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn it_handles_empty_bodies() {
+        let events: Vec<_> = values::<Event>(body(&["", "\n\n"])).collect().await;
+        assert!(events.is_empty());
+    }
+}
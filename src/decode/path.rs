@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Extraction of values from JSON bodies with JSONPath.
+//!
+//! When only one or two values in a response are needed, such as an ID or
+//! a pagination cursor, defining structs for the whole response is
+//! overkill. [`extract()`] finds a value with a [JSONPath] expression, such
+//! as `$.data.items[0].id`, and deserializes just that value.
+//!
+//! This module is only available with the **json-path** feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::decode::path;
+//!
+//! let body = r#"{"data": {"items": [{"id": 7}, {"id": 8}]}, "next": "abc"}"#;
+//! let id: u64 = path::extract(body, "$.data.items[0].id").unwrap();
+//! assert_eq!(id, 7);
+//!
+//! let ids: Vec<u64> = path::extract_all(body, "$.data.items[*].id").unwrap();
+//! assert_eq!(ids, [7, 8]);
+//!
+//! let cursor: Option<String> = path::extract_opt(body, "$.next").unwrap();
+//! assert_eq!(cursor.as_deref(), Some("abc"));
+//! ```
+//!
+//! [JSONPath]: https://www.rfc-editor.org/rfc/rfc9535
+
+use crate::decode::{DEFAULT_SNIPPET_LEN, json, snippet};
+use crate::{HttpError, HttpResult};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+/// Deserializes the single value at `path` in the JSON `body`.
+///
+/// Returns [`HttpError::JsonPathNotFound`] unless `path` matches exactly
+/// one value.
+pub fn extract<T: DeserializeOwned>(body: &str, path: &str) -> HttpResult<T> {
+    extract_value(&json(body)?, path)
+}
+
+/// Deserializes the value at `path` in the JSON `body`, or returns `None`
+/// if there is no such value.
+///
+/// Returns [`HttpError::JsonPathNotFound`] if `path` matches more than one
+/// value.
+pub fn extract_opt<T: DeserializeOwned>(body: &str, path: &str) -> HttpResult<Option<T>> {
+    let value = json(body)?;
+    let nodes = parse(path)?.query(&value);
+    let node = nodes
+        .at_most_one()
+        .map_err(|_| HttpError::JsonPathNotFound(path.to_string()))?;
+    node.map(|node| deserialize(node, path)).transpose()
+}
+
+/// Deserializes every value at `path` in the JSON `body`.
+pub fn extract_all<T: DeserializeOwned>(body: &str, path: &str) -> HttpResult<Vec<T>> {
+    let value = json(body)?;
+    let nodes = parse(path)?.query(&value);
+    nodes
+        .all()
+        .into_iter()
+        .map(|node| deserialize(node, path))
+        .collect()
+}
+
+/// Deserializes the single value at `path` in a JSON value that has
+/// already been parsed.
+pub fn extract_value<T: DeserializeOwned>(value: &Value, path: &str) -> HttpResult<T> {
+    deserialize(find(value, path)?, path)
+}
+
+/// Finds the single value at `path` in `value`.
+pub fn find<'a>(value: &'a Value, path: &str) -> HttpResult<&'a Value> {
+    parse(path)?
+        .query(value)
+        .exactly_one()
+        .map_err(|_| HttpError::JsonPathNotFound(path.to_string()))
+}
+
+fn parse(path: &str) -> HttpResult<JsonPath> {
+    JsonPath::parse(path).map_err(|err| HttpError::InvalidJsonPath(err.to_string()))
+}
+
+fn deserialize<T: DeserializeOwned>(node: &Value, path: &str) -> HttpResult<T> {
+    T::deserialize(node).map_err(|source| HttpError::Decode {
+        source,
+        path: Some(path.to_string()),
+        snippet: snippet(&node.to_string(), DEFAULT_SNIPPET_LEN).to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = r#"{"data": {"items": [{"id": 7, "tags": []}, {"id": 8}]}}"#;
+
+    #[test]
+    fn it_extracts_typed_values() -> HttpResult<()> {
+        assert_eq!(extract::<u64>(BODY, "$.data.items[1].id")?, 8);
+        assert_eq!(
+            extract::<Vec<String>>(BODY, "$.data.items[0].tags")?,
+            Vec::<String>::new()
+        );
+        assert_eq!(extract_all::<u64>(BODY, "$..id")?, [7, 8]);
+        assert_eq!(extract_opt::<u64>(BODY, "$.data.items[2].id")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_requires_exactly_one_value() {
+        let err = extract::<u64>(BODY, "$..id").unwrap_err();
+        assert!(matches!(&err, HttpError::JsonPathNotFound(path) if path == "$..id"));
+        assert!(extract::<u64>(BODY, "$.missing").is_err());
+        assert!(extract_opt::<u64>(BODY, "$..id").is_err());
+    }
+
+    #[test]
+    fn it_reports_invalid_paths() {
+        let err = extract::<u64>(BODY, "data.items").unwrap_err();
+        assert!(matches!(err, HttpError::InvalidJsonPath(_)));
+    }
+
+    #[test]
+    fn it_reports_values_of_the_wrong_type() {
+        let err = extract::<String>(BODY, "$.data.items[0].id").unwrap_err();
+        assert!(matches!(
+            &err,
+            HttpError::Decode { path: Some(path), snippet, .. } if path == "$.data.items[0].id" && snippet == "7"
+        ));
+    }
+}
@@ -0,0 +1,333 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Comparison of JSON bodies with the types they are deserialized into.
+//!
+//! By default, serde silently ignores fields it does not know about and
+//! fills in optional fields that are missing. [`detect()`] deserializes a
+//! body while keeping track of which fields the target type expected and
+//! which fields the body actually contained, and returns a [`DriftReport`]
+//! of the differences.
+//!
+//! Reports name fields by their path in the body, such as
+//! `user.address.zip` or `items[].id`. Enums are not inspected.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::decode::schema;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     name: String,
+//!     email: Option<String>,
+//! }
+//!
+//! let (user, report) = schema::detect::<User>(r#"{"name": "Alice", "role": "admin"}"#)?;
+//! assert_eq!(user.name, "Alice");
+//! assert_eq!(report.unknown_fields().collect::<Vec<_>>(), ["role"]);
+//! assert_eq!(report.missing_fields().collect::<Vec<_>>(), ["email"]);
+//! # Ok::<(), hypertyper::HttpError>(())
+//! ```
+
+use crate::HttpResult;
+use crate::decode;
+use serde::de::value::StringDeserializer;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The differences between a response and the type it was deserialized
+/// into.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DriftReport {
+    unknown: BTreeSet<String>,
+    missing: BTreeSet<String>,
+}
+
+impl DriftReport {
+    /// Whether the response matched the type exactly.
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty()
+    }
+
+    /// The paths of fields that were in the response but not in the type.
+    pub fn unknown_fields(&self) -> impl Iterator<Item = &str> {
+        self.unknown.iter().map(String::as_str)
+    }
+
+    /// The paths of fields that were in the type but not in the response.
+    ///
+    /// This includes optional fields and fields with defaults, which
+    /// deserialize successfully even though they are missing.
+    pub fn missing_fields(&self) -> impl Iterator<Item = &str> {
+        self.missing.iter().map(String::as_str)
+    }
+
+    fn compare(&mut self, path: &str, fields: &[&str], object: &Map<String, Value>) {
+        for key in object.keys() {
+            if !fields.contains(&key.as_str()) {
+                self.unknown.insert(child_path(path, key));
+            }
+        }
+        for field in fields {
+            if !object.contains_key(*field) {
+                self.missing.insert(child_path(path, field));
+            }
+        }
+    }
+}
+
+impl fmt::Display for DriftReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown: Vec<_> = self.unknown_fields().collect();
+        let missing: Vec<_> = self.missing_fields().collect();
+        write!(
+            f,
+            "unknown fields: [{}]; missing fields: [{}]",
+            unknown.join(", "),
+            missing.join(", ")
+        )
+    }
+}
+
+/// Deserializes `body` as an `R`, reporting any fields that do not match.
+pub fn detect<R: DeserializeOwned>(body: &str) -> HttpResult<(R, DriftReport)> {
+    let value: Value = decode::json(body)?;
+    match detect_value(value) {
+        Ok(result) => Ok(result),
+        // Decode the body again to get a more informative error.
+        Err(_) => decode::json(body).map(|value| (value, DriftReport::default())),
+    }
+}
+
+pub(crate) fn detect_value<R: DeserializeOwned>(
+    value: Value,
+) -> Result<(R, DriftReport), serde_json::Error> {
+    let report = RefCell::new(DriftReport::default());
+    let tracker = Tracker {
+        value,
+        path: String::new(),
+        report: &report,
+    };
+    let value = R::deserialize(tracker)?;
+    Ok((value, report.into_inner()))
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Deserializes a JSON value, recording the fields expected by each struct
+/// it is deserialized into.
+struct Tracker<'a> {
+    value: Value,
+    path: String,
+    report: &'a RefCell<DriftReport>,
+}
+
+impl<'de> Deserializer<'de> for Tracker<'_> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(object) => visitor.visit_map(TrackedMap {
+                entries: object.into_iter(),
+                pending: None,
+                path: self.path,
+                report: self.report,
+            }),
+            Value::Array(items) => visitor.visit_seq(TrackedSeq {
+                items: items.into_iter(),
+                path: format!("{}[]", self.path),
+                report: self.report,
+            }),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Value::Object(object) = &self.value {
+            self.report.borrow_mut().compare(&self.path, fields, object);
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier
+    }
+}
+
+struct TrackedMap<'a> {
+    entries: serde_json::map::IntoIter,
+    pending: Option<(String, Value)>,
+    path: String,
+    report: &'a RefCell<DriftReport>,
+}
+
+impl<'de> de::MapAccess<'de> for TrackedMap<'_> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        let deserializer = StringDeserializer::new(key.clone());
+        self.pending = Some((key, value));
+        seed.deserialize(deserializer).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (key, value) = self
+            .pending
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(Tracker {
+            value,
+            path: child_path(&self.path, &key),
+            report: self.report,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct TrackedSeq<'a> {
+    items: std::vec::IntoIter<Value>,
+    path: String,
+    report: &'a RefCell<DriftReport>,
+}
+
+impl<'de> de::SeqAccess<'de> for TrackedSeq<'_> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let Some(value) = self.items.next() else {
+            return Ok(None);
+        };
+        let tracker = Tracker {
+            value,
+            path: self.path.clone(),
+            report: self.report,
+        };
+        seed.deserialize(tracker).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Address {
+        city: String,
+        zip: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct User {
+        name: String,
+        #[serde(rename = "emailAddress")]
+        email: Option<String>,
+        address: Address,
+        tags: Vec<Tag>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Tag {
+        label: String,
+    }
+
+    const USER: &str = r#"{
+        "name": "Alice",
+        "address": {"city": "Boston", "country": "US"},
+        "tags": [{"label": "a", "color": "red"}, {"label": "b", "color": "blue"}],
+        "createdAt": "2026-01-01"
+    }"#;
+
+    #[test]
+    fn it_reports_unknown_and_missing_fields_at_any_depth() -> HttpResult<()> {
+        let (user, report) = detect::<User>(USER)?;
+        assert_eq!(user.address.city, "Boston");
+        let unknown: Vec<_> = report.unknown_fields().collect();
+        assert_eq!(unknown, ["address.country", "createdAt", "tags[].color"]);
+        let missing: Vec<_> = report.missing_fields().collect();
+        assert_eq!(missing, ["address.zip", "emailAddress"]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_nothing_for_exact_matches() -> HttpResult<()> {
+        let (_, report) = detect::<Tag>(r#"{"label": "a"}"#)?;
+        assert!(report.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_returns_decode_errors_for_mismatched_types() {
+        let result = detect::<Tag>(r#"{"label": 42}"#);
+        assert!(matches!(result, Err(HttpError::Decode { .. })));
+    }
+}
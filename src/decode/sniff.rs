@@ -0,0 +1,535 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Detection of the format of response bodies.
+//!
+//! Servers do not always send what their `Content-Type` header says they
+//! do, or send no `Content-Type` at all. Proxies, load balancers, and
+//! captive portals in particular are fond of answering with an HTML error
+//! page where an API would have sent JSON. Decoding such a page as JSON
+//! fails with a syntax error that says nothing about what actually
+//! happened.
+//!
+//! [`sniff()`] inspects the first bytes of a body to work out which
+//! [`Format`] it is really in, and [`decode()`] uses the result to pick the
+//! right parser, or to return a descriptive error such as
+//! [`HttpError::HtmlErrorPage`] when the body cannot be decoded at all.
+//! [`HttpResponse::decode()`] does the same for a whole response, and
+//! [`Decoder::with_sniffing()`] adds the same diagnosis to JSON decoding
+//! failures.
+//!
+//! # Captive portals
+//!
+//! On hotel and office networks, a captive portal or intercepting proxy
+//! may answer every request with a login or "access blocked" page until
+//! the user signs in to the network. HTML pages that look like one of
+//! these, because they ask for a password, redirect the browser
+//! elsewhere, or mention Wi-Fi, hotspots, or web filtering, are reported
+//! as [`HttpError::CaptivePortal`] instead of
+//! [`HttpError::HtmlErrorPage`], along with the URL of the portal if it
+//! can be found in the page, so that users can be told to sign in rather
+//! than being shown a parsing error. So are responses with a
+//! `511 Network Authentication Required` status, which is what well-behaved
+//! portals send.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::HttpError;
+//! use hypertyper::decode::sniff::{self, Format};
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let page = b"<!DOCTYPE html><html><head><title>502 Bad Gateway</title></head></html>";
+//! assert_eq!(sniff::sniff(page), Format::Html);
+//!
+//! let err = sniff::decode::<User>(Some("application/json"), page).unwrap_err();
+//! assert!(matches!(
+//!     err,
+//!     HttpError::HtmlErrorPage { title: Some(title), .. } if title == "502 Bad Gateway"
+//! ));
+//!
+//! let user: User = sniff::decode(Some("text/plain"), br#"{"name": "foo"}"#).unwrap();
+//! assert_eq!(user.name, "foo");
+//! ```
+//!
+//! [`Decoder::with_sniffing()`]: crate::decode::Decoder::with_sniffing
+//! [`HttpResponse::decode()`]: crate::service::HttpResponse::decode
+
+use crate::decode::{DEFAULT_SNIPPET_LEN, snippet};
+use crate::{HttpError, HttpResult};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use std::fmt;
+use url::Url;
+
+/// The number of bytes at the start of a body that are inspected.
+const SNIFF_LEN: usize = 1024;
+
+/// The format of a body, as determined by [`sniff()`] or [`detect()`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Format {
+    /// A JSON document.
+    Json,
+
+    /// An XML document other than an HTML page.
+    Xml,
+
+    /// An HTML page.
+    Html,
+
+    /// Text that is not in any of the other formats.
+    Text,
+
+    /// Data that is not text.
+    Binary,
+
+    /// A body with nothing but whitespace in it.
+    Empty,
+}
+
+impl Format {
+    /// The format described by `content_type`, if it is one that can be
+    /// recognized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hypertyper::decode::sniff::Format;
+    ///
+    /// assert_eq!(Format::from_content_type("application/problem+json"), Some(Format::Json));
+    /// assert_eq!(Format::from_content_type("text/html; charset=utf-8"), Some(Format::Html));
+    /// assert_eq!(Format::from_content_type("image/png"), None);
+    /// ```
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let format = match media_type.as_str() {
+            "application/json" | "text/json" => Format::Json,
+            "text/html" | "application/xhtml+xml" => Format::Html,
+            "application/xml" | "text/xml" => Format::Xml,
+            "application/octet-stream" => Format::Binary,
+            t if t.ends_with("+json") => Format::Json,
+            t if t.ends_with("+xml") => Format::Xml,
+            t if t.starts_with("text/") => Format::Text,
+            _ => return None,
+        };
+        Some(format)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "JSON",
+            Format::Xml => "XML",
+            Format::Html => "HTML",
+            Format::Text => "plain text",
+            Format::Binary => "binary data",
+            Format::Empty => "an empty body",
+        })
+    }
+}
+
+/// Determines the format of `body` from its first bytes.
+///
+/// Objects and arrays are recognized as JSON from their first character;
+/// other JSON values are only recognized if the whole body parses. Markup
+/// is recognized as HTML if it has a doctype or one of the `html`, `head`,
+/// `body`, or `title` elements near its start, and as XML otherwise.
+pub fn sniff(body: &[u8]) -> Format {
+    let body = body.strip_prefix(b"\xef\xbb\xbf").unwrap_or(body);
+    let body = body.trim_ascii_start();
+    let Some(&first) = body.first() else {
+        return Format::Empty;
+    };
+
+    let head = &body[..body.len().min(SNIFF_LEN)];
+    if head.contains(&0) || !is_utf8_prefix(head) {
+        return Format::Binary;
+    }
+    match first {
+        b'{' | b'[' => Format::Json,
+        b'<' if is_html(head) => Format::Html,
+        b'<' if head
+            .get(1)
+            .is_some_and(|c| c.is_ascii_alphabetic() || b"?!".contains(c)) =>
+        {
+            Format::Xml
+        }
+        b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n'
+            if serde_json::from_slice::<IgnoredAny>(body).is_ok() =>
+        {
+            Format::Json
+        }
+        _ => Format::Text,
+    }
+}
+
+/// Determines the format of `body`, which was sent with `content_type`.
+///
+/// The format of the body itself takes precedence, so a JSON body sent as
+/// `text/html` is still JSON, and an HTML page sent as `application/json`
+/// is still HTML. `content_type` is only used when the body is
+/// [text](Format::Text) that could be in any format.
+pub fn detect(content_type: Option<&str>, body: &[u8]) -> Format {
+    match sniff(body) {
+        Format::Text => content_type
+            .and_then(Format::from_content_type)
+            .unwrap_or(Format::Text),
+        format => format,
+    }
+}
+
+/// Deserializes `body`, which was sent with `content_type`, as a `T`,
+/// using the parser for the format that it is actually in.
+///
+/// JSON bodies are decoded with [`decode::json()`](crate::decode::json),
+/// and, with the **xml** feature enabled, XML bodies are decoded with
+/// `decode::xml::from_str()`.
+///
+/// # Errors
+///
+/// Returns [`HttpError::HtmlErrorPage`] if `body` is an HTML page, or
+/// [`HttpError::UnexpectedFormat`] if it is in any other format that
+/// cannot be decoded. Otherwise, returns the parser's error if `body` does
+/// not match `T`.
+pub fn decode<T: DeserializeOwned>(content_type: Option<&str>, body: &[u8]) -> HttpResult<T> {
+    let format = detect(content_type, body);
+    let text = match (format, std::str::from_utf8(body)) {
+        (Format::Json | Format::Xml | Format::Text, Ok(text)) => text,
+        (Format::Html, _) => return Err(html_error_page(&String::from_utf8_lossy(body))),
+        (format, _) => return Err(unexpected_format(format, body)),
+    };
+    match format {
+        Format::Json | Format::Text => crate::decode::json(text),
+        #[cfg(feature = "xml")]
+        Format::Xml => crate::decode::xml::from_str(text),
+        format => Err(unexpected_format(format, body)),
+    }
+}
+
+/// The text of the `title` element of the HTML page `body`, if it has one.
+///
+/// # Examples
+///
+/// ```
+/// use hypertyper::decode::sniff;
+///
+/// let page = "<html><head><title>\n  Service Unavailable\n</title></head></html>";
+/// assert_eq!(sniff::html_title(page).as_deref(), Some("Service Unavailable"));
+/// ```
+pub fn html_title(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = body[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// True if the HTML page `body` looks like it was served by a captive
+/// portal or an intercepting proxy rather than by the server that was
+/// asked for.
+///
+/// # Examples
+///
+/// ```
+/// use hypertyper::decode::sniff;
+///
+/// let page = r#"<html><body><form><input type="password" name="pw"></form></body></html>"#;
+/// assert!(sniff::is_portal_page(page));
+/// assert!(!sniff::is_portal_page("<html><title>502 Bad Gateway</title></html>"));
+/// ```
+pub fn is_portal_page(body: &str) -> bool {
+    const SIGNS: [&str; 14] = [
+        "type=\"password\"",
+        "type='password'",
+        "type=password",
+        "http-equiv=\"refresh\"",
+        "http-equiv='refresh'",
+        "http-equiv=refresh",
+        "captive portal",
+        "hotspot",
+        "wi-fi",
+        "wifi",
+        "accept the terms",
+        "network login",
+        "web filter",
+        "blocked by your organization",
+    ];
+    let lower = body.to_ascii_lowercase();
+    SIGNS.iter().any(|sign| lower.contains(sign)) || portal_url(body).is_some()
+}
+
+/// The URL that the HTML page `body` sends the browser to, if it has one.
+///
+/// This is the target of a `<meta http-equiv="refresh">` tag or of a
+/// script that assigns `location` or calls `location.replace()`. Only
+/// absolute `http` and `https` URLs are returned.
+///
+/// # Examples
+///
+/// ```
+/// use hypertyper::decode::sniff;
+///
+/// let page = r#"<meta http-equiv="refresh" content="0; URL='https://portal.example.net/login'">"#;
+/// let url = sniff::portal_url(page).unwrap();
+/// assert_eq!(url.as_str(), "https://portal.example.net/login");
+/// ```
+pub fn portal_url(body: &str) -> Option<Url> {
+    let lower = body.to_ascii_lowercase().replace(" = ", "=");
+    let body = body.replace(" = ", "=");
+    // Only look for a refresh URL in pages that have a refresh tag, so
+    // that query parameters like `?return_url=` are not mistaken for one.
+    let refresh = lower.contains("http-equiv").then_some("url=");
+    let markers = ["location.href=", "location=", "location.replace("];
+    let candidates = refresh.into_iter().chain(markers).flat_map(|marker| {
+        lower
+            .match_indices(marker)
+            .map(move |(i, _)| i + marker.len())
+    });
+    candidates.into_iter().find_map(|start| {
+        let rest = body[start..].trim_start_matches([' ', '\'', '"']);
+        let end = rest
+            .find(['\'', '"', ' ', ';', ')', '>'])
+            .unwrap_or(rest.len());
+        let url = Url::parse(&rest[..end]).ok()?;
+        matches!(url.scheme(), "http" | "https").then_some(url)
+    })
+}
+
+/// Creates an [`HttpError::CaptivePortal`] if `body` looks like a captive
+/// portal's page, or an [`HttpError::HtmlErrorPage`] otherwise.
+pub(crate) fn html_error_page(body: &str) -> HttpError {
+    if is_portal_page(body) {
+        return captive_portal(body);
+    }
+    HttpError::HtmlErrorPage {
+        title: html_title(body),
+        snippet: snippet(body, DEFAULT_SNIPPET_LEN).to_string(),
+    }
+}
+
+/// Creates an [`HttpError::CaptivePortal`] for the page `body`.
+pub(crate) fn captive_portal(body: &str) -> HttpError {
+    HttpError::CaptivePortal {
+        url: portal_url(body),
+        title: html_title(body),
+    }
+}
+
+fn unexpected_format(format: Format, body: &[u8]) -> HttpError {
+    let body = String::from_utf8_lossy(body);
+    HttpError::UnexpectedFormat {
+        format,
+        snippet: snippet(&body, DEFAULT_SNIPPET_LEN).to_string(),
+    }
+}
+
+/// True if `head` is valid UTF-8, apart from a character that may have
+/// been cut off at the end.
+fn is_utf8_prefix(head: &[u8]) -> bool {
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    }
+}
+
+fn is_html(head: &[u8]) -> bool {
+    let head = head.to_ascii_lowercase();
+    head.starts_with(b"<!doctype html")
+        || [&b"<html"[..], b"<head", b"<body", b"<title"]
+            .iter()
+            .any(|tag| head.windows(tag.len()).any(|window| window == *tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct User {
+        name: String,
+    }
+
+    #[test]
+    fn it_sniffs_json() {
+        assert_eq!(sniff(b"  {\"name\": \"foo\"}"), Format::Json);
+        assert_eq!(sniff(b"\xef\xbb\xbf[1, 2]"), Format::Json);
+        assert_eq!(sniff(b"42"), Format::Json);
+        assert_eq!(sniff(b"\"quoted\""), Format::Json);
+        assert_eq!(sniff(b"null"), Format::Json);
+    }
+
+    #[test]
+    fn it_sniffs_markup() {
+        assert_eq!(sniff(b"<!DOCTYPE html>\n<p>hi</p>"), Format::Html);
+        assert_eq!(
+            sniff(b"<HTML><BODY>Bad Gateway</BODY></HTML>"),
+            Format::Html
+        );
+        assert_eq!(
+            sniff(b"<?xml version=\"1.0\"?><html xmlns=\"http://www.w3.org/1999/xhtml\"/>"),
+            Format::Html
+        );
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?><user/>"), Format::Xml);
+        assert_eq!(sniff(b"<user><name>foo</name></user>"), Format::Xml);
+    }
+
+    #[test]
+    fn it_sniffs_text_binary_and_empty_bodies() {
+        assert_eq!(sniff(b"Service Unavailable"), Format::Text);
+        assert_eq!(sniff(b"nope"), Format::Text);
+        assert_eq!(sniff(b"< 3"), Format::Text);
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\x00\x00"), Format::Binary);
+        assert_eq!(sniff(b"\xff\xfe\xfd"), Format::Binary);
+        assert_eq!(sniff(b" \r\n"), Format::Empty);
+    }
+
+    #[test]
+    fn it_tolerates_characters_cut_off_at_the_end_of_the_sniffed_bytes() {
+        let mut body = "x".repeat(SNIFF_LEN - 1);
+        body.push('é');
+        assert_eq!(sniff(body.as_bytes()), Format::Text);
+    }
+
+    #[test]
+    fn it_prefers_the_body_to_the_content_type() {
+        assert_eq!(detect(Some("application/json"), b"<html/>"), Format::Html);
+        assert_eq!(detect(Some("text/html"), b"{}"), Format::Json);
+        assert_eq!(detect(Some("application/json"), b"oops"), Format::Json);
+        assert_eq!(detect(Some("text/csv"), b"a,b"), Format::Text);
+        assert_eq!(detect(None, b"oops"), Format::Text);
+    }
+
+    #[test]
+    fn it_decodes_json_regardless_of_content_type() -> HttpResult<()> {
+        let user: User = decode(None, br#"{"name": "foo"}"#)?;
+        assert_eq!(user.name, "foo");
+        let user: User = decode(Some("text/html"), br#"{"name": "bar"}"#)?;
+        assert_eq!(user.name, "bar");
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_html_error_pages() {
+        let page = b"<html><head><title>502 Bad Gateway</title></head><body>nginx</body></html>";
+        let err = decode::<User>(Some("application/json"), page).unwrap_err();
+        assert!(matches!(
+            &err,
+            HttpError::HtmlErrorPage { title: Some(title), snippet }
+                if title == "502 Bad Gateway" && snippet.starts_with("<html>")
+        ));
+        assert!(err.to_string().contains("\"502 Bad Gateway\""));
+    }
+
+    #[test]
+    fn it_reports_bodies_that_cannot_be_decoded() {
+        let err = decode::<User>(None, b"\x00\x01\x02").unwrap_err();
+        assert!(matches!(
+            err,
+            HttpError::UnexpectedFormat {
+                format: Format::Binary,
+                ..
+            }
+        ));
+        let err = decode::<User>(Some("application/json"), b"").unwrap_err();
+        assert!(matches!(
+            err,
+            HttpError::UnexpectedFormat {
+                format: Format::Empty,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn it_decodes_text_with_the_json_parser() {
+        let err = decode::<User>(Some("text/plain"), b"Service Unavailable").unwrap_err();
+        assert!(matches!(err, HttpError::Decode { .. }));
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn it_decodes_xml() -> HttpResult<()> {
+        let user: User = decode(None, b"<user><name>foo</name></user>")?;
+        assert_eq!(user.name, "foo");
+        Ok(())
+    }
+
+    #[cfg(not(feature = "xml"))]
+    #[test]
+    fn it_reports_xml_without_the_xml_feature() {
+        let err = decode::<User>(None, b"<user><name>foo</name></user>").unwrap_err();
+        assert!(matches!(
+            err,
+            HttpError::UnexpectedFormat {
+                format: Format::Xml,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn it_reports_captive_portals() {
+        let page = br#"<html><head><title>Guest Wi-Fi</title>
+            <script>window.location.href = "http://10.0.0.1/login?orig=api";</script>
+            </head></html>"#;
+        let err = decode::<User>(Some("application/json"), page).unwrap_err();
+        assert!(matches!(
+            &err,
+            HttpError::CaptivePortal { url: Some(url), title: Some(title) }
+                if url.as_str() == "http://10.0.0.1/login?orig=api" && title == "Guest Wi-Fi"
+        ));
+        assert!(err.to_string().contains("http://10.0.0.1/login?orig=api"));
+    }
+
+    #[test]
+    fn it_finds_portal_urls() {
+        let url = |body| portal_url(body).map(String::from);
+        assert_eq!(
+            url("<meta http-equiv=refresh content=\"5;url=https://login.example.net/\">"),
+            Some(String::from("https://login.example.net/"))
+        );
+        assert_eq!(
+            url("<script>location.replace('https://portal.example.net/?a=1')</script>"),
+            Some(String::from("https://portal.example.net/?a=1"))
+        );
+        assert_eq!(
+            url("<meta http-equiv=refresh content=\"0;url=/login\">"),
+            None
+        );
+        assert_eq!(url("<a href=\"javascript:void(0)\">"), None);
+    }
+
+    #[test]
+    fn it_tells_portals_from_error_pages() {
+        assert!(is_portal_page("<p>Please accept the terms of use</p>"));
+        assert!(is_portal_page("<input TYPE=\"password\">"));
+        assert!(!is_portal_page(
+            "<html><title>404 Not Found</title><body>nginx</body></html>"
+        ));
+    }
+
+    #[test]
+    fn it_finds_html_titles() {
+        assert_eq!(
+            html_title("<TITLE lang=\"en\">Log in to Wi-Fi</TITLE>").as_deref(),
+            Some("Log in to Wi-Fi")
+        );
+        assert_eq!(html_title("<title>  </title>"), None);
+        assert_eq!(html_title("<p>no title</p>"), None);
+    }
+}
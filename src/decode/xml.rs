@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! XML request and response bodies.
+//!
+//! Some APIs, particularly older enterprise ones, speak XML rather than
+//! JSON. This module serializes request bodies to XML with [`to_string()`]
+//! and deserializes response bodies with [`from_str()`], using [quick-xml]
+//! and the same serde derives used for JSON.
+//!
+//! [`HttpGetResponse::get_xml()`] and [`HttpPostStream::post_xml()`] make
+//! requests with XML bodies in one step, with the `Content-Type` and
+//! `Accept` headers set to [`CONTENT_TYPE`].
+//!
+//! The root element of a serialized value is named after its type, which
+//! can be changed with `#[serde(rename = "...")]`. Attributes are fields
+//! whose names start with `@`, and the text content of an element is a
+//! field named `$text`, as described in the [quick-xml documentation].
+//!
+//! This module is only available with the **xml** feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::decode::xml;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Deserialize, PartialEq, Serialize)]
+//! #[serde(rename = "order")]
+//! struct Order {
+//!     #[serde(rename = "@id")]
+//!     id: u32,
+//!     item: Vec<String>,
+//! }
+//!
+//! let order = Order { id: 7, item: vec!["apple".into(), "pear".into()] };
+//! let body = xml::to_string(&order).unwrap();
+//! assert_eq!(body, r#"<order id="7"><item>apple</item><item>pear</item></order>"#);
+//! assert_eq!(xml::from_str::<Order>(&body).unwrap(), order);
+//! ```
+//!
+//! [quick-xml]: https://crates.io/crates/quick-xml
+//! [quick-xml documentation]: https://docs.rs/quick-xml/latest/quick_xml/de/index.html
+
+use crate::decode::{DEFAULT_SNIPPET_LEN, snippet};
+use crate::{HttpError, HttpResult};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+#[cfg(doc)]
+use crate::service::{HttpGetResponse, HttpPostStream};
+
+/// The content type of XML request and response bodies.
+pub const CONTENT_TYPE: &str = "application/xml";
+
+/// Deserializes the XML document `body` as a `T`.
+///
+/// # Errors
+///
+/// Returns [`HttpError::XmlDecode`] if `body` is not an XML document that
+/// matches `T`.
+pub fn from_str<T: DeserializeOwned>(body: &str) -> HttpResult<T> {
+    quick_xml::de::from_str(body).map_err(|source| HttpError::XmlDecode {
+        source,
+        snippet: snippet(body, DEFAULT_SNIPPET_LEN).to_string(),
+    })
+}
+
+/// Serializes `value` as an XML document.
+///
+/// # Errors
+///
+/// Returns [`HttpError::XmlSerialization`] if `value` cannot be represented
+/// as XML.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> HttpResult<String> {
+    Ok(quick_xml::se::to_string(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::{HttpGetResponse, HttpPostStream, HttpResponse};
+    use crate::upload::UploadBody;
+    use reqwest::header::{self, HeaderMap, HeaderValue};
+    use reqwest::{IntoUrl, StatusCode};
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    #[serde(rename = "user")]
+    struct User {
+        name: String,
+    }
+
+    /// Answers every request with `body` and `status`, and records the
+    /// headers and body of the last request.
+    struct XmlServer {
+        status: StatusCode,
+        body: &'static str,
+        request: Mutex<Option<(HeaderMap, String)>>,
+    }
+
+    impl XmlServer {
+        fn new(status: StatusCode, body: &'static str) -> Self {
+            Self {
+                status,
+                body,
+                request: Mutex::default(),
+            }
+        }
+
+        fn request(&self) -> (HeaderMap, String) {
+            self.request.lock().unwrap().clone().unwrap()
+        }
+    }
+
+    impl HttpGetResponse for XmlServer {
+        async fn get_response<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            *self.request.lock().unwrap() = Some((headers.clone(), String::new()));
+            Ok(HttpResponse::new(self.status, self.body))
+        }
+    }
+
+    impl HttpPostStream for XmlServer {
+        async fn post_stream<U>(
+            &self,
+            _uri: U,
+            headers: &HeaderMap,
+            body: UploadBody,
+        ) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            let body = String::from_utf8(body.into_bytes().await?.to_vec()).unwrap();
+            *self.request.lock().unwrap() = Some((headers.clone(), body));
+            Ok(HttpResponse::new(self.status, self.body))
+        }
+    }
+
+    #[test]
+    fn it_reports_the_beginning_of_bodies_that_cannot_be_decoded() {
+        let result = from_str::<User>("<user><age>3</age></user>");
+        assert!(matches!(
+            result,
+            Err(HttpError::XmlDecode { snippet, .. }) if snippet.starts_with("<user><age>")
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_gets_xml() -> HttpResult<()> {
+        let server = XmlServer::new(StatusCode::OK, "<user><name>foo</name></user>");
+        let user: User = server.get_xml("https://api.example.com/user").await?;
+        assert_eq!(user.name, "foo");
+        let (headers, _) = server.request();
+        assert_eq!(headers[header::ACCEPT], CONTENT_TYPE);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_posts_xml() -> HttpResult<()> {
+        let server = XmlServer::new(StatusCode::CREATED, "<user><name>bar</name></user>");
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", HeaderValue::from_static("1"));
+        let user = User {
+            name: String::from("bar"),
+        };
+        let created: User = server
+            .post_xml("https://api.example.com/users", &headers, &user)
+            .await?;
+        assert_eq!(created, user);
+
+        let (headers, body) = server.request();
+        assert_eq!(headers[header::CONTENT_TYPE], CONTENT_TYPE);
+        assert_eq!(headers[header::ACCEPT], CONTENT_TYPE);
+        assert_eq!(headers["x-request-id"], "1");
+        assert_eq!(body, "<user><name>bar</name></user>");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_on_unsuccessful_responses() {
+        let server = XmlServer::new(StatusCode::NOT_FOUND, "<error/>");
+        let result: HttpResult<User> = server.get_xml("https://api.example.com/user").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+    }
+}
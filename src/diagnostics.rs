@@ -0,0 +1,815 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Connectivity diagnostics.
+//!
+//! When a request fails on a user's machine, the underlying reqwest error
+//! rarely says which part of the path to the server is broken, or what
+//! the user can do about it. [`self_test()`] checks each step of a request
+//! to a probe endpoint in turn (proxy configuration, DNS resolution, the
+//! TCP connection, the TLS handshake, the HTTP response, and
+//! authentication) and returns a [`SelfTestReport`] saying which step
+//! failed and how it might be fixed:
+//!
+//! ```no_run
+//! use hypertyper::HttpClientFactory;
+//! use hypertyper::diagnostics;
+//!
+//! # async fn run() -> hypertyper::HttpResult<()> {
+//! let factory = HttpClientFactory::with_user_agent("my-app/1.0");
+//! let report = diagnostics::self_test(&factory, "https://api.example.com/health").await?;
+//! if let Some(check) = report.first_failure() {
+//!     eprintln!("Cannot reach the API ({}):\n{report}", check.stage());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Use [`SelfTest`] to send credentials along with the probe, so that
+//! rejected credentials are reported too, or to change how long each step
+//! may take.
+
+use crate::auth::Authenticator;
+use crate::decode::sniff::{self, Format};
+use crate::{HttpClientFactory, HttpResult};
+use reqwest::header::{self, HeaderValue};
+use reqwest::{IntoUrl, StatusCode, Url};
+use std::error::Error as StdError;
+use std::fmt;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// The default time allowed for each step of a self-test.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks connectivity to `probe_url` using clients produced by `factory`.
+///
+/// This is a shortcut for [`SelfTest::new()`] followed by
+/// [`SelfTest::run()`], without credentials and with the
+/// [default timeout](DEFAULT_TIMEOUT).
+///
+/// # Errors
+///
+/// Returns an error if `probe_url` is not a valid URL. Connectivity
+/// problems are reported in the returned [`SelfTestReport`] rather than as
+/// errors.
+pub async fn self_test(
+    factory: &HttpClientFactory,
+    probe_url: impl IntoUrl,
+) -> HttpResult<SelfTestReport> {
+    Ok(SelfTest::new(probe_url)?.run(factory).await)
+}
+
+/// Configures and runs a connectivity self-test.
+///
+/// # Examples
+///
+/// ```no_run
+/// use hypertyper::HttpClientFactory;
+/// use hypertyper::auth::Auth;
+/// use hypertyper::diagnostics::SelfTest;
+/// use std::time::Duration;
+///
+/// # async fn run() -> hypertyper::HttpResult<()> {
+/// let factory = HttpClientFactory::with_user_agent("my-app/1.0");
+/// let report = SelfTest::new("https://api.example.com/me")?
+///     .with_auth(Auth::new("ThisIsMyApiKey"))
+///     .with_timeout(Duration::from_secs(3))
+///     .run(&factory)
+///     .await;
+/// println!("{report}");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SelfTest {
+    probe_url: Url,
+    auth: Option<Box<dyn Authenticator>>,
+    timeout: Duration,
+}
+
+impl SelfTest {
+    /// Creates a self-test that probes `probe_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `probe_url` is not a valid URL.
+    pub fn new(probe_url: impl IntoUrl) -> HttpResult<Self> {
+        Ok(Self {
+            probe_url: probe_url.into_url()?,
+            auth: None,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Authenticates the probe request with `auth`, so that the self-test
+    /// reports whether the credentials are accepted.
+    pub fn with_auth(mut self, auth: impl Authenticator + 'static) -> Self {
+        self.auth = Some(Box::new(auth));
+        self
+    }
+
+    /// Sets the time allowed for each step of the self-test.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The URL that is probed.
+    pub fn probe_url(&self) -> &Url {
+        &self.probe_url
+    }
+
+    /// The time allowed for each step of the self-test.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Runs the self-test using a client produced by `factory`.
+    ///
+    /// Each step is only attempted if the steps before it passed; the
+    /// steps after a failure are reported as [skipped](Outcome::Skipped).
+    pub async fn run(&self, factory: &HttpClientFactory) -> SelfTestReport {
+        let mut report = SelfTestReport {
+            probe_url: self.probe_url.clone(),
+            checks: Vec::with_capacity(Stage::ALL.len()),
+        };
+        self.check(&mut report, factory).await;
+        for stage in Stage::ALL.iter().skip(report.checks.len()) {
+            report.checks.push(Check {
+                stage: *stage,
+                outcome: Outcome::Skipped(String::from("an earlier check failed")),
+                elapsed: Duration::ZERO,
+            });
+        }
+        report
+    }
+
+    /// Appends a check for each stage to `report`, stopping at the first
+    /// failure.
+    async fn check(&self, report: &mut SelfTestReport, factory: &HttpClientFactory) {
+        let url = &self.probe_url;
+        let https = url.scheme() == "https";
+
+        // Proxy
+        let start = Instant::now();
+        let proxy = match proxy_for(url) {
+            Ok(proxy) => proxy,
+            Err(outcome) => return report.push(Stage::Proxy, outcome, start),
+        };
+        let outcome = match &proxy {
+            Some(proxy) => Outcome::Passed(format!("using {proxy}")),
+            None => Outcome::Skipped(String::from("no proxy configured")),
+        };
+        report.push(Stage::Proxy, outcome, start);
+
+        // DNS
+        let start = Instant::now();
+        let target = proxy.as_ref().unwrap_or(url);
+        let Some(host) = target.host_str().map(str::to_string) else {
+            let outcome = Outcome::failed("the URL has no host", "Check the probe URL.");
+            return report.push(Stage::Dns, outcome, start);
+        };
+        let Some(port) = target.port_or_known_default() else {
+            let outcome = Outcome::failed(
+                format!("no default port for {}", target.scheme()),
+                "Include a port in the URL.",
+            );
+            return report.push(Stage::Dns, outcome, start);
+        };
+        let addrs = match self.blocking(resolve(host.clone(), port)).await {
+            Some(Ok(addrs)) if !addrs.is_empty() => addrs,
+            Some(Ok(_)) => {
+                let outcome = Outcome::failed(
+                    format!("{host} has no addresses"),
+                    "Check that the host name is spelled correctly.",
+                );
+                return report.push(Stage::Dns, outcome, start);
+            }
+            Some(Err(err)) => {
+                let outcome = Outcome::failed(
+                    format!("could not resolve {host}: {err}"),
+                    "Check the host name and your network connection or DNS settings.",
+                );
+                return report.push(Stage::Dns, outcome, start);
+            }
+            None => {
+                let outcome = Outcome::failed(
+                    format!("resolving {host} timed out"),
+                    "Check your network connection or DNS settings.",
+                );
+                return report.push(Stage::Dns, outcome, start);
+            }
+        };
+        let count = addrs.len();
+        let noun = if count == 1 { "address" } else { "addresses" };
+        report.push(
+            Stage::Dns,
+            Outcome::Passed(format!("{host} resolved to {count} {noun}")),
+            start,
+        );
+
+        // Connect
+        let start = Instant::now();
+        let hint = match proxy {
+            Some(_) => "Check that the proxy is running and reachable.",
+            None => "Check that the server is running and that no firewall blocks it.",
+        };
+        match self.blocking(connect(addrs, self.timeout)).await {
+            Some(Ok(addr)) => report.push(
+                Stage::Connect,
+                Outcome::Passed(format!("connected to {addr}")),
+                start,
+            ),
+            Some(Err(err)) => {
+                let outcome = Outcome::failed(format!("could not connect: {err}"), hint);
+                return report.push(Stage::Connect, outcome, start);
+            }
+            None => {
+                let outcome = Outcome::failed("connecting timed out", hint);
+                return report.push(Stage::Connect, outcome, start);
+            }
+        }
+
+        // TLS and HTTP
+        let start = Instant::now();
+        let mut request = factory.create().get(url.clone());
+        if let Some(auth) = &self.auth {
+            request = match auth.apply(request) {
+                Ok(request) => request,
+                Err(err) => {
+                    for stage in [Stage::Tls, Stage::Http] {
+                        let outcome = Outcome::Skipped(String::from("no request was sent"));
+                        report.push(stage, outcome, start);
+                    }
+                    let outcome = Outcome::failed(
+                        format!("could not add credentials to the request: {err}"),
+                        "Check that the credentials are well-formed.",
+                    );
+                    return report.push(Stage::Auth, outcome, start);
+                }
+            };
+        }
+        let response = match tokio::time::timeout(self.timeout, request.send()).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(err)) if https && is_tls_error(&err) => {
+                let outcome = Outcome::failed(
+                    format!("TLS handshake failed: {}", describe(&err)),
+                    "Check the system clock and that the server's certificate is trusted; \
+                     a proxy that intercepts TLS needs its certificate installed.",
+                );
+                return report.push(Stage::Tls, outcome, start);
+            }
+            Ok(Err(err)) => {
+                let outcome = Outcome::failed(
+                    format!("request failed: {}", describe(&err)),
+                    "Check that the probe URL points to an HTTP server.",
+                );
+                if https {
+                    report.push(Stage::Tls, unknown_tls(), start);
+                }
+                return report.push(Stage::Http, outcome, start);
+            }
+            Err(_) => {
+                let outcome =
+                    Outcome::failed("the request timed out", "The server may be overloaded.");
+                if https {
+                    report.push(Stage::Tls, unknown_tls(), start);
+                }
+                return report.push(Stage::Http, outcome, start);
+            }
+        };
+        let outcome = if https {
+            Outcome::Passed(String::from("certificate accepted"))
+        } else {
+            Outcome::Skipped(String::from("not an https URL"))
+        };
+        report.push(Stage::Tls, outcome, start);
+
+        let status = response.status();
+        if status == StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+            let outcome = Outcome::failed(
+                "the proxy requires authentication",
+                "Include a user name and password in the proxy URL.",
+            );
+            return report.push(Stage::Http, outcome, start);
+        }
+        let challenge = response
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .and_then(|value| HeaderValue::to_str(value).ok())
+            .map(|challenge| format!(" ({challenge})"))
+            .unwrap_or_default();
+        if status.is_success() || status == StatusCode::NETWORK_AUTHENTICATION_REQUIRED {
+            let body = match tokio::time::timeout(self.timeout, response.text()).await {
+                Ok(Ok(body)) => body,
+                _ => String::new(),
+            };
+            let intercepted = status == StatusCode::NETWORK_AUTHENTICATION_REQUIRED
+                || (sniff::sniff(body.as_bytes()) == Format::Html && sniff::is_portal_page(&body));
+            if intercepted {
+                let hint = match sniff::portal_url(&body) {
+                    Some(url) => format!("Sign in to the network at {url}, then try again."),
+                    None => {
+                        String::from("Sign in to the network in a web browser, then try again.")
+                    }
+                };
+                let outcome = Outcome::failed(
+                    "the request was intercepted by a captive portal or proxy",
+                    hint,
+                );
+                return report.push(Stage::Http, outcome, start);
+            }
+        }
+        if status.is_server_error() {
+            let outcome = Outcome::failed(
+                format!("the server responded with {status}"),
+                "The server is having problems; try again later.",
+            );
+            return report.push(Stage::Http, outcome, start);
+        }
+        report.push(
+            Stage::Http,
+            Outcome::Passed(format!("the server responded with {status}")),
+            start,
+        );
+
+        // Auth
+        let start = Instant::now();
+        let outcome = match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                let (reason, hint) = match (&self.auth, status) {
+                    (None, _) => (
+                        "the server requires credentials",
+                        "Supply credentials for the service.",
+                    ),
+                    (Some(_), StatusCode::UNAUTHORIZED) => (
+                        "the credentials were rejected",
+                        "Check that the credentials are correct and have not expired.",
+                    ),
+                    (Some(_), _) => (
+                        "the credentials do not grant access",
+                        "Check that the account has permission to use the service.",
+                    ),
+                };
+                Outcome::failed(format!("{reason}{challenge}"), hint)
+            }
+            _ if self.auth.is_some() => Outcome::Passed(String::from("credentials accepted")),
+            _ => Outcome::Skipped(String::from("no credentials supplied")),
+        };
+        report.push(Stage::Auth, outcome, start);
+    }
+
+    /// Runs the blocking function `f` on a blocking thread, returning
+    /// `None` if it does not finish in time.
+    async fn blocking<T, F>(&self, f: F) -> Option<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = tokio::task::spawn_blocking(f);
+        match tokio::time::timeout(self.timeout, task).await {
+            Ok(Ok(value)) => Some(value),
+            Ok(Err(err)) => std::panic::resume_unwind(err.into_panic()),
+            Err(_) => None,
+        }
+    }
+}
+
+/// A step in a connectivity [self-test](SelfTest), in the order in which
+/// they are checked.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Stage {
+    /// Parsing the proxy configuration from the environment.
+    Proxy,
+
+    /// Resolving the host name of the server, or of the proxy if one is
+    /// used.
+    Dns,
+
+    /// Opening a TCP connection to the server or proxy.
+    Connect,
+
+    /// Completing a TLS handshake with the server.
+    Tls,
+
+    /// Receiving an HTTP response that is not a server error.
+    Http,
+
+    /// Having credentials accepted by the server.
+    Auth,
+}
+
+impl Stage {
+    /// All stages, in the order in which they are checked.
+    pub const ALL: [Stage; 6] = [
+        Stage::Proxy,
+        Stage::Dns,
+        Stage::Connect,
+        Stage::Tls,
+        Stage::Http,
+        Stage::Auth,
+    ];
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Proxy => "proxy",
+            Stage::Dns => "dns",
+            Stage::Connect => "connect",
+            Stage::Tls => "tls",
+            Stage::Http => "http",
+            Stage::Auth => "auth",
+        })
+    }
+}
+
+/// The result of a single [`Check`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// The check passed, with a description of what was found.
+    Passed(String),
+
+    /// The check failed.
+    Failed {
+        /// What went wrong.
+        reason: String,
+
+        /// What the user might do about it.
+        hint: String,
+    },
+
+    /// The check was not performed, with the reason why.
+    Skipped(String),
+}
+
+impl Outcome {
+    fn failed(reason: impl Into<String>, hint: impl Into<String>) -> Self {
+        Outcome::Failed {
+            reason: reason.into(),
+            hint: hint.into(),
+        }
+    }
+}
+
+/// The outcome of one [`Stage`] of a self-test.
+#[derive(Clone, Debug)]
+pub struct Check {
+    stage: Stage,
+    outcome: Outcome,
+    elapsed: Duration,
+}
+
+impl Check {
+    /// The stage that was checked.
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// The result of the check.
+    pub fn outcome(&self) -> &Outcome {
+        &self.outcome
+    }
+
+    /// How long the check took.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// True if the check failed.
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed { .. })
+    }
+}
+
+/// The results of a connectivity [self-test](SelfTest).
+///
+/// The report contains one [`Check`] for each [`Stage`], in order. Its
+/// [`Display`](fmt::Display) implementation lists the checks one per line,
+/// with a hint beneath any that failed, suitable for showing to users.
+#[derive(Clone, Debug)]
+pub struct SelfTestReport {
+    probe_url: Url,
+    checks: Vec<Check>,
+}
+
+impl SelfTestReport {
+    /// The URL that was probed.
+    pub fn probe_url(&self) -> &Url {
+        &self.probe_url
+    }
+
+    /// The checks that make up the self-test.
+    pub fn checks(&self) -> &[Check] {
+        &self.checks
+    }
+
+    /// The check for `stage`.
+    pub fn check(&self, stage: Stage) -> Option<&Check> {
+        self.checks.iter().find(|check| check.stage == stage)
+    }
+
+    /// The check that failed, if any.
+    pub fn first_failure(&self) -> Option<&Check> {
+        self.checks.iter().find(|check| check.is_failed())
+    }
+
+    /// True if no check failed.
+    pub fn is_ok(&self) -> bool {
+        self.first_failure().is_none()
+    }
+
+    fn push(&mut self, stage: Stage, outcome: Outcome, start: Instant) {
+        self.checks.push(Check {
+            stage,
+            outcome,
+            elapsed: start.elapsed(),
+        });
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Self-test of {}", self.probe_url)?;
+        for check in &self.checks {
+            let stage = check.stage;
+            match &check.outcome {
+                Outcome::Passed(detail) => write!(f, "\n[ok]      {stage}: {detail}")?,
+                Outcome::Failed { reason, hint } => {
+                    write!(f, "\n[FAILED]  {stage}: {reason}\n          {hint}")?
+                }
+                Outcome::Skipped(reason) => write!(f, "\n[skipped] {stage}: {reason}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Finds the proxy that reqwest will use for `url`, following the same
+/// environment variables.
+fn proxy_for(url: &Url) -> Result<Option<Url>, Outcome> {
+    let host = url.host_str().unwrap_or_default();
+    if let Some(no_proxy) = env_var("NO_PROXY") {
+        let bypassed = no_proxy
+            .split(',')
+            .map(|entry| entry.trim().trim_start_matches('.'))
+            .filter(|entry| !entry.is_empty())
+            .any(|entry| {
+                entry == "*"
+                    || host == entry
+                    || host
+                        .strip_suffix(entry)
+                        .is_some_and(|rest| rest.ends_with('.'))
+            });
+        if bypassed {
+            return Ok(None);
+        }
+    }
+
+    let specific = if url.scheme() == "https" {
+        "HTTPS_PROXY"
+    } else {
+        "HTTP_PROXY"
+    };
+    let Some(proxy) = env_var(specific).or_else(|| env_var("ALL_PROXY")) else {
+        return Ok(None);
+    };
+    let parsed = if proxy.contains("://") {
+        Url::parse(&proxy)
+    } else {
+        Url::parse(&format!("http://{proxy}"))
+    };
+    parsed.map(Some).map_err(|err| {
+        Outcome::failed(
+            format!("invalid proxy URL {proxy:?}: {err}"),
+            format!("Fix or unset the {specific} environment variable."),
+        )
+    })
+}
+
+/// The outcome of the TLS stage when the request failed for another reason.
+fn unknown_tls() -> Outcome {
+    Outcome::Skipped(String::from(
+        "could not be checked separately from the request",
+    ))
+}
+
+/// Reads the environment variable `name`, or its lowercase form.
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .or_else(|_| std::env::var(name.to_ascii_lowercase()))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+fn resolve(host: String, port: u16) -> impl FnOnce() -> std::io::Result<Vec<SocketAddr>> {
+    move || Ok((host.as_str(), port).to_socket_addrs()?.collect())
+}
+
+fn connect(
+    addrs: Vec<SocketAddr>,
+    timeout: Duration,
+) -> impl FnOnce() -> std::io::Result<SocketAddr> {
+    move || {
+        let mut last_err = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(_) => return Ok(addr),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.expect("at least one address"))
+    }
+}
+
+/// True if `err` was caused by a failed TLS handshake.
+fn is_tls_error(err: &reqwest::Error) -> bool {
+    let description = describe(err).to_ascii_lowercase();
+    ["certificate", "tls", "ssl", "handshake"]
+        .iter()
+        .any(|word| description.contains(word))
+}
+
+/// Describes `err` and its sources, which hold the useful details.
+fn describe(err: &reqwest::Error) -> String {
+    let mut description = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        description.push_str(": ");
+        description.push_str(&err.to_string());
+        source = err.source();
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use temp_env::async_with_vars;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const PROXY_VARS: [(&str, Option<&str>); 8] = [
+        ("HTTP_PROXY", None),
+        ("http_proxy", None),
+        ("HTTPS_PROXY", None),
+        ("https_proxy", None),
+        ("ALL_PROXY", None),
+        ("all_proxy", None),
+        ("NO_PROXY", None),
+        ("no_proxy", None),
+    ];
+
+    /// Serves a single request with `response`, returning the request.
+    async fn serve(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            // The first connection is the TCP connect check.
+            let _ = listener.accept().await.unwrap();
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let n = stream.read(&mut request).await.unwrap();
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request[..n]).to_ascii_lowercase()
+        });
+        (format!("http://{addr}/health"), server)
+    }
+
+    fn factory() -> HttpClientFactory {
+        HttpClientFactory::with_user_agent("self-test")
+    }
+
+    #[tokio::test]
+    async fn it_passes_when_the_probe_responds() {
+        async_with_vars(PROXY_VARS, async {
+            let (url, server) = serve("HTTP/1.1 204 No Content\r\n\r\n").await;
+            let report = self_test(&factory(), url.as_str()).await.unwrap();
+            server.await.unwrap();
+
+            assert!(report.is_ok(), "{report}");
+            let stages: Vec<_> = report.checks().iter().map(Check::stage).collect();
+            assert_eq!(stages, Stage::ALL);
+            assert!(matches!(
+                report.check(Stage::Tls).unwrap().outcome(),
+                Outcome::Skipped(_)
+            ));
+            assert!(matches!(
+                report.check(Stage::Auth).unwrap().outcome(),
+                Outcome::Skipped(_)
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_reports_rejected_credentials() {
+        async_with_vars(PROXY_VARS, async {
+            let (url, server) = serve(
+                "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Bearer realm=\"api\"\r\n\
+                 Content-Length: 0\r\n\r\n",
+            )
+            .await;
+            let report = SelfTest::new(url.as_str())
+                .unwrap()
+                .with_auth(Auth::new("wrong"))
+                .run(&factory())
+                .await;
+            let request = server.await.unwrap();
+
+            assert!(request.contains("authorization: bearer wrong\r\n"));
+            let failure = report.first_failure().unwrap();
+            assert_eq!(failure.stage(), Stage::Auth);
+            assert!(matches!(
+                failure.outcome(),
+                Outcome::Failed { reason, .. }
+                    if reason == "the credentials were rejected (Bearer realm=\"api\")"
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_reports_captive_portals() {
+        async_with_vars(PROXY_VARS, async {
+            let (url, server) = serve(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 82\r\n\r\n\
+                 <html><script>window.location = 'http://portal.example.net/login';</script></html>",
+            )
+            .await;
+            let report = self_test(&factory(), url.as_str()).await.unwrap();
+            server.await.unwrap();
+
+            let failure = report.first_failure().unwrap();
+            assert_eq!(failure.stage(), Stage::Http);
+            assert!(matches!(
+                failure.outcome(),
+                Outcome::Failed { hint, .. }
+                    if hint == "Sign in to the network at http://portal.example.net/login, then try again."
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_reports_server_errors() {
+        async_with_vars(PROXY_VARS, async {
+            let (url, server) =
+                serve("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n").await;
+            let report = self_test(&factory(), url.as_str()).await.unwrap();
+            server.await.unwrap();
+
+            assert_eq!(report.first_failure().unwrap().stage(), Stage::Http);
+            assert!(matches!(
+                report.check(Stage::Auth).unwrap().outcome(),
+                Outcome::Skipped(reason) if reason == "an earlier check failed"
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_reports_unresolvable_hosts() {
+        async_with_vars(PROXY_VARS, async {
+            let report = self_test(&factory(), "https://does-not-exist.invalid/")
+                .await
+                .unwrap();
+            assert_eq!(report.first_failure().unwrap().stage(), Stage::Dns);
+            assert!(report.to_string().contains("[FAILED]  dns: "));
+            assert!(report.to_string().contains("[skipped] connect: "));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_reports_refused_connections() {
+        async_with_vars(PROXY_VARS, async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            drop(listener);
+            let report = self_test(&factory(), format!("http://{addr}/"))
+                .await
+                .unwrap();
+            assert_eq!(report.first_failure().unwrap().stage(), Stage::Connect);
+        })
+        .await;
+    }
+
+    #[test]
+    fn it_finds_proxies_in_the_environment() {
+        let url = Url::parse("https://api.example.com/").unwrap();
+        let mut vars = PROXY_VARS;
+        vars[3] = ("https_proxy", Some("proxy.example.com:3128"));
+        temp_env::with_vars(vars, || {
+            let proxy = proxy_for(&url).unwrap().unwrap();
+            assert_eq!(proxy.as_str(), "http://proxy.example.com:3128/");
+        });
+
+        vars[6] = ("NO_PROXY", Some("localhost, .example.com"));
+        temp_env::with_vars(vars, || {
+            assert_eq!(proxy_for(&url).unwrap(), None);
+        });
+    }
+}
@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Locating HTTP services.
+//!
+//! Rather than hardcoding a base URL, clients can use the helpers in this
+//! module to find the services they need to talk to, and then hand the
+//! discovered URLs to the services they construct.
+
+#[cfg(feature = "mdns")]
+pub mod mdns;
+#[cfg(feature = "srv")]
+pub mod srv;
+pub mod well_known;
+
+use thiserror::Error;
+
+/// Indicates that a service could not be discovered.
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    /// An error that occurred while browsing for services over multicast DNS.
+    #[cfg(feature = "mdns")]
+    #[error("Error while browsing for mDNS services: {0}")]
+    Mdns(#[from] mdns_sd::Error),
+
+    /// An error that occurred while resolving DNS records.
+    #[cfg(feature = "srv")]
+    #[error("Error while resolving DNS records: {0}")]
+    Dns(#[from] hickory_resolver::net::NetError),
+}
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Multicast DNS (zeroconf) service discovery.
+//!
+//! Devices on a local network, such as printers and IoT hubs, often
+//! advertise their HTTP interfaces using [DNS-SD] over multicast DNS.
+//! [`discover()`] browses the local network for such services and returns
+//! a base URL for each one it finds.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::discovery::mdns::{self, HTTP_SERVICE};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> Result<(), hypertyper::discovery::DiscoveryError> {
+//! for service in mdns::discover(HTTP_SERVICE, Duration::from_secs(3)).await? {
+//!     println!("{} is at {}", service.name(), service.base_url());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [DNS-SD]: https://www.rfc-editor.org/rfc/rfc6763
+
+use crate::discovery::DiscoveryError;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// The DNS-SD service type for plain HTTP services.
+pub const HTTP_SERVICE: &str = "_http._tcp.local.";
+
+/// The DNS-SD service type for HTTPS services.
+pub const HTTPS_SERVICE: &str = "_https._tcp.local.";
+
+/// An HTTP service that was found on the local network.
+#[derive(Clone, Debug)]
+pub struct DiscoveredService {
+    name: String,
+    hostname: String,
+    port: u16,
+    addresses: Vec<IpAddr>,
+    base_url: Url,
+}
+
+impl DiscoveredService {
+    fn from_info(info: &ServiceInfo) -> Option<Self> {
+        let name = info.get_fullname().to_string();
+        let hostname = info.get_hostname().trim_end_matches('.').to_string();
+        let port = info.get_port();
+        let mut addresses = info.get_addresses().iter().copied().collect::<Vec<_>>();
+        addresses.sort_by_key(|addr| (addr.is_ipv6(), *addr));
+
+        // DNS-SD services advertise a path in the "path" TXT record (RFC 6763 §7.2).
+        let path = info.get_property_val_str("path").unwrap_or("/");
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let scheme = if info.get_type() == HTTPS_SERVICE {
+            "https"
+        } else {
+            "http"
+        };
+        // Link-local IPv6 addresses are only reachable through the interface
+        // they were seen on, and URLs cannot carry the zone ID needed to
+        // pick it, so they are never used in the base URL.
+        let routable = addresses.iter().find(|addr| match addr {
+            IpAddr::V4(_) => true,
+            IpAddr::V6(addr) => !addr.is_unicast_link_local(),
+        });
+        let authority = match routable {
+            Some(addr) => SocketAddr::new(*addr, port).to_string(),
+            None => format!("{hostname}:{port}"),
+        };
+        let base_url = Url::parse(&format!("{scheme}://{authority}/{path}")).ok()?;
+
+        Some(Self {
+            name,
+            hostname,
+            port,
+            addresses,
+            base_url,
+        })
+    }
+
+    /// The full DNS-SD instance name of the service, such as
+    /// `"Office Printer._http._tcp.local."`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The `.local` hostname of the device providing the service.
+    pub fn hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    /// The port on which the service is listening.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The IP addresses advertised for the service, IPv4 addresses first.
+    pub fn addresses(&self) -> &[IpAddr] {
+        &self.addresses
+    }
+
+    /// The base URL of the service.
+    ///
+    /// The URL uses the first advertised IP address, since `.local`
+    /// hostnames cannot be resolved on every system, and includes the path
+    /// from the service's `path` TXT record, if there is one. Link-local
+    /// IPv6 addresses are skipped, since a URL cannot say which interface
+    /// to reach them through; if the service advertises no other address,
+    /// the URL uses its hostname instead.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+}
+
+/// Browses the local network for services of type `service_type` for the
+/// given amount of time.
+///
+/// `service_type` is usually [`HTTP_SERVICE`] or [`HTTPS_SERVICE`]. Services
+/// that are found but cannot be resolved to a URL before `timeout` elapses
+/// are not included in the result.
+pub async fn discover(
+    service_type: &str,
+    timeout: Duration,
+) -> Result<Vec<DiscoveredService>, DiscoveryError> {
+    let daemon = ServiceDaemon::new()?;
+    let receiver = daemon.browse(service_type)?;
+    let deadline = Instant::now() + timeout;
+    let mut services = HashMap::new();
+
+    while let Ok(Ok(event)) = time::timeout_at(deadline, receiver.recv_async()).await {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                if let Some(service) = DiscoveredService::from_info(&info) {
+                    services.insert(service.name.clone(), service);
+                }
+            }
+            ServiceEvent::ServiceRemoved(_, name) => {
+                services.remove(&name);
+            }
+            _ => {}
+        }
+    }
+
+    let _ = daemon.stop_browse(service_type);
+    let _ = daemon.shutdown();
+
+    let mut services = services.into_values().collect::<Vec<_>>();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(services)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(service_type: &str, addrs: &str, properties: &[(&str, &str)]) -> ServiceInfo {
+        ServiceInfo::new(
+            service_type,
+            "Printer",
+            "printer.local.",
+            addrs,
+            8080,
+            properties,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_builds_a_base_url_from_the_first_ipv4_address() {
+        let info = info(HTTP_SERVICE, "fe80::1,192.168.1.20", &[]);
+        let service = DiscoveredService::from_info(&info).unwrap();
+        assert_eq!(service.base_url().as_str(), "http://192.168.1.20:8080/");
+        assert_eq!(service.hostname(), "printer.local");
+        assert_eq!(service.name(), "Printer._http._tcp.local.");
+    }
+
+    #[test]
+    fn it_includes_the_advertised_path() {
+        let info = info(HTTPS_SERVICE, "192.168.1.20", &[("path", "/api/v1/")]);
+        let service = DiscoveredService::from_info(&info).unwrap();
+        assert_eq!(
+            service.base_url().as_str(),
+            "https://192.168.1.20:8080/api/v1/"
+        );
+    }
+
+    #[test]
+    fn it_brackets_ipv6_addresses() {
+        let info = info(HTTP_SERVICE, "fe80::1,fd00::20", &[]);
+        let service = DiscoveredService::from_info(&info).unwrap();
+        assert_eq!(service.base_url().as_str(), "http://[fd00::20]:8080/");
+    }
+
+    #[test]
+    fn it_uses_the_hostname_instead_of_link_local_addresses() {
+        let info = info(HTTP_SERVICE, "fe80::1", &[]);
+        let service = DiscoveredService::from_info(&info).unwrap();
+        assert_eq!(service.base_url().as_str(), "http://printer.local:8080/");
+        assert_eq!(service.addresses(), ["fe80::1".parse::<IpAddr>().unwrap()]);
+    }
+}
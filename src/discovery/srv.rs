@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! DNS SRV record discovery.
+//!
+//! Some services publish the hosts and ports that clients should connect to
+//! as [SRV records] under a service-specific name, such as
+//! `_api._tcp.example.com`. [`lookup()`] resolves these records and orders
+//! the targets as described in RFC 2782, so that clients can try them in
+//! turn.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::discovery::srv;
+//!
+//! # async fn run() -> Result<(), hypertyper::discovery::DiscoveryError> {
+//! let targets = srv::lookup("_api._tcp", "example.com").await?;
+//! if let Some(target) = targets.first() {
+//!     println!("Connecting to {:?}", target.base_url("https"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [SRV records]: https://www.rfc-editor.org/rfc/rfc2782
+
+use crate::discovery::DiscoveryError;
+use hickory_resolver::TokioResolver;
+use hickory_resolver::proto::rr::RData;
+use reqwest::Url;
+
+/// A host and port published in an SRV record.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SrvTarget {
+    host: String,
+    port: u16,
+    priority: u16,
+    weight: u16,
+}
+
+impl SrvTarget {
+    /// Creates a new target.
+    pub fn new(host: impl Into<String>, port: u16, priority: u16, weight: u16) -> Self {
+        let host = host.into().trim_end_matches('.').to_string();
+        Self {
+            host,
+            port,
+            priority,
+            weight,
+        }
+    }
+
+    /// The hostname of the target.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The port on which the target is listening.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The target's priority; targets with lower values should be tried
+    /// first.
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// The target's relative weight among targets with the same priority.
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// The base URL of the target using the given scheme, such as `"https"`.
+    ///
+    /// Returns `None` if the target cannot be represented as a URL.
+    pub fn base_url(&self, scheme: &str) -> Option<Url> {
+        Url::parse(&format!("{scheme}://{}:{}/", self.host, self.port)).ok()
+    }
+}
+
+/// Looks up the SRV records for `service` (such as `"_api._tcp"`) on
+/// `domain` and returns the targets in the order they should be tried.
+///
+/// An SRV record with a target of `"."` means the service is explicitly not
+/// available, in which case an empty list is returned.
+pub async fn lookup(service: &str, domain: &str) -> Result<Vec<SrvTarget>, DiscoveryError> {
+    let resolver = TokioResolver::builder_tokio()?.build()?;
+    let name = format!("{service}.{}.", domain.trim_end_matches('.'));
+    let lookup = resolver.srv_lookup(name).await?;
+    let targets = lookup
+        .answers()
+        .iter()
+        .filter_map(|record| match &record.data {
+            RData::SRV(srv) => Some(SrvTarget::new(
+                srv.target.to_utf8(),
+                srv.port,
+                srv.priority,
+                srv.weight,
+            )),
+            _ => None,
+        })
+        .filter(|target| !target.host.is_empty())
+        .collect();
+    Ok(order(targets))
+}
+
+/// Orders targets by ascending priority and, within each priority, by a
+/// weighted random selection, as described in RFC 2782.
+pub fn order(mut targets: Vec<SrvTarget>) -> Vec<SrvTarget> {
+    targets.sort_by_key(|target| target.priority);
+    let mut ordered = Vec::with_capacity(targets.len());
+    while !targets.is_empty() {
+        let priority = targets[0].priority;
+        let end = targets
+            .iter()
+            .position(|target| target.priority != priority)
+            .unwrap_or(targets.len());
+        let mut group = targets.drain(..end).collect::<Vec<_>>();
+        while !group.is_empty() {
+            let total = group.iter().map(|t| u32::from(t.weight)).sum::<u32>();
+            let mut pick = fastrand::u32(0..=total);
+            let index = group
+                .iter()
+                .position(|target| {
+                    let weight = u32::from(target.weight);
+                    if pick <= weight {
+                        true
+                    } else {
+                        pick -= weight;
+                        false
+                    }
+                })
+                .unwrap_or(group.len() - 1);
+            ordered.push(group.remove(index));
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_orders_targets_by_priority() {
+        let targets = vec![
+            SrvTarget::new("c.example.com.", 443, 20, 0),
+            SrvTarget::new("a.example.com.", 443, 10, 5),
+            SrvTarget::new("b.example.com.", 443, 10, 5),
+        ];
+        let ordered = order(targets);
+        assert_eq!(ordered[2].host(), "c.example.com");
+        assert!(ordered[..2].iter().all(|target| target.priority() == 10));
+    }
+
+    #[test]
+    fn it_keeps_every_target_when_ordering() {
+        let targets = (0..10)
+            .map(|i| SrvTarget::new(format!("{i}.example.com"), 443, i % 3, i))
+            .collect::<Vec<_>>();
+        let mut ordered = order(targets.clone());
+        assert!(ordered.is_sorted_by_key(|target| target.priority()));
+        ordered.sort_by_key(|target| target.weight());
+        assert_eq!(ordered, targets);
+    }
+
+    #[test]
+    fn it_builds_base_urls() {
+        let target = SrvTarget::new("api.example.com.", 8443, 0, 0);
+        let url = target.base_url("https").unwrap();
+        assert_eq!(url.as_str(), "https://api.example.com:8443/");
+    }
+}
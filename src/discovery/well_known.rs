@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Discovery of well-known URIs.
+//!
+//! [RFC 8615] reserves the `/.well-known/` path prefix on every origin for
+//! site-wide metadata, which lets clients bootstrap their configuration
+//! from nothing more than a domain name. [`fetch()`] retrieves and
+//! deserializes any such document, and [`openid_configuration()`] fetches
+//! an OpenID Connect provider's metadata.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::discovery::well_known;
+//!
+//! let url = well_known::url("example.com", "security.txt").unwrap();
+//! assert_eq!(url.as_str(), "https://example.com/.well-known/security.txt");
+//! ```
+//!
+//! [RFC 8615]: https://www.rfc-editor.org/rfc/rfc8615
+
+use crate::HttpResult;
+use crate::decode;
+use crate::service::HttpGet;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+/// Builds the URL of the well-known resource `name` on the given origin.
+///
+/// `origin` may be a bare domain name, in which case HTTPS is assumed, or a
+/// URL, in which case any path, query, or fragment is discarded.
+pub fn url(origin: &str, name: &str) -> HttpResult<Url> {
+    let mut url = origin_url(origin)?;
+    url.set_path(&format!("/.well-known/{}", name.trim_start_matches('/')));
+    url.set_query(None);
+    url.set_fragment(None);
+    Ok(url)
+}
+
+/// Fetches the well-known resource `name` from the given origin and
+/// deserializes it from JSON.
+pub async fn fetch<S, T>(service: &S, origin: &str, name: &str) -> HttpResult<T>
+where
+    S: HttpGet,
+    T: DeserializeOwned,
+{
+    let body = service.get(url(origin, name)?).await?;
+    decode::json(&body)
+}
+
+/// Fetches the OpenID Connect provider metadata for `issuer`.
+///
+/// Unlike other well-known resources, OpenID Connect metadata lives
+/// _beneath_ the issuer's path rather than at the root of its origin, so
+/// an issuer of `https://example.com/tenant` has its configuration at
+/// `https://example.com/tenant/.well-known/openid-configuration`.
+pub async fn openid_configuration<S>(service: &S, issuer: &str) -> HttpResult<OpenIdConfiguration>
+where
+    S: HttpGet,
+{
+    let mut url = origin_url(issuer)?;
+    let path = format!(
+        "{}/.well-known/openid-configuration",
+        url.path().trim_end_matches('/')
+    );
+    url.set_path(&path);
+    let body = service.get(url).await?;
+    decode::json(&body)
+}
+
+fn origin_url(origin: &str) -> HttpResult<Url> {
+    let url = if origin.contains("://") {
+        Url::parse(origin)?
+    } else {
+        Url::parse(&format!("https://{origin}"))?
+    };
+    Ok(url)
+}
+
+/// OpenID Connect provider metadata, as described in [OpenID Connect
+/// Discovery 1.0].
+///
+/// Only the most commonly used fields are included.
+///
+/// [OpenID Connect Discovery 1.0]: https://openid.net/specs/openid-connect-discovery-1_0.html#ProviderMetadata
+#[derive(Clone, Debug, Deserialize)]
+pub struct OpenIdConfiguration {
+    /// The URL that identifies the provider and appears in the `iss` claim
+    /// of the tokens it issues.
+    pub issuer: String,
+
+    /// The URL of the provider's OAuth 2.0 authorization endpoint.
+    pub authorization_endpoint: String,
+
+    /// The URL of the provider's OAuth 2.0 token endpoint.
+    pub token_endpoint: Option<String>,
+
+    /// The URL of the provider's user info endpoint.
+    pub userinfo_endpoint: Option<String>,
+
+    /// The URL of the provider's JSON Web Key Set.
+    pub jwks_uri: String,
+
+    /// The URL of the provider's OAuth 2.0 device authorization endpoint.
+    pub device_authorization_endpoint: Option<String>,
+
+    /// The OAuth 2.0 scopes the provider supports.
+    #[serde(default)]
+    pub scopes_supported: Vec<String>,
+
+    /// The OAuth 2.0 response types the provider supports.
+    #[serde(default)]
+    pub response_types_supported: Vec<String>,
+
+    /// The OAuth 2.0 grant types the provider supports.
+    #[serde(default)]
+    pub grant_types_supported: Vec<String>,
+
+    /// The signing algorithms the provider may use for ID tokens.
+    #[serde(default)]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use reqwest::{IntoUrl, StatusCode};
+
+    struct ProviderService;
+
+    impl HttpGet for ProviderService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            match uri.as_str() {
+                "https://login.example.com/tenant/.well-known/openid-configuration" => {
+                    Ok(String::from(
+                        r#"{
+                            "issuer": "https://login.example.com/tenant",
+                            "authorization_endpoint": "https://login.example.com/tenant/authorize",
+                            "token_endpoint": "https://login.example.com/tenant/token",
+                            "jwks_uri": "https://login.example.com/tenant/keys",
+                            "scopes_supported": ["openid", "email"]
+                        }"#,
+                    ))
+                }
+                _ => Err(HttpError::Http(StatusCode::NOT_FOUND)),
+            }
+        }
+    }
+
+    #[test]
+    fn it_builds_well_known_urls_at_the_origin_root() -> HttpResult<()> {
+        let url = url("https://example.com/some/path?q=1", "/change-password")?;
+        assert_eq!(
+            url.as_str(),
+            "https://example.com/.well-known/change-password"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fetches_openid_configuration_beneath_the_issuer_path() -> HttpResult<()> {
+        let config =
+            openid_configuration(&ProviderService, "https://login.example.com/tenant/").await?;
+        assert_eq!(config.issuer, "https://login.example.com/tenant");
+        assert_eq!(config.jwks_uri, "https://login.example.com/tenant/keys");
+        assert_eq!(config.userinfo_endpoint, None);
+        assert_eq!(config.scopes_supported, vec!["openid", "email"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_returns_errors_for_missing_resources() {
+        let result: HttpResult<OpenIdConfiguration> = fetch(
+            &ProviderService,
+            "login.example.com",
+            "openid-configuration",
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+    }
+}
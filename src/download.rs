@@ -0,0 +1,641 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Downloading files.
+//!
+//! A [`Downloader`] streams response bodies straight to files, so that
+//! large downloads do not have to fit in memory. Each download is written
+//! to a temporary file next to its destination and only renamed into place
+//! once it is complete and its [checksum](Checksum) has been verified, so a
+//! failed or interrupted download never leaves a partial file behind.
+//!
+//! Large files from servers that support range requests can also be
+//! [downloaded in segments](Downloader::download_segmented) that are
+//! fetched concurrently and retried individually. To bound the number of
+//! connections this opens, wrap the service in a
+//! [`ConcurrencyLimitService`](crate::service::concurrency::ConcurrencyLimitService)
+//! and set the [connection pool size](crate::HttpClientFactory::pool_max_idle_per_host)
+//! of its client to match.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::download::{Checksum, Downloader};
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetStream;
+//!
+//! # async fn run<S: HttpGetStream + Sync>(service: S) -> HttpResult<()> {
+//! let downloader = Downloader::new(service).on_progress(|downloaded, total| {
+//!     if let Some(total) = total {
+//!         eprint!("\r{}%", downloaded * 100 / total.max(1));
+//!     }
+//! });
+//! let checksum = Checksum::sha256("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+//! downloader
+//!     .download_verified("https://example.com/release.tar.gz", "release.tar.gz", &checksum)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::service::retry::RetryPolicy;
+use crate::service::{HttpGetRange, HttpGetStream};
+use crate::{HttpError, HttpResult};
+use futures_util::{StreamExt, future};
+use reqwest::{IntoUrl, Method};
+use sha2::{Digest, Sha256};
+use std::fmt::{self, Write};
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+type ProgressCallback = Arc<dyn Fn(u64, Option<u64>) + Send + Sync>;
+
+/// The expected checksum of a downloaded file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Checksum {
+    /// A SHA-256 digest, as a hexadecimal string.
+    Sha256(String),
+}
+
+impl Checksum {
+    /// A SHA-256 digest given as a hexadecimal string, in either case.
+    pub fn sha256(hex: impl Into<String>) -> Self {
+        Self::Sha256(hex.into().to_ascii_lowercase())
+    }
+
+    fn expected(&self) -> &str {
+        match self {
+            Self::Sha256(hex) => hex,
+        }
+    }
+}
+
+/// Streams response bodies to files.
+///
+/// See the [module documentation](crate::download) for details.
+#[derive(Clone)]
+pub struct Downloader<S> {
+    service: S,
+    progress: Option<ProgressCallback>,
+    segments: usize,
+    segment_retries: RetryPolicy,
+}
+
+impl<S> Downloader<S> {
+    /// Creates a downloader that makes requests with `service`.
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            progress: None,
+            segments: 4,
+            segment_retries: RetryPolicy::new(),
+        }
+    }
+
+    /// Calls `progress` after each chunk is written, with the number of
+    /// bytes downloaded so far and the total size of the download, if the
+    /// server sent a `Content-Length` header.
+    pub fn on_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Splits [segmented downloads](Self::download_segmented) into
+    /// `segments` ranges, which are fetched concurrently. The default is 4.
+    pub fn with_segments(mut self, segments: usize) -> Self {
+        self.segments = segments.max(1);
+        self
+    }
+
+    /// Retries each segment of a
+    /// [segmented download](Self::download_segmented) according to
+    /// `policy`, resuming from the last byte received.
+    pub fn with_segment_retries(mut self, policy: RetryPolicy) -> Self {
+        self.segment_retries = policy;
+        self
+    }
+
+    /// The service used to make requests.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+}
+
+impl<S: HttpGetStream + Sync> Downloader<S> {
+    /// Downloads `uri` to `path`, replacing any existing file, and returns
+    /// the number of bytes downloaded.
+    pub async fn download<U, P>(&self, uri: U, path: P) -> HttpResult<u64>
+    where
+        U: IntoUrl + Send,
+        P: AsRef<Path>,
+    {
+        self.download_to(uri, path.as_ref(), None).await
+    }
+
+    /// Downloads `uri` to `path`, like [`download()`](Self::download), but
+    /// fails with [`HttpError::ChecksumMismatch`] without touching `path`
+    /// if the downloaded data does not match `checksum`.
+    pub async fn download_verified<U, P>(
+        &self,
+        uri: U,
+        path: P,
+        checksum: &Checksum,
+    ) -> HttpResult<u64>
+    where
+        U: IntoUrl + Send,
+        P: AsRef<Path>,
+    {
+        self.download_to(uri, path.as_ref(), Some(checksum)).await
+    }
+
+    async fn download_to<U>(
+        &self,
+        uri: U,
+        path: &Path,
+        checksum: Option<&Checksum>,
+    ) -> HttpResult<u64>
+    where
+        U: IntoUrl + Send,
+    {
+        let body = self.service.get_stream(uri).await?;
+        let tmp = temp_path(path);
+        let result = async {
+            let total = body.content_length();
+            let mut body = body;
+            let mut file = File::create(&tmp).await?;
+            let mut hasher = Sha256::new();
+            let mut downloaded = 0;
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk?;
+                file.write_all(&chunk).await?;
+                hasher.update(&chunk);
+                downloaded += chunk.len() as u64;
+                if let Some(progress) = &self.progress {
+                    progress(downloaded, total);
+                }
+            }
+            file.sync_all().await?;
+            drop(file);
+
+            if let Some(checksum) = checksum {
+                verify(checksum, hex(&hasher.finalize()))?;
+            }
+            fs::rename(&tmp, path).await?;
+            Ok(downloaded)
+        }
+        .await;
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp).await;
+        }
+        result
+    }
+}
+
+impl<S: HttpGetRange + Sync> Downloader<S> {
+    /// Downloads `uri` to `path` in several ranges fetched concurrently,
+    /// replacing any existing file, and returns the number of bytes
+    /// downloaded.
+    ///
+    /// Each range is [retried](Self::with_segment_retries) independently,
+    /// so a dropped connection only costs the rest of its own segment. If
+    /// the server does not support range requests, the file is downloaded
+    /// in one piece, as with [`download()`](Self::download).
+    pub async fn download_segmented<U, P>(&self, uri: U, path: P) -> HttpResult<u64>
+    where
+        U: IntoUrl + Send,
+        P: AsRef<Path>,
+    {
+        self.download_segments(uri.as_str(), path.as_ref(), None)
+            .await
+    }
+
+    /// Downloads `uri` to `path` in segments, like
+    /// [`download_segmented()`](Self::download_segmented), but fails with
+    /// [`HttpError::ChecksumMismatch`] without touching `path` if the
+    /// downloaded data does not match `checksum`.
+    pub async fn download_segmented_verified<U, P>(
+        &self,
+        uri: U,
+        path: P,
+        checksum: &Checksum,
+    ) -> HttpResult<u64>
+    where
+        U: IntoUrl + Send,
+        P: AsRef<Path>,
+    {
+        self.download_segments(uri.as_str(), path.as_ref(), Some(checksum))
+            .await
+    }
+
+    async fn download_segments(
+        &self,
+        uri: &str,
+        path: &Path,
+        checksum: Option<&Checksum>,
+    ) -> HttpResult<u64> {
+        let total = match self.service.range_length(uri).await? {
+            Some(total) if total > 1 && self.segments > 1 => total,
+            _ => return self.download_to(uri, path, checksum).await,
+        };
+        let tmp = temp_path(path);
+        let result = async {
+            File::create(&tmp).await?.set_len(total).await?;
+            let downloaded = AtomicU64::new(0);
+            let segments = segment_ranges(total, self.segments)
+                .map(|range| self.download_segment(uri, &tmp, range, total, &downloaded));
+            future::try_join_all(segments).await?;
+
+            if let Some(checksum) = checksum {
+                verify(checksum, sha256_file(&tmp).await?)?;
+            }
+            fs::rename(&tmp, path).await?;
+            Ok(total)
+        }
+        .await;
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp).await;
+        }
+        result
+    }
+
+    /// Writes `range` of `uri` to the same range of the file at `tmp`,
+    /// retrying from the last byte written if the request fails.
+    async fn download_segment(
+        &self,
+        uri: &str,
+        tmp: &Path,
+        range: Range<u64>,
+        total: u64,
+        downloaded: &AtomicU64,
+    ) -> HttpResult<()> {
+        let mut file = OpenOptions::new().write(true).open(tmp).await?;
+        let mut offset = range.start;
+        let mut attempts = 1;
+        loop {
+            let result = self
+                .write_range(uri, &mut file, &mut offset, range.end, total, downloaded)
+                .await;
+            let delay = match result {
+                Err(err)
+                    if self
+                        .segment_retries
+                        .should_retry(&Method::GET, &err, attempts) =>
+                {
+                    self.segment_retries.delay(attempts, &err)
+                }
+                Err(err) => return Err(err),
+                Ok(()) => break,
+            };
+            tokio::time::sleep(delay).await;
+            attempts += 1;
+        }
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    async fn write_range(
+        &self,
+        uri: &str,
+        file: &mut File,
+        offset: &mut u64,
+        end: u64,
+        total: u64,
+        downloaded: &AtomicU64,
+    ) -> HttpResult<()> {
+        let mut body = self.service.get_range(uri, *offset..end).await?;
+        file.seek(SeekFrom::Start(*offset)).await?;
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            // Never write past the end of the segment, even if the server
+            // sends more than was asked for.
+            let len = chunk.len().min((end - *offset) as usize);
+            file.write_all(&chunk[..len]).await?;
+            *offset += len as u64;
+            let so_far = downloaded.fetch_add(len as u64, Ordering::Relaxed) + len as u64;
+            if let Some(progress) = &self.progress {
+                progress(so_far, Some(total));
+            }
+            if *offset == end {
+                return Ok(());
+            }
+        }
+        if *offset < end {
+            let err = io::Error::new(io::ErrorKind::UnexpectedEof, "segment ended early");
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for Downloader<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Downloader")
+            .field("service", &self.service)
+            .field("segments", &self.segments)
+            .field("segment_retries", &self.segment_retries)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Splits `0..total` into `segments` contiguous ranges of nearly equal
+/// length.
+fn segment_ranges(total: u64, segments: usize) -> impl Iterator<Item = Range<u64>> {
+    let segments = (segments as u64).min(total).max(1);
+    (0..segments).map(move |i| (total * i / segments)..(total * (i + 1) / segments))
+}
+
+fn verify(checksum: &Checksum, actual: String) -> HttpResult<()> {
+    if actual == checksum.expected() {
+        Ok(())
+    } else {
+        let expected = checksum.expected().to_string();
+        Err(HttpError::ChecksumMismatch { expected, actual })
+    }
+}
+
+async fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(hex(&hasher.finalize()));
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+/// A hidden temporary file in the same directory as `path`, so that it can
+/// be renamed to `path` atomically.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{:08x}.part", fastrand::u32(..)))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::BodyStream;
+    use bytes::Bytes;
+    use futures_util::stream;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    const HELLO_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    /// Serves "hello world" in two chunks.
+    struct ChunkedService {
+        content_length: Option<u64>,
+        fail_midway: bool,
+    }
+
+    impl HttpGetStream for ChunkedService {
+        async fn get_stream<U>(&self, _uri: U) -> HttpResult<BodyStream>
+        where
+            U: IntoUrl + Send,
+        {
+            let second = if self.fail_midway {
+                Err(HttpError::Timeout(Duration::from_secs(1)))
+            } else {
+                Ok(Bytes::from_static(b" world"))
+            };
+            let chunks = stream::iter([Ok(Bytes::from_static(b"hello")), second]);
+            Ok(BodyStream::new(chunks).with_content_length(self.content_length))
+        }
+    }
+
+    /// Serves ranges of "hello world", cutting off the first response for
+    /// each range that starts at one of `flaky_starts` after one byte.
+    struct RangeService {
+        ranged: bool,
+        flaky_starts: Mutex<Vec<u64>>,
+        ranges: Mutex<Vec<Range<u64>>>,
+    }
+
+    impl RangeService {
+        fn new(flaky_starts: &[u64]) -> Self {
+            Self {
+                ranged: true,
+                flaky_starts: Mutex::new(flaky_starts.to_vec()),
+                ranges: Mutex::default(),
+            }
+        }
+
+        fn ranges(&self) -> Vec<Range<u64>> {
+            let mut ranges = self.ranges.lock().unwrap().clone();
+            ranges.sort_by_key(|range| (range.start, range.end));
+            ranges
+        }
+    }
+
+    impl HttpGetStream for RangeService {
+        async fn get_stream<U>(&self, _uri: U) -> HttpResult<BodyStream>
+        where
+            U: IntoUrl + Send,
+        {
+            let chunks = stream::iter([Ok(Bytes::from_static(b"hello world"))]);
+            Ok(BodyStream::new(chunks))
+        }
+    }
+
+    impl HttpGetRange for RangeService {
+        async fn range_length<U>(&self, _uri: U) -> HttpResult<Option<u64>>
+        where
+            U: IntoUrl + Send,
+        {
+            Ok(Some(11).filter(|_| self.ranged))
+        }
+
+        async fn get_range<U>(&self, _uri: U, range: Range<u64>) -> HttpResult<BodyStream>
+        where
+            U: IntoUrl + Send,
+        {
+            self.ranges.lock().unwrap().push(range.clone());
+            let data = &b"hello world"[range.start as usize..range.end as usize];
+            let mut flaky_starts = self.flaky_starts.lock().unwrap();
+            let chunks = match flaky_starts.iter().position(|&start| start == range.start) {
+                Some(i) => {
+                    flaky_starts.remove(i);
+                    let err = HttpError::Timeout(std::time::Duration::from_secs(1));
+                    vec![Ok(Bytes::copy_from_slice(&data[..1])), Err(err)]
+                }
+                None => vec![Ok(Bytes::copy_from_slice(data))],
+            };
+            Ok(BodyStream::new(stream::iter(chunks)))
+        }
+    }
+
+    fn no_backoff() -> RetryPolicy {
+        RetryPolicy::new().with_backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    fn service() -> ChunkedService {
+        ChunkedService {
+            content_length: Some(11),
+            fail_midway: false,
+        }
+    }
+
+    fn files_in(dir: &Path) -> Vec<String> {
+        let mut files: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[tokio::test]
+    async fn it_downloads_to_a_file_and_reports_progress() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hello.txt");
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&progress);
+        let downloader = Downloader::new(service())
+            .on_progress(move |downloaded, total| sink.lock().unwrap().push((downloaded, total)));
+
+        let checksum = Checksum::sha256(HELLO_SHA256.to_uppercase());
+        let len = downloader
+            .download_verified("https://example.com/hello.txt", &path, &checksum)
+            .await?;
+        assert_eq!(len, 11);
+        assert_eq!(std::fs::read_to_string(&path)?, "hello world");
+        assert_eq!(*progress.lock().unwrap(), [(5, Some(11)), (11, Some(11))]);
+        assert_eq!(files_in(dir.path()), ["hello.txt"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_keeps_the_existing_file_when_the_checksum_does_not_match() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hello.txt");
+        std::fs::write(&path, "old")?;
+
+        let checksum = Checksum::sha256("00");
+        let result = Downloader::new(service())
+            .download_verified("https://example.com/hello.txt", &path, &checksum)
+            .await;
+        assert!(
+            matches!(result, Err(HttpError::ChecksumMismatch { actual, .. }) if actual == HELLO_SHA256)
+        );
+        assert_eq!(std::fs::read_to_string(&path)?, "old");
+        assert_eq!(files_in(dir.path()), ["hello.txt"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_cleans_up_after_failed_downloads() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hello.txt");
+        let service = ChunkedService {
+            content_length: None,
+            fail_midway: true,
+        };
+        let result = Downloader::new(service)
+            .download("https://example.com/hello.txt", &path)
+            .await;
+        assert!(matches!(result, Err(HttpError::Timeout(_))));
+        assert!(files_in(dir.path()).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_splits_downloads_into_even_segments() {
+        let ranges: Vec<_> = segment_ranges(11, 4).collect();
+        assert_eq!(ranges, [0..2, 2..5, 5..8, 8..11]);
+        let ranges: Vec<_> = segment_ranges(2, 4).collect();
+        assert_eq!(ranges, [0..1, 1..2]);
+    }
+
+    #[tokio::test]
+    async fn it_downloads_segments_concurrently() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hello.txt");
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&progress);
+        let downloader = Downloader::new(RangeService::new(&[]))
+            .with_segments(3)
+            .on_progress(move |downloaded, total| sink.lock().unwrap().push((downloaded, total)));
+
+        let checksum = Checksum::sha256(HELLO_SHA256);
+        let len = downloader
+            .download_segmented_verified("https://example.com/hello.txt", &path, &checksum)
+            .await?;
+        assert_eq!(len, 11);
+        assert_eq!(std::fs::read_to_string(&path)?, "hello world");
+        assert_eq!(downloader.service().ranges(), [0..3, 3..7, 7..11]);
+        assert_eq!(progress.lock().unwrap().last(), Some(&(11, Some(11))));
+        assert_eq!(files_in(dir.path()), ["hello.txt"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_resumes_failed_segments() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hello.txt");
+        let downloader = Downloader::new(RangeService::new(&[3, 4]))
+            .with_segments(3)
+            .with_segment_retries(no_backoff());
+        downloader
+            .download_segmented("https://example.com/hello.txt", &path)
+            .await?;
+        assert_eq!(std::fs::read_to_string(&path)?, "hello world");
+        assert_eq!(
+            downloader.service().ranges(),
+            [0..3, 3..7, 4..7, 5..7, 7..11]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_on_segments_that_keep_failing() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hello.txt");
+        let downloader = Downloader::new(RangeService::new(&[0, 1]))
+            .with_segments(2)
+            .with_segment_retries(no_backoff().with_max_attempts(2));
+        let result = downloader
+            .download_segmented("https://example.com/hello.txt", &path)
+            .await;
+        assert!(matches!(result, Err(HttpError::Timeout(_))));
+        assert!(files_in(dir.path()).is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_falls_back_to_a_single_request_without_range_support() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hello.txt");
+        let service = RangeService {
+            ranged: false,
+            ..RangeService::new(&[])
+        };
+        let downloader = Downloader::new(service);
+        downloader
+            .download_segmented("https://example.com/hello.txt", &path)
+            .await?;
+        assert_eq!(std::fs::read_to_string(&path)?, "hello world");
+        assert!(downloader.service().ranges().is_empty());
+        Ok(())
+    }
+}
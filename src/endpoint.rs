@@ -0,0 +1,445 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Static tables of API endpoints.
+//!
+//! API clients with many endpoints tend to scatter their paths and types
+//! across dozens of methods that each build a URI with `format!()`. The
+//! [`endpoints!`](crate::endpoints) macro collects them into a single
+//! declaration instead. Each endpoint becomes a unit struct implementing
+//! [`Endpoint`], whose method, path template, and request and response
+//! types are fixed at compile time:
+//!
+//! ```
+//! use hypertyper::endpoint::Endpoint;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! pub struct User {
+//!     pub name: String,
+//! }
+//!
+//! #[derive(Serialize)]
+//! pub struct NewUser {
+//!     pub name: String,
+//! }
+//!
+//! hypertyper::endpoints! {
+//!     /// The endpoints of the example API.
+//!     pub mod api {
+//!         /// Fetches a user.
+//!         GetUser: GET "/users/{id}" => User;
+//!
+//!         /// Creates a user.
+//!         CreateUser: POST "/users" (NewUser) => User;
+//!
+//!         /// Fetches one of a user's posts.
+//!         GetPost: GET "/users/{user}/posts/{post}" => serde_json::Value;
+//!     }
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(api::GetPost::path(["jane doe", "42"]), "/users/jane%20doe/posts/42");
+//! assert_eq!(
+//!     api::GetUser::uri("https://api.example.com", ["7"]),
+//!     "https://api.example.com/users/7"
+//! );
+//! assert_eq!(api::CreateUser::METHOD, reqwest::Method::POST);
+//!
+//! // Every endpoint, in declaration order, for generating docs or tests.
+//! let paths: Vec<_> = api::ENDPOINTS.iter().map(|e| e.path).collect();
+//! assert_eq!(paths, ["/users/{id}", "/users", "/users/{user}/posts/{post}"]);
+//! # }
+//! ```
+//!
+//! Parameters in a path template are written in braces, and are filled in
+//! from an array whose length is checked by the compiler, so an endpoint
+//! cannot be called with too few or too many parameters. Parameters are
+//! percent-encoded, and paths are rendered into a single allocation of the
+//! right size rather than through the formatting machinery.
+//!
+//! `GET` and `POST` endpoints also get a `call()` function that makes the
+//! request with an [`HttpGet`] or [`HttpPost`] service and deserializes the
+//! response. Because every endpoint is its own type, calls are resolved
+//! statically, with no lookups at runtime. Endpoints with other methods
+//! only build URIs, which can be used with the other service traits.
+//!
+//! ```no_run
+//! # use hypertyper::prelude::*;
+//! # #[derive(serde::Deserialize)] pub struct User;
+//! # #[derive(serde::Serialize)] pub struct NewUser { name: String }
+//! # hypertyper::endpoints! {
+//! #     pub mod api {
+//! #         GetUser: GET "/users/{id}" => User;
+//! #         CreateUser: POST "/users" (NewUser) => User;
+//! #     }
+//! # }
+//! # fn main() {}
+//! # async fn run<S: HttpGet + HttpPost + Sync>(service: S, auth: Auth) -> HttpResult<()> {
+//! let base = "https://api.example.com";
+//! let user = api::GetUser::call(&service, base, ["7"]).await?;
+//! let new_user = NewUser { name: String::from("Jane") };
+//! let created = api::CreateUser::call(&service, base, [], &auth, &new_user).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use reqwest::Method;
+
+#[cfg(doc)]
+use crate::service::{HttpGet, HttpPost};
+
+/// An API endpoint declared with [`endpoints!`](crate::endpoints).
+pub trait Endpoint {
+    /// The name of the endpoint, which is the name of its type.
+    const NAME: &'static str;
+
+    /// The HTTP method used to call the endpoint.
+    const METHOD: Method;
+
+    /// The path template of the endpoint, such as `/users/{id}`.
+    const PATH: &'static str;
+
+    /// The number of parameters in the path template.
+    const PARAMS: usize = param_count(Self::PATH);
+
+    /// A description of the endpoint, for enumerating endpoints.
+    const INFO: EndpointInfo = EndpointInfo {
+        name: Self::NAME,
+        method: Self::METHOD,
+        path: Self::PATH,
+        params: Self::PARAMS,
+    };
+
+    /// The type of the request body, or `()` if the endpoint takes none.
+    type Request;
+
+    /// The type the response body is deserialized into.
+    type Response;
+}
+
+/// A description of an [`Endpoint`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EndpointInfo {
+    /// The name of the endpoint.
+    pub name: &'static str,
+
+    /// The HTTP method used to call the endpoint.
+    pub method: Method,
+
+    /// The path template of the endpoint.
+    pub path: &'static str,
+
+    /// The number of parameters in the path template.
+    pub params: usize,
+}
+
+/// Counts the parameters in the path `template`.
+///
+/// # Panics
+///
+/// Panics if the template has unbalanced or nested braces, or an empty
+/// parameter. When called in a constant, as by
+/// [`endpoints!`](crate::endpoints), this is a compile-time error.
+pub const fn param_count(template: &str) -> usize {
+    let bytes = template.as_bytes();
+    let mut count = 0;
+    let mut open = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], open) {
+            (b'{', None) => open = Some(i),
+            (b'}', Some(start)) => {
+                assert!(i > start + 1, "empty parameter in path template");
+                count += 1;
+                open = None;
+            }
+            (b'{', Some(_)) | (b'}', None) => panic!("unbalanced braces in path template"),
+            _ => {}
+        }
+        i += 1;
+    }
+    assert!(open.is_none(), "unbalanced braces in path template");
+    count
+}
+
+/// Renders the path `template`, replacing its parameters in order with
+/// the percent-encoded `params`.
+///
+/// Parameters beyond those in the template are ignored, and parameters
+/// missing from `params` are left empty. Endpoints declared with
+/// [`endpoints!`](crate::endpoints) check the number of parameters at
+/// compile time.
+pub fn render(template: &str, params: &[&str]) -> String {
+    let mut path = String::with_capacity(rendered_len(template, params));
+    let mut params = params.iter();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        if let Some(param) = params.next() {
+            encode_into(&mut path, param);
+        }
+        rest = match rest[start..].find('}') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+        };
+    }
+    path.push_str(rest);
+    path
+}
+
+fn rendered_len(template: &str, params: &[&str]) -> usize {
+    let encoded: usize = params
+        .iter()
+        .flat_map(|param| param.bytes())
+        .map(|b| if is_unreserved(b) { 1 } else { 3 })
+        .sum();
+    template.len() + encoded
+}
+
+fn encode_into(path: &mut String, param: &str) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    for b in param.bytes() {
+        if is_unreserved(b) {
+            path.push(b as char);
+        } else {
+            path.push('%');
+            path.push(HEX[(b >> 4) as usize] as char);
+            path.push(HEX[(b & 0xf) as usize] as char);
+        }
+    }
+}
+
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~')
+}
+
+/// Declares a module containing a static table of API endpoints.
+///
+/// Each endpoint is declared as
+///
+/// ```text
+/// Name: METHOD "/path/{param}" (RequestType) => ResponseType;
+/// ```
+///
+/// where the request type is optional and defaults to `()`. The module
+/// contains a unit struct implementing [`Endpoint`](crate::endpoint::Endpoint)
+/// for each endpoint, and an `ENDPOINTS` constant listing them all in
+/// declaration order. Items in the enclosing module are visible inside the
+/// generated module.
+///
+/// See the [`endpoint`] module for details.
+///
+/// [`endpoint`]: crate::endpoint
+#[macro_export]
+macro_rules! endpoints {
+    (
+        $(#[$meta:meta])*
+        $vis:vis mod $module:ident {
+            $(
+                $(#[$endpoint_meta:meta])*
+                $name:ident: $method:ident $path:literal $(($request:ty))? => $response:ty;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        $vis mod $module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+                $(#[$endpoint_meta])*
+                #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+                pub struct $name;
+
+                impl $crate::endpoint::Endpoint for $name {
+                    const NAME: &'static str = stringify!($name);
+                    const METHOD: $crate::endpoint::Method = $crate::endpoint::Method::$method;
+                    const PATH: &'static str = $path;
+                    type Request = $crate::endpoints!(@request $($request)?);
+                    type Response = $response;
+                }
+
+                #[allow(dead_code)]
+                impl $name {
+                    /// Renders the path of this endpoint with the given
+                    /// parameters.
+                    pub fn path(
+                        params: [&str; <$name as $crate::endpoint::Endpoint>::PARAMS],
+                    ) -> ::std::string::String {
+                        $crate::endpoint::render($path, &params)
+                    }
+
+                    /// Renders the URI of this endpoint relative to `base`
+                    /// with the given parameters.
+                    pub fn uri(
+                        base: &str,
+                        params: [&str; <$name as $crate::endpoint::Endpoint>::PARAMS],
+                    ) -> ::std::string::String {
+                        let base = base.trim_end_matches('/');
+                        let mut uri = ::std::string::String::with_capacity(
+                            base.len() + $path.len(),
+                        );
+                        uri.push_str(base);
+                        uri.push_str(&Self::path(params));
+                        uri
+                    }
+
+                    $crate::endpoints!(@call $name $method $($request)?);
+                }
+            )*
+
+            /// Every endpoint in this module, in declaration order.
+            pub const ENDPOINTS: &[$crate::endpoint::EndpointInfo] = &[
+                $(<$name as $crate::endpoint::Endpoint>::INFO),*
+            ];
+        }
+    };
+
+    (@request) => { () };
+    (@request $request:ty) => { $request };
+
+    (@call $name:ident GET) => {
+        /// Calls this endpoint relative to `base` with the given
+        /// parameters.
+        pub async fn call<S>(
+            service: &S,
+            base: &str,
+            params: [&str; <$name as $crate::endpoint::Endpoint>::PARAMS],
+        ) -> $crate::HttpResult<<$name as $crate::endpoint::Endpoint>::Response>
+        where
+            S: $crate::service::HttpGet + ::std::marker::Sync,
+        {
+            let body = $crate::service::HttpGet::get(service, Self::uri(base, params)).await?;
+            $crate::decode::json(&body)
+        }
+    };
+    (@call $name:ident POST $request:ty) => {
+        /// Calls this endpoint relative to `base` with the given
+        /// parameters and request body.
+        pub async fn call<S>(
+            service: &S,
+            base: &str,
+            params: [&str; <$name as $crate::endpoint::Endpoint>::PARAMS],
+            auth: &dyn $crate::auth::Authenticator,
+            body: &$request,
+        ) -> $crate::HttpResult<<$name as $crate::endpoint::Endpoint>::Response>
+        where
+            S: $crate::service::HttpPost + ::std::marker::Sync,
+        {
+            $crate::service::HttpPost::post(service, Self::uri(base, params), auth, body).await
+        }
+    };
+    (@call $name:ident $method:ident $($request:ty)?) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    pub struct User {
+        id: String,
+    }
+
+    #[derive(Serialize)]
+    pub struct NewUser {
+        name: String,
+    }
+
+    crate::endpoints! {
+        mod api {
+            GetUser: GET "/users/{id}" => User;
+            CreateUser: POST "/users" (NewUser) => serde_json::Value;
+            DeleteUser: DELETE "/users/{id}" => ();
+        }
+    }
+
+    /// Echoes the URI of every request back in the response.
+    struct EchoService;
+
+    impl HttpGet for EchoService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            Ok(json!({ "id": uri.as_str() }).to_string())
+        }
+    }
+
+    impl HttpPost for EchoService {
+        async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let data = serde_json::to_value(data)?;
+            Ok(serde_json::from_value(
+                json!({ "uri": uri.as_str(), "data": data }),
+            )?)
+        }
+    }
+
+    #[test]
+    fn it_counts_parameters() {
+        assert_eq!(param_count("/users"), 0);
+        assert_eq!(param_count("/users/{id}/posts/{post}"), 2);
+        assert_eq!(<api::GetUser as Endpoint>::PARAMS, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "unbalanced braces")]
+    fn it_rejects_unbalanced_braces() {
+        param_count("/users/{id");
+    }
+
+    #[test]
+    fn it_renders_paths_with_encoded_parameters() {
+        assert_eq!(render("/a/{x}/b/{y}", &["1", "c/d"]), "/a/1/b/c%2Fd");
+        assert_eq!(render("/{x}", &["é ~"]), "/%C3%A9%20~");
+        assert_eq!(api::DeleteUser::path(["7"]), "/users/7");
+        assert_eq!(
+            api::GetUser::uri("https://api.example.com/", ["7"]),
+            "https://api.example.com/users/7"
+        );
+    }
+
+    #[test]
+    fn it_lists_every_endpoint() {
+        let endpoints: Vec<_> = api::ENDPOINTS
+            .iter()
+            .map(|e| (e.name, e.method.as_str(), e.path))
+            .collect();
+        assert_eq!(
+            endpoints,
+            [
+                ("GetUser", "GET", "/users/{id}"),
+                ("CreateUser", "POST", "/users"),
+                ("DeleteUser", "DELETE", "/users/{id}"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_calls_endpoints() -> HttpResult<()> {
+        let base = "https://api.example.com";
+        let user = api::GetUser::call(&EchoService, base, ["a b"]).await?;
+        assert_eq!(user.id, "https://api.example.com/users/a%20b");
+
+        let new_user = NewUser {
+            name: String::from("foo"),
+        };
+        let created =
+            api::CreateUser::call(&EchoService, base, [], &Auth::new("key"), &new_user).await?;
+        assert_eq!(
+            created,
+            json!({ "uri": "https://api.example.com/users", "data": { "name": "foo" } })
+        );
+        Ok(())
+    }
+}
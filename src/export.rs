@@ -0,0 +1,464 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Bulk exports of paginated data.
+//!
+//! An [`Export`] fetches every page of a paginated endpoint and writes each
+//! record to a [`Sink`], such as an [NDJSON file](NdjsonSink) or, with the
+//! `csv` feature, a CSV file with `CsvSink`. This is the backbone of
+//! "download all my data" commands.
+//!
+//! Exports of large accounts can take a long time, so an export can save
+//! its progress to a [checkpoint file](Export::with_checkpoint) after each
+//! page. If the export is interrupted, or stops because it reached its
+//! [time limit](Export::with_time_limit), running it again with the same
+//! checkpoint file picks up where it left off, appending to the same sink.
+//! The checkpoint file is deleted once the export is complete.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::export::{Export, NdjsonSink, Page};
+//! use hypertyper::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//! use std::time::Duration;
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Event {
+//!     id: u64,
+//!     kind: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct EventsPage {
+//!     events: Vec<Event>,
+//!     next_cursor: Option<String>,
+//! }
+//!
+//! # async fn run<S: HttpGet>(service: S) -> HttpResult<()> {
+//! let mut sink = NdjsonSink::append("events.ndjson")?;
+//! let report = Export::new(|cursor: Option<String>| {
+//!     let uri = match cursor {
+//!         Some(cursor) => format!("https://api.example.com/events?cursor={cursor}"),
+//!         None => String::from("https://api.example.com/events"),
+//!     };
+//!     let service = &service;
+//!     async move {
+//!         let page: EventsPage = serde_json::from_str(&service.get(uri).await?)?;
+//!         Ok(Page::new(page.events, page.next_cursor))
+//!     }
+//! })
+//! .with_checkpoint("events.checkpoint")
+//! .with_time_limit(Duration::from_secs(15 * 60))
+//! .run(&mut sink)
+//! .await?;
+//!
+//! if !report.complete {
+//!     eprintln!("Exported {} events so far; run again to continue", report.records);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HttpResult;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// One page of records from a paginated endpoint.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Page<T> {
+    /// The records on the page.
+    pub items: Vec<T>,
+
+    /// The cursor for the next page, or `None` if this is the last page.
+    pub next: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Creates a page of `items`, followed by the page at the `next` cursor.
+    pub fn new(items: Vec<T>, next: Option<String>) -> Self {
+        Self { items, next }
+    }
+}
+
+/// A destination for exported records.
+pub trait Sink<T> {
+    /// Writes a single record.
+    fn write(&mut self, record: &T) -> io::Result<()>;
+
+    /// Ensures that every record written so far has reached its
+    /// destination.
+    ///
+    /// An [`Export`] flushes its sink before checkpointing, so that a
+    /// resumed export never skips records.
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// Writes records as [newline-delimited JSON](https://jsonlines.org/).
+#[derive(Debug)]
+pub struct NdjsonSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> NdjsonSink<W> {
+    /// Creates a sink that writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Consumes the sink and returns its writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl NdjsonSink<BufWriter<File>> {
+    /// Creates a sink that appends to the file at `path`, creating it if
+    /// it does not exist.
+    pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<T: Serialize, W: Write> Sink<T> for NdjsonSink<W> {
+    fn write(&mut self, record: &T) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes records as rows of a CSV file, with a header row taken from the
+/// field names of the first record.
+///
+/// Records must serialize to a flat sequence of fields, such as a struct
+/// whose fields are all strings, numbers, or booleans.
+#[cfg(feature = "csv")]
+#[derive(Debug)]
+pub struct CsvSink<W: Write> {
+    writer: csv::Writer<W>,
+}
+
+#[cfg(feature = "csv")]
+impl<W: Write> CsvSink<W> {
+    /// Creates a sink that writes to `writer`, starting with a header row.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: csv::Writer::from_writer(writer),
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl CsvSink<File> {
+    /// Creates a sink that appends to the file at `path`, creating it if
+    /// it does not exist.
+    ///
+    /// The header row is only written if the file is empty, so that
+    /// resumed exports do not repeat it.
+    pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+        let writer = csv::WriterBuilder::new()
+            .has_headers(empty)
+            .from_writer(file);
+        Ok(Self { writer })
+    }
+}
+
+#[cfg(feature = "csv")]
+impl<T: Serialize, W: Write> Sink<T> for CsvSink<W> {
+    fn write(&mut self, record: &T) -> io::Result<()> {
+        self.writer.serialize(record).map_err(io::Error::other)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// The progress of an export, as saved in its checkpoint file.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+struct Checkpoint {
+    cursor: Option<String>,
+    records: u64,
+    pages: u64,
+}
+
+/// The outcome of running an [`Export`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExportReport {
+    /// The total number of records exported, including those exported by
+    /// earlier runs that were resumed from the checkpoint.
+    pub records: u64,
+
+    /// The total number of pages exported, including those exported by
+    /// earlier runs.
+    pub pages: u64,
+
+    /// Whether the last page has been exported.
+    pub complete: bool,
+
+    /// The cursor of the next page to export, if the export is not
+    /// complete.
+    pub cursor: Option<String>,
+}
+
+/// Exports every record from a paginated endpoint to a [`Sink`].
+///
+/// See the [module documentation](crate::export) for details.
+#[derive(Debug)]
+pub struct Export<F> {
+    fetch: F,
+    checkpoint: Option<PathBuf>,
+    time_limit: Option<Duration>,
+}
+
+impl<F> Export<F> {
+    /// Creates an export that fetches pages with `fetch`.
+    ///
+    /// `fetch` is called with the cursor of the page to fetch, which is
+    /// `None` for the first page.
+    pub fn new(fetch: F) -> Self {
+        Self {
+            fetch,
+            checkpoint: None,
+            time_limit: None,
+        }
+    }
+
+    /// Saves the export's progress to the file at `path` after each page,
+    /// and resumes from it if it already exists.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(path.into());
+        self
+    }
+
+    /// Stops fetching new pages once the export has been running for
+    /// `limit`, leaving the rest for a later run.
+    ///
+    /// The page being fetched when the limit is reached is finished first,
+    /// so the export may run slightly longer than `limit`.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Runs the export, writing records to `sink`, until every page has
+    /// been exported or the time limit is reached.
+    pub async fn run<T, S, Fut>(mut self, sink: &mut S) -> HttpResult<ExportReport>
+    where
+        F: FnMut(Option<String>) -> Fut,
+        Fut: Future<Output = HttpResult<Page<T>>>,
+        S: Sink<T>,
+    {
+        let start = Instant::now();
+        let mut progress = match &self.checkpoint {
+            Some(path) => load_checkpoint(path)?,
+            None => Checkpoint::default(),
+        };
+        loop {
+            let page = (self.fetch)(progress.cursor.clone()).await?;
+            for record in &page.items {
+                sink.write(record)?;
+            }
+            sink.flush()?;
+            progress.records += page.items.len() as u64;
+            progress.pages += 1;
+            progress.cursor = page.next;
+
+            if progress.cursor.is_none() {
+                if let Some(path) = &self.checkpoint {
+                    remove_checkpoint(path)?;
+                }
+                return Ok(ExportReport {
+                    records: progress.records,
+                    pages: progress.pages,
+                    complete: true,
+                    cursor: None,
+                });
+            }
+            if let Some(path) = &self.checkpoint {
+                save_checkpoint(path, &progress)?;
+            }
+            if self
+                .time_limit
+                .is_some_and(|limit| start.elapsed() >= limit)
+            {
+                return Ok(ExportReport {
+                    records: progress.records,
+                    pages: progress.pages,
+                    complete: false,
+                    cursor: progress.cursor,
+                });
+            }
+        }
+    }
+}
+
+fn load_checkpoint(path: &Path) -> HttpResult<Checkpoint> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Checkpoint::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes the checkpoint to a temporary file first, so that a crash while
+/// saving never leaves a corrupt checkpoint behind.
+fn save_checkpoint(path: &Path, checkpoint: &Checkpoint) -> HttpResult<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, serde_json::to_vec(checkpoint)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn remove_checkpoint(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use reqwest::StatusCode;
+    use std::cell::Cell;
+
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+    struct Record {
+        id: u32,
+        name: String,
+    }
+
+    /// Serves pages of two records each, for ids up to `last`, failing
+    /// when asked for the page starting at `fail_at`.
+    fn pages(
+        last: u32,
+        fail_at: Option<u32>,
+    ) -> impl FnMut(Option<String>) -> std::future::Ready<HttpResult<Page<Record>>> {
+        move |cursor| {
+            let start: u32 = cursor.map_or(1, |cursor| cursor.parse().unwrap());
+            if Some(start) == fail_at {
+                let err = HttpError::Http(StatusCode::SERVICE_UNAVAILABLE);
+                return std::future::ready(Err(err));
+            }
+            let end = (start + 1).min(last);
+            let items = (start..=end)
+                .map(|id| Record {
+                    id,
+                    name: format!("record {id}"),
+                })
+                .collect();
+            let next = (end < last).then(|| (end + 1).to_string());
+            std::future::ready(Ok(Page::new(items, next)))
+        }
+    }
+
+    fn ids(ndjson: &[u8]) -> Vec<u32> {
+        std::str::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Record>(line).unwrap().id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn it_exports_every_page() -> HttpResult<()> {
+        let mut sink = NdjsonSink::new(Vec::new());
+        let report = Export::new(pages(5, None)).run(&mut sink).await?;
+        assert_eq!(
+            report,
+            ExportReport {
+                records: 5,
+                pages: 3,
+                complete: true,
+                cursor: None,
+            }
+        );
+        assert_eq!(ids(&sink.into_inner()), [1, 2, 3, 4, 5]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_resumes_from_the_checkpoint_after_a_failure() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let checkpoint = dir.path().join("export.checkpoint");
+        let output = dir.path().join("export.ndjson");
+
+        let mut sink = NdjsonSink::append(&output)?;
+        let result = Export::new(pages(7, Some(5)))
+            .with_checkpoint(&checkpoint)
+            .run(&mut sink)
+            .await;
+        assert!(matches!(result, Err(HttpError::Http(_))));
+        assert!(checkpoint.exists());
+
+        let mut sink = NdjsonSink::append(&output)?;
+        let report = Export::new(pages(7, None))
+            .with_checkpoint(&checkpoint)
+            .run(&mut sink)
+            .await?;
+        assert_eq!(report.records, 7);
+        assert_eq!(report.pages, 4);
+        assert!(!checkpoint.exists());
+        drop(sink);
+        assert_eq!(ids(&fs::read(&output)?), [1, 2, 3, 4, 5, 6, 7]);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_stops_at_the_time_limit() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let checkpoint = dir.path().join("export.checkpoint");
+        let fetched = Cell::new(0);
+        let mut serve = pages(9, None);
+        let fetch = |cursor| {
+            fetched.set(fetched.get() + 1);
+            let page = serve(cursor);
+            async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                page.await
+            }
+        };
+
+        let mut sink = NdjsonSink::new(Vec::new());
+        let report = Export::new(fetch)
+            .with_checkpoint(&checkpoint)
+            .with_time_limit(Duration::from_secs(15))
+            .run(&mut sink)
+            .await?;
+        assert_eq!(fetched.get(), 2);
+        assert!(!report.complete);
+        assert_eq!(report.cursor.as_deref(), Some("5"));
+        assert_eq!(load_checkpoint(&checkpoint)?.records, 4);
+        Ok(())
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn it_writes_csv_headers_once() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("export.csv");
+        let mut sink = CsvSink::append(&output)?;
+        Export::new(pages(2, None)).run(&mut sink).await?;
+        let mut sink = CsvSink::append(&output)?;
+        Export::new(pages(1, None)).run(&mut sink).await?;
+        assert_eq!(
+            fs::read_to_string(&output)?,
+            "id,name\n1,record 1\n2,record 2\n1,record 1\n"
+        );
+        Ok(())
+    }
+}
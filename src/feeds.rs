@@ -0,0 +1,334 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Fetching of RSS and Atom feeds.
+//!
+//! [`parse()`] turns the body of an RSS, Atom, or JSON feed into a [`Feed`]
+//! of typed [`Entry`]s, smoothing over the differences between the formats.
+//!
+//! Feeds are usually polled, so a [`FeedReader`] remembers the `ETag` and
+//! `Last-Modified` validators of each feed it fetches and sends
+//! [conditional requests](crate::service::conditional) after the first,
+//! returning `None` instead of downloading and parsing a feed that has not
+//! changed.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::feeds::FeedReader;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetResponse;
+//! use std::time::Duration;
+//!
+//! # async fn run<S: HttpGetResponse + Sync>(service: S) -> HttpResult<()> {
+//! let reader = FeedReader::new(service);
+//! loop {
+//!     if let Some(feed) = reader.fetch("https://blog.example.com/feed.xml").await? {
+//!         for entry in feed.entries {
+//!             println!("{}", entry.title.unwrap_or_default());
+//!         }
+//!     }
+//!     tokio::time::sleep(Duration::from_secs(15 * 60)).await;
+//! }
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::service::HttpGetResponse;
+use crate::service::conditional::Conditional;
+use reqwest::IntoUrl;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// A parsed feed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Feed {
+    /// The title of the feed.
+    pub title: Option<String>,
+
+    /// The URL of the website the feed belongs to.
+    pub link: Option<String>,
+
+    /// When the feed was last updated.
+    pub updated: Option<SystemTime>,
+
+    /// The entries in the feed, in the order the feed lists them.
+    pub entries: Vec<Entry>,
+}
+
+/// An entry in a feed, such as a blog post.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// An identifier for the entry that is unique within the feed.
+    ///
+    /// If the feed does not give one, it is derived from the entry's link.
+    pub id: String,
+
+    /// The title of the entry.
+    pub title: Option<String>,
+
+    /// The URL of the entry.
+    pub link: Option<String>,
+
+    /// A short summary of the entry.
+    pub summary: Option<String>,
+
+    /// The full content of the entry, usually HTML.
+    pub content: Option<String>,
+
+    /// The names of the entry's authors.
+    pub authors: Vec<String>,
+
+    /// When the entry was first published.
+    pub published: Option<SystemTime>,
+
+    /// When the entry was last updated.
+    pub updated: Option<SystemTime>,
+}
+
+/// Parses an RSS, Atom, or JSON feed.
+///
+/// # Errors
+///
+/// Returns [`HttpError::Feed`](crate::HttpError::Feed) if `body` is not a
+/// feed in a supported format.
+pub fn parse(body: &str) -> HttpResult<Feed> {
+    let feed = feed_rs::parser::parse(body.as_bytes())?;
+    Ok(Feed {
+        title: feed.title.map(|title| title.content),
+        link: link(&feed.links),
+        updated: feed.updated.map(SystemTime::from),
+        entries: feed.entries.into_iter().map(entry).collect(),
+    })
+}
+
+fn entry(entry: feed_rs::model::Entry) -> Entry {
+    Entry {
+        link: link(&entry.links),
+        id: entry.id,
+        title: entry.title.map(|title| title.content),
+        summary: entry.summary.map(|summary| summary.content),
+        content: entry.content.and_then(|content| content.body),
+        authors: entry
+            .authors
+            .into_iter()
+            .map(|author| author.name)
+            .collect(),
+        published: entry.published.map(SystemTime::from),
+        updated: entry.updated.map(SystemTime::from),
+    }
+}
+
+/// The link to the page itself, preferring an `alternate` link over
+/// others, such as links to the feed or to enclosures.
+fn link(links: &[feed_rs::model::Link]) -> Option<String> {
+    links
+        .iter()
+        .find(|link| link.rel.as_deref().is_none_or(|rel| rel == "alternate"))
+        .or(links.first())
+        .map(|link| link.href.clone())
+}
+
+/// Fetches feeds with conditional requests.
+#[derive(Debug)]
+pub struct FeedReader<S> {
+    service: S,
+    validators: Mutex<HashMap<String, Validators>>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<SystemTime>,
+}
+
+impl<S> FeedReader<S> {
+    /// Creates a reader that fetches feeds with `service`.
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            validators: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The service used to fetch feeds.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+}
+
+impl<S: HttpGetResponse + Sync> FeedReader<S> {
+    /// Fetches and parses the feed at `uri`, or returns `None` if it has
+    /// not changed since it was last fetched by this reader.
+    pub async fn fetch<U>(&self, uri: U) -> HttpResult<Option<Feed>>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        let validators = self
+            .validators
+            .lock()
+            .unwrap()
+            .get(&uri)
+            .cloned()
+            .unwrap_or_default();
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = validators
+            .etag
+            .and_then(|etag| HeaderValue::try_from(etag).ok())
+        {
+            headers.insert(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(since) = validators.last_modified {
+            let since = HeaderValue::from_str(&httpdate::fmt_http_date(since))?;
+            headers.insert(header::IF_MODIFIED_SINCE, since);
+        }
+
+        let response = self.service.get_response(uri.as_str(), &headers).await?;
+        match Conditional::from_response(response)? {
+            Conditional::NotModified => Ok(None),
+            Conditional::Modified {
+                body,
+                etag,
+                last_modified,
+            } => {
+                let feed = parse(&body)?;
+                let validators = Validators {
+                    etag,
+                    last_modified,
+                };
+                self.validators.lock().unwrap().insert(uri, validators);
+                Ok(Some(feed))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::service::HttpResponse;
+    use reqwest::StatusCode;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+        <rss version="2.0">
+          <channel>
+            <title>Release notes</title>
+            <link>https://example.com/releases</link>
+            <item>
+              <title>Version 2.0</title>
+              <link>https://example.com/releases/2.0</link>
+              <guid>release-2.0</guid>
+              <description>Faster &amp; smaller.</description>
+              <pubDate>Wed, 21 Oct 2015 07:28:00 GMT</pubDate>
+            </item>
+          </channel>
+        </rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <feed xmlns="http://www.w3.org/2005/Atom">
+          <title>Engineering blog</title>
+          <link rel="self" href="https://example.com/feed.atom"/>
+          <link href="https://example.com/blog"/>
+          <id>urn:example:blog</id>
+          <updated>2026-03-01T12:00:00Z</updated>
+          <entry>
+            <title>Hello, world</title>
+            <link rel="alternate" href="https://example.com/blog/hello"/>
+            <id>urn:example:blog:1</id>
+            <updated>2026-03-01T12:00:00Z</updated>
+            <author><name>Ada</name></author>
+            <content type="html">&lt;p&gt;Hi!&lt;/p&gt;</content>
+          </entry>
+        </feed>"#;
+
+    #[test]
+    fn it_parses_rss() -> HttpResult<()> {
+        let feed = parse(RSS)?;
+        assert_eq!(feed.title.as_deref(), Some("Release notes"));
+        assert_eq!(feed.link.as_deref(), Some("https://example.com/releases"));
+        let entry = &feed.entries[0];
+        assert_eq!(entry.id, "release-2.0");
+        assert_eq!(entry.title.as_deref(), Some("Version 2.0"));
+        assert_eq!(
+            entry.link.as_deref(),
+            Some("https://example.com/releases/2.0")
+        );
+        assert_eq!(entry.summary.as_deref(), Some("Faster & smaller."));
+        assert_eq!(
+            entry.published,
+            httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").ok()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_atom() -> HttpResult<()> {
+        let feed = parse(ATOM)?;
+        assert_eq!(feed.title.as_deref(), Some("Engineering blog"));
+        assert_eq!(feed.link.as_deref(), Some("https://example.com/blog"));
+        assert!(feed.updated.is_some());
+        let entry = &feed.entries[0];
+        assert_eq!(entry.id, "urn:example:blog:1");
+        assert_eq!(entry.authors, ["Ada"]);
+        assert_eq!(entry.content.as_deref(), Some("<p>Hi!</p>"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_bodies_that_are_not_feeds() {
+        assert!(matches!(parse("<html></html>"), Err(HttpError::Feed(_))));
+    }
+
+    /// Serves the RSS feed with an ETag, and records the headers of each
+    /// request.
+    #[derive(Default)]
+    struct Server {
+        requests: Mutex<Vec<HeaderMap>>,
+    }
+
+    impl HttpGetResponse for Server {
+        async fn get_response<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            self.requests.lock().unwrap().push(headers.clone());
+            if headers
+                .get(header::IF_NONE_MATCH)
+                .is_some_and(|etag| etag == "\"1\"")
+            {
+                return Ok(HttpResponse::new(StatusCode::NOT_MODIFIED, ""));
+            }
+            Ok(HttpResponse::new(StatusCode::OK, RSS)
+                .with_header(header::ETAG, HeaderValue::from_static("\"1\"")))
+        }
+    }
+
+    #[tokio::test]
+    async fn it_only_returns_feeds_that_changed() -> HttpResult<()> {
+        let reader = FeedReader::new(Server::default());
+        let feed = reader.fetch("https://example.com/feed.xml").await?;
+        assert_eq!(feed.unwrap().entries.len(), 1);
+        assert!(
+            reader
+                .fetch("https://example.com/feed.xml")
+                .await?
+                .is_none()
+        );
+        assert!(
+            reader
+                .fetch("https://example.com/other.xml")
+                .await?
+                .is_some()
+        );
+
+        let requests = reader.service().requests.lock().unwrap();
+        assert!(requests[0].is_empty());
+        assert_eq!(requests[1][header::IF_NONE_MATCH], "\"1\"");
+        assert!(requests[2].is_empty());
+        Ok(())
+    }
+}
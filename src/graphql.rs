@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! GraphQL requests over HTTP.
+//!
+//! GraphQL APIs take a [`GraphQlRequest`] in the body of a POST request and
+//! answer with a [`GraphQlResponse`], an envelope that holds the requested
+//! `data`, a list of `errors`, or both. [`HttpPost::post_graphql()`] sends a
+//! request and unwraps the envelope: it returns the `data` if the server
+//! reported no errors, and fails with [`HttpError::GraphQl`] otherwise.
+//!
+//! In tests, the `HttpTestService` provided by the **test-utils** feature
+//! loads the response to a GraphQL request with an operation name from a
+//! fixture named after the operation, so a single endpoint can answer many
+//! different queries.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::graphql::GraphQlRequest;
+//! use hypertyper::prelude::*;
+//! use serde::Deserialize;
+//! use serde_json::json;
+//!
+//! #[derive(Deserialize)]
+//! struct UserData {
+//!     user: User,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! # async fn run<S: HttpPost + Sync>(service: S, auth: Auth) -> HttpResult<()> {
+//! let request = GraphQlRequest::new("query GetUser($id: ID!) { user(id: $id) { name } }")
+//!     .with_variables(json!({ "id": "42" }))
+//!     .with_operation_name("GetUser");
+//! let data: UserData = service
+//!     .post_graphql("https://api.example.com/graphql", &auth, &request)
+//!     .await?;
+//! println!("Hello, {}", data.user.name);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{HttpError, HttpResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+#[cfg(doc)]
+use crate::service::HttpPost;
+
+/// A GraphQL query or mutation, serialized as the body of a POST request.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlRequest {
+    /// The query document.
+    pub query: String,
+
+    /// The values of the variables used by the query, as a JSON object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Value>,
+
+    /// The name of the operation to run, if the query document contains
+    /// more than one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+}
+
+impl GraphQlRequest {
+    /// Creates a request for `query` with no variables.
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            variables: None,
+            operation_name: None,
+        }
+    }
+
+    /// Sets the values of the query's variables.
+    pub fn with_variables(mut self, variables: Value) -> Self {
+        self.variables = Some(variables);
+        self
+    }
+
+    /// Sets the name of the operation to run.
+    pub fn with_operation_name(mut self, operation_name: impl Into<String>) -> Self {
+        self.operation_name = Some(operation_name.into());
+        self
+    }
+}
+
+/// The envelope a GraphQL API answers a request with.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GraphQlResponse<T> {
+    /// The requested data, which may be partial if there were errors.
+    #[serde(default = "Option::default")]
+    pub data: Option<T>,
+
+    /// The errors that occurred while running the request.
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<GraphQlError>,
+}
+
+impl<T> GraphQlResponse<T> {
+    /// Returns the data in the response if there were no errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::GraphQl`] if the response contains any errors,
+    /// even if it also contains partial data, or if it contains no data.
+    pub fn into_result(self) -> HttpResult<T> {
+        match self.data {
+            Some(data) if self.errors.is_empty() => Ok(data),
+            _ if self.errors.is_empty() => Err(HttpError::GraphQl(vec![GraphQlError::new(
+                "response contained neither data nor errors",
+            )])),
+            _ => Err(HttpError::GraphQl(self.errors)),
+        }
+    }
+}
+
+/// An error reported by a GraphQL API.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GraphQlError {
+    /// A description of the error.
+    pub message: String,
+
+    /// The locations in the query document the error refers to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<GraphQlLocation>,
+
+    /// The path to the field in the response that failed, made of field
+    /// names and list indices, such as `["user", "posts", 3]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<Value>,
+
+    /// Additional, API-specific information about the error, such as an
+    /// error code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+}
+
+impl GraphQlError {
+    /// Creates an error with the given message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            locations: Vec::new(),
+            path: Vec::new(),
+            extensions: None,
+        }
+    }
+}
+
+impl fmt::Display for GraphQlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if !self.path.is_empty() {
+            let path: Vec<_> = self
+                .path
+                .iter()
+                .map(|segment| match segment {
+                    Value::String(field) => field.clone(),
+                    other => other.to_string(),
+                })
+                .collect();
+            write!(f, " (at {})", path.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+/// A line and column in a GraphQL query document, both starting at 1.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GraphQlLocation {
+    /// The line number.
+    pub line: u32,
+
+    /// The column number.
+    pub column: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_serializes_requests() -> HttpResult<()> {
+        let request = GraphQlRequest::new("{ viewer { login } }");
+        assert_eq!(
+            serde_json::to_value(&request)?,
+            json!({ "query": "{ viewer { login } }" })
+        );
+
+        let request = request
+            .with_variables(json!({ "id": 1 }))
+            .with_operation_name("Viewer");
+        assert_eq!(
+            serde_json::to_value(&request)?,
+            json!({
+                "query": "{ viewer { login } }",
+                "variables": { "id": 1 },
+                "operationName": "Viewer",
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_unwraps_data() -> HttpResult<()> {
+        let response: GraphQlResponse<Value> =
+            serde_json::from_value(json!({ "data": { "viewer": { "login": "foo" } } }))?;
+        assert_eq!(
+            response.into_result()?,
+            json!({ "viewer": { "login": "foo" } })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_on_errors_even_with_partial_data() -> HttpResult<()> {
+        let response: GraphQlResponse<Value> = serde_json::from_value(json!({
+            "data": { "user": null },
+            "errors": [{
+                "message": "Not found",
+                "locations": [{ "line": 1, "column": 3 }],
+                "path": ["user", "posts", 0],
+                "extensions": { "code": "NOT_FOUND" },
+            }],
+        }))?;
+        match response.into_result() {
+            Err(HttpError::GraphQl(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].locations[0].column, 3);
+                assert_eq!(errors[0].to_string(), "Not found (at user.posts.0)");
+            }
+            other => panic!("expected GraphQL errors, got {other:?}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_without_data_or_errors() -> HttpResult<()> {
+        let response: GraphQlResponse<Value> = serde_json::from_value(json!({}))?;
+        assert!(matches!(response.into_result(), Err(HttpError::GraphQl(_))));
+        Ok(())
+    }
+}
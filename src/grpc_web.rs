@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Unary gRPC-Web calls.
+//!
+//! [gRPC-Web] lets clients that only speak plain HTTP call gRPC services
+//! through a gateway such as Envoy. [`HttpGrpcWeb::grpc_unary()`] makes a
+//! unary call: it sends a single request message and returns the single
+//! response message, without pulling in a full gRPC stack.
+//!
+//! Messages are opaque bytes, so they can be encoded and decoded with any
+//! Protocol Buffers library, such as [prost]. This module takes care of the
+//! rest of the protocol: it [frames](frame()) the request message, sends it
+//! as `application/grpc-web+proto`, and [decodes](decode_unary()) the
+//! response, whose status is sent in a trailer frame at the end of the
+//! body. Calls that end with a status other than `OK` fail with
+//! [`HttpError::Grpc`].
+//!
+//! This module is only available with the **grpc-web** feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use bytes::Bytes;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGrpcWeb;
+//! use reqwest::header::HeaderMap;
+//!
+//! # fn encode_request() -> Vec<u8> { Vec::new() }
+//! # async fn run<S: HttpGrpcWeb>(service: S) -> HttpResult<()> {
+//! // An encoded `helloworld.HelloRequest` message.
+//! let request: Vec<u8> = encode_request();
+//! let reply = service
+//!     .grpc_unary(
+//!         "https://grpc.example.com/helloworld.Greeter/SayHello",
+//!         &HeaderMap::new(),
+//!         Bytes::from(request),
+//!     )
+//!     .await?;
+//! println!("Received {} bytes", reply.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [gRPC-Web]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-WEB.md
+//! [prost]: https://crates.io/crates/prost
+
+use crate::{HttpError, HttpResult};
+use bytes::{BufMut, Bytes, BytesMut};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+#[cfg(doc)]
+use crate::service::HttpGrpcWeb;
+
+/// The content type of gRPC-Web requests and responses with binary
+/// Protocol Buffers messages.
+pub const CONTENT_TYPE: &str = "application/grpc-web+proto";
+
+/// The status code of a successful call.
+pub const OK: u32 = 0;
+
+/// The status code used when a response cannot be understood.
+pub const INTERNAL: u32 = 13;
+
+/// The flag bit that marks a frame as trailers rather than a message.
+const TRAILERS_FLAG: u8 = 0x80;
+
+/// The flag bit that marks a message as compressed.
+const COMPRESSED_FLAG: u8 = 0x01;
+
+/// Wraps `message` in a gRPC-Web frame: a flag byte, the length of the
+/// message as a big-endian 32-bit integer, and then the message itself.
+pub fn frame(message: &[u8]) -> Bytes {
+    let mut framed = BytesMut::with_capacity(5 + message.len());
+    framed.put_u8(0);
+    framed.put_u32(message.len() as u32);
+    framed.put_slice(message);
+    framed.freeze()
+}
+
+/// Decodes the body of a response to a unary call, returning its message.
+///
+/// The status of the call is read from the trailer frame at the end of the
+/// body or, if the server sent a response with no body, from `headers`.
+///
+/// # Errors
+///
+/// Returns [`HttpError::Grpc`] if the call did not succeed or the body is
+/// not a valid gRPC-Web response.
+pub fn decode_unary(headers: &HeaderMap, body: &[u8]) -> HttpResult<Bytes> {
+    let mut message = None;
+    let mut trailers = HeaderMap::new();
+    let mut rest = body;
+    while let Some((&flags, header)) = rest.split_first() {
+        let Some((len, payload)) = header.split_first_chunk::<4>() else {
+            return Err(malformed("truncated frame header"));
+        };
+        let len = u32::from_be_bytes(*len) as usize;
+        if payload.len() < len {
+            return Err(malformed("truncated frame"));
+        }
+        let (payload, next) = payload.split_at(len);
+        rest = next;
+
+        if flags & TRAILERS_FLAG != 0 {
+            trailers = parse_trailers(payload);
+        } else if flags & COMPRESSED_FLAG != 0 {
+            return Err(malformed("compressed messages are not supported"));
+        } else if message.is_some() {
+            return Err(malformed("more than one message in a unary response"));
+        } else {
+            message = Some(Bytes::copy_from_slice(payload));
+        }
+    }
+
+    let status = if trailers.contains_key("grpc-status") {
+        &trailers
+    } else {
+        headers
+    };
+    let code = status
+        .get("grpc-status")
+        .and_then(|code| code.to_str().ok())
+        .and_then(|code| code.trim().parse::<u32>().ok());
+    match code {
+        Some(OK) => Ok(message.unwrap_or_default()),
+        Some(code) => {
+            let message = status
+                .get("grpc-message")
+                .map(|message| percent_decode(message.as_bytes()))
+                .unwrap_or_default();
+            Err(HttpError::Grpc { code, message })
+        }
+        None => Err(malformed("missing grpc-status")),
+    }
+}
+
+fn malformed(reason: &str) -> HttpError {
+    HttpError::Grpc {
+        code: INTERNAL,
+        message: format!("malformed gRPC-Web response: {reason}"),
+    }
+}
+
+/// Parses the trailers in a trailer frame, which are formatted like HTTP/1
+/// header fields.
+fn parse_trailers(payload: &[u8]) -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    for line in payload.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let Some(colon) = line.iter().position(|&b| b == b':') else {
+            continue;
+        };
+        let (name, value) = line.split_at(colon);
+        let name = HeaderName::from_bytes(name.trim_ascii());
+        let value = HeaderValue::from_bytes(value[1..].trim_ascii());
+        if let (Ok(name), Ok(value)) = (name, value) {
+            trailers.append(name, value);
+        }
+    }
+    trailers
+}
+
+/// Decodes a percent-encoded `grpc-message`.
+fn percent_decode(value: &[u8]) -> String {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut i = 0;
+    while i < value.len() {
+        let hex = value
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (value[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::HttpGrpcWeb;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn trailers(trailers: &str) -> Vec<u8> {
+        let mut framed = vec![TRAILERS_FLAG];
+        framed.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+        framed.extend_from_slice(trailers.as_bytes());
+        framed
+    }
+
+    #[test]
+    fn it_frames_messages() {
+        assert_eq!(&frame(b"hi")[..], b"\x00\x00\x00\x00\x02hi");
+    }
+
+    #[test]
+    fn it_decodes_successful_responses() -> HttpResult<()> {
+        let mut body = frame(b"reply").to_vec();
+        body.extend(trailers("grpc-status: 0\r\ngrpc-message: \r\n"));
+        let message = decode_unary(&HeaderMap::new(), &body)?;
+        assert_eq!(&message[..], b"reply");
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_failed_calls() {
+        let body = trailers("grpc-status:5\r\ngrpc-message:user%20not%20found\r\n");
+        let result = decode_unary(&HeaderMap::new(), &body);
+        assert!(matches!(
+            result,
+            Err(HttpError::Grpc { code: 5, message }) if message == "user not found"
+        ));
+    }
+
+    #[test]
+    fn it_reads_the_status_of_trailers_only_responses_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("grpc-status", HeaderValue::from_static("16"));
+        let result = decode_unary(&headers, b"");
+        assert!(matches!(result, Err(HttpError::Grpc { code: 16, .. })));
+    }
+
+    #[test]
+    fn it_rejects_malformed_responses() {
+        for body in [&b"\x00\x00\x00\x00\x09short"[..], b"\x00\x00", b""] {
+            let result = decode_unary(&HeaderMap::new(), body);
+            assert!(matches!(
+                result,
+                Err(HttpError::Grpc { code: INTERNAL, .. })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn it_makes_unary_calls() -> HttpResult<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let n = stream.read(&mut request).await.unwrap();
+            request.truncate(n);
+
+            let mut body = frame(b"pong").to_vec();
+            body.extend(trailers("grpc-status: 0\r\n"));
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+            request
+        });
+
+        let client = reqwest::Client::builder().no_proxy().build()?;
+        let reply = client
+            .grpc_unary(
+                format!("http://{addr}/echo.Echo/Ping"),
+                &HeaderMap::new(),
+                Bytes::from_static(b"ping"),
+            )
+            .await?;
+        assert_eq!(&reply[..], b"pong");
+
+        let request = server.await.unwrap();
+        let text = String::from_utf8_lossy(&request).to_ascii_lowercase();
+        assert!(text.starts_with("post /echo.echo/ping "));
+        assert!(text.contains("content-type: application/grpc-web+proto\r\n"));
+        assert!(text.contains("x-grpc-web: 1\r\n"));
+        assert!(request.ends_with(b"\x00\x00\x00\x00\x04ping"));
+        Ok(())
+    }
+}
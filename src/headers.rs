@@ -0,0 +1,862 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Parsing of typed values from HTTP headers.
+
+use reqwest::header::{self, HeaderMap};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Parses the `Retry-After` header, if present.
+///
+/// `Retry-After` may either be a number of seconds or an HTTP date. Dates
+/// are converted to the amount of time remaining until that date; dates in
+/// the past are treated as no delay at all.
+///
+/// # Examples
+///
+/// ```
+/// # use hypertyper::headers;
+/// # use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+/// # use std::time::Duration;
+/// let mut headers = HeaderMap::new();
+/// headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+/// assert_eq!(headers::retry_after(&headers), Some(Duration::from_secs(120)));
+/// ```
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// The directives in a `Cache-Control` header, as described in
+/// [RFC 9111 § 5.2].
+///
+/// Only the directives that are meaningful to a client are parsed; others
+/// are ignored.
+///
+/// [RFC 9111 § 5.2]: https://www.rfc-editor.org/rfc/rfc9111#section-5.2
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheControl {
+    /// How long the response stays fresh.
+    pub max_age: Option<Duration>,
+
+    /// Whether the response must not be stored at all.
+    pub no_store: bool,
+
+    /// Whether the response must be revalidated before every use.
+    pub no_cache: bool,
+
+    /// Whether the response must not be used once it is stale without
+    /// first being revalidated.
+    pub must_revalidate: bool,
+
+    /// Whether the response is intended for a single user.
+    pub private: bool,
+
+    /// Whether the response may be cached even if it would normally not
+    /// be.
+    pub public: bool,
+
+    /// How long after it becomes stale the response may still be used while
+    /// it is revalidated in the background.
+    pub stale_while_revalidate: Option<Duration>,
+
+    /// How long after it becomes stale the response may still be used if
+    /// revalidating it fails.
+    pub stale_if_error: Option<Duration>,
+}
+
+/// Parses the `Cache-Control` headers, if present.
+///
+/// Directives from multiple headers are combined. Directive names are
+/// case-insensitive, and unknown directives are ignored.
+///
+/// # Examples
+///
+/// ```
+/// # use hypertyper::headers;
+/// # use reqwest::header::{HeaderMap, HeaderValue, CACHE_CONTROL};
+/// # use std::time::Duration;
+/// let mut headers = HeaderMap::new();
+/// headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=300"));
+/// let cache_control = headers::cache_control(&headers);
+/// assert!(cache_control.public);
+/// assert_eq!(cache_control.max_age, Some(Duration::from_secs(300)));
+/// ```
+pub fn cache_control(headers: &HeaderMap) -> CacheControl {
+    let mut cache_control = CacheControl::default();
+    let directives = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+    for directive in directives {
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (directive.trim(), None),
+        };
+        let seconds = || {
+            value
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs)
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "max-age" => cache_control.max_age = seconds(),
+            "no-store" => cache_control.no_store = true,
+            "no-cache" => cache_control.no_cache = true,
+            "must-revalidate" | "proxy-revalidate" => cache_control.must_revalidate = true,
+            "private" => cache_control.private = true,
+            "public" => cache_control.public = true,
+            "stale-while-revalidate" => cache_control.stale_while_revalidate = seconds(),
+            "stale-if-error" => cache_control.stale_if_error = seconds(),
+            _ => {}
+        }
+    }
+    cache_control
+}
+
+/// The state of a server's rate limit, as reported by its rate limit
+/// headers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    /// The number of requests allowed in each window, if the server said.
+    pub limit: Option<u64>,
+
+    /// The number of requests left in the current window.
+    pub remaining: u64,
+
+    /// How long until the current window ends and the limit resets.
+    pub reset: Duration,
+}
+
+/// Parses rate limit headers, if present.
+///
+/// Both the `RateLimit` header from the [IETF draft] and the common
+/// `RateLimit-Limit`, `RateLimit-Remaining`, and `RateLimit-Reset` headers
+/// are understood, with or without an `X-` prefix. Resets may be given as
+/// a number of seconds or as a Unix timestamp; timestamps in the past are
+/// treated as no delay at all.
+///
+/// Returns `None` unless both the remaining requests and the reset time
+/// are present.
+///
+/// # Examples
+///
+/// ```
+/// # use hypertyper::headers;
+/// # use reqwest::header::{HeaderMap, HeaderValue};
+/// # use std::time::Duration;
+/// let mut headers = HeaderMap::new();
+/// headers.insert("x-ratelimit-remaining", HeaderValue::from_static("42"));
+/// headers.insert("x-ratelimit-reset", HeaderValue::from_static("60"));
+/// let rate_limit = headers::rate_limit(&headers).unwrap();
+/// assert_eq!(rate_limit.remaining, 42);
+/// assert_eq!(rate_limit.reset, Duration::from_secs(60));
+/// ```
+///
+/// [IETF draft]: https://datatracker.ietf.org/doc/draft-ietf-httpapi-ratelimit-headers/
+pub fn rate_limit(headers: &HeaderMap) -> Option<RateLimit> {
+    let mut limit = None;
+    let mut remaining = None;
+    let mut reset = None;
+    if let Some(value) = headers.get("ratelimit").and_then(|v| v.to_str().ok()) {
+        for param in value.split([',', ';']) {
+            let Some((key, value)) = param.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match key.trim().to_ascii_lowercase().as_str() {
+                "limit" => limit = parse_count(value),
+                "remaining" | "r" => remaining = parse_count(value),
+                "reset" | "t" => reset = parse_reset(value),
+                _ => {}
+            }
+        }
+    }
+    let field = |name: &str| {
+        headers
+            .get(name)
+            .or_else(|| headers.get(format!("x-{name}")))
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    limit = limit.or_else(|| field("ratelimit-limit").and_then(parse_count));
+    remaining = remaining.or_else(|| field("ratelimit-remaining").and_then(parse_count));
+    reset = reset.or_else(|| field("ratelimit-reset").and_then(parse_reset));
+    Some(RateLimit {
+        limit,
+        remaining: remaining?,
+        reset: reset?,
+    })
+}
+
+fn parse_count(value: &str) -> Option<u64> {
+    // Some servers send counts as floats, such as "99.0".
+    let count = value.parse::<f64>().ok()?;
+    (count.is_finite() && count >= 0.0).then_some(count as u64)
+}
+
+fn parse_reset(value: &str) -> Option<Duration> {
+    // No window is anywhere near this long, so larger values must be
+    // timestamps.
+    const EPOCH_THRESHOLD: f64 = 1_000_000_000.0;
+
+    let seconds = value.parse::<f64>().ok()?;
+    let delay = Duration::try_from_secs_f64(seconds).ok()?;
+    if seconds < EPOCH_THRESHOLD {
+        return Some(delay);
+    }
+    let reset = SystemTime::UNIX_EPOCH.checked_add(delay)?;
+    Some(
+        reset
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// An authentication challenge from a `WWW-Authenticate` header, as
+/// described in [RFC 9110 § 11.6.1].
+///
+/// A challenge names an authentication scheme, such as `Basic` or `Bearer`,
+/// and carries either a list of parameters or a single opaque "token68"
+/// value.
+///
+/// [RFC 9110 § 11.6.1]: https://www.rfc-editor.org/rfc/rfc9110#section-11.6.1
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Challenge {
+    scheme: String,
+    params: Vec<(String, String)>,
+    token68: Option<String>,
+}
+
+impl Challenge {
+    /// Creates a challenge for the given scheme with no parameters.
+    pub fn new(scheme: impl Into<String>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            params: Vec::new(),
+            token68: None,
+        }
+    }
+
+    /// Adds a parameter to the challenge.
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into().to_ascii_lowercase();
+        self.params.push((name, value.into()));
+        self
+    }
+
+    /// The authentication scheme, as sent by the server.
+    ///
+    /// Schemes are case-insensitive; use [`is_scheme()`](Self::is_scheme)
+    /// to compare them.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Whether this challenge is for the given scheme, ignoring case.
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.scheme.eq_ignore_ascii_case(scheme)
+    }
+
+    /// The protection space of the challenge, if the server sent one.
+    pub fn realm(&self) -> Option<&str> {
+        self.param("realm")
+    }
+
+    /// The value of the parameter called `name`, ignoring case.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// All of the challenge's parameters, in the order they were sent.
+    ///
+    /// Parameter names are converted to lowercase.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The challenge's token68 value, for schemes that send a single opaque
+    /// value instead of parameters.
+    pub fn token68(&self) -> Option<&str> {
+        self.token68.as_deref()
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.scheme)?;
+        if let Some(token68) = &self.token68 {
+            return write!(f, " {token68}");
+        }
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, "{sep}{key}=\"{value}\"")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the authentication challenges in all `WWW-Authenticate` headers.
+///
+/// A single header may contain several challenges, and a response may
+/// contain several headers; challenges are returned in the order they
+/// appear. Malformed challenges are skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use hypertyper::headers;
+/// # use reqwest::header::{HeaderMap, HeaderValue, WWW_AUTHENTICATE};
+/// let mut headers = HeaderMap::new();
+/// headers.insert(
+///     WWW_AUTHENTICATE,
+///     HeaderValue::from_static(r#"Bearer realm="api", error="invalid_token", Basic realm="api""#),
+/// );
+/// let challenges = headers::www_authenticate(&headers);
+/// assert_eq!(challenges.len(), 2);
+/// assert!(challenges[0].is_scheme("bearer"));
+/// assert_eq!(challenges[0].param("error"), Some("invalid_token"));
+/// assert_eq!(challenges[1].realm(), Some("api"));
+/// ```
+pub fn www_authenticate(headers: &HeaderMap) -> Vec<Challenge> {
+    headers
+        .get_all(header::WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(parse_challenges)
+        .collect()
+}
+
+fn parse_challenges(value: &str) -> Vec<Challenge> {
+    let mut parser = HeaderParser {
+        input: value,
+        pos: 0,
+    };
+    let mut challenges = Vec::new();
+    loop {
+        parser.skip_separators();
+        if parser.at_end() {
+            break;
+        }
+        let Some(scheme) = parser.token() else {
+            // Skip whatever garbage is here and try again after the next
+            // comma.
+            parser.skip_until(',');
+            continue;
+        };
+        let mut challenge = Challenge::new(scheme);
+        parser.skip_whitespace();
+        match parser.token68() {
+            Some(token68) => challenge.token68 = Some(token68.to_string()),
+            None => challenge.params = parser.params(),
+        }
+        challenges.push(challenge);
+    }
+    challenges
+}
+
+/// A link from a `Link` header, as described in [RFC 8288].
+///
+/// A link has a target URI, which may be relative to the URI of the
+/// response, and parameters, the most important of which is `rel`, the
+/// relation between the response and the target.
+///
+/// [RFC 8288]: https://www.rfc-editor.org/rfc/rfc8288
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Link {
+    target: String,
+    params: Vec<(String, String)>,
+}
+
+impl Link {
+    /// Creates a link to `target` with no parameters.
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Adds a parameter to the link.
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into().to_ascii_lowercase();
+        self.params.push((name, value.into()));
+        self
+    }
+
+    /// The target URI of the link, as sent by the server.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The relation types of the link, such as `next` or `last`.
+    pub fn rels(&self) -> impl Iterator<Item = &str> {
+        self.param("rel")
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+    }
+
+    /// Whether the link has the relation type `rel`, ignoring case.
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rels()
+            .any(|candidate| candidate.eq_ignore_ascii_case(rel))
+    }
+
+    /// The value of the parameter called `name`, ignoring case.
+    ///
+    /// Only the first occurrence of a parameter is used.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// All of the link's parameters, in the order they were sent.
+    ///
+    /// Parameter names are converted to lowercase.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.target)?;
+        for (key, value) in &self.params {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, "; {key}=\"{value}\"")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the links in all `Link` headers.
+///
+/// Links are returned in the order they appear. Malformed links are
+/// skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use hypertyper::headers;
+/// # use reqwest::header::{HeaderMap, HeaderValue, LINK};
+/// let mut headers = HeaderMap::new();
+/// headers.insert(
+///     LINK,
+///     HeaderValue::from_static(r#"<https://api.example.com/items?page=2>; rel="next", <https://api.example.com/items?page=5>; rel="last""#),
+/// );
+/// let links = headers::links(&headers);
+/// assert_eq!(links.len(), 2);
+/// assert!(links[0].has_rel("next"));
+/// assert_eq!(links[1].target(), "https://api.example.com/items?page=5");
+/// ```
+pub fn links(headers: &HeaderMap) -> Vec<Link> {
+    headers
+        .get_all(header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(parse_links)
+        .collect()
+}
+
+/// Finds the first link in the `Link` headers with the relation type
+/// `rel`, such as `next`.
+///
+/// # Examples
+///
+/// ```
+/// # use hypertyper::headers;
+/// # use reqwest::header::{HeaderMap, HeaderValue, LINK};
+/// let mut headers = HeaderMap::new();
+/// headers.insert(LINK, HeaderValue::from_static("</items?page=2>; rel=next"));
+/// let next = headers::link(&headers, "next").unwrap();
+/// assert_eq!(next.target(), "/items?page=2");
+/// assert!(headers::link(&headers, "prev").is_none());
+/// ```
+pub fn link(headers: &HeaderMap, rel: &str) -> Option<Link> {
+    links(headers).into_iter().find(|link| link.has_rel(rel))
+}
+
+fn parse_links(value: &str) -> Vec<Link> {
+    let mut parser = HeaderParser {
+        input: value,
+        pos: 0,
+    };
+    let mut links = Vec::new();
+    loop {
+        parser.skip_separators();
+        if parser.at_end() {
+            break;
+        }
+        if !parser.eat('<') {
+            parser.skip_until(',');
+            continue;
+        }
+        let target = parser.take_while(|c| c != '>').trim();
+        if !parser.eat('>') {
+            // Unterminated; there is nothing more to parse.
+            break;
+        }
+        let mut link = Link::new(target);
+        loop {
+            parser.skip_whitespace();
+            if !parser.eat(';') {
+                break;
+            }
+            parser.skip_whitespace();
+            let Some(name) = parser.token() else {
+                break;
+            };
+            parser.skip_whitespace();
+            let mut value = String::new();
+            if parser.eat('=') {
+                parser.skip_whitespace();
+                value = if parser.peek() == Some('"') {
+                    parser.quoted_string()
+                } else {
+                    parser.token().unwrap_or_default().to_string()
+                };
+            }
+            link.params.push((name.to_ascii_lowercase(), value));
+        }
+        links.push(link);
+        // Skip anything malformed that is left in this link.
+        parser.skip_until(',');
+    }
+    links
+}
+
+struct HeaderParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> HeaderParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(|c| c == ' ' || c == '\t');
+    }
+
+    fn skip_separators(&mut self) {
+        self.take_while(|c| c == ' ' || c == '\t' || c == ',');
+    }
+
+    fn skip_until(&mut self, stop: char) {
+        self.take_while(|c| c != stop);
+    }
+
+    fn token(&mut self) -> Option<&'a str> {
+        let token = self.take_while(is_tchar);
+        (!token.is_empty()).then_some(token)
+    }
+
+    /// Parses a token68 value, which must be the only thing in its
+    /// challenge.
+    fn token68(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        let value = self.take_while(is_token68_char);
+        let padding = self.take_while(|c| c == '=');
+        let end = start + value.len() + padding.len();
+        self.skip_whitespace();
+        if !value.is_empty() && (self.at_end() || self.peek() == Some(',')) {
+            Some(&self.input[start..end])
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    fn params(&mut self) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        loop {
+            let start = self.pos;
+            let Some(name) = self.token() else {
+                break;
+            };
+            self.skip_whitespace();
+            if !self.eat('=') {
+                // This is the scheme of the next challenge.
+                self.pos = start;
+                break;
+            }
+            self.skip_whitespace();
+            let value = if self.peek() == Some('"') {
+                self.quoted_string()
+            } else {
+                self.token().unwrap_or_default().to_string()
+            };
+            params.push((name.to_ascii_lowercase(), value));
+            self.skip_whitespace();
+            if !self.eat(',') {
+                break;
+            }
+            self.skip_separators();
+        }
+        params
+    }
+
+    fn quoted_string(&mut self) -> String {
+        self.eat('"');
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return value;
+                }
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+        // Unterminated; take everything that is left.
+        self.pos = self.input.len();
+        value
+    }
+}
+
+fn is_tchar(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+fn is_token68_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~+/".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let value = HeaderValue::from_str(retry_after).unwrap();
+        headers.insert(header::RETRY_AFTER, value);
+        headers
+    }
+
+    #[test]
+    fn it_parses_retry_after_seconds() {
+        let headers = headers("30");
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn it_parses_retry_after_dates() {
+        let date = SystemTime::now() + Duration::from_secs(90);
+        let headers = headers(&httpdate::fmt_http_date(date));
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(85) && delay <= Duration::from_secs(90));
+    }
+
+    #[test]
+    fn it_treats_past_dates_as_no_delay() {
+        let headers = headers("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn it_ignores_invalid_retry_after_values() {
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn it_parses_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("100"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("99.0"));
+        headers.insert("ratelimit-reset", HeaderValue::from_static("30"));
+        let expected = RateLimit {
+            limit: Some(100),
+            remaining: 99,
+            reset: Duration::from_secs(30),
+        };
+        assert_eq!(rate_limit(&headers), Some(expected));
+
+        headers.remove("ratelimit-reset");
+        assert_eq!(rate_limit(&headers), None);
+    }
+
+    #[test]
+    fn it_parses_rate_limit_reset_timestamps() {
+        let reset = SystemTime::now() + Duration::from_secs(120);
+        let timestamp = reset.duration_since(SystemTime::UNIX_EPOCH).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("5"));
+        let value = HeaderValue::from_str(&timestamp.as_secs().to_string()).unwrap();
+        headers.insert("x-ratelimit-reset", value);
+        let reset = rate_limit(&headers).unwrap().reset;
+        assert!(reset > Duration::from_secs(115) && reset <= Duration::from_secs(120));
+    }
+
+    #[test]
+    fn it_ignores_rate_limit_resets_out_of_range() {
+        for value in ["1e20", "1e300", "inf", "NaN", "-5"] {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-ratelimit-remaining", HeaderValue::from_static("5"));
+            headers.insert("x-ratelimit-reset", HeaderValue::from_static(value));
+            assert_eq!(rate_limit(&headers), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn it_parses_the_combined_rate_limit_header() {
+        let mut headers = HeaderMap::new();
+        let value = HeaderValue::from_static("limit=10, remaining=4, reset=8");
+        headers.insert("ratelimit", value);
+        let parsed = rate_limit(&headers).unwrap();
+        assert_eq!(parsed.limit, Some(10));
+        assert_eq!(parsed.remaining, 4);
+
+        headers.insert("ratelimit", HeaderValue::from_static("\"default\";r=3;t=6"));
+        let parsed = rate_limit(&headers).unwrap();
+        assert_eq!(parsed.remaining, 3);
+        assert_eq!(parsed.reset, Duration::from_secs(6));
+    }
+
+    #[test]
+    fn it_parses_cache_control_directives() {
+        let mut headers = HeaderMap::new();
+        let first = HeaderValue::from_static("private, Max-Age=60, must-revalidate");
+        let second = HeaderValue::from_static("stale-if-error=\"30\", x-custom");
+        headers.append(header::CACHE_CONTROL, first);
+        headers.append(header::CACHE_CONTROL, second);
+        let cache_control = cache_control(&headers);
+        assert!(cache_control.private);
+        assert!(cache_control.must_revalidate);
+        assert!(!cache_control.no_store);
+        assert_eq!(cache_control.max_age, Some(Duration::from_secs(60)));
+        assert_eq!(cache_control.stale_if_error, Some(Duration::from_secs(30)));
+        assert_eq!(cache_control.stale_while_revalidate, None);
+    }
+
+    #[test]
+    fn it_parses_multiple_challenges_with_params() {
+        let challenges = parse_challenges(
+            r#"Newauth realm="apps", type=1, title="Login to \"apps\"", Basic realm="simple""#,
+        );
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].scheme(), "Newauth");
+        assert_eq!(challenges[0].realm(), Some("apps"));
+        assert_eq!(challenges[0].param("TYPE"), Some("1"));
+        assert_eq!(challenges[0].param("title"), Some(r#"Login to "apps""#));
+        assert!(challenges[1].is_scheme("basic"));
+        assert_eq!(challenges[1].realm(), Some("simple"));
+    }
+
+    #[test]
+    fn it_parses_token68_challenges() {
+        let challenges = parse_challenges("Negotiate YIIBhgYGKwYBBQUCoIIBejCC==, Basic");
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].token68(), Some("YIIBhgYGKwYBBQUCoIIBejCC=="));
+        assert_eq!(challenges[0].params().count(), 0);
+        assert!(challenges[1].is_scheme("Basic"));
+        assert_eq!(challenges[1].token68(), None);
+    }
+
+    #[test]
+    fn it_collects_challenges_from_every_header() {
+        let mut headers = HeaderMap::new();
+        let first = HeaderValue::from_static(r#"Bearer realm="api""#);
+        let second = HeaderValue::from_static("Basic");
+        headers.append(header::WWW_AUTHENTICATE, first);
+        headers.append(header::WWW_AUTHENTICATE, second);
+        let schemes: Vec<_> = www_authenticate(&headers)
+            .iter()
+            .map(|c| c.scheme().to_string())
+            .collect();
+        assert_eq!(schemes, ["Bearer", "Basic"]);
+    }
+
+    #[test]
+    fn it_parses_links() {
+        let mut headers = HeaderMap::new();
+        let value = HeaderValue::from_static(
+            r#"<https://example.com/a,b>; rel="next prefetch"; title="Page \"2\"", </items?page=9>;rel=LAST"#,
+        );
+        headers.insert(header::LINK, value);
+        headers.append(
+            header::LINK,
+            HeaderValue::from_static("garbage, <https://example.com/help>"),
+        );
+        let links = links(&headers);
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].target(), "https://example.com/a,b");
+        assert_eq!(links[0].rels().collect::<Vec<_>>(), ["next", "prefetch"]);
+        assert_eq!(links[0].param("TITLE"), Some("Page \"2\""));
+        assert!(links[1].has_rel("last"));
+        assert_eq!(links[2].params().count(), 0);
+        assert_eq!(link(&headers, "last"), Some(links[1].clone()));
+    }
+
+    #[test]
+    fn it_formats_links() {
+        let link = Link::new("/items?page=2")
+            .with_param("rel", "next")
+            .with_param("title", "Say \"hi\"");
+        assert_eq!(
+            link.to_string(),
+            r#"</items?page=2>; rel="next"; title="Say \"hi\"""#
+        );
+    }
+
+    #[test]
+    fn it_formats_challenges() {
+        let challenge = Challenge::new("Bearer")
+            .with_param("realm", "api")
+            .with_param("error", "invalid_token");
+        assert_eq!(
+            challenge.to_string(),
+            r#"Bearer realm="api", error="invalid_token""#
+        );
+    }
+}
@@ -0,0 +1,489 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! JSON-RPC 2.0 calls over HTTP.
+//!
+//! [JSON-RPC] wraps every call in an envelope: a [`JsonRpcRequest`] names
+//! the method to call and carries its parameters and an id, and the
+//! [`JsonRpcResponse`] echoes the id along with either a result or an
+//! [error object](JsonRpcError). A [`JsonRpcClient`] takes care of that
+//! boilerplate on top of any [`HttpPost`] service. It numbers its requests,
+//! checks that each response belongs to its request, and turns error
+//! objects into [`HttpError::JsonRpc`] errors, so that calling a method
+//! looks like calling a function.
+//!
+//! Several calls can be sent in a single [batch](JsonRpcClient::batch).
+//! The server may answer them in any order; the client matches the
+//! responses to the calls by id and returns the results in the order the
+//! calls were made.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::jsonrpc::JsonRpcClient;
+//! use hypertyper::prelude::*;
+//! use serde_json::json;
+//!
+//! # async fn run<S: HttpPost + Sync>(service: S, auth: Auth) -> HttpResult<()> {
+//! let client = JsonRpcClient::new(service, "https://rpc.example.com", auth);
+//! let block: String = client.call("eth_blockNumber", json!([])).await?;
+//! println!("Latest block: {block}");
+//!
+//! let addresses = ["0x407d73d8a49eeb85d32cf465507dd71d507100c1", "0x0"];
+//! let balances = client
+//!     .batch::<_, _, String>(
+//!         addresses
+//!             .iter()
+//!             .map(|address| ("eth_getBalance", json!([address, "latest"]))),
+//!     )
+//!     .await?;
+//! for (address, balance) in addresses.iter().zip(balances) {
+//!     println!("{address}: {}", balance?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [JSON-RPC]: https://www.jsonrpc.org/specification
+
+use crate::auth::Authenticator;
+use crate::service::HttpPost;
+use crate::{HttpError, HttpResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The version of the protocol sent in every envelope.
+pub const VERSION: &str = "2.0";
+
+/// The error code for a request body that is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+
+/// The error code for a request that is not a valid request object.
+pub const INVALID_REQUEST: i64 = -32600;
+
+/// The error code for a method that does not exist.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The error code for invalid method parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// The error code for an internal error in the server, which is also used
+/// for responses the client cannot make sense of.
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// The id of a request, which the server echoes in its response.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Id {
+    /// A numeric id.
+    Number(u64),
+
+    /// A string id.
+    String(String),
+}
+
+impl From<u64> for Id {
+    fn from(id: u64) -> Self {
+        Id::Number(id)
+    }
+}
+
+impl From<String> for Id {
+    fn from(id: String) -> Self {
+        Id::String(id)
+    }
+}
+
+impl From<&str> for Id {
+    fn from(id: &str) -> Self {
+        Id::String(id.to_string())
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Id::Number(id) => write!(f, "{id}"),
+            Id::String(id) => f.write_str(id),
+        }
+    }
+}
+
+/// A call to a remote method.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct JsonRpcRequest<P = Value> {
+    /// The version of the protocol, which is always [`VERSION`].
+    pub jsonrpc: String,
+
+    /// The name of the method to call.
+    pub method: String,
+
+    /// The parameters of the method, usually an array or an object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<P>,
+
+    /// The id of the request, or `None` for a notification, which the
+    /// server does not answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Id>,
+}
+
+impl<P> JsonRpcRequest<P> {
+    /// Creates a notification that calls `method` with `params`.
+    pub fn new(method: impl Into<String>, params: P) -> Self {
+        Self {
+            jsonrpc: VERSION.to_string(),
+            method: method.into(),
+            params: Some(params),
+            id: None,
+        }
+    }
+
+    /// Sets the id of the request, turning a notification into a call the
+    /// server answers.
+    pub fn with_id(mut self, id: impl Into<Id>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+}
+
+/// The answer to a [`JsonRpcRequest`].
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct JsonRpcResponse {
+    /// The version of the protocol, which should be [`VERSION`].
+    pub jsonrpc: String,
+
+    /// The result of the call, if it succeeded.
+    ///
+    /// This is kept as a [`Value`] because `null` is a perfectly good
+    /// result for many methods.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub result: Value,
+
+    /// The error that occurred, if the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+
+    /// The id of the request, or `None` if the server could not read it.
+    pub id: Option<Id>,
+}
+
+impl JsonRpcResponse {
+    /// Deserializes the result of the call as an `R`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::JsonRpc`] if the call failed, or an error if
+    /// the result cannot be deserialized as an `R`.
+    pub fn into_result<R: DeserializeOwned>(self) -> HttpResult<R> {
+        match self.error {
+            Some(error) => Err(HttpError::JsonRpc(error)),
+            None => serde_json::from_value(self.result).map_err(HttpError::from),
+        }
+    }
+}
+
+/// An error object returned by a failed call.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct JsonRpcError {
+    /// The error code, such as [`METHOD_NOT_FOUND`].
+    pub code: i64,
+
+    /// A short description of the error.
+    pub message: String,
+
+    /// Additional, server-specific information about the error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    /// Creates an error with the given code and message.
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl fmt::Display for JsonRpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+/// Calls remote methods on a JSON-RPC server.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct JsonRpcClient<S> {
+    service: S,
+    uri: String,
+    auth: Box<dyn Authenticator>,
+    next_id: AtomicU64,
+}
+
+impl<S> JsonRpcClient<S> {
+    /// Creates a client that sends calls to the server at `uri` with
+    /// `service`.
+    pub fn new(service: S, uri: impl Into<String>, auth: impl Authenticator + 'static) -> Self {
+        Self {
+            service,
+            uri: uri.into(),
+            auth: Box::new(auth),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// The service used to send calls.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+
+    /// The URI of the server.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    fn next_id(&self) -> Id {
+        Id::Number(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl<S: HttpPost + Sync> JsonRpcClient<S> {
+    /// Calls `method` with `params` and returns its result.
+    ///
+    /// Methods that take no parameters are usually called with an empty
+    /// array, such as `json!([])`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::JsonRpc`] if the call fails, or if the server
+    /// answers a different request.
+    pub async fn call<P, R>(&self, method: &str, params: P) -> HttpResult<R>
+    where
+        P: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id();
+        let request = JsonRpcRequest::new(method, params).with_id(id.clone());
+        let response: JsonRpcResponse = self
+            .service
+            .post(self.uri.as_str(), &*self.auth, &request)
+            .await?;
+        if response.error.is_none() && response.id.as_ref() != Some(&id) {
+            return Err(invalid_response(format!(
+                "expected a response to request {id}"
+            )));
+        }
+        response.into_result()
+    }
+
+    /// Sends a batch of calls to methods and their parameters, and returns
+    /// the result of each call in the same order as `calls`.
+    ///
+    /// Every call in the batch returns the same type, which can be
+    /// [`Value`] for methods whose results differ.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch could not be sent, or if the server
+    /// rejected the batch as a whole. Calls that fail individually, or that
+    /// the server did not answer, produce an error in their place in the
+    /// results.
+    pub async fn batch<M, P, R>(
+        &self,
+        calls: impl IntoIterator<Item = (M, P)>,
+    ) -> HttpResult<Vec<HttpResult<R>>>
+    where
+        M: Into<String>,
+        P: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let requests: Vec<_> = calls
+            .into_iter()
+            .map(|(method, params)| JsonRpcRequest::new(method, params).with_id(self.next_id()))
+            .collect();
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body: Value = self
+            .service
+            .post(self.uri.as_str(), &*self.auth, &requests)
+            .await?;
+        let responses: Vec<JsonRpcResponse> = match body {
+            Value::Array(responses) => responses
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<_, _>>()?,
+            // A batch that is rejected as a whole is answered with a single
+            // error response.
+            response => {
+                let response: JsonRpcResponse = serde_json::from_value(response)?;
+                return Err(HttpError::JsonRpc(response.error.unwrap_or_else(|| {
+                    JsonRpcError::new(INTERNAL_ERROR, "expected an array of responses")
+                })));
+            }
+        };
+
+        let mut responses: HashMap<_, _> = responses
+            .into_iter()
+            .filter_map(|response| Some((response.id.clone()?, response)))
+            .collect();
+        let results = requests
+            .iter()
+            .filter_map(|request| request.id.as_ref())
+            .map(|id| match responses.remove(id) {
+                Some(response) => response.into_result(),
+                None => Err(invalid_response(format!("no response to request {id}"))),
+            })
+            .collect();
+        Ok(results)
+    }
+}
+
+fn invalid_response(message: String) -> HttpError {
+    HttpError::JsonRpc(JsonRpcError::new(INTERNAL_ERROR, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use reqwest::IntoUrl;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Answers every request with the next scripted response, with its id
+    /// filled in from the request if it has none, and records the requests.
+    #[derive(Default)]
+    struct ScriptedServer {
+        responses: Mutex<Vec<Value>>,
+        requests: Mutex<Vec<Value>>,
+    }
+
+    impl ScriptedServer {
+        fn new(responses: impl IntoIterator<Item = Value>) -> Self {
+            let mut responses: Vec<_> = responses.into_iter().collect();
+            responses.reverse();
+            Self {
+                responses: Mutex::new(responses),
+                requests: Mutex::default(),
+            }
+        }
+    }
+
+    impl HttpPost for ScriptedServer {
+        async fn post<U, D, R>(&self, _uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let request = serde_json::to_value(data)?;
+            self.requests.lock().unwrap().push(request.clone());
+            let mut response = self.responses.lock().unwrap().pop().unwrap();
+            if let (Some(id), None) = (request.get("id"), response.get("id")) {
+                response["id"] = id.clone();
+            }
+            Ok(serde_json::from_value(response)?)
+        }
+    }
+
+    fn client(responses: impl IntoIterator<Item = Value>) -> JsonRpcClient<ScriptedServer> {
+        let server = ScriptedServer::new(responses);
+        JsonRpcClient::new(server, "https://rpc.example.com", Auth::new("key"))
+    }
+
+    #[tokio::test]
+    async fn it_calls_methods() -> HttpResult<()> {
+        let client = client([
+            json!({ "jsonrpc": "2.0", "result": "0x4b7" }),
+            json!({ "jsonrpc": "2.0", "result": null }),
+        ]);
+        let block: String = client.call("eth_blockNumber", json!([])).await?;
+        assert_eq!(block, "0x4b7");
+        let tx: Option<String> = client
+            .call("eth_getTransactionByHash", json!(["0xabc"]))
+            .await?;
+        assert_eq!(tx, None);
+
+        let requests = client.service().requests.lock().unwrap();
+        assert_eq!(
+            requests[0],
+            json!({ "jsonrpc": "2.0", "method": "eth_blockNumber", "params": [], "id": 1 })
+        );
+        assert_eq!(requests[1]["id"], 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_maps_error_objects() {
+        let client = client([json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32601, "message": "Method not found", "data": "eth_foo" },
+        })]);
+        let result: HttpResult<Value> = client.call("eth_foo", json!([])).await;
+        match result {
+            Err(HttpError::JsonRpc(error)) => {
+                assert_eq!(error.code, METHOD_NOT_FOUND);
+                assert_eq!(error.data, Some(json!("eth_foo")));
+                assert_eq!(error.to_string(), "Method not found (code -32601)");
+            }
+            other => panic!("expected a JSON-RPC error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_rejects_responses_to_other_requests() {
+        let client = client([json!({ "jsonrpc": "2.0", "result": 1, "id": 7 })]);
+        let result: HttpResult<u64> = client.call("eth_chainId", json!([])).await;
+        assert!(matches!(result, Err(HttpError::JsonRpc(error)) if error.code == INTERNAL_ERROR));
+    }
+
+    #[tokio::test]
+    async fn it_matches_batch_responses_to_calls() -> HttpResult<()> {
+        let client = client([json!([
+            { "jsonrpc": "2.0", "result": "0x2", "id": 2 },
+            { "jsonrpc": "2.0", "error": { "code": -32602, "message": "Invalid params" }, "id": 3 },
+            { "jsonrpc": "2.0", "result": "0x1", "id": 1 },
+        ])]);
+        let calls = ["0xa", "0xb", "0xc", "0xd"]
+            .map(|address| ("eth_getBalance", json!([address, "latest"])));
+        let results = client.batch::<_, _, String>(calls).await?;
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_deref().ok(), Some("0x1"));
+        assert_eq!(results[1].as_deref().ok(), Some("0x2"));
+        assert!(
+            matches!(&results[2], Err(HttpError::JsonRpc(error)) if error.code == INVALID_PARAMS)
+        );
+        assert!(
+            matches!(&results[3], Err(HttpError::JsonRpc(error)) if error.code == INTERNAL_ERROR)
+        );
+
+        let requests = client.service().requests.lock().unwrap();
+        assert_eq!(requests[0].as_array().unwrap().len(), 4);
+        assert_eq!(requests[0][3]["id"], 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reports_rejected_batches() {
+        let client = client([json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32600, "message": "Invalid Request" },
+            "id": null,
+        })]);
+        let result = client
+            .batch::<_, _, Value>([("eth_chainId", json!([]))])
+            .await;
+        assert!(matches!(result, Err(HttpError::JsonRpc(error)) if error.code == INVALID_REQUEST));
+    }
+}
@@ -32,9 +32,69 @@
 //!
 //! # Features
 //!
+//! - **oidc** -
+//!   Enables validation of OpenID Connect ID tokens.
+//! - **path-to-error** -
+//!   Reports the path to the value that could not be deserialized when a
+//!   response body does not match the expected type.
+//! - **schema-drift** -
+//!   Enables the `SchemaDriftService`, which reports differences between
+//!   API responses and the types they are deserialized into.
+//! - **json-path** -
+//!   Enables extraction of individual values from JSON bodies with JSONPath
+//!   expressions.
+//! - **loadtest** -
+//!   Enables the `loadtest` module, which runs request scenarios at a target
+//!   rate and reports latency percentiles and error rates.
+//! - **mdns** -
+//!   Enables discovery of HTTP services on the local network using
+//!   multicast DNS.
+//! - **srv** -
+//!   Enables discovery of HTTP services using DNS SRV records.
+//! - **csv** -
+//!   Enables decoding of CSV response bodies, and writing exported records
+//!   to CSV files.
+//! - **grpc-web** -
+//!   Enables unary gRPC-Web calls.
+//! - **html** -
+//!   Enables parsing of HTML response bodies and extraction of their
+//!   contents with CSS selectors.
+//! - **feeds** -
+//!   Enables the `feeds` module, which fetches and parses RSS and Atom
+//!   feeds.
+//! - **tracing** -
+//!   Records the time spent serializing and deserializing JSON in
+//!   [`tracing`](https://crates.io/crates/tracing) spans.
 //! - **test-utils** -
 //!   Includes features that are useful for testing HTTP functionality, such as
 //!   the `HttpTestService`.
+//! - **toml** -
+//!   Lets test utilities load TOML fixtures.
+//! - **yaml** -
+//!   Lets test utilities load YAML fixtures.
+//! - **ws** -
+//!   Enables WebSocket connections that share a client's configuration.
+//! - **xml** -
+//!   Enables XML request and response bodies.
+//! - **msgpack** -
+//!   Enables MessagePack request and response bodies.
+//! - **cbor** -
+//!   Enables CBOR request and response bodies.
+//! - **sigv4** -
+//!   Enables AWS Signature Version 4 signing of requests to AWS and
+//!   S3-compatible services.
+//! - **hmac** -
+//!   Enables signing of requests with an HMAC over their method, path,
+//!   timestamp, and body, and verification of webhook signatures.
+//! - **digest-auth** -
+//!   Enables HTTP Digest authentication, which many embedded devices
+//!   require.
+//! - **oauth1** -
+//!   Enables OAuth 1.0a request signing.
+//! - **keyring** -
+//!   Enables reading API keys from the operating system's keyring.
+//! - **encrypted-store** -
+//!   Enables encryption of saved OAuth 2.0 refresh tokens.
 //!
 //! # History
 //!
@@ -50,10 +110,37 @@
 //! [`hypertyper::prelude`]: prelude
 
 pub mod auth;
+pub mod buffer;
+pub mod codec;
+pub mod decode;
+pub mod diagnostics;
+pub mod discovery;
+pub mod download;
+pub mod endpoint;
+pub mod export;
+#[cfg(feature = "feeds")]
+pub mod feeds;
+pub mod graphql;
+#[cfg(feature = "grpc-web")]
+pub mod grpc_web;
+pub mod headers;
+pub mod jsonrpc;
+#[cfg(feature = "loadtest")]
+pub mod loadtest;
+pub mod pagination;
+pub mod poll;
+pub mod scope;
 pub mod service;
+pub mod sse;
+pub mod timing;
+pub mod upload;
+#[cfg(feature = "ws")]
+pub mod ws;
 
 pub use reqwest::Client as HttpClient;
-use reqwest::{self, header};
+use reqwest::header::HeaderMap;
+use reqwest::{self, StatusCode, header};
+use std::time::Duration;
 use thiserror::Error;
 
 /// Produces new HTTP clients from a template.
@@ -68,6 +155,7 @@ use thiserror::Error;
 #[derive(Debug)]
 pub struct HttpClientFactory {
     user_agent: String,
+    pool_max_idle_per_host: Option<usize>,
 }
 
 impl HttpClientFactory {
@@ -113,17 +201,41 @@ impl HttpClientFactory {
     pub fn with_user_agent(user_agent: impl Into<String>) -> Self {
         Self {
             user_agent: user_agent.into(),
+            pool_max_idle_per_host: None,
         }
     }
 
+    /// Sets the maximum number of idle connections that clients produced by
+    /// this factory keep open to each host.
+    ///
+    /// Raise this when making many concurrent requests to the same host,
+    /// such as for [segmented downloads](download::Downloader::with_segments),
+    /// so that connections are reused instead of reopened.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hypertyper::HttpClientFactory;
+    /// let factory = HttpClientFactory::with_user_agent("my cool user agent")
+    ///     .pool_max_idle_per_host(8);
+    /// assert_eq!(factory.max_idle_per_host(), Some(8));
+    /// ```
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
     /// Creates a new client that can be used to make HTTP requests.
     ///
     /// # Panics
     ///
     /// This method panics if a TLS backend cannot be initialized.
     pub fn create(&self) -> HttpClient {
-        reqwest::ClientBuilder::new()
-            .user_agent(self.user_agent())
+        let mut builder = reqwest::ClientBuilder::new().user_agent(self.user_agent());
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        builder
             .build()
             // Better error handling? According to the docs, build() only
             // fails if a TLS backend cannot be initialized, or if DNS
@@ -136,6 +248,12 @@ impl HttpClientFactory {
     pub fn user_agent(&self) -> &str {
         &self.user_agent
     }
+
+    /// The maximum number of idle connections per host kept by clients
+    /// produced by this factory, if it has been limited.
+    pub fn max_idle_per_host(&self) -> Option<usize> {
+        self.pool_max_idle_per_host
+    }
 }
 
 /// The result of an HTTP request.
@@ -157,10 +275,71 @@ pub enum HttpError {
     #[error("Error serializing POST body: {0}")]
     Serialization(#[from] serde_json::Error),
 
+    /// A response body that could not be deserialized into the expected
+    /// type, along with the beginning of the body.
+    ///
+    /// See [`decode::Decoder`].
+    #[error(
+        "Error decoding response body{}: {source}; body began with {snippet:?}",
+        path.as_ref().map(|path| format!(" at {path}")).unwrap_or_default()
+    )]
+    Decode {
+        /// The underlying deserialization error.
+        source: serde_json::Error,
+
+        /// The path to the value that could not be deserialized, such as
+        /// `data.users[3].email`.
+        ///
+        /// This is only available when the **path-to-error** feature is
+        /// enabled.
+        path: Option<String>,
+
+        /// The beginning of the response body.
+        snippet: String,
+    },
+
+    /// A response body that contained fields the expected type does not
+    /// have, when [unknown fields are denied](decode::UnknownFields::Deny).
+    #[error("Response contained unknown fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+
+    /// A response body that contained an API-specific error rather than the
+    /// expected data.
+    ///
+    /// See [`decode::Decoder::with_error_type()`].
+    #[error("API returned an error: {0}")]
+    Api(Box<dyn std::error::Error + Send + Sync>),
+
+    /// A URL that could not be parsed.
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+
     /// An unsuccessful HTTP status code in an HTTP response.
     #[error("Request returned HTTP {0}")]
     Http(reqwest::StatusCode),
 
+    /// An HTTP 401 response, along with the authentication challenges the
+    /// server sent in its `WWW-Authenticate` headers.
+    #[error("Request returned HTTP 401 Unauthorized{}", challenge_schemes(.0))]
+    Unauthorized(Vec<headers::Challenge>),
+
+    /// An HTTP 429 or 503 response that asked the client to wait for the
+    /// given amount of time before trying again.
+    #[error("Request returned HTTP {0}; retry after {1:?}")]
+    RetryAfter(reqwest::StatusCode, Duration),
+
+    /// A header value that could not be sent in a request.
+    #[error("Invalid header value: {0}")]
+    InvalidHeaderValue(#[from] header::InvalidHeaderValue),
+
+    /// A JSONPath expression that could not be parsed.
+    #[error("Invalid JSONPath expression: {0}")]
+    InvalidJsonPath(String),
+
+    /// A JSONPath expression that did not match exactly one value.
+    #[error("JSONPath expression did not match exactly one value: {0}")]
+    JsonPathNotFound(String),
+
     /// A missing Content-Type header in a response.
     #[error("Missing Content-Type header")]
     MissingContentType,
@@ -170,8 +349,426 @@ pub enum HttpError {
     InvalidContentType(#[from] header::ToStrError),
 
     /// A Content-Type that is not understood by the service.
-    #[error("Unexpected content type: {0}")]
-    UnexpectedContentType(String),
+    ///
+    /// See [`codec::Negotiator`].
+    #[error("Unexpected content type {content_type}; expected one of {}", acceptable.join(", "))]
+    UnexpectedContentType {
+        /// The content type of the response.
+        content_type: String,
+
+        /// The content types that would have been understood.
+        acceptable: Vec<String>,
+    },
+
+    /// An HTML page received where a structured body was expected, such as
+    /// an error page from a proxy or load balancer.
+    ///
+    /// See [`decode::sniff`].
+    #[error(
+        "Expected a structured body but received an HTML page{}; body began with {snippet:?}",
+        title.as_ref().map(|t| format!(" titled {t:?}")).unwrap_or_default()
+    )]
+    HtmlErrorPage {
+        /// The title of the page, if it has one.
+        title: Option<String>,
+
+        /// The beginning of the body.
+        snippet: String,
+    },
+
+    /// A response from a captive portal or intercepting proxy, such as a
+    /// hotel Wi-Fi login page, rather than from the server that was asked
+    /// for.
+    ///
+    /// See [`decode::sniff`].
+    #[error(
+        "Request was intercepted by a captive portal or proxy{}{}; sign in to the network and try again",
+        title.as_ref().map(|t| format!(" ({t:?})")).unwrap_or_default(),
+        url.as_ref().map(|u| format!(" at {u}")).unwrap_or_default()
+    )]
+    CaptivePortal {
+        /// The URL of the portal's login page, if it could be found.
+        url: Option<url::Url>,
+
+        /// The title of the portal's page, if it has one.
+        title: Option<String>,
+    },
+
+    /// A body in a format that cannot be decoded.
+    ///
+    /// See [`decode::sniff`].
+    #[error("Expected a structured body but received {format}; body began with {snippet:?}")]
+    UnexpectedFormat {
+        /// The format of the body.
+        format: decode::sniff::Format,
+
+        /// The beginning of the body.
+        snippet: String,
+    },
+
+    /// An error response from an OAuth 2.0 authorization server.
+    #[error("OAuth error: {error}{}", description.as_ref().map(|d| format!(" ({d})")).unwrap_or_default())]
+    OAuth {
+        /// The error code, such as `invalid_grant`.
+        error: String,
+
+        /// A human-readable description of the error, if the server
+        /// provided one.
+        description: Option<String>,
+    },
+
+    /// A GraphQL response that contained errors.
+    ///
+    /// See [`graphql`].
+    #[error("GraphQL request failed: {}", graphql_messages(.0))]
+    GraphQl(Vec<graphql::GraphQlError>),
+
+    /// An error object returned by a JSON-RPC call.
+    ///
+    /// See [`jsonrpc`].
+    #[error("JSON-RPC call failed: {0}")]
+    JsonRpc(jsonrpc::JsonRpcError),
+
+    /// A token that could not be decoded or failed validation.
+    #[error("Invalid token: {0}")]
+    InvalidToken(String),
+
+    /// Credentials that cannot be sent, such as a Basic authentication user
+    /// name containing a colon.
+    #[error("Invalid credentials: {0}")]
+    InvalidCredentials(String),
+
+    /// A signature that could not be verified, such as that of a webhook
+    /// whose body does not match its signature.
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    /// A request that did not complete within the given amount of time.
+    #[error("Request timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// A request that was cancelled along with the rest of its scope.
+    ///
+    /// See [`scope`].
+    #[error("Request was cancelled")]
+    Cancelled,
+
+    /// A request that was not sent because too many other requests were
+    /// already waiting for the rate limiter.
+    #[error("Too many requests are waiting for the rate limiter")]
+    RateLimited,
+
+    /// An error from a request whose result was shared by several callers.
+    ///
+    /// See [`service::single_flight`].
+    #[error("{0}")]
+    Shared(std::sync::Arc<HttpError>),
+
+    /// A request that was not sent because too many recent requests to the
+    /// same host have failed.
+    #[error("Circuit breaker is open for host: {0}")]
+    CircuitOpen(String),
+
+    /// A streamed response that was interrupted to make room for a
+    /// higher-priority request.
+    ///
+    /// See [`service::concurrency`].
+    #[error("Stream was preempted by a higher-priority request")]
+    Preempted,
+
+    /// An error reading or writing a file.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A downloaded file whose checksum did not match the expected value.
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        /// The expected checksum.
+        expected: String,
+
+        /// The checksum of the downloaded data.
+        actual: String,
+    },
+
+    /// A range request that the server answered with the entire resource.
+    ///
+    /// See [`service::HttpGetRange`].
+    #[error("Server does not support range requests for {0}")]
+    RangeNotSupported(String),
+
+    /// A response that accepted an asynchronous job without saying where
+    /// to check its status.
+    ///
+    /// See [`poll::job`].
+    #[error("Accepted job has no Location or Operation-Location header")]
+    MissingLocation,
+
+    /// An asynchronous job that failed or was canceled.
+    ///
+    /// See [`poll::job`].
+    #[error("Job {status}: {body}")]
+    JobFailed {
+        /// The final status of the job, in lowercase.
+        status: String,
+
+        /// The body of the job's final status response.
+        body: String,
+    },
+
+    /// A CSV body that could not be parsed or deserialized.
+    ///
+    /// See [`decode::csv`].
+    #[cfg(feature = "csv")]
+    #[error("Error decoding CSV: {0}")]
+    Csv(#[from] csv::Error),
+
+    /// A TOML document that could not be deserialized.
+    #[cfg(feature = "toml")]
+    #[error("Error decoding TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    /// A YAML document that could not be deserialized.
+    #[cfg(feature = "yaml")]
+    #[error("Error decoding YAML: {0}")]
+    Yaml(#[from] serde_norway::Error),
+
+    /// A body that could not be parsed as a feed.
+    ///
+    /// See [`feeds`].
+    #[cfg(feature = "feeds")]
+    #[error("Error parsing feed: {0}")]
+    Feed(#[from] feed_rs::parser::ParseFeedError),
+
+    /// An XML body that could not be deserialized into the expected type,
+    /// along with the beginning of the body.
+    ///
+    /// See [`decode::xml`].
+    #[cfg(feature = "xml")]
+    #[error("Error decoding XML body: {source}; body began with {snippet:?}")]
+    XmlDecode {
+        /// The underlying deserialization error.
+        source: quick_xml::DeError,
+
+        /// The beginning of the body.
+        snippet: String,
+    },
+
+    /// A value that could not be serialized as XML.
+    ///
+    /// See [`decode::xml`].
+    #[cfg(feature = "xml")]
+    #[error("Error serializing XML body: {0}")]
+    XmlSerialization(#[from] quick_xml::SeError),
+
+    /// A MessagePack body that could not be deserialized into the expected
+    /// type.
+    ///
+    /// See [`decode::msgpack`].
+    #[cfg(feature = "msgpack")]
+    #[error("Error decoding MessagePack body: {0}")]
+    MsgPackDecode(#[from] rmp_serde::decode::Error),
+
+    /// A value that could not be serialized as MessagePack.
+    ///
+    /// See [`decode::msgpack`].
+    #[cfg(feature = "msgpack")]
+    #[error("Error serializing MessagePack body: {0}")]
+    MsgPackSerialization(#[from] rmp_serde::encode::Error),
+
+    /// A CBOR body that could not be deserialized into the expected type.
+    ///
+    /// See [`decode::cbor`].
+    #[cfg(feature = "cbor")]
+    #[error("Error decoding CBOR body: {0}")]
+    CborDecode(#[from] ciborium::de::Error<std::io::Error>),
+
+    /// A value that could not be serialized as CBOR.
+    ///
+    /// See [`decode::cbor`].
+    #[cfg(feature = "cbor")]
+    #[error("Error serializing CBOR body: {0}")]
+    CborSerialization(#[from] ciborium::ser::Error<std::io::Error>),
+
+    /// An error on a WebSocket connection.
+    ///
+    /// See [`ws`].
+    #[cfg(feature = "ws")]
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+
+    /// A gRPC call that ended with a status other than `OK`.
+    ///
+    /// See [`grpc_web`].
+    #[cfg(feature = "grpc-web")]
+    #[error("gRPC call failed with status {code}: {message}")]
+    Grpc {
+        /// The gRPC status code.
+        code: u32,
+
+        /// The status message sent by the server.
+        message: String,
+    },
+
+    /// A CSS selector that could not be parsed.
+    ///
+    /// See [`decode::html`].
+    #[cfg(feature = "html")]
+    #[error("Invalid CSS selector {selector:?}: {message}")]
+    Selector {
+        /// The selector that could not be parsed.
+        selector: String,
+
+        /// Why the selector could not be parsed.
+        message: String,
+    },
+
+    /// A test service or loader that has no test data for a request or
+    /// resource, along with a description of what it was looking for.
+    ///
+    /// See [`service::testing`].
+    #[cfg(feature = "test-utils")]
+    #[error("Could not find test data: {0}")]
+    FixtureNotFound(String),
+
+    /// A request that was not sent because the cache is offline and has no
+    /// response for the given URI.
+    ///
+    /// See [`service::cache::CachePolicy::with_offline()`].
+    #[error("No cached response while offline: {0}")]
+    NotCached(String),
+}
+
+impl HttpError {
+    /// Creates an error for an unsuccessful HTTP response.
+    ///
+    /// If the response is an HTTP 429 or 503 with a valid `Retry-After`
+    /// header, the delay is preserved in an [`HttpError::RetryAfter`]. An
+    /// HTTP 401 becomes an [`HttpError::Unauthorized`] carrying the
+    /// response's authentication challenges. Otherwise an
+    /// [`HttpError::Http`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hypertyper::HttpError;
+    /// # use reqwest::StatusCode;
+    /// # use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    /// # use std::time::Duration;
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(RETRY_AFTER, HeaderValue::from_static("5"));
+    /// let err = HttpError::from_status(StatusCode::TOO_MANY_REQUESTS, &headers);
+    /// assert_eq!(err.retry_after(), Some(Duration::from_secs(5)));
+    /// ```
+    pub fn from_status(status: StatusCode, headers: &HeaderMap) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => HttpError::Unauthorized(headers::www_authenticate(headers)),
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+                match headers::retry_after(headers) {
+                    Some(delay) => HttpError::RetryAfter(status, delay),
+                    None => HttpError::Http(status),
+                }
+            }
+            _ => HttpError::Http(status),
+        }
+    }
+
+    /// The HTTP status code of the response that caused this error, if the
+    /// error was caused by an unsuccessful response.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            HttpError::Http(status) | HttpError::RetryAfter(status, _) => Some(*status),
+            HttpError::Unauthorized(_) => Some(StatusCode::UNAUTHORIZED),
+            HttpError::Request(err) => err.status(),
+            HttpError::Shared(err) => err.status(),
+            _ => None,
+        }
+    }
+
+    /// How long the server asked the client to wait before trying again,
+    /// if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            HttpError::RetryAfter(_, delay) => Some(*delay),
+            HttpError::Shared(err) => err.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// The API-specific error decoded from the response body, if it is an
+    /// `E`.
+    pub fn api_error<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            HttpError::Api(err) => err.downcast_ref(),
+            HttpError::Shared(err) => err.api_error(),
+            _ => None,
+        }
+    }
+
+    /// The authentication challenges sent with an HTTP 401 response.
+    ///
+    /// Returns an empty slice for any other error.
+    pub fn challenges(&self) -> &[headers::Challenge] {
+        match self {
+            HttpError::Unauthorized(challenges) => challenges,
+            HttpError::Shared(err) => err.challenges(),
+            _ => &[],
+        }
+    }
+
+    /// Whether the request that produced this error might succeed if it is
+    /// tried again.
+    ///
+    /// Connection failures, including [I/O errors](HttpError::Io) from
+    /// connections that were refused, reset, or dropped,
+    /// [timeouts](HttpError::Timeout), [preempted](HttpError::Preempted)
+    /// streams, and HTTP 408, 429, 500, 502, 503, and 504 responses are
+    /// considered retryable. All other errors are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HttpError::Request(err) => err.is_connect() || err.is_timeout(),
+            HttpError::Io(err) => is_connection_error(err),
+            HttpError::RetryAfter(_, _) | HttpError::Timeout(_) | HttpError::Preempted => true,
+            HttpError::Shared(err) => err.is_retryable(),
+            HttpError::Http(status) => matches!(
+                *status,
+                StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::INTERNAL_SERVER_ERROR
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Whether `err` means a connection failed, as opposed to a local problem
+/// such as a missing file.
+pub(crate) fn is_connection_error(err: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        err.kind(),
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::BrokenPipe
+            | ErrorKind::TimedOut
+    )
+}
+
+fn challenge_schemes(challenges: &[headers::Challenge]) -> String {
+    if challenges.is_empty() {
+        return String::new();
+    }
+    let schemes: Vec<_> = challenges.iter().map(|c| c.scheme()).collect();
+    format!(" (accepts {})", schemes.join(", "))
+}
+
+fn graphql_messages(errors: &[graphql::GraphQlError]) -> String {
+    let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+    messages.join("; ")
 }
 
 /// Convenience module for the most common Hypertyper imports.
@@ -185,7 +782,7 @@ pub enum HttpError {
 /// will bring in the most common and stable traits and data structures
 /// for Hypertyper into your module.
 pub mod prelude {
-    pub use crate::auth::Auth;
+    pub use crate::auth::{Auth, Authenticator};
     pub use crate::service::{HttpGet, HttpPost, HttpService};
     pub use crate::{HttpClient, HttpClientFactory, HttpError, HttpResult};
     pub use reqwest::IntoUrl;
@@ -206,13 +803,16 @@ macro_rules! http_factory {
 
 #[cfg(test)]
 mod tests {
-    use crate::HttpClientFactory;
+    use crate::{HttpClientFactory, HttpError};
     use regex::Regex;
+    use reqwest::StatusCode;
+    use reqwest::header::{self, HeaderMap, HeaderValue};
+    use std::time::Duration;
 
     impl Default for HttpClientFactory {
         fn default() -> Self {
             let user_agent = format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-            Self { user_agent }
+            HttpClientFactory::with_user_agent(user_agent)
         }
     }
 
@@ -222,10 +822,56 @@ mod tests {
         let user_agent = factory.user_agent();
         let version_re = Regex::new(r"^[a-z]+ v\d+\.\d+\.\d+(-(alpha|beta)(\.\d+)?)?$").unwrap();
         assert!(
-            version_re.is_match(&user_agent),
+            version_re.is_match(user_agent),
             "{} does not match {}",
             user_agent,
             version_re,
         );
     }
+
+    #[test]
+    fn it_classifies_transient_status_codes_as_retryable() {
+        assert!(HttpError::Http(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+        assert!(!HttpError::Http(StatusCode::NOT_FOUND).is_retryable());
+        assert!(!HttpError::MissingContentType.is_retryable());
+    }
+
+    #[test]
+    fn it_classifies_connection_failures_as_retryable() {
+        let io = |kind| HttpError::Io(std::io::Error::new(kind, "oops"));
+        assert!(io(std::io::ErrorKind::ConnectionReset).is_retryable());
+        assert!(io(std::io::ErrorKind::ConnectionRefused).is_retryable());
+        assert!(!io(std::io::ErrorKind::NotFound).is_retryable());
+    }
+
+    #[test]
+    fn it_creates_retry_after_errors_only_for_429_and_503() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("10"));
+        let err = HttpError::from_status(StatusCode::SERVICE_UNAVAILABLE, &headers);
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(10)));
+        assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(err.is_retryable());
+
+        let err = HttpError::from_status(StatusCode::BAD_REQUEST, &headers);
+        assert!(matches!(err, HttpError::Http(StatusCode::BAD_REQUEST)));
+        assert_eq!(err.retry_after(), None);
+    }
+
+    #[test]
+    fn it_exposes_challenges_on_401_errors() {
+        let mut headers = HeaderMap::new();
+        let value = HeaderValue::from_static(r#"Bearer realm="api", Basic realm="api""#);
+        headers.insert(header::WWW_AUTHENTICATE, value);
+        let err = HttpError::from_status(StatusCode::UNAUTHORIZED, &headers);
+        assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(err.challenges().len(), 2);
+        assert!(err.challenges()[0].is_scheme("Bearer"));
+        assert_eq!(
+            err.to_string(),
+            "Request returned HTTP 401 Unauthorized (accepts Bearer, Basic)"
+        );
+        assert!(!err.is_retryable());
+    }
 }
@@ -0,0 +1,294 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Load generation.
+//!
+//! A [`LoadTest`] runs a scenario, usually one or more requests made with
+//! an [`HttpService`](crate::service::HttpService), over and over at a
+//! fixed rate, and reports how long each run took and how many failed.
+//! This is useful for checking that an upstream API can handle the load a
+//! client is expected to produce, and for tuning client-side settings such
+//! as [concurrency limits](crate::service::concurrency) and connection
+//! pools before launch.
+//!
+//! Load is open-loop: scenarios are started at the target rate whether or
+//! not earlier runs have finished, so a slow upstream shows up as higher
+//! latency rather than as a lower request rate. Runs started during the
+//! warm-up period, while connections are being established and caches
+//! filled, are not included in the report.
+//!
+//! This module is only available with the **loadtest** feature.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::loadtest::LoadTest;
+//! use hypertyper::prelude::*;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! # async fn run<S: HttpService + Send + Sync + 'static>(service: S) {
+//! let service = Arc::new(service);
+//! let report = LoadTest::new(move || {
+//!     let service = Arc::clone(&service);
+//!     async move { service.get("https://api.example.com/users").await }
+//! })
+//! .with_rate(50.0)
+//! .with_warm_up(Duration::from_secs(5))
+//! .with_duration(Duration::from_secs(60))
+//! .run()
+//! .await;
+//! println!("{report}");
+//! # }
+//! ```
+
+use crate::HttpResult;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::{Instant, MissedTickBehavior};
+
+/// Runs a scenario repeatedly at a target rate.
+///
+/// See the [module documentation](crate::loadtest) for details.
+pub struct LoadTest<F> {
+    scenario: Arc<F>,
+    rate: f64,
+    warm_up: Duration,
+    duration: Duration,
+}
+
+impl<F, Fut, T> LoadTest<F>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = HttpResult<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    /// Creates a load test that runs `scenario` once per second for ten
+    /// seconds, without a warm-up period.
+    ///
+    /// A run of the scenario fails if it returns an error.
+    pub fn new(scenario: F) -> Self {
+        Self {
+            scenario: Arc::new(scenario),
+            rate: 1.0,
+            warm_up: Duration::ZERO,
+            duration: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets how many times per second the scenario is started.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is not positive.
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        assert!(rate > 0.0, "load test rate must be positive");
+        self.rate = rate;
+        self
+    }
+
+    /// Sets how long to run the scenario before measuring it.
+    pub fn with_warm_up(mut self, warm_up: Duration) -> Self {
+        self.warm_up = warm_up;
+        self
+    }
+
+    /// Sets how long to measure the scenario for, after warming up.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Runs the load test, waiting for every run of the scenario to finish.
+    pub async fn run(self) -> LoadReport {
+        let start = Instant::now();
+        let measure_from = start + self.warm_up;
+        let end = measure_from + self.duration;
+
+        let mut ticks = tokio::time::interval(Duration::from_secs_f64(1.0 / self.rate));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Burst);
+        let mut runs = JoinSet::new();
+        loop {
+            let started_at = ticks.tick().await;
+            if started_at >= end {
+                break;
+            }
+            let scenario = Arc::clone(&self.scenario);
+            runs.spawn(async move {
+                let succeeded = scenario().await.is_ok();
+                (started_at, started_at.elapsed(), succeeded)
+            });
+        }
+
+        let mut latencies = Vec::new();
+        let mut errors = 0;
+        while let Some(run) = runs.join_next().await {
+            // A scenario that panics counts as a failure.
+            let (started_at, latency, succeeded) = match run {
+                Ok(run) => run,
+                Err(_) => (end, Duration::ZERO, false),
+            };
+            if started_at < measure_from {
+                continue;
+            }
+            latencies.push(latency);
+            if !succeeded {
+                errors += 1;
+            }
+        }
+        latencies.sort();
+        LoadReport {
+            latencies,
+            errors,
+            duration: self.duration,
+        }
+    }
+}
+
+impl<F> fmt::Debug for LoadTest<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadTest")
+            .field("rate", &self.rate)
+            .field("warm_up", &self.warm_up)
+            .field("duration", &self.duration)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The results of a [`LoadTest`].
+///
+/// Latencies cover both successful and failed runs.
+#[derive(Clone, Debug)]
+pub struct LoadReport {
+    latencies: Vec<Duration>,
+    errors: usize,
+    duration: Duration,
+}
+
+impl LoadReport {
+    /// The number of times the scenario was run after warming up.
+    pub fn runs(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// The number of runs that failed.
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// The fraction of runs that failed, between 0 and 1.
+    pub fn error_rate(&self) -> f64 {
+        if self.latencies.is_empty() {
+            0.0
+        } else {
+            self.errors as f64 / self.latencies.len() as f64
+        }
+    }
+
+    /// The number of successful runs per second.
+    pub fn throughput(&self) -> f64 {
+        let successes = (self.runs() - self.errors) as f64;
+        successes / self.duration.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// The latency that `percentile` percent of runs finished within, such
+    /// as `99.0` for the 99th percentile.
+    ///
+    /// Returns `None` if the scenario never ran.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let fraction = percentile.clamp(0.0, 100.0) / 100.0;
+        let rank = (fraction * self.latencies.len() as f64).ceil() as usize;
+        self.latencies.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// The mean latency, or `None` if the scenario never ran.
+    pub fn mean(&self) -> Option<Duration> {
+        let total: Duration = self.latencies.iter().sum();
+        let runs = u32::try_from(self.latencies.len()).ok()?;
+        total.checked_div(runs)
+    }
+
+    /// The longest latency, or `None` if the scenario never ran.
+    pub fn max(&self) -> Option<Duration> {
+        self.latencies.last().copied()
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} runs, {} errors ({:.2}%), {:.1}/s",
+            self.runs(),
+            self.errors,
+            self.error_rate() * 100.0,
+            self.throughput()
+        )?;
+        if let (Some(p50), Some(p90), Some(p99), Some(max)) = (
+            self.percentile(50.0),
+            self.percentile(90.0),
+            self.percentile(99.0),
+            self.max(),
+        ) {
+            write!(f, "; p50 {p50:?}, p90 {p90:?}, p99 {p99:?}, max {max:?}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use reqwest::StatusCode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn it_runs_the_scenario_at_the_target_rate() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let runs = Arc::clone(&count);
+        let report = LoadTest::new(move || {
+            let run = runs.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let latency = if run % 10 == 9 { 100 } else { 10 };
+                tokio::time::sleep(Duration::from_millis(latency)).await;
+                if run % 4 == 3 {
+                    return Err(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE));
+                }
+                Ok(())
+            }
+        })
+        .with_rate(100.0)
+        .with_warm_up(Duration::from_secs(1))
+        .with_duration(Duration::from_secs(2))
+        .run()
+        .await;
+
+        assert_eq!(count.load(Ordering::SeqCst), 300);
+        assert_eq!(report.runs(), 200);
+        assert_eq!(report.errors(), 50);
+        assert_eq!(report.error_rate(), 0.25);
+        assert_eq!(report.throughput(), 75.0);
+        assert_eq!(report.percentile(50.0), Some(Duration::from_millis(10)));
+        assert_eq!(report.percentile(90.0), Some(Duration::from_millis(10)));
+        assert_eq!(report.percentile(99.0), Some(Duration::from_millis(100)));
+        assert_eq!(report.max(), Some(Duration::from_millis(100)));
+        assert_eq!(report.mean(), Some(Duration::from_millis(19)));
+    }
+
+    #[test]
+    fn it_handles_empty_reports() {
+        let report = LoadReport {
+            latencies: Vec::new(),
+            errors: 0,
+            duration: Duration::from_secs(1),
+        };
+        assert_eq!(report.error_rate(), 0.0);
+        assert_eq!(report.percentile(99.0), None);
+        assert_eq!(report.mean(), None);
+        assert_eq!(report.to_string(), "0 runs, 0 errors (0.00%), 0.0/s");
+    }
+}
@@ -0,0 +1,749 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Streaming the items of paginated endpoints.
+//!
+//! APIs that return long lists split them into pages, and each API has its
+//! own way of pointing to the next page: a cursor in the body, an offset
+//! and a limit in the query string, or a header. A [`Paginator`] describes
+//! one of those schemes, extracting the items on a page and the URL of the
+//! next page from each response, and [`paginate()`] turns it into a
+//! [`Pages`] stream of every item on every page. Pages are only fetched as
+//! the stream is consumed.
+//!
+//! [`OffsetPaginator`] handles APIs that page with `offset` and `limit`
+//! query parameters, [`CursorPaginator`] handles APIs that return a cursor
+//! for the next page in the body, [`LinkPaginator`] handles APIs that link
+//! to the next page in a [`Link` header](crate::headers::links), as
+//! GitHub's does, and [`from_fn()`] creates a paginator from a closure for
+//! everything else.
+//!
+//! [`paginate()`] works with any [`HttpGet`] service, but since
+//! [`HttpGet::get()`] only returns the body of a response, paginators used
+//! with it see no response headers. Paginators that need headers should be
+//! used with [`paginate_responses()`] and an [`HttpGetResponse`] service.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use futures_util::TryStreamExt;
+//! use hypertyper::export::Page;
+//! use hypertyper::pagination::{self, OffsetPaginator};
+//! use hypertyper::prelude::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     login: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Users {
+//!     users: Vec<User>,
+//!     next: Option<String>,
+//! }
+//!
+//! # async fn run<S: HttpGet + Sync>(service: S) -> HttpResult<()> {
+//! // GET /users?offset=0&limit=100, then /users?offset=100&limit=100, ...
+//! let paginator = OffsetPaginator::new(100, |response| response.json::<Vec<User>>());
+//! let users: Vec<User> = pagination::paginate(&service, "https://api.example.com/users", paginator)
+//!     .try_collect()
+//!     .await?;
+//!
+//! // Follow a link to the next page in the body.
+//! let paginator = pagination::from_fn(|_url, response| {
+//!     let page: Users = response.json()?;
+//!     Ok(Page::new(page.users, page.next))
+//! });
+//! let mut users = pagination::paginate(&service, "https://api.example.com/members", paginator);
+//! while let Some(user) = users.try_next().await? {
+//!     println!("{}", user.login);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::export::Page;
+use crate::headers;
+use crate::service::{HttpGet, HttpGetResponse, HttpResponse};
+use futures_util::{Stream, stream};
+use reqwest::header::HeaderMap;
+use reqwest::{IntoUrl, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use url::Url;
+
+/// Describes how an endpoint splits its items into pages.
+pub trait Paginator {
+    /// The type of the items on each page.
+    type Item;
+
+    /// Prepares the URL of the first page, such as by setting the page
+    /// size. By default, the URL is used as it is.
+    fn first(&mut self, url: Url) -> Url {
+        url
+    }
+
+    /// Extracts the items on the page fetched from `url`, and the URL of
+    /// the next page, if there is one.
+    ///
+    /// The URL of the next page may be relative to `url`.
+    fn page(&mut self, url: &Url, response: &HttpResponse) -> HttpResult<Page<Self::Item>>;
+}
+
+/// A paginator that pages with offset and limit query parameters.
+///
+/// Each page after the first is requested with an offset that skips the
+/// items on the pages before it. A page with fewer items than the limit is
+/// the last page.
+#[derive(Clone, Debug)]
+pub struct OffsetPaginator<F> {
+    limit: u64,
+    offset_param: String,
+    limit_param: String,
+    items: F,
+}
+
+impl<F> OffsetPaginator<F> {
+    /// Creates a paginator that requests `limit` items at a time, using
+    /// `items` to extract the items from each response.
+    ///
+    /// The offset and limit are sent as the `offset` and `limit` query
+    /// parameters.
+    pub fn new<T>(limit: u64, items: F) -> Self
+    where
+        F: FnMut(&HttpResponse) -> HttpResult<Vec<T>>,
+    {
+        Self {
+            limit,
+            offset_param: String::from("offset"),
+            limit_param: String::from("limit"),
+            items,
+        }
+    }
+
+    /// Sets the names of the query parameters for the offset and limit.
+    pub fn with_params(mut self, offset: impl Into<String>, limit: impl Into<String>) -> Self {
+        self.offset_param = offset.into();
+        self.limit_param = limit.into();
+        self
+    }
+
+    /// The number of items requested per page.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl<F, T> Paginator for OffsetPaginator<F>
+where
+    F: FnMut(&HttpResponse) -> HttpResult<Vec<T>>,
+{
+    type Item = T;
+
+    fn first(&mut self, url: Url) -> Url {
+        with_query_param(&url, &self.limit_param, &self.limit.to_string())
+    }
+
+    fn page(&mut self, url: &Url, response: &HttpResponse) -> HttpResult<Page<T>> {
+        let items = (self.items)(response)?;
+        if (items.len() as u64) < self.limit {
+            return Ok(Page::new(items, None));
+        }
+        let offset = url
+            .query_pairs()
+            .find(|(name, _)| *name == self.offset_param)
+            .and_then(|(_, value)| value.parse::<u64>().ok())
+            .unwrap_or(0);
+        let offset = offset + items.len() as u64;
+        let next = with_query_param(url, &self.offset_param, &offset.to_string());
+        Ok(Page::new(items, Some(next.into())))
+    }
+}
+
+/// A page of items followed by a cursor for the next page, in the shape
+/// that many APIs use:
+///
+/// ```json
+/// { "data": [{ "id": 1 }, { "id": 2 }], "next_cursor": "abc" }
+/// ```
+///
+/// The items may also be called `items`, and the cursor may also be called
+/// `cursor`. A missing, `null`, or empty cursor means that there are no
+/// more pages.
+///
+/// Responses in this shape can be paged with [`cursor_pages()`]. APIs with
+/// other field names can be paged by deriving `Deserialize` for a similar
+/// type and converting it to a [`Page`] in [`CursorPaginator::new()`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CursorPage<T> {
+    /// The items on the page.
+    #[serde(alias = "items")]
+    pub data: Vec<T>,
+
+    /// The cursor for the next page.
+    #[serde(default, alias = "cursor")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> From<CursorPage<T>> for Page<T> {
+    fn from(page: CursorPage<T>) -> Self {
+        let next = page.next_cursor.filter(|cursor| !cursor.is_empty());
+        Page::new(page.data, next)
+    }
+}
+
+/// The type of the function that a paginator created with
+/// [`cursor_pages()`] uses to read pages.
+pub type CursorPageFn<T> = fn(&HttpResponse) -> HttpResult<Page<T>>;
+
+/// A paginator that pages with a cursor returned in each response.
+///
+/// The cursor is sent back as a query parameter to request the next page.
+/// A page without a cursor is the last page.
+#[derive(Clone, Debug)]
+pub struct CursorPaginator<F> {
+    param: String,
+    page: F,
+}
+
+impl<F> CursorPaginator<F> {
+    /// Creates a paginator that uses `page` to extract the items and the
+    /// cursor for the next page from each response, and sends the cursor
+    /// in the query parameter `param`.
+    ///
+    /// `page` returns a [`Page`] whose `next` field is the cursor rather
+    /// than a URL.
+    pub fn new<T>(param: impl Into<String>, page: F) -> Self
+    where
+        F: FnMut(&HttpResponse) -> HttpResult<Page<T>>,
+    {
+        Self {
+            param: param.into(),
+            page,
+        }
+    }
+
+    /// The query parameter the cursor is sent in.
+    pub fn param(&self) -> &str {
+        &self.param
+    }
+}
+
+/// Creates a paginator for responses that are [`CursorPage`]s of `T`,
+/// sending the cursor in the query parameter `param`.
+pub fn cursor_pages<T: DeserializeOwned>(
+    param: impl Into<String>,
+) -> CursorPaginator<CursorPageFn<T>> {
+    CursorPaginator::new(param, |response| {
+        Ok(response.json::<CursorPage<T>>()?.into())
+    })
+}
+
+/// Creates a paginator that finds the items on each page and the cursor
+/// for the next page with the [JSONPath](crate::decode::path) expressions
+/// `items` and `cursor`, sending the cursor in the query parameter
+/// `param`.
+///
+/// Only available with the **json-path** feature.
+#[cfg(feature = "json-path")]
+pub fn cursor_paths<T: DeserializeOwned>(
+    param: impl Into<String>,
+    items: impl Into<String>,
+    cursor: impl Into<String>,
+) -> CursorPaginator<CursorPathFn<T>> {
+    use crate::decode::path;
+
+    let items = items.into();
+    let cursor = cursor.into();
+    CursorPaginator::new(
+        param,
+        Box::new(move |response: &HttpResponse| {
+            let items = path::extract(response.body(), &items)?;
+            let cursor = path::extract_opt::<Option<String>>(response.body(), &cursor)?;
+            let next = cursor.flatten().filter(|cursor| !cursor.is_empty());
+            Ok(Page::new(items, next))
+        }),
+    )
+}
+
+/// The type of the function that a paginator created with
+/// [`cursor_paths()`] uses to read pages.
+#[cfg(feature = "json-path")]
+pub type CursorPathFn<T> = Box<dyn FnMut(&HttpResponse) -> HttpResult<Page<T>> + Send>;
+
+impl<F, T> Paginator for CursorPaginator<F>
+where
+    F: FnMut(&HttpResponse) -> HttpResult<Page<T>>,
+{
+    type Item = T;
+
+    fn page(&mut self, url: &Url, response: &HttpResponse) -> HttpResult<Page<T>> {
+        let page = (self.page)(response)?;
+        let next = page
+            .next
+            .map(|cursor| with_query_param(url, &self.param, &cursor).into());
+        Ok(Page::new(page.items, next))
+    }
+}
+
+/// A paginator that follows the `rel="next"` link in each response's
+/// `Link` header.
+///
+/// The page without a next link is the last page. Since it needs response
+/// headers, this paginator must be used with [`paginate_responses()`].
+#[derive(Clone, Debug)]
+pub struct LinkPaginator<F> {
+    items: F,
+}
+
+impl<F> LinkPaginator<F> {
+    /// Creates a paginator that uses `items` to extract the items from
+    /// each response.
+    pub fn new<T>(items: F) -> Self
+    where
+        F: FnMut(&HttpResponse) -> HttpResult<Vec<T>>,
+    {
+        Self { items }
+    }
+}
+
+impl<F, T> Paginator for LinkPaginator<F>
+where
+    F: FnMut(&HttpResponse) -> HttpResult<Vec<T>>,
+{
+    type Item = T;
+
+    fn page(&mut self, _url: &Url, response: &HttpResponse) -> HttpResult<Page<T>> {
+        let items = (self.items)(response)?;
+        let next = headers::link(response.headers(), "next").map(|link| link.target().to_string());
+        Ok(Page::new(items, next))
+    }
+}
+
+/// Returns `url` with the query parameter `name` set to `value`, replacing
+/// any value it already had.
+pub(crate) fn with_query_param(url: &Url, name: &str, value: &str) -> Url {
+    let mut url = url.clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != name)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(name, value);
+    url
+}
+
+/// A paginator created by [`from_fn()`].
+#[derive(Clone)]
+pub struct FromFn<F> {
+    page: F,
+}
+
+impl<F, T> Paginator for FromFn<F>
+where
+    F: FnMut(&Url, &HttpResponse) -> HttpResult<Page<T>>,
+{
+    type Item = T;
+
+    fn page(&mut self, url: &Url, response: &HttpResponse) -> HttpResult<Page<T>> {
+        (self.page)(url, response)
+    }
+}
+
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromFn").finish_non_exhaustive()
+    }
+}
+
+/// Creates a paginator that calls `page` with the URL and response of each
+/// page to extract its items and the URL of the next page.
+pub fn from_fn<F, T>(page: F) -> FromFn<F>
+where
+    F: FnMut(&Url, &HttpResponse) -> HttpResult<Page<T>>,
+{
+    FromFn { page }
+}
+
+/// A stream of the items on every page of a paginated endpoint.
+///
+/// This is returned by [`paginate()`] and [`paginate_responses()`].
+pub struct Pages<'a, T> {
+    items: Pin<Box<dyn Stream<Item = HttpResult<T>> + Send + 'a>>,
+}
+
+impl<T> Stream for Pages<'_, T> {
+    type Item = HttpResult<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.items.as_mut().poll_next(cx)
+    }
+}
+
+impl<T> fmt::Debug for Pages<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pages").finish_non_exhaustive()
+    }
+}
+
+/// Streams the items on every page of the endpoint at `uri`, fetching
+/// pages with `service` as they are needed.
+///
+/// The paginator sees the body of each response, but not its headers.
+pub fn paginate<'a, S, U, P>(service: &'a S, uri: U, paginator: P) -> Pages<'a, P::Item>
+where
+    S: HttpGet + Sync,
+    U: IntoUrl,
+    P: Paginator + Send + 'a,
+    P::Item: Send + 'a,
+{
+    pages(uri, paginator, move |url| async move {
+        let body = service.get(url).await?;
+        Ok(HttpResponse::new(StatusCode::OK, body))
+    })
+}
+
+/// Streams the items on every page of the endpoint at `uri`, fetching
+/// pages with `service` as they are needed.
+///
+/// Unlike [`paginate()`], this gives the paginator each response's
+/// headers. An unsuccessful response ends the stream with an error.
+pub fn paginate_responses<'a, S, U, P>(service: &'a S, uri: U, paginator: P) -> Pages<'a, P::Item>
+where
+    S: HttpGetResponse + Sync,
+    U: IntoUrl,
+    P: Paginator + Send + 'a,
+    P::Item: Send + 'a,
+{
+    pages(uri, paginator, move |url| async move {
+        service
+            .get_response(url, &HeaderMap::new())
+            .await?
+            .error_for_status()
+    })
+}
+
+fn pages<'a, U, P, F, Fut>(uri: U, mut paginator: P, fetch: F) -> Pages<'a, P::Item>
+where
+    U: IntoUrl,
+    P: Paginator + Send + 'a,
+    P::Item: Send + 'a,
+    F: Fn(Url) -> Fut + Send + 'a,
+    Fut: Future<Output = HttpResult<HttpResponse>> + Send + 'a,
+{
+    let next = Url::parse(uri.as_str())
+        .map(|url| paginator.first(url))
+        .map_err(Into::into);
+    let state = State {
+        paginator,
+        fetch,
+        next: Some(next),
+        items: VecDeque::new(),
+    };
+    let items = stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        loop {
+            if let Some(item) = state.items.pop_front() {
+                return Some((Ok(item), Some(state)));
+            }
+            let result = match state.next.take()? {
+                Ok(url) => state.fetch_page(url).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                // There is no way to find the next page after an error.
+                return Some((Err(err), None));
+            }
+        }
+    });
+    Pages {
+        items: Box::pin(items),
+    }
+}
+
+struct State<P: Paginator, F> {
+    paginator: P,
+    fetch: F,
+    // The URL of the next page to fetch, or the error that prevented it
+    // from being found.
+    next: Option<HttpResult<Url>>,
+    items: VecDeque<P::Item>,
+}
+
+impl<P, F, Fut> State<P, F>
+where
+    P: Paginator,
+    F: Fn(Url) -> Fut,
+    Fut: Future<Output = HttpResult<HttpResponse>>,
+{
+    async fn fetch_page(&mut self, url: Url) -> HttpResult<()> {
+        let response = (self.fetch)(url.clone()).await?;
+        let page = self.paginator.page(&url, &response)?;
+        self.items.extend(page.items);
+        // A page that links to itself would be fetched forever.
+        self.next = page
+            .next
+            .map(|next| url.join(&next).map_err(Into::into))
+            .filter(|next| !matches!(next, Ok(next) if *next == url));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use futures_util::TryStreamExt;
+    use std::sync::Mutex;
+
+    /// Serves pages of numbers, with `total` numbers in all, and records
+    /// the URL of each request.
+    struct Numbers {
+        total: u64,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl Numbers {
+        fn new(total: u64) -> Self {
+            Self {
+                total,
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpGet for Numbers {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            let url = Url::parse(uri.as_str())?;
+            self.requests.lock().unwrap().push(url.to_string());
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .and_then(|(_, value)| value.parse::<u64>().ok())
+            };
+            let offset = param("offset").unwrap_or(0);
+            let limit = param("limit").unwrap_or(2);
+            let numbers: Vec<u64> = (offset..self.total).take(limit as usize).collect();
+            let next = (offset + limit < self.total).then(|| format!("?offset={}", offset + limit));
+            Ok(serde_json::json!({ "numbers": numbers, "next": next }).to_string())
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct NumbersPage {
+        numbers: Vec<u64>,
+        next: Option<String>,
+    }
+
+    fn numbers(response: &HttpResponse) -> HttpResult<Vec<u64>> {
+        Ok(response.json::<NumbersPage>()?.numbers)
+    }
+
+    #[tokio::test]
+    async fn it_pages_with_offsets_and_limits() -> HttpResult<()> {
+        let service = Numbers::new(7);
+        let paginator = OffsetPaginator::new(3, numbers);
+        let items: Vec<u64> = paginate(&service, "https://example.com/n?sort=asc", paginator)
+            .try_collect()
+            .await?;
+        assert_eq!(items, [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            service.requests(),
+            [
+                "https://example.com/n?sort=asc&limit=3",
+                "https://example.com/n?sort=asc&limit=3&offset=3",
+                "https://example.com/n?sort=asc&limit=3&offset=6",
+            ]
+        );
+        Ok(())
+    }
+
+    /// Serves pages of numbers with cursors, as a `CursorPage`.
+    struct Cursors;
+
+    impl HttpGet for Cursors {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            let url = Url::parse(uri.as_str())?;
+            let cursor = url
+                .query_pairs()
+                .find(|(key, _)| key == "after")
+                .map(|(_, value)| value.into_owned());
+            let body = match cursor.as_deref() {
+                None => r#"{"data": [1, 2], "next_cursor": "c2"}"#,
+                Some("c2") => r#"{"items": [3], "cursor": "c3"}"#,
+                _ => r#"{"data": [4], "next_cursor": ""}"#,
+            };
+            Ok(body.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_pages_with_cursors() -> HttpResult<()> {
+        let paginator = cursor_pages::<u64>("after");
+        assert_eq!(paginator.param(), "after");
+        let items: Vec<u64> = paginate(&Cursors, "https://example.com/n?q=x", paginator)
+            .try_collect()
+            .await?;
+        assert_eq!(items, [1, 2, 3, 4]);
+        Ok(())
+    }
+
+    #[cfg(feature = "json-path")]
+    #[tokio::test]
+    async fn it_pages_with_cursors_found_by_paths() -> HttpResult<()> {
+        struct Nested;
+
+        impl HttpGet for Nested {
+            async fn get<U>(&self, uri: U) -> HttpResult<String>
+            where
+                U: IntoUrl + Send,
+            {
+                let body = if uri.as_str().contains("page=") {
+                    r#"{"result": {"ids": [3]}, "meta": {"next": null}}"#
+                } else {
+                    r#"{"result": {"ids": [1, 2]}, "meta": {"next": "p2"}}"#
+                };
+                Ok(body.to_string())
+            }
+        }
+
+        let paginator = cursor_paths::<u64>("page", "$.result.ids", "$.meta.next");
+        let items: Vec<u64> = paginate(&Nested, "https://example.com/n", paginator)
+            .try_collect()
+            .await?;
+        assert_eq!(items, [1, 2, 3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_follows_next_links_from_a_closure() -> HttpResult<()> {
+        let service = Numbers::new(5);
+        let paginator = from_fn(|_url, response| {
+            let page: NumbersPage = response.json()?;
+            Ok(Page::new(page.numbers, page.next))
+        });
+        let items: Vec<u64> = paginate(&service, "https://example.com/n", paginator)
+            .try_collect()
+            .await?;
+        assert_eq!(items, [0, 1, 2, 3, 4]);
+        assert_eq!(service.requests().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_only_fetches_pages_as_they_are_needed() -> HttpResult<()> {
+        let service = Numbers::new(100);
+        let paginator = OffsetPaginator::new(10, numbers);
+        let mut items = paginate(&service, "https://example.com/n", paginator);
+        for _ in 0..11 {
+            items.try_next().await?;
+        }
+        assert_eq!(service.requests().len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_stops_at_pages_that_link_to_themselves() -> HttpResult<()> {
+        let service = Numbers::new(5);
+        let paginator = from_fn(|url: &Url, response: &HttpResponse| {
+            Ok(Page::new(numbers(response)?, Some(url.to_string())))
+        });
+        let items: Vec<u64> = paginate(&service, "https://example.com/n", paginator)
+            .try_collect()
+            .await?;
+        assert_eq!(items, [0, 1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_ends_with_an_error_for_invalid_urls() {
+        let service = Numbers::new(5);
+        let paginator = OffsetPaginator::new(3, numbers);
+        let result: HttpResult<Vec<u64>> = paginate(&service, "not a url", paginator)
+            .try_collect()
+            .await;
+        assert!(matches!(result, Err(HttpError::InvalidUrl(_))));
+    }
+
+    #[tokio::test]
+    async fn it_follows_link_headers() -> HttpResult<()> {
+        struct Linked;
+
+        impl HttpGetResponse for Linked {
+            async fn get_response<U>(
+                &self,
+                uri: U,
+                _headers: &HeaderMap,
+            ) -> HttpResult<HttpResponse>
+            where
+                U: IntoUrl + Send,
+            {
+                let response = match uri.as_str() {
+                    "https://example.com/n" => HttpResponse::new(StatusCode::OK, "[1, 2]")
+                        .with_header(
+                            reqwest::header::LINK,
+                            "</n?page=2>; rel=\"next\", </n?page=2>; rel=\"last\""
+                                .parse()
+                                .unwrap(),
+                        ),
+                    _ => HttpResponse::new(StatusCode::OK, "[3]"),
+                };
+                Ok(response)
+            }
+        }
+
+        let paginator = LinkPaginator::new(|response| response.json::<Vec<u64>>());
+        let items: Vec<u64> = paginate_responses(&Linked, "https://example.com/n", paginator)
+            .try_collect()
+            .await?;
+        assert_eq!(items, [1, 2, 3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_on_unsuccessful_responses() {
+        struct NotFound;
+
+        impl HttpGetResponse for NotFound {
+            async fn get_response<U>(
+                &self,
+                _uri: U,
+                _headers: &HeaderMap,
+            ) -> HttpResult<HttpResponse>
+            where
+                U: IntoUrl + Send,
+            {
+                Ok(HttpResponse::new(StatusCode::NOT_FOUND, ""))
+            }
+        }
+
+        let paginator = OffsetPaginator::new(3, numbers);
+        let result: HttpResult<Vec<u64>> =
+            paginate_responses(&NotFound, "https://example.com/n", paginator)
+                .try_collect()
+                .await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+    }
+}
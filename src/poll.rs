@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Polling endpoints until a condition is met.
+//!
+//! Many APIs run long operations in the background and expose an endpoint
+//! that reports their status, which clients request over and over until
+//! the operation is done. A [`Poller`] implements that loop: it GETs an
+//! endpoint at a fixed interval until a predicate on the response is
+//! satisfied, and fails with [`HttpError::Timeout`] if that does not happen
+//! before a deadline.
+//!
+//! When a response carries a `Retry-After` header, whether it is a
+//! successful response or an HTTP 429 or 503 error, the poller waits as
+//! long as the server asked instead of its usual interval. Other
+//! unsuccessful responses end polling with an error.
+//!
+//! The [`job`] module builds on the poller to start asynchronous jobs and
+//! wait for their results.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::poll::Poller;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetResponse;
+//! use serde::Deserialize;
+//! use std::time::Duration;
+//!
+//! #[derive(Deserialize)]
+//! struct Job {
+//!     state: String,
+//! }
+//!
+//! # async fn run<S: HttpGetResponse + Sync>(service: S) -> HttpResult<()> {
+//! let poller = Poller::new(service)
+//!     .with_interval(Duration::from_secs(2))
+//!     .with_timeout(Duration::from_secs(600));
+//! let job: Job = poller
+//!     .until_json("https://api.example.com/jobs/42", |job: &Job| job.state != "running")
+//!     .await?;
+//! println!("Job finished: {}", job.state);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod job;
+
+use crate::service::{HttpGetResponse, HttpResponse};
+use crate::{HttpError, HttpResult, headers};
+use reqwest::IntoUrl;
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Requests an endpoint repeatedly until its response satisfies a
+/// condition.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct Poller<S> {
+    service: S,
+    interval: Duration,
+    multiplier: u32,
+    max_interval: Duration,
+    timeout: Option<Duration>,
+    max_retry_after: Duration,
+}
+
+impl<S> Poller<S> {
+    /// Creates a poller that makes requests with `service` every second,
+    /// with no deadline.
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            interval: Duration::from_secs(1),
+            multiplier: 1,
+            max_interval: Duration::from_secs(1),
+            timeout: None,
+            max_retry_after: Duration::from_secs(300),
+        }
+    }
+
+    /// Sets how long to wait between requests.
+    ///
+    /// This also resets any [backoff](Self::with_backoff).
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self.multiplier = 1;
+        self.max_interval = interval;
+        self
+    }
+
+    /// Multiplies the wait between requests by `multiplier` after each
+    /// request, up to `max_interval`, starting from the
+    /// [interval](Self::with_interval).
+    ///
+    /// Operations that may finish in seconds or take hours are best polled
+    /// often at first and then less and less often.
+    pub fn with_backoff(mut self, multiplier: u32, max_interval: Duration) -> Self {
+        self.multiplier = multiplier.max(1);
+        self.max_interval = max_interval.max(self.interval);
+        self
+    }
+
+    /// Sets how long to keep polling before giving up with
+    /// [`HttpError::Timeout`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the longest the poller will wait when a server asks it to
+    /// retry after a given amount of time. The default is five minutes.
+    ///
+    /// Longer delays requested by the server are shortened to this maximum.
+    pub fn with_max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+
+    /// The service used to make requests.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+
+    /// How long the poller waits between requests.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// How long the poller keeps polling before giving up, if it ever does.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+impl<S: HttpGetResponse + Sync> Poller<S> {
+    /// GETs `uri` until `done` returns `true` for a successful response,
+    /// and returns that response.
+    pub async fn until<U, F>(&self, uri: U, mut done: F) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+        F: FnMut(&HttpResponse) -> bool,
+    {
+        let uri = uri.as_str().to_string();
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let mut interval = self.interval;
+        loop {
+            let response = self
+                .service
+                .get_response(uri.as_str(), &HeaderMap::new())
+                .await?;
+            let retry_after = headers::retry_after(response.headers());
+            let delay = match response.error_for_status() {
+                Ok(response) if done(&response) => return Ok(response),
+                Ok(_) => retry_after.unwrap_or(interval),
+                Err(err) => match err.retry_after() {
+                    Some(delay) => delay,
+                    None => return Err(err),
+                },
+            };
+            let delay = delay.min(self.max_retry_after);
+            if let Some(deadline) = deadline {
+                if Instant::now() + delay > deadline {
+                    return Err(HttpError::Timeout(self.timeout.unwrap_or_default()));
+                }
+            }
+            tokio::time::sleep(delay).await;
+            interval = interval
+                .saturating_mul(self.multiplier)
+                .min(self.max_interval);
+        }
+    }
+
+    /// GETs `uri` until `done` returns `true` for its body, deserialized as
+    /// a `T`, and returns the body.
+    ///
+    /// Polling stops with an error if a body cannot be deserialized.
+    pub async fn until_json<T, U, F>(&self, uri: U, mut done: F) -> HttpResult<T>
+    where
+        T: DeserializeOwned,
+        U: IntoUrl + Send,
+        F: FnMut(&T) -> bool,
+    {
+        let mut error = None;
+        let response = self
+            .until(uri, |response| match response.json::<T>() {
+                Ok(value) => done(&value),
+                Err(err) => {
+                    error = Some(err);
+                    true
+                }
+            })
+            .await?;
+        match error {
+            Some(err) => Err(err),
+            None => response.json(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use reqwest::header::{self, HeaderValue};
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Returns scripted responses in turn, recording when each request was
+    /// made.
+    struct Script {
+        responses: Mutex<VecDeque<HttpResponse>>,
+        requests: Mutex<Vec<Instant>>,
+    }
+
+    impl Script {
+        fn new(responses: Vec<HttpResponse>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn gaps(&self) -> Vec<Duration> {
+            let requests = self.requests.lock().unwrap();
+            requests.windows(2).map(|w| w[1] - w[0]).collect()
+        }
+    }
+
+    impl HttpGetResponse for Script {
+        async fn get_response<U>(&self, _uri: U, _headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            self.requests.lock().unwrap().push(Instant::now());
+            let response = self.responses.lock().unwrap().pop_front();
+            Ok(response.unwrap_or_else(|| HttpResponse::new(StatusCode::OK, "running")))
+        }
+    }
+
+    fn retry_after(response: HttpResponse, seconds: &'static str) -> HttpResponse {
+        response.with_header(header::RETRY_AFTER, HeaderValue::from_static(seconds))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_polls_until_done_honoring_retry_after() -> HttpResult<()> {
+        let poller = Poller::new(Script::new(vec![
+            HttpResponse::new(StatusCode::OK, "running"),
+            retry_after(HttpResponse::new(StatusCode::ACCEPTED, "running"), "5"),
+            retry_after(HttpResponse::new(StatusCode::TOO_MANY_REQUESTS, ""), "7"),
+            HttpResponse::new(StatusCode::OK, "done"),
+        ]))
+        .with_interval(Duration::from_secs(2));
+        let response = poller.until("/jobs/1", |r| r.body() == "done").await?;
+        assert_eq!(response.body(), "done");
+        assert_eq!(poller.service().gaps(), [2, 5, 7].map(Duration::from_secs));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_backs_off_between_requests() -> HttpResult<()> {
+        let mut responses = vec![HttpResponse::new(StatusCode::OK, "running"); 4];
+        responses.push(HttpResponse::new(StatusCode::OK, "done"));
+        let poller = Poller::new(Script::new(responses))
+            .with_interval(Duration::from_secs(1))
+            .with_backoff(3, Duration::from_secs(10));
+        poller.until("/jobs/1", |r| r.body() == "done").await?;
+        assert_eq!(
+            poller.service().gaps(),
+            [1, 3, 9, 10].map(Duration::from_secs)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_gives_up_at_the_deadline() {
+        let poller = Poller::new(Script::new(Vec::new()))
+            .with_interval(Duration::from_secs(3))
+            .with_timeout(Duration::from_secs(10));
+        let result = poller.until("/jobs/1", |r| r.body() == "done").await;
+        assert!(matches!(result, Err(HttpError::Timeout(timeout)) if timeout.as_secs() == 10));
+        assert_eq!(poller.service().requests.lock().unwrap().len(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_stops_at_errors_without_retry_after() {
+        let poller = Poller::new(Script::new(vec![HttpResponse::new(
+            StatusCode::NOT_FOUND,
+            "",
+        )]));
+        let result = poller.until("/jobs/1", |_| true).await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_polls_json_bodies() -> HttpResult<()> {
+        #[derive(serde::Deserialize)]
+        struct Job {
+            progress: u32,
+        }
+
+        let poller = Poller::new(Script::new(vec![
+            HttpResponse::new(StatusCode::OK, r#"{"progress": 50}"#),
+            HttpResponse::new(StatusCode::OK, r#"{"progress": 100}"#),
+        ]));
+        let job: Job = poller
+            .until_json("/jobs/1", |job: &Job| job.progress == 100)
+            .await?;
+        assert_eq!(job.progress, 100);
+
+        let result = poller
+            .until_json("/jobs/1", |job: &Job| job.progress == 100)
+            .await;
+        assert!(matches!(result, Err(HttpError::Decode { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,397 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Asynchronous jobs.
+//!
+//! APIs that take a long time to handle a request often start a job in
+//! the background instead, and answer with HTTP 202 Accepted and the URL of
+//! a status monitor in the `Operation-Location`, `Azure-AsyncOperation`, or
+//! `Location` header. The client polls that URL until the job is done and
+//! then fetches the result. [`Poller::run_job()`] handles the entire
+//! exchange, and [`Poller::follow_job()`] takes over after a job has been
+//! started some other way, with the poller's interval, backoff, and timeout.
+//!
+//! When only a `Location` is given, the job is done as soon as that URL
+//! stops responding with HTTP 202, and its body is the result.
+//!
+//! An `Operation-Location` monitor is polled until it stops responding
+//! with HTTP 202 and, if its body is a JSON object with a `status` field,
+//! until that status is `Succeeded`, `Failed`, or `Canceled`. Jobs that
+//! fail or are canceled produce an [`HttpError::JobFailed`]. The result of
+//! a successful job is taken from the first of:
+//!
+//! 1. The URL in the status body's `resourceLocation` field.
+//! 2. The `Location` header of the 202 response.
+//! 3. The body of the final status response.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::poll::Poller;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::{HttpGetResponse, HttpPostStream};
+//! use serde::{Deserialize, Serialize};
+//! use std::time::Duration;
+//!
+//! #[derive(Serialize)]
+//! struct Analyze<'a> {
+//!     url: &'a str,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Analysis {
+//!     pages: u32,
+//! }
+//!
+//! # async fn run<S: HttpGetResponse + HttpPostStream + Sync>(service: S) -> HttpResult<()> {
+//! let poller = Poller::new(service)
+//!     .with_interval(Duration::from_secs(1))
+//!     .with_backoff(2, Duration::from_secs(30))
+//!     .with_timeout(Duration::from_secs(15 * 60));
+//! let analysis: Analysis = poller
+//!     .run_job(
+//!         "https://api.example.com/documents:analyze",
+//!         &Analyze { url: "https://example.com/report.pdf" },
+//!     )
+//!     .await?;
+//! println!("{} pages", analysis.pages);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::Poller;
+use crate::service::{HttpGetResponse, HttpPostStream, HttpResponse};
+use crate::upload::UploadBody;
+use crate::{HttpError, HttpResult, timing};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{IntoUrl, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use url::Url;
+
+/// The headers that can hold the URL of a job's status monitor, in order
+/// of preference.
+const STATUS_HEADERS: [&str; 2] = ["operation-location", "azure-asyncoperation"];
+
+impl<S: HttpGetResponse + Sync> Poller<S> {
+    /// Waits for the job started by the request to `uri` that returned
+    /// `accepted`, and returns its result.
+    ///
+    /// If `accepted` is a successful response other than HTTP 202, the job
+    /// finished immediately and its body is returned. See the
+    /// [module documentation](self) for details.
+    pub async fn follow_job<T>(&self, uri: &str, accepted: HttpResponse) -> HttpResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let accepted = accepted.error_for_status()?;
+        if accepted.status() != StatusCode::ACCEPTED {
+            return accepted.json();
+        }
+        let base = Url::parse(uri)?;
+        let operation = STATUS_HEADERS
+            .iter()
+            .find_map(|name| header_url(&accepted, &base, name).transpose())
+            .transpose()?;
+        let location = header_url(&accepted, &base, header::LOCATION.as_str())?;
+        let monitor = operation
+            .as_ref()
+            .or(location.as_ref())
+            .ok_or(HttpError::MissingLocation)?;
+
+        // Only operation monitors report the state of the job in their
+        // bodies; a `Location` points at the result itself once it is ready.
+        if operation.is_none() {
+            return self
+                .until(monitor.as_str(), |response| {
+                    response.status() != StatusCode::ACCEPTED
+                })
+                .await?
+                .json();
+        }
+        let status = self.until(monitor.as_str(), is_done).await?;
+        let Some(state) = job_state(&status) else {
+            return status.json();
+        };
+        if is_failed(&state) {
+            return Err(HttpError::JobFailed {
+                status: state,
+                body: status.into_body(),
+            });
+        }
+        let resource = match resource_location(&status) {
+            Some(resource) => Some(base.join(&resource)?),
+            None => location,
+        };
+        match resource {
+            Some(resource) => self
+                .service()
+                .get_response(resource, &HeaderMap::new())
+                .await?
+                .error_for_status()?
+                .json(),
+            None => status.json(),
+        }
+    }
+}
+
+impl<S: HttpGetResponse + HttpPostStream + Sync> Poller<S> {
+    /// Starts a job by POSTing `data` as JSON to `uri`, waits for it to
+    /// finish, and returns its result.
+    ///
+    /// See the [module documentation](self) for details.
+    pub async fn run_job<U, D, T>(&self, uri: U, data: &D) -> HttpResult<T>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let uri = uri.as_str().to_string();
+        let body = UploadBody::from_bytes(timing::to_vec(data)?);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let accepted = self
+            .service()
+            .post_stream(uri.as_str(), &headers, body)
+            .await?;
+        self.follow_job(&uri, accepted).await
+    }
+}
+
+/// Resolves the URL in the header `name` of `response` against `base`.
+fn header_url(response: &HttpResponse, base: &Url, name: &str) -> HttpResult<Option<Url>> {
+    let Some(value) = response.headers().get(name) else {
+        return Ok(None);
+    };
+    Ok(Some(base.join(value.to_str()?)?))
+}
+
+fn is_done(response: &HttpResponse) -> bool {
+    if response.status() == StatusCode::ACCEPTED {
+        return false;
+    }
+    match job_state(response) {
+        Some(state) => matches!(
+            state.as_str(),
+            "succeeded" | "completed" | "failed" | "canceled" | "cancelled"
+        ),
+        None => true,
+    }
+}
+
+fn is_failed(state: &str) -> bool {
+    matches!(state, "failed" | "canceled" | "cancelled")
+}
+
+/// The lowercased `status` field of a status monitor's JSON body.
+fn job_state(response: &HttpResponse) -> Option<String> {
+    let body: Value = serde_json::from_str(response.body()).ok()?;
+    Some(body.get("status")?.as_str()?.to_ascii_lowercase())
+}
+
+fn resource_location(response: &HttpResponse) -> Option<String> {
+    let body: Value = serde_json::from_str(response.body()).ok()?;
+    Some(body.get("resourceLocation")?.as_str()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Report {
+        pages: u32,
+    }
+
+    /// Responds to each URL with its scripted responses in turn, repeating
+    /// the last one.
+    #[derive(Default)]
+    struct Api {
+        responses: Mutex<HashMap<String, VecDeque<HttpResponse>>>,
+        posted: Mutex<Vec<String>>,
+    }
+
+    impl Api {
+        fn on(self, uri: &str, responses: Vec<HttpResponse>) -> Self {
+            self.responses
+                .lock()
+                .unwrap()
+                .insert(uri.to_string(), responses.into());
+            self
+        }
+
+        fn respond(&self, uri: &str) -> HttpResponse {
+            let mut responses = self.responses.lock().unwrap();
+            let queue = responses.get_mut(uri).expect(uri);
+            if queue.len() > 1 {
+                queue.pop_front().unwrap()
+            } else {
+                queue[0].clone()
+            }
+        }
+    }
+
+    impl HttpGetResponse for Api {
+        async fn get_response<U>(&self, uri: U, _headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            Ok(self.respond(uri.as_str()))
+        }
+    }
+
+    impl HttpPostStream for Api {
+        async fn post_stream<U>(
+            &self,
+            uri: U,
+            _headers: &HeaderMap,
+            body: UploadBody,
+        ) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            let body = body.into_bytes().await?;
+            self.posted
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&body).into_owned());
+            Ok(self.respond(uri.as_str()))
+        }
+    }
+
+    fn response(status: StatusCode, body: &str) -> HttpResponse {
+        HttpResponse::new(status, body)
+    }
+
+    fn accepted(headers: &[(&'static str, &'static str)]) -> HttpResponse {
+        headers.iter().fold(
+            response(StatusCode::ACCEPTED, ""),
+            |response, (name, value)| {
+                response.with_header(
+                    header::HeaderName::from_static(name),
+                    HeaderValue::from_static(value),
+                )
+            },
+        )
+    }
+
+    fn poller(api: Api) -> Poller<Api> {
+        Poller::new(api).with_interval(Duration::from_millis(10))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_follows_the_location_header() -> HttpResult<()> {
+        let api = Api::default()
+            .on(
+                "https://api.test/reports",
+                vec![accepted(&[("location", "/reports/1")])],
+            )
+            .on(
+                "https://api.test/reports/1",
+                vec![
+                    response(StatusCode::ACCEPTED, ""),
+                    response(StatusCode::OK, r#"{"pages": 3, "status": "draft"}"#),
+                ],
+            );
+        let poller = poller(api);
+        let report: Report = poller
+            .run_job("https://api.test/reports", &[("url", "a.pdf")])
+            .await?;
+        assert_eq!(report, Report { pages: 3 });
+        assert_eq!(
+            *poller.service().posted.lock().unwrap(),
+            [r#"[["url","a.pdf"]]"#]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_monitors_the_operation_location() -> HttpResult<()> {
+        let api = Api::default()
+            .on(
+                "https://api.test/analyze",
+                vec![accepted(&[
+                    ("operation-location", "/operations/7"),
+                    ("location", "/results/7"),
+                ])],
+            )
+            .on(
+                "https://api.test/operations/7",
+                vec![
+                    response(StatusCode::OK, r#"{"status": "NotStarted"}"#),
+                    response(StatusCode::OK, r#"{"status": "Running"}"#),
+                    response(StatusCode::OK, r#"{"status": "Succeeded"}"#),
+                ],
+            )
+            .on(
+                "https://api.test/results/7",
+                vec![response(StatusCode::OK, r#"{"pages": 12}"#)],
+            );
+        let report: Report = poller(api).run_job("https://api.test/analyze", &()).await?;
+        assert_eq!(report, Report { pages: 12 });
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_prefers_the_resource_location() -> HttpResult<()> {
+        let api = Api::default()
+            .on(
+                "https://api.test/operations/8",
+                vec![response(
+                    StatusCode::OK,
+                    r#"{"status": "succeeded", "resourceLocation": "https://api.test/reports/8"}"#,
+                )],
+            )
+            .on(
+                "https://api.test/reports/8",
+                vec![response(StatusCode::OK, r#"{"pages": 1}"#)],
+            );
+        let accepted = accepted(&[("azure-asyncoperation", "/operations/8")]);
+        let report: Report = poller(api)
+            .follow_job("https://api.test/reports", accepted)
+            .await?;
+        assert_eq!(report, Report { pages: 1 });
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_reports_failed_jobs() {
+        let api = Api::default().on(
+            "https://api.test/operations/9",
+            vec![response(
+                StatusCode::OK,
+                r#"{"status": "Failed", "error": {"message": "bad PDF"}}"#,
+            )],
+        );
+        let accepted = accepted(&[("operation-location", "/operations/9")]);
+        let result = poller(api)
+            .follow_job::<Report>("https://api.test/reports", accepted)
+            .await;
+        assert!(matches!(
+            result,
+            Err(HttpError::JobFailed { status, body }) if status == "failed" && body.contains("bad PDF")
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_returns_immediate_results_and_requires_a_location() -> HttpResult<()> {
+        let poller = poller(Api::default());
+        let done = response(StatusCode::CREATED, r#"{"pages": 2}"#);
+        let report: Report = poller.follow_job("https://api.test/reports", done).await?;
+        assert_eq!(report, Report { pages: 2 });
+
+        let result = poller
+            .follow_job::<Report>("https://api.test/reports", accepted(&[]))
+            .await;
+        assert!(matches!(result, Err(HttpError::MissingLocation)));
+        Ok(())
+    }
+}
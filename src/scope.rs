@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Structured concurrency for groups of requests.
+//!
+//! Spawning requests as Tokio tasks lets them run concurrently, but a task
+//! that is not awaited keeps running after the operation that spawned it
+//! has given up. A [`RequestScope`] keeps track of every request spawned
+//! within it, so that a group of requests can be awaited or cancelled as a
+//! whole:
+//!
+//! - [`next()`](RequestScope::next) and [`join()`](RequestScope::join) wait
+//!   for the requests to complete.
+//! - [`cancel()`](RequestScope::cancel) cancels every request still in
+//!   flight, which then complete with [`HttpError::Cancelled`].
+//! - [`close()`](RequestScope::close) cancels the remaining requests and
+//!   waits until they have stopped.
+//!
+//! Dropping a scope cancels its requests, so none of them outlive the
+//! operation that owns the scope, even if that operation is itself
+//! cancelled or panics. Because `Drop` cannot wait, the requests stop the
+//! next time the runtime polls them rather than before the scope is
+//! dropped; call [`close()`](RequestScope::close) to be sure they have
+//! stopped.
+//!
+//! A [child scope](RequestScope::child) is cancelled along with its parent,
+//! which makes it easy to cancel nested operations together.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::scope::RequestScope;
+//!
+//! # async fn run<S>(service: S) -> HttpResult<()>
+//! # where
+//! #     S: HttpGet + Clone + Send + Sync + 'static,
+//! # {
+//! let mut scope = RequestScope::new();
+//! for id in 1..=3 {
+//!     let service = service.clone();
+//!     scope.spawn(async move {
+//!         service
+//!             .get(format!("https://api.example.com/users/{id}"))
+//!             .await
+//!     });
+//! }
+//!
+//! // Use whichever response arrives first, and cancel the others.
+//! let first = scope.next().await;
+//! scope.close().await;
+//! println!("{first:?}");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{HttpError, HttpResult};
+use std::fmt;
+use std::future::Future;
+use tokio::task::{JoinError, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+/// Tracks a group of requests so that they can be awaited or cancelled
+/// together.
+///
+/// See the [module documentation](self) for details.
+pub struct RequestScope<T> {
+    token: CancellationToken,
+    requests: JoinSet<(usize, HttpResult<T>)>,
+    spawned: usize,
+}
+
+impl<T: Send + 'static> RequestScope<T> {
+    /// Creates an empty scope.
+    pub fn new() -> Self {
+        Self::with_token(CancellationToken::new())
+    }
+
+    fn with_token(token: CancellationToken) -> Self {
+        Self {
+            token,
+            requests: JoinSet::new(),
+            spawned: 0,
+        }
+    }
+
+    /// Creates an empty scope that is cancelled whenever this one is.
+    ///
+    /// Cancelling the child scope does not cancel this one.
+    pub fn child<U: Send + 'static>(&self) -> RequestScope<U> {
+        RequestScope::with_token(self.token.child_token())
+    }
+
+    /// Spawns `request` as a task within the scope.
+    ///
+    /// If the scope has already been cancelled, the request completes with
+    /// [`HttpError::Cancelled`] without being started.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn spawn<F>(&mut self, request: F)
+    where
+        F: Future<Output = HttpResult<T>> + Send + 'static,
+    {
+        let index = self.spawned;
+        self.spawned += 1;
+        let token = self.token.clone();
+        self.requests.spawn(async move {
+            let result = tokio::select! {
+                biased;
+                _ = token.cancelled() => Err(HttpError::Cancelled),
+                result = request => result,
+            };
+            (index, result)
+        });
+    }
+
+    /// The number of requests in the scope that have not been returned by
+    /// [`next()`](Self::next) yet.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns true if every request spawned in the scope has been returned
+    /// by [`next()`](Self::next).
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Cancels every request in the scope, along with any child scopes.
+    ///
+    /// Requests that have not completed yet will complete with
+    /// [`HttpError::Cancelled`], as will any request spawned in the scope
+    /// from now on.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Returns true if the scope, or its parent scope, has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Waits for the next request in the scope to complete and returns its
+    /// result, or returns `None` if the scope is empty.
+    ///
+    /// Results are returned in the order the requests complete.
+    ///
+    /// # Panics
+    ///
+    /// Resumes the panic if the request panicked.
+    pub async fn next(&mut self) -> Option<HttpResult<T>> {
+        let (_, result) = self.join_next().await?;
+        Some(result)
+    }
+
+    /// Waits for every request in the scope to complete and returns their
+    /// results in the order the requests were spawned.
+    ///
+    /// Results already returned by [`next()`](Self::next) are not included.
+    ///
+    /// # Panics
+    ///
+    /// Resumes the panic if any request panicked.
+    pub async fn join(mut self) -> Vec<HttpResult<T>> {
+        let mut results = Vec::with_capacity(self.len());
+        while let Some(result) = self.join_next().await {
+            results.push(result);
+        }
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Cancels the requests in the scope and waits until all of them have
+    /// stopped.
+    pub async fn close(mut self) {
+        self.cancel();
+        self.requests.shutdown().await;
+    }
+
+    async fn join_next(&mut self) -> Option<(usize, HttpResult<T>)> {
+        match self.requests.join_next().await? {
+            Ok(result) => Some(result),
+            Err(err) => Some((usize::MAX, Err(join_error(err)))),
+        }
+    }
+}
+
+fn join_error(err: JoinError) -> HttpError {
+    match err.try_into_panic() {
+        Ok(panic) => std::panic::resume_unwind(panic),
+        // Tasks are only aborted when the scope is shut down.
+        Err(_) => HttpError::Cancelled,
+    }
+}
+
+impl<T: Send + 'static> Default for RequestScope<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for RequestScope<T> {
+    fn drop(&mut self) {
+        // Dropping the join set aborts the tasks, but cancelling the token
+        // also reaches child scopes that may outlive this one.
+        self.token.cancel();
+    }
+}
+
+impl<T> fmt::Debug for RequestScope<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestScope")
+            .field("len", &self.requests.len())
+            .field("cancelled", &self.token.is_cancelled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    /// Completes after `millis` milliseconds, counting how many requests
+    /// were dropped before completing.
+    async fn request(millis: u64, dropped: Arc<AtomicUsize>) -> HttpResult<u64> {
+        struct Guard(Arc<AtomicUsize>, bool);
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                if !self.1 {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let mut guard = Guard(dropped, false);
+        sleep(Duration::from_millis(millis)).await;
+        guard.1 = true;
+        Ok(millis)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_joins_results_in_spawn_order() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut scope = RequestScope::new();
+        for millis in [30, 10, 20] {
+            scope.spawn(request(millis, Arc::clone(&dropped)));
+        }
+        scope.spawn(async { Err(HttpError::RateLimited) });
+        assert_eq!(scope.len(), 4);
+
+        let results = scope.join().await;
+        assert_eq!(results.len(), 4);
+        assert!(matches!(results[0], Ok(30)));
+        assert!(matches!(results[1], Ok(10)));
+        assert!(matches!(results[2], Ok(20)));
+        assert!(matches!(results[3], Err(HttpError::RateLimited)));
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_cancels_remaining_requests() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut scope = RequestScope::new();
+        for millis in [30, 10, 20] {
+            scope.spawn(request(millis, Arc::clone(&dropped)));
+        }
+
+        assert!(matches!(scope.next().await, Some(Ok(10))));
+        scope.cancel();
+        assert!(scope.is_cancelled());
+        assert!(matches!(
+            scope.next().await,
+            Some(Err(HttpError::Cancelled))
+        ));
+        assert!(matches!(
+            scope.next().await,
+            Some(Err(HttpError::Cancelled))
+        ));
+        assert!(scope.next().await.is_none());
+        assert_eq!(dropped.load(Ordering::SeqCst), 2);
+
+        scope.spawn(request(5, Arc::clone(&dropped)));
+        assert!(matches!(
+            scope.next().await,
+            Some(Err(HttpError::Cancelled))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_stops_requests_when_closed_or_dropped() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut scope = RequestScope::new();
+        let mut child = scope.child();
+        scope.spawn(request(10, Arc::clone(&dropped)));
+        child.spawn(request(10, Arc::clone(&dropped)));
+        tokio::task::yield_now().await;
+
+        scope.close().await;
+        assert!(child.is_cancelled());
+        assert!(matches!(
+            child.next().await,
+            Some(Err(HttpError::Cancelled))
+        ));
+        assert_eq!(dropped.load(Ordering::SeqCst), 2);
+
+        let mut scope = RequestScope::new();
+        scope.spawn(request(10, Arc::clone(&dropped)));
+        tokio::task::yield_now().await;
+        drop(scope);
+        tokio::task::yield_now().await;
+        assert_eq!(dropped.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn it_cancels_children_without_cancelling_the_parent() {
+        let scope = RequestScope::<()>::new();
+        let child = scope.child::<()>();
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(!scope.is_cancelled());
+    }
+}
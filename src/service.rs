@@ -35,7 +35,7 @@
 //! ```
 //! use hypertyper::prelude::*;
 //! use hypertyper::auth::Auth;
-//! use reqwest::{header, StatusCode};
+//! use reqwest::StatusCode;
 //! use serde::{Serialize, de::DeserializeOwned};
 //! use std::fs;
 //!
@@ -61,17 +61,15 @@
 //! }
 //!
 //! impl HttpPost for RealService {
-//!     async fn post<U, D, R>(&self, uri: U, auth: &Auth, data: &D) -> HttpResult<R>
+//!     async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
 //!     where
 //!         U: IntoUrl + Send,
 //!         D: Serialize + Sync,
 //!         R: DeserializeOwned,
 //!     {
-//!         let json_object = self
-//!             .client
-//!             .post(uri)
-//!             .header(header::CONTENT_TYPE, "application/json")
-//!             .json(data)
+//!         let request = self.client.post(uri).json(data);
+//!         let json_object = auth
+//!             .apply(request)?
 //!             .send()
 //!             .await?
 //!             .json::<R>()
@@ -94,7 +92,7 @@
 //! }
 //!
 //! impl HttpPost for TestService {
-//!     async fn post<U, D, R>(&self, uri: U, auth: &Auth, data: &D) -> HttpResult<R>
+//!     async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
 //!     where
 //!         U: IntoUrl + Send,
 //!         D: Serialize + Sync,
@@ -137,12 +135,49 @@
 //! provide a uniform way of communicating over HTTP, whether code is
 //! under test or live in production.
 
+pub mod auth;
+pub mod cache;
+pub mod capabilities;
+pub mod circuit;
+pub mod concurrency;
+pub mod conditional;
+#[cfg(feature = "schema-drift")]
+pub mod drift;
+pub mod fallback;
+pub mod hedge;
+pub mod journal;
+pub mod layer;
+pub mod pacing;
+pub mod rate_limit;
+pub mod reauth;
+pub mod reload;
+pub mod retry;
+pub mod routing;
+pub mod shadow;
+pub mod single_flight;
+pub mod strict;
+pub mod tenant;
 #[cfg(feature = "test-utils")]
 pub mod testing;
+pub mod timeout;
 
+use crate::buffer::{BufferPool, PooledBuffer};
+use crate::graphql::{GraphQlRequest, GraphQlResponse};
 use crate::prelude::*;
+use crate::service::conditional::Conditional;
+use crate::sse::EventStream;
+use crate::upload::{Multipart, UploadBody};
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use reqwest::StatusCode;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use std::fmt;
+use std::ops::Range;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::SystemTime;
 
 /// An [HTTP service](HttpService) that only makes HTTP GET requests.
 pub trait HttpGet {
@@ -162,6 +197,59 @@ pub trait HttpGet {
     fn get<U>(&self, uri: U) -> impl Future<Output = HttpResult<String>> + Send
     where
         U: IntoUrl + Send;
+
+    /// Performs GET requests to each of `uris`, with at most `concurrency`
+    /// requests in flight at once, and returns their bodies in the same
+    /// order as `uris`.
+    ///
+    /// A failed request does not stop the others; its error takes its place
+    /// in the results.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use hypertyper::prelude::*;
+    /// # async fn run<S: HttpGet + Sync>(service: S) {
+    /// let uris = (1..=100).map(|id| format!("https://api.example.com/users/{id}"));
+    /// let bodies = service.get_many(uris, 8).await;
+    /// assert_eq!(bodies.len(), 100);
+    /// # }
+    /// ```
+    fn get_many<I>(
+        &self,
+        uris: I,
+        concurrency: usize,
+    ) -> impl Future<Output = Vec<HttpResult<String>>> + Send
+    where
+        I: IntoIterator + Send,
+        I::IntoIter: Send,
+        I::Item: IntoUrl + Send,
+        Self: Sync,
+    {
+        self.get_each(uris, concurrency).collect()
+    }
+
+    /// Performs GET requests to each of `uris`, with at most `concurrency`
+    /// requests in flight at once, and streams their bodies in the same
+    /// order as `uris`.
+    ///
+    /// Unlike [`get_many()`](Self::get_many), this makes each body available
+    /// as soon as it and every body before it have arrived.
+    fn get_each<I>(
+        &self,
+        uris: I,
+        concurrency: usize,
+    ) -> impl Stream<Item = HttpResult<String>> + Send
+    where
+        I: IntoIterator + Send,
+        I::IntoIter: Send,
+        I::Item: IntoUrl + Send,
+        Self: Sync,
+    {
+        futures_util::stream::iter(uris)
+            .map(|uri| self.get(uri))
+            .buffered(concurrency.max(1))
+    }
 }
 
 /// An [HTTP service](HttpService) that only makes HTTP POST requests.
@@ -174,18 +262,12 @@ pub trait HttpPost {
     ///
     /// # Examples
     ///
-    /// A simple implementation of this method with bearer authentication is
+    /// A simple implementation of this method is
     ///
     /// ```compile_fail
-    /// // use reqwest::header;
-    ///
-    /// let auth_header = format!("Bearer {}", auth.api_key());
-    /// let json_object = self
-    ///     .client
-    ///     .post(uri)
-    ///     .header(header::CONTENT_TYPE, "application/json")
-    ///     .header(header::AUTHORIZATION, auth_header)
-    ///     .json(data)
+    /// let request = self.client.post(uri).json(data);
+    /// let json_object = auth
+    ///     .apply(request)?
     ///     .send()
     ///     .await?
     ///     .json::<R>()
@@ -193,19 +275,931 @@ pub trait HttpPost {
     /// Ok(json_object)
     /// ```
     ///
-    /// (where `self.client` is a [Reqwest client] and `auth` is an [`Auth`] instance).
+    /// (where `self.client` is a [Reqwest client]). `auth` can be any
+    /// [`Authenticator`], such as an [`Auth`] bearer key.
     ///
     /// [Reqwest client]: https://docs.rs/reqwest/latest/reqwest/struct.Client.html
     fn post<U, D, R>(
         &self,
         uri: U,
-        auth: &Auth, // TODO: Auth should be optional, or specified in an auth() method (builder pattern?)
+        auth: &dyn Authenticator, // TODO: Auth should be optional, or specified in an auth() method (builder pattern?)
         data: &D,
     ) -> impl Future<Output = HttpResult<R>> + Send
     where
         U: IntoUrl + Send,
         D: Serialize + Sync,
         R: DeserializeOwned;
+
+    /// Sends a GraphQL `request` to `uri` and returns the `data` in the
+    /// response.
+    ///
+    /// See the [`graphql`](crate::graphql) module for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::GraphQl`] if the response contains any errors.
+    fn post_graphql<U, T>(
+        &self,
+        uri: U,
+        auth: &dyn Authenticator,
+        request: &GraphQlRequest,
+    ) -> impl Future<Output = HttpResult<T>> + Send
+    where
+        U: IntoUrl + Send,
+        T: DeserializeOwned + Send,
+        Self: Sync,
+    {
+        async move {
+            self.post::<U, GraphQlRequest, GraphQlResponse<T>>(uri, auth, request)
+                .await?
+                .into_result()
+        }
+    }
+}
+
+/// An HTTP service that can send URL-encoded forms in POST requests.
+///
+/// Unlike [`HttpPost`], which deserializes successful responses into a
+/// type of the caller's choosing, `HttpPostForm` returns the raw
+/// [`HttpResponse`] regardless of its status, because protocols that use
+/// forms, such as OAuth 2.0, often return meaningful bodies with error
+/// statuses.
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+pub trait HttpPostForm {
+    /// Sends a POST request to `uri` with `form` encoded as an
+    /// `application/x-www-form-urlencoded` body.
+    fn post_form<U, D>(
+        &self,
+        uri: U,
+        form: &D,
+    ) -> impl Future<Output = HttpResult<HttpResponse>> + Send
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync;
+}
+
+impl HttpPostForm for HttpClient {
+    async fn post_form<U, D>(&self, uri: U, form: &D) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+    {
+        let response = self.post(uri).form(form).send().await?;
+        HttpResponse::from_response(response).await
+    }
+}
+
+/// An HTTP service that can send streamed request bodies in POST requests.
+///
+/// [`HttpPost`] serializes the entire request body in memory, which is a
+/// problem for large uploads. `HttpPostStream` sends an [`UploadBody`] as
+/// it is read instead. Like [`HttpPostForm`], it returns the raw
+/// [`HttpResponse`] regardless of its status.
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly. See [`upload`](crate::upload)
+/// for an example.
+pub trait HttpPostStream {
+    /// Sends a POST request to `uri` with the given additional `headers`,
+    /// streaming `body` as the request body.
+    fn post_stream<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: UploadBody,
+    ) -> impl Future<Output = HttpResult<HttpResponse>> + Send
+    where
+        U: IntoUrl + Send;
+
+    /// Sends a POST request to `uri` with the given additional `headers`,
+    /// streaming `form` as a `multipart/form-data` body.
+    ///
+    /// See [`upload::multipart`](crate::upload::multipart) for details.
+    fn post_multipart<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        form: Multipart,
+    ) -> impl Future<Output = HttpResult<HttpResponse>> + Send
+    where
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        let content_type = HeaderValue::from_str(&form.content_type());
+        let mut headers = headers.clone();
+        async move {
+            headers.insert(header::CONTENT_TYPE, content_type?);
+            self.post_stream(uri, &headers, form.into_body()).await
+        }
+    }
+
+    /// Sends a POST request to `uri` with the given additional `headers`
+    /// and `data` serialized as an XML body, and deserializes the XML
+    /// response.
+    ///
+    /// See [`decode::xml`](crate::decode::xml) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::XmlSerialization`] if `data` cannot be
+    /// serialized, an HTTP error if the response is unsuccessful, and
+    /// [`HttpError::XmlDecode`] if the response body does not match `R`.
+    #[cfg(feature = "xml")]
+    fn post_xml<U, D, R>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        data: &D,
+    ) -> impl Future<Output = HttpResult<R>> + Send
+    where
+        U: IntoUrl + Send,
+        D: Serialize + ?Sized,
+        R: DeserializeOwned,
+        Self: Sync,
+    {
+        let body = crate::decode::xml::to_string(data);
+        let mut headers = headers.clone();
+        async move {
+            let body = UploadBody::from_bytes(body?);
+            let xml = HeaderValue::from_static(crate::decode::xml::CONTENT_TYPE);
+            headers.insert(header::CONTENT_TYPE, xml.clone());
+            headers.insert(header::ACCEPT, xml);
+            let response = self.post_stream(uri, &headers, body).await?;
+            crate::decode::xml::from_str(response.error_for_status()?.body())
+        }
+    }
+}
+
+impl HttpPostStream for HttpClient {
+    async fn post_stream<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: UploadBody,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let mut request = self.post(uri).headers(headers.clone());
+        if let Some(len) = body.content_length() {
+            request = request.header(header::CONTENT_LENGTH, len);
+        }
+        let body = reqwest::Body::wrap_stream(body.into_stream());
+        let response = request.body(body).send().await?;
+        HttpResponse::from_response(response).await
+    }
+}
+
+/// An HTTP service that can make GET requests with extra headers and
+/// return the entire response.
+///
+/// Unlike [`HttpGet`], which only returns the body of a successful
+/// response, `HttpGetResponse` returns the status, headers, and body of the
+/// response regardless of its status. Decorators that need to work at the
+/// level of headers, such as the [caching service](cache), are built on
+/// this trait.
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+pub trait HttpGetResponse {
+    /// Sends a GET request to `uri` with the given additional `headers`.
+    fn get_response<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+    ) -> impl Future<Output = HttpResult<HttpResponse>> + Send
+    where
+        U: IntoUrl + Send;
+
+    /// Sends a GET request to `uri` that only returns the body if the
+    /// resource's ETag no longer matches `etag`.
+    ///
+    /// `etag` is the value of the `ETag` header of an earlier response,
+    /// including its quotes. See [`conditional`] for details.
+    fn get_if_none_match<U>(
+        &self,
+        uri: U,
+        etag: &str,
+    ) -> impl Future<Output = HttpResult<Conditional>> + Send
+    where
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        let etag = HeaderValue::from_str(etag);
+        async move {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, etag?);
+            Conditional::from_response(self.get_response(uri, &headers).await?)
+        }
+    }
+
+    /// Sends a GET request to `uri` that only returns the body if the
+    /// resource has been modified since `since`.
+    ///
+    /// See [`conditional`] for details.
+    fn get_if_modified_since<U>(
+        &self,
+        uri: U,
+        since: SystemTime,
+    ) -> impl Future<Output = HttpResult<Conditional>> + Send
+    where
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        let since = HeaderValue::from_str(&httpdate::fmt_http_date(since));
+        async move {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_MODIFIED_SINCE, since?);
+            Conditional::from_response(self.get_response(uri, &headers).await?)
+        }
+    }
+
+    /// Sends a GET request to `uri` that accepts an XML response, and
+    /// deserializes the response body.
+    ///
+    /// See [`decode::xml`](crate::decode::xml) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an HTTP error if the response is unsuccessful, and
+    /// [`HttpError::XmlDecode`] if the response body does not match `T`.
+    #[cfg(feature = "xml")]
+    fn get_xml<U, T>(&self, uri: U) -> impl Future<Output = HttpResult<T>> + Send
+    where
+        U: IntoUrl + Send,
+        T: DeserializeOwned,
+        Self: Sync,
+    {
+        async move {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::ACCEPT,
+                HeaderValue::from_static(crate::decode::xml::CONTENT_TYPE),
+            );
+            let response = self.get_response(uri, &headers).await?;
+            crate::decode::xml::from_str(response.error_for_status()?.body())
+        }
+    }
+}
+
+impl HttpGetResponse for HttpClient {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let response = self.get(uri).headers(headers.clone()).send().await?;
+        HttpResponse::from_response(response).await
+    }
+}
+
+/// A stream of chunks of a response body.
+pub struct BodyStream {
+    chunks: Pin<Box<dyn Stream<Item = HttpResult<Bytes>> + Send>>,
+    content_length: Option<u64>,
+}
+
+impl BodyStream {
+    /// Creates a body from a stream of chunks.
+    pub fn new<S>(chunks: S) -> Self
+    where
+        S: Stream<Item = HttpResult<Bytes>> + Send + 'static,
+    {
+        Self {
+            chunks: Box::pin(chunks),
+            content_length: None,
+        }
+    }
+
+    /// Sets the length of the body, if it is known in advance.
+    pub fn with_content_length(mut self, content_length: Option<u64>) -> Self {
+        self.content_length = content_length;
+        self
+    }
+
+    /// The length of the body, if it was known in advance, such as from the
+    /// response's `Content-Length` header.
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    /// Reads the entire body into a buffer taken from `pool`.
+    ///
+    /// Room for the body's [content length](Self::content_length) is
+    /// reserved up front, but no more than the pool's
+    /// [maximum capacity](BufferPool::max_capacity), since the length is
+    /// only what the server claims; beyond that, the buffer grows as the
+    /// body arrives.
+    ///
+    /// See [`buffer`](crate::buffer) for details.
+    pub async fn read_pooled(mut self, pool: &BufferPool) -> HttpResult<PooledBuffer> {
+        let mut buffer = pool.get();
+        if let Some(len) = self.content_length {
+            let len = usize::try_from(len).unwrap_or(usize::MAX);
+            buffer.reserve(len.min(pool.max_capacity()));
+        }
+        while let Some(chunk) = self.next().await {
+            buffer.extend_from_slice(&chunk?);
+        }
+        Ok(buffer)
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = HttpResult<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.chunks.as_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyStream")
+            .field("content_length", &self.content_length)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An HTTP service that can make GET requests and return the body as a
+/// stream of chunks.
+///
+/// [`HttpGet::get()`] buffers the entire body in memory, which is a problem
+/// for large responses such as exports. `HttpGetStream` lets callers
+/// process the body as it arrives instead.
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures_util::StreamExt;
+/// use hypertyper::prelude::*;
+/// use hypertyper::service::HttpGetStream;
+///
+/// # async fn export<S: HttpGetStream>(service: S) -> HttpResult<()> {
+/// let mut body = service.get_stream("https://api.example.com/export").await?;
+/// let mut total = 0;
+/// while let Some(chunk) = body.next().await {
+///     total += chunk?.len();
+/// }
+/// println!("Exported {total} bytes");
+/// # Ok(())
+/// # }
+/// ```
+pub trait HttpGetStream {
+    /// Sends a GET request to `uri` and returns a stream of chunks of the
+    /// response body.
+    ///
+    /// Like [`HttpGet::get()`], this fails if the response has an
+    /// unsuccessful status, before any of the body is read.
+    fn get_stream<U>(&self, uri: U) -> impl Future<Output = HttpResult<BodyStream>> + Send
+    where
+        U: IntoUrl + Send;
+
+    /// Sends a GET request to `uri` and deserializes each line of the
+    /// newline-delimited JSON response body as a `T`, as the body arrives.
+    ///
+    /// See [`decode::ndjson`](crate::decode::ndjson) for details.
+    fn get_ndjson<T, U>(
+        &self,
+        uri: U,
+    ) -> impl Future<Output = HttpResult<crate::decode::ndjson::NdjsonValues<T>>> + Send
+    where
+        T: DeserializeOwned + Send + 'static,
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        async move {
+            let body = self.get_stream(uri).await?;
+            Ok(crate::decode::ndjson::values(body))
+        }
+    }
+
+    /// Sends a GET request to `uri` and deserializes each row of the CSV
+    /// response body as a `T`, as the body arrives.
+    ///
+    /// See [`decode::csv`](crate::decode::csv) for details.
+    #[cfg(feature = "csv")]
+    fn get_csv<T, U>(
+        &self,
+        uri: U,
+        options: &crate::decode::csv::CsvOptions,
+    ) -> impl Future<Output = HttpResult<crate::decode::csv::CsvRows<T>>> + Send
+    where
+        T: DeserializeOwned + Send + 'static,
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        let options = options.clone();
+        async move {
+            let body = self.get_stream(uri).await?;
+            Ok(crate::decode::csv::rows(body, &options))
+        }
+    }
+
+    /// Sends a GET request to `uri` and parses the response body as an HTML
+    /// document, whose relative links are resolved against `uri`.
+    ///
+    /// Bytes that are not valid UTF-8 are replaced with U+FFFD.
+    ///
+    /// See [`decode::html`](crate::decode::html) for details.
+    #[cfg(feature = "html")]
+    fn get_html<U>(
+        &self,
+        uri: U,
+    ) -> impl Future<Output = HttpResult<crate::decode::html::Document>> + Send
+    where
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        async move {
+            let url = url::Url::parse(uri.as_str()).ok();
+            let mut body = self.get_stream(uri).await?;
+            let mut bytes = Vec::new();
+            while let Some(chunk) = body.next().await {
+                bytes.extend_from_slice(&chunk?);
+            }
+            let document = crate::decode::html::Document::parse(&String::from_utf8_lossy(&bytes));
+            Ok(match url {
+                Some(url) => document.with_url(url),
+                None => document,
+            })
+        }
+    }
+}
+
+impl HttpGetStream for HttpClient {
+    async fn get_stream<U>(&self, uri: U) -> HttpResult<BodyStream>
+    where
+        U: IntoUrl + Send,
+    {
+        let response = self.get(uri).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(HttpError::from_status(status, response.headers()));
+        }
+        let content_length = response.content_length();
+        let chunks = response.bytes_stream().map(|chunk| Ok(chunk?));
+        Ok(BodyStream::new(chunks).with_content_length(content_length))
+    }
+}
+
+/// An HTTP service that can fetch parts of a resource with range requests.
+///
+/// Servers that support range requests let clients download a large file
+/// in several pieces at once, or resume an interrupted download. The
+/// [`Downloader`](crate::download::Downloader) uses this trait for
+/// [segmented downloads](crate::download::Downloader::download_segmented).
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+pub trait HttpGetRange: HttpGetStream {
+    /// Returns the length of the resource at `uri` if the server supports
+    /// byte range requests for it, or `None` if it does not.
+    fn range_length<U>(&self, uri: U) -> impl Future<Output = HttpResult<Option<u64>>> + Send
+    where
+        U: IntoUrl + Send;
+
+    /// Sends a GET request for the bytes of `uri` in `range` and returns a
+    /// stream of chunks of them.
+    ///
+    /// Fails with [`HttpError::RangeNotSupported`] if the server responds
+    /// with the entire resource instead.
+    fn get_range<U>(
+        &self,
+        uri: U,
+        range: Range<u64>,
+    ) -> impl Future<Output = HttpResult<BodyStream>> + Send
+    where
+        U: IntoUrl + Send;
+}
+
+impl HttpGetRange for HttpClient {
+    async fn range_length<U>(&self, uri: U) -> HttpResult<Option<u64>>
+    where
+        U: IntoUrl + Send,
+    {
+        let response = self.head(uri).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(HttpError::from_status(status, response.headers()));
+        }
+        let headers = response.headers();
+        let accepts_bytes = headers
+            .get(header::ACCEPT_RANGES)
+            .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"bytes"));
+        // reqwest reports an empty body for HEAD responses, so read the
+        // header directly.
+        let length = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        Ok(length.filter(|_| accepts_bytes))
+    }
+
+    async fn get_range<U>(&self, uri: U, range: Range<u64>) -> HttpResult<BodyStream>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        let value = format!("bytes={}-{}", range.start, range.end.saturating_sub(1));
+        let response = self
+            .get(uri.as_str())
+            .header(header::RANGE, value)
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() && status != StatusCode::PARTIAL_CONTENT {
+            return Err(HttpError::RangeNotSupported(uri));
+        } else if !status.is_success() {
+            return Err(HttpError::from_status(status, response.headers()));
+        }
+        let content_length = response.content_length();
+        let chunks = response.bytes_stream().map(|chunk| Ok(chunk?));
+        Ok(BodyStream::new(chunks).with_content_length(content_length))
+    }
+}
+
+/// An HTTP service that can send and receive binary bodies.
+///
+/// [`HttpGetResponse`] and the other services that return an
+/// [`HttpResponse`] read the body as text, which mangles binary formats.
+/// `HttpBinary` returns a [`BinaryResponse`] with the raw bytes of the body
+/// instead, and provides helpers for binary serialization formats such as
+/// MessagePack and CBOR.
+///
+/// Like [`HttpGet`], this fails if the response has an unsuccessful status.
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+pub trait HttpBinary {
+    /// Sends a GET request to `uri` with the given additional `headers`.
+    fn get_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+    ) -> impl Future<Output = HttpResult<BinaryResponse>> + Send
+    where
+        U: IntoUrl + Send;
+
+    /// Sends a POST request to `uri` with the given additional `headers`
+    /// and `body`.
+    fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> impl Future<Output = HttpResult<BinaryResponse>> + Send
+    where
+        U: IntoUrl + Send;
+
+    /// Sends a GET request to `uri` that accepts a MessagePack response,
+    /// and deserializes the response body.
+    ///
+    /// See [`decode::msgpack`](crate::decode::msgpack) for details.
+    #[cfg(feature = "msgpack")]
+    fn get_msgpack<U, T>(&self, uri: U) -> impl Future<Output = HttpResult<T>> + Send
+    where
+        U: IntoUrl + Send,
+        T: DeserializeOwned,
+        Self: Sync,
+    {
+        use crate::decode::msgpack;
+
+        async move {
+            let headers = binary_headers(msgpack::CONTENT_TYPE, false);
+            msgpack::from_slice(self.get_binary(uri, &headers).await?.body())
+        }
+    }
+
+    /// Sends a POST request to `uri` with the given additional `headers`
+    /// and `data` serialized as a MessagePack body, and deserializes the
+    /// MessagePack response.
+    ///
+    /// See [`decode::msgpack`](crate::decode::msgpack) for details.
+    #[cfg(feature = "msgpack")]
+    fn post_msgpack<U, D, R>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        data: &D,
+    ) -> impl Future<Output = HttpResult<R>> + Send
+    where
+        U: IntoUrl + Send,
+        D: Serialize + ?Sized,
+        R: DeserializeOwned,
+        Self: Sync,
+    {
+        use crate::decode::msgpack;
+
+        let body = msgpack::to_vec(data);
+        let mut headers = headers.clone();
+        headers.extend(binary_headers(msgpack::CONTENT_TYPE, true));
+        async move {
+            let response = self.post_binary(uri, &headers, body?.into()).await?;
+            msgpack::from_slice(response.body())
+        }
+    }
+
+    /// Sends a GET request to `uri` that accepts a CBOR response, and
+    /// deserializes the response body.
+    ///
+    /// See [`decode::cbor`](crate::decode::cbor) for details.
+    #[cfg(feature = "cbor")]
+    fn get_cbor<U, T>(&self, uri: U) -> impl Future<Output = HttpResult<T>> + Send
+    where
+        U: IntoUrl + Send,
+        T: DeserializeOwned,
+        Self: Sync,
+    {
+        use crate::decode::cbor;
+
+        async move {
+            let headers = binary_headers(cbor::CONTENT_TYPE, false);
+            cbor::from_slice(self.get_binary(uri, &headers).await?.body())
+        }
+    }
+
+    /// Sends a POST request to `uri` with the given additional `headers`
+    /// and `data` serialized as a CBOR body, and deserializes the CBOR
+    /// response.
+    ///
+    /// See [`decode::cbor`](crate::decode::cbor) for details.
+    #[cfg(feature = "cbor")]
+    fn post_cbor<U, D, R>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        data: &D,
+    ) -> impl Future<Output = HttpResult<R>> + Send
+    where
+        U: IntoUrl + Send,
+        D: Serialize + ?Sized,
+        R: DeserializeOwned,
+        Self: Sync,
+    {
+        use crate::decode::cbor;
+
+        let body = cbor::to_vec(data);
+        let mut headers = headers.clone();
+        headers.extend(binary_headers(cbor::CONTENT_TYPE, true));
+        async move {
+            let response = self.post_binary(uri, &headers, body?.into()).await?;
+            cbor::from_slice(response.body())
+        }
+    }
+}
+
+/// Headers that accept, and optionally send, bodies of `content_type`.
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+fn binary_headers(content_type: &'static str, with_body: bool) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let value = HeaderValue::from_static(content_type);
+    if with_body {
+        headers.insert(header::CONTENT_TYPE, value.clone());
+    }
+    headers.insert(header::ACCEPT, value);
+    headers
+}
+
+impl HttpBinary for HttpClient {
+    async fn get_binary<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let response = self.get(uri).headers(headers.clone()).send().await?;
+        BinaryResponse::from_response(response).await
+    }
+
+    async fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let request = self.post(uri).headers(headers.clone()).body(body);
+        BinaryResponse::from_response(request.send().await?).await
+    }
+}
+
+/// An HTTP service that can make unary [gRPC-Web calls](crate::grpc_web).
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+#[cfg(feature = "grpc-web")]
+pub trait HttpGrpcWeb {
+    /// Calls the gRPC method at `uri` with the encoded `message`, sending
+    /// the given additional `headers`, and returns the encoded response
+    /// message.
+    ///
+    /// `uri` is the URI of the gateway followed by the name of the service
+    /// and method, as in `https://example.com/helloworld.Greeter/SayHello`.
+    fn grpc_unary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        message: Bytes,
+    ) -> impl Future<Output = HttpResult<Bytes>> + Send
+    where
+        U: IntoUrl + Send;
+}
+
+#[cfg(feature = "grpc-web")]
+impl HttpGrpcWeb for HttpClient {
+    async fn grpc_unary<U>(&self, uri: U, headers: &HeaderMap, message: Bytes) -> HttpResult<Bytes>
+    where
+        U: IntoUrl + Send,
+    {
+        use crate::grpc_web;
+
+        let response = self
+            .post(uri)
+            .headers(headers.clone())
+            .header(header::CONTENT_TYPE, grpc_web::CONTENT_TYPE)
+            .header(header::ACCEPT, grpc_web::CONTENT_TYPE)
+            .header("x-grpc-web", "1")
+            .body(grpc_web::frame(&message))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(HttpError::from_status(status, response.headers()));
+        }
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        grpc_web::decode_unary(&headers, &body)
+    }
+}
+
+/// An HTTP service that can open [WebSocket connections](crate::ws).
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+#[cfg(feature = "ws")]
+pub trait HttpWebSocket {
+    /// Opens a WebSocket connection to `uri`, sending the given additional
+    /// `headers` with the upgrade request.
+    ///
+    /// `uri` may use the `ws` and `wss` schemes, or the equivalent `http`
+    /// and `https` schemes. Fails if the server does not agree to the
+    /// upgrade.
+    fn connect_ws<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+    ) -> impl Future<Output = HttpResult<crate::ws::WebSocket>> + Send
+    where
+        U: IntoUrl + Send;
+}
+
+#[cfg(feature = "ws")]
+impl HttpWebSocket for HttpClient {
+    async fn connect_ws<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<crate::ws::WebSocket>
+    where
+        U: IntoUrl + Send,
+    {
+        use tokio_tungstenite::tungstenite::error::ProtocolError;
+        use tokio_tungstenite::tungstenite::handshake::client::generate_key;
+        use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+
+        let mut url = url::Url::parse(uri.as_str())?;
+        let scheme = match url.scheme() {
+            "ws" => "http",
+            "wss" => "https",
+            scheme => scheme,
+        }
+        .to_string();
+        // Switching between these special schemes cannot fail.
+        let _ = url.set_scheme(&scheme);
+
+        let key = generate_key();
+        let response = self
+            .get(url)
+            .version(reqwest::Version::HTTP_11)
+            .headers(headers.clone())
+            .header(header::CONNECTION, "Upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, &key)
+            .send()
+            .await?;
+        let status = response.status();
+        if status != StatusCode::SWITCHING_PROTOCOLS {
+            return Err(HttpError::from_status(status, response.headers()));
+        }
+        let accept = derive_accept_key(key.as_bytes());
+        if response
+            .headers()
+            .get(header::SEC_WEBSOCKET_ACCEPT)
+            .is_none_or(|value| value != accept.as_str())
+        {
+            let err = tokio_tungstenite::tungstenite::Error::from(
+                ProtocolError::SecWebSocketAcceptKeyMismatch,
+            );
+            return Err(err.into());
+        }
+        let upgraded = response.upgrade().await?;
+        Ok(crate::ws::WebSocket::from_upgraded(upgraded).await)
+    }
+}
+
+/// An HTTP service that can subscribe to streams of
+/// [server-sent events](crate::sse).
+///
+/// Only [`open_sse()`](Self::open_sse) has to be implemented;
+/// [`get_sse()`](Self::get_sse) builds on it to parse events and reconnect
+/// when the connection is lost.
+///
+/// This trait is implemented for [`HttpClient`], so a client produced by an
+/// [`HttpClientFactory`] can be used directly.
+pub trait HttpGetSse {
+    /// Sends a GET request for the event stream at `uri` and returns the
+    /// response body, or `None` if the server responded with HTTP 204 No
+    /// Content to tell the client to stop reconnecting.
+    ///
+    /// `last_event_id` is sent in the `Last-Event-ID` header when
+    /// reconnecting.
+    fn open_sse<U>(
+        &self,
+        uri: U,
+        last_event_id: Option<&str>,
+    ) -> impl Future<Output = HttpResult<Option<BodyStream>>> + Send
+    where
+        U: IntoUrl + Send;
+
+    /// Subscribes to the event stream at `uri`, reconnecting according to
+    /// the default [`RetryPolicy`](retry::RetryPolicy).
+    ///
+    /// This fails if the first connection fails. See [`sse`](crate::sse)
+    /// for details.
+    fn get_sse<U>(&self, uri: U) -> impl Future<Output = HttpResult<EventStream<'_>>> + Send
+    where
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        self.get_sse_with_reconnect(uri, retry::RetryPolicy::new())
+    }
+
+    /// Subscribes to the event stream at `uri`, reconnecting according to
+    /// `policy`.
+    ///
+    /// The policy's maximum number of attempts limits consecutive failed
+    /// reconnection attempts. A connection that is established and later
+    /// lost is always reconnected.
+    fn get_sse_with_reconnect<U>(
+        &self,
+        uri: U,
+        policy: retry::RetryPolicy,
+    ) -> impl Future<Output = HttpResult<EventStream<'_>>> + Send
+    where
+        U: IntoUrl + Send,
+        Self: Sync,
+    {
+        async move {
+            let uri = uri.as_str().to_string();
+            let body = self.open_sse(uri.as_str(), None).await?;
+            Ok(crate::sse::events(self, uri, body, policy))
+        }
+    }
+}
+
+impl HttpGetSse for HttpClient {
+    async fn open_sse<U>(
+        &self,
+        uri: U,
+        last_event_id: Option<&str>,
+    ) -> HttpResult<Option<BodyStream>>
+    where
+        U: IntoUrl + Send,
+    {
+        let mut request = self
+            .get(uri)
+            .header(header::ACCEPT, "text/event-stream")
+            .header(header::CACHE_CONTROL, "no-cache");
+        if let Some(id) = last_event_id {
+            request = request.header("last-event-id", id);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if status == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(HttpError::from_status(status, response.headers()));
+        }
+        let chunks = response.bytes_stream().map(|chunk| Ok(chunk?));
+        Ok(Some(BodyStream::new(chunks)))
+    }
 }
 
 /// A service for making calls to an HTTP server and handling responses.
@@ -247,7 +1241,7 @@ pub trait HttpPost {
 /// }
 ///
 /// impl HttpPost for MyHTTPService {
-///     async fn post<U, D, R>(&self, uri: U, auth: &Auth, _data: &D) -> HttpResult<R>
+///     async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, _data: &D) -> HttpResult<R>
 ///     where
 ///         U: IntoUrl + Send,
 ///         D: Serialize + Sync,
@@ -267,3 +1261,427 @@ pub trait HttpPost {
 pub trait HttpService: HttpGet + HttpPost {}
 
 impl<T: HttpGet + HttpPost> HttpService for T {}
+
+/// The status, headers, and body of an HTTP response.
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: String,
+}
+
+impl HttpResponse {
+    /// Creates a new response with the given status and body, and no
+    /// headers.
+    ///
+    /// This is mostly useful for testing.
+    pub fn new(status: StatusCode, body: impl Into<String>) -> Self {
+        let headers = HeaderMap::new();
+        let body = body.into();
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Reads the entire body of a [Reqwest response].
+    ///
+    /// [Reqwest response]: https://docs.rs/reqwest/latest/reqwest/struct.Response.html
+    pub async fn from_response(response: reqwest::Response) -> HttpResult<Self> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await?;
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// The status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The headers of the response, for modification.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// The body of the response.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Consumes the response and returns its body.
+    pub fn into_body(self) -> String {
+        self.body
+    }
+
+    /// Deserializes the body of the response from JSON.
+    ///
+    /// If the body cannot be deserialized, the beginning of the body is
+    /// attached to the [error](HttpError::Decode).
+    pub fn json<T: DeserializeOwned>(&self) -> HttpResult<T> {
+        crate::decode::json(&self.body)
+    }
+
+    /// Deserializes the body of the response using the parser for the
+    /// format that it is actually in, regardless of its `Content-Type`.
+    ///
+    /// Responses with a `511 Network Authentication Required` status are
+    /// reported as [`HttpError::CaptivePortal`]. See
+    /// [`decode::sniff::decode()`](crate::decode::sniff::decode) for
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hypertyper::HttpError;
+    /// use hypertyper::service::HttpResponse;
+    /// use reqwest::StatusCode;
+    /// use reqwest::header::{self, HeaderValue};
+    ///
+    /// let response = HttpResponse::new(StatusCode::OK, "<html><title>Sign in</title></html>")
+    ///     .with_header(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    /// let err = response.decode::<serde_json::Value>().unwrap_err();
+    /// assert!(matches!(err, HttpError::HtmlErrorPage { .. }));
+    ///
+    /// let response = HttpResponse::new(StatusCode::NETWORK_AUTHENTICATION_REQUIRED, "");
+    /// let err = response.decode::<serde_json::Value>().unwrap_err();
+    /// assert!(matches!(err, HttpError::CaptivePortal { url: None, .. }));
+    /// ```
+    pub fn decode<T: DeserializeOwned>(&self) -> HttpResult<T> {
+        if self.status == StatusCode::NETWORK_AUTHENTICATION_REQUIRED {
+            return Err(crate::decode::sniff::captive_portal(&self.body));
+        }
+        let content_type = self
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        crate::decode::sniff::decode(content_type, self.body.as_bytes())
+    }
+
+    /// Returns the response if its status indicates success, or an
+    /// [error](HttpError::from_status) otherwise.
+    pub fn error_for_status(self) -> HttpResult<Self> {
+        if self.status.is_success() {
+            Ok(self)
+        } else {
+            Err(HttpError::from_status(self.status, &self.headers))
+        }
+    }
+}
+
+/// The status, headers, and raw body of a successful HTTP response.
+///
+/// See [`HttpBinary`].
+#[derive(Clone, Debug)]
+pub struct BinaryResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl BinaryResponse {
+    /// Creates a new response with the given status and body, and no
+    /// headers.
+    ///
+    /// This is mostly useful for testing.
+    pub fn new(status: StatusCode, body: impl Into<Bytes>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Reads the entire body of a [Reqwest response], failing if its status
+    /// is unsuccessful.
+    ///
+    /// [Reqwest response]: https://docs.rs/reqwest/latest/reqwest/struct.Response.html
+    pub async fn from_response(response: reqwest::Response) -> HttpResult<Self> {
+        let status = response.status();
+        if !status.is_success() {
+            return Err(HttpError::from_status(status, response.headers()));
+        }
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// The status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The body of the response.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Consumes the response and returns its body.
+    pub fn into_body(self) -> Bytes {
+        self.body
+    }
+}
+
+/// The host portion of `uri`, or an empty string if `uri` is not an
+/// absolute URL.
+pub(crate) fn host_of(uri: &str) -> String {
+    reqwest::Url::parse(uri)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Replaces the scheme, host, and port of `uri` with those of `origin`.
+///
+/// A relative URI is resolved against `origin` instead.
+pub(crate) fn with_origin(uri: &str, origin: &reqwest::Url) -> String {
+    match reqwest::Url::parse(uri) {
+        Ok(mut url) => {
+            // These only fail for URLs that cannot have a host, such as
+            // "mailto:" URLs, which are not useful here anyway.
+            let _ = url.set_scheme(origin.scheme());
+            let _ = url.set_host(origin.host_str());
+            let _ = url.set_port(origin.port());
+            url.into()
+        }
+        Err(_) => origin
+            .join(uri)
+            .map_or_else(|_| uri.to_string(), Into::into),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[tokio::test(start_paused = true)]
+    async fn it_gets_many_uris_with_bounded_concurrency() {
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        /// Takes as many milliseconds to respond as the number in the URI,
+        /// and records the most requests that were in flight at once.
+        #[derive(Default)]
+        struct Slow {
+            in_flight: Mutex<(usize, usize)>,
+        }
+
+        impl HttpGet for Slow {
+            async fn get<U>(&self, uri: U) -> HttpResult<String>
+            where
+                U: IntoUrl + Send,
+            {
+                let uri = uri.as_str().to_string();
+                let id = uri.rsplit('/').next().unwrap_or_default();
+                let millis: u64 = id
+                    .parse()
+                    .map_err(|_| HttpError::Http(StatusCode::NOT_FOUND))?;
+                {
+                    let mut in_flight = self.in_flight.lock().unwrap();
+                    in_flight.0 += 1;
+                    in_flight.1 = in_flight.1.max(in_flight.0);
+                }
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+                self.in_flight.lock().unwrap().0 -= 1;
+                Ok(id.to_string())
+            }
+        }
+
+        let service = Slow::default();
+        let uris = ["30", "10", "nope", "20", "5"].map(|id| format!("https://example.com/{id}"));
+        let results = service.get_many(uris, 2).await;
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_deref().ok(), Some("30"));
+        assert_eq!(results[1].as_deref().ok(), Some("10"));
+        assert!(matches!(
+            results[2],
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+        assert_eq!(results[4].as_deref().ok(), Some("5"));
+        assert_eq!(service.in_flight.lock().unwrap().1, 2);
+    }
+
+    /// Serves a single raw HTTP response on a local port and returns the
+    /// URL of the server.
+    fn serve_once(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{addr}/export")
+    }
+
+    /// Reads a single request with a `Content-Length` header, responds with
+    /// an empty 201 response, and returns the raw request.
+    fn capture_once() -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).into_owned();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let len = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .and_then(|len| len.parse::<usize>().ok())
+                        .unwrap_or_default();
+                    if n == 0 || body.len() >= len {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (format!("http://{addr}/upload"), handle)
+    }
+
+    fn client() -> HttpClient {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_streams_response_bodies() -> HttpResult<()> {
+        let uri = serve_once(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+        );
+        let mut body = client().get_stream(uri).await?;
+        let mut collected = Vec::new();
+        while let Some(chunk) = body.next().await {
+            collected.extend_from_slice(&chunk?);
+        }
+        assert_eq!(collected, b"hello world");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fetches_byte_ranges() -> HttpResult<()> {
+        let uri = serve_once(
+            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 6-10/11\r\nContent-Length: 5\r\n\r\nworld",
+        );
+        let mut body = client().get_range(uri, 6..11).await?;
+        assert_eq!(body.content_length(), Some(5));
+        assert_eq!(body.next().await.unwrap()?, "world");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_rejects_responses_that_ignore_the_range() {
+        let uri = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world");
+        let result = client().get_range(uri, 6..11).await;
+        assert!(matches!(result, Err(HttpError::RangeNotSupported(_))));
+    }
+
+    #[tokio::test]
+    async fn it_reports_the_length_of_ranged_resources() -> HttpResult<()> {
+        let uri =
+            serve_once("HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nContent-Length: 11\r\n\r\n");
+        assert_eq!(client().range_length(uri).await?, Some(11));
+        let uri = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n");
+        assert_eq!(client().range_length(uri).await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_streams_request_bodies() -> HttpResult<()> {
+        let (uri, server) = capture_once();
+        let chunks = futures_util::stream::iter([
+            Ok(Bytes::from_static(b"hello")),
+            Ok(Bytes::from_static(b" world")),
+        ]);
+        let body = UploadBody::from_stream(chunks).with_content_length(Some(11));
+        let response = client().post_stream(uri, &HeaderMap::new(), body).await?;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let request = server.join().unwrap();
+        assert!(request.contains("content-length: 11\r\n"));
+        assert!(request.ends_with("\r\n\r\nhello world"));
+        Ok(())
+    }
+
+    #[cfg(feature = "html")]
+    #[tokio::test]
+    async fn it_parses_html_pages() -> HttpResult<()> {
+        let uri = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Length: 46\r\n\r\n<title>Export</title><a href=\"page/2\">Next</a>",
+        );
+        let document = client().get_html(uri.as_str()).await?;
+        assert_eq!(document.title().as_deref(), Some("Export"));
+        assert_eq!(
+            document.links("a")?[0].as_str(),
+            uri.replace("/export", "/page/2")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_subscribes_to_server_sent_events() -> HttpResult<()> {
+        let uri = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: 20\r\n\r\nid: 1\ndata: hello\n\n",
+        );
+        let client = client();
+        let mut events = client.get_sse(uri).await?;
+        let event = events.next().await.unwrap()?;
+        assert_eq!(event.id.as_deref(), Some("1"));
+        assert_eq!(event.data, "hello");
+
+        let uri = serve_once("HTTP/1.1 204 No Content\r\n\r\n");
+        assert!(client.get_sse(uri).await?.next().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_before_streaming_unsuccessful_responses() {
+        let uri = serve_once("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+        let result = client().get_stream(uri).await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+    }
+}
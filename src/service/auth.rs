@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Authentication of every request made through a service.
+//!
+//! An [`AuthService`] applies an [`Authenticator`] to each request before
+//! handing it to the wrapped service, so credentials are configured once
+//! rather than added by each endpoint. Any authenticator can be used,
+//! including API keys in [headers](crate::auth::ApiKeyHeader) or
+//! [query parameters](crate::auth::ApiKeyQuery).
+//!
+//! Verbs that take additional headers, such as
+//! [`HttpGetResponse`] and [`HttpPostStream`], receive the authenticated
+//! URL and headers. [`HttpGet`] cannot send headers, so `get` requests are
+//! sent with the wrapped service's [`HttpGetResponse`] implementation
+//! instead, which the wrapped service must have for an `AuthService` to
+//! implement `HttpGet`. [`HttpPost`] requests are authenticated with the
+//! service's authenticator in place of the one they are called with.
+//!
+//! URIs may be relative, as they often are with test services; they are
+//! passed on to the wrapped service relative, with any query parameters
+//! the authenticator added.
+//!
+//! Services that make requests on behalf of many customers can choose the
+//! credentials for each request with a [`CredentialSelector`] instead; see [`tenant`](crate::service::tenant) for details.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::auth::ApiKeyQuery;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::auth::AuthService;
+//!
+//! fn authenticated<S: HttpService>(service: S, key: &str) -> AuthService<S> {
+//!     AuthService::new(service, ApiKeyQuery::new(key))
+//! }
+//! ```
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::tenant::{CredentialSelector, Selected};
+use crate::service::{
+    BinaryResponse, HttpBinary, HttpGet, HttpGetResponse, HttpPost, HttpPostStream, HttpResponse,
+};
+use crate::upload::UploadBody;
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{IntoUrl, Method, Request, Url};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+/// The base that relative URIs are resolved against while they are
+/// authenticated.
+const RELATIVE_BASE: &str = "http://relative.invalid";
+
+/// Wraps an HTTP service and authenticates every request made through it.
+///
+/// See the [module documentation](crate::service::auth) for details.
+#[derive(Debug)]
+pub struct AuthService<S> {
+    inner: S,
+    auth: Arc<dyn Authenticator>,
+}
+
+impl<S> AuthService<S> {
+    /// Wraps `inner` in a service that authenticates requests with `auth`.
+    pub fn new(inner: S, auth: impl Authenticator + 'static) -> Self {
+        let auth = Arc::new(auth);
+        Self { inner, auth }
+    }
+
+    /// Wraps `inner` in a service that authenticates each request with
+    /// the credentials chosen by `selector`.
+    pub fn with_selector(inner: S, selector: impl CredentialSelector + 'static) -> Self {
+        Self::new(inner, Selected(Box::new(selector)))
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The authenticator applied to each request.
+    ///
+    /// If the service was created [with a
+    /// selector](AuthService::with_selector), this is an authenticator
+    /// that delegates to the selected credentials.
+    pub fn authenticator(&self) -> &dyn Authenticator {
+        &*self.auth
+    }
+
+    /// Authenticates a request to `uri` with `headers`, returning the URI
+    /// and headers to send to the wrapped service.
+    fn authenticate(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &HeaderMap,
+    ) -> HttpResult<(String, HeaderMap)> {
+        authenticate(&*self.auth, method, uri, headers)
+    }
+}
+
+/// Authenticates a request to `uri` with `headers` using `auth`, returning
+/// the URI and headers to send.
+///
+/// Relative URIs are resolved against a placeholder origin while they are
+/// authenticated, and returned relative.
+pub(crate) fn authenticate(
+    auth: &dyn Authenticator,
+    method: Method,
+    uri: &str,
+    headers: &HeaderMap,
+) -> HttpResult<(String, HeaderMap)> {
+    let (url, relative) = resolve(uri)?;
+    let mut request = Request::new(method, url);
+    *request.headers_mut() = headers.clone();
+    auth.authenticate(&mut request)?;
+    let uri = unresolve(request.url(), relative);
+    Ok((uri, request.headers().clone()))
+}
+
+/// Parses `uri`, resolving it against a placeholder origin if it is
+/// relative, and returns the URL and whether it was relative.
+pub(crate) fn resolve(uri: &str) -> HttpResult<(Url, bool)> {
+    match Url::parse(uri) {
+        Ok(url) => Ok((url, false)),
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            Ok((Url::parse(RELATIVE_BASE)?.join(uri)?, true))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The URI of `url`, which is made relative again if it was
+/// [resolved](resolve) from a relative URI.
+pub(crate) fn unresolve(url: &Url, relative: bool) -> String {
+    if relative {
+        url[url::Position::BeforePath..].to_string()
+    } else {
+        url.to_string()
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGet for AuthService<S> {
+    /// Sends an authenticated GET request with the wrapped service's
+    /// [`HttpGetResponse`] implementation, so that credentials can be sent
+    /// in headers, and returns the body of a successful response.
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let (uri, headers) = self.authenticate(Method::GET, uri.as_str(), &HeaderMap::new())?;
+        let response = self.inner.get_response(uri, &headers).await?;
+        Ok(response.error_for_status()?.into_body())
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for AuthService<S> {
+    async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        self.inner.post(uri, &*self.auth, data).await
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGetResponse for AuthService<S> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let (uri, headers) = self.authenticate(Method::GET, uri.as_str(), headers)?;
+        self.inner.get_response(uri, &headers).await
+    }
+}
+
+impl<S: HttpPostStream + Sync> HttpPostStream for AuthService<S> {
+    async fn post_stream<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: UploadBody,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let (uri, headers) = self.authenticate(Method::POST, uri.as_str(), headers)?;
+        self.inner.post_stream(uri, &headers, body).await
+    }
+}
+
+impl<S: HttpBinary + Sync> HttpBinary for AuthService<S> {
+    async fn get_binary<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let (uri, headers) = self.authenticate(Method::GET, uri.as_str(), headers)?;
+        self.inner.get_binary(uri, &headers).await
+    }
+
+    async fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let (uri, headers) = self.authenticate(Method::POST, uri.as_str(), headers)?;
+        self.inner.post_binary(uri, &headers, body).await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for AuthService<S> {
+    fn capabilities(&self) -> Capabilities {
+        let capabilities = self.inner.capabilities();
+        // Plain GET requests are sent as GetResponse requests.
+        let get = capabilities
+            .supports(Verb::GetResponse)
+            .then_some(Verb::Get);
+        let verbs: Vec<_> = [
+            Verb::Post,
+            Verb::GetResponse,
+            Verb::PostStream,
+            Verb::Binary,
+        ]
+        .into_iter()
+        .chain(get)
+        .collect();
+        capabilities
+            .limit_verbs(&verbs)
+            .wrap(Middleware::new("auth").with_config(self.auth.scheme()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::auth::{ApiKeyHeader, ApiKeyQuery, Auth};
+    use reqwest::StatusCode;
+    use std::sync::Mutex;
+
+    /// Records the URI and headers of the last request.
+    #[derive(Default)]
+    struct RecordingService {
+        request: Mutex<Option<(String, HeaderMap)>>,
+    }
+
+    impl RecordingService {
+        fn request(&self) -> (String, HeaderMap) {
+            self.request.lock().unwrap().clone().unwrap()
+        }
+    }
+
+    impl HttpPost for RecordingService {
+        async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, _data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let mut request = Request::new(Method::POST, uri.into_url()?);
+            auth.authenticate(&mut request)?;
+            let uri = request.url().to_string();
+            *self.request.lock().unwrap() = Some((uri, request.headers().clone()));
+            Ok(serde_json::from_str("null")?)
+        }
+    }
+
+    impl HttpGetResponse for RecordingService {
+        async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            *self.request.lock().unwrap() = Some((uri.as_str().to_string(), headers.clone()));
+            Ok(HttpResponse::new(StatusCode::OK, "ok"))
+        }
+    }
+
+    #[tokio::test]
+    async fn it_adds_query_keys_to_get_requests() -> HttpResult<()> {
+        let service = AuthService::new(RecordingService::default(), ApiKeyQuery::new("secret"));
+        service.get("https://api.example.com/users?page=2").await?;
+        let (uri, _) = service.inner().request();
+        assert_eq!(uri, "https://api.example.com/users?page=2&api_key=secret");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_keeps_relative_uris_relative() -> HttpResult<()> {
+        let service = AuthService::new(RecordingService::default(), ApiKeyQuery::new("secret"));
+        service.get("/users").await?;
+        assert_eq!(service.inner().request().0, "/users?api_key=secret");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_adds_header_keys_to_requests_with_headers() -> HttpResult<()> {
+        let service = AuthService::new(RecordingService::default(), ApiKeyHeader::new("secret"));
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "1".parse().unwrap());
+        service.get_response("/users", &headers).await?;
+        let (uri, headers) = service.inner().request();
+        assert_eq!(uri, "/users");
+        assert_eq!(headers["x-api-key"], "secret");
+        assert_eq!(headers["x-request-id"], "1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_sends_header_keys_with_plain_gets() -> HttpResult<()> {
+        let service = AuthService::new(RecordingService::default(), Auth::new("secret"));
+        assert_eq!(service.get("/users").await?, "ok");
+        let (uri, headers) = service.inner().request();
+        assert_eq!(uri, "/users");
+        assert_eq!(headers[reqwest::header::AUTHORIZATION], "Bearer secret");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_authenticates_posts_with_its_own_authenticator() -> HttpResult<()> {
+        let service = AuthService::new(RecordingService::default(), ApiKeyQuery::new("secret"));
+        let _: () = service
+            .post("https://api.example.com/users", &Auth::new("ignored"), &())
+            .await?;
+        let (uri, headers) = service.inner().request();
+        assert_eq!(uri, "https://api.example.com/users?api_key=secret");
+        assert!(headers.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_authenticates_requests_with_the_selected_credentials() -> HttpResult<()> {
+        use crate::service::tenant::{self, TenantCredentials};
+
+        let credentials = TenantCredentials::new()
+            .with_tenant("acme", ApiKeyQuery::new("acme-key"))
+            .with_tenant("globex", ApiKeyQuery::new("globex-key"));
+        let service = AuthService::with_selector(RecordingService::default(), credentials);
+
+        tenant::scope("acme", service.get("/users")).await?;
+        assert_eq!(service.inner().request().0, "/users?api_key=acme-key");
+        let uri = "https://api.example.com/users";
+        let _: () = tenant::scope("globex", service.post(uri, &Auth::new("x"), &())).await?;
+        assert_eq!(
+            service.inner().request().0,
+            "https://api.example.com/users?api_key=globex-key"
+        );
+        assert!(matches!(
+            service.get("/users").await,
+            Err(HttpError::InvalidCredentials(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_the_scheme_but_not_the_key() {
+        struct Origin;
+
+        impl HttpCapabilities for Origin {
+            fn capabilities(&self) -> Capabilities {
+                Capabilities::new([Verb::Get, Verb::Post, Verb::GetSse])
+            }
+        }
+
+        let service = AuthService::new(Origin, ApiKeyHeader::new("secret"));
+        let capabilities = service.capabilities();
+        let auth = capabilities.find("auth").unwrap();
+        assert_eq!(auth.config(), "api-key-header");
+        assert!(!capabilities.supports(Verb::GetSse));
+        assert!(!capabilities.to_string().contains("secret"));
+    }
+}
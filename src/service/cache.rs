@@ -0,0 +1,1153 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! HTTP caching.
+//!
+//! A [`CachingService`] is an HTTP cache, as described in [RFC 9111]. It
+//! stores responses to GET requests and serves them again while they are
+//! fresh, according to their `Cache-Control`, `Expires`, and
+//! `Last-Modified` headers. Once a response is stale, the next request for
+//! it is sent with `If-None-Match` or `If-Modified-Since` headers, so that
+//! the server can answer with a short HTTP 304 response instead of sending
+//! the whole body again.
+//!
+//! A single service often makes requests on behalf of many users, so the
+//! cache follows the rules for shared caches. Responses marked `private`
+//! and responses to requests with an `Authorization` header are never
+//! stored, and a response that varies on request headers, as named by its
+//! `Vary` header, is only served for requests with the same values of
+//! those headers.
+//!
+//! Responses are kept in a [`CacheStore`]. By default, a
+//! [`MemoryCache`] holds the most recently used responses in memory, but
+//! any other store, such as a [`DiskCache`] that persists responses between
+//! runs, can be used with [`CachingService::with_store()`].
+//!
+//! A [`CachePolicy`] can relax these rules for clients on unreliable
+//! networks, serving stale responses while they are revalidated in the
+//! background or when the server cannot be reached, or serving only cached
+//! responses in offline mode.
+//!
+//! [`CachingService::stats()`] reports how often requests were answered
+//! from the cache, which helps when tuning policies and store sizes.
+//!
+//! POST requests are never cached, and a successful POST request removes
+//! any cached response for the same URI, since it has probably changed.
+//!
+//! # Usage
+//!
+//! Caching works at the level of headers, so the wrapped service must
+//! implement [`HttpGetResponse`], as [`HttpClient`](crate::HttpClient)
+//! does:
+//!
+//! ```
+//! use hypertyper::service::HttpGetResponse;
+//! use hypertyper::service::cache::{CachingService, MemoryCache};
+//!
+//! fn cached<S: HttpGetResponse>(service: S) -> CachingService<S> {
+//!     CachingService::with_store(service, MemoryCache::new(500))
+//! }
+//! ```
+//!
+//! [RFC 9111]: https://www.rfc-editor.org/rfc/rfc9111
+
+pub mod disk;
+pub mod memory;
+
+pub use disk::DiskCache;
+pub use memory::MemoryCache;
+
+use crate::auth::Authenticator;
+use crate::headers;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpGetResponse, HttpPost, HttpResponse};
+use crate::{HttpError, HttpResult};
+use reqwest::header::{self, HeaderMap, HeaderName};
+use reqwest::{IntoUrl, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Status codes that may be cached without explicit freshness information,
+/// as listed in [RFC 9110 § 15.1].
+///
+/// [RFC 9110 § 15.1]: https://www.rfc-editor.org/rfc/rfc9110#section-15.1
+const HEURISTICALLY_CACHEABLE: [StatusCode; 11] = [
+    StatusCode::OK,
+    StatusCode::NON_AUTHORITATIVE_INFORMATION,
+    StatusCode::NO_CONTENT,
+    StatusCode::MULTIPLE_CHOICES,
+    StatusCode::MOVED_PERMANENTLY,
+    StatusCode::PERMANENT_REDIRECT,
+    StatusCode::NOT_FOUND,
+    StatusCode::METHOD_NOT_ALLOWED,
+    StatusCode::GONE,
+    StatusCode::URI_TOO_LONG,
+    StatusCode::NOT_IMPLEMENTED,
+];
+
+/// The greatest number of seconds a header such as `Age` is taken to
+/// mean, as described in [RFC 9111 § 1.2.2].
+///
+/// [RFC 9111 § 1.2.2]: https://www.rfc-editor.org/rfc/rfc9111#section-1.2.2
+const MAX_DELTA_SECONDS: u64 = 1 << 31;
+
+/// Storage for cached responses.
+///
+/// Stores are keyed by request URI. A response that varies on request
+/// headers keeps their values in its [`CachedResponse::request_headers()`],
+/// which must be stored along with it. Implementations must be safe to share
+/// between threads, since a [`CachingService`] may be used by many tasks at
+/// once.
+pub trait CacheStore: Send + Sync {
+    /// The response stored for `key`, if there is one.
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+
+    /// Stores a response for `key`, replacing any existing response.
+    fn put(&self, key: &str, entry: CachedResponse);
+
+    /// Removes the response stored for `key`, if there is one.
+    fn remove(&self, key: &str);
+
+    /// The number of responses in the store, if the store keeps track.
+    fn entry_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// The approximate space taken up by responses in the store, in bytes,
+    /// if the store keeps track.
+    fn size(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl<T: CacheStore + ?Sized> CacheStore for Arc<T> {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &str, entry: CachedResponse) {
+        (**self).put(key, entry)
+    }
+
+    fn remove(&self, key: &str) {
+        (**self).remove(key)
+    }
+
+    fn entry_count(&self) -> Option<usize> {
+        (**self).entry_count()
+    }
+
+    fn size(&self) -> Option<u64> {
+        (**self).size()
+    }
+}
+
+/// A response held in a [`CacheStore`], along with when it was stored.
+#[derive(Clone, Debug)]
+pub struct CachedResponse {
+    response: HttpResponse,
+    stored_at: SystemTime,
+    request_headers: HeaderMap,
+}
+
+impl CachedResponse {
+    /// Creates a cache entry for a response that was received at
+    /// `stored_at`.
+    pub fn new(response: HttpResponse, stored_at: SystemTime) -> Self {
+        Self {
+            response,
+            stored_at,
+            request_headers: HeaderMap::new(),
+        }
+    }
+
+    /// Records the headers of the request the response answered that are
+    /// named by its `Vary` header.
+    ///
+    /// Only these headers are kept, so `headers` may be the full set sent
+    /// with the request.
+    pub fn with_request_headers(mut self, headers: &HeaderMap) -> Self {
+        self.request_headers = HeaderMap::new();
+        for name in vary(self.response.headers()) {
+            for value in headers.get_all(&name) {
+                self.request_headers.append(name.clone(), value.clone());
+            }
+        }
+        self
+    }
+
+    /// The cached response.
+    pub fn response(&self) -> &HttpResponse {
+        &self.response
+    }
+
+    /// Consumes the entry and returns the cached response.
+    pub fn into_response(self) -> HttpResponse {
+        self.response
+    }
+
+    /// When the response was received.
+    pub fn stored_at(&self) -> SystemTime {
+        self.stored_at
+    }
+
+    /// The headers of the request the response answered that are named by
+    /// its `Vary` header.
+    pub fn request_headers(&self) -> &HeaderMap {
+        &self.request_headers
+    }
+
+    /// Whether the response may answer a request with `headers`, which it
+    /// may if they have the same values of the headers it varies on, as
+    /// described in [RFC 9111 § 4.1].
+    ///
+    /// [RFC 9111 § 4.1]: https://www.rfc-editor.org/rfc/rfc9111#section-4.1
+    pub fn matches_request(&self, headers: &HeaderMap) -> bool {
+        vary(self.response.headers()).iter().all(|name| {
+            self.request_headers
+                .get_all(name)
+                .iter()
+                .eq(headers.get_all(name).iter())
+        })
+    }
+
+    /// The approximate space the response takes up, in bytes: the length of
+    /// its body and headers.
+    pub fn size(&self) -> usize {
+        let headers = self.response.headers();
+        let header_len: usize = headers
+            .iter()
+            .map(|(name, value)| name.as_str().len() + value.len())
+            .sum();
+        self.response.body().len() + header_len
+    }
+
+    /// How long the response stays fresh after it was generated by the
+    /// server, as described in [RFC 9111 § 4.2.1].
+    ///
+    /// [RFC 9111 § 4.2.1]: https://www.rfc-editor.org/rfc/rfc9111#section-4.2.1
+    pub fn freshness_lifetime(&self) -> Duration {
+        let headers = self.response.headers();
+        let cache_control = headers::cache_control(headers);
+        if cache_control.no_cache {
+            return Duration::ZERO;
+        }
+        if let Some(max_age) = cache_control.max_age {
+            return max_age;
+        }
+        let date = header_date(headers, &header::DATE).unwrap_or(self.stored_at);
+        if headers.contains_key(header::EXPIRES) {
+            // An invalid Expires header, such as "0", means the response
+            // has already expired.
+            return header_date(headers, &header::EXPIRES)
+                .and_then(|expires| expires.duration_since(date).ok())
+                .unwrap_or(Duration::ZERO);
+        }
+        if HEURISTICALLY_CACHEABLE.contains(&self.response.status()) {
+            // RFC 9111 § 4.2.2 suggests 10% of the time since the resource
+            // was last modified.
+            if let Some(last_modified) = header_date(headers, &header::LAST_MODIFIED) {
+                return date.duration_since(last_modified).unwrap_or_default() / 10;
+            }
+        }
+        Duration::ZERO
+    }
+
+    /// How old the response is at `now`, as described in
+    /// [RFC 9111 § 4.2.3].
+    ///
+    /// [RFC 9111 § 4.2.3]: https://www.rfc-editor.org/rfc/rfc9111#section-4.2.3
+    pub fn age(&self, now: SystemTime) -> Duration {
+        let headers = self.response.headers();
+        let apparent_age = header_date(headers, &header::DATE)
+            .and_then(|date| self.stored_at.duration_since(date).ok())
+            .unwrap_or_default();
+        let age_header = headers
+            .get(header::AGE)
+            .and_then(|age| age.to_str().ok())
+            .and_then(parse_delta_seconds)
+            .unwrap_or_default();
+        let resident_time = now.duration_since(self.stored_at).unwrap_or_default();
+        apparent_age.max(age_header).saturating_add(resident_time)
+    }
+
+    /// Whether the response can be used without revalidating it at `now`.
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        self.freshness_lifetime() > self.age(now)
+    }
+
+    /// How long the response has been stale at `now`, which is zero if it
+    /// is still fresh.
+    pub fn staleness(&self, now: SystemTime) -> Duration {
+        self.age(now).saturating_sub(self.freshness_lifetime())
+    }
+
+    /// Whether the response has an `ETag` or `Last-Modified` header that
+    /// can be used to revalidate it.
+    pub fn has_validators(&self) -> bool {
+        let headers = self.response.headers();
+        headers.contains_key(header::ETAG) || headers.contains_key(header::LAST_MODIFIED)
+    }
+
+    /// The conditional request headers used to revalidate the response.
+    fn conditional_headers(&self) -> HeaderMap {
+        let headers = self.response.headers();
+        let mut conditional = HeaderMap::new();
+        if let Some(etag) = headers.get(header::ETAG) {
+            conditional.insert(header::IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = headers.get(header::LAST_MODIFIED) {
+            conditional.insert(header::IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        conditional
+    }
+
+    /// Updates the entry with the headers of a HTTP 304 response, as
+    /// described in [RFC 9111 § 3.2].
+    ///
+    /// [RFC 9111 § 3.2]: https://www.rfc-editor.org/rfc/rfc9111#section-3.2
+    fn revalidated(mut self, not_modified: &HeaderMap, now: SystemTime) -> Self {
+        let headers = self.response.headers_mut();
+        for name in not_modified.keys() {
+            if *name == header::CONTENT_LENGTH {
+                continue;
+            }
+            headers.remove(name);
+            for value in not_modified.get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+        self.stored_at = now;
+        self
+    }
+}
+
+/// Parses a number of seconds, as described in [RFC 9111 § 1.2.2].
+///
+/// Values too large to represent are clamped to 2<sup>31</sup> seconds,
+/// as the RFC requires, so that they cannot overflow later calculations.
+///
+/// [RFC 9111 § 1.2.2]: https://www.rfc-editor.org/rfc/rfc9111#section-1.2.2
+fn parse_delta_seconds(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let seconds = value
+        .parse::<u64>()
+        .unwrap_or(MAX_DELTA_SECONDS)
+        .min(MAX_DELTA_SECONDS);
+    Some(Duration::from_secs(seconds))
+}
+
+/// The request headers named by the `Vary` headers in `headers`.
+fn vary(headers: &HeaderMap) -> Vec<HeaderName> {
+    headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|vary| vary.to_str().ok())
+        .flat_map(|vary| vary.split(','))
+        .filter_map(|name| HeaderName::try_from(name.trim()).ok())
+        .collect()
+}
+
+fn header_date(headers: &HeaderMap, name: &HeaderName) -> Option<SystemTime> {
+    let value = headers.get(name)?.to_str().ok()?;
+    httpdate::parse_http_date(value.trim()).ok()
+}
+
+/// Whether a response to a GET request with `request_headers` may be
+/// stored by a shared cache, as described in [RFC 9111 § 3].
+///
+/// [RFC 9111 § 3]: https://www.rfc-editor.org/rfc/rfc9111#section-3
+fn is_storable(response: &HttpResponse, request_headers: &HeaderMap) -> bool {
+    let status = response.status();
+    let headers = response.headers();
+    let cache_control = headers::cache_control(headers);
+    if cache_control.no_store
+        || cache_control.private
+        || request_headers.contains_key(header::AUTHORIZATION)
+        || status.is_informational()
+        || status == StatusCode::PARTIAL_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+    {
+        return false;
+    }
+    let varies_on_everything = headers
+        .get_all(header::VARY)
+        .iter()
+        .any(|vary| vary.to_str().is_ok_and(|vary| vary.trim() == "*"));
+    if varies_on_everything {
+        return false;
+    }
+    cache_control.public
+        || cache_control.max_age.is_some()
+        || headers.contains_key(header::EXPIRES)
+        || HEURISTICALLY_CACHEABLE.contains(&status)
+}
+
+/// Determines when a [`CachingService`] may serve stale responses.
+///
+/// By default, the service follows [RFC 9111] strictly, except that it
+/// honors the `stale-while-revalidate` and `stale-if-error` directives of
+/// [RFC 5861] when responses carry them. A policy can allow stale responses
+/// to be served for longer than responses ask, or put the service in
+/// offline mode.
+///
+/// Stale responses are never served in place of responses with
+/// `must-revalidate` or `no-cache` directives, except in offline mode.
+///
+/// [RFC 9111]: https://www.rfc-editor.org/rfc/rfc9111
+/// [RFC 5861]: https://www.rfc-editor.org/rfc/rfc5861
+#[derive(Clone, Debug, Default)]
+pub struct CachePolicy {
+    stale_while_revalidate: Duration,
+    stale_if_error: Duration,
+    offline: bool,
+}
+
+impl CachePolicy {
+    /// Creates the default cache policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves responses that have been stale for up to `window` straight
+    /// from the cache, while revalidating them in the background.
+    pub fn with_stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_while_revalidate = window;
+        self
+    }
+
+    /// Serves responses that have been stale for up to `window` when
+    /// revalidating them fails with a network error or an HTTP 500, 502,
+    /// 503, or 504 response.
+    pub fn with_stale_if_error(mut self, window: Duration) -> Self {
+        self.stale_if_error = window;
+        self
+    }
+
+    /// Enables or disables offline mode.
+    ///
+    /// In offline mode, no requests are sent. Cached responses are served
+    /// no matter how stale they are, and requests for anything else fail
+    /// with [`HttpError::NotCached`].
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// How long responses may be served stale while they are revalidated.
+    pub fn stale_while_revalidate(&self) -> Duration {
+        self.stale_while_revalidate
+    }
+
+    /// How long responses may be served stale when revalidation fails.
+    pub fn stale_if_error(&self) -> Duration {
+        self.stale_if_error
+    }
+
+    /// Whether the cache is in offline mode.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Whether `entry` may be served while it is revalidated.
+    fn may_serve_while_revalidating(&self, entry: &CachedResponse, now: SystemTime) -> bool {
+        let window = self.window(
+            entry,
+            |cc| cc.stale_while_revalidate,
+            self.stale_while_revalidate,
+        );
+        entry.staleness(now) <= window && !window.is_zero()
+    }
+
+    /// Whether `entry` may be served in place of an error.
+    fn may_serve_on_error(&self, entry: &CachedResponse, now: SystemTime) -> bool {
+        let window = self.window(entry, |cc| cc.stale_if_error, self.stale_if_error);
+        entry.staleness(now) <= window && !window.is_zero()
+    }
+
+    fn window<F>(&self, entry: &CachedResponse, directive: F, configured: Duration) -> Duration
+    where
+        F: Fn(&headers::CacheControl) -> Option<Duration>,
+    {
+        let cache_control = headers::cache_control(entry.response().headers());
+        if cache_control.must_revalidate || cache_control.no_cache {
+            return Duration::ZERO;
+        }
+        directive(&cache_control)
+            .unwrap_or_default()
+            .max(configured)
+    }
+}
+
+/// How effective a [`CachingService`] has been since it was created.
+///
+/// Every GET request counts as exactly one hit, miss, or stale lookup,
+/// except requests that carry their own conditional headers, which bypass
+/// the cache.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Requests answered with a fresh cached response.
+    pub hits: u64,
+
+    /// Requests for which no response was cached.
+    pub misses: u64,
+
+    /// Requests for which the cached response was stale.
+    pub stale: u64,
+
+    /// Stale responses that were served without waiting for them to be
+    /// revalidated, as allowed by the [`CachePolicy`].
+    pub stale_served: u64,
+
+    /// Stale responses that the server confirmed were unchanged with an
+    /// HTTP 304 response.
+    pub revalidated: u64,
+
+    /// The number of responses in the store, if the store keeps track.
+    pub entries: Option<usize>,
+
+    /// The approximate space taken up by responses in the store, in bytes,
+    /// if the store keeps track.
+    pub size: Option<u64>,
+}
+
+impl CacheStats {
+    /// The fraction of requests that were answered from the cache without
+    /// contacting the server, between 0 and 1.
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.misses + self.stale;
+        if lookups == 0 {
+            0.0
+        } else {
+            (self.hits + self.stale_served) as f64 / lookups as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale: AtomicU64,
+    stale_served: AtomicU64,
+    revalidated: AtomicU64,
+}
+
+impl Counters {
+    fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Wraps an HTTP service and caches its responses to GET requests.
+///
+/// See the [module documentation](crate::service::cache) for details.
+#[derive(Debug)]
+pub struct CachingService<S, C = MemoryCache> {
+    shared: Arc<Shared<S, C>>,
+    policy: CachePolicy,
+}
+
+/// The parts of a [`CachingService`] that background revalidation needs.
+#[derive(Debug)]
+struct Shared<S, C> {
+    inner: S,
+    store: C,
+    revalidating: Mutex<HashSet<String>>,
+    counters: Counters,
+}
+
+impl<S> CachingService<S> {
+    /// Wraps `inner` in a service that caches responses in a
+    /// [`MemoryCache`] with the default capacity.
+    pub fn new(inner: S) -> Self {
+        Self::with_store(inner, MemoryCache::default())
+    }
+}
+
+impl<S, C> CachingService<S, C> {
+    /// Wraps `inner` in a service that caches responses in `store`.
+    pub fn with_store(inner: S, store: C) -> Self {
+        let shared = Shared {
+            inner,
+            store,
+            revalidating: Mutex::default(),
+            counters: Counters::default(),
+        };
+        Self {
+            shared: Arc::new(shared),
+            policy: CachePolicy::default(),
+        }
+    }
+
+    /// Sets the policy that determines when stale responses are served.
+    pub fn with_policy(mut self, policy: CachePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.shared.inner
+    }
+
+    /// The store that holds cached responses.
+    pub fn store(&self) -> &C {
+        &self.shared.store
+    }
+
+    /// The policy that determines when stale responses are served.
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+}
+
+impl<S, C: CacheStore> CachingService<S, C> {
+    /// Statistics about how effective the cache has been.
+    pub fn stats(&self) -> CacheStats {
+        let counters = &self.shared.counters;
+        CacheStats {
+            hits: counters.hits.load(Ordering::Relaxed),
+            misses: counters.misses.load(Ordering::Relaxed),
+            stale: counters.stale.load(Ordering::Relaxed),
+            stale_served: counters.stale_served.load(Ordering::Relaxed),
+            revalidated: counters.revalidated.load(Ordering::Relaxed),
+            entries: self.shared.store.entry_count(),
+            size: self.shared.store.size(),
+        }
+    }
+}
+
+impl<S, C> Shared<S, C>
+where
+    S: HttpGetResponse + Sync,
+    C: CacheStore,
+{
+    /// Requests `uri`, revalidating `cached` if there is a cached response,
+    /// and updates the store with the result.
+    ///
+    /// Server errors are returned without touching the store, so that the
+    /// cached response can still be served if stale responses are allowed.
+    async fn revalidate(
+        &self,
+        uri: &str,
+        headers: &HeaderMap,
+        cached: Option<CachedResponse>,
+    ) -> HttpResult<HttpResponse> {
+        let mut request_headers = headers.clone();
+        if let Some(entry) = &cached {
+            request_headers.extend(entry.conditional_headers());
+        }
+
+        let response = self.inner.get_response(uri, &request_headers).await?;
+        let now = SystemTime::now();
+        match cached {
+            Some(entry) if response.status() == StatusCode::NOT_MODIFIED => {
+                Counters::increment(&self.counters.revalidated);
+                let entry = entry.revalidated(response.headers(), now);
+                self.store.put(uri, entry.clone());
+                Ok(entry.into_response())
+            }
+            _ if response.status().is_server_error() => Ok(response),
+            _ => {
+                let entry =
+                    CachedResponse::new(response.clone(), now).with_request_headers(headers);
+                let worth_storing = entry.has_validators() || !entry.freshness_lifetime().is_zero();
+                if is_storable(&response, headers) && worth_storing {
+                    self.store.put(uri, entry);
+                } else {
+                    self.store.remove(uri);
+                }
+                Ok(response)
+            }
+        }
+    }
+}
+
+impl<S, C> CachingService<S, C>
+where
+    S: HttpGetResponse + Send + Sync + 'static,
+    C: CacheStore + 'static,
+{
+    async fn fetch(&self, uri: &str, headers: &HeaderMap) -> HttpResult<HttpResponse> {
+        // The caller is doing its own revalidation, so stay out of its way.
+        if headers.contains_key(header::IF_NONE_MATCH)
+            || headers.contains_key(header::IF_MODIFIED_SINCE)
+        {
+            return self.shared.inner.get_response(uri, headers).await;
+        }
+
+        let now = SystemTime::now();
+        let cached = self
+            .shared
+            .store
+            .get(uri)
+            .filter(|entry| entry.matches_request(headers));
+        let counters = &self.shared.counters;
+        match &cached {
+            Some(entry) if entry.is_fresh(now) => {
+                Counters::increment(&counters.hits);
+                return Ok(entry.response().clone());
+            }
+            Some(_) => Counters::increment(&counters.stale),
+            None => Counters::increment(&counters.misses),
+        }
+        match &cached {
+            Some(entry) if self.policy.offline => {
+                Counters::increment(&counters.stale_served);
+                return Ok(entry.response().clone());
+            }
+            Some(entry) if self.policy.may_serve_while_revalidating(entry, now) => {
+                Counters::increment(&counters.stale_served);
+                self.revalidate_in_background(uri, headers, entry.clone());
+                return Ok(entry.response().clone());
+            }
+            None if self.policy.offline => return Err(HttpError::NotCached(uri.to_string())),
+            _ => {}
+        }
+
+        let result = self.shared.revalidate(uri, headers, cached.clone()).await;
+        let failed = match &result {
+            Ok(response) => is_error_for_stale(response.status()),
+            Err(HttpError::Request(_) | HttpError::Timeout(_)) => true,
+            Err(err) => err.status().is_some_and(is_error_for_stale),
+        };
+        match cached {
+            Some(entry) if failed && self.policy.may_serve_on_error(&entry, now) => {
+                Counters::increment(&counters.stale_served);
+                Ok(entry.into_response())
+            }
+            _ => result,
+        }
+    }
+
+    fn revalidate_in_background(&self, uri: &str, headers: &HeaderMap, entry: CachedResponse) {
+        if !self
+            .shared
+            .revalidating
+            .lock()
+            .unwrap()
+            .insert(uri.to_string())
+        {
+            return;
+        }
+        let shared = Arc::clone(&self.shared);
+        let uri = uri.to_string();
+        let headers = headers.clone();
+        tokio::spawn(async move {
+            // Failures are ignored; the next request will try again.
+            let _ = shared.revalidate(&uri, &headers, Some(entry)).await;
+            shared.revalidating.lock().unwrap().remove(&uri);
+        });
+    }
+}
+
+/// Whether a status allows a stale response to be served instead, as
+/// described in [RFC 5861 § 4].
+///
+/// [RFC 5861 § 4]: https://www.rfc-editor.org/rfc/rfc5861#section-4
+fn is_error_for_stale(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+impl<S, C> HttpGetResponse for CachingService<S, C>
+where
+    S: HttpGetResponse + Send + Sync + 'static,
+    C: CacheStore + 'static,
+{
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.fetch(uri.as_str(), headers).await
+    }
+}
+
+impl<S, C> HttpGet for CachingService<S, C>
+where
+    S: HttpGetResponse + Send + Sync + 'static,
+    C: CacheStore + 'static,
+{
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let response = self.fetch(uri.as_str(), &HeaderMap::new()).await?;
+        Ok(response.error_for_status()?.into_body())
+    }
+}
+
+impl<S, C> HttpPost for CachingService<S, C>
+where
+    S: HttpPost + Send + Sync,
+    C: CacheStore,
+{
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let key = uri.as_str().to_string();
+        let response = self.shared.inner.post(uri, auth, data).await?;
+        self.shared.store.remove(&key);
+        Ok(response)
+    }
+}
+
+impl<S: HttpCapabilities, C> HttpCapabilities for CachingService<S, C> {
+    fn capabilities(&self) -> Capabilities {
+        let cache = Middleware::new("cache").with_config(format!("{:?}", self.policy));
+        self.inner()
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post, Verb::GetResponse])
+            .wrap(cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use reqwest::header::HeaderValue;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Returns scripted responses and records the headers of each request.
+    #[derive(Default)]
+    struct OriginServer {
+        responses: Mutex<VecDeque<HttpResponse>>,
+        requests: Mutex<Vec<HeaderMap>>,
+    }
+
+    impl OriginServer {
+        fn new(responses: impl IntoIterator<Item = HttpResponse>) -> Self {
+            let responses = Mutex::new(responses.into_iter().collect());
+            Self {
+                responses,
+                ..Self::default()
+            }
+        }
+
+        fn requests(&self) -> Vec<HeaderMap> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpGetResponse for OriginServer {
+        async fn get_response<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            self.requests.lock().unwrap().push(headers.clone());
+            let response = self.responses.lock().unwrap().pop_front();
+            Ok(response.expect("no more scripted responses"))
+        }
+    }
+
+    impl HttpPost for OriginServer {
+        async fn post<U, D, R>(
+            &self,
+            _uri: U,
+            _auth: &dyn Authenticator,
+            _data: &D,
+        ) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            Ok(serde_json::from_str("null")?)
+        }
+    }
+
+    fn ok(body: &str, cache_control: &'static str) -> HttpResponse {
+        HttpResponse::new(StatusCode::OK, body).with_header(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(cache_control),
+        )
+    }
+
+    fn not_modified() -> HttpResponse {
+        HttpResponse::new(StatusCode::NOT_MODIFIED, "").with_header(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("max-age=60"),
+        )
+    }
+
+    const URI: &str = "https://api.example.com/users";
+
+    #[tokio::test]
+    async fn it_serves_fresh_responses_from_the_cache() -> HttpResult<()> {
+        let service = CachingService::new(OriginServer::new([ok("users", "max-age=60")]));
+        assert_eq!(service.get(URI).await?, "users");
+        assert_eq!(service.get(URI).await?, "users");
+        assert_eq!(service.inner().requests().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_revalidates_stale_responses_with_etags() -> HttpResult<()> {
+        let first =
+            ok("users", "max-age=0").with_header(header::ETAG, HeaderValue::from_static("\"v1\""));
+        let service = CachingService::new(OriginServer::new([first, not_modified()]));
+        assert_eq!(service.get(URI).await?, "users");
+        assert_eq!(service.get(URI).await?, "users");
+        // The 304 made the response fresh again.
+        assert_eq!(service.get(URI).await?, "users");
+
+        let stats = service.stats();
+        assert_eq!((stats.hits, stats.misses, stats.stale), (1, 1, 1));
+        assert_eq!(stats.revalidated, 1);
+        assert_eq!(stats.entries, Some(1));
+        assert!(stats.size.is_some_and(|size| size > 0));
+        assert_eq!(stats.hit_ratio(), 1.0 / 3.0);
+
+        let requests = service.inner().requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].is_empty());
+        assert_eq!(requests[1][header::IF_NONE_MATCH], "\"v1\"");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_revalidates_with_last_modified_dates() -> HttpResult<()> {
+        let last_modified = "Wed, 21 Oct 2015 07:28:00 GMT";
+        let first = ok("users", "no-cache").with_header(
+            header::LAST_MODIFIED,
+            HeaderValue::from_static(last_modified),
+        );
+        let second = ok("new users", "no-cache");
+        let service = CachingService::new(OriginServer::new([first, second]));
+        assert_eq!(service.get(URI).await?, "users");
+        assert_eq!(service.get(URI).await?, "new users");
+        assert_eq!(
+            service.inner().requests()[1][header::IF_MODIFIED_SINCE],
+            last_modified
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_does_not_store_no_store_responses() -> HttpResult<()> {
+        let responses = [
+            ok("one", "no-store, max-age=60"),
+            ok("two", "no-store, max-age=60"),
+        ];
+        let service = CachingService::new(OriginServer::new(responses));
+        assert_eq!(service.get(URI).await?, "one");
+        assert_eq!(service.get(URI).await?, "two");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_does_not_store_private_or_authorized_responses() -> HttpResult<()> {
+        let responses = [
+            ok("mine", "private, max-age=60"),
+            ok("yours", "private, max-age=60"),
+            ok("alice", "max-age=60"),
+            ok("bob", "max-age=60"),
+        ];
+        let service = CachingService::new(OriginServer::new(responses));
+        assert_eq!(service.get(URI).await?, "mine");
+        assert_eq!(service.get(URI).await?, "yours");
+
+        let headers = |token: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_static(token));
+            headers
+        };
+        let response = service.get_response(URI, &headers("Bearer alice")).await?;
+        assert_eq!(response.body(), "alice");
+        let response = service.get_response(URI, &headers("Bearer bob")).await?;
+        assert_eq!(response.body(), "bob");
+        assert_eq!(service.stats().entries, Some(0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_only_serves_responses_for_the_headers_they_vary_on() -> HttpResult<()> {
+        let varying = |body: &str| {
+            ok(body, "max-age=60").with_header(header::VARY, HeaderValue::from_static("Accept"))
+        };
+        let responses = [varying("json"), varying("xml"), varying("json again")];
+        let service = CachingService::new(OriginServer::new(responses));
+        let accept = |accept: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, HeaderValue::from_static(accept));
+            headers
+        };
+
+        let json = accept("application/json");
+        assert_eq!(service.get_response(URI, &json).await?.body(), "json");
+        assert_eq!(service.get_response(URI, &json).await?.body(), "json");
+        let xml = accept("application/xml");
+        assert_eq!(service.get_response(URI, &xml).await?.body(), "xml");
+        assert_eq!(service.get_response(URI, &xml).await?.body(), "xml");
+        assert_eq!(service.get(URI).await?, "json again");
+        assert_eq!(service.inner().requests().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_expires_responses_as_they_age() -> HttpResult<()> {
+        let store = Arc::new(MemoryCache::new(10));
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        store.put(
+            URI,
+            CachedResponse::new(ok("old", "max-age=60"), an_hour_ago),
+        );
+        let origin = OriginServer::new([ok("new", "max-age=60")]);
+        let service = CachingService::with_store(origin, Arc::clone(&store));
+        assert_eq!(service.get(URI).await?, "new");
+        assert_eq!(store.get(URI).unwrap().response().body(), "new");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_invalidates_responses_after_posts() -> HttpResult<()> {
+        let responses = [ok("before", "max-age=60"), ok("after", "max-age=60")];
+        let service = CachingService::new(OriginServer::new(responses));
+        assert_eq!(service.get(URI).await?, "before");
+        let _: () = service.post(URI, &Auth::new("key"), &()).await?;
+        assert_eq!(service.get(URI).await?, "after");
+        Ok(())
+    }
+
+    /// A service whose store holds a response for [`URI`] that was received
+    /// `secs_ago` seconds ago.
+    fn with_cached(
+        origin: OriginServer,
+        cache_control: &'static str,
+        secs_ago: u64,
+    ) -> CachingService<OriginServer, Arc<MemoryCache>> {
+        let store = Arc::new(MemoryCache::new(10));
+        let stored_at = SystemTime::now() - Duration::from_secs(secs_ago);
+        let response =
+            ok("old", cache_control).with_header(header::ETAG, HeaderValue::from_static("\"v1\""));
+        store.put(URI, CachedResponse::new(response, stored_at));
+        CachingService::with_store(origin, store)
+    }
+
+    fn server_error() -> HttpResponse {
+        HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, "down")
+    }
+
+    #[tokio::test]
+    async fn it_serves_stale_responses_while_revalidating() -> HttpResult<()> {
+        let origin = OriginServer::new([ok("new", "max-age=60")]);
+        let service = with_cached(origin, "max-age=60, stale-while-revalidate=120", 90);
+        assert_eq!(service.get(URI).await?, "old");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(service.get(URI).await?, "new");
+        assert_eq!(
+            service.inner().requests()[0][header::IF_NONE_MATCH],
+            "\"v1\""
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_serves_stale_responses_for_as_long_as_the_policy_allows() -> HttpResult<()> {
+        let policy = CachePolicy::new().with_stale_while_revalidate(Duration::from_secs(60));
+        let origin = OriginServer::new([ok("new", "max-age=60")]);
+        let service = with_cached(origin, "max-age=60", 90).with_policy(policy.clone());
+        assert_eq!(service.get(URI).await?, "old");
+
+        let origin = OriginServer::new([ok("new", "max-age=60")]);
+        let service = with_cached(origin, "max-age=60", 150).with_policy(policy);
+        assert_eq!(service.get(URI).await?, "new");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_serves_stale_responses_on_server_errors() -> HttpResult<()> {
+        let policy = CachePolicy::new().with_stale_if_error(Duration::from_secs(3600));
+        let service = with_cached(OriginServer::new([server_error()]), "max-age=60", 90)
+            .with_policy(policy.clone());
+        assert_eq!(service.get(URI).await?, "old");
+        assert!(service.store().get(URI).is_some());
+        assert_eq!(service.stats().stale_served, 1);
+
+        let service = with_cached(OriginServer::new([server_error()]), "max-age=60", 90);
+        let result = service.get(URI).await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+
+        let origin = OriginServer::new([server_error()]);
+        let service = with_cached(origin, "max-age=60, must-revalidate", 90).with_policy(policy);
+        assert!(service.get(URI).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_only_serves_cached_responses_when_offline() -> HttpResult<()> {
+        let policy = CachePolicy::new().with_offline(true);
+        let service = with_cached(OriginServer::default(), "no-cache", 86_400).with_policy(policy);
+        assert_eq!(service.get(URI).await?, "old");
+        let result = service.get("https://api.example.com/teams").await;
+        assert!(
+            matches!(result, Err(HttpError::NotCached(uri)) if uri == "https://api.example.com/teams")
+        );
+        assert!(service.inner().requests().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_computes_freshness_from_expires_headers() {
+        let now = SystemTime::now();
+        let response = HttpResponse::new(StatusCode::OK, "")
+            .with_header(header::DATE, httpdate::fmt_http_date(now).parse().unwrap())
+            .with_header(
+                header::EXPIRES,
+                httpdate::fmt_http_date(now + Duration::from_secs(120))
+                    .parse()
+                    .unwrap(),
+            );
+        let entry = CachedResponse::new(response, now);
+        assert_eq!(entry.freshness_lifetime(), Duration::from_secs(120));
+        assert!(entry.is_fresh(now + Duration::from_secs(60)));
+        assert!(!entry.is_fresh(now + Duration::from_secs(180)));
+    }
+
+    #[test]
+    fn it_includes_the_age_header_in_the_age() {
+        let now = SystemTime::now();
+        let response =
+            ok("", "max-age=60").with_header(header::AGE, HeaderValue::from_static("50"));
+        let entry = CachedResponse::new(response, now);
+        assert_eq!(
+            entry.age(now + Duration::from_secs(5)),
+            Duration::from_secs(55)
+        );
+        assert!(!entry.is_fresh(now + Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn it_clamps_huge_age_headers() {
+        let now = SystemTime::now();
+        let huge = ["18446744073709551615", "99999999999999999999999"];
+        for age in huge {
+            let response =
+                ok("", "max-age=60").with_header(header::AGE, HeaderValue::from_static(age));
+            let entry = CachedResponse::new(response, now - Duration::from_secs(10));
+            assert_eq!(entry.age(now), Duration::from_secs(MAX_DELTA_SECONDS + 10));
+            assert!(!entry.is_fresh(now));
+        }
+    }
+}
@@ -0,0 +1,398 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! A cache store that persists responses on disk.
+//!
+//! A [`DiskCache`] keeps each response in its own file in a directory,
+//! along with an index that records which file holds which URI, how large
+//! each file is, and when it was last used. Since the cache survives
+//! between runs of a program, command-line tools that use it start with a
+//! warm cache.
+//!
+//! The cache is limited by the total size of its responses. When it grows
+//! larger than its limit, the least recently used responses are removed.
+//!
+//! Caching is best effort: if a response cannot be read or written, it is
+//! treated as a miss rather than an error.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::service::HttpGetResponse;
+//! use hypertyper::service::cache::{CachingService, DiskCache};
+//!
+//! fn cached<S: HttpGetResponse>(service: S) -> std::io::Result<CachingService<S, DiskCache>> {
+//!     let store = DiskCache::open("/tmp/my-cli/http-cache", 50 * 1024 * 1024)?;
+//!     Ok(CachingService::with_store(service, store))
+//! }
+//! ```
+
+use crate::service::HttpResponse;
+use crate::service::cache::{CacheStore, CachedResponse};
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+const INDEX_FILE: &str = "index.json";
+
+/// A [`CacheStore`] that persists responses in a directory, evicting the
+/// least recently used responses when they take up too much space.
+///
+/// See the [module documentation](crate::service::cache::disk) for details.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Index {
+    clock: u64,
+    evictions: u64,
+    entries: HashMap<String, IndexEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct IndexEntry {
+    file: String,
+    size: u64,
+    last_used: u64,
+}
+
+/// The format of each response file.
+#[derive(Deserialize, Serialize)]
+struct StoredResponse {
+    key: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    stored_at_ms: u64,
+    #[serde(default)]
+    request_headers: Vec<(String, String)>,
+}
+
+impl StoredResponse {
+    fn new(key: &str, entry: &CachedResponse) -> Self {
+        let response = entry.response();
+        let stored_at = entry.stored_at().duration_since(UNIX_EPOCH);
+        Self {
+            key: key.to_string(),
+            status: response.status().as_u16(),
+            headers: to_pairs(response.headers()),
+            body: response.body().to_string(),
+            stored_at_ms: stored_at.unwrap_or_default().as_millis() as u64,
+            request_headers: to_pairs(entry.request_headers()),
+        }
+    }
+
+    fn into_entry(self) -> Option<CachedResponse> {
+        let status = StatusCode::from_u16(self.status).ok()?;
+        let mut response = HttpResponse::new(status, self.body);
+        *response.headers_mut() = from_pairs(self.headers)?;
+        let request_headers = from_pairs(self.request_headers)?;
+        let stored_at = UNIX_EPOCH + Duration::from_millis(self.stored_at_ms);
+        Some(CachedResponse::new(response, stored_at).with_request_headers(&request_headers))
+    }
+}
+
+fn to_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+fn from_pairs(pairs: Vec<(String, String)>) -> Option<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        let name = HeaderName::try_from(name).ok()?;
+        let value = HeaderValue::try_from(value).ok()?;
+        headers.append(name, value);
+    }
+    Some(headers)
+}
+
+impl DiskCache {
+    /// Opens the cache in `dir`, creating the directory if necessary, and
+    /// limits it to `max_bytes` of responses.
+    ///
+    /// If the index is missing or damaged, it is rebuilt from the response
+    /// files in `dir`.
+    pub fn open(dir: impl AsRef<Path>, max_bytes: u64) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let index = fs::read_to_string(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|index| serde_json::from_str(&index).ok());
+        let index = match index {
+            Some(index) => index,
+            None => rebuild_index(&dir)?,
+        };
+        let cache = Self {
+            dir,
+            max_bytes,
+            index: Mutex::new(index),
+        };
+        let mut index = cache.index.lock().unwrap();
+        cache.evict(&mut index);
+        cache.save(&index);
+        drop(index);
+        Ok(cache)
+    }
+
+    /// The directory that holds the cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The most space responses may take up, in bytes.
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// The number of responses in the cache.
+    pub fn len(&self) -> usize {
+        self.index.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The space taken up by responses in the cache, in bytes.
+    pub fn size(&self) -> u64 {
+        let index = self.index.lock().unwrap();
+        index.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// The number of responses that have been evicted to make room for
+    /// others since the cache was created.
+    pub fn evictions(&self) -> u64 {
+        self.index.lock().unwrap().evictions
+    }
+
+    /// Removes every response from the cache.
+    pub fn clear(&self) {
+        let mut index = self.index.lock().unwrap();
+        for (_, entry) in index.entries.drain() {
+            let _ = fs::remove_file(self.dir.join(entry.file));
+        }
+        self.save(&index);
+    }
+
+    fn evict(&self, index: &mut Index) {
+        let mut size: u64 = index.entries.values().map(|entry| entry.size).sum();
+        while size > self.max_bytes {
+            let oldest = index
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            let Some(entry) = oldest.and_then(|key| index.entries.remove(&key)) else {
+                break;
+            };
+            let _ = fs::remove_file(self.dir.join(&entry.file));
+            size -= entry.size;
+            index.evictions += 1;
+        }
+    }
+
+    fn save(&self, index: &Index) {
+        if let Ok(json) = serde_json::to_string(index) {
+            let _ = write_atomically(&self.dir.join(INDEX_FILE), &json);
+        }
+    }
+}
+
+impl CacheStore for DiskCache {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut index = self.index.lock().unwrap();
+        let file = self.dir.join(&index.entries.get(key)?.file);
+        let stored = fs::read_to_string(file)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoredResponse>(&json).ok())
+            .filter(|stored| stored.key == key)
+            .and_then(StoredResponse::into_entry);
+        let Some(entry) = stored else {
+            index.entries.remove(key);
+            self.save(&index);
+            return None;
+        };
+        index.clock += 1;
+        let clock = index.clock;
+        if let Some(indexed) = index.entries.get_mut(key) {
+            indexed.last_used = clock;
+        }
+        self.save(&index);
+        Some(entry)
+    }
+
+    fn put(&self, key: &str, entry: CachedResponse) {
+        let Ok(json) = serde_json::to_string(&StoredResponse::new(key, &entry)) else {
+            return;
+        };
+        let mut index = self.index.lock().unwrap();
+        if let Some(previous) = index.entries.remove(key) {
+            let _ = fs::remove_file(self.dir.join(previous.file));
+        }
+        let size = json.len() as u64;
+        let file = format!("{:016x}.json", fastrand::u64(..));
+        if size <= self.max_bytes && write_atomically(&self.dir.join(&file), &json).is_ok() {
+            index.clock += 1;
+            let last_used = index.clock;
+            let entry = IndexEntry {
+                file,
+                size,
+                last_used,
+            };
+            index.entries.insert(key.to_string(), entry);
+            self.evict(&mut index);
+        }
+        self.save(&index);
+    }
+
+    fn remove(&self, key: &str) {
+        let mut index = self.index.lock().unwrap();
+        if let Some(entry) = index.entries.remove(key) {
+            let _ = fs::remove_file(self.dir.join(entry.file));
+            self.save(&index);
+        }
+    }
+
+    fn entry_count(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(DiskCache::size(self))
+    }
+}
+
+/// Indexes the response files in `dir`, treating them all as equally old.
+fn rebuild_index(dir: &Path) -> io::Result<Index> {
+    let mut index = Index::default();
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if name == INDEX_FILE || !name.ends_with(".json") {
+            continue;
+        }
+        let Ok(json) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(stored) = serde_json::from_str::<StoredResponse>(&json) else {
+            continue;
+        };
+        let entry = IndexEntry {
+            file: name.to_string(),
+            size: json.len() as u64,
+            last_used: 0,
+        };
+        index.entries.insert(stored.key, entry);
+    }
+    Ok(index)
+}
+
+/// Writes `contents` to `path` without leaving a partially written file
+/// behind if the program is interrupted.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header;
+
+    fn entry(body: &str) -> CachedResponse {
+        let response = HttpResponse::new(StatusCode::OK, body)
+            .with_header(header::ETAG, HeaderValue::from_static("\"v1\""));
+        CachedResponse::new(response, UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    }
+
+    #[test]
+    fn it_persists_responses_between_runs() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        DiskCache::open(dir.path(), 1024)?.put("/users", entry("users"));
+
+        let cache = DiskCache::open(dir.path(), 1024)?;
+        let cached = cache.get("/users").expect("response should be cached");
+        assert_eq!(cached.response().body(), "users");
+        assert_eq!(cached.response().headers()[header::ETAG], "\"v1\"");
+        assert_eq!(cached.stored_at(), entry("").stored_at());
+        assert!(cache.get("/teams").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn it_persists_the_headers_responses_vary_on() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut request = HeaderMap::new();
+        request.insert(header::ACCEPT, HeaderValue::from_static("text/csv"));
+        let response = HttpResponse::new(StatusCode::OK, "a,b")
+            .with_header(header::VARY, HeaderValue::from_static("Accept"));
+        let entry = CachedResponse::new(response, UNIX_EPOCH).with_request_headers(&request);
+        DiskCache::open(dir.path(), 1024)?.put("/users", entry);
+
+        let cached = DiskCache::open(dir.path(), 1024)?.get("/users").unwrap();
+        assert!(cached.matches_request(&request));
+        assert!(!cached.matches_request(&HeaderMap::new()));
+        Ok(())
+    }
+
+    #[test]
+    fn it_evicts_the_least_recently_used_responses() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DiskCache::open(dir.path(), 1024)?;
+        cache.put("/a", entry("a"));
+        let max_bytes = cache.size() * 2;
+
+        let cache = DiskCache::open(dir.path(), max_bytes)?;
+        cache.put("/b", entry("b"));
+        assert!(cache.get("/a").is_some());
+        cache.put("/c", entry("c"));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.evictions(), 1);
+        assert!(cache.get("/b").is_none());
+        assert!(cache.size() <= max_bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rebuilds_a_missing_index() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DiskCache::open(dir.path(), 1024)?;
+        cache.put("/users", entry("users"));
+        cache.put("/teams", entry("teams"));
+        cache.remove("/teams");
+        fs::remove_file(dir.path().join(INDEX_FILE))?;
+
+        let cache = DiskCache::open(dir.path(), 1024)?;
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("/users").unwrap().response().body(), "users");
+        Ok(())
+    }
+
+    #[test]
+    fn it_treats_missing_files_as_misses() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DiskCache::open(dir.path(), 1024)?;
+        cache.put("/users", entry("users"));
+        cache.clear();
+        assert!(cache.get("/users").is_none());
+        assert!(cache.is_empty());
+        Ok(())
+    }
+}
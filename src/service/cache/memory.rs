@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! An in-memory cache store.
+
+use crate::service::cache::{CacheStore, CachedResponse};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// The number of responses a [`MemoryCache`] holds by default.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// A [`CacheStore`] that holds a limited number of responses in memory,
+/// evicting the least recently used response when it is full.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    entries: HashMap<String, (CachedResponse, u64)>,
+    // Keys ordered from least to most recently used.
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) -> Option<&CachedResponse> {
+        self.clock += 1;
+        let (entry, last_used) = self.entries.get_mut(key)?;
+        self.recency.remove(last_used);
+        self.recency.insert(self.clock, key.to_string());
+        *last_used = self.clock;
+        Some(entry)
+    }
+}
+
+impl MemoryCache {
+    /// Creates a cache that holds up to `capacity` responses.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::default(),
+        }
+    }
+
+    /// The maximum number of responses the cache holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of responses in the cache.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every response from the cache.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.recency.clear();
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut state = self.state.lock().unwrap();
+        state.touch(key).cloned()
+    }
+
+    fn put(&self, key: &str, entry: CachedResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let last_used = state.clock;
+        if let Some((_, previous)) = state.entries.insert(key.to_string(), (entry, last_used)) {
+            state.recency.remove(&previous);
+        }
+        state.recency.insert(last_used, key.to_string());
+        while state.entries.len() > self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some((_, last_used)) = state.entries.remove(key) {
+            state.recency.remove(&last_used);
+        }
+    }
+
+    fn entry_count(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn size(&self) -> Option<u64> {
+        let state = self.state.lock().unwrap();
+        let size: usize = state.entries.values().map(|(entry, _)| entry.size()).sum();
+        Some(size as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::HttpResponse;
+    use reqwest::StatusCode;
+    use std::time::SystemTime;
+
+    fn entry(body: &str) -> CachedResponse {
+        CachedResponse::new(HttpResponse::new(StatusCode::OK, body), SystemTime::now())
+    }
+
+    #[test]
+    fn it_evicts_the_least_recently_used_response() {
+        let cache = MemoryCache::new(2);
+        cache.put("/a", entry("a"));
+        cache.put("/b", entry("b"));
+        assert!(cache.get("/a").is_some());
+        cache.put("/c", entry("c"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("/a").is_some());
+        assert!(cache.get("/b").is_none());
+        assert!(cache.get("/c").is_some());
+    }
+
+    #[test]
+    fn it_replaces_and_removes_responses() {
+        let cache = MemoryCache::new(2);
+        cache.put("/a", entry("old"));
+        cache.put("/a", entry("new"));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("/a").unwrap().response().body(), "new");
+        cache.remove("/a");
+        assert!(cache.is_empty());
+    }
+}
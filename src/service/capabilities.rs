@@ -0,0 +1,397 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Introspection of composed services.
+//!
+//! A service built from several decorators, such as a [`RetryService`]
+//! around a [`CachingService`] around an [`HttpClient`], is opaque once it
+//! has been constructed, and its configuration is often assembled from
+//! environment variables and configuration files. When something goes
+//! wrong, the first question is usually how the client was actually set
+//! up.
+//!
+//! [`HttpCapabilities::capabilities()`] answers that question. It reports
+//! which [verbs](Verb) the composed service supports, which body formats are
+//! available, and which [middleware](Middleware) is active, from the
+//! outermost layer inwards, along with the policy each one was configured
+//! with. The report's [`Display`](fmt::Display) output is meant for startup
+//! logs and support diagnostics:
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::capabilities::{Capabilities, HttpCapabilities, Verb};
+//! use hypertyper::service::cache::CachingService;
+//! use hypertyper::service::retry::{RetryPolicy, RetryService};
+//! # use serde::{Serialize, de::DeserializeOwned};
+//!
+//! struct ApiService {
+//!     client: HttpClient,
+//! }
+//!
+//! # impl HttpGet for ApiService {
+//! #     async fn get<U: IntoUrl + Send>(&self, uri: U) -> HttpResult<String> {
+//! #         Ok(self.client.get(uri).send().await?.text().await?)
+//! #     }
+//! # }
+//! # impl HttpPost for ApiService {
+//! #     async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+//! #     where U: IntoUrl + Send, D: Serialize + Sync, R: DeserializeOwned {
+//! #         Ok(self.client.post(uri).json(data).send().await?.json().await?)
+//! #     }
+//! # }
+//! impl HttpCapabilities for ApiService {
+//!     fn capabilities(&self) -> Capabilities {
+//!         Capabilities::new([Verb::Get, Verb::Post])
+//!     }
+//! }
+//!
+//! let client = HttpClientFactory::with_user_agent("my-app v1.0").create();
+//! let service = RetryService::new(
+//!     CachingService::new(ApiService { client }),
+//!     RetryPolicy::default(),
+//! );
+//!
+//! let capabilities = service.capabilities();
+//! assert!(capabilities.supports(Verb::Get));
+//! assert!(!capabilities.supports(Verb::GetStream));
+//! let layers: Vec<_> = capabilities.middleware().iter().map(|m| m.name()).collect();
+//! assert_eq!(layers, ["retry", "cache"]);
+//! println!("{capabilities}");
+//! ```
+//!
+//! Services that make requests themselves, rather than wrapping another
+//! service, create a report of their own with [`Capabilities::new()`].
+//! Verbs are narrowed as decorators are added: a decorator only passes on
+//! the verbs that it implements itself, so the report reflects what the
+//! composed service can actually do.
+
+use crate::HttpClient;
+use std::fmt;
+
+#[cfg(doc)]
+use crate::service::{cache::CachingService, retry::RetryService};
+
+/// An HTTP service that can report how it is composed.
+///
+/// This is implemented for [`HttpClient`] and for every decorator in
+/// [`service`](crate::service) whose wrapped services implement it.
+pub trait HttpCapabilities {
+    /// Reports the verbs, formats, and middleware of this service.
+    fn capabilities(&self) -> Capabilities;
+}
+
+/// The service trait that provides a kind of request.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Verb {
+    /// [`HttpGet`](crate::service::HttpGet).
+    Get,
+
+    /// [`HttpPost`](crate::service::HttpPost).
+    Post,
+
+    /// [`HttpPostForm`](crate::service::HttpPostForm).
+    PostForm,
+
+    /// [`HttpPostStream`](crate::service::HttpPostStream).
+    PostStream,
+
+    /// [`HttpGetResponse`](crate::service::HttpGetResponse).
+    GetResponse,
+
+    /// [`HttpGetStream`](crate::service::HttpGetStream).
+    GetStream,
+
+    /// [`HttpGetRange`](crate::service::HttpGetRange).
+    GetRange,
+
+    /// [`HttpGetSse`](crate::service::HttpGetSse).
+    GetSse,
+
+    /// [`HttpBinary`](crate::service::HttpBinary).
+    Binary,
+
+    /// `HttpGrpcWeb`, which is only available with the **grpc-web**
+    /// feature.
+    GrpcWeb,
+
+    /// `HttpWebSocket`, which is only available with the **ws** feature.
+    WebSocket,
+}
+
+impl Verb {
+    /// The name of the trait that provides this verb.
+    pub fn trait_name(&self) -> &'static str {
+        match self {
+            Verb::Get => "HttpGet",
+            Verb::Post => "HttpPost",
+            Verb::PostForm => "HttpPostForm",
+            Verb::PostStream => "HttpPostStream",
+            Verb::GetResponse => "HttpGetResponse",
+            Verb::GetStream => "HttpGetStream",
+            Verb::GetRange => "HttpGetRange",
+            Verb::GetSse => "HttpGetSse",
+            Verb::Binary => "HttpBinary",
+            Verb::GrpcWeb => "HttpGrpcWeb",
+            Verb::WebSocket => "HttpWebSocket",
+        }
+    }
+}
+
+impl fmt::Display for Verb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.trait_name())
+    }
+}
+
+/// The body formats this build of Hypertyper can send or receive, which
+/// depends on the features it was built with.
+const FORMATS: &[&str] = &[
+    "json",
+    "ndjson",
+    "form",
+    "multipart",
+    "sse",
+    #[cfg(feature = "csv")]
+    "csv",
+    #[cfg(feature = "html")]
+    "html",
+    #[cfg(feature = "xml")]
+    "xml",
+    #[cfg(feature = "msgpack")]
+    "msgpack",
+    #[cfg(feature = "cbor")]
+    "cbor",
+    #[cfg(feature = "grpc-web")]
+    "grpc-web",
+];
+
+/// A report of how a service is composed.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Capabilities {
+    verbs: Vec<Verb>,
+    formats: Vec<&'static str>,
+    middleware: Vec<Middleware>,
+}
+
+impl Capabilities {
+    /// Creates a report for a service that supports `verbs` and has no
+    /// middleware.
+    pub fn new(verbs: impl IntoIterator<Item = Verb>) -> Self {
+        let mut verbs: Vec<_> = verbs.into_iter().collect();
+        verbs.sort();
+        verbs.dedup();
+        Self {
+            verbs,
+            formats: FORMATS.to_vec(),
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Removes the verbs that are not in `verbs`, for a decorator that
+    /// only implements those.
+    pub fn limit_verbs(mut self, verbs: &[Verb]) -> Self {
+        self.verbs.retain(|verb| verbs.contains(verb));
+        self
+    }
+
+    /// Adds `middleware` as the new outermost layer.
+    pub fn wrap(mut self, middleware: Middleware) -> Self {
+        self.middleware.insert(0, middleware);
+        self
+    }
+
+    /// The verbs the service supports.
+    pub fn verbs(&self) -> &[Verb] {
+        &self.verbs
+    }
+
+    /// Whether the service supports `verb`.
+    pub fn supports(&self, verb: Verb) -> bool {
+        self.verbs.contains(&verb)
+    }
+
+    /// The body formats available, such as `json` or `xml`.
+    pub fn formats(&self) -> &[&'static str] {
+        &self.formats
+    }
+
+    /// The active middleware, from the outermost layer inwards.
+    pub fn middleware(&self) -> &[Middleware] {
+        &self.middleware
+    }
+
+    /// The outermost middleware named `name`, if any.
+    pub fn find(&self, name: &str) -> Option<&Middleware> {
+        self.middleware.iter().find(|m| m.name == name)
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        let pad = " ".repeat(indent);
+        let verbs: Vec<_> = self.verbs.iter().map(Verb::trait_name).collect();
+        writeln!(f, "{pad}verbs: {}", verbs.join(", "))?;
+        writeln!(f, "{pad}formats: {}", self.formats.join(", "))?;
+        if self.middleware.is_empty() {
+            return writeln!(f, "{pad}middleware: none");
+        }
+        writeln!(f, "{pad}middleware:")?;
+        for middleware in &self.middleware {
+            write!(f, "{pad}  - {}", middleware.name)?;
+            if !middleware.config.is_empty() {
+                write!(f, ": {}", middleware.config)?;
+            }
+            writeln!(f)?;
+            for (label, branch) in &middleware.branches {
+                writeln!(f, "{pad}    {label}:")?;
+                branch.fmt_indented(f, indent + 6)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// A layer of middleware in a composed service.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Middleware {
+    name: &'static str,
+    config: String,
+    branches: Vec<(&'static str, Capabilities)>,
+}
+
+impl Middleware {
+    /// Creates a layer named `name` with no configuration.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            config: String::new(),
+            branches: Vec::new(),
+        }
+    }
+
+    /// Sets a description of the layer's configuration, usually the
+    /// `Debug` output of its policy.
+    pub fn with_config(mut self, config: impl Into<String>) -> Self {
+        self.config = config.into();
+        self
+    }
+
+    /// Adds another service that this layer sends requests to, such as the
+    /// secondary service of a fallback.
+    pub fn with_branch(mut self, label: &'static str, capabilities: Capabilities) -> Self {
+        self.branches.push((label, capabilities));
+        self
+    }
+
+    /// The name of the layer, such as `retry` or `cache`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// A description of the layer's configuration.
+    pub fn config(&self) -> &str {
+        &self.config
+    }
+
+    /// The other services that this layer sends requests to, besides the
+    /// service it wraps.
+    pub fn branches(&self) -> &[(&'static str, Capabilities)] {
+        &self.branches
+    }
+}
+
+impl HttpCapabilities for HttpClient {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new([
+            Verb::PostForm,
+            Verb::PostStream,
+            Verb::GetResponse,
+            Verb::GetStream,
+            Verb::GetRange,
+            Verb::GetSse,
+            Verb::Binary,
+            #[cfg(feature = "grpc-web")]
+            Verb::GrpcWeb,
+            #[cfg(feature = "ws")]
+            Verb::WebSocket,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpClientFactory;
+    use crate::service::fallback::{FallbackPolicy, FallbackService};
+    use crate::service::retry::{RetryPolicy, RetryService};
+    use crate::service::timeout::{TimeoutPolicy, TimeoutService};
+    use std::time::Duration;
+
+    struct Origin;
+
+    impl HttpCapabilities for Origin {
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::new([Verb::Post, Verb::Get, Verb::GetStream, Verb::Get])
+        }
+    }
+
+    #[test]
+    fn it_reports_the_verbs_of_a_client() {
+        let client = HttpClientFactory::with_user_agent("test").create();
+        let capabilities = client.capabilities();
+        assert!(capabilities.supports(Verb::GetStream));
+        assert!(capabilities.supports(Verb::Binary));
+        assert!(!capabilities.supports(Verb::Get));
+        assert!(capabilities.middleware().is_empty());
+        assert!(capabilities.formats().contains(&"json"));
+    }
+
+    #[test]
+    fn it_reports_middleware_from_the_outside_in() {
+        assert_eq!(
+            Origin.capabilities().verbs(),
+            [Verb::Get, Verb::Post, Verb::GetStream]
+        );
+
+        let policy = TimeoutPolicy::new(Duration::from_secs(5));
+        let service =
+            RetryService::new(TimeoutService::new(Origin, policy), RetryPolicy::default());
+        let capabilities = service.capabilities();
+        assert_eq!(capabilities.verbs(), [Verb::Get, Verb::Post]);
+        let names: Vec<_> = capabilities.middleware().iter().map(|m| m.name()).collect();
+        assert_eq!(names, ["retry", "timeout"]);
+        assert!(
+            capabilities
+                .find("timeout")
+                .unwrap()
+                .config()
+                .contains("5s")
+        );
+        assert!(capabilities.find("cache").is_none());
+    }
+
+    #[test]
+    fn it_reports_branches() {
+        let secondary = RetryService::new(Origin, RetryPolicy::default());
+        let service = FallbackService::new(Origin, secondary, FallbackPolicy::default());
+        let capabilities = service.capabilities();
+        let fallback = capabilities.find("fallback").unwrap();
+        let (label, secondary) = &fallback.branches()[0];
+        assert_eq!(*label, "secondary");
+        assert_eq!(secondary.middleware()[0].name(), "retry");
+
+        let report = capabilities.to_string();
+        assert!(report.starts_with("verbs: HttpGet, HttpPost\n"), "{report}");
+        assert!(report.contains("  - fallback: "), "{report}");
+        assert!(
+            report.contains("    secondary:\n      verbs: HttpGet, HttpPost\n"),
+            "{report}"
+        );
+    }
+}
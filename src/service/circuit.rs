@@ -0,0 +1,517 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Stop calling hosts that are failing.
+//!
+//! A [`CircuitBreakerService`] keeps track of the failure rate for each host
+//! it talks to over a sliding window of recent requests. Once enough of a
+//! host's recent requests have failed, the circuit for that host "opens",
+//! and further requests to it fail immediately with
+//! [`HttpError::CircuitOpen`] instead of being sent. After a cool-down
+//! period, the circuit becomes "half-open" and a single trial request is let
+//! through: if it succeeds, the circuit closes and requests flow normally
+//! again; if it fails, the circuit opens for another cool-down period.
+//!
+//! This protects both sides during an outage: the client stops waiting on
+//! requests that are doomed to fail, and the struggling server is not
+//! hammered while it recovers.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::circuit::{CircuitBreakerPolicy, CircuitBreakerService};
+//! use std::time::Duration;
+//!
+//! fn protected<S: HttpService>(service: S) -> CircuitBreakerService<S> {
+//!     let policy = CircuitBreakerPolicy::new()
+//!         .with_failure_threshold(10)
+//!         .with_failure_rate(0.25)
+//!         .with_window(Duration::from_secs(120))
+//!         .with_cool_down(Duration::from_secs(60));
+//!     CircuitBreakerService::new(service, policy)
+//! }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost, host_of};
+use crate::{HttpError, HttpResult};
+use reqwest::IntoUrl;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+type FailurePredicate = Arc<dyn Fn(&HttpError) -> bool + Send + Sync>;
+
+/// Determines when a [`CircuitBreakerService`] opens and closes its circuits.
+///
+/// A circuit opens once at least [`failure_threshold`] requests to a host
+/// have failed within the last [`window`], and those failures make up at
+/// least [`failure_rate`] of the requests sent to that host in the window.
+/// The threshold keeps a handful of requests from tripping the circuit on
+/// their own; the rate keeps a busy host with occasional failures from
+/// tripping it at all.
+///
+/// By default, a circuit opens after 5 failures that make up at least half
+/// of the requests in the last 60 seconds, and half-opens after 30 seconds.
+/// Only [retryable errors](HttpError::is_retryable), such
+/// as connection failures and HTTP 503 responses, count as failures; an
+/// HTTP 404, for example, means the server is working just fine.
+///
+/// [`failure_threshold`]: CircuitBreakerPolicy::with_failure_threshold
+/// [`window`]: CircuitBreakerPolicy::with_window
+/// [`failure_rate`]: CircuitBreakerPolicy::with_failure_rate
+#[derive(Clone)]
+pub struct CircuitBreakerPolicy {
+    failure_threshold: u32,
+    failure_rate: f64,
+    window: Duration,
+    cool_down: Duration,
+    trip_on: FailurePredicate,
+}
+
+impl CircuitBreakerPolicy {
+    /// Creates the default circuit breaker policy.
+    pub fn new() -> Self {
+        Self {
+            failure_threshold: 5,
+            failure_rate: 0.5,
+            window: Duration::from_secs(60),
+            cool_down: Duration::from_secs(30),
+            trip_on: Arc::new(HttpError::is_retryable),
+        }
+    }
+
+    /// Sets the number of failures within the window after which a circuit
+    /// may open.
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Sets the fraction of requests within the window, between 0 and 1,
+    /// that must fail before a circuit opens.
+    pub fn with_failure_rate(mut self, rate: f64) -> Self {
+        self.failure_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets how far back failures are counted.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Sets how long a circuit stays open before a trial request is allowed.
+    pub fn with_cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// Sets the predicate that decides whether an error counts as a failure.
+    ///
+    /// The default predicate is [`HttpError::is_retryable()`].
+    pub fn trip_on<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&HttpError) -> bool + Send + Sync + 'static,
+    {
+        self.trip_on = Arc::new(predicate);
+        self
+    }
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CircuitBreakerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreakerPolicy")
+            .field("failure_threshold", &self.failure_threshold)
+            .field("failure_rate", &self.failure_rate)
+            .field("window", &self.window)
+            .field("cool_down", &self.cool_down)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The state of the circuit for a single host.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CircuitState {
+    /// Requests are sent normally.
+    Closed,
+
+    /// Requests fail immediately without being sent.
+    Open,
+
+    /// A single trial request is allowed to determine whether the host has
+    /// recovered.
+    HalfOpen,
+}
+
+/// The number of slices a window is divided into. Outcomes are counted per
+/// slice so that a busy host does not need to remember every request.
+const WINDOW_SLICES: u32 = 10;
+
+#[derive(Debug)]
+struct Slice {
+    started: Instant,
+    requests: u32,
+    failures: u32,
+}
+
+/// The outcomes of recent requests to a host.
+#[derive(Debug, Default)]
+struct Window {
+    slices: VecDeque<Slice>,
+}
+
+impl Window {
+    /// Records an outcome and returns the number of requests and failures
+    /// within the last `span`.
+    fn record(&mut self, now: Instant, span: Duration, failed: bool) -> (u32, u32) {
+        while self
+            .slices
+            .front()
+            .is_some_and(|slice| slice.started + span <= now)
+        {
+            self.slices.pop_front();
+        }
+        let slice_span = span / WINDOW_SLICES;
+        let current = match self.slices.back_mut() {
+            Some(slice) if now < slice.started + slice_span => slice,
+            _ => {
+                self.slices.push_back(Slice {
+                    started: now,
+                    requests: 0,
+                    failures: 0,
+                });
+                self.slices.back_mut().unwrap()
+            }
+        };
+        current.requests += 1;
+        current.failures += u32::from(failed);
+        self.slices
+            .iter()
+            .fold((0, 0), |(requests, failures), slice| {
+                (requests + slice.requests, failures + slice.failures)
+            })
+    }
+}
+
+#[derive(Debug)]
+enum Circuit {
+    Closed { window: Window },
+    Open { until: Instant },
+    HalfOpen { probe: u64, probe_started: Instant },
+}
+
+/// Wraps an HTTP service and stops sending requests to hosts that keep
+/// failing.
+///
+/// See the [module documentation](crate::service::circuit) for details.
+#[derive(Debug)]
+pub struct CircuitBreakerService<S> {
+    inner: S,
+    policy: CircuitBreakerPolicy,
+    circuits: Mutex<HashMap<String, Circuit>>,
+    probes: AtomicU64,
+}
+
+impl<S> CircuitBreakerService<S> {
+    /// Wraps `inner` in a service that breaks circuits according to `policy`.
+    pub fn new(inner: S, policy: CircuitBreakerPolicy) -> Self {
+        let circuits = Mutex::new(HashMap::new());
+        Self {
+            inner,
+            policy,
+            circuits,
+            probes: AtomicU64::new(0),
+        }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The current state of the circuit for `host`.
+    pub fn state(&self, host: &str) -> CircuitState {
+        let circuits = self.circuits.lock().unwrap();
+        match circuits.get(host) {
+            None | Some(Circuit::Closed { .. }) => CircuitState::Closed,
+            Some(Circuit::Open { until }) if Instant::now() < *until => CircuitState::Open,
+            Some(Circuit::Open { .. } | Circuit::HalfOpen { .. }) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Decides whether a request to `host` may be sent, returning the ID of
+    /// the trial request if it is one.
+    fn acquire(&self, host: &str) -> HttpResult<Option<u64>> {
+        let now = Instant::now();
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits
+            .entry(host.to_string())
+            .or_insert_with(|| Circuit::Closed {
+                window: Window::default(),
+            });
+        match circuit {
+            Circuit::Closed { .. } => Ok(None),
+            Circuit::Open { until } if now < *until => {
+                Err(HttpError::CircuitOpen(host.to_string()))
+            }
+            // A trial request that has been outstanding for longer than the
+            // cool-down was most likely cancelled, so allow another one.
+            Circuit::HalfOpen { probe_started, .. }
+                if now < *probe_started + self.policy.cool_down =>
+            {
+                Err(HttpError::CircuitOpen(host.to_string()))
+            }
+            Circuit::Open { .. } | Circuit::HalfOpen { .. } => {
+                let probe = self.probes.fetch_add(1, Ordering::Relaxed);
+                *circuit = Circuit::HalfOpen {
+                    probe,
+                    probe_started: now,
+                };
+                Ok(Some(probe))
+            }
+        }
+    }
+
+    /// Records the result of a request to `host`.
+    ///
+    /// Only the trial request `probe` can move an open or half-open circuit;
+    /// results of requests that were sent before the circuit opened are
+    /// ignored.
+    fn record<T>(&self, host: &str, probe: Option<u64>, result: &HttpResult<T>) {
+        let failed = match result {
+            Err(HttpError::CircuitOpen(_)) => return,
+            Err(err) => (self.policy.trip_on)(err),
+            Ok(_) => false,
+        };
+        let mut circuits = self.circuits.lock().unwrap();
+        let Some(circuit) = circuits.get_mut(host) else {
+            return;
+        };
+        let now = Instant::now();
+        let tripped = match circuit {
+            Circuit::Closed { window } => {
+                let (requests, failures) = window.record(now, self.policy.window, failed);
+                failures >= self.policy.failure_threshold
+                    && f64::from(failures) >= self.policy.failure_rate * f64::from(requests)
+            }
+            Circuit::HalfOpen { probe: current, .. } if probe == Some(*current) => {
+                if !failed {
+                    *circuit = Circuit::Closed {
+                        window: Window::default(),
+                    };
+                }
+                failed
+            }
+            Circuit::Open { .. } | Circuit::HalfOpen { .. } => false,
+        };
+        if tripped {
+            *circuit = Circuit::Open {
+                until: now + self.policy.cool_down,
+            };
+        }
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for CircuitBreakerService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let host = host_of(uri.as_str());
+        let probe = self.acquire(&host)?;
+        let result = self.inner.get(uri).await;
+        self.record(&host, probe, &result);
+        result
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for CircuitBreakerService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let host = host_of(uri.as_str());
+        let probe = self.acquire(&host)?;
+        let result = self.inner.post(uri, auth, data).await;
+        self.record(&host, probe, &result);
+        result
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for CircuitBreakerService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("circuit-breaker").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    #[derive(Default)]
+    struct SwitchableService {
+        down: AtomicBool,
+        slow: AtomicBool,
+        calls: AtomicU32,
+    }
+
+    impl HttpGet for SwitchableService {
+        async fn get<U>(&self, _uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let down = self.down.load(Ordering::SeqCst);
+            if self.slow.swap(false, Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            if down {
+                Err(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE))
+            } else {
+                Ok(String::from("ok"))
+            }
+        }
+    }
+
+    const FLAKY: &str = "https://flaky.example.com/status";
+    const STABLE: &str = "https://stable.example.com/status";
+
+    fn breaker() -> CircuitBreakerService<SwitchableService> {
+        let policy = CircuitBreakerPolicy::new()
+            .with_failure_threshold(2)
+            .with_cool_down(Duration::from_secs(10));
+        CircuitBreakerService::new(SwitchableService::default(), policy)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_opens_after_repeated_failures() {
+        let service = breaker();
+        service.inner().down.store(true, Ordering::SeqCst);
+        assert!(service.get(FLAKY).await.is_err());
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Closed);
+        assert!(service.get(FLAKY).await.is_err());
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Open);
+
+        let result = service.get(FLAKY).await;
+        assert!(matches!(result, Err(HttpError::CircuitOpen(host)) if host == "flaky.example.com"));
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_forgets_failures_outside_the_window() {
+        let policy = CircuitBreakerPolicy::new()
+            .with_failure_threshold(2)
+            .with_window(Duration::from_secs(10));
+        let service = CircuitBreakerService::new(SwitchableService::default(), policy);
+        service.inner().down.store(true, Ordering::SeqCst);
+        let _ = service.get(FLAKY).await;
+        tokio::time::advance(Duration::from_secs(10)).await;
+        let _ = service.get(FLAKY).await;
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Closed);
+        let _ = service.get(FLAKY).await;
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Open);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_stays_closed_while_the_failure_rate_is_low() {
+        let policy = CircuitBreakerPolicy::new()
+            .with_failure_threshold(2)
+            .with_failure_rate(0.5);
+        let service = CircuitBreakerService::new(SwitchableService::default(), policy);
+        for _ in 0..3 {
+            assert!(service.get(FLAKY).await.is_ok());
+        }
+        service.inner().down.store(true, Ordering::SeqCst);
+        let _ = service.get(FLAKY).await;
+        let _ = service.get(FLAKY).await;
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Closed);
+        let _ = service.get(FLAKY).await;
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Open);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_tracks_each_host_separately() {
+        let service = breaker();
+        service.inner().down.store(true, Ordering::SeqCst);
+        let _ = service.get(FLAKY).await;
+        let _ = service.get(FLAKY).await;
+        service.inner().down.store(false, Ordering::SeqCst);
+        assert!(service.get(STABLE).await.is_ok());
+        assert_eq!(service.state("stable.example.com"), CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_closes_after_a_successful_trial_request() {
+        let service = breaker();
+        service.inner().down.store(true, Ordering::SeqCst);
+        let _ = service.get(FLAKY).await;
+        let _ = service.get(FLAKY).await;
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(service.state("flaky.example.com"), CircuitState::HalfOpen);
+        service.inner().down.store(false, Ordering::SeqCst);
+        assert!(service.get(FLAKY).await.is_ok());
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_reopens_after_a_failed_trial_request() {
+        let service = breaker();
+        service.inner().down.store(true, Ordering::SeqCst);
+        let _ = service.get(FLAKY).await;
+        let _ = service.get(FLAKY).await;
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(service.get(FLAKY).await.is_err());
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Open);
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_ignores_slow_results_that_finish_after_it_opens() {
+        let service = breaker();
+        service.inner().slow.store(true, Ordering::SeqCst);
+        let slow = service.get(FLAKY);
+        let trip = async {
+            service.inner().down.store(true, Ordering::SeqCst);
+            let _ = service.get(FLAKY).await;
+            let _ = service.get(FLAKY).await;
+            assert_eq!(service.state("flaky.example.com"), CircuitState::Open);
+        };
+        let (result, ()) = tokio::join!(slow, trip);
+        assert!(result.is_ok());
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Open);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_ignores_errors_that_do_not_indicate_failure() {
+        let policy = CircuitBreakerPolicy::new()
+            .with_failure_threshold(1)
+            .trip_on(|_| false);
+        let service = CircuitBreakerService::new(SwitchableService::default(), policy);
+        service.inner().down.store(true, Ordering::SeqCst);
+        let _ = service.get(FLAKY).await;
+        assert_eq!(service.state("flaky.example.com"), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,462 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Limits on the number of requests in flight at once.
+//!
+//! A [`ConcurrencyLimitService`] acts as a bulkhead: it caps how many
+//! requests can be waiting on the wrapped service at the same time, both
+//! overall and, optionally, for each host. Requests beyond the limit wait
+//! for an earlier request to finish, which provides back-pressure when
+//! fanning out many requests at once.
+//!
+//! Streamed responses hold their permits until the body has been read or
+//! dropped, so a limited service also bounds the number of concurrent
+//! [segmented download](crate::download::Downloader::download_segmented)
+//! connections.
+//!
+//! # Preemption
+//!
+//! Long-running streams can hold every slot for minutes at a time, leaving
+//! interactive requests stuck behind them. With
+//! [preemption](ConcurrencyLimitPolicy::with_preemption) enabled, streamed
+//! responses are treated as low priority: when a request finds every slot
+//! taken, it takes over the slot of the stream that started most recently,
+//! which then ends with [`HttpError::Preempted`] and closes its connection.
+//!
+//! A preempted stream is meant to be retried later, picking up where it
+//! left off. [Segmented downloads](crate::download::Downloader::download_segmented)
+//! do this automatically, since [`HttpError::Preempted`] is
+//! [retryable](HttpError::is_retryable). Callers that read streams
+//! themselves should be ready to do the same. The number of streams that
+//! have been preempted is available from
+//! [`preemptions()`](ConcurrencyLimitService::preemptions).
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::concurrency::{ConcurrencyLimitPolicy, ConcurrencyLimitService};
+//!
+//! fn bounded<S: HttpService>(service: S) -> ConcurrencyLimitService<S> {
+//!     // At most 32 requests in flight, and no more than 4 to any one host.
+//!     let policy = ConcurrencyLimitPolicy::new(32).with_max_per_host(4);
+//!     ConcurrencyLimitService::new(service, policy)
+//! }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{BodyStream, HttpGet, HttpGetRange, HttpGetStream, HttpPost, host_of};
+use crate::{HttpError, HttpResult};
+use futures_util::{StreamExt, stream};
+use reqwest::IntoUrl;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+/// Determines how many requests a [`ConcurrencyLimitService`] allows in
+/// flight at once.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimitPolicy {
+    max_in_flight: usize,
+    max_per_host: Option<usize>,
+    preemption: bool,
+}
+
+impl ConcurrencyLimitPolicy {
+    /// Creates a policy that allows at most `max_in_flight` requests at
+    /// once, regardless of host.
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight: max_in_flight.max(1),
+            max_per_host: None,
+            preemption: false,
+        }
+    }
+
+    /// Additionally limits the number of requests in flight to each host.
+    pub fn with_max_per_host(mut self, max_per_host: usize) -> Self {
+        self.max_per_host = Some(max_per_host.max(1));
+        self
+    }
+
+    /// The maximum number of requests in flight at once.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// The maximum number of requests in flight to each host, if limited.
+    pub fn max_per_host(&self) -> Option<usize> {
+        self.max_per_host
+    }
+
+    /// Lets requests take over the slots held by streamed responses when
+    /// the global limit has been reached.
+    ///
+    /// See the [module documentation](crate::service::concurrency#preemption)
+    /// for details.
+    pub fn with_preemption(mut self, preemption: bool) -> Self {
+        self.preemption = preemption;
+        self
+    }
+
+    /// Whether requests may take over the slots held by streamed responses.
+    pub fn preemption(&self) -> bool {
+        self.preemption
+    }
+}
+
+/// Wraps an HTTP service and limits how many requests can be in flight at
+/// once.
+///
+/// See the [module documentation](crate::service::concurrency) for details.
+#[derive(Debug)]
+pub struct ConcurrencyLimitService<S> {
+    inner: S,
+    policy: ConcurrencyLimitPolicy,
+    global: Arc<Semaphore>,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+    streams: Mutex<Vec<Weak<Preemptible>>>,
+    preemptions: AtomicU64,
+}
+
+/// Permits that are held for the duration of a request.
+struct Permits {
+    host: Option<OwnedSemaphorePermit>,
+    global: OwnedSemaphorePermit,
+}
+
+/// The global permit held by a streamed response that may be preempted.
+#[derive(Debug)]
+struct Preemptible {
+    global: Mutex<Option<OwnedSemaphorePermit>>,
+    preempted: Notify,
+}
+
+impl<S> ConcurrencyLimitService<S> {
+    /// Wraps `inner` in a service that limits requests according to `policy`.
+    pub fn new(inner: S, policy: ConcurrencyLimitPolicy) -> Self {
+        let global = Arc::new(Semaphore::new(policy.max_in_flight));
+        let hosts = Mutex::new(HashMap::new());
+        Self {
+            inner,
+            policy,
+            global,
+            hosts,
+            streams: Mutex::default(),
+            preemptions: AtomicU64::new(0),
+        }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy used to limit requests.
+    pub fn policy(&self) -> &ConcurrencyLimitPolicy {
+        &self.policy
+    }
+
+    /// The number of additional requests that could be sent right now
+    /// without waiting for the global limit.
+    pub fn available(&self) -> usize {
+        self.global.available_permits()
+    }
+
+    /// The number of streamed responses that have been preempted by other
+    /// requests since the service was created.
+    pub fn preemptions(&self) -> u64 {
+        self.preemptions.load(Ordering::Relaxed)
+    }
+
+    /// Waits until a request to `uri` may be sent, preempting a stream if
+    /// the policy allows it.
+    async fn acquire(&self, uri: &str) -> Permits {
+        self.acquire_with(uri, self.policy.preemption).await
+    }
+
+    /// Waits until a stream from `uri` may be requested. Streams never
+    /// preempt other streams.
+    async fn acquire_stream(&self, uri: &str) -> Permits {
+        self.acquire_with(uri, false).await
+    }
+
+    async fn acquire_with(&self, uri: &str, preempt: bool) -> Permits {
+        // Wait for the host first, so that a request queued behind a busy
+        // host does not hold one of the global slots while it waits.
+        let host = match self.host_semaphore(uri) {
+            Some(semaphore) => Some(acquire(semaphore).await),
+            None => None,
+        };
+        let global = match Arc::clone(&self.global).try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => match preempt.then(|| self.preempt()).flatten() {
+                Some(permit) => permit,
+                None => acquire(Arc::clone(&self.global)).await,
+            },
+        };
+        Permits { host, global }
+    }
+
+    /// Takes the global permit of the stream that started most recently,
+    /// which has probably made the least progress, and tells it to stop.
+    fn preempt(&self) -> Option<OwnedSemaphorePermit> {
+        let mut streams = self.streams.lock().unwrap();
+        streams.retain(|stream| stream.strong_count() > 0);
+        let permit = streams.iter().rev().find_map(|stream| {
+            let stream = stream.upgrade()?;
+            let permit = stream.global.lock().unwrap().take()?;
+            stream.preempted.notify_one();
+            Some(permit)
+        })?;
+        self.preemptions.fetch_add(1, Ordering::Relaxed);
+        Some(permit)
+    }
+
+    /// Holds `permits` until `body` has been read to the end or dropped,
+    /// letting other requests preempt it if the policy allows it.
+    fn hold(&self, body: BodyStream, permits: Permits) -> BodyStream {
+        if !self.policy.preemption {
+            return hold(body, permits);
+        }
+
+        let stream = Arc::new(Preemptible {
+            global: Mutex::new(Some(permits.global)),
+            preempted: Notify::new(),
+        });
+        self.streams.lock().unwrap().push(Arc::downgrade(&stream));
+
+        let content_length = body.content_length();
+        let state = Some((body, permits.host, stream));
+        let chunks = stream::unfold(state, |state| async move {
+            let (mut body, host, stream) = state?;
+            tokio::select! {
+                biased;
+                // Dropping the body closes the connection.
+                _ = stream.preempted.notified() => Some((Err(HttpError::Preempted), None)),
+                chunk = body.next() => {
+                    let chunk = chunk?;
+                    Some((chunk, Some((body, host, stream))))
+                }
+            }
+        });
+        BodyStream::new(chunks).with_content_length(content_length)
+    }
+
+    fn host_semaphore(&self, uri: &str) -> Option<Arc<Semaphore>> {
+        let max_per_host = self.policy.max_per_host?;
+        let mut hosts = self.hosts.lock().unwrap();
+        let semaphore = hosts
+            .entry(host_of(uri))
+            .or_insert_with(|| Arc::new(Semaphore::new(max_per_host)));
+        Some(Arc::clone(semaphore))
+    }
+}
+
+async fn acquire(semaphore: Arc<Semaphore>) -> OwnedSemaphorePermit {
+    semaphore
+        .acquire_owned()
+        .await
+        // The semaphores are never closed.
+        .expect("concurrency limit semaphore was closed")
+}
+
+impl<S: HttpGet + Sync> HttpGet for ConcurrencyLimitService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let _permits = self.acquire(uri.as_str()).await;
+        self.inner.get(uri).await
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for ConcurrencyLimitService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let _permits = self.acquire(uri.as_str()).await;
+        self.inner.post(uri, auth, data).await
+    }
+}
+
+impl<S: HttpGetStream + Sync> HttpGetStream for ConcurrencyLimitService<S> {
+    async fn get_stream<U>(&self, uri: U) -> HttpResult<BodyStream>
+    where
+        U: IntoUrl + Send,
+    {
+        let permits = self.acquire_stream(uri.as_str()).await;
+        let body = self.inner.get_stream(uri).await?;
+        Ok(self.hold(body, permits))
+    }
+}
+
+impl<S: HttpGetRange + Sync> HttpGetRange for ConcurrencyLimitService<S> {
+    async fn range_length<U>(&self, uri: U) -> HttpResult<Option<u64>>
+    where
+        U: IntoUrl + Send,
+    {
+        let _permits = self.acquire(uri.as_str()).await;
+        self.inner.range_length(uri).await
+    }
+
+    async fn get_range<U>(&self, uri: U, range: Range<u64>) -> HttpResult<BodyStream>
+    where
+        U: IntoUrl + Send,
+    {
+        let permits = self.acquire_stream(uri.as_str()).await;
+        let body = self.inner.get_range(uri, range).await?;
+        Ok(self.hold(body, permits))
+    }
+}
+
+/// Holds `permits` until `body` has been read to the end or dropped.
+fn hold(body: BodyStream, permits: Permits) -> BodyStream {
+    let content_length = body.content_length();
+    let chunks = body.map(move |chunk| {
+        let _ = &permits;
+        chunk
+    });
+    BodyStream::new(chunks).with_content_length(content_length)
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for ConcurrencyLimitService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post, Verb::GetStream, Verb::GetRange])
+            .wrap(Middleware::new("concurrency-limit").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Records the highest number of concurrent requests, overall and to
+    /// hosts named "a".
+    #[derive(Default)]
+    struct SlowService {
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+        in_flight_a: AtomicUsize,
+        peak_a: AtomicUsize,
+    }
+
+    impl HttpGet for SlowService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            let is_a = host_of(uri.as_str()) == "a.example.com";
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            if is_a {
+                let now = self.in_flight_a.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak_a.fetch_max(now, Ordering::SeqCst);
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if is_a {
+                self.in_flight_a.fetch_sub(1, Ordering::SeqCst);
+            }
+            Ok(String::from("ok"))
+        }
+    }
+
+    impl HttpGetStream for SlowService {
+        /// Sends one chunk, and then nothing more.
+        async fn get_stream<U>(&self, _uri: U) -> HttpResult<BodyStream>
+        where
+            U: IntoUrl + Send,
+        {
+            let chunk = Ok(bytes::Bytes::from_static(b"chunk"));
+            Ok(BodyStream::new(
+                stream::iter([chunk]).chain(stream::pending()),
+            ))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_limits_requests_in_flight() {
+        let policy = ConcurrencyLimitPolicy::new(3);
+        let service = ConcurrencyLimitService::new(SlowService::default(), policy);
+        let results = future::join_all((0..10).map(|_| service.get("/users"))).await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(service.inner().peak.load(Ordering::SeqCst), 3);
+        assert_eq!(service.available(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_limits_requests_to_each_host() {
+        let policy = ConcurrencyLimitPolicy::new(10).with_max_per_host(2);
+        let service = ConcurrencyLimitService::new(SlowService::default(), policy);
+        let uris = (0..12).map(|i| {
+            if i % 2 == 0 {
+                "https://a.example.com/"
+            } else {
+                "https://b.example.com/"
+            }
+        });
+        future::join_all(uris.map(|uri| service.get(uri))).await;
+        assert_eq!(service.inner().peak_a.load(Ordering::SeqCst), 2);
+        assert_eq!(service.inner().peak.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn it_holds_permits_until_streams_are_dropped() -> HttpResult<()> {
+        let policy = ConcurrencyLimitPolicy::new(1);
+        let service = ConcurrencyLimitService::new(SlowService::default(), policy);
+        let body = service.get_stream("/export").await?;
+        assert_eq!(service.available(), 0);
+        drop(body);
+        assert_eq!(service.available(), 1);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_preempts_streams_for_other_requests() -> HttpResult<()> {
+        let policy = ConcurrencyLimitPolicy::new(2).with_preemption(true);
+        let service = ConcurrencyLimitService::new(SlowService::default(), policy);
+        let mut first = service.get_stream("/export/1").await?;
+        let mut second = service.get_stream("/export/2").await?;
+        assert!(first.next().await.unwrap().is_ok());
+        assert_eq!(service.available(), 0);
+
+        assert_eq!(service.get("/users").await?, "ok");
+        assert_eq!(service.preemptions(), 1);
+        assert!(matches!(
+            second.next().await,
+            Some(Err(HttpError::Preempted))
+        ));
+        assert!(second.next().await.is_none());
+        assert_eq!(service.available(), 1);
+
+        // The other stream is still waiting for its next chunk.
+        let next = tokio::time::timeout(Duration::from_secs(60), first.next()).await;
+        assert!(next.is_err());
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_does_not_preempt_streams_by_default() -> HttpResult<()> {
+        let policy = ConcurrencyLimitPolicy::new(1);
+        let service = ConcurrencyLimitService::new(SlowService::default(), policy);
+        let _body = service.get_stream("/export").await?;
+        let result = tokio::time::timeout(Duration::from_secs(60), service.get("/users")).await;
+        assert!(result.is_err());
+        assert_eq!(service.preemptions(), 0);
+        Ok(())
+    }
+}
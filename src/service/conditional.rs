@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Conditional GET requests.
+//!
+//! A client that polls a resource can avoid downloading it again when it
+//! has not changed by sending the `ETag` or `Last-Modified` value of the
+//! last response it received. If the resource is unchanged, the server
+//! answers with a short HTTP 304 response.
+//!
+//! [`HttpGetResponse::get_if_none_match()`] and
+//! [`HttpGetResponse::get_if_modified_since()`] send such requests and
+//! return a [`Conditional`], without needing a full
+//! [caching service](crate::service::cache).
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetResponse;
+//! use hypertyper::service::conditional::Conditional;
+//!
+//! # async fn poll<S: HttpGetResponse + Sync>(service: S) -> HttpResult<()> {
+//! let mut etag = String::from("\"initial\"");
+//! loop {
+//!     match service.get_if_none_match("https://api.example.com/status", &etag).await? {
+//!         Conditional::NotModified => {}
+//!         Conditional::Modified { body, etag: new_etag, .. } => {
+//!             println!("{body}");
+//!             etag = new_etag.unwrap_or_default();
+//!         }
+//!     }
+//!     tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+//! }
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::service::HttpResponse;
+use reqwest::StatusCode;
+use reqwest::header::{self, HeaderMap, HeaderName};
+use std::time::SystemTime;
+
+#[cfg(doc)]
+use crate::service::HttpGetResponse;
+
+/// The result of a conditional GET request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Conditional {
+    /// The resource has not changed since the given validator.
+    NotModified,
+
+    /// The resource has changed.
+    Modified {
+        /// The new body of the resource.
+        body: String,
+
+        /// The new `ETag` of the resource, including its quotes, if the
+        /// server sent one.
+        etag: Option<String>,
+
+        /// When the resource was last modified, if the server said.
+        last_modified: Option<SystemTime>,
+    },
+}
+
+impl Conditional {
+    /// Interprets the response to a conditional GET request.
+    ///
+    /// Returns an error if the response is neither successful nor an
+    /// HTTP 304 response.
+    pub fn from_response(response: HttpResponse) -> HttpResult<Self> {
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Self::NotModified);
+        }
+        let response = response.error_for_status()?;
+        let etag = header_str(response.headers(), &header::ETAG).map(String::from);
+        let last_modified = header_str(response.headers(), &header::LAST_MODIFIED)
+            .and_then(|date| httpdate::parse_http_date(date).ok());
+        Ok(Self::Modified {
+            body: response.into_body(),
+            etag,
+            last_modified,
+        })
+    }
+
+    /// Whether the resource has changed.
+    pub fn is_modified(&self) -> bool {
+        matches!(self, Self::Modified { .. })
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::service::HttpGetResponse;
+    use reqwest::IntoUrl;
+    use reqwest::header::HeaderValue;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Serves a resource with a fixed ETag and modification time, and
+    /// records the headers of each request.
+    #[derive(Default)]
+    struct Resource {
+        requests: Mutex<Vec<HeaderMap>>,
+    }
+
+    const ETAG: &str = "\"v2\"";
+    const LAST_MODIFIED: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+    impl HttpGetResponse for Resource {
+        async fn get_response<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            self.requests.lock().unwrap().push(headers.clone());
+            let unchanged = headers
+                .get(header::IF_NONE_MATCH)
+                .is_some_and(|etag| etag == ETAG)
+                || headers
+                    .get(header::IF_MODIFIED_SINCE)
+                    .is_some_and(|date| date == LAST_MODIFIED);
+            if unchanged {
+                return Ok(HttpResponse::new(StatusCode::NOT_MODIFIED, ""));
+            }
+            Ok(HttpResponse::new(StatusCode::OK, "body")
+                .with_header(header::ETAG, HeaderValue::from_static(ETAG))
+                .with_header(
+                    header::LAST_MODIFIED,
+                    HeaderValue::from_static(LAST_MODIFIED),
+                ))
+        }
+    }
+
+    #[tokio::test]
+    async fn it_sends_if_none_match() -> HttpResult<()> {
+        let resource = Resource::default();
+        let result = resource.get_if_none_match("/status", "\"v1\"").await?;
+        let last_modified = httpdate::parse_http_date(LAST_MODIFIED).ok();
+        assert_eq!(
+            result,
+            Conditional::Modified {
+                body: String::from("body"),
+                etag: Some(String::from(ETAG)),
+                last_modified,
+            }
+        );
+        let result = resource.get_if_none_match("/status", ETAG).await?;
+        assert_eq!(result, Conditional::NotModified);
+        assert_eq!(
+            resource.requests.lock().unwrap()[0][header::IF_NONE_MATCH],
+            "\"v1\""
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_sends_if_modified_since() -> HttpResult<()> {
+        let resource = Resource::default();
+        let since = httpdate::parse_http_date(LAST_MODIFIED).unwrap();
+        let result = resource.get_if_modified_since("/status", since).await?;
+        assert!(!result.is_modified());
+
+        let earlier = since - Duration::from_secs(60);
+        let result = resource.get_if_modified_since("/status", earlier).await?;
+        assert!(result.is_modified());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_rejects_invalid_etags() {
+        let result = Resource::default()
+            .get_if_none_match("/status", "\"v1\"\n")
+            .await;
+        assert!(matches!(result, Err(HttpError::InvalidHeaderValue(_))));
+    }
+
+    #[test]
+    fn it_fails_on_unsuccessful_responses() {
+        let response = HttpResponse::new(StatusCode::NOT_FOUND, "");
+        let result = Conditional::from_response(response);
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+    }
+}
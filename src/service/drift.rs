@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Detection of drift between API responses and the types they are
+//! deserialized into.
+//!
+//! APIs change over time, and by default serde silently ignores fields it
+//! does not know about and fills in optional fields that are missing. A
+//! [`SchemaDriftService`] deserializes each response while keeping track of
+//! which fields the target type expected and which fields the response
+//! actually contained, and reports any differences, so clients learn about
+//! upstream changes before they become breaking changes.
+//!
+//! Reports name fields by their path in the response, such as
+//! `user.address.zip` or `items[].id`. By default, they are logged as
+//! warnings with [`tracing`], but they can also be sent anywhere else with
+//! [`SchemaDriftService::with_reporter()`].
+//!
+//! Detection is meant for development and testing, since it costs an extra
+//! pass over every response. It does not look inside enums.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::drift::SchemaDriftService;
+//!
+//! fn watched<S: HttpService>(service: S) -> SchemaDriftService<S> {
+//!     SchemaDriftService::new(service)
+//! }
+//! ```
+//!
+//! Responses that are fetched with [`HttpGet`] are not deserialized by the
+//! service, so check them with [`detect()`] instead.
+//!
+//! [`tracing`]: https://crates.io/crates/tracing
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use crate::decode;
+use crate::decode::schema::detect_value;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost};
+use reqwest::IntoUrl;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+pub use crate::decode::schema::{DriftReport, detect};
+
+type Reporter = Arc<dyn Fn(&str, &str, &DriftReport) + Send + Sync>;
+
+/// Wraps an HTTP service and reports differences between its responses and
+/// the types they are deserialized into.
+///
+/// See the [module documentation](crate::service::drift) for details.
+#[derive(Clone)]
+pub struct SchemaDriftService<S> {
+    inner: S,
+    reporter: Reporter,
+}
+
+impl<S> SchemaDriftService<S> {
+    /// Wraps `inner` in a service that logs schema drift as warnings.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            reporter: Arc::new(|uri, type_name, report| {
+                tracing::warn!(uri, type_name, "response schema drift: {report}");
+            }),
+        }
+    }
+
+    /// Sends drift reports to `reporter` instead of logging them.
+    ///
+    /// `reporter` is called with the request URI, the name of the type the
+    /// response was deserialized into, and the report, and only when the
+    /// report is not empty.
+    pub fn with_reporter<F>(mut self, reporter: F) -> Self
+    where
+        F: Fn(&str, &str, &DriftReport) + Send + Sync + 'static,
+    {
+        self.reporter = Arc::new(reporter);
+        self
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for SchemaDriftService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchemaDriftService")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for SchemaDriftService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        self.inner.get(uri).await
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for SchemaDriftService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str().to_string();
+        let value: Value = self.inner.post(uri.as_str(), auth, data).await?;
+        let (response, report) = match detect_value(value.clone()) {
+            Ok(result) => result,
+            // Decode the body again to get a more informative error.
+            Err(_) => return decode::json(&value.to_string()),
+        };
+        if !report.is_empty() {
+            (self.reporter)(&uri, std::any::type_name::<R>(), &report);
+        }
+        Ok(response)
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for SchemaDriftService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("schema-drift"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct User {
+        name: String,
+        email: Option<String>,
+    }
+
+    const USER: &str = r#"{"name": "Alice", "createdAt": "2026-01-01"}"#;
+
+    struct JsonService(&'static str);
+
+    impl HttpPost for JsonService {
+        async fn post<U, D, R>(
+            &self,
+            _uri: U,
+            _auth: &dyn Authenticator,
+            _data: &D,
+        ) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            Ok(serde_json::from_str(self.0)?)
+        }
+    }
+
+    #[tokio::test]
+    async fn it_reports_drift_in_post_responses() -> HttpResult<()> {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let service = SchemaDriftService::new(JsonService(USER)).with_reporter(
+            move |uri, type_name, report| {
+                let entry = (uri.to_string(), type_name.to_string(), report.clone());
+                sink.lock().unwrap().push(entry);
+            },
+        );
+        let user: User = service.post("/users", &Auth::new("key"), &()).await?;
+        assert_eq!(user.name, "Alice");
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].0, "/users");
+        assert!(reports[0].1.ends_with("User"));
+        assert!(
+            reports[0]
+                .2
+                .unknown_fields()
+                .any(|field| field == "createdAt")
+        );
+        Ok(())
+    }
+}
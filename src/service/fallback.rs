@@ -0,0 +1,356 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Fail over from one service to another.
+//!
+//! A [`FallbackService`] sends each request to a primary service and, if
+//! the primary appears to be down, sends it to a secondary service
+//! instead. This is useful for APIs with mirrored regional endpoints: the
+//! secondary service can be the same kind of service as the primary, with
+//! request URIs [rewritten](FallbackPolicy::with_secondary_origin) to point
+//! at the mirror.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::fallback::{FallbackPolicy, FallbackService};
+//!
+//! fn with_failover<S: HttpService>(primary: S, secondary: S) -> FallbackService<S, S> {
+//!     let policy = FallbackPolicy::new().with_secondary_origin("https://eu.api.example.com");
+//!     FallbackService::new(primary, secondary, policy)
+//! }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost, with_origin};
+use crate::{HttpError, HttpResult};
+use reqwest::{IntoUrl, Url};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::Arc;
+
+type FallbackPredicate = Arc<dyn Fn(&HttpError) -> bool + Send + Sync>;
+
+/// Determines when a [`FallbackService`] sends a request to its secondary
+/// service.
+///
+/// By default, GET requests fall back when the primary fails with a
+/// connection error, a timeout, an open [circuit](HttpError::CircuitOpen),
+/// or an HTTP 5xx response. Because the primary may already have processed
+/// a POST request that failed in some other way, POST requests only fall
+/// back when they were never sent at all, unless
+/// [`with_non_idempotent_fallback()`](FallbackPolicy::with_non_idempotent_fallback)
+/// is enabled.
+#[derive(Clone)]
+pub struct FallbackPolicy {
+    secondary_origin: Option<Url>,
+    fallback_non_idempotent: bool,
+    fall_back_on: FallbackPredicate,
+}
+
+impl FallbackPolicy {
+    /// Creates the default fallback policy.
+    pub fn new() -> Self {
+        Self {
+            secondary_origin: None,
+            fallback_non_idempotent: false,
+            fall_back_on: Arc::new(is_outage),
+        }
+    }
+
+    /// Sends requests to the secondary service with their scheme, host, and
+    /// port replaced by those of `origin`.
+    ///
+    /// # Panics
+    ///
+    /// If `origin` is not a valid absolute URL.
+    pub fn with_secondary_origin(mut self, origin: &str) -> Self {
+        let origin = Url::parse(origin).expect("secondary origin must be a valid URL");
+        self.secondary_origin = Some(origin);
+        self
+    }
+
+    /// Allows POST requests to fall back after any error accepted by the
+    /// predicate, even if the primary may have processed them.
+    pub fn with_non_idempotent_fallback(mut self, enabled: bool) -> Self {
+        self.fallback_non_idempotent = enabled;
+        self
+    }
+
+    /// Sets the predicate that decides whether an error from the primary
+    /// service should cause a fallback.
+    pub fn fall_back_on<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&HttpError) -> bool + Send + Sync + 'static,
+    {
+        self.fall_back_on = Arc::new(predicate);
+        self
+    }
+
+    /// Rewrites `uri` for the secondary service.
+    pub fn secondary_uri(&self, uri: &str) -> String {
+        match &self.secondary_origin {
+            Some(origin) => with_origin(uri, origin),
+            None => uri.to_string(),
+        }
+    }
+
+    fn should_fall_back(&self, err: &HttpError, idempotent: bool) -> bool {
+        if !idempotent && !self.fallback_non_idempotent && !was_not_sent(err) {
+            return false;
+        }
+        (self.fall_back_on)(err)
+    }
+}
+
+impl Default for FallbackPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for FallbackPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FallbackPolicy")
+            .field("secondary_origin", &self.secondary_origin)
+            .field("fallback_non_idempotent", &self.fallback_non_idempotent)
+            .finish_non_exhaustive()
+    }
+}
+
+fn is_outage(err: &HttpError) -> bool {
+    match err {
+        HttpError::Request(err) => err.is_connect() || err.is_timeout(),
+        HttpError::Io(err) => crate::is_connection_error(err),
+        HttpError::Timeout(_) | HttpError::CircuitOpen(_) => true,
+        err => err.status().is_some_and(|status| status.is_server_error()),
+    }
+}
+
+fn was_not_sent(err: &HttpError) -> bool {
+    match err {
+        HttpError::Request(err) => err.is_connect(),
+        HttpError::Io(err) => err.kind() == std::io::ErrorKind::ConnectionRefused,
+        HttpError::CircuitOpen(_) => true,
+        _ => false,
+    }
+}
+
+/// Sends requests to a primary service, falling back to a secondary service
+/// when the primary is down.
+///
+/// See the [module documentation](crate::service::fallback) for details.
+#[derive(Debug)]
+pub struct FallbackService<A, B> {
+    primary: A,
+    secondary: B,
+    policy: FallbackPolicy,
+}
+
+impl<A, B> FallbackService<A, B> {
+    /// Creates a service that sends requests to `primary`, falling back to
+    /// `secondary` according to `policy`.
+    pub fn new(primary: A, secondary: B, policy: FallbackPolicy) -> Self {
+        Self {
+            primary,
+            secondary,
+            policy,
+        }
+    }
+
+    /// The primary service.
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    /// The secondary service.
+    pub fn secondary(&self) -> &B {
+        &self.secondary
+    }
+
+    /// The policy that determines when to fall back.
+    pub fn policy(&self) -> &FallbackPolicy {
+        &self.policy
+    }
+}
+
+impl<A, B> HttpGet for FallbackService<A, B>
+where
+    A: HttpGet + Sync,
+    B: HttpGet + Sync,
+{
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        match self.primary.get(uri.as_str()).await {
+            Err(err) if self.policy.should_fall_back(&err, true) => {}
+            result => return result,
+        }
+        let uri = self.policy.secondary_uri(&uri);
+        self.secondary.get(uri).await
+    }
+}
+
+impl<A, B> HttpPost for FallbackService<A, B>
+where
+    A: HttpPost + Sync,
+    B: HttpPost + Sync,
+{
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str().to_string();
+        match self.primary.post(uri.as_str(), auth, data).await {
+            Err(err) if self.policy.should_fall_back(&err, false) => {}
+            result => return result,
+        }
+        let uri = self.policy.secondary_uri(&uri);
+        self.secondary.post(uri, auth, data).await
+    }
+}
+
+impl<A, B> HttpCapabilities for FallbackService<A, B>
+where
+    A: HttpCapabilities,
+    B: HttpCapabilities,
+{
+    fn capabilities(&self) -> Capabilities {
+        let secondary = self.secondary.capabilities();
+        let fallback = Middleware::new("fallback")
+            .with_config(format!("{:?}", self.policy))
+            .with_branch("secondary", secondary.clone());
+        self.primary
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .limit_verbs(secondary.verbs())
+            .wrap(fallback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use reqwest::StatusCode;
+    use std::sync::Mutex;
+
+    /// Fails with the given status, or succeeds if there is none, and
+    /// records the URIs it was asked for.
+    #[derive(Default)]
+    struct StubService {
+        status: Option<StatusCode>,
+        uris: Mutex<Vec<String>>,
+    }
+
+    impl StubService {
+        fn failing(status: StatusCode) -> Self {
+            let status = Some(status);
+            Self {
+                status,
+                ..Self::default()
+            }
+        }
+
+        fn result(&self, uri: &str) -> HttpResult<()> {
+            self.uris.lock().unwrap().push(uri.to_string());
+            match self.status {
+                Some(status) => Err(HttpError::Http(status)),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl HttpGet for StubService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            self.result(uri.as_str())?;
+            Ok(uri.as_str().to_string())
+        }
+    }
+
+    impl HttpPost for StubService {
+        async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, _data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            self.result(uri.as_str())?;
+            Ok(serde_json::from_str("null")?)
+        }
+    }
+
+    fn policy() -> FallbackPolicy {
+        FallbackPolicy::new().with_secondary_origin("https://eu.example.com:8443")
+    }
+
+    #[tokio::test]
+    async fn it_uses_the_primary_when_it_succeeds() -> HttpResult<()> {
+        let service =
+            FallbackService::new(StubService::default(), StubService::default(), policy());
+        let body = service.get("https://us.example.com/users").await?;
+        assert_eq!(body, "https://us.example.com/users");
+        assert!(service.secondary().uris.lock().unwrap().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_falls_back_on_server_errors() -> HttpResult<()> {
+        let primary = StubService::failing(StatusCode::BAD_GATEWAY);
+        let service = FallbackService::new(primary, StubService::default(), policy());
+        let body = service.get("https://us.example.com/users?page=2").await?;
+        assert_eq!(body, "https://eu.example.com:8443/users?page=2");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_does_not_fall_back_on_client_errors() {
+        let primary = StubService::failing(StatusCode::NOT_FOUND);
+        let service = FallbackService::new(primary, StubService::default(), policy());
+        let result = service.get("https://us.example.com/users").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+        assert!(service.secondary().uris.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_only_falls_back_on_post_when_allowed() -> HttpResult<()> {
+        let auth = Auth::new("key");
+        let primary = StubService::failing(StatusCode::INTERNAL_SERVER_ERROR);
+        let service = FallbackService::new(primary, StubService::default(), policy());
+        let result: HttpResult<()> = service
+            .post("https://us.example.com/users", &auth, &())
+            .await;
+        assert!(result.is_err());
+
+        let primary = StubService::failing(StatusCode::INTERNAL_SERVER_ERROR);
+        let policy = policy().with_non_idempotent_fallback(true);
+        let service = FallbackService::new(primary, StubService::default(), policy);
+        let _: () = service
+            .post("https://us.example.com/users", &auth, &())
+            .await?;
+        assert_eq!(service.secondary().uris.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_resolves_relative_uris_against_the_secondary_origin() {
+        assert_eq!(
+            policy().secondary_uri("/users"),
+            "https://eu.example.com:8443/users"
+        );
+        assert_eq!(FallbackPolicy::new().secondary_uri("/users"), "/users");
+    }
+}
@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Hedged requests for latency-sensitive reads.
+//!
+//! A [`HedgedService`] sends a GET request and, if no response has arrived
+//! after a short delay, sends a second, identical request, either to the
+//! same endpoint or to an [alternate one](HedgePolicy::with_hedge_origin).
+//! Whichever request succeeds first wins, and the other is cancelled. This
+//! trims the long tail of response times caused by the occasional slow
+//! server, at the cost of some extra requests.
+//!
+//! Only GET requests are hedged, because sending a POST request twice may
+//! have side effects. POST requests are passed straight through to the
+//! wrapped service.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::hedge::{HedgePolicy, HedgedService};
+//! use std::time::Duration;
+//!
+//! fn hedged<S: HttpService>(service: S) -> HedgedService<S> {
+//!     // Roughly the 95th percentile response time of the API.
+//!     let policy = HedgePolicy::new(Duration::from_millis(200));
+//!     HedgedService::new(service, policy)
+//! }
+//! ```
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost, with_origin};
+use reqwest::{IntoUrl, Url};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::pin::pin;
+use std::time::Duration;
+
+/// Determines when and where a [`HedgedService`] sends a second request.
+#[derive(Clone, Debug)]
+pub struct HedgePolicy {
+    delay: Duration,
+    hedge_origin: Option<Url>,
+}
+
+impl HedgePolicy {
+    /// Creates a policy that sends a second request to the same endpoint if
+    /// the first has not completed after `delay`.
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            hedge_origin: None,
+        }
+    }
+
+    /// Sends the second request with its scheme, host, and port replaced by
+    /// those of `origin`.
+    ///
+    /// # Panics
+    ///
+    /// If `origin` is not a valid absolute URL.
+    pub fn with_hedge_origin(mut self, origin: &str) -> Self {
+        let origin = Url::parse(origin).expect("hedge origin must be a valid URL");
+        self.hedge_origin = Some(origin);
+        self
+    }
+
+    /// How long to wait for the first request before sending the second.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Rewrites `uri` for the second request.
+    pub fn hedge_uri(&self, uri: &str) -> String {
+        match &self.hedge_origin {
+            Some(origin) => with_origin(uri, origin),
+            None => uri.to_string(),
+        }
+    }
+}
+
+/// Wraps an HTTP service and hedges slow GET requests.
+///
+/// See the [module documentation](crate::service::hedge) for details.
+#[derive(Debug)]
+pub struct HedgedService<S> {
+    inner: S,
+    policy: HedgePolicy,
+}
+
+impl<S> HedgedService<S> {
+    /// Wraps `inner` in a service that hedges requests according to
+    /// `policy`.
+    pub fn new(inner: S, policy: HedgePolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy used to hedge requests.
+    pub fn policy(&self) -> &HedgePolicy {
+        &self.policy
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for HedgedService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        let hedge_uri = self.policy.hedge_uri(&uri);
+
+        let mut primary = pin!(self.inner.get(uri.as_str()));
+        tokio::select! {
+            result = &mut primary => return result,
+            _ = tokio::time::sleep(self.policy.delay) => {}
+        }
+
+        // If the first request to finish fails, the other one may still
+        // succeed, so wait for it before giving up.
+        let mut hedge = pin!(self.inner.get(hedge_uri.as_str()));
+        tokio::select! {
+            result = &mut primary => match result {
+                Ok(body) => Ok(body),
+                Err(_) => hedge.await,
+            },
+            result = &mut hedge => match result {
+                Ok(body) => Ok(body),
+                Err(_) => primary.await,
+            },
+        }
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for HedgedService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        self.inner.post(uri, auth, data).await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for HedgedService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("hedge").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use reqwest::StatusCode;
+    use std::sync::Mutex;
+    use tokio::time::Instant;
+
+    /// Responds to each request after the next scripted delay, failing if
+    /// the delay is paired with `false`.
+    struct ScriptedService {
+        script: Mutex<Vec<(u64, bool)>>,
+        uris: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedService {
+        fn new(script: &[(u64, bool)]) -> Self {
+            Self {
+                script: Mutex::new(script.iter().rev().copied().collect()),
+                uris: Mutex::default(),
+            }
+        }
+    }
+
+    impl HttpGet for ScriptedService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            let index = {
+                let mut uris = self.uris.lock().unwrap();
+                uris.push(uri.as_str().to_string());
+                uris.len()
+            };
+            let (millis, ok) = self.script.lock().unwrap().pop().unwrap();
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+            if ok {
+                Ok(format!("response {index}"))
+            } else {
+                Err(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE))
+            }
+        }
+    }
+
+    fn hedged(script: &[(u64, bool)]) -> HedgedService<ScriptedService> {
+        let policy = HedgePolicy::new(Duration::from_millis(100));
+        HedgedService::new(ScriptedService::new(script), policy)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_does_not_hedge_fast_requests() -> HttpResult<()> {
+        let service = hedged(&[(50, true)]);
+        assert_eq!(service.get("/users").await?, "response 1");
+        assert_eq!(service.inner().uris.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_returns_the_hedge_when_it_finishes_first() -> HttpResult<()> {
+        let start = Instant::now();
+        let service = hedged(&[(1000, true), (50, true)]);
+        assert_eq!(service.get("/users").await?, "response 2");
+        assert_eq!(start.elapsed(), Duration::from_millis(150));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_returns_the_primary_when_it_finishes_first() -> HttpResult<()> {
+        let service = hedged(&[(120, true), (1000, true)]);
+        assert_eq!(service.get("/users").await?, "response 1");
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_waits_for_the_other_request_when_one_fails() -> HttpResult<()> {
+        let service = hedged(&[(500, true), (10, false)]);
+        assert_eq!(service.get("/users").await?, "response 1");
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_sends_hedges_to_the_alternate_origin() -> HttpResult<()> {
+        let policy = HedgePolicy::new(Duration::from_millis(100))
+            .with_hedge_origin("https://replica.example.com");
+        let service = HedgedService::new(ScriptedService::new(&[(1000, true), (10, true)]), policy);
+        service.get("https://primary.example.com/users").await?;
+        let uris = service.inner().uris.lock().unwrap();
+        assert_eq!(uris[1], "https://replica.example.com/users");
+        Ok(())
+    }
+}
@@ -0,0 +1,577 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! A write-ahead journal of mutating requests.
+//!
+//! Data-sync tools need to know that every change they made was delivered
+//! at least once, even if the program crashed, was killed, or lost its
+//! network connection halfway through. A [`JournaledService`] writes each
+//! POST request to a [`Journal`] on disk before sending it, and only
+//! removes it once the server has given a definitive answer. Requests that
+//! are still in the journal when the program starts again can be
+//! [inspected](Journal::entries) and [replayed](JournaledService::replay).
+//!
+//! Each request is sent with an `Idempotency-Key` header, which stays the
+//! same when it is replayed, so that servers that support idempotency keys
+//! can recognize and discard duplicates. A key that is already in the
+//! request's headers is used as is.
+//!
+//! A request is removed from the journal when it succeeds, or when it
+//! fails with a client error other than `408 Request Timeout` or `429 Too
+//! Many Requests`, since sending it again would fail the same way. Requests
+//! that fail with a server error, or that never receive a response, stay in
+//! the journal.
+//!
+//! Sensitive headers, such as `Authorization`, are not written to the
+//! journal. Wrap the service that adds credentials, such as an
+//! [`AuthService`](crate::service::auth::AuthService), in the
+//! `JournaledService` so that replayed requests are authenticated again.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::HttpResult;
+//! use hypertyper::service::HttpPostStream;
+//! use hypertyper::service::journal::{Journal, JournaledService};
+//!
+//! # async fn run<S: HttpPostStream + Sync>(service: S) -> HttpResult<()> {
+//! let journal = Journal::open("/var/lib/my-sync/journal")?;
+//! let service = JournaledService::new(service, journal);
+//!
+//! // Deliver anything left over from the last run before making new requests.
+//! for (entry, result) in service.replay().await? {
+//!     println!("replayed {} {}: {:?}", entry.method(), entry.uri(), result.map(|r| r.status()));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpGetResponse, HttpPostStream, HttpResponse};
+use crate::upload::UploadBody;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{IntoUrl, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The header that carries the idempotency key of a journaled request.
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// A request recorded in a [`Journal`].
+#[derive(Clone, Debug)]
+pub struct JournalEntry {
+    sequence: u64,
+    method: Method,
+    uri: String,
+    headers: HeaderMap,
+    body: Bytes,
+    recorded_at: SystemTime,
+}
+
+impl JournalEntry {
+    /// The position of the entry in the journal; entries recorded later
+    /// have higher sequence numbers.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// The idempotency key sent with the request.
+    pub fn idempotency_key(&self) -> &str {
+        self.headers
+            .get(IDEMPOTENCY_KEY)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    }
+
+    /// The method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The URI the request was sent to.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// The headers of the request, without sensitive headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The body of the request.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// When the request was first recorded.
+    pub fn recorded_at(&self) -> SystemTime {
+        self.recorded_at
+    }
+}
+
+/// The format of each entry file.
+#[derive(Deserialize, Serialize)]
+struct StoredEntry {
+    sequence: u64,
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    recorded_at_ms: u64,
+}
+
+impl StoredEntry {
+    fn new(entry: &JournalEntry) -> Self {
+        let headers = entry
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let recorded_at = entry.recorded_at.duration_since(UNIX_EPOCH);
+        Self {
+            sequence: entry.sequence,
+            method: entry.method.to_string(),
+            uri: entry.uri.clone(),
+            headers,
+            body: STANDARD.encode(&entry.body),
+            recorded_at_ms: recorded_at.unwrap_or_default().as_millis() as u64,
+        }
+    }
+
+    fn into_entry(self) -> Option<JournalEntry> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers {
+            let name = HeaderName::try_from(name).ok()?;
+            let value = HeaderValue::try_from(value).ok()?;
+            headers.append(name, value);
+        }
+        Some(JournalEntry {
+            sequence: self.sequence,
+            method: Method::from_bytes(self.method.as_bytes()).ok()?,
+            uri: self.uri,
+            headers,
+            body: Bytes::from(STANDARD.decode(self.body).ok()?),
+            recorded_at: UNIX_EPOCH + Duration::from_millis(self.recorded_at_ms),
+        })
+    }
+}
+
+/// A directory of requests that have not yet been delivered.
+///
+/// Each entry is kept in its own file, which is written in full and
+/// flushed to disk before the request is sent.
+///
+/// See the [module documentation](crate::service::journal) for details.
+#[derive(Debug)]
+pub struct Journal {
+    dir: PathBuf,
+    next_sequence: AtomicU64,
+}
+
+impl Journal {
+    /// Opens the journal in `dir`, creating the directory if necessary.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let journal = Self {
+            dir,
+            next_sequence: AtomicU64::new(0),
+        };
+        let next = journal
+            .entries()?
+            .last()
+            .map_or(0, |entry| entry.sequence + 1);
+        journal.next_sequence.store(next, Ordering::Relaxed);
+        Ok(journal)
+    }
+
+    /// The directory the journal is kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The requests in the journal, in the order in which they were
+    /// recorded.
+    ///
+    /// Files in the journal's directory that are not valid entries are
+    /// ignored.
+    pub fn entries(&self) -> io::Result<Vec<JournalEntry>> {
+        let mut entries = Vec::new();
+        for file in fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let entry = fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<StoredEntry>(&bytes).ok())
+                .and_then(StoredEntry::into_entry);
+            entries.extend(entry);
+        }
+        entries.sort_by_key(|entry| entry.sequence);
+        Ok(entries)
+    }
+
+    /// True if there are no requests in the journal.
+    pub fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.entries()?.is_empty())
+    }
+
+    /// Removes `entry` from the journal, so that it is not replayed.
+    pub fn remove(&self, entry: &JournalEntry) -> io::Result<()> {
+        match fs::remove_file(self.path(entry.sequence)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records a request, returning its entry.
+    fn record(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> io::Result<JournalEntry> {
+        let headers = headers
+            .iter()
+            .filter(|(name, value)| !value.is_sensitive() && !is_sensitive(name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let entry = JournalEntry {
+            sequence: self.next_sequence.fetch_add(1, Ordering::Relaxed),
+            method,
+            uri: uri.to_string(),
+            headers,
+            body,
+            recorded_at: SystemTime::now(),
+        };
+
+        let path = self.path(entry.sequence);
+        let temp = path.with_extension("tmp");
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(&serde_json::to_vec(&StoredEntry::new(&entry))?)?;
+        file.sync_all()?;
+        fs::rename(temp, path)?;
+        Ok(entry)
+    }
+
+    fn path(&self, sequence: u64) -> PathBuf {
+        self.dir.join(format!("{sequence:020}.json"))
+    }
+}
+
+fn is_sensitive(name: &HeaderName) -> bool {
+    [
+        header::AUTHORIZATION,
+        header::PROXY_AUTHORIZATION,
+        header::COOKIE,
+    ]
+    .contains(name)
+}
+
+/// True if sending a request again could not change the outcome of
+/// `result`.
+fn is_settled(result: &HttpResult<HttpResponse>) -> bool {
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            !(status.is_server_error()
+                || status == StatusCode::REQUEST_TIMEOUT
+                || status == StatusCode::TOO_MANY_REQUESTS)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Wraps an HTTP service and journals POST requests until they are
+/// delivered.
+///
+/// GET requests are passed through without being journaled.
+///
+/// See the [module documentation](crate::service::journal) for details.
+#[derive(Debug)]
+pub struct JournaledService<S> {
+    inner: S,
+    journal: Journal,
+}
+
+impl<S> JournaledService<S> {
+    /// Wraps `inner` in a service that records POST requests in `journal`.
+    pub fn new(inner: S, journal: Journal) -> Self {
+        Self { inner, journal }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The journal that requests are recorded in.
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+}
+
+impl<S: HttpPostStream + Sync> JournaledService<S> {
+    /// Sends the requests in the journal again, in the order in which they
+    /// were recorded, and returns the outcome of each one.
+    ///
+    /// Replaying stops at the first request that is not settled, such as
+    /// one that fails with a server error, so that later requests are not
+    /// delivered before earlier ones; that request and those after it stay
+    /// in the journal for the next attempt.
+    ///
+    /// This should be called before any new requests are made through the
+    /// service.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal cannot be read or updated.
+    pub async fn replay(&self) -> HttpResult<Vec<(JournalEntry, HttpResult<HttpResponse>)>> {
+        let mut outcomes = Vec::new();
+        for entry in self.journal.entries()? {
+            let body = UploadBody::from_bytes(entry.body.clone());
+            let result = self
+                .inner
+                .post_stream(&entry.uri, &entry.headers, body)
+                .await;
+            let settled = is_settled(&result);
+            if settled {
+                self.journal.remove(&entry)?;
+            }
+            outcomes.push((entry, result));
+            if !settled {
+                break;
+            }
+        }
+        Ok(outcomes)
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for JournaledService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        self.inner.get(uri).await
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGetResponse for JournaledService<S> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.inner.get_response(uri, headers).await
+    }
+}
+
+impl<S: HttpPostStream + Sync> HttpPostStream for JournaledService<S> {
+    async fn post_stream<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: UploadBody,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let body = body.into_bytes().await?;
+        let mut headers = headers.clone();
+        if !headers.contains_key(IDEMPOTENCY_KEY) {
+            let key = format!("{:016x}{:016x}", fastrand::u64(..), fastrand::u64(..));
+            headers.insert(IDEMPOTENCY_KEY, HeaderValue::try_from(key)?);
+        }
+        let entry = self
+            .journal
+            .record(Method::POST, uri.as_str(), &headers, body.clone())?;
+
+        let result = self
+            .inner
+            .post_stream(uri, &headers, UploadBody::from_bytes(body))
+            .await;
+        if is_settled(&result) {
+            // If the entry cannot be removed, the request is delivered
+            // again on replay, which is allowed by at-least-once delivery.
+            let _ = self.journal.remove(&entry);
+        }
+        result
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for JournaledService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::GetResponse, Verb::PostStream])
+            .wrap(Middleware::new("journal").with_config(self.journal.dir.display().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use std::sync::Mutex;
+
+    /// Answers POST requests with the next status in a list, and records
+    /// each request's idempotency key and body.
+    struct SyncServer {
+        statuses: Mutex<Vec<Option<StatusCode>>>,
+        received: Mutex<Vec<(String, HeaderMap, Bytes)>>,
+    }
+
+    impl SyncServer {
+        /// `None` statuses fail as if the connection had dropped.
+        fn new(statuses: impl IntoIterator<Item = Option<StatusCode>>) -> Self {
+            let mut statuses: Vec<_> = statuses.into_iter().collect();
+            statuses.reverse();
+            Self {
+                statuses: Mutex::new(statuses),
+                received: Mutex::default(),
+            }
+        }
+
+        fn received(&self) -> Vec<(String, HeaderMap, Bytes)> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpPostStream for SyncServer {
+        async fn post_stream<U>(
+            &self,
+            uri: U,
+            headers: &HeaderMap,
+            body: UploadBody,
+        ) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            let body = body.into_bytes().await?;
+            let uri = uri.as_str().to_string();
+            self.received
+                .lock()
+                .unwrap()
+                .push((uri, headers.clone(), body));
+            match self.statuses.lock().unwrap().pop().flatten() {
+                Some(status) => Ok(HttpResponse::new(status, "")),
+                None => Err(HttpError::Cancelled),
+            }
+        }
+    }
+
+    async fn post(
+        service: &JournaledService<SyncServer>,
+        body: &'static str,
+    ) -> HttpResult<StatusCode> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer key"),
+        );
+        let response = service
+            .post_stream(
+                "https://api.example.com/items",
+                &headers,
+                UploadBody::from_bytes(body),
+            )
+            .await?;
+        Ok(response.status())
+    }
+
+    #[tokio::test]
+    async fn it_removes_delivered_requests() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let server = SyncServer::new([Some(StatusCode::CREATED), Some(StatusCode::BAD_REQUEST)]);
+        let service = JournaledService::new(server, Journal::open(dir.path())?);
+        assert_eq!(post(&service, "a").await?, StatusCode::CREATED);
+        assert_eq!(post(&service, "b").await?, StatusCode::BAD_REQUEST);
+        assert!(service.journal().is_empty()?);
+
+        let received = service.inner().received();
+        let (_, headers, _) = &received[0];
+        assert_eq!(headers[IDEMPOTENCY_KEY].len(), 32);
+        assert_eq!(headers[header::AUTHORIZATION], "Bearer key");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_keeps_undelivered_requests_for_replay() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let server = SyncServer::new([None, Some(StatusCode::SERVICE_UNAVAILABLE)]);
+        let service = JournaledService::new(server, Journal::open(dir.path())?);
+        assert!(post(&service, "first").await.is_err());
+        assert_eq!(
+            post(&service, "second").await?,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        let sent = service.inner().received();
+
+        // Start again, as if after a crash.
+        let journal = Journal::open(dir.path())?;
+        let entries = journal.entries()?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].body(), "first");
+        assert_eq!(entries[0].method(), Method::POST);
+        assert_eq!(entries[0].uri(), "https://api.example.com/items");
+        assert!(!entries[0].headers().contains_key(header::AUTHORIZATION));
+
+        let server = SyncServer::new([Some(StatusCode::OK), Some(StatusCode::OK)]);
+        let service = JournaledService::new(server, journal);
+        let outcomes = service.replay().await?;
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(|(_, result)| result.is_ok()));
+        assert!(service.journal().is_empty()?);
+
+        let replayed = service.inner().received();
+        assert_eq!(replayed[0].1[IDEMPOTENCY_KEY], sent[0].1[IDEMPOTENCY_KEY]);
+        assert_eq!(replayed[1].2, "second");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_stops_replaying_at_the_first_undelivered_request() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let server = SyncServer::new([None, None, None]);
+        let service = JournaledService::new(server, Journal::open(dir.path())?);
+        for body in ["a", "b", "c"] {
+            assert!(post(&service, body).await.is_err());
+        }
+
+        let server = SyncServer::new([Some(StatusCode::OK), Some(StatusCode::BAD_GATEWAY)]);
+        let service = JournaledService::new(server, Journal::open(dir.path())?);
+        let outcomes = service.replay().await?;
+        assert_eq!(outcomes.len(), 2);
+        let remaining: Vec<_> = service
+            .journal()
+            .entries()?
+            .iter()
+            .map(|entry| entry.body().clone())
+            .collect();
+        assert_eq!(remaining, ["b", "c"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_keeps_existing_idempotency_keys() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let service = JournaledService::new(SyncServer::new([None]), Journal::open(dir.path())?);
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_KEY, HeaderValue::from_static("order-17"));
+        let _ = service
+            .post_stream("/orders", &headers, UploadBody::from_bytes("{}"))
+            .await;
+        let entries = service.journal().entries()?;
+        assert_eq!(entries[0].idempotency_key(), "order-17");
+        Ok(())
+    }
+}
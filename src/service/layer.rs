@@ -0,0 +1,575 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Middleware for HTTP services.
+//!
+//! An [`Interceptor`] is run before and after every request made through a
+//! [`LayeredService`]. Interceptors can inspect and rewrite the outgoing
+//! [`Request`], including its headers and body, abort it entirely, and
+//! observe the outcome once the wrapped service has returned. This provides
+//! a single extension point for cross-cutting concerns like logging,
+//! metrics, authentication, and header propagation, rather than requiring a
+//! bespoke wrapper type for each one.
+//!
+//! [`HttpGet`] cannot send headers, so `get` requests are sent with the
+//! wrapped service's [`HttpGetResponse`] implementation. [`HttpPost`]
+//! requests are sent with the headers added by interceptors alongside the
+//! credentials they are called with, and with the body as interceptors
+//! left it.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::layer::{Interceptor, LayeredService, Request};
+//!
+//! struct Logger;
+//!
+//! impl Interceptor for Logger {
+//!     fn before_request(&self, request: &mut Request) -> HttpResult<()> {
+//!         println!("{} {}", request.method(), request.uri());
+//!         Ok(())
+//!     }
+//! }
+//!
+//! fn logged<S: HttpService>(service: S) -> LayeredService<S> {
+//!     LayeredService::new(service).with_interceptor(Logger)
+//! }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpGetResponse, HttpPost, HttpResponse};
+use crate::{HttpError, HttpResult};
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{IntoUrl, Method};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// An outgoing request as seen by an [`Interceptor`].
+#[derive(Clone, Debug)]
+pub struct Request {
+    method: Method,
+    uri: String,
+    headers: HeaderMap,
+    body: Option<Bytes>,
+}
+
+impl Request {
+    /// Creates a new request for the given method and URI, without headers
+    /// or a body.
+    pub fn new(method: Method, uri: impl Into<String>) -> Self {
+        let uri = uri.into();
+        Self {
+            method,
+            uri,
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    /// Sets the headers of the request.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Sets the body of the request.
+    pub fn with_body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// The HTTP method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The URI the request will be sent to.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Changes the URI the request will be sent to.
+    pub fn set_uri(&mut self, uri: impl Into<String>) {
+        self.uri = uri.into();
+    }
+
+    /// The headers the request will be sent with.
+    ///
+    /// These do not include credentials, which are added by the wrapped
+    /// service after every interceptor has run.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The headers the request will be sent with, which interceptors may
+    /// change.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// The body of the request, if it has one.
+    ///
+    /// The bodies of [`HttpPost`] requests are JSON.
+    pub fn body(&self) -> Option<&Bytes> {
+        self.body.as_ref()
+    }
+
+    /// Changes the body of the request.
+    ///
+    /// The bodies of [`HttpPost`] requests must remain JSON, or the request
+    /// fails with [`HttpError::Serialization`].
+    pub fn set_body(&mut self, body: impl Into<Bytes>) {
+        self.body = Some(body.into());
+    }
+}
+
+/// Authenticates a request with `auth` after adding the headers that
+/// interceptors set.
+#[derive(Debug)]
+struct WithHeaders<'a> {
+    headers: HeaderMap,
+    auth: &'a dyn Authenticator,
+}
+
+impl Authenticator for WithHeaders<'_> {
+    fn authenticate(&self, request: &mut reqwest::Request) -> HttpResult<()> {
+        request.headers_mut().extend(self.headers.clone());
+        self.auth.authenticate(request)
+    }
+
+    fn scheme(&self) -> &str {
+        self.auth.scheme()
+    }
+}
+
+/// Hooks that are run around each request made through a [`LayeredService`].
+///
+/// Both methods have default implementations that do nothing, so
+/// implementors only need to override the hooks they care about.
+pub trait Interceptor: Send + Sync {
+    /// Called before the request is handed to the wrapped service.
+    ///
+    /// Interceptors may modify the request. Returning an error aborts the
+    /// request, and the error is returned to the caller without the wrapped
+    /// service ever being called.
+    fn before_request(&self, _request: &mut Request) -> HttpResult<()> {
+        Ok(())
+    }
+
+    /// Called after the request has completed, whether it succeeded or not.
+    ///
+    /// A successful result carries the response, including its status and
+    /// headers, for requests whose wrapped service returns one; it is
+    /// `None` for [`HttpPost`] requests, which only return the decoded
+    /// body. An unsuccessful response to a plain [`HttpGet`] request is
+    /// seen as the error it is returned to the caller as, whose
+    /// [status](HttpError::status) is that of the response.
+    ///
+    /// This is also called if the request was aborted by an interceptor's
+    /// [`before_request()`](Interceptor::before_request) hook.
+    fn after_response(
+        &self,
+        _request: &Request,
+        _result: Result<Option<&HttpResponse>, &HttpError>,
+    ) {
+    }
+}
+
+/// Wraps an HTTP service and runs a chain of [interceptors](Interceptor)
+/// around each request.
+///
+/// [`before_request()`](Interceptor::before_request) hooks are run in the
+/// order in which the interceptors were added, and
+/// [`after_response()`](Interceptor::after_response) hooks are run in the
+/// reverse order, so the first interceptor added is the outermost layer.
+pub struct LayeredService<S> {
+    inner: S,
+    interceptors: Vec<Box<dyn Interceptor>>,
+}
+
+impl<S> LayeredService<S> {
+    /// Wraps `inner` in a service with no interceptors.
+    pub fn new(inner: S) -> Self {
+        let interceptors = Vec::new();
+        Self {
+            inner,
+            interceptors,
+        }
+    }
+
+    /// Adds an interceptor to the end of the chain.
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Box::new(interceptor));
+        self
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn before_request(&self, request: &mut Request) -> HttpResult<()> {
+        self.interceptors
+            .iter()
+            .try_for_each(|interceptor| interceptor.before_request(request))
+    }
+
+    fn after_response(&self, request: &Request, result: Result<Option<&HttpResponse>, &HttpError>) {
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.after_response(request, result);
+        }
+    }
+}
+
+impl<S: HttpGetResponse + Sync> LayeredService<S> {
+    /// Runs the `before_request()` hooks and sends a GET request to `uri`
+    /// with `headers`, returning the request as the interceptors left it.
+    async fn send_get(
+        &self,
+        uri: &str,
+        headers: &HeaderMap,
+    ) -> (Request, HttpResult<HttpResponse>) {
+        let mut request = Request::new(Method::GET, uri).with_headers(headers.clone());
+        let result = match self.before_request(&mut request) {
+            Ok(()) => {
+                self.inner
+                    .get_response(request.uri(), request.headers())
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        (request, result)
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGet for LayeredService<S> {
+    /// Runs the interceptors around a GET request, which is sent with the
+    /// wrapped service's [`HttpGetResponse`] implementation, and returns
+    /// the body of a successful response.
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let (request, result) = self.send_get(uri.as_str(), &HeaderMap::new()).await;
+        let result = result.and_then(HttpResponse::error_for_status);
+        self.after_response(&request, result.as_ref().map(Some));
+        Ok(result?.into_body())
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGetResponse for LayeredService<S> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let (request, result) = self.send_get(uri.as_str(), headers).await;
+        self.after_response(&request, result.as_ref().map(Some));
+        result
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for LayeredService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let body = Bytes::from(serde_json::to_vec(data)?);
+        let mut request = Request::new(Method::POST, uri.as_str()).with_body(body.clone());
+        let result = match self.before_request(&mut request) {
+            Ok(()) => {
+                let auth = WithHeaders {
+                    headers: request.headers().clone(),
+                    auth,
+                };
+                match request.body() {
+                    Some(changed) if *changed != body => {
+                        match serde_json::from_slice::<Value>(changed) {
+                            Ok(data) => self.inner.post(request.uri(), &auth, &data).await,
+                            Err(err) => Err(err.into()),
+                        }
+                    }
+                    _ => self.inner.post(request.uri(), &auth, data).await,
+                }
+            }
+            Err(err) => Err(err),
+        };
+        self.after_response(&request, result.as_ref().map(|_| None));
+        result
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for LayeredService<S> {
+    fn capabilities(&self) -> Capabilities {
+        let config = format!("{} interceptors", self.interceptors.len());
+        let capabilities = self.inner.capabilities();
+        // Plain GET requests are sent as GetResponse requests.
+        let get = capabilities
+            .supports(Verb::GetResponse)
+            .then_some(Verb::Get);
+        let verbs: Vec<_> = [Verb::Post, Verb::GetResponse]
+            .into_iter()
+            .chain(get)
+            .collect();
+        capabilities
+            .limit_verbs(&verbs)
+            .wrap(Middleware::new("interceptors").with_config(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use reqwest::header::{self, HeaderValue};
+    use reqwest::{StatusCode, Url};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// Answers requests with their URIs, and records the headers and body
+    /// of the last request.
+    #[derive(Default)]
+    struct EchoService {
+        headers: Mutex<HeaderMap>,
+        body: Mutex<Option<Value>>,
+    }
+
+    impl HttpGetResponse for EchoService {
+        async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            *self.headers.lock().unwrap() = headers.clone();
+            Ok(HttpResponse::new(StatusCode::OK, uri.as_str()))
+        }
+    }
+
+    impl HttpPost for EchoService {
+        async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let url = Url::parse("https://api.example.com")?.join(uri.as_str())?;
+            let mut request = reqwest::Request::new(Method::POST, url);
+            auth.authenticate(&mut request)?;
+            *self.headers.lock().unwrap() = request.headers().clone();
+            *self.body.lock().unwrap() = Some(serde_json::to_value(data)?);
+            Ok(serde_json::from_value(serde_json::json!(uri.as_str()))?)
+        }
+    }
+
+    struct Prefix(&'static str);
+
+    impl Interceptor for Prefix {
+        fn before_request(&self, request: &mut Request) -> HttpResult<()> {
+            let uri = format!("{}{}", self.0, request.uri());
+            request.set_uri(uri);
+            Ok(())
+        }
+    }
+
+    struct Reject;
+
+    impl Interceptor for Reject {
+        fn before_request(&self, _request: &mut Request) -> HttpResult<()> {
+            Err(HttpError::Http(StatusCode::FORBIDDEN))
+        }
+    }
+
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Interceptor for Recorder {
+        fn before_request(&self, request: &mut Request) -> HttpResult<()> {
+            let entry = format!("before {} {}", self.name, request.method());
+            self.log.lock().unwrap().push(entry);
+            Ok(())
+        }
+
+        fn after_response(
+            &self,
+            request: &Request,
+            result: Result<Option<&HttpResponse>, &HttpError>,
+        ) {
+            let outcome = if result.is_ok() { "ok" } else { "err" };
+            let entry = format!("after {} {} {outcome}", self.name, request.uri());
+            self.log.lock().unwrap().push(entry);
+        }
+    }
+
+    #[tokio::test]
+    async fn it_passes_requests_through_without_interceptors() -> HttpResult<()> {
+        let service = LayeredService::new(EchoService::default());
+        assert_eq!(service.get("/users").await?, "/users");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_lets_interceptors_rewrite_the_uri() -> HttpResult<()> {
+        let service = LayeredService::new(EchoService::default())
+            .with_interceptor(Prefix("/v2"))
+            .with_interceptor(Prefix("/api"));
+        assert_eq!(service.get("/users").await?, "/api/v2/users");
+        let auth = Auth::new("my-api-key");
+        let response: String = service.post("/users", &auth, &()).await?;
+        assert_eq!(response, "/api/v2/users");
+        Ok(())
+    }
+
+    struct RequestId;
+
+    impl Interceptor for RequestId {
+        fn before_request(&self, request: &mut Request) -> HttpResult<()> {
+            let id = HeaderValue::from_static("42");
+            request.headers_mut().insert("x-request-id", id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_lets_interceptors_add_headers() -> HttpResult<()> {
+        let service = LayeredService::new(EchoService::default()).with_interceptor(RequestId);
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/plain"));
+        service.get_response("/users", &headers).await?;
+        let sent = service.inner().headers.lock().unwrap().clone();
+        assert_eq!(sent["x-request-id"], "42");
+        assert_eq!(sent[header::ACCEPT], "text/plain");
+
+        let _: String = service.post("/users", &Auth::new("key"), &()).await?;
+        let sent = service.inner().headers.lock().unwrap().clone();
+        assert_eq!(sent["x-request-id"], "42");
+        assert_eq!(sent[header::AUTHORIZATION], "Bearer key");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_lets_interceptors_rewrite_post_bodies() -> HttpResult<()> {
+        struct Stamp;
+
+        impl Interceptor for Stamp {
+            fn before_request(&self, request: &mut Request) -> HttpResult<()> {
+                let Some(body) = request.body() else {
+                    return Ok(());
+                };
+                let mut data: Value = serde_json::from_slice(body)?;
+                data["source"] = json!("layer");
+                request.set_body(serde_json::to_vec(&data)?);
+                Ok(())
+            }
+        }
+
+        let service = LayeredService::new(EchoService::default()).with_interceptor(Stamp);
+        let auth = Auth::new("key");
+        let _: String = service
+            .post("/users", &auth, &json!({"name": "foo"}))
+            .await?;
+        let body = service.inner().body.lock().unwrap().clone();
+        assert_eq!(body, Some(json!({"name": "foo", "source": "layer"})));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_runs_after_hooks_in_reverse_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let service = LayeredService::new(EchoService::default())
+            .with_interceptor(Recorder {
+                name: "outer",
+                log: Arc::clone(&log),
+            })
+            .with_interceptor(Recorder {
+                name: "inner",
+                log: Arc::clone(&log),
+            });
+        let _ = service.get("/users").await;
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "before outer GET",
+                "before inner GET",
+                "after inner /users ok",
+                "after outer /users ok",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_shows_after_hooks_the_status_of_the_response() {
+        struct Broken;
+
+        impl HttpGetResponse for Broken {
+            async fn get_response<U>(
+                &self,
+                _uri: U,
+                _headers: &HeaderMap,
+            ) -> HttpResult<HttpResponse>
+            where
+                U: IntoUrl + Send,
+            {
+                Ok(HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR, "oops"))
+            }
+        }
+
+        struct Statuses(Arc<Mutex<Vec<Option<StatusCode>>>>);
+
+        impl Interceptor for Statuses {
+            fn after_response(
+                &self,
+                _request: &Request,
+                result: Result<Option<&HttpResponse>, &HttpError>,
+            ) {
+                let status = match result {
+                    Ok(response) => response.map(HttpResponse::status),
+                    Err(err) => err.status(),
+                };
+                self.0.lock().unwrap().push(status);
+            }
+        }
+
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let service = LayeredService::new(Broken).with_interceptor(Statuses(Arc::clone(&statuses)));
+        let result = service.get("/users").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::INTERNAL_SERVER_ERROR))
+        ));
+        let response = service.get_response("/users", &HeaderMap::new()).await;
+        assert!(
+            response.is_ok_and(|response| response.status() == StatusCode::INTERNAL_SERVER_ERROR)
+        );
+        assert_eq!(
+            *statuses.lock().unwrap(),
+            [Some(StatusCode::INTERNAL_SERVER_ERROR); 2]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_aborts_requests_rejected_by_an_interceptor() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let service = LayeredService::new(EchoService::default())
+            .with_interceptor(Recorder {
+                name: "outer",
+                log: Arc::clone(&log),
+            })
+            .with_interceptor(Reject)
+            .with_interceptor(Prefix("/never"));
+        let result = service.get("/users").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::FORBIDDEN))
+        ));
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["before outer GET", "after outer /users err"]
+        );
+    }
+}
@@ -0,0 +1,438 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Pacing requests across a server's rate limit windows.
+//!
+//! Many APIs report how many requests a client has left and when its limit
+//! resets in [rate limit headers](crate::headers::rate_limit). A
+//! [`PacedService`] reads those headers from every response and spreads
+//! the requests that follow evenly over the rest of the window, instead of
+//! sending them as fast as possible and running into HTTP 429 errors
+//! halfway through. This suits bulk jobs that make many requests to APIs
+//! with strict limits.
+//!
+//! If the requests left in a window run out, later requests wait until
+//! the window resets. After that, they are sent at the same pace until a
+//! response reports the state of the new window. A 429 or 503 response
+//! with a `Retry-After` header also holds back requests until the server
+//! is ready for them again.
+//!
+//! Requests that are in flight when a response arrives are not yet counted
+//! by the server, so a service that sends many requests at once should
+//! [keep some requests in reserve](PacingPolicy::with_reserve).
+//!
+//! Only the verbs that return whole responses are paced, since the others
+//! do not expose response headers.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::service::HttpGetResponse;
+//! use hypertyper::service::pacing::{PacedService, PacingPolicy};
+//! use std::time::Duration;
+//!
+//! fn paced<S: HttpGetResponse>(service: S) -> PacedService<S> {
+//!     let policy = PacingPolicy::new()
+//!         .with_reserve(5)
+//!         .with_max_delay(Duration::from_secs(600));
+//!     PacedService::new(service, policy)
+//! }
+//! ```
+
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGetResponse, HttpPostForm, HttpPostStream, HttpResponse, host_of};
+use crate::upload::UploadBody;
+use crate::{HttpError, HttpResult, headers};
+use reqwest::IntoUrl;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The furthest off a reset is taken to be, so that a server cannot push
+/// the end of a window past what an [`Instant`] can represent.
+const MAX_RESET: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Determines how a [`PacedService`] spreads out requests.
+#[derive(Clone, Debug)]
+pub struct PacingPolicy {
+    per_host: bool,
+    reserve: u64,
+    max_delay: Option<Duration>,
+}
+
+impl PacingPolicy {
+    /// Creates a policy that paces requests to each host separately, with
+    /// nothing held in reserve and no limit on how long requests wait.
+    pub fn new() -> Self {
+        Self {
+            per_host: true,
+            reserve: 0,
+            max_delay: None,
+        }
+    }
+
+    /// Paces requests to each host separately, rather than treating every
+    /// request as part of the same rate limit.
+    pub fn with_per_host(mut self, per_host: bool) -> Self {
+        self.per_host = per_host;
+        self
+    }
+
+    /// Leaves `reserve` requests in each window unused, as a margin for
+    /// requests in flight and for other clients sharing the same limit.
+    pub fn with_reserve(mut self, reserve: u64) -> Self {
+        self.reserve = reserve;
+        self
+    }
+
+    /// Sets the longest a request may wait to be sent.
+    ///
+    /// Requests that would have to wait longer fail immediately with
+    /// [`HttpError::RateLimited`]. By default, there is no limit.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    /// The number of requests left unused in each window.
+    pub fn reserve(&self) -> u64 {
+        self.reserve
+    }
+
+    /// The longest a request may wait to be sent, if there is a limit.
+    pub fn max_delay(&self) -> Option<Duration> {
+        self.max_delay
+    }
+}
+
+impl Default for PacingPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What is known about a server's current rate limit window.
+#[derive(Debug)]
+struct Window {
+    remaining: u64,
+    reset_at: Instant,
+    // When the next request may be sent.
+    next_at: Instant,
+    // The gap between the last two requests that were scheduled.
+    spacing: Duration,
+}
+
+/// Wraps an HTTP service and spreads requests across the rate limit
+/// windows reported by the server.
+///
+/// See the [module documentation](crate::service::pacing) for details.
+#[derive(Debug)]
+pub struct PacedService<S> {
+    inner: S,
+    policy: PacingPolicy,
+    windows: Mutex<HashMap<String, Window>>,
+}
+
+impl<S> PacedService<S> {
+    /// Wraps `inner` in a service that paces requests according to
+    /// `policy`.
+    pub fn new(inner: S, policy: PacingPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy used to pace requests.
+    pub fn policy(&self) -> &PacingPolicy {
+        &self.policy
+    }
+
+    fn key(&self, uri: &str) -> String {
+        if self.policy.per_host {
+            host_of(uri)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Waits until a request to `uri` may be sent.
+    async fn acquire(&self, uri: &str) -> HttpResult<()> {
+        let delay = self.reserve(uri)?;
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    /// Schedules a request to `uri`, returning how long the caller must
+    /// wait before sending it.
+    fn reserve(&self, uri: &str) -> HttpResult<Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let Some(window) = windows.get_mut(&self.key(uri)) else {
+            return Ok(Duration::ZERO);
+        };
+
+        let mut slot = window.next_at.max(now);
+        let available = window.remaining.saturating_sub(self.policy.reserve);
+        let spacing = if available > 0 && slot < window.reset_at {
+            (window.reset_at - slot).div_f64(available as f64)
+        } else {
+            slot = slot.max(window.reset_at);
+            window.spacing
+        };
+        let delay = slot - now;
+        if self.policy.max_delay.is_some_and(|max| delay > max) {
+            return Err(HttpError::RateLimited);
+        }
+
+        if available > 0 {
+            window.remaining -= 1;
+        }
+        window.spacing = spacing;
+        window.next_at = slot + spacing;
+        Ok(delay)
+    }
+
+    /// Updates what is known about the rate limit for `uri` from the
+    /// headers of a response.
+    fn record(&self, uri: &str, response: &HttpResponse) {
+        let now = Instant::now();
+        let retry_after = match response.status() {
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+                headers::retry_after(response.headers())
+            }
+            _ => None,
+        };
+        let (remaining, reset) = match (retry_after, headers::rate_limit(response.headers())) {
+            (Some(retry_after), _) => (0, retry_after),
+            (None, Some(rate_limit)) => (rate_limit.remaining, rate_limit.reset),
+            (None, None) => return,
+        };
+
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(self.key(uri)).or_insert_with(|| Window {
+            remaining,
+            reset_at: now,
+            next_at: now,
+            spacing: Duration::ZERO,
+        });
+        window.remaining = remaining;
+        window.reset_at = now + reset.min(MAX_RESET);
+    }
+
+    async fn paced<F>(&self, uri: String, send: F) -> HttpResult<HttpResponse>
+    where
+        F: Future<Output = HttpResult<HttpResponse>>,
+    {
+        self.acquire(&uri).await?;
+        let response = send.await?;
+        self.record(&uri, &response);
+        Ok(response)
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGetResponse for PacedService<S> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        self.paced(uri.clone(), self.inner.get_response(uri, headers))
+            .await
+    }
+}
+
+impl<S: HttpPostForm + Sync> HttpPostForm for PacedService<S> {
+    async fn post_form<U, D>(&self, uri: U, form: &D) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+    {
+        let uri = uri.as_str().to_string();
+        self.paced(uri.clone(), self.inner.post_form(uri, form))
+            .await
+    }
+}
+
+impl<S: HttpPostStream + Sync> HttpPostStream for PacedService<S> {
+    async fn post_stream<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: UploadBody,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        self.paced(uri.clone(), self.inner.post_stream(uri, headers, body))
+            .await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for PacedService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::GetResponse, Verb::PostForm, Verb::PostStream])
+            .wrap(Middleware::new("pacing").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{self, HeaderName, HeaderValue};
+    use std::collections::VecDeque;
+
+    /// Returns scripted responses in turn, recording when each request was
+    /// made.
+    struct Script {
+        responses: Mutex<VecDeque<HttpResponse>>,
+        requests: Mutex<Vec<Instant>>,
+    }
+
+    impl Script {
+        fn new(responses: Vec<HttpResponse>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn times(&self, start: Instant) -> Vec<Duration> {
+            let requests = self.requests.lock().unwrap();
+            requests.iter().map(|&at| at - start).collect()
+        }
+    }
+
+    impl HttpGetResponse for Script {
+        async fn get_response<U>(&self, _uri: U, _headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            self.requests.lock().unwrap().push(Instant::now());
+            let response = self.responses.lock().unwrap().pop_front();
+            Ok(response.unwrap_or_else(|| HttpResponse::new(StatusCode::OK, "")))
+        }
+    }
+
+    fn limited(remaining: &'static str, reset: &'static str) -> HttpResponse {
+        HttpResponse::new(StatusCode::OK, "")
+            .with_header(
+                HeaderName::from_static("x-ratelimit-remaining"),
+                HeaderValue::from_static(remaining),
+            )
+            .with_header(
+                HeaderName::from_static("x-ratelimit-reset"),
+                HeaderValue::from_static(reset),
+            )
+    }
+
+    async fn get_all(service: &PacedService<Script>, count: usize) -> HttpResult<()> {
+        for _ in 0..count {
+            service
+                .get_response("https://api.example.com/items", &HeaderMap::new())
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_spreads_requests_across_the_window() -> HttpResult<()> {
+        let script = Script::new(vec![limited("4", "8")]);
+        let service = PacedService::new(script, PacingPolicy::new());
+        let start = Instant::now();
+        get_all(&service, 5).await?;
+        assert_eq!(
+            service.inner().times(start),
+            [0, 0, 2, 4, 6].map(Duration::from_secs)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_waits_for_the_reset_once_requests_run_out() -> HttpResult<()> {
+        let script = Script::new(vec![limited("2", "4"), limited("0", "2")]);
+        let service = PacedService::new(script, PacingPolicy::new());
+        let start = Instant::now();
+        get_all(&service, 4).await?;
+        // The second request says that none are left, so the third waits
+        // for the reset and the fourth keeps the same pace.
+        assert_eq!(
+            service.inner().times(start),
+            [0, 0, 2, 4].map(Duration::from_secs)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_keeps_requests_in_reserve() -> HttpResult<()> {
+        let script = Script::new(vec![limited("3", "9")]);
+        let policy = PacingPolicy::new().with_reserve(2);
+        let service = PacedService::new(script, policy);
+        let start = Instant::now();
+        get_all(&service, 3).await?;
+        assert_eq!(
+            service.inner().times(start),
+            [0, 0, 9].map(Duration::from_secs)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_honors_retry_after_on_too_many_requests() -> HttpResult<()> {
+        let throttled = HttpResponse::new(StatusCode::TOO_MANY_REQUESTS, "")
+            .with_header(header::RETRY_AFTER, HeaderValue::from_static("5"));
+        let service = PacedService::new(Script::new(vec![throttled]), PacingPolicy::new());
+        let start = Instant::now();
+        get_all(&service, 2).await?;
+        assert_eq!(
+            service.inner().times(start),
+            [0, 5].map(Duration::from_secs)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_clamps_retry_after_delays_too_large_to_represent() -> HttpResult<()> {
+        let throttled = HttpResponse::new(StatusCode::TOO_MANY_REQUESTS, "").with_header(
+            header::RETRY_AFTER,
+            HeaderValue::from_static("18446744073709551615"),
+        );
+        let policy = PacingPolicy::new().with_max_delay(Duration::from_secs(600));
+        let service = PacedService::new(Script::new(vec![throttled]), policy);
+        get_all(&service, 1).await?;
+        let result = get_all(&service, 1).await;
+        assert!(matches!(result, Err(HttpError::RateLimited)));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_fails_fast_when_the_wait_is_too_long() -> HttpResult<()> {
+        let script = Script::new(vec![limited("0", "60")]);
+        let policy = PacingPolicy::new().with_max_delay(Duration::from_secs(10));
+        let service = PacedService::new(script, policy);
+        get_all(&service, 1).await?;
+        let result = get_all(&service, 1).await;
+        assert!(matches!(result, Err(HttpError::RateLimited)));
+
+        // Other hosts are paced separately.
+        service
+            .get_response("https://other.example.com/", &HeaderMap::new())
+            .await?;
+        Ok(())
+    }
+}
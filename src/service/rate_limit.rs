@@ -0,0 +1,584 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Client-side rate limiting.
+//!
+//! A [`RateLimitedService`] uses a [token bucket] to limit how quickly
+//! requests are sent to the wrapped service. The bucket holds up to a
+//! "burst" number of tokens and refills at a steady rate; each request
+//! takes a token, and requests that arrive when the bucket is empty wait
+//! until a token becomes available rather than failing.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::rate_limit::{RateLimitPolicy, RateLimitedService};
+//!
+//! fn throttled<S: HttpService>(service: S) -> RateLimitedService<S> {
+//!     // At most 10 requests per second to each host, with bursts of up to 20.
+//!     let policy = RateLimitPolicy::per_second(10.0)
+//!         .with_burst(20)
+//!         .with_per_host(true);
+//!     RateLimitedService::new(service, policy)
+//! }
+//! ```
+//!
+//! # Persistence
+//!
+//! A command-line tool that is run over and over starts each run with a
+//! full bucket, so running it several times in a row can exceed a quota
+//! that a single run respects. A service created with
+//! [`with_state_file()`](RateLimitedService::with_state_file) saves its
+//! buckets to a file as requests are made and picks them up again in the
+//! next run, as if the program had never stopped. The file is written in
+//! the background, so requests never wait for it, and once more when the
+//! service is dropped, so the last run's state is never lost. Requests
+//! that were still waiting for a token when the state was saved are not
+//! carried over, so the next run starts with an empty bucket at worst.
+//!
+//! Like caching, saving state is best effort: if the file cannot be read
+//! or written, the service behaves as if it had no state file.
+//!
+//! [token bucket]: https://en.wikipedia.org/wiki/Token_bucket
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost, host_of};
+use crate::{HttpError, HttpResult};
+use reqwest::IntoUrl;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+
+/// Determines how quickly a [`RateLimitedService`] sends requests.
+#[derive(Clone, Debug)]
+pub struct RateLimitPolicy {
+    rate: f64,
+    burst: u32,
+    per_host: bool,
+    max_queue: Option<usize>,
+}
+
+impl RateLimitPolicy {
+    /// Creates a policy that allows `rate` requests per second, with bursts
+    /// of up to one request.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is not a positive number.
+    pub fn per_second(rate: f64) -> Self {
+        assert!(rate > 0.0, "rate must be positive");
+        Self {
+            rate,
+            burst: 1,
+            per_host: false,
+            max_queue: None,
+        }
+    }
+
+    /// Creates a policy that allows `count` requests per minute, with bursts
+    /// of up to one request.
+    ///
+    /// # Panics
+    ///
+    /// If `count` is zero.
+    pub fn per_minute(count: u32) -> Self {
+        Self::per_second(f64::from(count) / 60.0)
+    }
+
+    /// Sets the maximum number of requests that can be sent at once after
+    /// a period of inactivity.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Limits requests to each host separately, rather than limiting all
+    /// requests together.
+    pub fn with_per_host(mut self, per_host: bool) -> Self {
+        self.per_host = per_host;
+        self
+    }
+
+    /// Sets the maximum number of requests that may wait for a token.
+    ///
+    /// Requests that arrive when this many requests are already waiting
+    /// fail immediately with [`HttpError::RateLimited`]. By default, there
+    /// is no limit.
+    pub fn with_max_queue(mut self, max_queue: usize) -> Self {
+        self.max_queue = Some(max_queue);
+        self
+    }
+
+    /// The number of requests allowed per second.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// The maximum number of requests that can be sent at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    // May be negative, in which case it is the number of requests waiting
+    // for a token.
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(policy: &RateLimitPolicy) -> Self {
+        let tokens = f64::from(policy.burst);
+        let updated_at = Instant::now();
+        Self { tokens, updated_at }
+    }
+
+    fn refill(&mut self, policy: &RateLimitPolicy, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * policy.rate).min(f64::from(policy.burst));
+        self.updated_at = now;
+    }
+
+    fn waiting(&self) -> usize {
+        if self.tokens < 0.0 {
+            (-self.tokens).ceil() as usize
+        } else {
+            0
+        }
+    }
+
+    /// Converts a bucket loaded from a state file, whose update time is a
+    /// wall clock time, back to a bucket.
+    ///
+    /// Requests that were waiting when the state was saved belong to a
+    /// process that no longer exists, so the bucket starts with no more
+    /// than `burst` tokens and no fewer than none.
+    fn from_saved(saved: &SavedBucket, policy: &RateLimitPolicy, now: Instant) -> Self {
+        let updated_at = UNIX_EPOCH + Duration::from_millis(saved.updated_at_ms);
+        let age = SystemTime::now()
+            .duration_since(updated_at)
+            .unwrap_or(Duration::ZERO);
+        Self {
+            tokens: saved.tokens.clamp(0.0, f64::from(policy.burst)),
+            updated_at: now.checked_sub(age).unwrap_or(now),
+        }
+    }
+
+    fn to_saved(&self, policy: &RateLimitPolicy, now: Instant) -> SavedBucket {
+        let age = now.saturating_duration_since(self.updated_at);
+        let updated_at = SystemTime::now() - age;
+        let updated_at_ms = updated_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_millis() as u64;
+        SavedBucket {
+            tokens: self.tokens.clamp(0.0, f64::from(policy.burst)),
+            updated_at_ms,
+        }
+    }
+}
+
+/// The format of a bucket in a state file.
+#[derive(Debug, Deserialize, Serialize)]
+struct SavedBucket {
+    tokens: f64,
+    updated_at_ms: u64,
+}
+
+/// Writes snapshots of the buckets to a state file off the async
+/// executor.
+///
+/// Snapshots taken while a write is in progress replace each other, so
+/// only the latest is written once the write finishes.
+#[derive(Debug)]
+struct StateWriter {
+    path: PathBuf,
+    pending: Mutex<Option<String>>,
+    // Held while the file is written, so writes happen in the order their
+    // snapshots were taken.
+    file: Mutex<()>,
+    writing: AtomicBool,
+}
+
+impl StateWriter {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            pending: Mutex::default(),
+            file: Mutex::default(),
+            writing: AtomicBool::new(false),
+        }
+    }
+
+    /// Writes `snapshot` in the background, unless a later one is written
+    /// first.
+    fn schedule(self: &Arc<Self>, snapshot: String) {
+        *self.pending.lock().unwrap() = Some(snapshot);
+        if self.writing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let writer = Arc::clone(self);
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || writer.drain());
+            }
+            Err(_) => writer.drain(),
+        }
+    }
+
+    /// Writes pending snapshots until there are none left.
+    fn drain(&self) {
+        loop {
+            self.flush();
+            self.writing.store(false, Ordering::Release);
+            // A snapshot may have been scheduled after the last flush but
+            // before the flag was cleared, without starting a writer.
+            let more = self.pending.lock().unwrap().is_some();
+            if !more || self.writing.swap(true, Ordering::AcqRel) {
+                return;
+            }
+        }
+    }
+
+    /// Writes the pending snapshot, if there is one.
+    fn flush(&self) {
+        let _file = self.file.lock().unwrap();
+        let Some(snapshot) = self.pending.lock().unwrap().take() else {
+            return;
+        };
+        // Write to a temporary file first, so that a crash while saving
+        // never leaves a corrupt state file behind.
+        let tmp = self.path.with_extension("tmp");
+        if fs::write(&tmp, snapshot).is_ok() {
+            let _ = fs::rename(tmp, &self.path);
+        }
+    }
+}
+
+/// Wraps an HTTP service and limits how quickly requests are sent to it.
+///
+/// See the [module documentation](crate::service::rate_limit) for details.
+#[derive(Debug)]
+pub struct RateLimitedService<S> {
+    inner: S,
+    policy: RateLimitPolicy,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+    state: Option<Arc<StateWriter>>,
+}
+
+impl<S> RateLimitedService<S> {
+    /// Wraps `inner` in a service that limits requests according to `policy`.
+    pub fn new(inner: S, policy: RateLimitPolicy) -> Self {
+        let buckets = Mutex::new(HashMap::new());
+        Self {
+            inner,
+            policy,
+            buckets,
+            state: None,
+        }
+    }
+
+    /// Saves the state of the rate limiter to the file at `path` as
+    /// requests are made, and restores it from that file if it already
+    /// exists.
+    ///
+    /// See the [module documentation](crate::service::rate_limit#persistence)
+    /// for details.
+    pub fn with_state_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let saved = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<HashMap<String, SavedBucket>>(&json).ok());
+        if let Some(saved) = saved {
+            let now = Instant::now();
+            let buckets = self.buckets.get_mut().unwrap();
+            for (key, bucket) in saved {
+                buckets.insert(key, TokenBucket::from_saved(&bucket, &self.policy, now));
+            }
+        }
+        self.state = Some(Arc::new(StateWriter::new(path)));
+        self
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy used to limit requests.
+    pub fn policy(&self) -> &RateLimitPolicy {
+        &self.policy
+    }
+
+    /// The file that the state of the rate limiter is saved to, if any.
+    pub fn state_file(&self) -> Option<&Path> {
+        self.state.as_ref().map(|state| state.path.as_path())
+    }
+
+    /// Waits until a request to `uri` may be sent.
+    async fn acquire(&self, uri: &str) -> HttpResult<()> {
+        let delay = self.reserve(uri)?;
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    /// Takes a token from the bucket for `uri`, returning how long the
+    /// caller must wait before the token is actually available.
+    fn reserve(&self, uri: &str) -> HttpResult<Duration> {
+        let key = if self.policy.per_host {
+            host_of(uri)
+        } else {
+            String::new()
+        };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::full(&self.policy));
+        bucket.refill(&self.policy, now);
+
+        let queue_full = self
+            .policy
+            .max_queue
+            .is_some_and(|max_queue| bucket.tokens < 1.0 && bucket.waiting() >= max_queue);
+        if queue_full {
+            return Err(HttpError::RateLimited);
+        }
+
+        bucket.tokens -= 1.0;
+        let delay = if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64(-bucket.tokens / self.policy.rate).unwrap_or(Duration::MAX)
+        };
+        let snapshot = self
+            .state
+            .as_ref()
+            .and_then(|_| snapshot(&buckets, &self.policy, now));
+        drop(buckets);
+        if let (Some(state), Some(snapshot)) = (&self.state, snapshot) {
+            state.schedule(snapshot);
+        }
+        Ok(delay)
+    }
+}
+
+/// The contents of a state file that holds `buckets`.
+fn snapshot(
+    buckets: &HashMap<String, TokenBucket>,
+    policy: &RateLimitPolicy,
+    now: Instant,
+) -> Option<String> {
+    let saved: HashMap<&str, SavedBucket> = buckets
+        .iter()
+        .map(|(key, bucket)| (key.as_str(), bucket.to_saved(policy, now)))
+        .collect();
+    serde_json::to_string(&saved).ok()
+}
+
+impl<S> Drop for RateLimitedService<S> {
+    fn drop(&mut self) {
+        if let Some(state) = &self.state {
+            state.flush();
+        }
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for RateLimitedService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        self.acquire(uri.as_str()).await?;
+        self.inner.get(uri).await
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for RateLimitedService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        self.acquire(uri.as_str()).await?;
+        self.inner.post(uri, auth, data).await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for RateLimitedService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("rate-limit").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future;
+
+    struct OkService;
+
+    impl HttpGet for OkService {
+        async fn get<U>(&self, _uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            Ok(String::from("ok"))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_allows_bursts_without_waiting() -> HttpResult<()> {
+        let policy = RateLimitPolicy::per_second(1.0).with_burst(3);
+        let service = RateLimitedService::new(OkService, policy);
+        let start = Instant::now();
+        for _ in 0..3 {
+            service.get("/users").await?;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_spaces_out_requests_beyond_the_burst() -> HttpResult<()> {
+        let policy = RateLimitPolicy::per_second(2.0);
+        let service = RateLimitedService::new(OkService, policy);
+        let start = Instant::now();
+        for _ in 0..5 {
+            service.get("/users").await?;
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_limits_each_host_separately() -> HttpResult<()> {
+        let policy = RateLimitPolicy::per_second(1.0).with_per_host(true);
+        let service = RateLimitedService::new(OkService, policy);
+        let start = Instant::now();
+        service.get("https://a.example.com/").await?;
+        service.get("https://b.example.com/").await?;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        service.get("https://a.example.com/").await?;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_restores_its_state_between_runs() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rate-limit.json");
+        let policy = RateLimitPolicy::per_second(1.0).with_burst(2);
+
+        let service = RateLimitedService::new(OkService, policy.clone()).with_state_file(&path);
+        service.get("/users").await?;
+        service.get("/users").await?;
+        drop(service);
+
+        let service = RateLimitedService::new(OkService, policy).with_state_file(&path);
+        assert_eq!(service.state_file(), Some(path.as_path()));
+        let start = Instant::now();
+        service.get("/users").await?;
+        assert!(start.elapsed() > Duration::from_millis(900));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_saves_its_state_in_the_background() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rate-limit.json");
+        let policy = RateLimitPolicy::per_second(1.0).with_burst(2);
+        let service = RateLimitedService::new(OkService, policy).with_state_file(&path);
+        service.get("/users").await?;
+
+        let mut saved = None;
+        for _ in 0..100 {
+            saved = fs::read_to_string(&path)
+                .ok()
+                .and_then(|json| serde_json::from_str::<HashMap<String, SavedBucket>>(&json).ok());
+            if saved.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let saved = saved.expect("state file should be written");
+        assert!(saved[""].tokens < 2.0);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_ignores_damaged_state_files() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rate-limit.json");
+        fs::write(&path, "{not json")?;
+        let policy = RateLimitPolicy::per_second(1.0).with_burst(2);
+        let service = RateLimitedService::new(OkService, policy).with_state_file(&path);
+        let start = Instant::now();
+        service.get("/users").await?;
+        service.get("/users").await?;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_clamps_saved_balances_to_the_burst() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rate-limit.json");
+        let policy = RateLimitPolicy::per_second(1.0).with_burst(2);
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+
+        fs::write(
+            &path,
+            format!(r#"{{"": {{"tokens": -1e300, "updated_at_ms": {now_ms}}}}}"#),
+        )?;
+        let service = RateLimitedService::new(OkService, policy.clone()).with_state_file(&path);
+        let start = Instant::now();
+        service.get("/users").await?;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        drop(service);
+
+        fs::write(
+            &path,
+            format!(r#"{{"": {{"tokens": 1e300, "updated_at_ms": {now_ms}}}}}"#),
+        )?;
+        let service = RateLimitedService::new(OkService, policy).with_state_file(&path);
+        let start = Instant::now();
+        service.get("/users").await?;
+        service.get("/users").await?;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        service.get("/users").await?;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_fails_fast_when_the_queue_is_full() {
+        let policy = RateLimitPolicy::per_second(1.0).with_max_queue(2);
+        let service = RateLimitedService::new(OkService, policy);
+        let results = future::join_all((0..4).map(|_| service.get("/users"))).await;
+        let limited = results
+            .iter()
+            .filter(|result| matches!(result, Err(HttpError::RateLimited)))
+            .count();
+        assert_eq!(limited, 1);
+    }
+}
@@ -0,0 +1,403 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Re-authentication of requests that are rejected with an HTTP 401.
+//!
+//! Credentials can stop working before anyone expects them to: tokens are
+//! revoked, rotated by another process, or expire early because of clock
+//! skew. A [`ReauthService`] authenticates each request with a
+//! [`Refreshable`] authenticator, like an
+//! [`AuthService`](crate::service::auth::AuthService) does, and when a
+//! request is rejected with an HTTP 401, it refreshes the credentials and
+//! replays the request once with the new ones. If the replayed request is
+//! also rejected, its response is returned as it is.
+//!
+//! Requests made while credentials are being refreshed, such as a request
+//! to a token endpoint made through the same service, are never replayed,
+//! so a refresh that is itself rejected cannot start another one.
+//!
+//! [`JwtAuth`](crate::auth::JwtAuth) is refreshable when it is given a
+//! refresh callback, which can obtain a new token from an
+//! [OAuth 2.0 flow](crate::auth::oauth2), so together they rotate tokens
+//! without callers noticing.
+//!
+//! [`HttpPost`] requests are only recognized as rejected if they fail with
+//! an HTTP 401 [error](crate::HttpError::status), and verbs that return an
+//! [`HttpResponse`] also recognize a response with a 401 status. Like an
+//! [`AuthService`](crate::service::auth::AuthService), a `ReauthService`
+//! sends [`HttpGet`] requests with the wrapped service's
+//! [`HttpGetResponse`] implementation, so that credentials can be sent in
+//! headers.
+//! Streamed uploads cannot be replayed, so [`HttpPostStream`] is not
+//! supported.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::auth::JwtAuth;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetResponse;
+//! use hypertyper::service::reauth::ReauthService;
+//!
+//! # async fn log_in() -> HttpResult<String> { todo!() }
+//! # async fn run<S: HttpGetResponse + Sync>(service: S) -> HttpResult<()> {
+//! let auth = JwtAuth::new(log_in().await?)?.with_refresh(log_in);
+//! let service = ReauthService::new(service, auth);
+//! let body = service.get("https://api.example.com/users").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HttpPostStream`]: crate::service::HttpPostStream
+
+use crate::HttpResult;
+use crate::auth::{Authenticator, Refreshable};
+use crate::service::auth::authenticate;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{
+    BinaryResponse, HttpBinary, HttpGet, HttpGetResponse, HttpPost, HttpResponse,
+};
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{IntoUrl, Method, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+tokio::task_local! {
+    static REFRESHING: ();
+}
+
+/// Wraps an HTTP service, authenticates every request made through it,
+/// and replays requests that are rejected after refreshing the
+/// credentials.
+///
+/// See the [module documentation](crate::service::reauth) for details.
+#[derive(Debug)]
+pub struct ReauthService<S, A> {
+    inner: S,
+    auth: A,
+}
+
+impl<S, A: Refreshable> ReauthService<S, A> {
+    /// Wraps `inner` in a service that authenticates requests with `auth`,
+    /// and refreshes `auth` when a request is rejected.
+    pub fn new(inner: S, auth: A) -> Self {
+        Self { inner, auth }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The authenticator applied to each request.
+    pub fn authenticator(&self) -> &A {
+        &self.auth
+    }
+
+    /// Sends a request with `send`, refreshing the credentials and sending
+    /// it once more if `rejected` says the credentials were refused.
+    async fn send<R, F, Fut>(&self, rejected: fn(&HttpResult<R>) -> bool, send: F) -> HttpResult<R>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = HttpResult<R>>,
+    {
+        match send().await {
+            result if !rejected(&result) || is_refreshing() || !self.auth.can_refresh() => {
+                return result;
+            }
+            _ => {}
+        }
+        REFRESHING.scope((), self.auth.refresh()).await?;
+        send().await
+    }
+}
+
+/// True if the current task is refreshing credentials.
+fn is_refreshing() -> bool {
+    REFRESHING.try_with(|_| ()).is_ok()
+}
+
+/// True if `result` is an HTTP 401 error.
+fn is_unauthorized<R>(result: &HttpResult<R>) -> bool {
+    matches!(result, Err(err) if err.status() == Some(StatusCode::UNAUTHORIZED))
+}
+
+/// True if `result` is a response with an HTTP 401 status.
+fn is_unauthorized_response(result: &HttpResult<HttpResponse>) -> bool {
+    match result {
+        Ok(response) => response.status() == StatusCode::UNAUTHORIZED,
+        Err(_) => is_unauthorized(result),
+    }
+}
+
+impl<S: HttpGetResponse + Sync, A: Refreshable> HttpGet for ReauthService<S, A> {
+    /// Sends an authenticated GET request with the wrapped service's
+    /// [`HttpGetResponse`] implementation, as an
+    /// [`AuthService`](crate::service::auth::AuthService) does, and
+    /// returns the body of a successful response.
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str();
+        self.send(is_unauthorized, || async move {
+            let (uri, headers) = authenticate(&self.auth, Method::GET, uri, &HeaderMap::new())?;
+            let response = self.inner.get_response(uri, &headers).await?;
+            Ok(response.error_for_status()?.into_body())
+        })
+        .await
+    }
+}
+
+impl<S: HttpPost + Sync, A: Refreshable> HttpPost for ReauthService<S, A> {
+    async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str();
+        self.send(is_unauthorized, || self.inner.post(uri, &self.auth, data))
+            .await
+    }
+}
+
+impl<S: HttpGetResponse + Sync, A: Refreshable> HttpGetResponse for ReauthService<S, A> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str();
+        self.send(is_unauthorized_response, || async move {
+            let (uri, headers) = authenticate(&self.auth, Method::GET, uri, headers)?;
+            self.inner.get_response(uri, &headers).await
+        })
+        .await
+    }
+}
+
+impl<S: HttpBinary + Sync, A: Refreshable> HttpBinary for ReauthService<S, A> {
+    async fn get_binary<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str();
+        self.send(is_unauthorized, || async move {
+            let (uri, headers) = authenticate(&self.auth, Method::GET, uri, headers)?;
+            self.inner.get_binary(uri, &headers).await
+        })
+        .await
+    }
+
+    async fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str();
+        self.send(is_unauthorized, || {
+            let body = body.clone();
+            async move {
+                let (uri, headers) = authenticate(&self.auth, Method::POST, uri, headers)?;
+                self.inner.post_binary(uri, &headers, body).await
+            }
+        })
+        .await
+    }
+}
+
+impl<S: HttpCapabilities, A: Refreshable> HttpCapabilities for ReauthService<S, A> {
+    fn capabilities(&self) -> Capabilities {
+        let capabilities = self.inner.capabilities();
+        // Plain GET requests are sent as GetResponse requests.
+        let get = capabilities
+            .supports(Verb::GetResponse)
+            .then_some(Verb::Get);
+        let verbs: Vec<_> = [Verb::Post, Verb::GetResponse, Verb::Binary]
+            .into_iter()
+            .chain(get)
+            .collect();
+        capabilities
+            .limit_verbs(&verbs)
+            .wrap(Middleware::new("reauth").with_config(self.auth.scheme()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::auth::Auth;
+    use reqwest::Request;
+    use reqwest::header;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Sends `token-N` as a bearer token, where N is the number of times
+    /// it has been refreshed.
+    #[derive(Debug, Default)]
+    struct Rotating {
+        refreshes: AtomicUsize,
+    }
+
+    impl Rotating {
+        fn token(&self) -> String {
+            format!("token-{}", self.refreshes.load(Ordering::SeqCst))
+        }
+    }
+
+    impl Authenticator for Rotating {
+        fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+            Auth::new(self.token()).authenticate(request)
+        }
+    }
+
+    impl Refreshable for Rotating {
+        async fn refresh(&self) -> HttpResult<()> {
+            self.refreshes.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// Accepts only `accepted` as a bearer token, and records the tokens
+    /// of the requests it receives.
+    struct Api {
+        accepted: &'static str,
+        tokens: Mutex<Vec<String>>,
+    }
+
+    impl Api {
+        fn accepting(accepted: &'static str) -> Self {
+            let tokens = Mutex::default();
+            Self { accepted, tokens }
+        }
+
+        fn tokens(&self) -> Vec<String> {
+            self.tokens.lock().unwrap().clone()
+        }
+
+        fn respond(&self, headers: &HeaderMap) -> HttpResponse {
+            let token = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .unwrap_or_default()
+                .to_string();
+            let accepted = token == self.accepted;
+            self.tokens.lock().unwrap().push(token);
+            if accepted {
+                HttpResponse::new(StatusCode::OK, "ok")
+            } else {
+                HttpResponse::new(StatusCode::UNAUTHORIZED, "")
+            }
+        }
+    }
+
+    impl HttpPost for Api {
+        async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, _data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let mut request = Request::new(Method::POST, uri.into_url()?);
+            auth.authenticate(&mut request)?;
+            let response = self.respond(request.headers());
+            if response.status() == StatusCode::UNAUTHORIZED {
+                return Err(HttpError::Http(response.status()));
+            }
+            Ok(serde_json::from_str("null")?)
+        }
+    }
+
+    impl HttpGetResponse for Api {
+        async fn get_response<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+        where
+            U: IntoUrl + Send,
+        {
+            Ok(self.respond(headers))
+        }
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_rejected_credentials_and_replays_the_request() -> HttpResult<()> {
+        let service = ReauthService::new(Api::accepting("token-1"), Rotating::default());
+        let response = service.get_response("/users", &HeaderMap::new()).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(service.inner().tokens(), ["token-0", "token-1"]);
+
+        service.get_response("/users", &HeaderMap::new()).await?;
+        assert_eq!(service.inner().tokens().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_replays_rejected_gets() -> HttpResult<()> {
+        let service = ReauthService::new(Api::accepting("token-1"), Rotating::default());
+        assert_eq!(service.get("/users").await?, "ok");
+        assert_eq!(service.inner().tokens(), ["token-0", "token-1"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_replays_rejected_posts() -> HttpResult<()> {
+        let service = ReauthService::new(Api::accepting("token-1"), Rotating::default());
+        let uri = "https://api.example.com/users";
+        let _: () = service.post(uri, &Auth::new("ignored"), &()).await?;
+        assert_eq!(service.inner().tokens(), ["token-0", "token-1"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_only_replays_requests_once() -> HttpResult<()> {
+        let service = ReauthService::new(Api::accepting("token-5"), Rotating::default());
+        let response = service.get_response("/users", &HeaderMap::new()).await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(service.inner().tokens(), ["token-0", "token-1"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_does_not_replay_requests_made_while_refreshing() -> HttpResult<()> {
+        let service = ReauthService::new(Api::accepting("token-1"), Rotating::default());
+        let response = REFRESHING
+            .scope((), service.get_response("/token", &HeaderMap::new()))
+            .await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(service.inner().tokens(), ["token-0"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_does_not_replay_requests_it_cannot_refresh() -> HttpResult<()> {
+        #[derive(Debug)]
+        struct Fixed(Auth);
+
+        impl Authenticator for Fixed {
+            fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+                self.0.authenticate(request)
+            }
+        }
+
+        impl Refreshable for Fixed {
+            async fn refresh(&self) -> HttpResult<()> {
+                panic!("refreshed credentials that cannot be refreshed");
+            }
+
+            fn can_refresh(&self) -> bool {
+                false
+            }
+        }
+
+        let service = ReauthService::new(Api::accepting("token-0"), Fixed(Auth::new("fixed")));
+        let response = service.get_response("/users", &HeaderMap::new()).await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(service.inner().tokens(), ["fixed"]);
+        Ok(())
+    }
+}
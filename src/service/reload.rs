@@ -0,0 +1,301 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Live reloading of service configuration.
+//!
+//! Long-running programs often need to pick up new configuration, such as
+//! rotated API keys, new base URLs, timeouts, or proxies, without being
+//! restarted. A [`Reloadable`] value can be replaced at any time; each
+//! request [loads](Reloadable::load) the current value once and uses it
+//! until it finishes, so changes apply atomically to subsequent requests
+//! without disturbing requests that are already in flight.
+//!
+//! A [`ReloadableService`] applies this to a whole service: build a new
+//! service from the new configuration, with a new client if the proxy
+//! changed, and [swap it in](ReloadableService::reload). Configuration can
+//! also be reloaded automatically whenever a file changes with
+//! [`Reloadable::watch_file()`].
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::reload::ReloadableService;
+//! use hypertyper::service::timeout::{TimeoutPolicy, TimeoutService};
+//! use std::time::Duration;
+//!
+//! # async fn run<S: HttpService + Clone + Send + Sync + 'static>(client: S) -> HttpResult<()> {
+//! // Builds a service from a configuration file that contains a timeout.
+//! let build = move |config: &str| -> HttpResult<TimeoutService<S>> {
+//!     let secs = config.trim().parse().unwrap_or(10);
+//!     let policy = TimeoutPolicy::new(Duration::from_secs(secs));
+//!     Ok(TimeoutService::new(client.clone(), policy))
+//! };
+//!
+//! let service = ReloadableService::new(build("10")?);
+//! service.handle().watch_file("timeout.conf", Duration::from_secs(5), build);
+//! let body = service.get("https://api.example.com/users").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{BodyStream, HttpGet, HttpGetResponse, HttpGetStream, HttpPost, HttpResponse};
+use reqwest::IntoUrl;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// A shared value that can be replaced while it is in use.
+///
+/// Clones share the same value, so one clone can be kept to
+/// [`store()`](Self::store) new values while others are used to
+/// [`load()`](Self::load) them.
+#[derive(Debug)]
+pub struct Reloadable<T> {
+    current: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Reloadable<T> {
+    /// Creates a reloadable value.
+    pub fn new(value: T) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(value))),
+        }
+    }
+
+    /// The current value.
+    ///
+    /// The value stays valid, and unchanged, for as long as it is held,
+    /// even if a new value is stored in the meantime.
+    pub fn load(&self) -> Arc<T> {
+        Arc::clone(&self.current.read().unwrap())
+    }
+
+    /// Replaces the current value.
+    pub fn store(&self, value: T) {
+        *self.current.write().unwrap() = Arc::new(value);
+    }
+}
+
+impl<T: Send + Sync + 'static> Reloadable<T> {
+    /// Checks the file at `path` every `interval`, and whenever its
+    /// contents change, stores the value that `parse` produces from them.
+    ///
+    /// If the file cannot be read or `parse` fails, the current value is
+    /// kept, and the file is tried again once it changes. The file is
+    /// watched until the returned task is aborted.
+    pub fn watch_file<F>(
+        &self,
+        path: impl Into<PathBuf>,
+        interval: Duration,
+        parse: F,
+    ) -> JoinHandle<()>
+    where
+        F: Fn(&str) -> HttpResult<T> + Send + 'static,
+    {
+        let path = path.into();
+        let reloadable = self.clone();
+        tokio::spawn(async move {
+            let mut last_seen = None;
+            loop {
+                if let Ok(contents) = tokio::fs::read_to_string(&path).await {
+                    if last_seen.as_ref() != Some(&contents) {
+                        if let Ok(value) = parse(&contents) {
+                            reloadable.store(value);
+                        }
+                        last_seen = Some(contents);
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+/// Wraps an HTTP service that can be replaced while requests are in
+/// flight.
+///
+/// See the [module documentation](crate::service::reload) for details.
+#[derive(Clone, Debug)]
+pub struct ReloadableService<S> {
+    service: Reloadable<S>,
+}
+
+impl<S> ReloadableService<S> {
+    /// Wraps `service` so that it can be replaced later.
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Reloadable::new(service),
+        }
+    }
+
+    /// Replaces the wrapped service for all subsequent requests.
+    pub fn reload(&self, service: S) {
+        self.service.store(service);
+    }
+
+    /// The current wrapped service.
+    pub fn current(&self) -> Arc<S> {
+        self.service.load()
+    }
+
+    /// A handle that shares the wrapped service, which can be used to
+    /// replace it from elsewhere, such as a task that watches a
+    /// configuration file.
+    pub fn handle(&self) -> Reloadable<S> {
+        self.service.clone()
+    }
+}
+
+impl<S: HttpGet + Send + Sync> HttpGet for ReloadableService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        self.current().get(uri).await
+    }
+}
+
+impl<S: HttpPost + Send + Sync> HttpPost for ReloadableService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        self.current().post(uri, auth, data).await
+    }
+}
+
+impl<S: HttpGetResponse + Send + Sync> HttpGetResponse for ReloadableService<S> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.current().get_response(uri, headers).await
+    }
+}
+
+impl<S: HttpGetStream + Send + Sync> HttpGetStream for ReloadableService<S> {
+    async fn get_stream<U>(&self, uri: U) -> HttpResult<BodyStream>
+    where
+        U: IntoUrl + Send,
+    {
+        self.current().get_stream(uri).await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for ReloadableService<S> {
+    /// Reports the capabilities of the current wrapped service.
+    fn capabilities(&self) -> Capabilities {
+        self.current()
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post, Verb::GetResponse, Verb::GetStream])
+            .wrap(Middleware::new("reloadable"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use tokio::sync::Notify;
+
+    /// Returns its name, optionally waiting to be released first.
+    struct NamedService {
+        name: String,
+        release: Option<Arc<Notify>>,
+    }
+
+    impl NamedService {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                release: None,
+            }
+        }
+    }
+
+    impl HttpGet for NamedService {
+        async fn get<U>(&self, _uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            if let Some(release) = &self.release {
+                release.notified().await;
+            }
+            Ok(self.name.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_applies_reloads_to_subsequent_requests() -> HttpResult<()> {
+        let release = Arc::new(Notify::new());
+        let slow = NamedService {
+            name: String::from("v1"),
+            release: Some(Arc::clone(&release)),
+        };
+        let service = ReloadableService::new(slow);
+        let in_flight = service.get("/config");
+        tokio::pin!(in_flight);
+        // Start the request so that it holds on to the first service.
+        assert!(futures_util::poll!(in_flight.as_mut()).is_pending());
+
+        service.handle().store(NamedService::new("v2"));
+        assert_eq!(service.get("/config").await?, "v2");
+        release.notify_one();
+        assert_eq!(in_flight.await?, "v1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reloads_when_a_file_changes() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("service.conf");
+        std::fs::write(&path, "v1")?;
+
+        let service = ReloadableService::new(NamedService::new("initial"));
+        let watcher = service
+            .handle()
+            .watch_file(&path, Duration::from_millis(5), |config| {
+                match config.trim() {
+                    "" => Err(HttpError::InvalidToken(String::from("empty config"))),
+                    name => Ok(NamedService::new(name)),
+                }
+            });
+
+        async fn wait_for(service: &ReloadableService<NamedService>, name: &str) {
+            for _ in 0..200 {
+                if service.current().name == name {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            panic!("service was never reloaded to {name}");
+        }
+
+        wait_for(&service, "v1").await;
+        std::fs::write(&path, "")?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(service.get("/config").await?, "v1");
+        std::fs::write(&path, "v2")?;
+        wait_for(&service, "v2").await;
+        watcher.abort();
+        Ok(())
+    }
+}
@@ -0,0 +1,460 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Automatically retry failed requests.
+//!
+//! A [`RetryService`] wraps another HTTP service and retries requests that
+//! fail with a [retryable error](crate::HttpError::is_retryable), waiting
+//! between attempts according to a [`RetryPolicy`].
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::retry::{RetryPolicy, RetryService};
+//! use std::time::Duration;
+//!
+//! fn with_retries<S: HttpService>(service: S) -> RetryService<S> {
+//!     let policy = RetryPolicy::new()
+//!         .with_max_attempts(5)
+//!         .with_backoff(Duration::from_millis(250), Duration::from_secs(30));
+//!     RetryService::new(service, policy)
+//! }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost};
+use crate::{HttpError, HttpResult};
+use reqwest::{IntoUrl, Method};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+type RetryPredicate = Arc<dyn Fn(&HttpError) -> bool + Send + Sync>;
+
+/// Determines when and how often a [`RetryService`] retries a request.
+///
+/// By default, a request is attempted up to 3 times, starting with a 100 ms
+/// delay that doubles after each attempt, up to a maximum of 10 seconds.
+/// Random jitter is applied to each delay. If the server responds with a
+/// [`Retry-After`](HttpError::RetryAfter) delay, that delay is used instead,
+/// up to a maximum of 60 seconds. Only
+/// [retryable errors](HttpError::is_retryable) are retried, and only for
+/// idempotent methods like GET; POST requests are never retried unless
+/// [`with_non_idempotent_retries()`](RetryPolicy::with_non_idempotent_retries)
+/// is enabled.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_retry_after: Duration,
+    multiplier: u32,
+    jitter: bool,
+    retry_non_idempotent: bool,
+    retry_on: RetryPredicate,
+}
+
+impl RetryPolicy {
+    /// Creates the default retry policy.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            max_retry_after: Duration::from_secs(60),
+            multiplier: 2,
+            jitter: true,
+            retry_non_idempotent: false,
+            retry_on: Arc::new(HttpError::is_retryable),
+        }
+    }
+
+    /// Sets the maximum number of times a request is attempted, including
+    /// the first attempt.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry and the maximum delay between
+    /// any two attempts.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets the longest the service will wait when a server asks it to
+    /// [retry after](HttpError::retry_after) a given amount of time.
+    ///
+    /// Longer delays requested by the server are shortened to this maximum.
+    pub fn with_max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+
+    /// Sets the factor by which the delay grows after each attempt.
+    pub fn with_multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Enables or disables random jitter.
+    ///
+    /// With jitter enabled, each delay is a random duration between zero
+    /// and the computed backoff, which keeps many clients from retrying in
+    /// lockstep.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Allows requests using non-idempotent methods, such as POST, to be
+    /// retried.
+    ///
+    /// Only enable this if the server can safely handle receiving the same
+    /// request more than once.
+    pub fn with_non_idempotent_retries(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Sets the predicate that decides whether an error should be retried.
+    ///
+    /// The default predicate is [`HttpError::is_retryable()`].
+    pub fn retry_on<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&HttpError) -> bool + Send + Sync + 'static,
+    {
+        self.retry_on = Arc::new(predicate);
+        self
+    }
+
+    /// The maximum number of times a request is attempted.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// The delay before the next attempt, given the number of attempts that
+    /// have already been made, ignoring jitter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hypertyper::service::retry::RetryPolicy;
+    /// # use std::time::Duration;
+    /// let policy = RetryPolicy::new();
+    /// assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    /// assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    /// assert_eq!(policy.backoff(3), Duration::from_millis(400));
+    /// ```
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1);
+        let factor = self.multiplier.saturating_pow(exponent);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Whether a request using `method` that failed with `err` after
+    /// `attempts` attempts should be tried again.
+    pub fn should_retry(&self, method: &Method, err: &HttpError, attempts: u32) -> bool {
+        attempts < self.max_attempts
+            && (self.retry_non_idempotent || method.is_idempotent())
+            && (self.retry_on)(err)
+    }
+
+    pub(crate) fn delay(&self, attempts: u32, err: &HttpError) -> Duration {
+        if let Some(delay) = err.retry_after() {
+            return delay.min(self.max_retry_after);
+        }
+        let backoff = self.backoff(attempts);
+        if self.jitter {
+            backoff.mul_f64(fastrand::f64())
+        } else {
+            backoff
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("max_retry_after", &self.max_retry_after)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("retry_non_idempotent", &self.retry_non_idempotent)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wraps an HTTP service and retries failed requests according to a
+/// [`RetryPolicy`].
+#[derive(Debug)]
+pub struct RetryService<S> {
+    inner: S,
+    policy: RetryPolicy,
+}
+
+impl<S> RetryService<S> {
+    /// Wraps `inner` in a service that retries requests using `policy`.
+    pub fn new(inner: S, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy used to retry requests.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for RetryService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        let mut attempts = 1;
+        loop {
+            let delay = match self.inner.get(uri.as_str()).await {
+                Err(err) if self.policy.should_retry(&Method::GET, &err, attempts) => {
+                    self.policy.delay(attempts, &err)
+                }
+                result => return result,
+            };
+            tokio::time::sleep(delay).await;
+            attempts += 1;
+        }
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for RetryService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str().to_string();
+        let mut attempts = 1;
+        loop {
+            let delay = match self.inner.post(uri.as_str(), auth, data).await {
+                Err(err) if self.policy.should_retry(&Method::POST, &err, attempts) => {
+                    self.policy.delay(attempts, &err)
+                }
+                result => return result,
+            };
+            tokio::time::sleep(delay).await;
+            attempts += 1;
+        }
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for RetryService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("retry").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use reqwest::StatusCode;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::time::Instant;
+
+    struct FlakyService {
+        failures: u32,
+        status: StatusCode,
+        calls: AtomicU32,
+    }
+
+    impl FlakyService {
+        fn new(failures: u32, status: StatusCode) -> Self {
+            let calls = AtomicU32::new(0);
+            Self {
+                failures,
+                status,
+                calls,
+            }
+        }
+
+        fn respond<T>(&self, value: T) -> HttpResult<T> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+            if calls < self.failures {
+                Err(HttpError::Http(self.status))
+            } else {
+                Ok(value)
+            }
+        }
+    }
+
+    impl HttpGet for FlakyService {
+        async fn get<U>(&self, _uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            self.respond(String::from("ok"))
+        }
+    }
+
+    impl HttpPost for FlakyService {
+        async fn post<U, D, R>(
+            &self,
+            _uri: U,
+            _auth: &dyn Authenticator,
+            _data: &D,
+        ) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            self.respond(())?;
+            Ok(serde_json::from_str("null")?)
+        }
+    }
+
+    struct RetryAfterService {
+        inner: FlakyService,
+        delay: Duration,
+    }
+
+    impl HttpGet for RetryAfterService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            self.inner.get(uri).await.map_err(|err| match err {
+                HttpError::Http(status) => HttpError::RetryAfter(status, self.delay),
+                err => err,
+            })
+        }
+    }
+
+    fn immediate() -> RetryPolicy {
+        RetryPolicy::new().with_backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    fn retry(failures: u32, status: StatusCode, policy: RetryPolicy) -> RetryService<FlakyService> {
+        RetryService::new(FlakyService::new(failures, status), policy)
+    }
+
+    #[test]
+    fn it_caps_backoff_at_the_maximum() {
+        let policy = RetryPolicy::new()
+            .with_backoff(Duration::from_secs(1), Duration::from_secs(5))
+            .with_multiplier(3);
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(3));
+        assert_eq!(policy.backoff(3), Duration::from_secs(5));
+        assert_eq!(policy.backoff(100), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn it_retries_get_requests_until_they_succeed() -> HttpResult<()> {
+        let service = retry(2, StatusCode::SERVICE_UNAVAILABLE, immediate());
+        assert_eq!(service.get("/flaky").await?, "ok");
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_waits_as_long_as_the_server_asks() -> HttpResult<()> {
+        let delay = Duration::from_secs(5);
+        let service = RetryService::new(
+            RetryAfterService {
+                inner: FlakyService::new(1, StatusCode::TOO_MANY_REQUESTS),
+                delay,
+            },
+            immediate(),
+        );
+        let start = Instant::now();
+        service.get("/flaky").await?;
+        assert_eq!(start.elapsed(), delay);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_caps_the_retry_after_delay() -> HttpResult<()> {
+        let max = Duration::from_secs(2);
+        let service = RetryService::new(
+            RetryAfterService {
+                inner: FlakyService::new(1, StatusCode::SERVICE_UNAVAILABLE),
+                delay: Duration::from_secs(3600),
+            },
+            immediate().with_max_retry_after(max),
+        );
+        let start = Instant::now();
+        service.get("/flaky").await?;
+        assert_eq!(start.elapsed(), max);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_after_max_attempts() {
+        let service = retry(5, StatusCode::SERVICE_UNAVAILABLE, immediate());
+        let result = service.get("/flaky").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_non_retryable_errors() {
+        let service = retry(1, StatusCode::NOT_FOUND, immediate());
+        assert!(service.get("/flaky").await.is_err());
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn it_uses_a_custom_retry_predicate() -> HttpResult<()> {
+        let policy = immediate().retry_on(|err| matches!(err, HttpError::Http(_)));
+        let service = retry(1, StatusCode::NOT_FOUND, policy);
+        assert_eq!(service.get("/flaky").await?, "ok");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_post_requests_by_default() {
+        let auth = Auth::new("my-api-key");
+        let service = retry(1, StatusCode::SERVICE_UNAVAILABLE, immediate());
+        let result: HttpResult<()> = service.post("/flaky", &auth, &()).await;
+        assert!(result.is_err());
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn it_retries_post_requests_when_allowed() -> HttpResult<()> {
+        let auth = Auth::new("my-api-key");
+        let policy = immediate().with_non_idempotent_retries(true);
+        let service = retry(1, StatusCode::SERVICE_UNAVAILABLE, policy);
+        let _: () = service.post("/flaky", &auth, &()).await?;
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+}
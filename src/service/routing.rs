@@ -0,0 +1,317 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Routing of requests based on feature flags.
+//!
+//! Migrating a client from one version of an API to another is safest when
+//! it is done gradually. A [`RoutingService`] asks a flag evaluator which
+//! variant each request should use, and rewrites the request's URI for
+//! that variant, such as by sending it to a [different
+//! origin](RoutingPolicy::with_origin_variant). Requests for which the
+//! evaluator does not choose a variant are sent unchanged.
+//!
+//! The evaluator is any function of the request URI, so it can consult a
+//! feature flag service, configuration, or a [`Rollout`], which enables a
+//! variant for a stable percentage of keys, such as tenant IDs, with
+//! per-key overrides.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::routing::{Rollout, RoutingPolicy, RoutingService};
+//!
+//! fn migrating<S: HttpService>(service: S, tenant: &str) -> RoutingService<S> {
+//!     let rollout = Rollout::new(25.0).with_override("acme", true);
+//!     let use_v2 = rollout.is_enabled(tenant);
+//!     let policy = RoutingPolicy::new()
+//!         .with_origin_variant("v2", "https://v2.api.example.com")
+//!         .route_with(move |_uri| use_v2.then(|| String::from("v2")));
+//!     RoutingService::new(service, policy)
+//! }
+//! ```
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost, with_origin};
+use reqwest::{IntoUrl, Url};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+type Evaluator = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+type Rewrite = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Determines how a [`RoutingService`] rewrites each request.
+#[derive(Clone)]
+pub struct RoutingPolicy {
+    variants: HashMap<String, Rewrite>,
+    evaluator: Evaluator,
+}
+
+impl RoutingPolicy {
+    /// Creates a policy with no variants, which sends every request
+    /// unchanged.
+    pub fn new() -> Self {
+        Self {
+            variants: HashMap::new(),
+            evaluator: Arc::new(|_| None),
+        }
+    }
+
+    /// Adds a variant called `name` that rewrites URIs with `rewrite`.
+    pub fn with_variant<F>(mut self, name: impl Into<String>, rewrite: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.variants.insert(name.into(), Arc::new(rewrite));
+        self
+    }
+
+    /// Adds a variant called `name` that replaces the scheme, host, and
+    /// port of URIs with those of `origin`.
+    ///
+    /// # Panics
+    ///
+    /// If `origin` is not a valid absolute URL.
+    pub fn with_origin_variant(self, name: impl Into<String>, origin: &str) -> Self {
+        let origin = Url::parse(origin).expect("variant origin must be a valid URL");
+        self.with_variant(name, move |uri| with_origin(uri, &origin))
+    }
+
+    /// Sets the flag evaluator, which returns the name of the variant to
+    /// use for a request URI, or `None` to send the request unchanged.
+    pub fn route_with<F>(mut self, evaluator: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.evaluator = Arc::new(evaluator);
+        self
+    }
+
+    /// The name of the variant chosen for `uri`, if the evaluator chose a
+    /// variant that the policy has.
+    pub fn variant_for(&self, uri: &str) -> Option<String> {
+        (self.evaluator)(uri).filter(|name| self.variants.contains_key(name))
+    }
+
+    /// Rewrites `uri` for the variant the evaluator chooses.
+    pub fn route(&self, uri: &str) -> String {
+        let rewrite = (self.evaluator)(uri).and_then(|name| self.variants.get(&name));
+        match rewrite {
+            Some(rewrite) => rewrite(uri),
+            None => uri.to_string(),
+        }
+    }
+}
+
+impl Default for RoutingPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RoutingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut variants: Vec<_> = self.variants.keys().collect();
+        variants.sort();
+        f.debug_struct("RoutingPolicy")
+            .field("variants", &variants)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Enables a feature for a stable percentage of keys.
+///
+/// Each key, such as a tenant or user ID, is hashed into one of 10,000
+/// buckets, so the same key is always either in or out of the rollout, and
+/// raising the percentage only ever adds keys. Overrides enable or disable
+/// the feature for specific keys regardless of the percentage.
+#[derive(Clone, Debug, Default)]
+pub struct Rollout {
+    percentage: f64,
+    overrides: HashMap<String, bool>,
+}
+
+impl Rollout {
+    /// Creates a rollout that enables a feature for `percentage` percent of
+    /// keys, between 0 and 100.
+    pub fn new(percentage: f64) -> Self {
+        Self {
+            percentage: percentage.clamp(0.0, 100.0),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Always enables or disables the feature for `key`.
+    pub fn with_override(mut self, key: impl Into<String>, enabled: bool) -> Self {
+        self.overrides.insert(key.into(), enabled);
+        self
+    }
+
+    /// The percentage of keys the feature is enabled for.
+    pub fn percentage(&self) -> f64 {
+        self.percentage
+    }
+
+    /// Whether the feature is enabled for `key`.
+    pub fn is_enabled(&self, key: &str) -> bool {
+        if let Some(enabled) = self.overrides.get(key) {
+            return *enabled;
+        }
+        let bucket = fnv1a(key.as_bytes()) % 10_000;
+        (bucket as f64) < self.percentage * 100.0
+    }
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which, unlike the standard library's
+/// hashers, is stable across releases and processes.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Wraps an HTTP service and rewrites request URIs based on feature flags.
+///
+/// See the [module documentation](crate::service::routing) for details.
+#[derive(Clone, Debug)]
+pub struct RoutingService<S> {
+    inner: S,
+    policy: RoutingPolicy,
+}
+
+impl<S> RoutingService<S> {
+    /// Wraps `inner` in a service that routes requests according to
+    /// `policy`.
+    pub fn new(inner: S, policy: RoutingPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy that determines how requests are routed.
+    pub fn policy(&self) -> &RoutingPolicy {
+        &self.policy
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for RoutingService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = self.policy.route(uri.as_str());
+        self.inner.get(uri).await
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for RoutingService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = self.policy.route(uri.as_str());
+        self.inner.post(uri, auth, data).await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for RoutingService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("routing").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the URI it was asked for.
+    struct EchoService;
+
+    impl HttpGet for EchoService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            Ok(uri.as_str().to_string())
+        }
+    }
+
+    fn policy() -> RoutingPolicy {
+        RoutingPolicy::new()
+            .with_origin_variant("v2-host", "https://v2.example.com")
+            .with_variant("v2-path", |uri| uri.replacen("/v1/", "/v2/", 1))
+            .route_with(|uri| {
+                if uri.contains("/reports/") {
+                    Some(String::from("v2-host"))
+                } else if uri.contains("/users/") {
+                    Some(String::from("v2-path"))
+                } else if uri.contains("/teams/") {
+                    Some(String::from("v3"))
+                } else {
+                    None
+                }
+            })
+    }
+
+    #[tokio::test]
+    async fn it_rewrites_uris_for_the_chosen_variant() -> HttpResult<()> {
+        let service = RoutingService::new(EchoService, policy());
+        let body = service.get("https://api.example.com/v1/reports/1").await?;
+        assert_eq!(body, "https://v2.example.com/v1/reports/1");
+        let body = service.get("https://api.example.com/v1/users/1").await?;
+        assert_eq!(body, "https://api.example.com/v2/users/1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_leaves_unrouted_uris_alone() -> HttpResult<()> {
+        let service = RoutingService::new(EchoService, policy());
+        let body = service.get("https://api.example.com/v1/teams/1").await?;
+        assert_eq!(body, "https://api.example.com/v1/teams/1");
+        assert_eq!(service.policy().variant_for("/v1/teams/1"), None);
+        let body = service.get("https://api.example.com/v1/health").await?;
+        assert_eq!(body, "https://api.example.com/v1/health");
+        Ok(())
+    }
+
+    #[test]
+    fn it_rolls_out_to_a_stable_percentage_of_keys() {
+        let keys: Vec<_> = (0..10_000).map(|i| format!("tenant-{i}")).collect();
+        let enabled = |rollout: &Rollout| keys.iter().filter(|key| rollout.is_enabled(key)).count();
+
+        let quarter = Rollout::new(25.0);
+        let count = enabled(&quarter);
+        assert!((2_250..2_750).contains(&count), "{count} keys enabled");
+
+        // Raising the percentage only adds keys.
+        let half = Rollout::new(50.0);
+        assert!(
+            keys.iter()
+                .all(|key| !quarter.is_enabled(key) || half.is_enabled(key))
+        );
+        assert_eq!(enabled(&Rollout::new(0.0)), 0);
+        assert_eq!(enabled(&Rollout::new(100.0)), keys.len());
+    }
+
+    #[test]
+    fn it_applies_overrides() {
+        let rollout = Rollout::new(0.0).with_override("acme", true);
+        assert!(rollout.is_enabled("acme"));
+        assert!(!rollout.is_enabled("globex"));
+        let rollout = Rollout::new(100.0).with_override("globex", false);
+        assert!(!rollout.is_enabled("globex"));
+    }
+}
@@ -0,0 +1,644 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Shadow traffic for migrations between APIs.
+//!
+//! Before switching from one API provider or version to another, it helps
+//! to know whether the new one behaves the same way under real traffic. A
+//! [`ShadowService`] sends each request to a primary service, as usual,
+//! and also sends a copy of a sample of requests to a shadow service in
+//! the background. Callers only ever see the primary response; the shadow
+//! response is compared with it by a [`Comparator`], any [`Divergence`] is
+//! reported, and the results are tallied in [`ShadowStats`] to show how
+//! compatible the two services are.
+//!
+//! Only GET requests are shadowed by default. POST requests can be
+//! shadowed too, with separate credentials, by enabling
+//! [dual writes](ShadowPolicy::with_dual_writes); make sure the shadow
+//! service can safely receive them.
+//!
+//! Shadow requests are spawned as Tokio tasks, so they never slow down or
+//! fail primary requests.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::shadow::{Comparator, ShadowPolicy, ShadowService};
+//!
+//! fn shadowed<S: HttpService>(old: S, new: S) -> ShadowService<S, S> {
+//!     let policy = ShadowPolicy::new()
+//!         .with_sample_rate(0.1)
+//!         .with_shadow_origin("https://api.new-provider.example.com")
+//!         .with_comparator(Comparator::new().ignore("/meta/request_id"))
+//!         .on_divergence(|divergence| eprintln!("{divergence}"));
+//!     ShadowService::new(old, new, policy)
+//! }
+//! ```
+
+pub mod compare;
+
+pub use compare::{Comparator, Difference};
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost, with_origin};
+use crate::{HttpError, HttpResult};
+use reqwest::{IntoUrl, StatusCode, Url};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+type Selector = Arc<dyn Fn(&str, &str) -> bool + Send + Sync>;
+type Reporter = Arc<dyn Fn(&Divergence) + Send + Sync>;
+
+/// What a primary or shadow service returned for a request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// A successful response with the given body.
+    Response(String),
+
+    /// An error, with its HTTP status, if it has one.
+    Error {
+        /// The status of the error response, if there was a response.
+        status: Option<StatusCode>,
+
+        /// A description of the error.
+        message: String,
+    },
+}
+
+impl Outcome {
+    fn from_result(result: &HttpResult<String>) -> Self {
+        match result {
+            Ok(body) => Self::Response(body.clone()),
+            Err(err) => Self::Error {
+                status: err.status(),
+                message: err.to_string(),
+            },
+        }
+    }
+
+    /// The status of the outcome, which is 200 OK for any successful
+    /// response, since services only return the bodies of successful
+    /// responses.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Response(_) => Some(StatusCode::OK),
+            Self::Error { status, .. } => *status,
+        }
+    }
+
+    /// The body of a successful response.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            Self::Response(body) => Some(body),
+            Self::Error { .. } => None,
+        }
+    }
+}
+
+/// A difference between the primary and shadow responses to a request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// The HTTP method of the request.
+    pub method: &'static str,
+
+    /// The URI of the request sent to the primary service.
+    pub uri: String,
+
+    /// What the primary service returned.
+    pub primary: Outcome,
+
+    /// What the shadow service returned.
+    pub shadow: Outcome,
+
+    /// How the two outcomes differ.
+    pub difference: Difference,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} diverged: {}",
+            self.method, self.uri, self.difference
+        )
+    }
+}
+
+/// How closely a [`ShadowService`]'s shadow responses have matched its
+/// primary responses since it was created.
+///
+/// Requests that were not shadowed, and shadow requests whose primary
+/// request was abandoned, are not counted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShadowStats {
+    /// Requests whose primary and shadow responses were compared.
+    pub compared: u64,
+
+    /// Requests whose shadow response was equivalent to the primary one.
+    pub matched: u64,
+
+    /// Requests whose responses had different statuses.
+    pub status_mismatches: u64,
+
+    /// Requests whose responses had the same status but different bodies.
+    pub body_mismatches: u64,
+
+    /// The number of body mismatches at each JSON Pointer, with array
+    /// indices replaced by `*`, so that the fields that most often differ
+    /// stand out.
+    pub divergent_paths: BTreeMap<String, u64>,
+}
+
+impl ShadowStats {
+    /// The fraction of compared requests whose responses matched, between
+    /// 0 and 1.
+    pub fn match_ratio(&self) -> f64 {
+        if self.compared == 0 {
+            0.0
+        } else {
+            self.matched as f64 / self.compared as f64
+        }
+    }
+
+    fn record(&mut self, difference: Option<&Difference>) {
+        self.compared += 1;
+        match difference {
+            None => self.matched += 1,
+            Some(Difference::Status { .. }) => self.status_mismatches += 1,
+            Some(Difference::Body { paths }) => {
+                self.body_mismatches += 1;
+                for path in paths {
+                    *self.divergent_paths.entry(generalize(path)).or_default() += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the array indices in `pointer` with `*`.
+fn generalize(pointer: &str) -> String {
+    pointer
+        .split('/')
+        .map(|segment| {
+            if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+                "*"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Determines which requests a [`ShadowService`] copies to its shadow
+/// service, and what happens when the responses differ.
+pub struct ShadowPolicy {
+    sample_rate: f64,
+    shadow_origin: Option<Url>,
+    selector: Selector,
+    comparator: Arc<Comparator>,
+    reporter: Option<Reporter>,
+    dual_write_auth: Option<Arc<dyn Authenticator>>,
+}
+
+impl ShadowPolicy {
+    /// Creates a policy that copies every GET request to the shadow service
+    /// and ignores divergences.
+    pub fn new() -> Self {
+        Self {
+            sample_rate: 1.0,
+            shadow_origin: None,
+            selector: Arc::new(|_, _| true),
+            comparator: Arc::default(),
+            reporter: None,
+            dual_write_auth: None,
+        }
+    }
+
+    /// Only copies a random fraction of requests, between 0 and 1.
+    pub fn with_sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sends shadow requests with their scheme, host, and port replaced by
+    /// those of `origin`.
+    ///
+    /// # Panics
+    ///
+    /// If `origin` is not a valid absolute URL.
+    pub fn with_shadow_origin(mut self, origin: &str) -> Self {
+        let origin = Url::parse(origin).expect("shadow origin must be a valid URL");
+        self.shadow_origin = Some(origin);
+        self
+    }
+
+    /// Only copies requests for which `selector` returns true.
+    ///
+    /// `selector` is called with the HTTP method and the URI of each
+    /// request.
+    pub fn shadow_when<F>(mut self, selector: F) -> Self
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        self.selector = Arc::new(selector);
+        self
+    }
+
+    /// Compares primary and shadow responses with `comparator`.
+    ///
+    /// By default, statuses and entire bodies are compared.
+    pub fn with_comparator(mut self, comparator: Comparator) -> Self {
+        self.comparator = Arc::new(comparator);
+        self
+    }
+
+    /// Calls `reporter` whenever the shadow response differs from the
+    /// primary response.
+    pub fn on_divergence<F>(mut self, reporter: F) -> Self
+    where
+        F: Fn(&Divergence) + Send + Sync + 'static,
+    {
+        self.reporter = Some(Arc::new(reporter));
+        self
+    }
+
+    /// Copies POST requests too, authenticating them to the shadow service
+    /// with `auth`.
+    pub fn with_dual_writes(mut self, auth: impl Authenticator + 'static) -> Self {
+        self.dual_write_auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// Rewrites `uri` for the shadow service.
+    pub fn shadow_uri(&self, uri: &str) -> String {
+        match &self.shadow_origin {
+            Some(origin) => with_origin(uri, origin),
+            None => uri.to_string(),
+        }
+    }
+
+    fn should_shadow(&self, method: &str, uri: &str) -> bool {
+        if method == "POST" && self.dual_write_auth.is_none() {
+            return false;
+        }
+        (self.selector)(method, uri) && fastrand::f64() < self.sample_rate
+    }
+}
+
+impl Default for ShadowPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ShadowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShadowPolicy")
+            .field("sample_rate", &self.sample_rate)
+            .field("shadow_origin", &self.shadow_origin)
+            .field("comparator", &self.comparator)
+            .field("dual_writes", &self.dual_write_auth.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sends requests to a primary service and copies a sample of them to a
+/// shadow service in the background.
+///
+/// See the [module documentation](crate::service::shadow) for details.
+#[derive(Debug)]
+pub struct ShadowService<P, S> {
+    primary: P,
+    shadow: Arc<S>,
+    policy: ShadowPolicy,
+    stats: Arc<Mutex<ShadowStats>>,
+}
+
+impl<P, S> ShadowService<P, S> {
+    /// Creates a service that sends requests to `primary` and copies them
+    /// to `shadow` according to `policy`.
+    pub fn new(primary: P, shadow: S, policy: ShadowPolicy) -> Self {
+        Self {
+            primary,
+            shadow: Arc::new(shadow),
+            policy,
+            stats: Arc::default(),
+        }
+    }
+
+    /// The primary service.
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    /// The shadow service.
+    pub fn shadow(&self) -> &S {
+        &self.shadow
+    }
+
+    /// The policy that determines which requests are shadowed.
+    pub fn policy(&self) -> &ShadowPolicy {
+        &self.policy
+    }
+
+    /// Statistics about how closely the shadow responses have matched.
+    ///
+    /// Comparisons happen in the background, so the statistics may lag
+    /// slightly behind the primary responses.
+    pub fn stats(&self) -> ShadowStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Spawns a task that waits for the shadow request and the primary
+    /// outcome, compares them, and reports any divergence, and returns the
+    /// sender for the primary outcome.
+    fn spawn_comparison<F>(
+        &self,
+        method: &'static str,
+        uri: &str,
+        shadow: F,
+    ) -> oneshot::Sender<Outcome>
+    where
+        F: Future<Output = HttpResult<String>> + Send + 'static,
+    {
+        let (primary_tx, primary_rx) = oneshot::channel::<Outcome>();
+        let comparator = Arc::clone(&self.policy.comparator);
+        let reporter = self.policy.reporter.clone();
+        let stats = Arc::clone(&self.stats);
+        let uri = uri.to_string();
+        tokio::spawn(async move {
+            let shadow = Outcome::from_result(&shadow.await);
+            // The caller may have given up on the primary request.
+            let Ok(primary) = primary_rx.await else {
+                return;
+            };
+            let difference = comparator.compare(&primary, &shadow);
+            stats.lock().unwrap().record(difference.as_ref());
+            if let (Some(reporter), Some(difference)) = (reporter, difference) {
+                reporter(&Divergence {
+                    method,
+                    uri,
+                    primary,
+                    shadow,
+                    difference,
+                });
+            }
+        });
+        primary_tx
+    }
+}
+
+impl<P, S> HttpGet for ShadowService<P, S>
+where
+    P: HttpGet + Sync,
+    S: HttpGet + Send + Sync + 'static,
+{
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        if !self.policy.should_shadow("GET", &uri) {
+            return self.primary.get(uri).await;
+        }
+        let shadow = Arc::clone(&self.shadow);
+        let shadow_uri = self.policy.shadow_uri(&uri);
+        let primary_tx =
+            self.spawn_comparison("GET", &uri, async move { shadow.get(shadow_uri).await });
+        let result = self.primary.get(uri.as_str()).await;
+        let _ = primary_tx.send(Outcome::from_result(&result));
+        result
+    }
+}
+
+impl<P, S> HttpPost for ShadowService<P, S>
+where
+    P: HttpPost + Sync,
+    S: HttpPost + Send + Sync + 'static,
+{
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str().to_string();
+        let shadow_auth = match &self.policy.dual_write_auth {
+            Some(auth) if self.policy.should_shadow("POST", &uri) => Arc::clone(auth),
+            _ => return self.primary.post(uri, auth, data).await,
+        };
+        let body = serde_json::to_value(data)?;
+        let shadow = Arc::clone(&self.shadow);
+        let shadow_uri = self.policy.shadow_uri(&uri);
+        let primary_tx = self.spawn_comparison("POST", &uri, async move {
+            let response: Value = shadow.post(shadow_uri, &*shadow_auth, &body).await?;
+            Ok(response.to_string())
+        });
+        let result: HttpResult<Value> = self.primary.post(uri.as_str(), auth, data).await;
+        let outcome = match &result {
+            Ok(value) => Outcome::Response(value.to_string()),
+            Err(err) => Outcome::Error {
+                status: err.status(),
+                message: err.to_string(),
+            },
+        };
+        let _ = primary_tx.send(outcome);
+        let value = result?;
+        serde_json::from_value(value).map_err(HttpError::from)
+    }
+}
+
+impl<P, S> HttpCapabilities for ShadowService<P, S>
+where
+    P: HttpCapabilities,
+    S: HttpCapabilities,
+{
+    fn capabilities(&self) -> Capabilities {
+        let shadow = Middleware::new("shadow")
+            .with_config(format!("{:?}", self.policy))
+            .with_branch("shadow", self.shadow.capabilities());
+        self.primary
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(shadow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Returns a fixed result and records the URIs it was asked for.
+    struct StubService {
+        result: Result<&'static str, StatusCode>,
+        uris: Mutex<Vec<String>>,
+    }
+
+    impl StubService {
+        fn new(result: Result<&'static str, StatusCode>) -> Self {
+            Self {
+                result,
+                uris: Mutex::default(),
+            }
+        }
+
+        fn uris(&self) -> Vec<String> {
+            self.uris.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpGet for StubService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            self.uris.lock().unwrap().push(uri.as_str().to_string());
+            self.result.map(String::from).map_err(HttpError::Http)
+        }
+    }
+
+    impl HttpPost for StubService {
+        async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, _data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let body = self.get(uri).await?;
+            Ok(serde_json::from_str(&body)?)
+        }
+    }
+
+    fn recording_policy() -> (ShadowPolicy, Arc<Mutex<Vec<Divergence>>>) {
+        let divergences = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&divergences);
+        let policy = ShadowPolicy::new()
+            .with_shadow_origin("https://shadow.example.com")
+            .on_divergence(move |divergence| sink.lock().unwrap().push(divergence.clone()));
+        (policy, divergences)
+    }
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_primary_response_and_reports_divergences() -> HttpResult<()> {
+        let (policy, divergences) = recording_policy();
+        let primary = StubService::new(Ok(r#"{"id":1}"#));
+        let shadow = StubService::new(Err(StatusCode::NOT_FOUND));
+        let service = ShadowService::new(primary, shadow, policy);
+
+        let body = service.get("https://api.example.com/users/1").await?;
+        assert_eq!(body, r#"{"id":1}"#);
+        settle().await;
+
+        assert_eq!(
+            service.shadow().uris(),
+            ["https://shadow.example.com/users/1"]
+        );
+        let divergences = divergences.lock().unwrap();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].uri, "https://api.example.com/users/1");
+        assert_eq!(divergences[0].primary.body(), Some(r#"{"id":1}"#));
+        assert_eq!(divergences[0].shadow.status(), Some(StatusCode::NOT_FOUND));
+        assert!(matches!(
+            divergences[0].difference,
+            Difference::Status { .. }
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_does_not_report_matching_responses() -> HttpResult<()> {
+        let (policy, divergences) = recording_policy();
+        let primary = StubService::new(Ok("same"));
+        let service = ShadowService::new(primary, StubService::new(Ok("same")), policy);
+        service.get("/users").await?;
+        settle().await;
+        assert_eq!(service.shadow().uris().len(), 1);
+        assert!(divergences.lock().unwrap().is_empty());
+        assert_eq!(service.stats().matched, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_only_shadows_selected_and_sampled_requests() -> HttpResult<()> {
+        let policy = ShadowPolicy::new().shadow_when(|_, uri| uri.contains("/users"));
+        let service =
+            ShadowService::new(StubService::new(Ok("")), StubService::new(Ok("")), policy);
+        service.get("/users").await?;
+        service.get("/teams").await?;
+        settle().await;
+        assert_eq!(service.shadow().uris(), ["/users"]);
+
+        let policy = ShadowPolicy::new().with_sample_rate(0.0);
+        let service =
+            ShadowService::new(StubService::new(Ok("")), StubService::new(Ok("")), policy);
+        service.get("/users").await?;
+        settle().await;
+        assert!(service.shadow().uris().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_only_shadows_posts_with_dual_writes() -> HttpResult<()> {
+        let auth = Auth::new("key");
+        let primary = StubService::new(Ok(r#"{"id":1}"#));
+        let service = ShadowService::new(primary, StubService::new(Ok("{}")), ShadowPolicy::new());
+        let _: Value = service.post("/users", &auth, &()).await?;
+        settle().await;
+        assert!(service.shadow().uris().is_empty());
+
+        let (policy, divergences) = recording_policy();
+        let policy = policy.with_dual_writes(Auth::new("shadow-key"));
+        let primary = StubService::new(Ok(r#"{"id": 1}"#));
+        let service = ShadowService::new(primary, StubService::new(Ok(r#"{"id":2}"#)), policy);
+        let response: Value = service.post("/users", &auth, &()).await?;
+        assert_eq!(response["id"], 1);
+        settle().await;
+        assert_eq!(service.shadow().uris().len(), 1);
+        let divergences = divergences.lock().unwrap();
+        assert_eq!(divergences[0].method, "POST");
+        assert_eq!(divergences[0].primary.body(), Some(r#"{"id":1}"#));
+        let paths = vec![String::from("/id")];
+        assert_eq!(divergences[0].difference, Difference::Body { paths });
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_aggregates_divergence_statistics() -> HttpResult<()> {
+        let comparator = Comparator::new().ignore("/updated_at");
+        let policy = ShadowPolicy::new().with_comparator(comparator);
+        let primary = StubService::new(Ok(r#"{"items": [{"id": 1}], "updated_at": 1}"#));
+        let shadow = StubService::new(Ok(r#"{"items": [{"id": 2}], "updated_at": 2}"#));
+        let service = ShadowService::new(primary, shadow, policy);
+        service.get("/items").await?;
+        service.get("/items").await?;
+        settle().await;
+
+        let stats = service.stats();
+        assert_eq!(stats.compared, 2);
+        assert_eq!(stats.body_mismatches, 2);
+        assert_eq!(stats.match_ratio(), 0.0);
+        assert_eq!(stats.divergent_paths["/items/*/id"], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn it_generalizes_array_indices() {
+        assert_eq!(generalize("/items/12/tags/0"), "/items/*/tags/*");
+        assert_eq!(generalize(""), "");
+    }
+}
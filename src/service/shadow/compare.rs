@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Comparing primary and shadow responses.
+//!
+//! A [`Comparator`] decides whether a shadow response is equivalent to the
+//! primary response. Statuses must match. JSON bodies are compared
+//! structurally, so whitespace and the order of keys do not matter, and
+//! fields that are expected to differ between the two services, such as
+//! timestamps or generated IDs, can be [ignored](Comparator::ignore). Other
+//! bodies are compared as text, after an optional
+//! [normalization](Comparator::normalize_with) step.
+//!
+//! # Examples
+//!
+//! ```
+//! use hypertyper::service::shadow::{Comparator, Difference, Outcome};
+//!
+//! let comparator = Comparator::new().ignore("/items/*/updated_at");
+//! let primary = Outcome::Response(r#"{"items": [{"id": 1, "updated_at": 10}]}"#.into());
+//! let shadow = Outcome::Response(r#"{"items":[{"updated_at":20,"id":1}]}"#.into());
+//! assert_eq!(comparator.compare(&primary, &shadow), None);
+//!
+//! let shadow = Outcome::Response(r#"{"items": [{"id": 2}]}"#.into());
+//! let difference = comparator.compare(&primary, &shadow).unwrap();
+//! assert_eq!(difference, Difference::Body { paths: vec!["/items/0/id".into()] });
+//! ```
+
+use super::Outcome;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+type Normalizer = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// How a shadow response differed from the primary response.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Difference {
+    /// The responses had different statuses.
+    Status {
+        /// The status of the primary response, if there was one.
+        primary: Option<StatusCode>,
+
+        /// The status of the shadow response, if there was one.
+        shadow: Option<StatusCode>,
+    },
+
+    /// The responses had the same status but different bodies.
+    Body {
+        /// [JSON Pointers](https://www.rfc-editor.org/rfc/rfc6901) to the
+        /// values that differ, or a single empty pointer, referring to the
+        /// whole body, if the bodies are not both JSON.
+        paths: Vec<String>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = |status: &Option<StatusCode>| {
+            status.map_or_else(|| String::from("no response"), |status| status.to_string())
+        };
+        match self {
+            Self::Status { primary, shadow } => {
+                write!(f, "status {} != {}", status(primary), status(shadow))
+            }
+            Self::Body { paths } if paths.iter().all(String::is_empty) => {
+                write!(f, "bodies differ")
+            }
+            Self::Body { paths } => write!(f, "bodies differ at {}", paths.join(", ")),
+        }
+    }
+}
+
+/// Decides whether primary and shadow responses are equivalent.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone, Default)]
+pub struct Comparator {
+    ignored: Vec<Vec<String>>,
+    normalizer: Option<Normalizer>,
+}
+
+impl Comparator {
+    /// Creates a comparator that compares statuses and entire bodies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignores the JSON value at `pointer`, and everything inside it, when
+    /// comparing JSON bodies.
+    ///
+    /// `pointer` is a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901),
+    /// such as `/meta/request_id`. A `*` segment matches any key or array
+    /// index, so `/items/*/updated_at` ignores the `updated_at` field of
+    /// every item.
+    pub fn ignore(mut self, pointer: &str) -> Self {
+        self.ignored.push(segments(pointer));
+        self
+    }
+
+    /// Transforms bodies with `normalizer` before comparing them, such as
+    /// to strip out a timestamp embedded in an HTML page.
+    pub fn normalize_with<F>(mut self, normalizer: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.normalizer = Some(Arc::new(normalizer));
+        self
+    }
+
+    /// Compares two outcomes, returning how they differ, or `None` if they
+    /// are equivalent.
+    ///
+    /// Errors with the same status are equivalent, even if their messages
+    /// differ.
+    pub fn compare(&self, primary: &Outcome, shadow: &Outcome) -> Option<Difference> {
+        let (primary_body, shadow_body) = match (primary.body(), shadow.body()) {
+            (Some(primary), Some(shadow)) => (primary, shadow),
+            _ if primary.status() == shadow.status() => return None,
+            _ => {
+                return Some(Difference::Status {
+                    primary: primary.status(),
+                    shadow: shadow.status(),
+                });
+            }
+        };
+        let (primary_body, shadow_body) = match &self.normalizer {
+            Some(normalize) => (normalize(primary_body), normalize(shadow_body)),
+            None => (primary_body.to_string(), shadow_body.to_string()),
+        };
+
+        let paths = match (
+            serde_json::from_str::<Value>(&primary_body),
+            serde_json::from_str::<Value>(&shadow_body),
+        ) {
+            (Ok(primary), Ok(shadow)) => {
+                let mut paths = Vec::new();
+                self.diff(&primary, &shadow, &mut Vec::new(), &mut paths);
+                paths
+            }
+            _ if primary_body == shadow_body => Vec::new(),
+            _ => vec![String::new()],
+        };
+        if paths.is_empty() {
+            None
+        } else {
+            Some(Difference::Body { paths })
+        }
+    }
+
+    /// Appends the pointers to the values that differ between `a` and `b`,
+    /// which are at `path`, to `paths`.
+    fn diff(&self, a: &Value, b: &Value, path: &mut Vec<String>, paths: &mut Vec<String>) {
+        if self.is_ignored(path) {
+            return;
+        }
+        match (a, b) {
+            (Value::Object(a), Value::Object(b)) => {
+                let keys: BTreeSet<_> = a.keys().chain(b.keys()).collect();
+                for key in keys {
+                    path.push(key.clone());
+                    match (a.get(key), b.get(key)) {
+                        (Some(a), Some(b)) => self.diff(a, b, path, paths),
+                        _ if self.is_ignored(path) => {}
+                        _ => paths.push(pointer(path)),
+                    }
+                    path.pop();
+                }
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                for i in 0..a.len().max(b.len()) {
+                    path.push(i.to_string());
+                    match (a.get(i), b.get(i)) {
+                        (Some(a), Some(b)) => self.diff(a, b, path, paths),
+                        _ if self.is_ignored(path) => {}
+                        _ => paths.push(pointer(path)),
+                    }
+                    path.pop();
+                }
+            }
+            _ if a != b => paths.push(pointer(path)),
+            _ => {}
+        }
+    }
+
+    fn is_ignored(&self, path: &[String]) -> bool {
+        self.ignored.iter().any(|ignored| {
+            ignored.len() <= path.len()
+                && ignored
+                    .iter()
+                    .zip(path)
+                    .all(|(ignored, segment)| ignored == "*" || ignored == segment)
+        })
+    }
+}
+
+impl fmt::Debug for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ignored: Vec<_> = self.ignored.iter().map(|path| pointer(path)).collect();
+        f.debug_struct("Comparator")
+            .field("ignored", &ignored)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Splits a JSON Pointer into its unescaped segments.
+fn segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Joins unescaped segments into a JSON Pointer.
+fn pointer(segments: &[String]) -> String {
+    segments.iter().fold(String::new(), |mut pointer, segment| {
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        pointer
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> Outcome {
+        Outcome::Response(body.to_string())
+    }
+
+    fn error(status: StatusCode, message: &str) -> Outcome {
+        Outcome::Error {
+            status: Some(status),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_compares_statuses() {
+        let comparator = Comparator::new();
+        let not_found = error(StatusCode::NOT_FOUND, "missing");
+        assert_eq!(
+            comparator.compare(&response("{}"), &not_found),
+            Some(Difference::Status {
+                primary: Some(StatusCode::OK),
+                shadow: Some(StatusCode::NOT_FOUND),
+            })
+        );
+        let other = error(StatusCode::NOT_FOUND, "not here");
+        assert_eq!(comparator.compare(&not_found, &other), None);
+    }
+
+    #[test]
+    fn it_lists_the_paths_that_differ_in_json_bodies() {
+        let comparator = Comparator::new();
+        let primary = response(r#"{"a": 1, "b": {"c": [1, 2]}, "d/e": true}"#);
+        let shadow = response(r#"{"b": {"c": [1, 3, 4]}, "d/e": false, "f": null}"#);
+        assert_eq!(
+            comparator.compare(&primary, &shadow),
+            Some(Difference::Body {
+                paths: vec![
+                    "/a".into(),
+                    "/b/c/1".into(),
+                    "/b/c/2".into(),
+                    "/d~1e".into(),
+                    "/f".into(),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn it_skips_ignored_paths() {
+        let comparator = Comparator::new().ignore("/meta").ignore("/items/*/at");
+        let primary = response(r#"{"meta": {"id": 1}, "items": [{"at": 1}, {"at": 2}]}"#);
+        let shadow = response(r#"{"items": [{"at": 3}, {"at": 4}]}"#);
+        assert_eq!(comparator.compare(&primary, &shadow), None);
+    }
+
+    #[test]
+    fn it_compares_other_bodies_as_normalized_text() {
+        let comparator = Comparator::new();
+        let difference = comparator.compare(&response("<p>a</p>"), &response("<p>b</p>"));
+        assert_eq!(difference.unwrap().to_string(), "bodies differ");
+
+        let comparator = comparator.normalize_with(|body| body.to_lowercase());
+        assert_eq!(
+            comparator.compare(&response("<P>a</P>"), &response("<p>A</p>")),
+            None
+        );
+    }
+}
@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Coalescing of identical concurrent requests.
+//!
+//! When many tasks ask for the same resource at the same time, a
+//! [`SingleFlightService`] sends only one request and shares its response
+//! with all of them. A request that arrives after the shared request has
+//! completed starts a new one; nothing is cached.
+//!
+//! Requests are identified by their URI. Headers are not taken into
+//! account, since they are set by the wrapped service rather than by the
+//! caller, so they are the same for every request. Only GET requests are
+//! coalesced; POST requests are passed straight through.
+//!
+//! Because an error cannot be copied, callers that share a failed request
+//! all receive the same error wrapped in an [`HttpError::Shared`].
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::single_flight::SingleFlightService;
+//!
+//! fn coalesced<S: HttpService>(service: S) -> SingleFlightService<S> {
+//!     SingleFlightService::new(service)
+//! }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost};
+use crate::{HttpError, HttpResult};
+use reqwest::IntoUrl;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+type Outcome = Result<String, Arc<HttpError>>;
+
+/// Wraps an HTTP service and coalesces identical concurrent GET requests.
+///
+/// See the [module documentation](crate::service::single_flight) for details.
+#[derive(Debug)]
+pub struct SingleFlightService<S> {
+    inner: S,
+    in_flight: Mutex<HashMap<String, broadcast::Sender<Outcome>>>,
+}
+
+enum Role {
+    Leader(broadcast::Sender<Outcome>),
+    Follower(broadcast::Receiver<Outcome>),
+}
+
+/// Removes a request from the in-flight table when the request that is
+/// sending it completes or is cancelled.
+struct Flight<'a> {
+    in_flight: &'a Mutex<HashMap<String, broadcast::Sender<Outcome>>>,
+    key: &'a str,
+}
+
+impl Drop for Flight<'_> {
+    fn drop(&mut self) {
+        self.in_flight.lock().unwrap().remove(self.key);
+    }
+}
+
+impl<S> SingleFlightService<S> {
+    /// Wraps `inner` in a service that coalesces identical requests.
+    pub fn new(inner: S) -> Self {
+        let in_flight = Mutex::new(HashMap::new());
+        Self { inner, in_flight }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The number of distinct requests currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+
+    fn join(&self, key: &str) -> Role {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        match in_flight.get(key) {
+            Some(sender) => Role::Follower(sender.subscribe()),
+            None => {
+                let (sender, _) = broadcast::channel(1);
+                in_flight.insert(key.to_string(), sender.clone());
+                Role::Leader(sender)
+            }
+        }
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for SingleFlightService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let key = uri.as_str().to_string();
+        loop {
+            let sender = match self.join(&key) {
+                Role::Leader(sender) => sender,
+                Role::Follower(mut receiver) => match receiver.recv().await {
+                    Ok(outcome) => return outcome.map_err(HttpError::Shared),
+                    // The request was cancelled before it completed, so
+                    // try again.
+                    Err(_) => continue,
+                },
+            };
+
+            let flight = Flight {
+                in_flight: &self.in_flight,
+                key: &key,
+            };
+            let result = self.inner.get(key.as_str()).await;
+            drop(flight);
+
+            if sender.receiver_count() == 0 {
+                return result;
+            }
+            let outcome = result.map_err(Arc::new);
+            let _ = sender.send(outcome.clone());
+            return outcome.map_err(HttpError::Shared);
+        }
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for SingleFlightService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        self.inner.post(uri, auth, data).await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for SingleFlightService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("single-flight"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future;
+    use reqwest::StatusCode;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct SlowService {
+        calls: AtomicU32,
+        failing: AtomicBool,
+    }
+
+    impl HttpGet for SlowService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if self.failing.load(Ordering::SeqCst) {
+                Err(HttpError::Http(StatusCode::BAD_GATEWAY))
+            } else {
+                Ok(format!("{} #{call}", uri.as_str()))
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_coalesces_identical_requests() {
+        let service = SingleFlightService::new(SlowService::default());
+        let results = future::join_all((0..5).map(|_| service.get("/users"))).await;
+        assert!(results.iter().all(|r| r.as_deref().unwrap() == "/users #1"));
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 1);
+        assert_eq!(service.in_flight(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_does_not_coalesce_different_uris() {
+        let service = SingleFlightService::new(SlowService::default());
+        let (a, b) = tokio::join!(service.get("/users"), service.get("/teams"));
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(service.inner().calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_sends_a_new_request_after_the_first_completes() -> HttpResult<()> {
+        let service = SingleFlightService::new(SlowService::default());
+        assert_eq!(service.get("/users").await?, "/users #1");
+        assert_eq!(service.get("/users").await?, "/users #2");
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_shares_errors() {
+        let service = SingleFlightService::new(SlowService::default());
+        service.inner().failing.store(true, Ordering::SeqCst);
+        let (a, b) = tokio::join!(service.get("/users"), service.get("/users"));
+        for result in [a, b] {
+            let err = result.unwrap_err();
+            assert!(matches!(err, HttpError::Shared(_)));
+            assert_eq!(err.status(), Some(StatusCode::BAD_GATEWAY));
+            assert!(err.is_retryable());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_recovers_when_the_leader_is_cancelled() -> HttpResult<()> {
+        let service = SingleFlightService::new(SlowService::default());
+        let leader = tokio::time::timeout(Duration::from_millis(50), service.get("/users"));
+        let follower = service.get("/users");
+        let (leader, follower) = tokio::join!(leader, follower);
+        assert!(leader.is_err());
+        assert_eq!(follower?, "/users #2");
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Strict checking of response contracts.
+//!
+//! A [`StrictFieldsService`] rejects POST responses that contain fields the
+//! response type does not have, as if every type involved were annotated
+//! with `#[serde(deny_unknown_fields)]`. Because the policy is chosen when
+//! the service is built, the same client can check its contract strictly
+//! in CI and parse leniently in production.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::decode::UnknownFields;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::strict::StrictFieldsService;
+//!
+//! fn checked<S: HttpService>(service: S) -> StrictFieldsService<S> {
+//!     let policy = if std::env::var_os("CI").is_some() {
+//!         UnknownFields::Deny
+//!     } else {
+//!         UnknownFields::Ignore
+//!     };
+//!     StrictFieldsService::new(service, policy)
+//! }
+//! ```
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use crate::decode::{Decoder, UnknownFields};
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost};
+use reqwest::IntoUrl;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Wraps an HTTP service and applies an [`UnknownFields`] policy to its
+/// responses.
+///
+/// See the [module documentation](crate::service::strict) for details.
+#[derive(Debug)]
+pub struct StrictFieldsService<S> {
+    inner: S,
+    policy: UnknownFields,
+}
+
+impl<S> StrictFieldsService<S> {
+    /// Wraps `inner` in a service that handles unknown response fields
+    /// according to `policy`.
+    pub fn new(inner: S, policy: UnknownFields) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy for unknown fields.
+    pub fn policy(&self) -> UnknownFields {
+        self.policy
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for StrictFieldsService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        self.inner.get(uri).await
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for StrictFieldsService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        if self.policy == UnknownFields::Ignore {
+            return self.inner.post(uri, auth, data).await;
+        }
+        let value: Value = self.inner.post(uri, auth, data).await?;
+        Decoder::new()
+            .with_unknown_fields(self.policy)
+            .decode(&value.to_string())
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for StrictFieldsService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("strict-fields").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::auth::Auth;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Account {
+        id: u32,
+        owner: Owner,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Owner {
+        name: String,
+    }
+
+    struct AccountService;
+
+    impl HttpPost for AccountService {
+        async fn post<U, D, R>(
+            &self,
+            _uri: U,
+            _auth: &dyn Authenticator,
+            _data: &D,
+        ) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            let body = r#"{"id": 7, "owner": {"name": "Alice", "plan": "pro"}}"#;
+            Ok(serde_json::from_str(body)?)
+        }
+    }
+
+    #[tokio::test]
+    async fn it_ignores_unknown_fields_by_default() -> HttpResult<()> {
+        let service = StrictFieldsService::new(AccountService, UnknownFields::default());
+        let account: Account = service.post("/accounts", &Auth::new("key"), &()).await?;
+        assert_eq!(account.id, 7);
+        assert_eq!(account.owner.name, "Alice");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_denies_nested_unknown_fields() {
+        let service = StrictFieldsService::new(AccountService, UnknownFields::Deny);
+        let result: HttpResult<Account> = service.post("/accounts", &Auth::new("key"), &()).await;
+        assert!(
+            matches!(result, Err(HttpError::UnknownFields(fields)) if fields == ["owner.plan"])
+        );
+    }
+}
@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Per-request selection of credentials.
+//!
+//! A service that acts on behalf of many customers cannot bake a single
+//! credential into an [`AuthService`]. Instead, it can be given a
+//! [`CredentialSelector`] with [`AuthService::with_selector()`], which
+//! chooses the [`Authenticator`] for each request as it is made.
+//!
+//! The service verbs do not carry arbitrary request metadata, so the
+//! tenant a request is made for is set by running it inside [`scope()`].
+//! The tenant is visible to the selector of every request made within the
+//! scope, however deeply the service is wrapped, and is read with
+//! [`current()`]. Like other task-local values, it is not inherited by
+//! tasks spawned within the scope.
+//!
+//! [`TenantCredentials`] is a selector that maps tenant IDs to
+//! authenticators, optionally falling back to a default.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::auth::Auth;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::auth::AuthService;
+//! use hypertyper::service::tenant::{self, TenantCredentials};
+//! # use hypertyper::service::{HttpGetResponse, HttpResponse};
+//! # use reqwest::StatusCode;
+//! # use reqwest::header::{self, HeaderMap};
+//! #
+//! # /// Answers each request with the credentials it was sent with.
+//! # struct Api;
+//! #
+//! # impl HttpGetResponse for Api {
+//! #     async fn get_response<U>(&self, _uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+//! #     where
+//! #         U: IntoUrl + Send,
+//! #     {
+//! #         let auth = headers[header::AUTHORIZATION].to_str().unwrap();
+//! #         Ok(HttpResponse::new(StatusCode::OK, auth))
+//! #     }
+//! # }
+//! #
+//! # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! # runtime.block_on(async {
+//! let credentials = TenantCredentials::new()
+//!     .with_tenant("acme", Auth::new("AcmeApiKey"))
+//!     .with_tenant("globex", Auth::new("GlobexApiKey"));
+//! let service = AuthService::with_selector(Api, credentials);
+//!
+//! let body = tenant::scope("acme", service.get("https://api.example.com/users")).await?;
+//! # assert_eq!(body, "Bearer AcmeApiKey");
+//! # Ok::<(), HttpError>(())
+//! # }).unwrap();
+//! ```
+//!
+//! [`AuthService`]: crate::service::auth::AuthService
+//! [`AuthService::with_selector()`]: crate::service::auth::AuthService::with_selector
+
+use crate::auth::Authenticator;
+use crate::{HttpError, HttpResult};
+use reqwest::Request;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+tokio::task_local! {
+    static TENANT: String;
+}
+
+/// Runs `future` on behalf of `tenant`.
+///
+/// Requests made by `future` are authenticated with the credentials that
+/// a [`CredentialSelector`] chooses for `tenant`. Scopes can be nested, in
+/// which case the innermost tenant is used.
+pub async fn scope<F: Future>(tenant: impl Into<String>, future: F) -> F::Output {
+    TENANT.scope(tenant.into(), future).await
+}
+
+/// The tenant of the enclosing [`scope()`], if any.
+pub fn current() -> Option<String> {
+    TENANT.try_with(Clone::clone).ok()
+}
+
+/// Chooses the credentials for each request made through an
+/// [`AuthService`](crate::service::auth::AuthService).
+pub trait CredentialSelector: Send + Sync {
+    /// The authenticator to apply to `request`, which is being made for
+    /// `tenant`.
+    ///
+    /// `tenant` is the tenant of the enclosing [`scope()`], or `None` if
+    /// the request is not made within one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no credentials for the request, in
+    /// which case the request is not sent.
+    fn select(&self, tenant: Option<&str>, request: &Request)
+    -> HttpResult<Arc<dyn Authenticator>>;
+
+    /// A short description of the selector, which is safe to show in logs
+    /// and diagnostics.
+    fn describe(&self) -> String {
+        String::from("custom")
+    }
+}
+
+/// Selects credentials by tenant ID.
+///
+/// Requests for tenants without credentials, and requests made outside a
+/// [`scope()`], use the [default](TenantCredentials::with_default)
+/// credentials if there are any, and otherwise fail with
+/// [`HttpError::InvalidCredentials`].
+#[derive(Default)]
+pub struct TenantCredentials {
+    tenants: HashMap<String, Arc<dyn Authenticator>>,
+    default: Option<Arc<dyn Authenticator>>,
+}
+
+impl TenantCredentials {
+    /// Creates a selector without any credentials.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authenticates requests for `tenant` with `auth`.
+    pub fn with_tenant(
+        mut self,
+        tenant: impl Into<String>,
+        auth: impl Authenticator + 'static,
+    ) -> Self {
+        self.tenants.insert(tenant.into(), Arc::new(auth));
+        self
+    }
+
+    /// Authenticates requests for unknown tenants, and requests made
+    /// outside a [`scope()`], with `auth`.
+    pub fn with_default(mut self, auth: impl Authenticator + 'static) -> Self {
+        self.default = Some(Arc::new(auth));
+        self
+    }
+
+    /// The number of tenants with credentials.
+    pub fn len(&self) -> usize {
+        self.tenants.len()
+    }
+
+    /// True if no tenants have credentials.
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+}
+
+impl CredentialSelector for TenantCredentials {
+    fn select(
+        &self,
+        tenant: Option<&str>,
+        _request: &Request,
+    ) -> HttpResult<Arc<dyn Authenticator>> {
+        tenant
+            .and_then(|tenant| self.tenants.get(tenant))
+            .or(self.default.as_ref())
+            .cloned()
+            .ok_or_else(|| match tenant {
+                Some(tenant) => {
+                    HttpError::InvalidCredentials(format!("no credentials for tenant {tenant}"))
+                }
+                None => HttpError::InvalidCredentials(String::from(
+                    "the request was not made for a tenant",
+                )),
+            })
+    }
+
+    fn describe(&self) -> String {
+        format!("{} tenants", self.tenants.len())
+    }
+}
+
+impl fmt::Debug for TenantCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tenants: Vec<_> = self.tenants.keys().collect();
+        tenants.sort();
+        f.debug_struct("TenantCredentials")
+            .field("tenants", &tenants)
+            .field("has_default", &self.default.is_some())
+            .finish()
+    }
+}
+
+/// An authenticator that delegates to the one chosen by a selector.
+pub(crate) struct Selected(pub(crate) Box<dyn CredentialSelector>);
+
+impl Authenticator for Selected {
+    fn authenticate(&self, request: &mut Request) -> HttpResult<()> {
+        let tenant = current();
+        let auth = self.0.select(tenant.as_deref(), request)?;
+        auth.authenticate(request)
+    }
+
+    fn scheme(&self) -> &str {
+        "selected"
+    }
+}
+
+impl fmt::Debug for Selected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Selected").field(&self.0.describe()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{ApiKeyQuery, Auth};
+    use reqwest::Method;
+
+    fn request() -> Request {
+        Request::new(Method::GET, "https://api.example.com/".parse().unwrap())
+    }
+
+    #[test]
+    fn it_selects_credentials_by_tenant() -> HttpResult<()> {
+        let credentials = TenantCredentials::new()
+            .with_tenant("acme", Auth::new("acme-key"))
+            .with_tenant("globex", ApiKeyQuery::new("globex-key"));
+        assert_eq!(
+            credentials.select(Some("acme"), &request())?.scheme(),
+            "bearer"
+        );
+        assert_eq!(
+            credentials.select(Some("globex"), &request())?.scheme(),
+            "api-key-query"
+        );
+        assert!(matches!(
+            credentials.select(Some("initech"), &request()),
+            Err(HttpError::InvalidCredentials(message)) if message.ends_with("initech")
+        ));
+        assert!(credentials.select(None, &request()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_falls_back_to_the_default_credentials() -> HttpResult<()> {
+        let credentials = TenantCredentials::new()
+            .with_tenant("acme", Auth::new("acme-key"))
+            .with_default(ApiKeyQuery::new("shared-key"));
+        assert_eq!(
+            credentials.select(Some("initech"), &request())?.scheme(),
+            "api-key-query"
+        );
+        assert_eq!(
+            credentials.select(None, &request())?.scheme(),
+            "api-key-query"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_scopes_requests_to_a_tenant() {
+        assert_eq!(current(), None);
+        let tenants = scope("acme", async {
+            let inner = scope("globex", async { current() }).await;
+            (current(), inner)
+        })
+        .await;
+        assert_eq!(
+            tenants,
+            (Some(String::from("acme")), Some(String::from("globex")))
+        );
+        assert_eq!(current(), None);
+    }
+
+    #[test]
+    fn it_does_not_show_credentials() {
+        let credentials = TenantCredentials::new().with_tenant("acme", Auth::new("acme-key"));
+        let debug = format!("{credentials:?}");
+        assert!(debug.contains("acme"));
+        assert!(!debug.contains("acme-key"));
+    }
+}
@@ -8,20 +8,67 @@
 //! is useful to test HTTP clients in unit tests.
 //!
 //! [`TestDataLoader`] is an easy way to load and deserialize data that
-//! can be used when making HTTP POST or PUT calls.
+//! can be used when making HTTP POST or PUT calls. A
+//! [`MockService`](mock::MockService) answers requests with responses
+//! declared in code instead of fixtures.
+//!
+//! [`BudgetService`](budget::BudgetService) fails tests whose requests
+//! take too long or return bodies that are too large, a
+//! [`Scenario`](scenario::Scenario) runs multi-step client flows, and a
+//! [`CallbackReceiver`](callback::CallbackReceiver) receives callbacks from
+//! APIs that send their results to a URL. A
+//! [`FixtureRefresh`](refresh::FixtureRefresh) keeps fixtures up to date
+//! with a live API, and a [`Transcript`] explains
+//! why a request could not be answered. A
+//! [`RecordingService`](cassette::RecordingService) records a live run on a
+//! cassette that a [`ReplayService`](cassette::ReplayService) answers
+//! requests from offline.
 //!
 //! See each struct's documentation for examples of common usage.
 
-use crate::auth::Auth;
-use crate::service::{HttpGet, HttpPost, HttpResult};
-use reqwest::IntoUrl;
+pub mod budget;
+pub mod callback;
+pub mod cassette;
+pub mod mock;
+pub mod refresh;
+pub mod scenario;
+pub mod transcript;
+
+use crate::HttpError;
+use crate::auth::Authenticator;
+use crate::graphql::{GraphQlRequest, GraphQlResponse};
+use crate::service::auth::authenticate;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Verb};
+use crate::service::testing::transcript::{Expectation, RequestSummary, Transcript};
+use crate::service::{
+    BinaryResponse, BodyStream, HttpBinary, HttpGet, HttpGetStream, HttpPost, HttpPostStream,
+    HttpResponse, HttpResult,
+};
+use crate::upload::{Multipart, UploadBody};
+use bytes::Bytes;
+use futures_util::stream;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{IntoUrl, Method, StatusCode};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 
 #[cfg(doc)]
 use crate::service::HttpService;
 
+/// The name of the fixture that answers requests for a path with any query
+/// string.
+const ANY_QUERY: &str = "_any";
+
+/// The names of the directories that hold fixtures for a single method.
+const METHOD_DIRS: &[&str] = &["DELETE", "GET", "HEAD", "OPTIONS", "PATCH", "POST", "PUT"];
+
 /// A service useful for unit tests that return responses containing
 /// test data.
 ///
@@ -88,7 +135,9 @@ use crate::service::HttpService;
 /// ```
 ///
 /// `HttpTestService` would load data from `tests/data/users/foo/about.json`,
-/// relative to where you ran `cargo test`.
+/// relative to where you ran `cargo test`. Fixtures can instead be
+/// [embedded](HttpTestService::embedded) in the test binary, so that they
+/// are found wherever the tests are run from.
 ///
 /// You can also make POST requests the same way:
 ///
@@ -113,23 +162,790 @@ use crate::service::HttpService;
 ///
 /// And `HttpTestService` would deserialize the data in `tests/data/users.json`
 /// and return the deserialized object in the response.
+///
+/// ## Methods
+///
+/// A GET and a POST to the same URI load the same fixture, unless there is
+/// a fixture for the request's method in a directory named after it. For
+/// example, a GET request to `/users` loads `tests/data/output/GET/users.json`
+/// if it exists, and `tests/data/output/users.json` otherwise, while a POST
+/// request to `/users` loads `tests/data/output/POST/users.json` first. The
+/// two layouts can be mixed freely, so method directories are only needed
+/// for the URIs whose responses depend on the method.
+///
+/// ## Query strings
+///
+/// Requests with a query string load a fixture named after their query
+/// parameters, in a directory named after their path. The parameters are
+/// decoded and sorted by name, and any character in them other than a
+/// letter, a digit, `-`, `_`, `.`, `~`, or `,` is replaced with `_`, so
+/// `/search?q=rust&page=2` and `/search?page=2&q=rust` both load
+/// `tests/data/output/search/page=2&q=rust.json`. If there is no fixture
+/// for the exact parameters, the catch-all fixture `search/_any.json` is
+/// loaded instead, and failing that, `search.json`.
+///
+/// ## Fixture formats
+///
+/// Fixtures are JSON by default, but a service can be told to look for
+/// fixtures in other [formats](FixtureFormat) instead, such as XML, plain
+/// text, or raw bytes. Given several formats, it looks for a fixture in each
+/// of them in turn, so an API that returns XML from some endpoints and
+/// JSON from others can be modeled with a single service:
+///
+/// ```
+/// # use hypertyper::service::testing::{FixtureFormat, HttpTestService};
+/// let service = HttpTestService::new("tests/data/output")
+///     .with_formats([FixtureFormat::Json, FixtureFormat::Xml]);
+/// ```
+///
+/// [`get()`](HttpGet::get) returns the contents of text fixtures as they
+/// are, and [`post()`](HttpPost::post) parses them according to their
+/// format. Binary fixtures can be loaded with [`HttpBinary`], whose
+/// responses carry a `Content-Type` header for the fixture's format.
+///
+/// ## Errors
+///
+/// Requests to specific URIs can be made to fail, so that a client's error
+/// handling can be tested without a live server. A URI can be given an
+/// unsuccessful [status](HttpTestService::with_status), or any other
+/// [simulated outcome](Simulation), such as a timeout or a dropped
+/// connection:
+///
+/// ```
+/// # use hypertyper::prelude::*;
+/// # use hypertyper::service::testing::{HttpTestService, Simulation};
+/// # use reqwest::StatusCode;
+/// # use std::io;
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// let service = HttpTestService::new("tests/data/output")
+///     .with_status("/users/bar/about", StatusCode::NOT_FOUND)
+///     .with_simulation("/users/baz/about", Simulation::Transport(io::ErrorKind::ConnectionReset));
+///
+/// let result = service.get("/users/bar/about").await;
+/// assert_eq!(result.unwrap_err().status(), Some(StatusCode::NOT_FOUND));
+/// # });
+/// ```
+///
+/// Requests without test data panic, failing the test, unless the service
+/// is [fallible](HttpTestService::fallible), in which case they fail with
+/// [`HttpError::FixtureNotFound`].
+///
+/// A URI can also be given a [sequence](Sequence) of outcomes, one for
+/// each request made to it, such as to test that a client retries a
+/// request that fails the first time:
+///
+/// ```
+/// # use hypertyper::prelude::*;
+/// # use hypertyper::service::testing::{HttpTestService, Sequence, Simulation};
+/// # use reqwest::StatusCode;
+/// # use reqwest::header::HeaderMap;
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// let service = HttpTestService::new("tests/data/output").with_sequence(
+///     "/users/foo/about",
+///     Sequence::new([Simulation::Status(StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new())]),
+/// );
+///
+/// assert!(service.get("/users/foo/about").await.is_err());
+/// assert!(service.get("/users/foo/about").await.is_ok());
+/// # });
+/// ```
+///
+/// ## Requests
+///
+/// Every request is recorded, with the headers and body that were sent,
+/// so that tests can check what a client actually did:
+///
+/// ```
+/// # use hypertyper::auth::Auth;
+/// # use hypertyper::prelude::*;
+/// # use hypertyper::service::testing::HttpTestService;
+/// # use serde_json::{Value, json};
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// let service = HttpTestService::new("tests/data/output");
+/// let auth = Auth::new("my-api-key");
+/// let _: Value = service.post("/users", &auth, &json!({"username": "foo"})).await.unwrap();
+///
+/// service.assert_posted_json_eq("/users", json!({"username": "foo"}));
+/// let requests = service.requests();
+/// assert_eq!(requests[0].headers["authorization"], "Bearer my-api-key");
+/// # });
+/// ```
+///
+/// ## Uploads
+///
+/// Streamed uploads are read and discarded, and the test data for the URI
+/// is returned with a 200 OK status. Multipart forms are recorded, so that
+/// tests can check what was uploaded:
+///
+/// ```
+/// # use hypertyper::service::HttpPostStream;
+/// # use hypertyper::service::testing::HttpTestService;
+/// # use hypertyper::upload::Multipart;
+/// # use reqwest::header::HeaderMap;
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// let service = HttpTestService::new("tests/data/output");
+/// let form = Multipart::new().text("summary", "It broke");
+/// service.post_multipart("/users/foo/about", &HeaderMap::new(), form).await.unwrap();
+///
+/// let forms = service.multipart_requests();
+/// assert_eq!(forms[0].part("summary").unwrap().text(), Some("It broke"));
+/// # });
+/// ```
 pub struct HttpTestService {
-    root: String,
-    ext: String,
+    fixtures: Fixtures,
+    formats: Vec<FixtureFormat>,
+    simulations: HashMap<String, Simulation>,
+    sequences: Mutex<HashMap<String, Sequence>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+    multipart: Mutex<Vec<RecordedMultipart>>,
+    fallible: bool,
 }
 
 impl HttpTestService {
     /// Creates a new test service that loads data from the `root` directory
     /// for its responses.
     pub fn new(root: impl Into<String>) -> Self {
-        let root = root.into();
-        let ext = String::from("json"); // TODO: Allow callers to specify
-        Self { root, ext }
+        Self::with_fixtures(Fixtures::Directory(root.into()))
+    }
+
+    /// Creates a new test service that loads data for its responses from
+    /// `fixtures` instead of the file system.
+    ///
+    /// Each fixture is named by its path relative to the root directory it
+    /// would otherwise be loaded from, such as `users/foo/about.json`, and
+    /// is usually embedded in the test binary with [`embed_fixtures!`], so
+    /// that tests pass whatever directory they are run from.
+    ///
+    /// [`embed_fixtures!`]: crate::embed_fixtures
+    pub fn embedded(fixtures: impl IntoIterator<Item = (&'static str, &'static [u8])>) -> Self {
+        let fixtures = fixtures
+            .into_iter()
+            .map(|(path, data)| (path.trim_start_matches('/').to_string(), data))
+            .collect();
+        Self::with_fixtures(Fixtures::Embedded(fixtures))
+    }
+
+    fn with_fixtures(fixtures: Fixtures) -> Self {
+        let formats = vec![FixtureFormat::default()];
+        let simulations = HashMap::new();
+        let sequences = Mutex::default();
+        let requests = Mutex::default();
+        let multipart = Mutex::default();
+        Self {
+            fixtures,
+            formats,
+            simulations,
+            sequences,
+            requests,
+            multipart,
+            fallible: false,
+        }
+    }
+
+    /// Loads fixtures in `format` instead of JSON.
+    pub fn with_format(self, format: FixtureFormat) -> Self {
+        self.with_formats([format])
+    }
+
+    /// Loads fixtures in any of `formats`, looking for each in turn.
+    ///
+    /// # Panics
+    ///
+    /// If `formats` is empty.
+    pub fn with_formats(mut self, formats: impl IntoIterator<Item = FixtureFormat>) -> Self {
+        self.formats = formats.into_iter().collect();
+        assert!(!self.formats.is_empty(), "no fixture formats were given");
+        self
+    }
+
+    /// The formats that fixtures are loaded in, in the order they are
+    /// looked for.
+    pub fn formats(&self) -> &[FixtureFormat] {
+        &self.formats
+    }
+
+    /// Fails requests without test data with
+    /// [`HttpError::FixtureNotFound`] instead of panicking.
+    ///
+    /// The error describes the closest fixtures, just as the panic would,
+    /// so that negative tests can check that a request fails without
+    /// aborting.
+    pub fn fallible(mut self) -> Self {
+        self.fallible = true;
+        self
+    }
+
+    /// Answers requests to `uri` with `status` instead of a 200 OK.
+    ///
+    /// This is a shortcut for a [`Simulation::Status`] without headers.
+    pub fn with_status(self, uri: impl Into<String>, status: StatusCode) -> Self {
+        self.with_simulation(uri, Simulation::Status(status, HeaderMap::new()))
+    }
+
+    /// Answers requests to `uri` as `simulation` describes.
+    ///
+    /// `uri` must match the URI of the request exactly, including its
+    /// query string.
+    pub fn with_simulation(mut self, uri: impl Into<String>, simulation: Simulation) -> Self {
+        self.simulations.insert(uri.into(), simulation);
+        self
+    }
+
+    /// Answers successive requests to `uri` with the steps of `sequence`,
+    /// one step per request.
+    ///
+    /// Once the sequence is exhausted, requests to `uri` are answered as
+    /// they would be without it, unless it
+    /// [repeats its last step](Sequence::repeating_last). Like
+    /// [`with_simulation()`](HttpTestService::with_simulation), `uri` must
+    /// match the URI of the request exactly.
+    pub fn with_sequence(self, uri: impl Into<String>, sequence: Sequence) -> Self {
+        self.sequences.lock().unwrap().insert(uri.into(), sequence);
+        self
+    }
+
+    /// The requests that have been made to this service, in the order
+    /// they were received.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The multipart forms that have been posted to this service, in the
+    /// order they were received.
+    pub fn multipart_requests(&self) -> Vec<RecordedMultipart> {
+        self.multipart.lock().unwrap().clone()
+    }
+
+    /// Asserts that the body of the last POST request to `uri` is JSON
+    /// equal to `expected`.
+    ///
+    /// The bodies are compared as JSON values, so the order of keys and
+    /// whitespace do not matter.
+    ///
+    /// # Panics
+    ///
+    /// If no POST request has been made to `uri`, or the body of the last
+    /// one is not JSON equal to `expected`.
+    #[track_caller]
+    pub fn assert_posted_json_eq(&self, uri: &str, expected: impl Serialize) {
+        let expected = serde_json::to_value(expected).expect("expected value is not valid JSON");
+        let requests = self.requests();
+        let Some(request) = requests
+            .iter()
+            .rev()
+            .find(|request| request.method == Method::POST && request.uri == uri)
+        else {
+            let made: Vec<_> = requests
+                .iter()
+                .map(|request| format!("  {} {}", request.method, request.uri))
+                .collect();
+            panic!(
+                "no POST request was made to {uri}\n\nRequests made:\n{}",
+                made.join("\n")
+            );
+        };
+        let Some(actual) = request.json() else {
+            panic!(
+                "the body of the POST request to {uri} is not JSON: {:?}",
+                request.body
+            );
+        };
+        assert_eq!(actual, expected, "unexpected body posted to {uri}");
+    }
+
+    /// Records a `method` request to `uri`.
+    fn record(&self, method: Method, uri: &str, headers: HeaderMap, body: Option<Bytes>) {
+        self.record_request(RecordedRequest {
+            method,
+            uri: uri.to_string(),
+            headers,
+            auth: None,
+            body,
+        });
+    }
+
+    /// Records a `method` request to `uri` with a JSON `body`, authenticated
+    /// by `auth`.
+    fn record_json<D: Serialize>(
+        &self,
+        method: Method,
+        uri: &str,
+        auth: &dyn Authenticator,
+        body: &D,
+    ) -> HttpResult<()> {
+        let (_, headers) = authenticate(auth, method.clone(), uri, &HeaderMap::new())?;
+        let body = serde_json::to_vec(body)?;
+        self.record_request(RecordedRequest {
+            method,
+            uri: uri.to_string(),
+            headers,
+            auth: Some(auth.scheme().to_string()),
+            body: Some(Bytes::from(body)),
+        });
+        Ok(())
+    }
+
+    /// Adds `request` to the log of requests.
+    fn record_request(&self, request: RecordedRequest) {
+        self.requests.lock().unwrap().push(request);
+    }
+
+    /// Loads the test data for a request to `uri`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no test data for `uri`, with a
+    /// [transcript](transcript::Transcript) of the closest fixtures, unless
+    /// the service is [fallible](HttpTestService::fallible), in which case
+    /// it fails with [`HttpError::FixtureNotFound`] instead.
+    fn load_fixture(&self, method: Method, uri: impl IntoUrl + Send) -> HttpResult<Fixture> {
+        if let Some(fixture) = self.find_fixture(&method, uri.as_str()) {
+            return Ok(fixture);
+        }
+
+        let request = RequestSummary::new(method, uri.as_str());
+        let expectations =
+            self.fixtures
+                .requests(&self.formats)
+                .into_iter()
+                .map(|(method, uri)| match method {
+                    Some(method) => Expectation::new(uri).with_method(method),
+                    None => Expectation::new(uri),
+                });
+        let transcript = Transcript::new(request, expectations);
+        if self.fallible {
+            return Err(HttpError::FixtureNotFound(transcript.to_string()));
+        }
+        panic!("could not find test data\n\n{transcript}")
+    }
+
+    /// The test data for a `method` request to `uri`, if there is any.
+    ///
+    /// Test data in the directory for `method` is preferred to test data
+    /// for any method.
+    fn find_fixture(&self, method: &Method, uri: &str) -> Option<Fixture> {
+        let dirs = [method.to_string(), String::new()];
+        fixture_names(uri).iter().find_map(|name| {
+            dirs.iter().find_map(|dir| {
+                self.formats.iter().find_map(|&format| {
+                    let path = format!("{dir}{name}.{}", format.extension());
+                    let data = self.fixtures.read(path.trim_start_matches('/'))?;
+                    Some(Fixture { format, data })
+                })
+            })
+        })
+    }
+
+    /// The simulated outcome of the next request to `uri`, if there is one.
+    ///
+    /// Each call takes the next step of the URI's sequence, if it has one.
+    fn simulation(&self, uri: &str) -> Option<Simulation> {
+        let step = self
+            .sequences
+            .lock()
+            .unwrap()
+            .get_mut(uri)
+            .and_then(Sequence::next);
+        step.or_else(|| self.simulations.get(uri).cloned())
+    }
+
+    /// Fails with the simulated error for requests to `uri`, if there is
+    /// one.
+    fn simulate(&self, uri: &str) -> HttpResult<()> {
+        match self.simulation(uri).as_ref().and_then(Simulation::error) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// The simulated response to a request to `uri`, for verbs that return
+    /// responses of any status.
+    ///
+    /// The body of the response is the test data for `uri` if there is
+    /// any, and empty otherwise.
+    fn simulate_response(&self, method: Method, uri: &str) -> HttpResult<Option<HttpResponse>> {
+        let (status, headers) = match self.simulation(uri) {
+            Some(Simulation::Status(status, headers)) => (status, headers),
+            Some(simulation) => return simulation.error().map_or(Ok(None), Err),
+            None => return Ok(None),
+        };
+        let body = self
+            .find_fixture(&method, uri)
+            .map(|fixture| String::from_utf8_lossy(&fixture.into_body()).into_owned())
+            .unwrap_or_default();
+        let response = headers.iter().fold(
+            HttpResponse::new(status, body),
+            |response, (name, value)| response.with_header(name.clone(), value.clone()),
+        );
+        Ok(Some(response))
+    }
+
+    /// Loads the test data for a request to `uri` as text.
+    ///
+    /// Test data that is not valid UTF-8 is an [`HttpError::Io`] with
+    /// [`io::ErrorKind::InvalidData`].
+    ///
+    /// # Panics
+    ///
+    /// If there is no test data for `uri` and the service is not
+    /// [fallible](HttpTestService::fallible).
+    fn load_resource(&self, method: Method, uri: impl IntoUrl + Send) -> HttpResult<String> {
+        let fixture = self.load_fixture(method, uri)?;
+        String::from_utf8(fixture.data)
+            .map_err(|err| HttpError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+}
+
+/// Where an [`HttpTestService`] loads its fixtures from.
+enum Fixtures {
+    /// Files beneath a root directory.
+    Directory(String),
+
+    /// Fixtures embedded in the test binary, by their paths relative to
+    /// the root directory.
+    Embedded(HashMap<String, &'static [u8]>),
+}
+
+impl Fixtures {
+    /// The contents of the fixture at `path`, relative to the root
+    /// directory, if there is one.
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Directory(root) => fs::read(format!("{root}/{path}")).ok(),
+            Self::Embedded(fixtures) => fixtures.get(path).map(|data| data.to_vec()),
+        }
+    }
+
+    /// The method and URI each fixture in one of `formats` answers, sorted
+    /// by the fixtures' paths.
+    fn requests(&self, formats: &[FixtureFormat]) -> Vec<(Option<Method>, String)> {
+        match self {
+            Self::Directory(root) => {
+                let root = Path::new(root);
+                let paths = fixture_paths(root, formats).unwrap_or_default();
+                paths
+                    .iter()
+                    .map(|path| fixture_request(root, path))
+                    .collect()
+            }
+            Self::Embedded(fixtures) => {
+                let mut paths: Vec<_> = fixtures
+                    .keys()
+                    .map(Path::new)
+                    .filter(|path| {
+                        path.extension()
+                            .and_then(|ext| FixtureFormat::from_extension(&ext.to_string_lossy()))
+                            .is_some_and(|format| formats.contains(&format))
+                    })
+                    .collect();
+                paths.sort();
+                paths
+                    .iter()
+                    .map(|path| fixture_request(Path::new(""), path))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Embeds fixtures in the test binary, for an
+/// [`HttpTestService::embedded()`](crate::service::testing::HttpTestService::embedded).
+///
+/// The first argument is the directory the fixtures are in, relative to
+/// the calling package's manifest, and the second lists the paths of the
+/// fixtures within it. Because the fixtures are found relative to the
+/// manifest at compile time, tests that use them pass whatever directory
+/// they are run from, and a missing fixture fails to compile.
+///
+/// ```
+/// use hypertyper::embed_fixtures;
+/// use hypertyper::service::testing::HttpTestService;
+///
+/// let service = HttpTestService::embedded(embed_fixtures!(
+///     "tests/data/output",
+///     ["users/foo/about.json", "users.json"]
+/// ));
+/// ```
+#[macro_export]
+macro_rules! embed_fixtures {
+    ($root:literal, [$($path:literal),* $(,)?]) => {
+        [$((
+            $path,
+            &include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $root, "/", $path))[..],
+        )),*]
+    };
+}
+
+/// The outcomes of successive requests to a URI of an [`HttpTestService`].
+///
+/// Each request to the URI takes the next step of the sequence. A
+/// successful [`Simulation::Status`] answers the request with the URI's
+/// test data, as if there were no simulation, so a flaky endpoint that
+/// succeeds on its third attempt is:
+///
+/// ```
+/// # use hypertyper::prelude::*;
+/// # use hypertyper::service::retry::{RetryPolicy, RetryService};
+/// # use hypertyper::service::testing::{HttpTestService, Sequence, Simulation};
+/// # use reqwest::StatusCode;
+/// # use reqwest::header::HeaderMap;
+/// # use std::io;
+/// # use std::time::Duration;
+/// # let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+/// # runtime.block_on(async {
+/// let sequence = Sequence::new([
+///     Simulation::Status(StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new()),
+///     Simulation::Transport(io::ErrorKind::ConnectionReset),
+///     Simulation::Status(StatusCode::OK, HeaderMap::new()),
+/// ]);
+/// let service =
+///     HttpTestService::new("tests/data/output").with_sequence("/users/foo/about", sequence);
+/// # let policy = RetryPolicy::new().with_backoff(Duration::ZERO, Duration::ZERO);
+/// let service = RetryService::new(service, policy);
+///
+/// assert!(service.get("/users/foo/about").await.is_ok());
+/// assert_eq!(service.inner().requests().len(), 3);
+/// # });
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sequence {
+    steps: VecDeque<Simulation>,
+    repeat_last: bool,
+}
+
+impl Sequence {
+    /// Creates a sequence of `steps`, in the order they are taken.
+    pub fn new(steps: impl IntoIterator<Item = Simulation>) -> Self {
+        let steps = steps.into_iter().collect();
+        Self {
+            steps,
+            repeat_last: false,
+        }
+    }
+
+    /// Answers every request after the sequence is exhausted with its last
+    /// step, instead of as if there were no sequence.
+    pub fn repeating_last(mut self) -> Self {
+        self.repeat_last = true;
+        self
+    }
+
+    /// The number of steps that have not been taken yet.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// True if every step has been taken.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Takes the next step, if there is one.
+    fn next(&mut self) -> Option<Simulation> {
+        match self.steps.len() {
+            1 if self.repeat_last => self.steps.front().cloned(),
+            _ => self.steps.pop_front(),
+        }
+    }
+}
+
+/// How an [`HttpTestService`] answers requests to a URI in place of a
+/// 200 OK with its test data.
+#[derive(Clone, Debug)]
+pub enum Simulation {
+    /// A response with a status and headers.
+    ///
+    /// Verbs that only return successful responses fail as they would for
+    /// a real response with this status, with the error from
+    /// [`HttpError::from_status()`], so a 429 with a `Retry-After` header
+    /// fails with [`HttpError::RetryAfter`]. Verbs that return responses
+    /// of any status, such as [`HttpPostStream`], return a response with
+    /// this status and headers, whose body is the URI's test data if it
+    /// has any.
+    Status(StatusCode, HeaderMap),
+
+    /// A request that times out after the given amount of time, which
+    /// fails with [`HttpError::Timeout`] straight away.
+    Timeout(Duration),
+
+    /// A request that fails before a response is received, such as
+    /// because the connection was refused or reset, which fails with an
+    /// [`HttpError::Io`] of the given kind.
+    ///
+    /// Connection failures, such as [`io::ErrorKind::ConnectionRefused`]
+    /// and [`io::ErrorKind::ConnectionReset`], are
+    /// [retryable](HttpError::is_retryable), so they can be used to test
+    /// retries and failover.
+    Transport(io::ErrorKind),
+}
+
+impl Simulation {
+    /// The error that requests fail with, or `None` if the simulated
+    /// response is successful.
+    pub fn error(&self) -> Option<HttpError> {
+        match self {
+            Self::Status(status, headers) => {
+                (!status.is_success()).then(|| HttpError::from_status(*status, headers))
+            }
+            Self::Timeout(timeout) => Some(HttpError::Timeout(*timeout)),
+            Self::Transport(kind) => Some(HttpError::Io(io::Error::new(
+                *kind,
+                "simulated transport error",
+            ))),
+        }
+    }
+}
+
+/// The format of a fixture file, which determines its extension and how
+/// it is parsed.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum FixtureFormat {
+    /// JSON, in `.json` files.
+    #[default]
+    Json,
+
+    /// XML, in `.xml` files, which can only be parsed with the **xml**
+    /// feature enabled.
+    Xml,
+
+    /// Plain text, in `.txt` files, which is parsed as a string without
+    /// its surrounding whitespace.
+    Text,
+
+    /// Raw bytes, in `.bin` files, which cannot be parsed.
+    Binary,
+
+    /// YAML, in `.yaml` files, which can only be parsed with the
+    /// **yaml** feature enabled.
+    Yaml,
+
+    /// TOML, in `.toml` files, which can only be parsed with the **toml**
+    /// feature enabled.
+    Toml,
+
+    /// CSV, in `.csv` files, whose rows can be parsed one at a time with
+    /// [`TestDataLoader::load_csv()`] when the **csv** feature is enabled,
+    /// but which cannot be parsed as a whole.
+    Csv,
+}
+
+impl FixtureFormat {
+    /// The extension of fixture files in this format, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Xml => "xml",
+            Self::Text => "txt",
+            Self::Binary => "bin",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Csv => "csv",
+        }
+    }
+
+    /// The format of fixture files with the extension `ext`, if it is one
+    /// of the supported formats.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        [
+            Self::Json,
+            Self::Xml,
+            Self::Text,
+            Self::Binary,
+            Self::Yaml,
+            Self::Toml,
+            Self::Csv,
+        ]
+        .into_iter()
+        .find(|format| format.extension() == ext)
+    }
+
+    /// The content type of responses in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Xml => "application/xml",
+            Self::Text => "text/plain; charset=utf-8",
+            Self::Binary => "application/octet-stream",
+            Self::Yaml => "application/yaml",
+            Self::Toml => "application/toml",
+            Self::Csv => "text/csv",
+        }
+    }
+
+    /// The formats that can be parsed with the enabled features.
+    fn parseable() -> Vec<Self> {
+        vec![
+            Self::Json,
+            Self::Text,
+            #[cfg(feature = "xml")]
+            Self::Xml,
+            #[cfg(feature = "yaml")]
+            Self::Yaml,
+            #[cfg(feature = "toml")]
+            Self::Toml,
+        ]
+    }
+
+    /// Parses `data` as a `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` does not match `T`, or
+    /// [`HttpError::UnexpectedContentType`] if data in this format cannot
+    /// be parsed.
+    pub fn parse<T: DeserializeOwned>(&self, data: &[u8]) -> HttpResult<T> {
+        match self {
+            Self::Json => Ok(serde_json::from_slice(data)?),
+            #[cfg(feature = "xml")]
+            Self::Xml => crate::decode::xml::from_str(&String::from_utf8_lossy(data)),
+            Self::Text => {
+                let text = String::from_utf8_lossy(data).trim().to_string();
+                Ok(serde_json::from_value(serde_json::Value::String(text))?)
+            }
+            #[cfg(feature = "yaml")]
+            Self::Yaml => Ok(serde_norway::from_slice(data)?),
+            #[cfg(feature = "toml")]
+            Self::Toml => Ok(toml::from_slice(data)?),
+            _ => Err(HttpError::UnexpectedContentType {
+                content_type: self.content_type().to_string(),
+                acceptable: Self::parseable()
+                    .iter()
+                    .map(|format| format.content_type().to_string())
+                    .collect(),
+            }),
+        }
+    }
+}
+
+/// A fixture and the format it was loaded in.
+struct Fixture {
+    format: FixtureFormat,
+    data: Vec<u8>,
+}
+
+impl Fixture {
+    /// Parses the fixture as a `T`.
+    fn parse<T: DeserializeOwned>(&self) -> HttpResult<T> {
+        self.format.parse(&self.data)
+    }
+
+    /// The fixture's contents, trimmed of surrounding whitespace unless it
+    /// is binary.
+    fn into_body(self) -> Bytes {
+        let body = Bytes::from(self.data);
+        match self.format {
+            FixtureFormat::Binary => body,
+            _ => body.slice_ref(body.trim_ascii()),
+        }
     }
 
-    fn load_resource(&self, uri: impl IntoUrl + Send) -> String {
-        let path = format!("{}{}.{}", self.root, uri.as_str(), self.ext);
-        fs::read_to_string(path).expect("could not find test data")
+    /// A `Content-Type` header value for the fixture's format.
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static(self.format.content_type())
     }
 }
 
@@ -143,27 +959,300 @@ impl HttpGet for HttpTestService {
     where
         U: IntoUrl + Send,
     {
-        Ok(self.load_resource(uri).trim().to_string())
+        self.record(Method::GET, uri.as_str(), HeaderMap::new(), None);
+        self.simulate(uri.as_str())?;
+        Ok(self.load_resource(Method::GET, uri)?.trim().to_string())
+    }
+}
+
+impl HttpCapabilities for HttpTestService {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new([
+            Verb::Get,
+            Verb::Post,
+            Verb::GetStream,
+            Verb::PostStream,
+            Verb::Binary,
+        ])
+    }
+}
+
+impl HttpGetStream for HttpTestService {
+    /// Mocks a streaming HTTP GET request by loading test data mapped to the
+    /// given `uri` and returning it as a single chunk.
+    ///
+    /// # Panics
+    ///
+    /// If test data cannot be loaded.
+    async fn get_stream<U>(&self, uri: U) -> HttpResult<BodyStream>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::GET, uri.as_str(), HeaderMap::new(), None);
+        self.simulate(uri.as_str())?;
+        let body = self.load_fixture(Method::GET, uri)?.into_body();
+        let content_length = Some(body.len() as u64);
+        Ok(BodyStream::new(stream::iter([Ok(body)])).with_content_length(content_length))
     }
 }
 
 impl HttpPost for HttpTestService {
-    /// Mocks an HTTP POST request by loading test data mapped to the given `uri`.
+    /// Mocks an HTTP POST request by loading test data mapped to the given `uri`,
+    /// and parsing it according to its [format](FixtureFormat).
     ///
-    /// This method does nothing with the POST `data` itself, nor does it
-    /// operate on `auth`; it just loads a response from the file system.
+    /// The request is [recorded](HttpTestService::requests) with `data`
+    /// serialized as JSON and the headers that `auth` adds, but the
+    /// response does not depend on either of them.
     ///
     /// # Panics
     ///
     /// If test data cannot be loaded.
-    async fn post<U, D, R>(&self, uri: U, _auth: &Auth, _data: &D) -> HttpResult<R>
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
     where
         U: IntoUrl + Send,
         D: Serialize + Sync,
         R: DeserializeOwned,
     {
-        let data = self.load_resource(uri);
-        Ok(serde_json::from_str(&data)?)
+        self.record_json(Method::POST, uri.as_str(), auth, data)?;
+        self.simulate(uri.as_str())?;
+        self.load_fixture(Method::POST, uri)?.parse()
+    }
+
+    /// Mocks a GraphQL request by loading a response envelope mapped to the
+    /// given `uri` and the request's operation name.
+    ///
+    /// A request with an operation name is answered from a fixture named
+    /// after the operation in a directory named after the URI. For example,
+    /// a `GetUser` operation sent to `/graphql` loads
+    /// `tests/data/output/graphql/GetUser.json`. A request without an
+    /// operation name loads `tests/data/output/graphql.json`.
+    ///
+    /// The fixture holds the entire response, so it can include `errors`
+    /// as well as `data`:
+    ///
+    /// ```json
+    /// { "data": { "user": { "name": "foo" } } }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If test data cannot be loaded.
+    async fn post_graphql<U, T>(
+        &self,
+        uri: U,
+        auth: &dyn Authenticator,
+        request: &GraphQlRequest,
+    ) -> HttpResult<T>
+    where
+        U: IntoUrl + Send,
+        T: DeserializeOwned + Send,
+        Self: Sync,
+    {
+        self.record_json(Method::POST, uri.as_str(), auth, request)?;
+        self.simulate(uri.as_str())?;
+        let data = match &request.operation_name {
+            Some(operation) => self.load_resource(
+                Method::POST,
+                format!("{}/{operation}", uri.as_str()).as_str(),
+            )?,
+            None => self.load_resource(Method::POST, uri)?,
+        };
+        serde_json::from_str::<GraphQlResponse<T>>(&data)?.into_result()
+    }
+}
+
+impl HttpPostStream for HttpTestService {
+    /// Mocks a streaming HTTP POST request by recording `body` and loading
+    /// test data mapped to the given `uri`.
+    ///
+    /// # Panics
+    ///
+    /// If test data cannot be loaded.
+    async fn post_stream<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: UploadBody,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let body = body.into_bytes().await?;
+        self.record(Method::POST, uri.as_str(), headers.clone(), Some(body));
+        if let Some(response) = self.simulate_response(Method::POST, uri.as_str())? {
+            return Ok(response);
+        }
+        let body = self.load_resource(Method::POST, uri)?.trim().to_string();
+        Ok(HttpResponse::new(StatusCode::OK, body))
+    }
+
+    /// Mocks a multipart HTTP POST request by recording the parts of `form`
+    /// and loading test data mapped to the given `uri`.
+    ///
+    /// The parts are recorded in [`multipart_requests()`], and the request
+    /// is recorded in [`requests()`] without a body.
+    ///
+    /// # Panics
+    ///
+    /// If test data cannot be loaded.
+    ///
+    /// [`multipart_requests()`]: HttpTestService::multipart_requests
+    /// [`requests()`]: HttpTestService::requests
+    async fn post_multipart<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        form: Multipart,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let mut parts = Vec::new();
+        for part in form.into_parts() {
+            let name = part.name().to_string();
+            let filename = part.filename().map(str::to_string);
+            let content_type = part.content_type().map(str::to_string);
+            let data = part.into_body().into_bytes().await?;
+            parts.push(RecordedPart {
+                name,
+                filename,
+                content_type,
+                data,
+            });
+        }
+        let uri = uri.as_str().to_string();
+        self.record(Method::POST, &uri, headers.clone(), None);
+        let form = RecordedMultipart {
+            uri: uri.clone(),
+            parts,
+        };
+        self.multipart.lock().unwrap().push(form);
+        if let Some(response) = self.simulate_response(Method::POST, &uri)? {
+            return Ok(response);
+        }
+        let body = self
+            .load_resource(Method::POST, uri.as_str())?
+            .trim()
+            .to_string();
+        Ok(HttpResponse::new(StatusCode::OK, body))
+    }
+}
+
+impl HttpBinary for HttpTestService {
+    /// Mocks an HTTP GET request by loading test data mapped to the given
+    /// `uri` as bytes.
+    ///
+    /// # Panics
+    ///
+    /// If test data cannot be loaded.
+    async fn get_binary<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::GET, uri.as_str(), headers.clone(), None);
+        self.simulate(uri.as_str())?;
+        let fixture = self.load_fixture(Method::GET, uri)?;
+        let content_type = fixture.content_type();
+        Ok(BinaryResponse::new(StatusCode::OK, fixture.into_body())
+            .with_header(header::CONTENT_TYPE, content_type))
+    }
+
+    /// Mocks an HTTP POST request by loading test data mapped to the given
+    /// `uri` as bytes.
+    ///
+    /// # Panics
+    ///
+    /// If test data cannot be loaded.
+    async fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::POST, uri.as_str(), headers.clone(), Some(body));
+        self.simulate(uri.as_str())?;
+        let fixture = self.load_fixture(Method::POST, uri)?;
+        let content_type = fixture.content_type();
+        Ok(BinaryResponse::new(StatusCode::OK, fixture.into_body())
+            .with_header(header::CONTENT_TYPE, content_type))
+    }
+}
+
+/// A request made to an [`HttpTestService`].
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    /// The method of the request.
+    pub method: Method,
+
+    /// The URI the request was made to.
+    pub uri: String,
+
+    /// The headers that were sent, including any that were added by the
+    /// request's authenticator.
+    pub headers: HeaderMap,
+
+    /// The [scheme](Authenticator::scheme) of the authenticator the
+    /// request was made with, for verbs that take one.
+    pub auth: Option<String>,
+
+    /// The body of the request, if it had one.
+    pub body: Option<Bytes>,
+}
+
+impl RecordedRequest {
+    /// The body of the request as text, if it had one and it is valid
+    /// UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(self.body.as_ref()?).ok()
+    }
+
+    /// The body of the request as JSON, if it had one and it is valid
+    /// JSON.
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(self.body.as_ref()?).ok()
+    }
+}
+
+/// A multipart form posted to an [`HttpTestService`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordedMultipart {
+    /// The URI the form was posted to.
+    pub uri: String,
+
+    /// The parts of the form, in order.
+    pub parts: Vec<RecordedPart>,
+}
+
+impl RecordedMultipart {
+    /// The first part named `name`, if there is one.
+    pub fn part(&self, name: &str) -> Option<&RecordedPart> {
+        self.parts.iter().find(|part| part.name == name)
+    }
+}
+
+/// One part of a [`RecordedMultipart`] form.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordedPart {
+    /// The name of the field.
+    pub name: String,
+
+    /// The filename of the field, if it was a file upload.
+    pub filename: Option<String>,
+
+    /// The content type of the field, if one was given.
+    pub content_type: Option<String>,
+
+    /// The contents of the field.
+    pub data: Bytes,
+}
+
+impl RecordedPart {
+    /// The contents of the field as text, if they are valid UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
     }
 }
 
@@ -207,17 +1296,48 @@ impl HttpPost for HttpTestService {
 /// let service = HttpTestService::new("tests/data/output");
 /// let response = service.post::<&str, Resource, Resource>("/resources/1", &auth, &data);
 /// ```
+///
+/// Test data in other [formats](FixtureFormat) can be loaded by giving the
+/// loader a format, and data that should not be parsed at all can be loaded
+/// with [`load_raw()`](TestDataLoader::load_raw):
+///
+/// ```
+/// # use hypertyper::service::testing::{FixtureFormat, TestDataLoader};
+/// let loader = TestDataLoader::new("tests/data/input").with_format(FixtureFormat::Xml);
+/// let body = loader.load_raw("resource");
+/// ```
+///
+/// A resource that is named with the extension of a format is loaded in
+/// that format instead of the loader's, so a single loader can load
+/// fixtures in several formats:
+///
+/// ```
+/// # use hypertyper::service::testing::TestDataLoader;
+/// let loader = TestDataLoader::new("tests/data/input");
+/// let json = loader.load_raw("resource");
+/// let xml = loader.load_raw("resource.xml");
+/// ```
+///
+/// YAML and TOML fixtures can be parsed with the **yaml** and **toml**
+/// features, and CSV fixtures can be parsed row by row with
+/// [`load_csv()`](TestDataLoader::load_csv) and the **csv** feature.
 pub struct TestDataLoader {
     root: String,
-    ext: String,
+    format: FixtureFormat,
 }
 
 impl TestDataLoader {
     /// Create a new loader that loads test data from the `root` directory.
     pub fn new(root: impl Into<String>) -> Self {
         let root = root.into();
-        let ext = String::from("json"); // TODO: Allow callers to specify
-        Self { root, ext }
+        let format = FixtureFormat::default();
+        Self { root, format }
+    }
+
+    /// Loads test data in `format` instead of JSON.
+    pub fn with_format(mut self, format: FixtureFormat) -> Self {
+        self.format = format;
+        self
     }
 }
 
@@ -231,13 +1351,201 @@ impl TestDataLoader {
     where
         T: DeserializeOwned,
     {
-        let resource = resource.into();
-        let path = format!("{}/{resource}.{}", self.root, self.ext);
-        let data = fs::read_to_string(path).expect("could not read test data");
-        serde_json::from_str(&data).expect("could not deserialize test data")
+        let (format, data) = self
+            .read(&resource.into(), self.format)
+            .expect("could not read test data");
+        format
+            .parse(&data)
+            .expect("could not deserialize test data")
+    }
+
+    /// Loads test data without parsing it.
+    ///
+    /// # Panics
+    ///
+    /// If the test data cannot be loaded.
+    pub fn load_raw(&self, resource: impl Into<String>) -> Vec<u8> {
+        self.try_load_raw(resource)
+            .expect("could not read test data")
+    }
+
+    /// Loads test data and serializes it into an object, without
+    /// panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::FixtureNotFound`] if there is no test data for
+    /// `resource`, or another error if it cannot be read or deserialized.
+    pub fn try_load<T>(&self, resource: impl Into<String>) -> HttpResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let (format, data) = self.read(&resource.into(), self.format)?;
+        format.parse(&data)
+    }
+
+    /// Loads test data without parsing it or panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::FixtureNotFound`] if there is no test data for
+    /// `resource`, or [`HttpError::Io`] if it cannot be read.
+    pub fn try_load_raw(&self, resource: impl Into<String>) -> HttpResult<Vec<u8>> {
+        let (_, data) = self.read(&resource.into(), self.format)?;
+        Ok(data)
+    }
+
+    /// Loads the rows of CSV test data and deserializes each of them into
+    /// an object.
+    ///
+    /// The first row of the data names the columns, which are matched to
+    /// the fields of `T` by name.
+    ///
+    /// # Panics
+    ///
+    /// If the test data cannot be loaded.
+    #[cfg(feature = "csv")]
+    pub fn load_csv<T>(&self, resource: impl Into<String>) -> Vec<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.try_load_csv(resource)
+            .expect("could not load CSV test data")
+    }
+
+    /// Loads the rows of CSV test data and deserializes each of them into
+    /// an object, without panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::FixtureNotFound`] if there is no test data for
+    /// `resource`, or another error if it cannot be read or deserialized.
+    #[cfg(feature = "csv")]
+    pub fn try_load_csv<T>(&self, resource: impl Into<String>) -> HttpResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let (_, data) = self.read(&resource.into(), FixtureFormat::Csv)?;
+        let rows = csv::Reader::from_reader(data.as_slice())
+            .deserialize()
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+
+    /// Reads `resource` and detects its format from its extension, if it
+    /// has the extension of a format, or assumes it is in `format`.
+    fn read(&self, resource: &str, format: FixtureFormat) -> HttpResult<(FixtureFormat, Vec<u8>)> {
+        let detected = Path::new(resource)
+            .extension()
+            .and_then(|ext| FixtureFormat::from_extension(&ext.to_string_lossy()));
+        let (format, path) = match detected {
+            Some(format) => (format, format!("{}/{resource}", self.root)),
+            None => (
+                format,
+                format!("{}/{resource}.{}", self.root, format.extension()),
+            ),
+        };
+        let data = fs::read(&path).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => HttpError::FixtureNotFound(path),
+            _ => HttpError::Io(err),
+        })?;
+        Ok((format, data))
+    }
+}
+
+/// Every fixture file in one of `formats` beneath `dir`, sorted by path.
+fn fixture_paths(dir: &Path, formats: &[FixtureFormat]) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            paths.extend(fixture_paths(&path, formats)?);
+        } else if path
+            .extension()
+            .and_then(|ext| FixtureFormat::from_extension(&ext.to_string_lossy()))
+            .is_some_and(|format| formats.contains(&format))
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// The names of the fixtures that can answer a request to `uri`, without
+/// their extensions, from the most to the least specific.
+fn fixture_names(uri: &str) -> Vec<String> {
+    match uri.split_once('?') {
+        Some((path, query)) if !query.is_empty() => vec![
+            format!("{path}/{}", canonical_query(query)),
+            format!("{path}/{ANY_QUERY}"),
+            path.to_string(),
+        ],
+        Some((path, _)) => vec![path.to_string()],
+        None => vec![uri.to_string()],
     }
 }
 
+/// The name of the fixture for the query string `query`: its decoded
+/// parameters sorted by name, with characters that are unsafe in file
+/// names replaced with `_`.
+fn canonical_query(query: &str) -> String {
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| {
+                if c.is_alphanumeric() || "-_.~,".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    let mut params: Vec<_> = url::form_urlencoded::parse(query.as_bytes())
+        .map(|(name, value)| (sanitize(&name), sanitize(&value)))
+        .collect();
+    params.sort();
+    params
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// The method and URI the fixture at `path` answers, such as `None` and
+/// `/users/foo/about` for `users/foo/about.json`, or `POST` and `/users`
+/// for `POST/users.json`.
+///
+/// Query fixtures answer URIs with their query string, such as
+/// `/search?page=2&q=rust` for `search/page=2&q=rust.json`, and catch-all
+/// fixtures answer URIs whose query string is `*`.
+fn fixture_request(root: &Path, path: &Path) -> (Option<Method>, String) {
+    let relative = path.strip_prefix(root).unwrap_or(path).with_extension("");
+    let mut components: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let method = match components.first().map(String::as_str) {
+        Some(method) if components.len() > 1 && METHOD_DIRS.contains(&method) => {
+            Method::from_bytes(components.remove(0).as_bytes()).ok()
+        }
+        _ => None,
+    };
+    let query = match components.last().map(String::as_str) {
+        Some(ANY_QUERY) => components.pop().map(|_| String::from("*")),
+        Some(last) if last.contains('=') => components.pop(),
+        _ => None,
+    };
+    let mut uri: String = components
+        .iter()
+        .map(|component| format!("/{component}"))
+        .collect();
+    if let Some(query) = query {
+        uri = format!("{uri}?{query}");
+    }
+    (method, uri)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,6 +1570,93 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn embedded_services_load_data() -> Result<(), HttpError> {
+        let service = HttpTestService::embedded(crate::embed_fixtures!(
+            "tests/data/methods",
+            ["GET/users.json", "POST/users.json", "users/foo.json"]
+        ));
+        let auth = Auth::new("key");
+        assert_eq!(service.get("/users").await?, r#"[{"username": "foo"}]"#);
+        let created: User = service.post("/users", &auth, &()).await?;
+        assert_eq!(created.username, "bar");
+        assert_eq!(service.get("/users/foo").await?, r#"{"username": "foo"}"#);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn embedded_services_describe_their_fixtures() {
+        let service = HttpTestService::embedded([
+            ("/users/foo/about.json", &b"{}"[..]),
+            ("users/bar/about.json", &b"{}"[..]),
+            ("ping.txt", &b"pong"[..]),
+        ])
+        .fallible();
+        let result = service.get("/users/foo/abuot").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::FixtureNotFound(message))
+                if message.contains("Closest 2 of 2 expectations:\n\n  * /users/foo/about\n")
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_fails_if_data_is_not_text() {
+        let service = HttpTestService::embedded([("logo.json", &b"\x89PNG\xff"[..])]);
+        let result = service.get("/logo").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Io(err)) if err.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_stream_loads_data() -> Result<(), HttpError> {
+        use futures_util::StreamExt;
+
+        let chunks: Vec<_> = SERVICE
+            .get_stream("/users/foo/about")
+            .await?
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].as_ref().unwrap(), "{\"username\": \"foo\"}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "No expectation matched GET /users/foo/abuot\n\
+        Closest 3 of 5 expectations:\n\n  * /users/foo/about\n")]
+    async fn get_panics_with_a_transcript_if_data_does_not_exist() {
+        let _ = SERVICE.get("/users/foo/abuot").await;
+    }
+
+    #[tokio::test]
+    async fn fallible_services_fail_if_data_does_not_exist() {
+        let service = HttpTestService::new("tests/data/output").fallible();
+        let result = service.get("/users/foo/abuot").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::FixtureNotFound(message))
+                if message.contains("Closest 3 of 5 expectations:\n\n  * /users/foo/about\n")
+        ));
+        let auth = Auth::new("my-api-key");
+        let result: Result<User, _> = service.post("/admin", &auth, &()).await;
+        assert!(matches!(result, Err(HttpError::FixtureNotFound(_))));
+    }
+
+    #[test]
+    fn loader_fails_if_data_does_not_exist() -> Result<(), HttpError> {
+        let user: User = LOADER.try_load("user")?;
+        assert_eq!(user.username, "foo");
+        let result: Result<User, _> = LOADER.try_load("no-resource");
+        assert!(matches!(
+            result,
+            Err(HttpError::FixtureNotFound(path)) if path == "tests/data/input/no-resource.json"
+        ));
+        Ok(())
+    }
+
     #[tokio::test]
     #[should_panic]
     async fn get_panics_if_data_does_not_exist() {
@@ -292,4 +1687,537 @@ mod tests {
         let data: User = LOADER.load("user");
         let _: Result<User, _> = SERVICE.post("/admin", &auth, &data).await;
     }
+
+    #[tokio::test]
+    async fn post_graphql_loads_data_by_operation_name() -> Result<(), HttpError> {
+        use crate::graphql::GraphQlRequest;
+
+        #[derive(Deserialize)]
+        struct UserData {
+            user: User,
+        }
+
+        let auth = Auth::new("my-api-key");
+        let request = GraphQlRequest::new("query GetUser { user { username } }")
+            .with_operation_name("GetUser");
+        let data: UserData = SERVICE.post_graphql("/graphql", &auth, &request).await?;
+        assert_eq!(data.user.username, "foo");
+
+        let request = GraphQlRequest::new("mutation DeleteUser { deleteUser }")
+            .with_operation_name("DeleteUser");
+        let result: HttpResult<serde_json::Value> =
+            SERVICE.post_graphql("/graphql", &auth, &request).await;
+        assert!(
+            matches!(result, Err(HttpError::GraphQl(errors)) if errors[0].message == "Forbidden")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_loads_fixtures_in_any_of_its_formats() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/output").with_formats([
+            FixtureFormat::Json,
+            FixtureFormat::Xml,
+            FixtureFormat::Text,
+        ]);
+        assert_eq!(
+            service.get("/users/foo/about").await?,
+            "{\"username\": \"foo\"}"
+        );
+        assert_eq!(
+            service.get("/reports/7").await?,
+            r#"<report id="7"><status>ok</status></report>"#
+        );
+        assert_eq!(service.get("/ping").await?, "pong");
+        let pong: String = service.post("/ping", &Auth::new("key"), &()).await?;
+        assert_eq!(pong, "pong");
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Closest 1 of 1 expectations")]
+    async fn get_only_suggests_fixtures_in_its_formats() {
+        let service = HttpTestService::new("tests/data/output").with_format(FixtureFormat::Xml);
+        let _ = service.get("/users/foo/about").await;
+    }
+
+    #[cfg(feature = "xml")]
+    #[tokio::test]
+    async fn post_parses_xml_fixtures() -> Result<(), HttpError> {
+        #[derive(Deserialize)]
+        struct Report {
+            #[serde(rename = "@id")]
+            id: u32,
+            status: String,
+        }
+
+        let service = HttpTestService::new("tests/data/output").with_format(FixtureFormat::Xml);
+        let report: Report = service.post("/reports/7", &Auth::new("key"), &()).await?;
+        assert_eq!(report.id, 7);
+        assert_eq!(report.status, "ok");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_binary_loads_raw_bytes() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/output").with_format(FixtureFormat::Binary);
+        let response = service
+            .get_binary("/avatars/foo", &HeaderMap::new())
+            .await?;
+        assert_eq!(response.body(), &b"\x89PNG\r\n\x1a\n\n"[..]);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+
+        let result: HttpResult<()> = service.post("/avatars/foo", &Auth::new("key"), &()).await;
+        assert!(matches!(
+            result,
+            Err(HttpError::UnexpectedContentType { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn loader_loads_data_in_other_formats() {
+        let loader = TestDataLoader::new("tests/data/input").with_format(FixtureFormat::Xml);
+        assert_eq!(
+            loader.load_raw("resource"),
+            b"<resource><foo>bar</foo></resource>\n"
+        );
+    }
+
+    #[test]
+    fn loader_detects_formats_by_extension() -> Result<(), HttpError> {
+        assert_eq!(
+            LOADER.try_load_raw("resource.xml")?,
+            b"<resource><foo>bar</foo></resource>\n"
+        );
+        let user: User = LOADER.try_load("user.json")?;
+        assert_eq!(user.username, "foo");
+        Ok(())
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn loader_loads_yaml() {
+        let user: User = LOADER.load("user.yaml");
+        assert_eq!(user.username, "foo");
+        let loader = TestDataLoader::new("tests/data/input").with_format(FixtureFormat::Yaml);
+        let user: User = loader.load("user");
+        assert_eq!(user.username, "foo");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn loader_loads_toml() {
+        let user: User = LOADER.load("user.toml");
+        assert_eq!(user.username, "foo");
+    }
+
+    #[cfg(not(feature = "toml"))]
+    #[test]
+    fn loader_cannot_parse_toml_without_its_feature() {
+        let result: Result<User, _> = LOADER.try_load("user.toml");
+        assert!(matches!(
+            result,
+            Err(HttpError::UnexpectedContentType { content_type, .. })
+                if content_type == "application/toml"
+        ));
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn loader_loads_csv_rows() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            username: String,
+            age: u32,
+        }
+
+        let rows: Vec<Row> = LOADER.load_csv("users");
+        assert_eq!(
+            rows,
+            [
+                Row {
+                    username: String::from("foo"),
+                    age: 31
+                },
+                Row {
+                    username: String::from("bar"),
+                    age: 27
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_load_fixtures_for_their_method() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/methods");
+        let auth = Auth::new("key");
+        assert_eq!(service.get("/users").await?, r#"[{"username": "foo"}]"#);
+        let created: User = service.post("/users", &auth, &()).await?;
+        assert_eq!(created.username, "bar");
+        assert_eq!(service.get("/users/foo").await?, r#"{"username": "foo"}"#);
+        let updated: User = service.post("/users/foo", &auth, &()).await?;
+        assert_eq!(updated.username, "foo");
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Closest 3 of 3 expectations:\n\n  GET /users\n")]
+    async fn requests_suggest_fixtures_with_their_method() {
+        let service = HttpTestService::new("tests/data/methods");
+        let _ = service.get("/user").await;
+    }
+
+    #[test]
+    fn fixture_requests_have_methods_from_their_directories() {
+        let root = Path::new("tests/data/methods");
+        assert_eq!(
+            fixture_request(root, &root.join("POST/users.json")),
+            (Some(Method::POST), String::from("/users"))
+        );
+        assert_eq!(
+            fixture_request(root, &root.join("users/foo.json")),
+            (None, String::from("/users/foo"))
+        );
+        assert_eq!(
+            fixture_request(root, &root.join("GET.json")),
+            (None, String::from("/GET"))
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_load_fixtures_for_their_query_parameters() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/queries");
+        let page = r#"{"page": 2, "results": ["rustup"]}"#;
+        assert_eq!(service.get("/search?q=rust&page=2").await?, page);
+        assert_eq!(service.get("/search?page=2&q=rust").await?, page);
+        assert_eq!(
+            service.get("/search?q=%72ust").await?,
+            r#"{"page": 1, "results": ["rust", "rustc"]}"#
+        );
+        assert_eq!(
+            service.get("/search?q=go").await?,
+            r#"{"page": 1, "results": []}"#
+        );
+        assert_eq!(service.get("/search").await?, r#"{"results": null}"#);
+        assert_eq!(service.get("/search?").await?, r#"{"results": null}"#);
+        Ok(())
+    }
+
+    #[test]
+    fn query_strings_are_sorted_and_sanitized() {
+        assert_eq!(canonical_query("q=rust&page=2"), "page=2&q=rust");
+        assert_eq!(canonical_query("b=2&a=1&a=0"), "a=0&a=1&b=2");
+        assert_eq!(canonical_query("path=..%2Fetc&q=a+b"), "path=.._etc&q=a_b");
+        assert_eq!(canonical_query("flag"), "flag=");
+    }
+
+    #[test]
+    fn fixture_requests_have_query_strings_from_their_names() {
+        let root = Path::new("tests/data/queries");
+        assert_eq!(
+            fixture_request(root, &root.join("search/page=2&q=rust.json")),
+            (None, String::from("/search?page=2&q=rust"))
+        );
+        assert_eq!(
+            fixture_request(root, &root.join("search/_any.json")),
+            (None, String::from("/search?*"))
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_fail_with_simulated_statuses() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("5"));
+        let service = HttpTestService::new("tests/data/output")
+            .with_status("/users/foo/about", StatusCode::NOT_FOUND)
+            .with_simulation(
+                "/users",
+                Simulation::Status(StatusCode::TOO_MANY_REQUESTS, headers),
+            );
+
+        let result = service.get("/users/foo/about").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+        let result: HttpResult<User> = service.post("/users", &Auth::new("key"), &()).await;
+        assert_eq!(
+            result.unwrap_err().retry_after(),
+            Some(Duration::from_secs(5))
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_fail_with_simulated_transport_errors() {
+        let service = HttpTestService::new("tests/data/output")
+            .with_simulation("/users", Simulation::Timeout(Duration::from_secs(30)))
+            .with_simulation(
+                "/users/foo/about",
+                Simulation::Transport(io::ErrorKind::ConnectionRefused),
+            );
+
+        let result = service
+            .get_binary("/users", &HeaderMap::new())
+            .await
+            .map(|_| ());
+        assert!(matches!(result, Err(HttpError::Timeout(_))));
+        let result = service.get_stream("/users/foo/about").await.map(|_| ());
+        assert!(matches!(
+            result,
+            Err(HttpError::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused
+        ));
+    }
+
+    #[tokio::test]
+    async fn simulated_connection_failures_are_retried() {
+        use crate::service::retry::{RetryPolicy, RetryService};
+
+        let service = HttpTestService::new("tests/data/output").with_simulation(
+            "/users/foo/about",
+            Simulation::Transport(io::ErrorKind::ConnectionReset),
+        );
+        let policy = RetryPolicy::new()
+            .with_max_attempts(3)
+            .with_backoff(Duration::ZERO, Duration::ZERO);
+        let service = RetryService::new(service, policy);
+
+        let result = service.get("/users/foo/about").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Io(err)) if err.kind() == io::ErrorKind::ConnectionReset
+        ));
+        assert_eq!(service.inner().requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn responses_carry_simulated_statuses() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/output")
+            .with_status("/users", StatusCode::INTERNAL_SERVER_ERROR)
+            .with_status("/uploads", StatusCode::BAD_REQUEST)
+            .with_status("/users/foo/about", StatusCode::ACCEPTED);
+
+        let body = || UploadBody::from_bytes(&b"data"[..]);
+        let response = service
+            .post_stream("/users", &HeaderMap::new(), body())
+            .await?;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.body(), "{\"username\": \"foo\"}");
+
+        let form = Multipart::new().text("username", "foo");
+        let response = service
+            .post_multipart("/uploads", &HeaderMap::new(), form)
+            .await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.body(), "");
+        assert_eq!(service.multipart_requests().len(), 1);
+
+        assert!(service.get("/users/foo/about").await.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sequences_answer_successive_requests() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/output")
+            .with_status("/users/foo/about", StatusCode::FORBIDDEN)
+            .with_sequence(
+                "/users/foo/about",
+                Sequence::new([
+                    Simulation::Status(StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new()),
+                    Simulation::Status(StatusCode::OK, HeaderMap::new()),
+                ]),
+            );
+
+        let first = service.get("/users/foo/about").await;
+        assert!(matches!(
+            first,
+            Err(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+        assert_eq!(
+            service.get("/users/foo/about").await?,
+            "{\"username\": \"foo\"}"
+        );
+        let third = service.get("/users/foo/about").await;
+        assert!(matches!(third, Err(HttpError::Http(StatusCode::FORBIDDEN))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sequences_can_repeat_their_last_step() -> Result<(), HttpError> {
+        let sequence = Sequence::new([
+            Simulation::Status(StatusCode::OK, HeaderMap::new()),
+            Simulation::Timeout(Duration::from_secs(30)),
+        ])
+        .repeating_last();
+        let service =
+            HttpTestService::new("tests/data/output").with_sequence("/users/foo/about", sequence);
+
+        service.get("/users/foo/about").await?;
+        for _ in 0..3 {
+            let result = service.get("/users/foo/about").await;
+            assert!(matches!(result, Err(HttpError::Timeout(_))));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retried_requests_take_the_next_step() -> Result<(), HttpError> {
+        use crate::service::retry::{RetryPolicy, RetryService};
+
+        let sequence = Sequence::new([
+            Simulation::Status(StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new()),
+            Simulation::Status(StatusCode::BAD_GATEWAY, HeaderMap::new()),
+        ]);
+        let service =
+            HttpTestService::new("tests/data/output").with_sequence("/users/foo/about", sequence);
+        let policy = RetryPolicy::new()
+            .with_max_attempts(3)
+            .with_backoff(Duration::ZERO, Duration::ZERO);
+        let service = RetryService::new(service, policy);
+
+        assert_eq!(
+            service.get("/users/foo/about").await?,
+            "{\"username\": \"foo\"}"
+        );
+        assert_eq!(service.inner().requests().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retried_requests_survive_dropped_connections() -> Result<(), HttpError> {
+        use crate::service::retry::{RetryPolicy, RetryService};
+
+        let sequence = Sequence::new([
+            Simulation::Status(StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new()),
+            Simulation::Transport(io::ErrorKind::ConnectionReset),
+            Simulation::Status(StatusCode::OK, HeaderMap::new()),
+        ]);
+        let service =
+            HttpTestService::new("tests/data/output").with_sequence("/users/foo/about", sequence);
+        let policy = RetryPolicy::new()
+            .with_max_attempts(3)
+            .with_backoff(Duration::ZERO, Duration::ZERO);
+        let service = RetryService::new(service, policy);
+
+        assert_eq!(
+            service.get("/users/foo/about").await?,
+            "{\"username\": \"foo\"}"
+        );
+        assert_eq!(service.inner().requests().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requests_are_recorded() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/output")
+            .with_status("/users/bar/about", StatusCode::NOT_FOUND);
+        let auth = Auth::new("my-api-key");
+        service.get("/users/foo/about").await?;
+        let data = User {
+            username: String::from("foo"),
+        };
+        let _: User = service.post("/users", &auth, &data).await?;
+        let _ = service.get("/users/bar/about").await;
+
+        let requests = service.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].uri, "/users/foo/about");
+        assert_eq!(requests[0].body, None);
+        assert_eq!(requests[1].method, Method::POST);
+        assert_eq!(
+            requests[1].headers[header::AUTHORIZATION],
+            "Bearer my-api-key"
+        );
+        assert_eq!(requests[1].auth.as_deref(), Some("bearer"));
+        assert_eq!(requests[1].text(), Some(r#"{"username":"foo"}"#));
+        assert_eq!(requests[2].uri, "/users/bar/about");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requests_record_their_headers_and_bodies() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/output");
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        service
+            .post_stream("/users", &headers, UploadBody::from_bytes("hello"))
+            .await?;
+
+        let requests = service.requests();
+        assert_eq!(requests[0].headers, headers);
+        assert_eq!(requests[0].auth, None);
+        assert_eq!(requests[0].text(), Some("hello"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn assert_posted_json_eq_compares_the_last_body() -> Result<(), HttpError> {
+        let service = HttpTestService::new("tests/data/output");
+        let auth = Auth::new("my-api-key");
+        let _: User = service
+            .post("/users", &auth, &serde_json::json!({"username": "bar"}))
+            .await?;
+        let _: User = service
+            .post(
+                "/users",
+                &auth,
+                &serde_json::json!({"username": "foo", "admin": false}),
+            )
+            .await?;
+        service.assert_posted_json_eq(
+            "/users",
+            serde_json::json!({"admin": false, "username": "foo"}),
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "unexpected body posted to /users")]
+    async fn assert_posted_json_eq_panics_if_bodies_differ() {
+        let service = HttpTestService::new("tests/data/output");
+        let auth = Auth::new("my-api-key");
+        let data = User {
+            username: String::from("foo"),
+        };
+        let _: Result<User, _> = service.post("/users", &auth, &data).await;
+        service.assert_posted_json_eq("/users", serde_json::json!({"username": "bar"}));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "no POST request was made to /users\n\nRequests made:\n  GET /users")]
+    async fn assert_posted_json_eq_panics_if_nothing_was_posted() {
+        let service = HttpTestService::new("tests/data/output");
+        let _ = service.get("/users").await;
+        service.assert_posted_json_eq("/users", serde_json::json!({}));
+    }
+
+    #[tokio::test]
+    async fn post_multipart_records_parts() -> Result<(), HttpError> {
+        use crate::upload::Part;
+
+        let service = HttpTestService::new("tests/data/output");
+        let form = Multipart::new().text("username", "foo").part(
+            Part::bytes("avatar", &b"\x89PNG"[..])
+                .with_filename("foo.png")
+                .with_content_type("image/png"),
+        );
+        let response = service
+            .post_multipart("/users", &HeaderMap::new(), form)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let forms = service.multipart_requests();
+        assert_eq!(forms.len(), 1);
+        assert_eq!(forms[0].uri, "/users");
+        assert_eq!(forms[0].part("username").unwrap().text(), Some("foo"));
+        let avatar = forms[0].part("avatar").unwrap();
+        assert_eq!(avatar.filename.as_deref(), Some("foo.png"));
+        assert_eq!(avatar.content_type.as_deref(), Some("image/png"));
+        assert_eq!(avatar.data, &b"\x89PNG"[..]);
+        Ok(())
+    }
 }
@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Latency and body-size budgets for tests.
+//!
+//! A [`BudgetService`] wraps a service and panics, failing the test, if any
+//! request takes longer or returns a larger body than its [`Budget`]
+//! allows. Wrapping the service used by an integration test or a
+//! [`HttpTestService`](super::HttpTestService) with a budget turns
+//! performance regressions in client flows, such as an extra round trip or
+//! a forgotten page size, into test failures.
+//!
+//! Latency is measured with [`tokio::time::Instant`], so tests that pause
+//! Tokio's clock can check budgets deterministically.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::testing::budget::{Budget, BudgetService};
+//! use std::time::Duration;
+//!
+//! fn budgeted<S: HttpService>(service: S) -> BudgetService<S> {
+//!     let budget = Budget::new()
+//!         .with_max_latency(Duration::from_millis(250))
+//!         .with_max_body_len(64 * 1024);
+//!     BudgetService::new(service, budget)
+//! }
+//! ```
+
+use crate::HttpResult;
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost};
+use reqwest::IntoUrl;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The limits a [`BudgetService`] enforces on each request.
+///
+/// A new budget has no limits.
+#[derive(Clone, Debug, Default)]
+pub struct Budget {
+    max_latency: Option<Duration>,
+    max_body_len: Option<usize>,
+}
+
+impl Budget {
+    /// Creates a budget with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails any request that takes longer than `max_latency`.
+    pub fn with_max_latency(mut self, max_latency: Duration) -> Self {
+        self.max_latency = Some(max_latency);
+        self
+    }
+
+    /// Fails any request whose response body is longer than `max_body_len`
+    /// bytes.
+    pub fn with_max_body_len(mut self, max_body_len: usize) -> Self {
+        self.max_body_len = Some(max_body_len);
+        self
+    }
+
+    /// The longest a request may take, if there is a limit.
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.max_latency
+    }
+
+    /// The longest a response body may be, in bytes, if there is a limit.
+    pub fn max_body_len(&self) -> Option<usize> {
+        self.max_body_len
+    }
+
+    fn check(&self, method: &str, uri: &str, latency: Duration, body_len: usize) {
+        if let Some(max_latency) = self.max_latency {
+            assert!(
+                latency <= max_latency,
+                "{method} {uri} took {latency:?}, exceeding its latency budget of {max_latency:?}"
+            );
+        }
+        if let Some(max_body_len) = self.max_body_len {
+            assert!(
+                body_len <= max_body_len,
+                "{method} {uri} returned {body_len} bytes, exceeding its body size budget of {max_body_len} bytes"
+            );
+        }
+    }
+}
+
+/// Wraps an HTTP service and panics if any request exceeds its [`Budget`].
+///
+/// See the [module documentation](crate::service::testing::budget) for
+/// details.
+#[derive(Clone, Debug)]
+pub struct BudgetService<S> {
+    inner: S,
+    budget: Budget,
+}
+
+impl<S> BudgetService<S> {
+    /// Wraps `inner` in a service that enforces `budget`.
+    pub fn new(inner: S, budget: Budget) -> Self {
+        Self { inner, budget }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The budget enforced on each request.
+    pub fn budget(&self) -> &Budget {
+        &self.budget
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for BudgetService<S> {
+    /// Sends a GET request with the wrapped service.
+    ///
+    /// # Panics
+    ///
+    /// If the request takes too long or its response body is too large.
+    /// Failed requests are only checked against the latency budget.
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        let start = Instant::now();
+        let result = self.inner.get(uri.as_str()).await;
+        let body_len = result.as_ref().map_or(0, String::len);
+        self.budget.check("GET", &uri, start.elapsed(), body_len);
+        result
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for BudgetService<S> {
+    /// Sends a POST request with the wrapped service.
+    ///
+    /// Since POST responses are deserialized by the wrapped service, the
+    /// size of the body is measured by serializing the response again, so
+    /// it does not count insignificant whitespace.
+    ///
+    /// # Panics
+    ///
+    /// If the request takes too long or its response body is too large.
+    /// Failed requests are only checked against the latency budget.
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str().to_string();
+        let start = Instant::now();
+        let result: HttpResult<Value> = self.inner.post(uri.as_str(), auth, data).await;
+        let body_len = result.as_ref().map_or(0, |value| value.to_string().len());
+        self.budget.check("POST", &uri, start.elapsed(), body_len);
+        Ok(serde_json::from_value(result?)?)
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for BudgetService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("budget").with_config(format!("{:?}", self.budget)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+
+    /// Waits for a while, then returns a fixed body.
+    struct SlowService {
+        delay: Duration,
+        body: &'static str,
+    }
+
+    impl HttpGet for SlowService {
+        async fn get<U>(&self, _uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            tokio::time::sleep(self.delay).await;
+            Ok(self.body.to_string())
+        }
+    }
+
+    impl HttpPost for SlowService {
+        async fn post<U, D, R>(
+            &self,
+            _uri: U,
+            _auth: &dyn Authenticator,
+            _data: &D,
+        ) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            tokio::time::sleep(self.delay).await;
+            Ok(serde_json::from_str(self.body)?)
+        }
+    }
+
+    fn service(delay_ms: u64, body: &'static str) -> BudgetService<SlowService> {
+        let delay = Duration::from_millis(delay_ms);
+        let budget = Budget::new()
+            .with_max_latency(Duration::from_millis(100))
+            .with_max_body_len(16);
+        BudgetService::new(SlowService { delay, body }, budget)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_allows_requests_within_budget() -> HttpResult<()> {
+        let service = service(100, r#"{"id": 1}"#);
+        assert_eq!(service.get("/users/1").await?, r#"{"id": 1}"#);
+        let user: Value = service.post("/users", &Auth::new("key"), &()).await?;
+        assert_eq!(user["id"], 1);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[should_panic(expected = "GET /users/1 took 150ms, exceeding its latency budget of 100ms")]
+    async fn it_fails_slow_requests() {
+        let _ = service(150, "{}").get("/users/1").await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[should_panic(expected = "GET /users returned 18 bytes")]
+    async fn it_fails_large_get_responses() {
+        let _ = service(0, r#"["alice", "bobby"]"#).get("/users").await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[should_panic(expected = "POST /users returned 17 bytes")]
+    async fn it_fails_large_post_responses() {
+        let service = service(0, r#"["alice", "bobby"]"#);
+        let _: HttpResult<Value> = service.post("/users", &Auth::new("key"), &()).await;
+    }
+}
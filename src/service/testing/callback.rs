@@ -0,0 +1,347 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Receiving callbacks from asynchronous APIs in tests.
+//!
+//! Some APIs do not respond to a request with its result. Instead, they
+//! take a callback URL and later send the result to it, like a webhook. A
+//! [`CallbackReceiver`] makes those APIs testable: it listens on a local
+//! port, hands out [URLs](CallbackReceiver::url) that the API under test
+//! can call back, and resolves [`next()`](CallbackReceiver::next) with
+//! each [`Callback`] it receives.
+//!
+//! The receiver speaks just enough HTTP/1.1 to accept a request and answer
+//! it with an empty response, `200 OK` by default. Use
+//! [`respond_with()`](CallbackReceiver::respond_with) to check how the
+//! API handles callbacks that fail.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::testing::callback::CallbackReceiver;
+//! use serde::Deserialize;
+//! use serde_json::json;
+//! use std::time::Duration;
+//!
+//! #[derive(Deserialize)]
+//! struct Report {
+//!     status: String,
+//! }
+//!
+//! # async fn run<S: HttpPost>(service: S, auth: Auth) -> HttpResult<()> {
+//! let mut receiver = CallbackReceiver::bind().await?;
+//! let request = json!({ "callback_url": receiver.url("/reports") });
+//! let _: serde_json::Value = service
+//!     .post("https://api.example.com/reports", &auth, &request)
+//!     .await?;
+//!
+//! let callback = receiver.wait(Duration::from_secs(30)).await?;
+//! assert_eq!(callback.path, "/reports");
+//! let report: Report = callback.json()?;
+//! assert_eq!(report.status, "complete");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::decode::Decoder;
+use crate::{HttpError, HttpResult};
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// A request received by a [`CallbackReceiver`].
+#[derive(Clone, Debug)]
+pub struct Callback {
+    /// The request method, usually `POST`.
+    pub method: Method,
+
+    /// The path and query of the request, such as `/reports?id=7`.
+    pub path: String,
+
+    /// The request headers.
+    pub headers: HeaderMap,
+
+    /// The request body.
+    pub body: Bytes,
+}
+
+impl Callback {
+    /// The request body as text, with any invalid UTF-8 replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Deserializes the request body as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> HttpResult<T> {
+        Decoder::new().decode(&self.text())
+    }
+}
+
+/// Listens on a local port for callbacks from the API under test.
+///
+/// The listener stops when the receiver is dropped.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct CallbackReceiver {
+    addr: SocketAddr,
+    status: Arc<AtomicU16>,
+    callbacks: mpsc::UnboundedReceiver<Callback>,
+    listener: JoinHandle<()>,
+}
+
+impl CallbackReceiver {
+    /// Starts listening on a free port on the loopback interface.
+    pub async fn bind() -> io::Result<Self> {
+        Self::bind_to("127.0.0.1:0").await
+    }
+
+    /// Starts listening on `addr`.
+    ///
+    /// Bind to a public interface when the API under test runs on another
+    /// machine, such as a staging environment.
+    pub async fn bind_to(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let status = Arc::new(AtomicU16::new(StatusCode::OK.as_u16()));
+        let (sender, callbacks) = mpsc::unbounded_channel();
+        let listener = tokio::spawn(accept(listener, sender, Arc::clone(&status)));
+        Ok(Self {
+            addr,
+            status,
+            callbacks,
+            listener,
+        })
+    }
+
+    /// The address the receiver is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The URL that calls back to the receiver at `path`.
+    pub fn url(&self, path: &str) -> String {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        format!("http://{}/{path}", self.addr)
+    }
+
+    /// Sets the status of the responses to callbacks received from now on.
+    pub fn respond_with(&self, status: StatusCode) {
+        self.status.store(status.as_u16(), Ordering::Relaxed);
+    }
+
+    /// Waits for the next callback.
+    ///
+    /// Callbacks are returned in the order they arrived, including any
+    /// that arrived before this was called.
+    pub async fn next(&mut self) -> Option<Callback> {
+        self.callbacks.recv().await
+    }
+
+    /// Waits up to `timeout` for the next callback.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Timeout`] if no callback arrives in time.
+    pub async fn wait(&mut self, timeout: Duration) -> HttpResult<Callback> {
+        match tokio::time::timeout(timeout, self.next()).await {
+            Ok(Some(callback)) => Ok(callback),
+            _ => Err(HttpError::Timeout(timeout)),
+        }
+    }
+}
+
+impl Drop for CallbackReceiver {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+async fn accept(
+    listener: TcpListener,
+    sender: mpsc::UnboundedSender<Callback>,
+    status: Arc<AtomicU16>,
+) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let sender = sender.clone();
+        let status = Arc::clone(&status);
+        tokio::spawn(async move {
+            // A malformed request is simply dropped; the API under test
+            // will see a closed connection.
+            if let Ok(callback) = handle(stream, &status).await {
+                let _ = sender.send(callback);
+            }
+        });
+    }
+}
+
+/// Reads a single request from `stream` and answers it.
+async fn handle(stream: TcpStream, status: &AtomicU16) -> io::Result<Callback> {
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+    let mut parts = line.split_ascii_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(invalid("malformed request line"));
+    };
+    let method = Method::from_bytes(method.as_bytes()).map_err(|_| invalid("invalid method"))?;
+    let path = path.to_string();
+
+    let mut headers = HeaderMap::new();
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await? == 0 {
+            return Err(invalid("unexpected end of request"));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(invalid("malformed header"));
+        };
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| invalid("invalid header name"))?;
+        let value =
+            HeaderValue::from_str(value.trim()).map_err(|_| invalid("invalid header value"))?;
+        headers.append(name, value);
+    }
+
+    let chunked = headers
+        .get(reqwest::header::TRANSFER_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.to_ascii_lowercase().contains("chunked"));
+    let body = if chunked {
+        read_chunked(&mut stream).await?
+    } else {
+        let len = headers
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; len];
+        stream.read_exact(&mut body).await?;
+        body
+    };
+
+    let status = StatusCode::from_u16(status.load(Ordering::Relaxed)).unwrap_or(StatusCode::OK);
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default()
+    );
+    stream.get_mut().write_all(response.as_bytes()).await?;
+    stream.get_mut().shutdown().await?;
+
+    Ok(Callback {
+        method,
+        path,
+        headers,
+        body: Bytes::from(body),
+    })
+}
+
+async fn read_chunked(stream: &mut BufReader<TcpStream>) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await?;
+        // Chunk extensions follow a semicolon and are ignored.
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| invalid("invalid chunk size"))?;
+        if size == 0 {
+            // Skip any trailers, up to the final blank line.
+            loop {
+                line.clear();
+                if stream.read_line(&mut line).await? == 0 || line.trim_end().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        stream.read_exact(&mut body[start..]).await?;
+        line.clear();
+        stream.read_line(&mut line).await?;
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::{HttpPostStream, HttpResponse};
+    use crate::upload::UploadBody;
+    use futures_util::stream;
+    use serde_json::{Value, json};
+
+    fn client() -> crate::HttpClient {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_receives_callbacks() -> HttpResult<()> {
+        let mut receiver = CallbackReceiver::bind().await?;
+        let url = receiver.url("/jobs/7?attempt=1");
+        assert!(url.starts_with("http://127.0.0.1:"));
+
+        let response = client()
+            .post(&url)
+            .header("x-signature", "abc")
+            .json(&json!({ "status": "done" }))
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let callback = receiver.wait(Duration::from_secs(5)).await?;
+        assert_eq!(callback.method, Method::POST);
+        assert_eq!(callback.path, "/jobs/7?attempt=1");
+        assert_eq!(callback.headers["x-signature"], "abc");
+        assert_eq!(callback.json::<Value>()?, json!({ "status": "done" }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_receives_chunked_bodies_and_sets_the_status() -> HttpResult<()> {
+        let mut receiver = CallbackReceiver::bind().await?;
+        receiver.respond_with(StatusCode::SERVICE_UNAVAILABLE);
+        let chunks =
+            stream::iter(["hello, ", "world"].map(|chunk| Ok::<_, HttpError>(Bytes::from(chunk))));
+        let response: HttpResponse = client()
+            .post_stream(
+                receiver.url("hook"),
+                &HeaderMap::new(),
+                UploadBody::from_stream(chunks),
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            receiver.wait(Duration::from_secs(5)).await?.text(),
+            "hello, world"
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_times_out_without_a_callback() -> io::Result<()> {
+        let mut receiver = CallbackReceiver::bind().await?;
+        let result = receiver.wait(Duration::from_secs(30)).await;
+        assert!(matches!(result, Err(HttpError::Timeout(_))));
+        Ok(())
+    }
+}
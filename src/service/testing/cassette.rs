@@ -0,0 +1,713 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Recording live interactions and replaying them in tests.
+//!
+//! Hand-written fixtures drift from the APIs they stand in for. A
+//! [`RecordingService`] wraps a live service and writes every request it
+//! sends, and the response it receives, to a [`Cassette`] file. A
+//! [`ReplayService`] later answers the same requests from the cassette,
+//! without a network, so a test suite can be run against realistic data
+//! from a single live run.
+//!
+//! Requests are matched to recorded interactions by the [rules](MatchRule)
+//! a `ReplayService` is given, which are its method and URI by default.
+//! Each interaction answers one request, in the order they were recorded,
+//! and the last interaction that matches a request answers it again once
+//! the others have been used, so replays are deterministic.
+//!
+//! Only the method, URI, and body of requests are recorded, so credentials
+//! in request headers are never written to a cassette, but responses are
+//! recorded in full, apart from `Set-Cookie` headers. Requests that fail
+//! before a response is received are not recorded.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::testing::cassette::{RecordingService, ReplayService};
+//!
+//! # async fn record<S: HttpService + Sync>(live: S) -> HttpResult<()> {
+//! // Once, against the live API:
+//! let service = RecordingService::new(live, "tests/cassettes/users.json");
+//! service.get("https://api.example.com/users/foo").await?;
+//!
+//! // From then on, offline:
+//! let service = ReplayService::open("tests/cassettes/users.json")?;
+//! let body = service.get("https://api.example.com/users/foo").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::testing::transcript::{Expectation, RequestSummary, Transcript};
+use crate::service::{HttpGet, HttpGetResponse, HttpPost, HttpResponse};
+use crate::{HttpError, HttpResult};
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{IntoUrl, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Interactions with an API, in the order they were recorded.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Cassette {
+    /// The recorded interactions.
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Creates a cassette without any interactions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the cassette in the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Io`] if the file cannot be read, or an error if
+    /// it is not a cassette.
+    pub fn load(path: impl AsRef<Path>) -> HttpResult<Self> {
+        let data = fs::read(path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Writes the cassette to the file at `path`, creating its directory
+    /// if necessary.
+    ///
+    /// The cassette is written as indented JSON, so that changes to it can
+    /// be reviewed, and replaces the file in a single step.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Io`] if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> HttpResult<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(temp, path)?;
+        Ok(())
+    }
+
+    /// The number of interactions on the cassette.
+    pub fn len(&self) -> usize {
+        self.interactions.len()
+    }
+
+    /// True if there are no interactions on the cassette.
+    pub fn is_empty(&self) -> bool {
+        self.interactions.is_empty()
+    }
+}
+
+/// A request and the response it received.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Interaction {
+    /// The request that was sent.
+    pub request: CassetteRequest,
+
+    /// The response that was received.
+    pub response: CassetteResponse,
+}
+
+/// A request recorded on a [`Cassette`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CassetteRequest {
+    /// The method of the request, such as `GET`.
+    pub method: String,
+
+    /// The URI the request was sent to.
+    pub uri: String,
+
+    /// The body of the request, if it had one.
+    pub body: Option<String>,
+}
+
+impl CassetteRequest {
+    fn new(method: Method, uri: &str, body: Option<String>) -> Self {
+        Self {
+            method: method.to_string(),
+            uri: uri.to_string(),
+            body,
+        }
+    }
+
+    /// True if `request` matches this one by all of `rules`.
+    fn matches(&self, request: &CassetteRequest, rules: &[MatchRule]) -> bool {
+        rules.iter().all(|rule| match rule {
+            MatchRule::Method => self.method == request.method,
+            MatchRule::Uri => self.uri == request.uri,
+            MatchRule::Body => bodies_match(self.body.as_deref(), request.body.as_deref()),
+        })
+    }
+
+    /// A description of this request, for a transcript.
+    fn expectation(&self) -> Expectation {
+        let expectation = Expectation::new(&self.uri);
+        let expectation = match Method::from_bytes(self.method.as_bytes()) {
+            Ok(method) => expectation.with_method(method),
+            Err(_) => expectation,
+        };
+        match &self.body {
+            Some(body) => expectation.with_body(body),
+            None => expectation,
+        }
+    }
+}
+
+/// A response recorded on a [`Cassette`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CassetteResponse {
+    /// The status code of the response.
+    pub status: u16,
+
+    /// The headers of the response, in order.
+    pub headers: Vec<(String, String)>,
+
+    /// The body of the response.
+    pub body: String,
+}
+
+impl CassetteResponse {
+    /// A 200 OK with `body` and no headers.
+    fn ok(body: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::OK.as_u16(),
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// The response that `err` was created from, as far as it can be
+    /// recovered, or `None` if no response was received.
+    fn from_error(err: &HttpError) -> Option<Self> {
+        let status = err.status()?;
+        let headers = err
+            .retry_after()
+            .map(|delay| (header::RETRY_AFTER.to_string(), delay.as_secs().to_string()))
+            .into_iter()
+            .collect();
+        Some(Self {
+            status: status.as_u16(),
+            headers,
+            body: String::new(),
+        })
+    }
+
+    /// The response to a request made with a verb that returns responses of
+    /// any status.
+    fn from_response(response: &HttpResponse) -> Self {
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| **name != header::SET_COOKIE)
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Self {
+            status: response.status().as_u16(),
+            headers,
+            body: response.body().to_string(),
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// The headers that are valid.
+    fn header_map(&self) -> HeaderMap {
+        self.headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = HeaderName::try_from(name.as_str()).ok()?;
+                let value = HeaderValue::try_from(value.as_str()).ok()?;
+                Some((name, value))
+            })
+            .collect()
+    }
+
+    /// The body of a successful response, or the error that an unsuccessful
+    /// one fails with.
+    fn into_body(self) -> HttpResult<String> {
+        let status = self.status();
+        if !status.is_success() {
+            return Err(HttpError::from_status(status, &self.header_map()));
+        }
+        Ok(self.body)
+    }
+
+    fn into_response(self) -> HttpResponse {
+        self.header_map().iter().fold(
+            HttpResponse::new(self.status(), self.body),
+            |response, (name, value)| response.with_header(name.clone(), value.clone()),
+        )
+    }
+}
+
+/// True if two request bodies are the same, ignoring insignificant
+/// differences in JSON bodies, such as the order of keys.
+fn bodies_match(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            match (
+                serde_json::from_str::<Value>(a),
+                serde_json::from_str::<Value>(b),
+            ) {
+                (Ok(a), Ok(b)) => a == b,
+                _ => a == b,
+            }
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// Wraps a service and records its interactions on a [`Cassette`].
+///
+/// The cassette is written to its file after each interaction, so it is
+/// complete even if the recording run fails partway through. A file that
+/// already exists is replaced.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct RecordingService<S> {
+    inner: S,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl<S> RecordingService<S> {
+    /// Wraps `inner` in a service that records its interactions on a
+    /// cassette at `path`.
+    pub fn new(inner: S, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Mutex::default(),
+        }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The file the cassette is written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The interactions that have been recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    /// Records `request` and the response it received, if it received one.
+    fn record(
+        &self,
+        request: CassetteRequest,
+        response: Option<CassetteResponse>,
+    ) -> HttpResult<()> {
+        let Some(response) = response else {
+            return Ok(());
+        };
+        let mut cassette = self.cassette.lock().unwrap();
+        cassette
+            .interactions
+            .push(Interaction { request, response });
+        cassette.save(&self.path)
+    }
+}
+
+impl<S: HttpGet + Sync> HttpGet for RecordingService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        let result = self.inner.get(uri.as_str()).await;
+        let response = match &result {
+            Ok(body) => Some(CassetteResponse::ok(body.as_str())),
+            Err(err) => CassetteResponse::from_error(err),
+        };
+        self.record(CassetteRequest::new(Method::GET, &uri, None), response)?;
+        result
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for RecordingService<S> {
+    /// Sends a POST request with the wrapped service, and records its
+    /// response as JSON.
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let uri = uri.as_str().to_string();
+        let body = serde_json::to_string(data)?;
+        let result: HttpResult<Value> = self.inner.post(uri.as_str(), auth, data).await;
+        let response = match &result {
+            Ok(value) => Some(CassetteResponse::ok(value.to_string())),
+            Err(err) => CassetteResponse::from_error(err),
+        };
+        self.record(
+            CassetteRequest::new(Method::POST, &uri, Some(body)),
+            response,
+        )?;
+        Ok(serde_json::from_value(result?)?)
+    }
+}
+
+impl<S: HttpGetResponse + Sync> HttpGetResponse for RecordingService<S> {
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let uri = uri.as_str().to_string();
+        let result = self.inner.get_response(uri.as_str(), headers).await;
+        let response = match &result {
+            Ok(response) => Some(CassetteResponse::from_response(response)),
+            Err(err) => CassetteResponse::from_error(err),
+        };
+        self.record(CassetteRequest::new(Method::GET, &uri, None), response)?;
+        result
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for RecordingService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post, Verb::GetResponse])
+            .wrap(Middleware::new("recording").with_config(self.path.display().to_string()))
+    }
+}
+
+/// A part of a request that must be the same as that of a recorded
+/// request for the recorded response to be replayed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MatchRule {
+    /// The method of the request.
+    Method,
+
+    /// The URI of the request, including its query string.
+    Uri,
+
+    /// The body of the request. JSON bodies match if they hold the same
+    /// values, whatever the order of their keys or their whitespace.
+    Body,
+}
+
+/// Answers requests with the interactions on a [`Cassette`].
+///
+/// Requests that do not match any interaction fail with
+/// [`HttpError::FixtureNotFound`], which describes the closest ones.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct ReplayService {
+    cassette: Cassette,
+    rules: Vec<MatchRule>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayService {
+    /// Creates a service that replays the interactions on `cassette`,
+    /// matching requests by their method and URI.
+    pub fn new(cassette: Cassette) -> Self {
+        let used = Mutex::new(vec![false; cassette.len()]);
+        Self {
+            cassette,
+            rules: vec![MatchRule::Method, MatchRule::Uri],
+            used,
+        }
+    }
+
+    /// Creates a service that replays the cassette in the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cassette cannot be [loaded](Cassette::load).
+    pub fn open(path: impl AsRef<Path>) -> HttpResult<Self> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    /// Matches requests to interactions by `rules` instead of by their
+    /// method and URI.
+    pub fn with_match_rules(mut self, rules: impl IntoIterator<Item = MatchRule>) -> Self {
+        self.rules = rules.into_iter().collect();
+        self
+    }
+
+    /// The rules that requests are matched to interactions by.
+    pub fn match_rules(&self) -> &[MatchRule] {
+        &self.rules
+    }
+
+    /// The cassette being replayed.
+    pub fn cassette(&self) -> &Cassette {
+        &self.cassette
+    }
+
+    /// The recorded response to `request`.
+    ///
+    /// The first unused interaction that matches is used, or if every
+    /// match has been used, the last one.
+    fn replay(&self, request: CassetteRequest) -> HttpResult<CassetteResponse> {
+        let matches: Vec<_> = self
+            .cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request.matches(&request, &self.rules))
+            .map(|(index, _)| index)
+            .collect();
+        let mut used = self.used.lock().unwrap();
+        let index = matches
+            .iter()
+            .find(|&&index| !used[index])
+            .or(matches.last())
+            .copied();
+        match index {
+            Some(index) => {
+                used[index] = true;
+                Ok(self.cassette.interactions[index].response.clone())
+            }
+            None => Err(HttpError::FixtureNotFound(self.transcript(&request))),
+        }
+    }
+
+    /// A transcript of the interactions closest to `request`.
+    fn transcript(&self, request: &CassetteRequest) -> String {
+        let method = Method::from_bytes(request.method.as_bytes()).unwrap_or_default();
+        let summary = RequestSummary::new(method, &request.uri);
+        let summary = match &request.body {
+            Some(body) if self.rules.contains(&MatchRule::Body) => summary.with_body(body),
+            _ => summary,
+        };
+        let expectations = self.cassette.interactions.iter().map(|interaction| {
+            let mut request = interaction.request.clone();
+            if !self.rules.contains(&MatchRule::Body) {
+                request.body = None;
+            }
+            request.expectation()
+        });
+        Transcript::new(summary, expectations).to_string()
+    }
+}
+
+impl HttpGet for ReplayService {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let request = CassetteRequest::new(Method::GET, uri.as_str(), None);
+        self.replay(request)?.into_body()
+    }
+}
+
+impl HttpPost for ReplayService {
+    /// Answers a POST request from the cassette, deserializing the recorded
+    /// body from JSON.
+    ///
+    /// `auth` is ignored, since credentials are not recorded.
+    async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let body = serde_json::to_string(data)?;
+        let request = CassetteRequest::new(Method::POST, uri.as_str(), Some(body));
+        let status = StatusCode::OK;
+        let body = self.replay(request)?.into_body()?;
+        HttpResponse::new(status, body).json()
+    }
+}
+
+impl HttpGetResponse for ReplayService {
+    async fn get_response<U>(&self, uri: U, _headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let request = CassetteRequest::new(Method::GET, uri.as_str(), None);
+        Ok(self.replay(request)?.into_response())
+    }
+}
+
+impl HttpCapabilities for ReplayService {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new([Verb::Get, Verb::Post, Verb::GetResponse])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use crate::service::testing::mock::MockService;
+    use serde_json::json;
+
+    fn live() -> MockService {
+        MockService::builder()
+            .on_get("/users/foo")
+            .respond_json(json!({"username": "foo"}))
+            .on_get("/users/bar")
+            .respond_status(StatusCode::NOT_FOUND)
+            .on_post("/users")
+            .respond_json(json!({"username": "baz", "id": 1}))
+            .on_get("/feed")
+            .with_header(header::ETAG, HeaderValue::from_static("\"v1\""))
+            .with_header(
+                header::SET_COOKIE,
+                HeaderValue::from_static("session=secret"),
+            )
+            .respond_text("[]")
+            .build()
+    }
+
+    async fn record(path: &Path) -> HttpResult<()> {
+        let service = RecordingService::new(live(), path);
+        let auth = Auth::new("my-api-key");
+        service.get("https://api.example.com/users/foo").await?;
+        assert!(
+            service
+                .get("https://api.example.com/users/bar")
+                .await
+                .is_err()
+        );
+        let _: Value = service
+            .post(
+                "https://api.example.com/users",
+                &auth,
+                &json!({"username": "baz"}),
+            )
+            .await?;
+        service
+            .get_response("https://api.example.com/feed", &HeaderMap::new())
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_records_interactions_to_a_file() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("cassettes/users.json");
+        record(&path).await?;
+
+        let cassette = Cassette::load(&path)?;
+        assert_eq!(cassette.len(), 4);
+        let post = &cassette.interactions[2];
+        assert_eq!(post.request.method, "POST");
+        assert_eq!(post.request.body.as_deref(), Some(r#"{"username":"baz"}"#));
+        assert_eq!(cassette.interactions[1].response.status, 404);
+        let feed = &cassette.interactions[3].response;
+        assert_eq!(
+            feed.headers,
+            [(String::from("etag"), String::from("\"v1\""))]
+        );
+        let contents = fs::read_to_string(&path)?;
+        assert!(!contents.contains("my-api-key"));
+        assert!(!contents.contains("secret"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_replays_recorded_interactions() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("users.json");
+        record(&path).await?;
+
+        let service = ReplayService::open(&path)?;
+        let auth = Auth::new("another-key");
+        assert_eq!(
+            service.get("https://api.example.com/users/foo").await?,
+            r#"{"username":"foo"}"#
+        );
+        let result = service.get("https://api.example.com/users/bar").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+        let user: Value = service
+            .post(
+                "https://api.example.com/users",
+                &auth,
+                &json!({"username": "qux"}),
+            )
+            .await?;
+        assert_eq!(user["id"], 1);
+        let response = service
+            .get_response("https://api.example.com/feed", &HeaderMap::new())
+            .await?;
+        assert_eq!(response.headers()[header::ETAG], "\"v1\"");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_matches_requests_by_body() -> HttpResult<()> {
+        let interaction = |body: &str, id: u32| Interaction {
+            request: CassetteRequest::new(Method::POST, "/users", Some(body.to_string())),
+            response: CassetteResponse::ok(json!({"id": id}).to_string()),
+        };
+        let cassette = Cassette {
+            interactions: vec![
+                interaction(r#"{"username":"foo","admin":false}"#, 1),
+                interaction(r#"{"username":"bar"}"#, 2),
+            ],
+        };
+        let service = ReplayService::new(cassette).with_match_rules([
+            MatchRule::Method,
+            MatchRule::Uri,
+            MatchRule::Body,
+        ]);
+        let auth = Auth::new("key");
+
+        let user: Value = service
+            .post("/users", &auth, &json!({"username": "bar"}))
+            .await?;
+        assert_eq!(user["id"], 2);
+        let user: Value = service
+            .post("/users", &auth, &json!({"admin": false, "username": "foo"}))
+            .await?;
+        assert_eq!(user["id"], 1);
+        let result: HttpResult<Value> = service
+            .post("/users", &auth, &json!({"username": "baz"}))
+            .await;
+        assert!(matches!(
+            result,
+            Err(HttpError::FixtureNotFound(message)) if message.contains("/users")
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_replays_interactions_in_order() -> HttpResult<()> {
+        let interaction = |body: &str| Interaction {
+            request: CassetteRequest::new(Method::GET, "/jobs/1", None),
+            response: CassetteResponse::ok(body),
+        };
+        let cassette = Cassette {
+            interactions: vec![interaction("running"), interaction("done")],
+        };
+        let service = ReplayService::new(cassette);
+
+        assert_eq!(service.get("/jobs/1").await?, "running");
+        assert_eq!(service.get("/jobs/1").await?, "done");
+        assert_eq!(service.get("/jobs/1").await?, "done");
+        let result = service.get("/jobs/2").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::FixtureNotFound(message)) if message.contains("GET /jobs/1")
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,651 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! A test service whose responses are declared in code.
+//!
+//! [`HttpTestService`](super::HttpTestService) answers requests from
+//! fixtures on the file system, which suits tests that share large
+//! responses. A small test is easier to read when its responses sit next
+//! to its assertions, so a [`MockService`] is built from routes instead,
+//! each of which pairs a method and URI with a response:
+//!
+//! ```
+//! use hypertyper::auth::Auth;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::testing::mock::MockService;
+//! use reqwest::StatusCode;
+//! use serde_json::json;
+//!
+//! # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! # runtime.block_on(async {
+//! let service = MockService::builder()
+//!     .on_get("/users/1")
+//!     .respond_json(json!({"username": "foo"}))
+//!     .on_post("/users")
+//!     .respond_status(StatusCode::CREATED)
+//!     .build();
+//!
+//! assert_eq!(service.get("/users/1").await.unwrap(), r#"{"username":"foo"}"#);
+//! let auth = Auth::new("my-api-key");
+//! let () = service.post("/users", &auth, &json!({"username": "bar"})).await.unwrap();
+//! assert_eq!(service.requests().len(), 2);
+//! # });
+//! ```
+//!
+//! Routes match the path and query string of a request exactly, whether
+//! the request is made to a relative URI or an absolute URL. A request
+//! that matches no route panics with a [transcript](super::transcript::Transcript) of
+//! the closest routes. Giving the same route several responses answers
+//! successive requests with each in turn, and the last one is repeated
+//! once the others are used up.
+
+use crate::HttpError;
+use crate::auth::Authenticator;
+use crate::service::auth::authenticate;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Verb};
+use crate::service::testing::transcript::{Expectation, RequestSummary, Transcript};
+use crate::service::testing::{RecordedRequest, Simulation};
+use crate::service::{
+    BinaryResponse, BodyStream, HttpBinary, HttpGet, HttpGetResponse, HttpGetStream, HttpPost,
+    HttpPostStream, HttpResponse, HttpResult,
+};
+use crate::upload::{Multipart, UploadBody};
+use bytes::Bytes;
+use futures_util::stream;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{IntoUrl, Method, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use url::Url;
+
+/// A test service that answers requests with responses declared in code.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug)]
+pub struct MockService {
+    routes: Mutex<Vec<Route>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl MockService {
+    /// Starts building a service without any routes.
+    pub fn builder() -> MockServiceBuilder {
+        MockServiceBuilder::default()
+    }
+
+    /// The requests that have been made to this service, in the order
+    /// they were received.
+    ///
+    /// Multipart forms are recorded without a body.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Records a `method` request to `uri`.
+    fn record(&self, method: Method, uri: &str, headers: HeaderMap, body: Option<Bytes>) {
+        self.record_request(RecordedRequest {
+            method,
+            uri: uri.to_string(),
+            headers,
+            auth: None,
+            body,
+        });
+    }
+
+    /// Records a `method` request to `uri` with a JSON `body`, authenticated
+    /// by `auth`.
+    fn record_json<D: Serialize>(
+        &self,
+        method: Method,
+        uri: &str,
+        auth: &dyn Authenticator,
+        body: &D,
+    ) -> HttpResult<()> {
+        let (_, headers) = authenticate(auth, method.clone(), uri, &HeaderMap::new())?;
+        let body = serde_json::to_vec(body)?;
+        self.record_request(RecordedRequest {
+            method,
+            uri: uri.to_string(),
+            headers,
+            auth: Some(auth.scheme().to_string()),
+            body: Some(Bytes::from(body)),
+        });
+        Ok(())
+    }
+
+    /// Adds `request` to the log of requests.
+    fn record_request(&self, request: RecordedRequest) {
+        self.requests.lock().unwrap().push(request);
+    }
+
+    /// The response to a `method` request to `uri`, whatever its status.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request, with a transcript of the closest
+    /// routes.
+    fn respond(&self, method: Method, uri: &str) -> HttpResult<Reply> {
+        let path = request_path(uri);
+        let reply = self
+            .routes
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|route| route.method == method && route.path == path)
+            .map(Route::next);
+        match reply {
+            Some(Response::Reply(reply)) => Ok(reply),
+            Some(Response::Simulated(simulation)) => match simulation.error() {
+                Some(err) => Err(err),
+                None => match simulation {
+                    Simulation::Status(status, headers) => Ok(Reply {
+                        status,
+                        headers,
+                        body: Bytes::new(),
+                    }),
+                    _ => Ok(Reply::default()),
+                },
+            },
+            None => {
+                let expectations = self
+                    .routes
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|route| Expectation::new(&route.path).with_method(route.method.clone()))
+                    .collect::<Vec<_>>();
+                let transcript = Transcript::new(RequestSummary::new(method, path), expectations);
+                panic!("no route matched the request\n\n{transcript}")
+            }
+        }
+    }
+
+    /// The successful response to a `method` request to `uri`.
+    ///
+    /// Unsuccessful responses fail with the error from
+    /// [`HttpError::from_status()`].
+    fn respond_successfully(&self, method: Method, uri: &str) -> HttpResult<Reply> {
+        let reply = self.respond(method, uri)?;
+        if !reply.status.is_success() {
+            return Err(HttpError::from_status(reply.status, &reply.headers));
+        }
+        Ok(reply)
+    }
+}
+
+/// Builds a [`MockService`] from routes.
+///
+/// Each route is started with [`on_get()`](MockServiceBuilder::on_get),
+/// [`on_post()`](MockServiceBuilder::on_post), or
+/// [`on()`](MockServiceBuilder::on), and finished with one of the
+/// `respond_*` methods of the returned [`RouteBuilder`].
+#[derive(Debug, Default)]
+pub struct MockServiceBuilder {
+    routes: Vec<Route>,
+}
+
+impl MockServiceBuilder {
+    /// Starts a route for GET requests to `uri`.
+    pub fn on_get(self, uri: impl Into<String>) -> RouteBuilder {
+        self.on(Method::GET, uri)
+    }
+
+    /// Starts a route for POST requests to `uri`.
+    pub fn on_post(self, uri: impl Into<String>) -> RouteBuilder {
+        self.on(Method::POST, uri)
+    }
+
+    /// Starts a route for `method` requests to `uri`.
+    pub fn on(self, method: Method, uri: impl Into<String>) -> RouteBuilder {
+        RouteBuilder {
+            builder: self,
+            method,
+            path: uri.into(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Builds the service.
+    pub fn build(self) -> MockService {
+        MockService {
+            routes: Mutex::new(self.routes),
+            requests: Mutex::default(),
+        }
+    }
+
+    /// Adds `response` to the route for `method` requests to `path`.
+    fn route(mut self, method: Method, path: String, response: Response) -> Self {
+        match self
+            .routes
+            .iter_mut()
+            .find(|route| route.method == method && route.path == path)
+        {
+            Some(route) => route.responses.push_back(response),
+            None => self.routes.push(Route {
+                method,
+                path,
+                responses: VecDeque::from([response]),
+            }),
+        }
+        self
+    }
+}
+
+/// Declares the response to a route of a [`MockService`].
+///
+/// Each `respond_*` method finishes the route and returns the
+/// [`MockServiceBuilder`], so that more routes can be added.
+#[derive(Debug)]
+pub struct RouteBuilder {
+    builder: MockServiceBuilder,
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+}
+
+impl RouteBuilder {
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Responds with a 200 OK whose body is `body` serialized as JSON.
+    ///
+    /// # Panics
+    ///
+    /// If `body` cannot be serialized as JSON.
+    pub fn respond_json(self, body: impl Serialize) -> MockServiceBuilder {
+        let body = serde_json::to_vec(&body).expect("response body is not valid JSON");
+        self.with_header(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )
+        .respond(StatusCode::OK, body)
+    }
+
+    /// Responds with a 200 OK whose body is `body`.
+    pub fn respond_text(self, body: impl Into<String>) -> MockServiceBuilder {
+        self.respond(StatusCode::OK, body.into())
+    }
+
+    /// Responds with a 200 OK whose body is `body`.
+    pub fn respond_bytes(self, body: impl Into<Bytes>) -> MockServiceBuilder {
+        self.respond(StatusCode::OK, body)
+    }
+
+    /// Responds with `status` and an empty body.
+    ///
+    /// [`HttpPost::post()`] deserializes an empty body as if it were JSON
+    /// `null`, so a successful response can be deserialized into `()` or
+    /// an `Option`.
+    pub fn respond_status(self, status: StatusCode) -> MockServiceBuilder {
+        self.respond(status, Bytes::new())
+    }
+
+    /// Responds with `status` and `body`.
+    pub fn respond(self, status: StatusCode, body: impl Into<Bytes>) -> MockServiceBuilder {
+        let reply = Reply {
+            status,
+            headers: self.headers,
+            body: body.into(),
+        };
+        self.builder
+            .route(self.method, self.path, Response::Reply(reply))
+    }
+
+    /// Answers the request as `simulation` describes, such as by timing
+    /// out or dropping the connection.
+    pub fn simulate(self, simulation: Simulation) -> MockServiceBuilder {
+        self.builder
+            .route(self.method, self.path, Response::Simulated(simulation))
+    }
+}
+
+/// The responses to requests with a method and path.
+#[derive(Debug)]
+struct Route {
+    method: Method,
+    path: String,
+    responses: VecDeque<Response>,
+}
+
+impl Route {
+    /// Takes the next response, repeating the last one forever.
+    fn next(&mut self) -> Response {
+        match self.responses.len() {
+            1 => self.responses[0].clone(),
+            _ => self.responses.pop_front().unwrap(),
+        }
+    }
+}
+
+/// How a [`Route`] answers a request.
+#[derive(Clone, Debug)]
+enum Response {
+    Reply(Reply),
+    Simulated(Simulation),
+}
+
+/// A response declared by a [`RouteBuilder`].
+#[derive(Clone, Debug, Default)]
+struct Reply {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Reply {
+    /// The body as text, replacing invalid UTF-8.
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    fn into_response(self) -> HttpResponse {
+        let body = self.text();
+        self.headers.iter().fold(
+            HttpResponse::new(self.status, body),
+            |response, (name, value)| response.with_header(name.clone(), value.clone()),
+        )
+    }
+
+    fn into_binary(self) -> BinaryResponse {
+        self.headers.iter().fold(
+            BinaryResponse::new(self.status, self.body),
+            |response, (name, value)| response.with_header(name.clone(), value.clone()),
+        )
+    }
+}
+
+/// The path and query string of `uri`, which routes are matched against.
+fn request_path(uri: &str) -> String {
+    match Url::parse(uri) {
+        Ok(url) => url[url::Position::BeforePath..].to_string(),
+        Err(_) => uri.to_string(),
+    }
+}
+
+impl HttpGet for MockService {
+    /// Answers a GET request to `uri` from its route.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::GET, uri.as_str(), HeaderMap::new(), None);
+        Ok(self.respond_successfully(Method::GET, uri.as_str())?.text())
+    }
+}
+
+impl HttpPost for MockService {
+    /// Answers a POST request to `uri` from its route, deserializing the
+    /// response body from JSON.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        self.record_json(Method::POST, uri.as_str(), auth, data)?;
+        let reply = self.respond_successfully(Method::POST, uri.as_str())?;
+        let body = if reply.body.is_empty() {
+            String::from("null")
+        } else {
+            reply.text()
+        };
+        HttpResponse::new(reply.status, body).json()
+    }
+}
+
+impl HttpGetResponse for MockService {
+    /// Answers a GET request to `uri` from its route, whatever the status
+    /// of the response.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn get_response<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::GET, uri.as_str(), headers.clone(), None);
+        Ok(self.respond(Method::GET, uri.as_str())?.into_response())
+    }
+}
+
+impl HttpGetStream for MockService {
+    /// Answers a streaming GET request to `uri` from its route, returning
+    /// the body as a single chunk.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn get_stream<U>(&self, uri: U) -> HttpResult<BodyStream>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::GET, uri.as_str(), HeaderMap::new(), None);
+        let body = self.respond_successfully(Method::GET, uri.as_str())?.body;
+        let content_length = Some(body.len() as u64);
+        Ok(BodyStream::new(stream::iter([Ok(body)])).with_content_length(content_length))
+    }
+}
+
+impl HttpPostStream for MockService {
+    /// Answers a streaming POST request to `uri` from its route, whatever
+    /// the status of the response.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn post_stream<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: UploadBody,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        let body = body.into_bytes().await?;
+        self.record(Method::POST, uri.as_str(), headers.clone(), Some(body));
+        Ok(self.respond(Method::POST, uri.as_str())?.into_response())
+    }
+
+    /// Answers a multipart POST request to `uri` from its route, whatever
+    /// the status of the response.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn post_multipart<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        _form: Multipart,
+    ) -> HttpResult<HttpResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::POST, uri.as_str(), headers.clone(), None);
+        Ok(self.respond(Method::POST, uri.as_str())?.into_response())
+    }
+}
+
+impl HttpBinary for MockService {
+    /// Answers a GET request to `uri` from its route.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn get_binary<U>(&self, uri: U, headers: &HeaderMap) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::GET, uri.as_str(), headers.clone(), None);
+        Ok(self
+            .respond_successfully(Method::GET, uri.as_str())?
+            .into_binary())
+    }
+
+    /// Answers a POST request to `uri` from its route.
+    ///
+    /// # Panics
+    ///
+    /// If no route matches the request.
+    async fn post_binary<U>(
+        &self,
+        uri: U,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> HttpResult<BinaryResponse>
+    where
+        U: IntoUrl + Send,
+    {
+        self.record(Method::POST, uri.as_str(), headers.clone(), Some(body));
+        Ok(self
+            .respond_successfully(Method::POST, uri.as_str())?
+            .into_binary())
+    }
+}
+
+impl HttpCapabilities for MockService {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new([
+            Verb::Get,
+            Verb::Post,
+            Verb::GetResponse,
+            Verb::GetStream,
+            Verb::PostStream,
+            Verb::Binary,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::io;
+
+    #[derive(Debug, Deserialize)]
+    struct User {
+        username: String,
+    }
+
+    #[tokio::test]
+    async fn it_answers_requests_from_routes() -> HttpResult<()> {
+        let service = MockService::builder()
+            .on_get("/users/1")
+            .respond_json(json!({"username": "foo"}))
+            .on_post("/users")
+            .respond_json(json!({"username": "bar"}))
+            .on_post("/users/1/avatar")
+            .respond_status(StatusCode::NO_CONTENT)
+            .build();
+        let auth = Auth::new("my-api-key");
+
+        assert_eq!(service.get("/users/1").await?, r#"{"username":"foo"}"#);
+        let user: User = service
+            .post("/users", &auth, &json!({"username": "bar"}))
+            .await?;
+        assert_eq!(user.username, "bar");
+        let () = service.post("/users/1/avatar", &auth, &()).await?;
+
+        let requests = service.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].json(), Some(json!({"username": "bar"})));
+        assert_eq!(requests[1].auth.as_deref(), Some("bearer"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_matches_absolute_urls_by_path_and_query() -> HttpResult<()> {
+        let service = MockService::builder()
+            .on_get("/search?q=rust")
+            .respond_text("found")
+            .build();
+        assert_eq!(
+            service.get("https://api.example.com/search?q=rust").await?,
+            "found"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_with_unsuccessful_statuses() {
+        let service = MockService::builder()
+            .on_get("/users/2")
+            .with_header(header::RETRY_AFTER, HeaderValue::from_static("5"))
+            .respond_status(StatusCode::TOO_MANY_REQUESTS)
+            .on(Method::GET, "/users/3")
+            .simulate(Simulation::Transport(io::ErrorKind::ConnectionRefused))
+            .build();
+
+        let result = service.get("/users/2").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::RetryAfter(StatusCode::TOO_MANY_REQUESTS, _))
+        ));
+        let response = service
+            .get_response("/users/2", &HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "5");
+        let result = service.get("/users/3").await;
+        assert!(
+            matches!(result, Err(HttpError::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_answers_successive_requests_in_turn() -> HttpResult<()> {
+        let service = MockService::builder()
+            .on_get("/flaky")
+            .respond_status(StatusCode::SERVICE_UNAVAILABLE)
+            .on_get("/flaky")
+            .respond_text("ok")
+            .build();
+
+        assert!(service.get("/flaky").await.is_err());
+        assert_eq!(service.get("/flaky").await?, "ok");
+        assert_eq!(service.get("/flaky").await?, "ok");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_returns_binary_responses_with_their_headers() -> HttpResult<()> {
+        let service = MockService::builder()
+            .on_get("/avatars/1")
+            .with_header(header::CONTENT_TYPE, HeaderValue::from_static("image/png"))
+            .respond_bytes(&b"\x89PNG"[..])
+            .build();
+
+        let response = service.get_binary("/avatars/1", &HeaderMap::new()).await?;
+        assert_eq!(response.body(), &b"\x89PNG"[..]);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "no route matched the request")]
+    async fn it_panics_if_no_route_matches() {
+        let service = MockService::builder()
+            .on_get("/users/1")
+            .respond_text("foo")
+            .build();
+        let _ = service.get("/users/2").await;
+    }
+}
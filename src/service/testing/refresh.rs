@@ -0,0 +1,453 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Refreshing test fixtures from a live API.
+//!
+//! The fixtures an [`HttpTestService`](super::HttpTestService) serves drift
+//! out of date as the real API changes. A [`FixtureRefresh`] walks an
+//! existing fixture tree, requests the URI each fixture stands in for from
+//! the live API, and rewrites the fixtures whose responses have changed,
+//! reporting which ones did. Only existing fixtures are refreshed; new
+//! fixtures are still added by hand.
+//!
+//! JSON fixtures are compared by value, so a fixture that was formatted by
+//! hand is left alone unless its data has changed. Changed JSON fixtures
+//! are rewritten pretty-printed.
+//!
+//! Refreshing makes real requests, so it is opt-in: a refresh is usually
+//! run from a test that returns early unless the
+//! [`HYPERTYPER_REFRESH_FIXTURES`](REFRESH_ENV) environment variable is set.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::testing::refresh::FixtureRefresh;
+//!
+//! async fn refresh_fixtures<S: HttpGet + Sync>(live: S) -> HttpResult<()> {
+//!     if !FixtureRefresh::enabled() {
+//!         return Ok(());
+//!     }
+//!
+//!     let report = FixtureRefresh::new("tests/data/output", live, "https://api.example.com")
+//!         .skip("/graphql")
+//!         .run()
+//!         .await?;
+//!     println!("{report}");
+//!     assert!(!report.has_failures());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::HttpResult;
+use crate::service::HttpGet;
+use crate::service::testing::{FixtureFormat, fixture_paths, fixture_request};
+use reqwest::Method;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that opts in to refreshing fixtures.
+pub const REFRESH_ENV: &str = "HYPERTYPER_REFRESH_FIXTURES";
+
+/// The formats of fixture files that are refreshed.
+const FORMATS: &[FixtureFormat] = &[FixtureFormat::Json];
+
+/// Refreshes an existing fixture tree from a live API.
+///
+/// See the [module documentation](crate::service::testing::refresh) for
+/// details.
+#[derive(Debug)]
+pub struct FixtureRefresh<S> {
+    root: PathBuf,
+    live: S,
+    base_url: String,
+    skipped: Vec<String>,
+}
+
+impl FixtureRefresh<()> {
+    /// True if the [`REFRESH_ENV`] environment variable is set to anything
+    /// other than an empty string, `0`, or `false`.
+    pub fn enabled() -> bool {
+        std::env::var_os(REFRESH_ENV).is_some_and(|value| {
+            let value = value.to_string_lossy();
+            !(value.is_empty() || value == "0" || value.eq_ignore_ascii_case("false"))
+        })
+    }
+}
+
+impl<S: HttpGet + Sync> FixtureRefresh<S> {
+    /// Refreshes the fixtures in `root` by making GET requests through
+    /// `live` to the fixture's URI appended to `base_url`.
+    ///
+    /// `root` is the same directory the fixtures'
+    /// [`HttpTestService`](super::HttpTestService) loads them from.
+    pub fn new(root: impl Into<PathBuf>, live: S, base_url: impl Into<String>) -> Self {
+        let root = root.into();
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        let skipped = Vec::new();
+        Self {
+            root,
+            live,
+            base_url,
+            skipped,
+        }
+    }
+
+    /// Leaves fixtures for `uri`, and for any URI beneath it, untouched.
+    ///
+    /// Fixtures that answer POST or GraphQL requests cannot be refreshed
+    /// with a GET request, so they should be skipped. Fixtures in the
+    /// directories for methods other than GET, and catch-all query
+    /// fixtures, are always skipped.
+    pub fn skip(mut self, uri: impl Into<String>) -> Self {
+        self.skipped.push(uri.into());
+        self
+    }
+
+    /// Requests every fixture's URI from the live API and rewrites the
+    /// fixtures whose responses have changed.
+    ///
+    /// A failed request is recorded in the report and leaves its fixture
+    /// untouched. An error is only returned if the fixture tree cannot be
+    /// read or written.
+    pub async fn run(&self) -> HttpResult<RefreshReport> {
+        let mut fixtures = Vec::new();
+        for path in fixture_paths(&self.root, FORMATS)? {
+            let (method, uri) = fixture_request(&self.root, &path);
+            let outcome = if self.is_skipped(&uri)
+                || method.is_some_and(|m| m != Method::GET)
+                || uri.ends_with("?*")
+            {
+                Refreshed::Skipped
+            } else {
+                let url = format!("{}{uri}", self.base_url);
+                match self.live.get(url.as_str()).await {
+                    Ok(body) => refresh_fixture(&path, &body)?,
+                    Err(err) => Refreshed::Failed(err.to_string()),
+                }
+            };
+            fixtures.push(RefreshedFixture { uri, path, outcome });
+        }
+        Ok(RefreshReport { fixtures })
+    }
+
+    fn is_skipped(&self, uri: &str) -> bool {
+        self.skipped.iter().any(|skipped| {
+            uri.strip_prefix(skipped.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+/// Rewrites the fixture at `path` if `body` differs from it.
+fn refresh_fixture(path: &Path, body: &str) -> io::Result<Refreshed> {
+    let current = fs::read_to_string(path)?;
+    let live = match serde_json::from_str::<Value>(body) {
+        Ok(live) => live,
+        Err(_) => {
+            if current.trim() == body.trim() {
+                return Ok(Refreshed::Unchanged);
+            }
+            fs::write(path, format!("{}\n", body.trim()))?;
+            return Ok(Refreshed::Changed);
+        }
+    };
+    if serde_json::from_str::<Value>(&current).is_ok_and(|current| current == live) {
+        return Ok(Refreshed::Unchanged);
+    }
+    let pretty = serde_json::to_string_pretty(&live).map_err(io::Error::other)?;
+    fs::write(path, format!("{pretty}\n"))?;
+    Ok(Refreshed::Changed)
+}
+
+/// What happened to a fixture during a refresh.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Refreshed {
+    /// The live response matched the fixture.
+    Unchanged,
+
+    /// The live response differed, and the fixture was rewritten.
+    Changed,
+
+    /// The fixture's URI was [skipped](FixtureRefresh::skip).
+    Skipped,
+
+    /// The live request failed, and the fixture was left untouched.
+    Failed(String),
+}
+
+/// A fixture visited during a refresh.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RefreshedFixture {
+    /// The URI the fixture answers.
+    pub uri: String,
+
+    /// The path of the fixture file.
+    pub path: PathBuf,
+
+    /// What happened to the fixture.
+    pub outcome: Refreshed,
+}
+
+/// The results of a [`FixtureRefresh`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RefreshReport {
+    fixtures: Vec<RefreshedFixture>,
+}
+
+impl RefreshReport {
+    /// Every fixture visited, sorted by path.
+    pub fn fixtures(&self) -> &[RefreshedFixture] {
+        &self.fixtures
+    }
+
+    /// The fixtures that were rewritten.
+    pub fn changed(&self) -> impl Iterator<Item = &RefreshedFixture> {
+        self.with_outcome(|outcome| *outcome == Refreshed::Changed)
+    }
+
+    /// The fixtures whose live requests failed.
+    pub fn failed(&self) -> impl Iterator<Item = &RefreshedFixture> {
+        self.with_outcome(|outcome| matches!(outcome, Refreshed::Failed(_)))
+    }
+
+    /// True if any fixture was rewritten.
+    pub fn has_changes(&self) -> bool {
+        self.changed().next().is_some()
+    }
+
+    /// True if any fixture's live request failed.
+    pub fn has_failures(&self) -> bool {
+        self.failed().next().is_some()
+    }
+
+    fn with_outcome(
+        &self,
+        f: impl Fn(&Refreshed) -> bool,
+    ) -> impl Iterator<Item = &RefreshedFixture> {
+        self.fixtures
+            .iter()
+            .filter(move |fixture| f(&fixture.outcome))
+    }
+}
+
+impl fmt::Display for RefreshReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for fixture in &self.fixtures {
+            match &fixture.outcome {
+                Refreshed::Changed => writeln!(f, "[changed] {}", fixture.uri)?,
+                Refreshed::Failed(reason) => writeln!(f, "[FAILED]  {}: {reason}", fixture.uri)?,
+                Refreshed::Unchanged | Refreshed::Skipped => {}
+            }
+        }
+        let count = |outcome: Refreshed| {
+            self.fixtures
+                .iter()
+                .filter(|fixture| fixture.outcome == outcome)
+                .count()
+        };
+        write!(
+            f,
+            "{} changed, {} unchanged, {} skipped, {} failed",
+            count(Refreshed::Changed),
+            count(Refreshed::Unchanged),
+            count(Refreshed::Skipped),
+            self.failed().count()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use reqwest::IntoUrl;
+    use std::collections::HashMap;
+
+    /// Answers GET requests from a map of URLs to bodies.
+    struct LiveService(HashMap<&'static str, &'static str>);
+
+    impl HttpGet for LiveService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            match self.0.get(uri.as_str()) {
+                Some(body) => Ok(body.to_string()),
+                None => Err(HttpError::InvalidToken(format!(
+                    "no body for {}",
+                    uri.as_str()
+                ))),
+            }
+        }
+    }
+
+    fn fixture(root: &Path, uri: &str, contents: &str) -> PathBuf {
+        let path = root.join(format!("{}.json", uri.trim_start_matches('/')));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn it_rewrites_only_changed_fixtures() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let same = fixture(dir.path(), "/users/foo/about", "{\"username\": \"foo\"}\n");
+        let stale = fixture(dir.path(), "/users/bar/about", "{\"username\": \"bar\"}\n");
+        let live = LiveService(HashMap::from([
+            (
+                "https://api.example.com/users/foo/about",
+                "{\"username\":\"foo\"}",
+            ),
+            (
+                "https://api.example.com/users/bar/about",
+                "{\"username\":\"bar\",\"admin\":true}",
+            ),
+        ]));
+
+        let report = FixtureRefresh::new(dir.path(), live, "https://api.example.com/")
+            .run()
+            .await?;
+
+        let changed: Vec<_> = report
+            .changed()
+            .map(|fixture| fixture.uri.as_str())
+            .collect();
+        assert_eq!(changed, ["/users/bar/about"]);
+        assert_eq!(fs::read_to_string(same)?, "{\"username\": \"foo\"}\n");
+        let stale: Value = serde_json::from_str(&fs::read_to_string(stale)?)?;
+        assert_eq!(stale["admin"], true);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_leaves_skipped_and_failed_fixtures_untouched() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        fixture(dir.path(), "/graphql/GetUser", "{}");
+        fixture(dir.path(), "/graphqlish", "{}");
+        let missing = fixture(dir.path(), "/users/missing", "{}");
+        let live = LiveService(HashMap::from([(
+            "https://api.example.com/graphqlish",
+            "{}",
+        )]));
+
+        let report = FixtureRefresh::new(dir.path(), live, "https://api.example.com")
+            .skip("/graphql")
+            .run()
+            .await?;
+
+        let outcomes: Vec<_> = report
+            .fixtures()
+            .iter()
+            .map(|fixture| (fixture.uri.as_str(), &fixture.outcome))
+            .collect();
+        assert_eq!(outcomes[0], ("/graphql/GetUser", &Refreshed::Skipped));
+        assert_eq!(outcomes[1], ("/graphqlish", &Refreshed::Unchanged));
+        assert_eq!(outcomes[2].0, "/users/missing");
+        assert!(report.has_failures());
+        assert!(!report.has_changes());
+        assert_eq!(fs::read_to_string(missing)?, "{}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_only_refreshes_fixtures_for_get_requests() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let get = fixture(dir.path(), "/GET/users", "[]");
+        let post = fixture(dir.path(), "/POST/users", "{}");
+        let live = LiveService(HashMap::from([(
+            "https://api.example.com/users",
+            r#"[{"username":"foo"}]"#,
+        )]));
+
+        let report = FixtureRefresh::new(dir.path(), live, "https://api.example.com")
+            .run()
+            .await?;
+
+        let outcomes: Vec<_> = report
+            .fixtures()
+            .iter()
+            .map(|fixture| (fixture.uri.as_str(), &fixture.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("/users", &Refreshed::Changed),
+                ("/users", &Refreshed::Skipped)
+            ]
+        );
+        assert_ne!(fs::read_to_string(get)?, "[]");
+        assert_eq!(fs::read_to_string(post)?, "{}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_query_fixtures_with_their_query_strings() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let query = fixture(dir.path(), "/search/page=2&q=rust", "[]");
+        fixture(dir.path(), "/search/_any", "[]");
+        let live = LiveService(HashMap::from([(
+            "https://api.example.com/search?page=2&q=rust",
+            r#"["rustup"]"#,
+        )]));
+
+        let report = FixtureRefresh::new(dir.path(), live, "https://api.example.com")
+            .run()
+            .await?;
+
+        let outcomes: Vec<_> = report
+            .fixtures()
+            .iter()
+            .map(|fixture| (fixture.uri.as_str(), &fixture.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("/search?*", &Refreshed::Skipped),
+                ("/search?page=2&q=rust", &Refreshed::Changed)
+            ]
+        );
+        assert_ne!(fs::read_to_string(query)?, "[]");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reports_changes_and_failures() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        fixture(dir.path(), "/health", "ok");
+        fixture(dir.path(), "/users", "[]");
+        let live = LiveService(HashMap::from([("http://localhost/health", "degraded")]));
+
+        let report = FixtureRefresh::new(dir.path(), live, "http://localhost")
+            .run()
+            .await?;
+
+        assert_eq!(
+            report.to_string(),
+            "[changed] /health\n\
+             [FAILED]  /users: Invalid token: no body for http://localhost/users\n\
+             1 changed, 0 unchanged, 0 skipped, 1 failed"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("health.json"))?,
+            "degraded\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_is_enabled_by_the_environment() {
+        temp_env::with_var(
+            REFRESH_ENV,
+            Some("1"),
+            || assert!(FixtureRefresh::enabled()),
+        );
+        temp_env::with_var(REFRESH_ENV, Some("false"), || {
+            assert!(!FixtureRefresh::enabled())
+        });
+        temp_env::with_var_unset(REFRESH_ENV, || assert!(!FixtureRefresh::enabled()));
+    }
+}
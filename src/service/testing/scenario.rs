@@ -0,0 +1,440 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Multi-step client flows for end-to-end tests.
+//!
+//! A [`Scenario`] is a sequence of named steps, each of which makes a
+//! request, checks the response, and extracts values from it into
+//! variables that later steps can use. Variables are referred to as
+//! `{name}` in URIs and in the strings of POST bodies.
+//!
+//! Scenarios work with any [`HttpService`], so the same scenario can run
+//! against an [`HttpTestService`](super::HttpTestService) in unit tests and
+//! against a real API in integration tests.
+//!
+//! Values are found in JSON responses by their path, such as `token` or
+//! `data.items[0].id`. With the **json-path** feature enabled, paths that
+//! start with `$` are [JSONPath](crate::decode::path) expressions instead.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::service::testing::HttpTestService;
+//! use hypertyper::service::testing::scenario::Scenario;
+//! use serde_json::json;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let service = HttpTestService::new("tests/data/output");
+//! let vars = Scenario::new()
+//!     .step("create user")
+//!     .post("/users", json!({"username": "foo"}))
+//!     .expect_status(200)
+//!     .extract("username", "username")
+//!     .step("fetch profile")
+//!     .get("/users/{username}/about")
+//!     .expect_json("username", "foo")
+//!     .run(&service)
+//!     .await;
+//! assert_eq!(vars["username"], "foo");
+//! # }
+//! ```
+//!
+//! # Statuses
+//!
+//! [`HttpGet`] and [`HttpPost`] only return the bodies of successful
+//! responses, so a step that succeeds is treated as having received
+//! HTTP 200 OK, and a step that fails has the status of its
+//! [`HttpError`](crate::HttpError), if it has one.
+
+use crate::auth::{Auth, Authenticator};
+use crate::service::HttpService;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[cfg(doc)]
+use crate::service::{HttpGet, HttpPost};
+
+/// The variables extracted by a scenario, by name.
+pub type Variables = HashMap<String, Value>;
+
+#[derive(Clone, Debug)]
+enum Request {
+    Get(String),
+    Post(String, Value),
+}
+
+/// A single request in a [`Scenario`] and the checks made on its response.
+#[derive(Clone, Debug)]
+struct Step {
+    name: String,
+    request: Option<Request>,
+    status: Option<StatusCode>,
+    expectations: Vec<(String, Value)>,
+    extractions: Vec<(String, String)>,
+}
+
+/// A sequence of requests that pass values from one to the next.
+///
+/// Methods other than [`step()`](Self::step) and
+/// [`with_var()`](Self::with_var) configure the most recently added step.
+///
+/// See the [module documentation](crate::service::testing::scenario) for
+/// details.
+#[derive(Debug)]
+pub struct Scenario {
+    auth: Box<dyn Authenticator>,
+    vars: Variables,
+    steps: Vec<Step>,
+}
+
+impl Scenario {
+    /// Creates an empty scenario.
+    pub fn new() -> Self {
+        Self {
+            auth: Box::new(Auth::new("")),
+            vars: Variables::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Sets the credentials used for POST requests.
+    pub fn with_auth(mut self, auth: impl Authenticator + 'static) -> Self {
+        self.auth = Box::new(auth);
+        self
+    }
+
+    /// Sets the starting value of a variable.
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Adds a step called `name`, which is used in failure messages.
+    pub fn step(mut self, name: impl Into<String>) -> Self {
+        self.steps.push(Step {
+            name: name.into(),
+            request: None,
+            status: None,
+            expectations: Vec::new(),
+            extractions: Vec::new(),
+        });
+        self
+    }
+
+    /// Makes the step send a GET request to `uri`.
+    ///
+    /// # Panics
+    ///
+    /// If no step has been added.
+    pub fn get(mut self, uri: impl Into<String>) -> Self {
+        self.current().request = Some(Request::Get(uri.into()));
+        self
+    }
+
+    /// Makes the step send a POST request with `body` to `uri`.
+    ///
+    /// # Panics
+    ///
+    /// If no step has been added.
+    pub fn post(mut self, uri: impl Into<String>, body: Value) -> Self {
+        self.current().request = Some(Request::Post(uri.into(), body));
+        self
+    }
+
+    /// Checks that the step's response has the given status.
+    ///
+    /// Without this, any error fails the step.
+    ///
+    /// # Panics
+    ///
+    /// If no step has been added, or `status` is not a valid status code.
+    pub fn expect_status(mut self, status: u16) -> Self {
+        let status = StatusCode::from_u16(status).expect("invalid status code");
+        self.current().status = Some(status);
+        self
+    }
+
+    /// Checks that the value at `path` in the step's response equals
+    /// `value`.
+    ///
+    /// # Panics
+    ///
+    /// If no step has been added.
+    pub fn expect_json(mut self, path: impl Into<String>, value: impl Into<Value>) -> Self {
+        let expectation = (path.into(), value.into());
+        self.current().expectations.push(expectation);
+        self
+    }
+
+    /// Stores the value at `path` in the step's response in the variable
+    /// called `name`.
+    ///
+    /// # Panics
+    ///
+    /// If no step has been added.
+    pub fn extract(mut self, name: impl Into<String>, path: impl Into<String>) -> Self {
+        let extraction = (name.into(), path.into());
+        self.current().extractions.push(extraction);
+        self
+    }
+
+    fn current(&mut self) -> &mut Step {
+        self.steps
+            .last_mut()
+            .expect("call step() before configuring a step")
+    }
+
+    /// Runs each step in order with `service`, and returns the variables
+    /// extracted along the way.
+    ///
+    /// # Panics
+    ///
+    /// If any step fails: its request returns an unexpected status or
+    /// error, its response is not JSON when values are needed from it, an
+    /// expected value differs, or a value to extract is missing. The panic
+    /// message names the failing step.
+    pub async fn run<S: HttpService + Sync>(&self, service: &S) -> Variables {
+        let mut vars = self.vars.clone();
+        for step in &self.steps {
+            let name = &step.name;
+            let result = match &step.request {
+                Some(Request::Get(uri)) => {
+                    let uri = substitute(uri, &vars, name);
+                    service.get(uri).await.map(|body| parse(&body))
+                }
+                Some(Request::Post(uri, body)) => {
+                    let uri = substitute(uri, &vars, name);
+                    let body = substitute_json(body, &vars, name);
+                    service.post::<_, _, Value>(uri, &*self.auth, &body).await
+                }
+                None => panic!("step `{name}` has no request"),
+            };
+
+            let (status, body) = match result {
+                Ok(body) => (StatusCode::OK, body),
+                Err(err) => match (err.status(), step.status) {
+                    (Some(status), Some(expected)) if status == expected => (status, Value::Null),
+                    _ => panic!("step `{name}` failed: {err}"),
+                },
+            };
+            if let Some(expected) = step.status {
+                assert_eq!(status, expected, "step `{name}` returned the wrong status");
+            }
+            for (path, expected) in &step.expectations {
+                let actual = lookup(&body, path)
+                    .unwrap_or_else(|| panic!("step `{name}`: response has no `{path}`"));
+                assert_eq!(
+                    actual, expected,
+                    "step `{name}`: unexpected value at `{path}`"
+                );
+            }
+            for (var, path) in &step.extractions {
+                let value = lookup(&body, path)
+                    .unwrap_or_else(|| panic!("step `{name}`: response has no `{path}`"));
+                vars.insert(var.clone(), value.clone());
+            }
+        }
+        vars
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses a GET response as JSON, treating any other body as a string.
+fn parse(body: &str) -> Value {
+    serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string()))
+}
+
+/// Finds the value at a path such as `data.items[0].id`.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    #[cfg(feature = "json-path")]
+    if path.starts_with('$') {
+        return crate::decode::path::find(value, path).ok();
+    }
+
+    let mut pointer = String::new();
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let (key, indices) = segment.split_once('[').unwrap_or((segment, ""));
+        if !key.is_empty() {
+            pointer.push('/');
+            pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+        }
+        for index in indices.split('[') {
+            let index = index.trim_end_matches(']');
+            if !index.is_empty() {
+                pointer.push('/');
+                pointer.push_str(index);
+            }
+        }
+    }
+    value.pointer(&pointer)
+}
+
+/// Replaces each `{name}` in `template` with the value of the variable.
+fn substitute(template: &str, vars: &Variables, step: &str) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let var = &rest[start + 1..start + len];
+        let value = vars
+            .get(var)
+            .unwrap_or_else(|| panic!("step `{step}` uses undefined variable `{var}`"));
+        result.push_str(&rest[..start]);
+        match value {
+            Value::String(value) => result.push_str(value),
+            value => result.push_str(&value.to_string()),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn substitute_json(value: &Value, vars: &Variables, step: &str) -> Value {
+    match value {
+        Value::String(s) => Value::String(substitute(s, vars, step)),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| substitute_json(value, vars, step))
+            .collect(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| (key.clone(), substitute_json(value, vars, step)))
+            .collect(),
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::testing::HttpTestService;
+    use crate::service::{HttpGet, HttpPost};
+    use crate::{HttpError, HttpResult};
+    use reqwest::IntoUrl;
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Echoes POST bodies, records URIs, and fails GETs for `/missing`.
+    #[derive(Default)]
+    struct EchoService {
+        uris: Mutex<Vec<String>>,
+    }
+
+    impl HttpGet for EchoService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            self.uris.lock().unwrap().push(uri.as_str().to_string());
+            if uri.as_str().ends_with("/missing") {
+                return Err(HttpError::Http(StatusCode::NOT_FOUND));
+            }
+            Ok(String::from(r#"{"data": {"items": [{"id": 7}]}}"#))
+        }
+    }
+
+    impl HttpPost for EchoService {
+        async fn post<U, D, R>(&self, uri: U, _auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+        where
+            U: IntoUrl + Send,
+            D: Serialize + Sync,
+            R: DeserializeOwned,
+        {
+            self.uris.lock().unwrap().push(uri.as_str().to_string());
+            Ok(serde_json::from_value(serde_json::to_value(data)?)?)
+        }
+    }
+
+    #[tokio::test]
+    async fn it_runs_against_the_test_service() {
+        let service = HttpTestService::new("tests/data/output");
+        let vars = Scenario::new()
+            .step("create user")
+            .post("/users", json!({"username": "foo"}))
+            .extract("username", "username")
+            .step("fetch profile")
+            .get("/users/{username}/about")
+            .expect_json("username", "foo")
+            .run(&service)
+            .await;
+        assert_eq!(vars["username"], "foo");
+    }
+
+    #[tokio::test]
+    async fn it_passes_variables_between_steps() {
+        let service = EchoService::default();
+        let vars = Scenario::new()
+            .with_var("team", "blue")
+            .step("list")
+            .get("/teams/{team}/items")
+            .extract("item", "data.items[0].id")
+            .step("claim")
+            .post(
+                "/items/{item}/claims",
+                json!({"team": "{team}", "ids": ["{item}"]}),
+            )
+            .expect_json("ids[0]", "7")
+            .extract("claimed_by", "team")
+            .step("missing")
+            .get("/items/missing")
+            .expect_status(404)
+            .run(&service)
+            .await;
+
+        assert_eq!(vars["item"], 7);
+        assert_eq!(vars["claimed_by"], "blue");
+        let uris = service.uris.lock().unwrap();
+        assert_eq!(
+            *uris,
+            ["/teams/blue/items", "/items/7/claims", "/items/missing"]
+        );
+    }
+
+    #[cfg(feature = "json-path")]
+    #[tokio::test]
+    async fn it_extracts_values_with_json_path() {
+        let vars = Scenario::new()
+            .step("list")
+            .get("/items")
+            .expect_json("$.data.items[?@.id > 5].id", 7)
+            .extract("item", "$..id")
+            .run(&EchoService::default())
+            .await;
+        assert_eq!(vars["item"], 7);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "step `fetch` failed: Request returned HTTP 404 Not Found")]
+    async fn it_fails_on_unexpected_errors() {
+        let service = EchoService::default();
+        Scenario::new()
+            .step("fetch")
+            .get("/missing")
+            .run(&service)
+            .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "step `fetch`: unexpected value at `data.items[0].id`")]
+    async fn it_fails_on_unexpected_values() {
+        let service = EchoService::default();
+        Scenario::new()
+            .step("fetch")
+            .get("/items")
+            .expect_json("data.items[0].id", 8)
+            .run(&service)
+            .await;
+    }
+}
@@ -0,0 +1,509 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Readable explanations of requests that test services could not answer.
+//!
+//! When a test service has no response for a request, a bare "not found"
+//! leaves the test author comparing the request against every fixture by
+//! eye. A [`Transcript`] does the comparison instead: it ranks the
+//! [`Expectation`]s a service knows about by how closely they match the
+//! request, and lists the fields of the closest ones that differ, down to
+//! individual headers and fields of JSON bodies.
+//!
+//! [`HttpTestService`](super::HttpTestService) includes a transcript in the
+//! message it panics with when a fixture is missing.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::service::testing::transcript::{Expectation, RequestSummary, Transcript};
+//! use reqwest::Method;
+//!
+//! let expectations = [
+//!     Expectation::new("/users/foo/about").with_method(Method::GET),
+//!     Expectation::new("/users").with_method(Method::POST),
+//! ];
+//! let request = RequestSummary::new(Method::GET, "/users/foo/abuot");
+//! let transcript = Transcript::new(request, expectations);
+//!
+//! let (closest, differences) = transcript.closest().next().unwrap();
+//! assert_eq!(closest.path(), "/users/foo/about");
+//! assert_eq!(differences[0].field, "path");
+//! println!("{transcript}");
+//! ```
+
+use reqwest::Method;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+use std::fmt;
+
+/// How many of the closest expectations a transcript shows.
+const CLOSEST: usize = 3;
+
+/// A request made to a test service.
+#[derive(Clone, Debug)]
+pub struct RequestSummary {
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    body: Option<String>,
+}
+
+impl RequestSummary {
+    /// Summarizes a request without headers or a body.
+    pub fn new(method: Method, path: impl Into<String>) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    /// Sets the headers the request was sent with.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Sets the body the request was sent with.
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// The method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The path of the request.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The headers of the request.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The body of the request, if it had one.
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+}
+
+impl fmt::Display for RequestSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)
+    }
+}
+
+/// A request a test service knows how to answer.
+///
+/// Only the parts of a request that are set are compared: an expectation
+/// without a method matches any method, only the headers it lists are
+/// checked, and a body is only compared if one is set.
+#[derive(Clone, Debug)]
+pub struct Expectation {
+    method: Option<Method>,
+    path: String,
+    headers: HeaderMap,
+    body: Option<String>,
+}
+
+impl Expectation {
+    /// Expects a request to `path` with any method.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            method: None,
+            path: path.into(),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    /// Expects the request to be made with `method`.
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Expects the request to have a `name` header with `value`.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Expects the request to have `body`.
+    ///
+    /// JSON bodies are compared by value, so formatting does not matter.
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// The method expected, if any.
+    pub fn method(&self) -> Option<&Method> {
+        self.method.as_ref()
+    }
+
+    /// The path expected.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// True if `request` matches this expectation.
+    pub fn matches(&self, request: &RequestSummary) -> bool {
+        self.diff(request).is_empty()
+    }
+
+    /// The fields of `request` that do not match this expectation.
+    pub fn diff(&self, request: &RequestSummary) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        if let Some(method) = &self.method {
+            if *method != request.method {
+                differences.push(Difference::new("method", method, &request.method));
+            }
+        }
+        if self.path != request.path {
+            differences.push(Difference::new(
+                "path",
+                format!("{:?}", self.path),
+                format!("{:?}", request.path),
+            ));
+        }
+        for name in self.headers.keys() {
+            let expected: Vec<_> = self.headers.get_all(name).iter().collect();
+            let actual: Vec<_> = request.headers.get_all(name).iter().collect();
+            if expected != actual {
+                differences.push(Difference::new(
+                    format!("header {name}"),
+                    header_values(&expected),
+                    header_values(&actual),
+                ));
+            }
+        }
+        if let Some(expected) = &self.body {
+            diff_bodies(expected, request.body.as_deref(), &mut differences);
+        }
+        differences
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.method {
+            Some(method) => write!(f, "{method} {}", self.path),
+            None => write!(f, "* {}", self.path),
+        }
+    }
+}
+
+/// A field of a request that does not match an [`Expectation`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Difference {
+    /// The field, such as `method`, `header accept`, or `body.user.id`.
+    pub field: String,
+
+    /// The value the expectation wanted.
+    pub expected: String,
+
+    /// The value the request had.
+    pub actual: String,
+}
+
+impl Difference {
+    fn new(field: impl Into<String>, expected: impl ToString, actual: impl ToString) -> Self {
+        Self {
+            field: field.into(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, got {}",
+            self.field, self.expected, self.actual
+        )
+    }
+}
+
+/// Shown in place of a value that is not there.
+const MISSING: &str = "(missing)";
+
+fn header_values(values: &[&HeaderValue]) -> String {
+    if values.is_empty() {
+        return String::from(MISSING);
+    }
+    let values: Vec<_> = values
+        .iter()
+        .map(|value| format!("{:?}", String::from_utf8_lossy(value.as_bytes())))
+        .collect();
+    values.join(", ")
+}
+
+fn diff_bodies(expected: &str, actual: Option<&str>, differences: &mut Vec<Difference>) {
+    let Some(actual) = actual else {
+        differences.push(Difference::new("body", format!("{expected:?}"), MISSING));
+        return;
+    };
+    match (
+        serde_json::from_str::<Value>(expected),
+        serde_json::from_str::<Value>(actual),
+    ) {
+        (Ok(expected), Ok(actual)) => diff_json("body", &expected, &actual, differences),
+        _ if expected != actual => {
+            differences.push(Difference::new(
+                "body",
+                format!("{expected:?}"),
+                format!("{actual:?}"),
+            ));
+        }
+        _ => {}
+    }
+}
+
+fn diff_json(field: &str, expected: &Value, actual: &Value, differences: &mut Vec<Difference>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let field = format!("{field}.{key}");
+                match actual.get(key) {
+                    Some(actual) => diff_json(&field, value, actual, differences),
+                    None => differences.push(Difference::new(field, value, MISSING)),
+                }
+            }
+            for (key, value) in actual {
+                if !expected.contains_key(key) {
+                    differences.push(Difference::new(format!("{field}.{key}"), MISSING, value));
+                }
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items))
+            if expected_items.len() == actual_items.len() =>
+        {
+            for (i, (expected, actual)) in expected_items.iter().zip(actual_items).enumerate() {
+                diff_json(&format!("{field}[{i}]"), expected, actual, differences);
+            }
+        }
+        _ if expected != actual => {
+            differences.push(Difference::new(field, expected, actual));
+        }
+        _ => {}
+    }
+}
+
+/// The number of single-character edits that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// An explanation of why a request matched none of a service's
+/// expectations.
+///
+/// See the [module documentation](crate::service::testing::transcript) for
+/// details.
+#[derive(Clone, Debug)]
+pub struct Transcript {
+    request: RequestSummary,
+    closest: Vec<(Expectation, Vec<Difference>)>,
+    total: usize,
+}
+
+impl Transcript {
+    /// Compares `request` against each of `expectations` and keeps the
+    /// closest.
+    ///
+    /// Expectations are ranked by how many fields differ and then by how
+    /// similar their paths are to the request's.
+    pub fn new(
+        request: RequestSummary,
+        expectations: impl IntoIterator<Item = Expectation>,
+    ) -> Self {
+        let mut ranked: Vec<_> = expectations
+            .into_iter()
+            .map(|expectation| {
+                let differences = expectation.diff(&request);
+                let distance = edit_distance(&expectation.path, &request.path);
+                (differences.len(), distance, expectation, differences)
+            })
+            .collect();
+        let total = ranked.len();
+        ranked.sort_by_key(|(count, distance, ..)| (*count, *distance));
+        let closest = ranked
+            .into_iter()
+            .take(CLOSEST)
+            .map(|(_, _, expectation, differences)| (expectation, differences))
+            .collect();
+        Self {
+            request,
+            closest,
+            total,
+        }
+    }
+
+    /// The request that was not matched.
+    pub fn request(&self) -> &RequestSummary {
+        &self.request
+    }
+
+    /// The closest expectations and how the request differs from each,
+    /// closest first.
+    pub fn closest(&self) -> impl Iterator<Item = (&Expectation, &[Difference])> {
+        self.closest
+            .iter()
+            .map(|(expectation, differences)| (expectation, differences.as_slice()))
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "No expectation matched {}", self.request)?;
+        if self.total == 0 {
+            return write!(f, "No expectations are registered");
+        }
+        write!(
+            f,
+            "Closest {} of {} expectations:",
+            self.closest.len(),
+            self.total
+        )?;
+        for (expectation, differences) in &self.closest {
+            write!(f, "\n\n  {expectation}")?;
+            for difference in differences {
+                write!(f, "\n    {difference}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::CONTENT_TYPE;
+
+    #[test]
+    fn it_ranks_expectations_by_closeness() {
+        let expectations = [
+            Expectation::new("/admin").with_method(Method::POST),
+            Expectation::new("/users/bar/about").with_method(Method::GET),
+            Expectation::new("/users/foo/about").with_method(Method::GET),
+            Expectation::new("/users/foo/about").with_method(Method::DELETE),
+        ];
+        let request = RequestSummary::new(Method::GET, "/users/foo/abuot");
+        let transcript = Transcript::new(request, expectations);
+
+        let closest: Vec<_> = transcript
+            .closest()
+            .map(|(expectation, _)| expectation.to_string())
+            .collect();
+        assert_eq!(
+            closest,
+            [
+                "GET /users/foo/about",
+                "GET /users/bar/about",
+                "DELETE /users/foo/about"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_diffs_headers() {
+        let expectation = Expectation::new("/users")
+            .with_header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .with_header(
+                HeaderName::from_static("x-api-key"),
+                HeaderValue::from_static("secret"),
+            );
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        let request = RequestSummary::new(Method::POST, "/users").with_headers(headers);
+
+        let differences: Vec<_> = expectation
+            .diff(&request)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            differences,
+            [
+                r#"header content-type: expected "application/json", got "text/plain""#,
+                r#"header x-api-key: expected "secret", got (missing)"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn it_diffs_json_bodies_field_by_field() {
+        let expectation =
+            Expectation::new("/users").with_body(r#"{"user": {"name": "foo", "tags": [1, 2]}}"#);
+        let request = RequestSummary::new(Method::POST, "/users")
+            .with_body(r#"{"user":{"name":"bar","tags":[1,3]},"dry_run":true}"#);
+
+        let differences: Vec<_> = expectation
+            .diff(&request)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            differences,
+            [
+                r#"body.user.name: expected "foo", got "bar""#,
+                "body.user.tags[1]: expected 2, got 3",
+                "body.dry_run: expected (missing), got true",
+            ]
+        );
+        assert!(
+            Expectation::new("/users")
+                .with_body(r#"{"a": 1}"#)
+                .matches(&RequestSummary::new(Method::POST, "/users").with_body(r#"{"a":1}"#))
+        );
+    }
+
+    #[test]
+    fn it_prints_a_transcript() {
+        let expectations = [
+            Expectation::new("/users").with_method(Method::POST),
+            Expectation::new("/users/foo/about"),
+        ];
+        let request = RequestSummary::new(Method::GET, "/users/foo");
+        let transcript = Transcript::new(request, expectations);
+        assert_eq!(
+            transcript.to_string(),
+            "No expectation matched GET /users/foo\n\
+             Closest 2 of 2 expectations:\n\
+             \n  * /users/foo/about\
+             \n    path: expected \"/users/foo/about\", got \"/users/foo\"\
+             \n\n  POST /users\
+             \n    method: expected POST, got GET\
+             \n    path: expected \"/users\", got \"/users/foo\""
+        );
+    }
+
+    #[test]
+    fn it_explains_when_there_are_no_expectations() {
+        let request = RequestSummary::new(Method::GET, "/users");
+        let transcript = Transcript::new(request, []);
+        assert_eq!(
+            transcript.to_string(),
+            "No expectation matched GET /users\nNo expectations are registered"
+        );
+    }
+}
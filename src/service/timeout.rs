@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Deadlines for individual requests.
+//!
+//! A [`TimeoutService`] fails any request that takes longer than its
+//! deadline with [`HttpError::Timeout`]. Unlike a timeout configured on the
+//! underlying `reqwest` client, the deadline covers everything the wrapped
+//! service does, including retries and time spent waiting on rate limiters,
+//! and it can vary between endpoints: slow endpoints, such as ones that
+//! generate reports, can be given a longer deadline than the rest of an
+//! API.
+//!
+//! # Usage
+//!
+//! ```
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::timeout::{TimeoutPolicy, TimeoutService};
+//! use std::time::Duration;
+//!
+//! fn bounded<S: HttpService>(service: S) -> TimeoutService<S> {
+//!     let policy = TimeoutPolicy::new(Duration::from_secs(10))
+//!         .with_timeout_for("/reports/", Duration::from_secs(120));
+//!     TimeoutService::new(service, policy)
+//! }
+//! ```
+
+use crate::auth::Authenticator;
+use crate::service::capabilities::{Capabilities, HttpCapabilities, Middleware, Verb};
+use crate::service::{HttpGet, HttpPost};
+use crate::{HttpError, HttpResult};
+use reqwest::{IntoUrl, Url};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::time::Duration;
+
+/// Determines how long a [`TimeoutService`] waits for each request.
+#[derive(Clone, Debug)]
+pub struct TimeoutPolicy {
+    timeout: Duration,
+    overrides: Vec<(String, Duration)>,
+}
+
+impl TimeoutPolicy {
+    /// Creates a policy that gives every request `timeout` to complete.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Gives requests whose URI starts with `prefix` a different timeout.
+    ///
+    /// A prefix that starts with `/` is matched against the path of the
+    /// URI; any other prefix is matched against the entire URI, so that a
+    /// prefix such as `https://reports.example.com/` can match a specific
+    /// host. If several prefixes match, the longest one wins.
+    pub fn with_timeout_for(mut self, prefix: impl Into<String>, timeout: Duration) -> Self {
+        self.overrides.push((prefix.into(), timeout));
+        self
+    }
+
+    /// The timeout for requests that do not match any override.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// The timeout for a request to `uri`.
+    pub fn timeout_for(&self, uri: &str) -> Duration {
+        let path = Url::parse(uri)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| uri.to_string());
+        self.overrides
+            .iter()
+            .filter(|(prefix, _)| {
+                if prefix.starts_with('/') {
+                    path.starts_with(prefix.as_str())
+                } else {
+                    uri.starts_with(prefix.as_str())
+                }
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.timeout, |(_, timeout)| *timeout)
+    }
+}
+
+/// Wraps an HTTP service and fails requests that take too long.
+///
+/// See the [module documentation](crate::service::timeout) for details.
+#[derive(Debug)]
+pub struct TimeoutService<S> {
+    inner: S,
+    policy: TimeoutPolicy,
+}
+
+impl<S> TimeoutService<S> {
+    /// Wraps `inner` in a service that times out requests according to
+    /// `policy`.
+    pub fn new(inner: S, policy: TimeoutPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The policy used to time out requests.
+    pub fn policy(&self) -> &TimeoutPolicy {
+        &self.policy
+    }
+}
+
+async fn with_deadline<T>(
+    timeout: Duration,
+    request: impl Future<Output = HttpResult<T>>,
+) -> HttpResult<T> {
+    tokio::time::timeout(timeout, request)
+        .await
+        .unwrap_or(Err(HttpError::Timeout(timeout)))
+}
+
+impl<S: HttpGet + Sync> HttpGet for TimeoutService<S> {
+    async fn get<U>(&self, uri: U) -> HttpResult<String>
+    where
+        U: IntoUrl + Send,
+    {
+        let timeout = self.policy.timeout_for(uri.as_str());
+        with_deadline(timeout, self.inner.get(uri)).await
+    }
+}
+
+impl<S: HttpPost + Sync> HttpPost for TimeoutService<S> {
+    async fn post<U, D, R>(&self, uri: U, auth: &dyn Authenticator, data: &D) -> HttpResult<R>
+    where
+        U: IntoUrl + Send,
+        D: Serialize + Sync,
+        R: DeserializeOwned,
+    {
+        let timeout = self.policy.timeout_for(uri.as_str());
+        with_deadline(timeout, self.inner.post(uri, auth, data)).await
+    }
+}
+
+impl<S: HttpCapabilities> HttpCapabilities for TimeoutService<S> {
+    fn capabilities(&self) -> Capabilities {
+        self.inner
+            .capabilities()
+            .limit_verbs(&[Verb::Get, Verb::Post])
+            .wrap(Middleware::new("timeout").with_config(format!("{:?}", self.policy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Takes as many seconds to respond as the last path segment says.
+    struct SlowService;
+
+    impl HttpGet for SlowService {
+        async fn get<U>(&self, uri: U) -> HttpResult<String>
+        where
+            U: IntoUrl + Send,
+        {
+            let seconds = uri.as_str().rsplit('/').next().unwrap().parse().unwrap();
+            tokio::time::sleep(Duration::from_secs(seconds)).await;
+            Ok(String::from("ok"))
+        }
+    }
+
+    fn service() -> TimeoutService<SlowService> {
+        let policy = TimeoutPolicy::new(Duration::from_secs(5))
+            .with_timeout_for("/reports/", Duration::from_secs(60))
+            .with_timeout_for("/reports/quick/", Duration::from_secs(1));
+        TimeoutService::new(SlowService, policy)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_allows_requests_that_finish_in_time() -> HttpResult<()> {
+        assert_eq!(service().get("/users/4").await?, "ok");
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_times_out_slow_requests() {
+        let result = service().get("/users/6").await;
+        assert!(
+            matches!(result, Err(HttpError::Timeout(timeout)) if timeout == Duration::from_secs(5))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_uses_the_timeout_for_the_longest_matching_prefix() {
+        let service = service();
+        assert!(
+            service
+                .get("https://api.example.com/reports/30")
+                .await
+                .is_ok()
+        );
+        assert!(service.get("/reports/quick/2").await.is_err());
+    }
+
+    #[test]
+    fn it_matches_full_uri_prefixes() {
+        let policy = TimeoutPolicy::new(Duration::from_secs(5))
+            .with_timeout_for("https://slow.example.com/", Duration::from_secs(30));
+        assert_eq!(
+            policy.timeout_for("https://slow.example.com/anything"),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            policy.timeout_for("https://fast.example.com/anything"),
+            Duration::from_secs(5)
+        );
+    }
+}
@@ -0,0 +1,489 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Clients for [server-sent events](https://html.spec.whatwg.org/multipage/server-sent-events.html).
+//!
+//! Many APIs, including most that stream the output of language models,
+//! push results to clients as server-sent events: a long-lived response
+//! with a `text/event-stream` body made up of small text records.
+//! [`HttpGetSse::get_sse()`] parses such a body into a stream of [`Event`]s.
+//!
+//! When the connection is dropped, the stream reconnects on its own, and
+//! sends the ID of the last event it received in a `Last-Event-ID` header
+//! so the server can pick up where it left off. Reconnection attempts back
+//! off exponentially according to a [`RetryPolicy`], starting from the
+//! delay the server asks for with the `retry` field, if any. The stream
+//! ends when the server responds with HTTP 204 No Content, or with an
+//! error once the policy gives up on reconnecting.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use futures_util::StreamExt;
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetSse;
+//!
+//! # async fn run<S: HttpGetSse + Sync>(service: S) -> HttpResult<()> {
+//! let mut events = service.get_sse("https://api.example.com/completions").await?;
+//! while let Some(event) = events.next().await {
+//!     let event = event?;
+//!     if event.data == "[DONE]" {
+//!         break;
+//!     }
+//!     print!("{}", event.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HttpResult;
+use crate::service::retry::RetryPolicy;
+use crate::service::{BodyStream, HttpGetSse};
+use futures_util::{Stream, StreamExt, stream};
+use reqwest::Method;
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A server-sent event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Event {
+    /// The ID of the most recent event that had one, which is sent back to
+    /// the server when reconnecting.
+    pub id: Option<String>,
+
+    /// The type of the event, which is `message` unless the server gave
+    /// another type.
+    pub event: String,
+
+    /// The data of the event, with the lines of multi-line data joined by
+    /// `\n`.
+    pub data: String,
+
+    /// How long the server asked clients to wait before reconnecting, if
+    /// the event changed it.
+    pub retry: Option<Duration>,
+}
+
+/// A stream of server-sent events.
+///
+/// This is returned by [`HttpGetSse::get_sse()`].
+pub struct EventStream<'a> {
+    events: Pin<Box<dyn Stream<Item = HttpResult<Event>> + Send + 'a>>,
+}
+
+impl Stream for EventStream<'_> {
+    type Item = HttpResult<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.as_mut().poll_next(cx)
+    }
+}
+
+impl fmt::Debug for EventStream<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventStream").finish_non_exhaustive()
+    }
+}
+
+/// Reads events from `body`, reconnecting to `uri` with `service` when the
+/// body ends, or returns an empty stream if there is no body.
+pub(crate) fn events<S>(
+    service: &S,
+    uri: String,
+    body: Option<BodyStream>,
+    policy: RetryPolicy,
+) -> EventStream<'_>
+where
+    S: HttpGetSse + Sync + ?Sized,
+{
+    let Some(body) = body else {
+        return EventStream {
+            events: Box::pin(stream::empty()),
+        };
+    };
+    let state = State {
+        service,
+        uri,
+        body: Some(body),
+        parser: Parser::new(),
+        events: VecDeque::new(),
+        policy,
+    };
+    let events = stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        match state.next_event().await {
+            Ok(Some(event)) => Some((Ok(event), Some(state))),
+            Ok(None) => None,
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+    EventStream {
+        events: Box::pin(events),
+    }
+}
+
+struct State<'a, S: ?Sized> {
+    service: &'a S,
+    uri: String,
+    body: Option<BodyStream>,
+    parser: Parser,
+    events: VecDeque<Event>,
+    policy: RetryPolicy,
+}
+
+impl<S: HttpGetSse + Sync + ?Sized> State<'_, S> {
+    /// Reads the next event, reconnecting as necessary, or returns `None`
+    /// if the server has asked the client to stop.
+    async fn next_event(&mut self) -> HttpResult<Option<Event>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(Some(event));
+            }
+            match &mut self.body {
+                Some(body) => match body.next().await {
+                    Some(Ok(chunk)) => self.events.extend(self.parser.feed(&chunk)),
+                    // A connection that drops while the body is being read
+                    // is reconnected like one the server closed.
+                    Some(Err(_)) | None => self.body = None,
+                },
+                None => {
+                    if !self.reconnect().await? {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconnects to the event stream, returning `false` if the server has
+    /// asked the client to stop.
+    async fn reconnect(&mut self) -> HttpResult<bool> {
+        self.parser.reset();
+        let mut attempts = 1;
+        loop {
+            let backoff = self.policy.backoff(attempts);
+            let delay = backoff.max(self.parser.reconnection_time.unwrap_or_default());
+            tokio::time::sleep(delay).await;
+            let last_event_id = self.parser.last_event_id.as_deref();
+            match self
+                .service
+                .open_sse(self.uri.as_str(), last_event_id)
+                .await
+            {
+                Ok(Some(body)) => {
+                    self.body = Some(body);
+                    return Ok(true);
+                }
+                Ok(None) => return Ok(false),
+                Err(err) if self.policy.should_retry(&Method::GET, &err, attempts) => {
+                    attempts += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Parses an event stream incrementally, keeping partial lines between
+/// chunks.
+#[derive(Debug, Default)]
+struct Parser {
+    line: Vec<u8>,
+    after_cr: bool,
+    started: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    retry: Option<Duration>,
+    id: Option<String>,
+    last_event_id: Option<String>,
+    reconnection_time: Option<Duration>,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses `chunk`, returning the events it completes.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        let mut events = Vec::new();
+        for &b in chunk {
+            match b {
+                b'\n' if self.after_cr => self.after_cr = false,
+                b'\r' | b'\n' => {
+                    self.after_cr = b == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    events.extend(self.process_line(&line));
+                }
+                _ => {
+                    self.after_cr = false;
+                    self.line.push(b);
+                }
+            }
+        }
+        events
+    }
+
+    /// Discards any partially received event, as when the connection is
+    /// lost, but remembers the last event ID and reconnection time.
+    fn reset(&mut self) {
+        *self = Self {
+            id: self.last_event_id.clone(),
+            last_event_id: self.last_event_id.take(),
+            reconnection_time: self.reconnection_time,
+            ..Self::default()
+        };
+    }
+
+    fn process_line(&mut self, line: &[u8]) -> Option<Event> {
+        let mut line = String::from_utf8_lossy(line);
+        if !self.started {
+            self.started = true;
+            if let Some(rest) = line.strip_prefix('\u{feff}') {
+                line = rest.to_string().into();
+            }
+        }
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(millis) = value.parse() {
+                    let retry = Duration::from_millis(millis);
+                    self.retry = Some(retry);
+                    self.reconnection_time = Some(retry);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        // The last event ID changes even if there is no event to dispatch.
+        self.last_event_id.clone_from(&self.id);
+        let event = std::mem::take(&mut self.event);
+        let data = std::mem::take(&mut self.data);
+        let retry = self.retry.take();
+        if !std::mem::take(&mut self.has_data) {
+            return None;
+        }
+        Some(Event {
+            id: self.last_event_id.clone(),
+            event: if event.is_empty() {
+                String::from("message")
+            } else {
+                event
+            },
+            data,
+            retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use bytes::Bytes;
+    use reqwest::{IntoUrl, StatusCode};
+    use std::sync::Mutex;
+
+    fn parse(chunks: &[&str]) -> Vec<Event> {
+        let mut parser = Parser::new();
+        chunks
+            .iter()
+            .flat_map(|chunk| parser.feed(chunk.as_bytes()))
+            .collect()
+    }
+
+    fn message(id: Option<&str>, data: &str) -> Event {
+        Event {
+            id: id.map(String::from),
+            event: String::from("message"),
+            data: String::from(data),
+            retry: None,
+        }
+    }
+
+    #[test]
+    fn it_parses_events_split_across_chunks() {
+        let events = parse(&[
+            "\u{feff}: keep-alive\r\nda",
+            "ta: first\r",
+            "\ndata:  second line\n\nevent: update\nid: 7\nretry: 3000\ndata\n\n",
+        ]);
+        assert_eq!(
+            events,
+            [
+                message(None, "first\n second line"),
+                Event {
+                    id: Some(String::from("7")),
+                    event: String::from("update"),
+                    data: String::new(),
+                    retry: Some(Duration::from_secs(3)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_last_event_id_and_skips_events_without_data() {
+        let mut parser = Parser::new();
+        let events = parser.feed(b"id: 1\ndata: a\n\nid: 2\nevent: ping\n\ndata: b\n\nretry: x\n");
+        assert_eq!(events, [message(Some("1"), "a"), message(Some("2"), "b")]);
+        assert_eq!(parser.last_event_id.as_deref(), Some("2"));
+        assert_eq!(parser.reconnection_time, None);
+    }
+
+    #[test]
+    fn it_discards_partial_events_when_reset() {
+        let mut parser = Parser::new();
+        assert!(parser.feed(b"id: 4\nretry: 10\n\ndata: lost").is_empty());
+        parser.reset();
+        assert_eq!(
+            parser.feed(b"\n\ndata: kept\n\n"),
+            [message(Some("4"), "kept")]
+        );
+        assert_eq!(parser.reconnection_time, Some(Duration::from_millis(10)));
+    }
+
+    /// The result of a connection: a body made of chunks, or `None` for
+    /// HTTP 204 No Content.
+    type Connection = HttpResult<Option<Vec<HttpResult<&'static str>>>>;
+
+    /// Serves scripted bodies in turn, recording the `Last-Event-ID` of
+    /// each connection.
+    struct Scripted {
+        bodies: Mutex<VecDeque<Connection>>,
+        last_event_ids: Mutex<Vec<Option<String>>>,
+    }
+
+    impl Scripted {
+        fn new(bodies: Vec<Connection>) -> Self {
+            Self {
+                bodies: Mutex::new(bodies.into()),
+                last_event_ids: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl HttpGetSse for Scripted {
+        async fn open_sse<U>(
+            &self,
+            _uri: U,
+            last_event_id: Option<&str>,
+        ) -> HttpResult<Option<BodyStream>>
+        where
+            U: IntoUrl + Send,
+        {
+            self.last_event_ids
+                .lock()
+                .unwrap()
+                .push(last_event_id.map(String::from));
+            let body = self.bodies.lock().unwrap().pop_front().unwrap()?;
+            Ok(body.map(|chunks| {
+                let chunks = chunks
+                    .into_iter()
+                    .map(|chunk| chunk.map(|chunk| Bytes::from_static(chunk.as_bytes())));
+                BodyStream::new(stream::iter(chunks))
+            }))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_reconnects_with_the_last_event_id() -> HttpResult<()> {
+        let service = Scripted::new(vec![
+            Ok(Some(vec![Ok("id: 1\ndata: a\n\nid: 2\ndata: b")])),
+            Ok(Some(vec![
+                Ok("data: c\n\n"),
+                Err(HttpError::Timeout(Duration::from_secs(1))),
+            ])),
+            Err(HttpError::Http(StatusCode::SERVICE_UNAVAILABLE)),
+            Ok(Some(vec![Ok("retry: 5000\nid: 3\ndata: d\n\n")])),
+            Ok(None),
+        ]);
+        let start = tokio::time::Instant::now();
+        let events: Vec<_> = service
+            .get_sse("https://example.com/events")
+            .await?
+            .collect()
+            .await;
+        let data: Vec<_> = events
+            .into_iter()
+            .map(|event| event.map(|event| event.data))
+            .collect::<HttpResult<_>>()?;
+        assert_eq!(data, ["a", "c", "d"]);
+        assert_eq!(
+            *service.last_event_ids.lock().unwrap(),
+            [
+                None,
+                Some(String::from("1")),
+                Some(String::from("1")),
+                Some(String::from("1")),
+                Some(String::from("3")),
+            ]
+        );
+        assert!(start.elapsed() >= Duration::from_secs(5));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_gives_up_when_the_policy_does() {
+        let service = Scripted::new(vec![
+            Ok(Some(vec![Ok("data: a\n\n")])),
+            Err(HttpError::Http(StatusCode::BAD_GATEWAY)),
+            Err(HttpError::Http(StatusCode::BAD_GATEWAY)),
+        ]);
+        let policy = RetryPolicy::new().with_max_attempts(2);
+        let events: Vec<_> = service
+            .get_sse_with_reconnect("https://example.com/events", policy)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[1],
+            Err(HttpError::Http(StatusCode::BAD_GATEWAY))
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_stops_when_told_not_to_reconnect() -> HttpResult<()> {
+        let service = Scripted::new(vec![Ok(None)]);
+        let mut events = service.get_sse("https://example.com/events").await?;
+        assert!(events.next().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_if_the_first_connection_fails() {
+        let service = Scripted::new(vec![Err(HttpError::Http(StatusCode::NOT_FOUND))]);
+        let result = service.get_sse("https://example.com/events").await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::NOT_FOUND))
+        ));
+    }
+}
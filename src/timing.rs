@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Timing of JSON serialization and deserialization.
+//!
+//! A request that seems slow is not always waiting on the network. Large
+//! or deeply nested bodies can take a surprising amount of time to
+//! serialize and deserialize, and that time is easy to mistake for
+//! latency. [`measure()`] runs a future and reports how much of its time
+//! was spent serializing and deserializing JSON, and how many bytes were
+//! involved, in a [`SerdeStats`], so the two can be told apart:
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpGetResponse;
+//! use hypertyper::timing;
+//! use reqwest::header::HeaderMap;
+//! use std::time::Instant;
+//!
+//! # #[derive(serde::Deserialize)]
+//! # struct Report;
+//! # async fn run<S: HttpGetResponse>(service: S) -> HttpResult<()> {
+//! let start = Instant::now();
+//! let (report, serde) = timing::measure(async {
+//!     let response = service
+//!         .get_response("https://api.example.com/report", &HeaderMap::new())
+//!         .await?;
+//!     response.json::<Report>()
+//! })
+//! .await;
+//! let total = start.elapsed();
+//! println!(
+//!     "{total:?} total, {:?} decoding {} bytes",
+//!     serde.deserialize_time, serde.deserialized_bytes
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Bodies decoded with a [`Decoder`], including [`HttpResponse::json()`],
+//! are measured, as are request bodies that Hypertyper serializes itself,
+//! such as the bodies of [jobs](crate::poll::job) and WebSocket messages.
+//! Costs are attributed to the task that incurred them, so work done in
+//! tasks spawned by the measured future is not included.
+//!
+//! With the **tracing** feature enabled, each serialization and
+//! deserialization is also recorded in a `serialize` or `deserialize`
+//! [`tracing`] span at the `DEBUG` level, with the name of the type, the
+//! size of the body in bytes, and the time taken in microseconds.
+//!
+//! [`tracing`]: https://crates.io/crates/tracing
+
+use crate::HttpResult;
+use serde::Serialize;
+use std::cell::Cell;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+#[cfg(doc)]
+use crate::{decode::Decoder, service::HttpResponse};
+
+tokio::task_local! {
+    static STATS: Cell<SerdeStats>;
+}
+
+/// The time spent serializing and deserializing JSON.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SerdeStats {
+    /// The number of values serialized.
+    pub serializations: u64,
+
+    /// The time spent serializing values.
+    pub serialize_time: Duration,
+
+    /// The total size of the serialized values, in bytes.
+    pub serialized_bytes: u64,
+
+    /// The number of bodies deserialized.
+    pub deserializations: u64,
+
+    /// The time spent deserializing bodies.
+    pub deserialize_time: Duration,
+
+    /// The total size of the deserialized bodies, in bytes.
+    pub deserialized_bytes: u64,
+}
+
+impl SerdeStats {
+    /// The total time spent serializing and deserializing.
+    pub fn total_time(&self) -> Duration {
+        self.serialize_time + self.deserialize_time
+    }
+
+    fn add(&mut self, other: &SerdeStats) {
+        self.serializations += other.serializations;
+        self.serialize_time += other.serialize_time;
+        self.serialized_bytes += other.serialized_bytes;
+        self.deserializations += other.deserializations;
+        self.deserialize_time += other.deserialize_time;
+        self.deserialized_bytes += other.deserialized_bytes;
+    }
+}
+
+/// Runs `future` and returns its output along with the time it spent
+/// serializing and deserializing JSON.
+///
+/// Measurements can be nested; the costs measured by an inner call are
+/// also counted by the outer one.
+pub async fn measure<F: Future>(future: F) -> (F::Output, SerdeStats) {
+    let (output, stats) = STATS
+        .scope(Cell::default(), async {
+            let output = future.await;
+            (output, STATS.with(Cell::get))
+        })
+        .await;
+    update(|outer| outer.add(&stats));
+    (output, stats)
+}
+
+fn update(f: impl FnOnce(&mut SerdeStats)) {
+    let _ = STATS.try_with(|stats| {
+        let mut current = stats.get();
+        f(&mut current);
+        stats.set(current);
+    });
+}
+
+/// Runs `deserialize` on `body`, measuring the time it takes.
+pub(crate) fn deserialize<T>(
+    body: &str,
+    deserialize: impl FnOnce(&str) -> HttpResult<T>,
+) -> HttpResult<T> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "deserialize",
+        type_name = std::any::type_name::<T>(),
+        bytes = body.len(),
+        elapsed_us = tracing::field::Empty
+    )
+    .entered();
+
+    let start = Instant::now();
+    let result = deserialize(body);
+    let elapsed = start.elapsed();
+
+    #[cfg(feature = "tracing")]
+    span.record("elapsed_us", elapsed.as_micros() as u64);
+
+    update(|stats| {
+        stats.deserializations += 1;
+        stats.deserialize_time += elapsed;
+        stats.deserialized_bytes += body.len() as u64;
+    });
+    result
+}
+
+/// Serializes `value` as JSON, measuring the time it takes.
+pub(crate) fn to_vec<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Vec<u8>> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "serialize",
+        type_name = std::any::type_name::<T>(),
+        bytes = tracing::field::Empty,
+        elapsed_us = tracing::field::Empty
+    )
+    .entered();
+
+    let start = Instant::now();
+    let result = serde_json::to_vec(value);
+    let elapsed = start.elapsed();
+    let bytes = result.as_ref().map_or(0, Vec::len);
+
+    #[cfg(feature = "tracing")]
+    {
+        span.record("bytes", bytes);
+        span.record("elapsed_us", elapsed.as_micros() as u64);
+    }
+
+    update(|stats| {
+        stats.serializations += 1;
+        stats.serialize_time += elapsed;
+        stats.serialized_bytes += bytes as u64;
+    });
+    result
+}
+
+/// Serializes `value` as a JSON string, measuring the time it takes.
+#[cfg(feature = "ws")]
+pub(crate) fn to_string<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    // serde_json only ever produces valid UTF-8.
+    to_vec(value).map(|json| String::from_utf8(json).expect("JSON is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::decode;
+    use serde_json::{Value, json};
+
+    #[tokio::test]
+    async fn it_measures_serialization_and_deserialization() -> HttpResult<()> {
+        let body = r#"{"name": "foo"}"#;
+        let (result, stats) = measure(async {
+            let value: Value = decode::json(body)?;
+            Ok::<_, HttpError>(to_vec(&value)?)
+        })
+        .await;
+        assert_eq!(result?, br#"{"name":"foo"}"#);
+        assert_eq!(stats.deserializations, 1);
+        assert_eq!(stats.deserialized_bytes, body.len() as u64);
+        assert_eq!(stats.serializations, 1);
+        assert_eq!(stats.serialized_bytes, 14);
+        assert_eq!(
+            stats.total_time(),
+            stats.serialize_time + stats.deserialize_time
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_counts_nested_measurements_in_the_outer_one() {
+        let (inner, outer) = measure(async {
+            let _ = to_vec(&json!([1, 2, 3]));
+            let (_, inner) = measure(async { to_vec(&json!(true)) }).await;
+            inner
+        })
+        .await;
+        assert_eq!(inner.serializations, 1);
+        assert_eq!(inner.serialized_bytes, 4);
+        assert_eq!(outer.serializations, 2);
+        assert_eq!(outer.serialized_bytes, 11);
+    }
+
+    #[test]
+    fn it_does_nothing_outside_of_a_measurement() -> HttpResult<()> {
+        let value: Value = decode::json("[]")?;
+        assert_eq!(to_vec(&value)?, b"[]");
+        Ok(())
+    }
+}
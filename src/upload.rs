@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! Uploading large request bodies.
+//!
+//! An [`UploadBody`] streams a request body from a file, an [`AsyncRead`],
+//! or a stream of chunks, so that large uploads do not have to fit in
+//! memory. Upload bodies are sent with an
+//! [`HttpPostStream`](crate::service::HttpPostStream) service, such as an
+//! [`HttpClient`](crate::HttpClient). [`Multipart`] forms, for file upload
+//! endpoints, are built on top of them.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpPostStream;
+//! use hypertyper::upload::{self, UploadBody};
+//! use reqwest::header::{self, HeaderMap, HeaderValue};
+//!
+//! # async fn run<S: HttpPostStream>(service: S) -> HttpResult<()> {
+//! let body = UploadBody::from_file("backup.tar.gz")
+//!     .await?
+//!     .on_progress(|uploaded, total| {
+//!         if let Some(total) = total {
+//!             eprint!("\r{}%", uploaded * 100 / total.max(1));
+//!         }
+//!     });
+//! let mut headers = HeaderMap::new();
+//! let content_type = upload::content_type_for("backup.tar.gz");
+//! headers.insert(header::CONTENT_TYPE, HeaderValue::from_str(&content_type)?);
+//! service
+//!     .post_stream("https://example.com/backups", &headers, body)
+//!     .await?
+//!     .error_for_status()?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod multipart;
+
+pub use multipart::{Multipart, Part};
+
+use crate::HttpResult;
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt, stream};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::pin::{Pin, pin};
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::AsyncRead;
+use tokio_util::io::ReaderStream;
+
+/// Guesses the content type of the file at `path` from its extension,
+/// falling back to `application/octet-stream`.
+///
+/// # Examples
+///
+/// ```
+/// # use hypertyper::upload::content_type_for;
+/// assert_eq!(content_type_for("report.pdf"), "application/pdf");
+/// assert_eq!(content_type_for("data.unknown"), "application/octet-stream");
+/// ```
+pub fn content_type_for(path: impl AsRef<Path>) -> String {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .to_string()
+}
+
+type ProgressCallback = Arc<dyn Fn(u64, Option<u64>) + Send + Sync>;
+
+/// A request body that is streamed to the server in chunks.
+///
+/// See the [module documentation](crate::upload) for details.
+pub struct UploadBody {
+    chunks: Pin<Box<dyn Stream<Item = HttpResult<Bytes>> + Send>>,
+    content_length: Option<u64>,
+    progress: Option<ProgressCallback>,
+}
+
+impl UploadBody {
+    /// Creates a body from a stream of chunks.
+    pub fn from_stream<S>(chunks: S) -> Self
+    where
+        S: Stream<Item = HttpResult<Bytes>> + Send + 'static,
+    {
+        Self {
+            chunks: Box::pin(chunks),
+            content_length: None,
+            progress: None,
+        }
+    }
+
+    /// Creates a body that reads from `reader` until it is exhausted.
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let chunks = ReaderStream::new(reader).map(|chunk| Ok(chunk?));
+        Self::from_stream(chunks)
+    }
+
+    /// Creates a body from the contents of the file at `path`, whose size is
+    /// sent as the `Content-Length` of the request.
+    pub async fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path).await?;
+        let len = file.metadata().await?.len();
+        Ok(Self::from_reader(file).with_content_length(Some(len)))
+    }
+
+    /// Creates a body from bytes that are already in memory.
+    pub fn from_bytes(bytes: impl Into<Bytes>) -> Self {
+        let bytes = bytes.into();
+        let len = bytes.len() as u64;
+        Self::from_stream(stream::iter([Ok(bytes)])).with_content_length(Some(len))
+    }
+
+    /// Sets the length of the body, if it is known in advance.
+    ///
+    /// If the length is known, it is sent in the `Content-Length` header;
+    /// otherwise, the body is sent with chunked transfer encoding, which
+    /// some servers do not accept.
+    pub fn with_content_length(mut self, content_length: Option<u64>) -> Self {
+        self.content_length = content_length;
+        self
+    }
+
+    /// Calls `progress` after each chunk is read, with the number of bytes
+    /// uploaded so far and the total size of the body, if it is known.
+    pub fn on_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// The length of the body, if it is known in advance.
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    /// Consumes the body and reads all of it into memory.
+    ///
+    /// This is mostly useful for testing.
+    pub async fn into_bytes(self) -> HttpResult<Bytes> {
+        let mut chunks = pin!(self.into_stream());
+        let mut collected = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            collected.extend_from_slice(&chunk?);
+        }
+        Ok(Bytes::from(collected))
+    }
+
+    /// Consumes the body and returns its chunks, reporting progress as they
+    /// are read.
+    pub fn into_stream(self) -> impl Stream<Item = HttpResult<Bytes>> + Send + 'static {
+        let total = self.content_length;
+        let progress = self.progress;
+        let mut uploaded = 0;
+        self.chunks.inspect(move |chunk| {
+            if let (Ok(chunk), Some(progress)) = (chunk, &progress) {
+                uploaded += chunk.len() as u64;
+                progress(uploaded, total);
+            }
+        })
+    }
+}
+
+impl fmt::Debug for UploadBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UploadBody")
+            .field("content_length", &self.content_length)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn it_reads_files_and_reports_progress() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("upload.bin");
+        std::fs::write(&path, vec![7; 20_000])?;
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&progress);
+        let body = UploadBody::from_file(&path)
+            .await?
+            .on_progress(move |uploaded, total| sink.lock().unwrap().push((uploaded, total)));
+        assert_eq!(body.content_length(), Some(20_000));
+        assert_eq!(body.into_bytes().await?, vec![7; 20_000]);
+
+        let progress = progress.lock().unwrap();
+        assert!(progress.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(progress.last(), Some(&(20_000, Some(20_000))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reads_from_readers_of_unknown_length() -> HttpResult<()> {
+        let body = UploadBody::from_reader(&b"hello world"[..]);
+        assert_eq!(body.content_length(), None);
+        assert_eq!(body.into_bytes().await?, &b"hello world"[..]);
+        Ok(())
+    }
+}
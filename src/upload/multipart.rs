@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! `multipart/form-data` request bodies.
+//!
+//! File upload endpoints, such as S3 presigned POSTs, often expect a
+//! [`Multipart`] form: a series of named [parts](Part), each of which is
+//! either a simple text field or a file with a filename and content type.
+//! File parts are streamed, so large files do not have to fit in memory.
+//!
+//! [`Part::file()`] fills in the content type of a file from its extension,
+//! and filenames that are not ASCII are also sent in the `filename*`
+//! parameter, encoded as described in
+//! [RFC 5987](https://www.rfc-editor.org/rfc/rfc5987), for servers that do
+//! not accept raw UTF-8. When the lengths of all of the parts are known, as
+//! they are for files, the length of the whole form is sent in the
+//! `Content-Length` header.
+//!
+//! Forms are sent with
+//! [`HttpPostStream::post_multipart()`](crate::service::HttpPostStream::post_multipart).
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpPostStream;
+//! use hypertyper::upload::{Multipart, Part};
+//! use reqwest::header::HeaderMap;
+//!
+//! # async fn run<S: HttpPostStream + Sync>(service: S) -> HttpResult<()> {
+//! let screenshot = Part::file("attachment", "screenshot.png").await?;
+//! let form = Multipart::new()
+//!     .text("summary", "The app crashes on launch")
+//!     .part(screenshot);
+//! let response = service
+//!     .post_multipart("https://tickets.example.com/issues", &HeaderMap::new(), form)
+//!     .await?
+//!     .error_for_status()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::UploadBody;
+use crate::HttpResult;
+use bytes::Bytes;
+use futures_util::StreamExt;
+use futures_util::stream::{self, BoxStream};
+use std::io;
+use std::path::Path;
+
+/// One field of a [`Multipart`] form.
+#[derive(Debug)]
+pub struct Part {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    body: UploadBody,
+}
+
+impl Part {
+    /// Creates a text field.
+    pub fn text(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self::stream(name, UploadBody::from_bytes(value.into()))
+    }
+
+    /// Creates a field containing `bytes`.
+    pub fn bytes(name: impl Into<String>, bytes: impl Into<Bytes>) -> Self {
+        Self::stream(name, UploadBody::from_bytes(bytes))
+    }
+
+    /// Creates a field whose contents are streamed from `body`.
+    pub fn stream(name: impl Into<String>, body: UploadBody) -> Self {
+        Self {
+            name: name.into(),
+            filename: None,
+            content_type: None,
+            body,
+        }
+    }
+
+    /// Creates a file field whose contents are streamed from the file at
+    /// `path`, using the name of the file as its filename and guessing its
+    /// content type from its extension.
+    pub async fn file(name: impl Into<String>, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let body = UploadBody::from_file(path).await?;
+        let part = Self::stream(name, body).with_content_type(super::content_type_for(path));
+        Ok(match path.file_name() {
+            Some(filename) => part.with_filename(filename.to_string_lossy()),
+            None => part,
+        })
+    }
+
+    /// Sets the filename of the field, which makes it a file upload.
+    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    /// Sets the content type of the field.
+    ///
+    /// [`content_type_for()`](super::content_type_for) guesses the content
+    /// type of a file from its name. Servers assume `text/plain` for fields without a filename and
+    /// `application/octet-stream` for files if no content type is given.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// The name of the field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The filename of the field, if it is a file upload.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// The content type of the field, if one was given.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// The length of the field's contents, if it is known in advance.
+    pub fn content_length(&self) -> Option<u64> {
+        self.body.content_length()
+    }
+
+    /// Consumes the part and returns its contents.
+    pub fn into_body(self) -> UploadBody {
+        self.body
+    }
+
+    /// The `Content-Disposition` and `Content-Type` headers that precede
+    /// the part's contents.
+    fn headers(&self) -> String {
+        let mut headers = format!(
+            "Content-Disposition: form-data; name=\"{}\"",
+            escape(&self.name)
+        );
+        if let Some(filename) = &self.filename {
+            headers.push_str(&format!("; filename=\"{}\"", escape(filename)));
+            if !filename.is_ascii() {
+                headers.push_str(&format!("; filename*=UTF-8''{}", ext_value(filename)));
+            }
+        }
+        headers.push_str("\r\n");
+        if let Some(content_type) = &self.content_type {
+            headers.push_str(&format!("Content-Type: {content_type}\r\n"));
+        }
+        headers
+    }
+}
+
+/// A `multipart/form-data` request body.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct Multipart {
+    boundary: String,
+    parts: Vec<Part>,
+}
+
+impl Multipart {
+    /// Creates an empty form with a random boundary.
+    pub fn new() -> Self {
+        let boundary = format!(
+            "hypertyper-{:016x}{:016x}",
+            fastrand::u64(..),
+            fastrand::u64(..)
+        );
+        Self {
+            boundary,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Adds a text field to the form.
+    pub fn text(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.part(Part::text(name, value))
+    }
+
+    /// Adds a part to the form.
+    pub fn part(mut self, part: Part) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// The boundary that separates the parts of the encoded form.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// The value of the `Content-Type` header for the encoded form.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// The parts of the form.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// Consumes the form and returns its parts.
+    pub fn into_parts(self) -> Vec<Part> {
+        self.parts
+    }
+
+    /// The length of the encoded form, if the lengths of all of its parts
+    /// are known.
+    pub fn content_length(&self) -> Option<u64> {
+        self.parts
+            .iter()
+            .try_fold(self.closing().len() as u64, |len, part| {
+                let body = part.body.content_length()?;
+                Some(len + self.opening(part).len() as u64 + body + 2)
+            })
+    }
+
+    /// Consumes the form and encodes it as a streamed request body.
+    pub fn into_body(self) -> UploadBody {
+        let content_length = self.content_length();
+        let closing = self.closing();
+        let openings: Vec<_> = self.parts.iter().map(|part| self.opening(part)).collect();
+        let mut pieces: Vec<BoxStream<'static, HttpResult<Bytes>>> = Vec::new();
+        for (opening, part) in openings.into_iter().zip(self.parts) {
+            pieces.push(once(opening));
+            pieces.push(part.body.into_stream().boxed());
+            pieces.push(once(String::from("\r\n")));
+        }
+        pieces.push(once(closing));
+        UploadBody::from_stream(stream::iter(pieces).flatten()).with_content_length(content_length)
+    }
+
+    /// The delimiter and headers that precede the contents of `part`.
+    fn opening(&self, part: &Part) -> String {
+        format!("--{}\r\n{}\r\n", self.boundary, part.headers())
+    }
+
+    /// The delimiter that ends the form.
+    fn closing(&self) -> String {
+        format!("--{}--\r\n", self.boundary)
+    }
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn once(text: String) -> BoxStream<'static, HttpResult<Bytes>> {
+    stream::once(async move { Ok(Bytes::from(text)) }).boxed()
+}
+
+/// Percent-encodes the characters that cannot appear in a quoted field name
+/// or filename, as browsers do.
+fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Percent-encodes `value` as the value of an extended parameter, as
+/// described in RFC 5987.
+fn ext_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'!'
+            | b'#'
+            | b'$'
+            | b'&'
+            | b'+'
+            | b'-'
+            | b'.'
+            | b'^'
+            | b'_'
+            | b'`'
+            | b'|'
+            | b'~' => char::from(b).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn it_encodes_fields_and_files() -> HttpResult<()> {
+        let form = Multipart::new().text("summary", "It \"broke\"").part(
+            Part::stream("log", UploadBody::from_reader(&b"line 1\n"[..]))
+                .with_filename("app.log")
+                .with_content_type("text/plain"),
+        );
+        assert_eq!(form.content_length(), None);
+        let boundary = form.boundary().to_string();
+        let body = form.into_body().into_bytes().await?;
+        let expected = format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"summary\"\r\n\r\n\
+             It \"broke\"\r\n\
+             --{boundary}\r\n\
+             Content-Disposition: form-data; name=\"log\"; filename=\"app.log\"\r\n\
+             Content-Type: text/plain\r\n\r\n\
+             line 1\n\r\n\
+             --{boundary}--\r\n"
+        );
+        assert_eq!(body, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_computes_the_length_of_forms_with_known_lengths() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, [0; 100])?;
+        let form = Multipart::new()
+            .text("a", "b")
+            .part(Part::file("data", &path).await?);
+        assert_eq!(form.parts()[1].filename(), Some("data.bin"));
+        assert_eq!(form.parts()[1].content_length(), Some(100));
+
+        let len = form.content_length().unwrap();
+        let body = form.into_body().into_bytes().await?;
+        assert_eq!(len, body.len() as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn it_escapes_quotes_and_newlines_in_names() {
+        let part = Part::text("a\"b\r\n", "").with_filename("c\".txt");
+        assert_eq!(
+            part.headers(),
+            "Content-Disposition: form-data; name=\"a%22b%0D%0A\"; filename=\"c%22.txt\"\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_guesses_the_content_type_of_files() -> HttpResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("chart.png");
+        std::fs::write(&path, [0; 8])?;
+        let part = Part::file("chart", &path).await?;
+        assert_eq!(part.content_type(), Some("image/png"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_encodes_non_ascii_filenames() {
+        let part = Part::text("cv", "").with_filename("résumé 2026.pdf");
+        assert_eq!(
+            part.headers(),
+            "Content-Disposition: form-data; name=\"cv\"; filename=\"résumé 2026.pdf\"; \
+             filename*=UTF-8''r%C3%A9sum%C3%A9%202026.pdf\r\n"
+        );
+    }
+}
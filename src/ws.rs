@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (C) 2026 Michael Dippery <michael@monkey-robot.com>
+
+//! WebSocket connections.
+//!
+//! Many HTTP APIs push events to their clients over WebSockets.
+//! [`HttpWebSocket::connect_ws()`] upgrades a request made with an
+//! [`HttpClient`](crate::HttpClient) to a [`WebSocket`], so the
+//! connection uses the same TLS, proxy, and user agent configuration as
+//! every other request made with a client from the same
+//! [`HttpClientFactory`](crate::HttpClientFactory).
+//!
+//! A [`WebSocket`] is a [`Stream`] of incoming [`Message`]s and a [`Sink`]
+//! for outgoing ones. It also sends and receives JSON values directly with
+//! [`send_json()`](WebSocket::send_json) and
+//! [`next_json()`](WebSocket::next_json). Pings from the server are
+//! answered automatically.
+//!
+//! The upgrade requires HTTP/1.1, so a connection to a server that
+//! negotiates HTTP/2 for the request fails.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hypertyper::prelude::*;
+//! use hypertyper::service::HttpWebSocket;
+//! use reqwest::header::HeaderMap;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize)]
+//! struct Subscribe<'a> {
+//!     channel: &'a str,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Trade {
+//!     price: f64,
+//! }
+//!
+//! # async fn run<S: HttpWebSocket>(service: S) -> HttpResult<()> {
+//! let mut socket = service
+//!     .connect_ws("wss://stream.example.com/trades", &HeaderMap::new())
+//!     .await?;
+//! socket.send_json(&Subscribe { channel: "BTC-USD" }).await?;
+//! while let Some(trade) = socket.next_json::<Trade>().await {
+//!     println!("{}", trade?.price);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::decode::Decoder;
+use crate::{HttpResult, timing};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use reqwest::Upgraded;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::protocol::Role;
+
+pub use tokio_tungstenite::tungstenite::Message;
+
+#[cfg(doc)]
+use crate::service::HttpWebSocket;
+
+/// An open WebSocket connection.
+pub struct WebSocket {
+    inner: WebSocketStream<Upgraded>,
+}
+
+impl WebSocket {
+    /// Starts speaking the WebSocket protocol as a client on a connection
+    /// that has completed the upgrade handshake.
+    pub async fn from_upgraded(upgraded: Upgraded) -> Self {
+        let inner = WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await;
+        Self { inner }
+    }
+
+    /// Sends a text message.
+    pub async fn send_text(&mut self, text: impl Into<String>) -> HttpResult<()> {
+        self.send(Message::text(text.into())).await
+    }
+
+    /// Sends `value` as a JSON text message.
+    pub async fn send_json<T: Serialize + ?Sized>(&mut self, value: &T) -> HttpResult<()> {
+        let text = timing::to_string(value)?;
+        self.send_text(text).await
+    }
+
+    /// Receives the next text or binary message and deserializes it as a
+    /// `T`, or returns `None` once the connection is closed.
+    ///
+    /// Control messages such as pings are skipped.
+    pub async fn next_json<T: DeserializeOwned>(&mut self) -> Option<HttpResult<T>> {
+        loop {
+            let text = match self.next().await? {
+                Ok(Message::Text(text)) => text.to_string(),
+                Ok(Message::Binary(data)) => String::from_utf8_lossy(&data).into_owned(),
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            };
+            return Some(Decoder::new().decode(&text));
+        }
+    }
+
+    /// Closes the connection, waiting for the server to acknowledge it.
+    pub async fn close(&mut self) -> HttpResult<()> {
+        self.inner.close(None).await?;
+        Ok(())
+    }
+
+    /// The underlying WebSocket stream.
+    pub fn into_inner(self) -> WebSocketStream<Upgraded> {
+        self.inner
+    }
+}
+
+impl Stream for WebSocket {
+    type Item = HttpResult<Message>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner
+            .poll_next_unpin(cx)
+            .map(|message| message.map(|message| Ok(message?)))
+    }
+}
+
+impl Sink<Message> for WebSocket {
+    type Error = crate::HttpError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<HttpResult<()>> {
+        self.inner.poll_ready_unpin(cx).map_err(Into::into)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, message: Message) -> HttpResult<()> {
+        self.inner.start_send_unpin(message).map_err(Into::into)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<HttpResult<()>> {
+        self.inner.poll_flush_unpin(cx).map_err(Into::into)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<HttpResult<()>> {
+        self.inner.poll_close_unpin(cx).map_err(Into::into)
+    }
+}
+
+impl fmt::Debug for WebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocket").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::service::HttpWebSocket;
+    use reqwest::StatusCode;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use serde::Deserialize;
+    use tokio::net::TcpListener;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Ping {
+        seq: u32,
+    }
+
+    fn client() -> crate::HttpClient {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    /// Accepts one WebSocket connection and echoes messages back until the
+    /// client closes it.
+    async fn echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(message)) = socket.next().await {
+                if message.is_close() {
+                    break;
+                }
+                socket.send(message).await.unwrap();
+            }
+        });
+        format!("ws://{addr}/echo")
+    }
+
+    #[tokio::test]
+    async fn it_exchanges_json_messages() -> HttpResult<()> {
+        let uri = echo_server().await;
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer token"));
+        let mut socket = client().connect_ws(uri, &headers).await?;
+
+        socket.send_json(&Ping { seq: 1 }).await?;
+        socket.send(Message::Ping(Default::default())).await?;
+        socket
+            .send(Message::binary(b"{\"seq\": 2}".to_vec()))
+            .await?;
+        assert_eq!(socket.next_json::<Ping>().await.unwrap()?, Ping { seq: 1 });
+        assert_eq!(socket.next_json::<Ping>().await.unwrap()?, Ping { seq: 2 });
+
+        socket.send_text("not json").await?;
+        let result = socket.next_json::<Ping>().await.unwrap();
+        assert!(matches!(result, Err(HttpError::Decode { .. })));
+
+        socket.close().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_when_the_server_does_not_upgrade() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            stream
+                .write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+        let result = client()
+            .connect_ws(format!("ws://{addr}/"), &HeaderMap::new())
+            .await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Http(StatusCode::FORBIDDEN))
+        ));
+    }
+}